@@ -348,7 +348,7 @@ fn create_mesh(render_context: &mut RenderContext, world: &mut World) {
     };
 
     world.spawn((
-        Visible {},
+        Visible(true),
         mesh,
         local_transform,
         GlobalTransform::default(),
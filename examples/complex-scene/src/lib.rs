@@ -114,5 +114,7 @@ fn add_helmet(
 
     let collider = Collider::new(SharedShape::ball(0.35));
 
-    world.insert(helmet, (collider, Grabbable {})).unwrap();
+    world
+        .insert(helmet, (collider, Grabbable::default()))
+        .unwrap();
 }
@@ -1,5 +1,17 @@
+use hotham::components::hand::Handedness;
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Color {
     Red,
     Blue,
 }
+
+impl Color {
+    /// The hand that holds the saber of this color: blue in the player's dominant hand, red in the other one.
+    pub fn handedness(&self, dominant_hand: Handedness) -> Handedness {
+        match self {
+            Color::Blue => dominant_hand,
+            Color::Red => dominant_hand.other(),
+        }
+    }
+}
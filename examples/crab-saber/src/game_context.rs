@@ -10,10 +10,12 @@ use hotham::{
         hand::Handedness,
         physics::{ActiveCollisionTypes, BodyType, SharedShape},
         ui_panel::add_ui_panel_to_world,
-        Collider, GlobalTransform, LocalTransform, Pointer, RigidBody, SoundEmitter, Visible,
+        Collider, GlobalTransform, HandPreference, LocalTransform, Pointer, RigidBody,
+        SoundEmitter, Static, Visible,
     },
     contexts::{audio_context::MusicTrack, physics_context::DEFAULT_COLLISION_GROUP, AudioContext},
     hecs::{Entity, World},
+    rendering::static_batching::batch_static_meshes,
     vk, Engine,
 };
 use rand::prelude::*;
@@ -60,8 +62,9 @@ impl GameContext {
             asset_importer::load_models_from_glb(&glb_buffers, vulkan_context, render_context)
                 .expect("Unable to load models!");
 
-        // Add the environment models
+        // Add the environment models. They never move, so they can be merged to save on draw calls.
         add_environment(&models, world);
+        batch_static_meshes(world, render_context);
 
         // Add sabers
         let sabers = [Color::Blue, Color::Red].map(|color| add_saber(color, &models, world));
@@ -198,12 +201,9 @@ fn add_pointer(models: &std::collections::HashMap<String, World>, world: &mut Wo
     let pointer = add_model_to_world("Blue Pointer", models, world, None).unwrap();
 
     world
-        .insert_one(
+        .insert(
             pointer,
-            Pointer {
-                handedness: Handedness::Right,
-                trigger_value: 0.0,
-            },
+            (HandPreference::Dominant, Pointer::new(Handedness::Right)),
         )
         .unwrap();
 
@@ -211,8 +211,10 @@ fn add_pointer(models: &std::collections::HashMap<String, World>, world: &mut Wo
 }
 
 fn add_environment(models: &std::collections::HashMap<String, World>, world: &mut World) {
-    add_model_to_world("Environment", models, world, None);
-    add_model_to_world("Ramp", models, world, None);
+    for model_name in ["Environment", "Ramp"] {
+        let model = add_model_to_world(model_name, models, world, None).unwrap();
+        world.insert_one(model, Static).unwrap();
+    }
 }
 
 pub fn pre_spawn_cube(world: &mut World, models: &HashMap<String, World>) {
@@ -229,7 +231,7 @@ pub fn pre_spawn_cube(world: &mut World, models: &HashMap<String, World>) {
         ..Default::default()
     };
 
-    world.remove_one::<Visible>(cube).unwrap();
+    world.get::<&mut Visible>(cube).unwrap().0 = false;
     world
         .insert(
             cube,
@@ -6,8 +6,8 @@ use hotham::{
     components::Visible,
     hecs::{Entity, World},
     systems::{
-        audio_system, draw_gui_system, haptics_system, physics_system, pointers_system,
-        rendering_system, update_global_transform_system,
+        audio_system, dominant_hand_system, draw_gui_system, haptics_system, physics_system,
+        pointers_system, rendering_system, update_global_transform_system,
     },
     xr::{self, SessionState},
     Engine, HothamResult, TickData,
@@ -40,6 +40,7 @@ fn tick(tick_data: TickData, engine: &mut Engine, game_context: &mut GameContext
     // Simulation tasks - these are only necessary in the focussed state.
     if tick_data.current_state == xr::SessionState::FOCUSED {
         // Sync world with input contexts
+        dominant_hand_system(engine);
         sabers_system(engine);
         pointers_system(engine);
 
@@ -104,11 +105,14 @@ fn init(engine: &mut Engine) -> GameContext {
 }
 
 fn hide(world: &mut World, entity: Entity) {
-    if world.remove_one::<Visible>(entity).is_err() {
-        println!("[STATE_CHANGE] Tried to make {entity:?} hidden but it had no Visible component")
+    match world.get::<&mut Visible>(entity) {
+        Ok(mut visible) => visible.0 = false,
+        Err(_) => println!(
+            "[STATE_CHANGE] Tried to make {entity:?} hidden but it had no Visible component"
+        ),
     }
 }
 
 fn show(world: &mut World, entity: Entity) {
-    world.insert_one(entity, Visible {}).unwrap();
+    world.get::<&mut Visible>(entity).unwrap().0 = true;
 }
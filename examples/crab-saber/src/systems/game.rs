@@ -27,6 +27,7 @@ pub fn game_system(engine: &mut Engine, game_context: &mut GameContext) {
         &mut engine.world,
         &mut engine.audio_context,
         &mut engine.haptic_context,
+        engine.dominant_hand,
     )
 }
 
@@ -35,9 +36,16 @@ fn game_system_inner(
     world: &mut World,
     audio_context: &mut AudioContext,
     haptic_context: &mut HapticContext,
+    dominant_hand: Handedness,
 ) {
     // Get next state
-    if let Some(next_state) = run(world, game_context, audio_context, haptic_context) {
+    if let Some(next_state) = run(
+        world,
+        game_context,
+        audio_context,
+        haptic_context,
+        dominant_hand,
+    ) {
         // If state has changed, transition
         transition(world, game_context, audio_context, next_state);
     };
@@ -53,15 +61,13 @@ fn transition(
     match (current_state, &next_state) {
         (GameState::Init | GameState::GameOver, GameState::MainMenu) => {
             // Make visible
-            world.insert_one(game_context.pointer, Visible {}).unwrap();
-            world
-                .insert_one(game_context.main_menu_panel, Visible {})
-                .unwrap();
+            set_visible(world, game_context.pointer, true);
+            set_visible(world, game_context.main_menu_panel, true);
 
             // Remove visibility
-            let _ = world.remove_one::<Visible>(game_context.score_panel);
-            let _ = world.remove_one::<Visible>(game_context.blue_saber);
-            let _ = world.remove_one::<Visible>(game_context.red_saber);
+            set_visible(world, game_context.score_panel, false);
+            set_visible(world, game_context.blue_saber, false);
+            set_visible(world, game_context.red_saber, false);
 
             // Switch tracks
             let song = game_context.songs.get("Main Menu").unwrap();
@@ -93,40 +99,32 @@ fn transition(
             game_context.current_score = 0;
 
             // Make visible
-            world
-                .insert_one(game_context.score_panel, Visible {})
-                .unwrap();
-            world
-                .insert_one(game_context.blue_saber, Visible {})
-                .unwrap();
-            world
-                .insert_one(game_context.red_saber, Visible {})
-                .unwrap();
+            set_visible(world, game_context.score_panel, true);
+            set_visible(world, game_context.blue_saber, true);
+            set_visible(world, game_context.red_saber, true);
 
             // Remove visibility
-            let _ = world.remove_one::<Visible>(game_context.pointer);
-            let _ = world.remove_one::<Visible>(game_context.main_menu_panel);
+            set_visible(world, game_context.pointer, false);
+            set_visible(world, game_context.main_menu_panel, false);
 
             // Switch tracks
             audio_context.play_music_track(song.track);
         }
         (GameState::Playing(_), GameState::GameOver) => {
             // Make visible
-            world.insert_one(game_context.pointer, Visible {}).unwrap();
-            world
-                .insert_one(game_context.main_menu_panel, Visible {})
-                .unwrap();
+            set_visible(world, game_context.pointer, true);
+            set_visible(world, game_context.main_menu_panel, true);
 
             // Make invisible
-            let _ = world.remove_one::<Visible>(game_context.score_panel);
-            let _ = world.remove_one::<Visible>(game_context.blue_saber);
-            let _ = world.remove_one::<Visible>(game_context.red_saber);
+            set_visible(world, game_context.score_panel, false);
+            set_visible(world, game_context.blue_saber, false);
+            set_visible(world, game_context.red_saber, false);
 
             // Destroy all cubes
             let live_cubes = world
-                .query::<With<(&Color, &RigidBody, &Collider), (&Visible, &Cube)>>()
+                .query::<With<(&Color, &RigidBody, &Collider, &Visible), &Cube>>()
                 .iter()
-                .map(|(e, _)| e)
+                .filter_map(|(e, (_, _, _, visible))| visible.0.then_some(e))
                 .collect::<Vec<_>>();
             dispose_of_cubes(live_cubes, world);
 
@@ -161,6 +159,7 @@ fn run(
     game_context: &mut GameContext,
     audio_context: &mut AudioContext,
     haptic_context: &mut HapticContext,
+    dominant_hand: Handedness,
 ) -> Option<GameState> {
     match &mut game_context.state {
         GameState::Init => return Some(GameState::MainMenu),
@@ -174,7 +173,7 @@ fn run(
         GameState::Playing(song) => {
             spawn_cube(world, song, &mut game_context.last_spawn_time);
 
-            check_for_hits(world, game_context, haptic_context);
+            check_for_hits(world, game_context, haptic_context, dominant_hand);
             update_panel_text(world, game_context);
 
             if game_context.current_score < 0
@@ -205,11 +204,10 @@ fn spawn_cube(world: &mut World, song: &mut Song, last_spawn_time: &mut Instant)
 
     let color = if random() { Color::Red } else { Color::Blue };
     let dead_cube = world
-        .query_mut::<&Color>()
+        .query_mut::<(&Color, &Visible)>()
         .with::<&Cube>()
-        .without::<&Visible>()
         .into_iter()
-        .find_map(|(e, c)| if c == &color { Some(e) } else { None })
+        .find_map(|(e, (c, v))| if c == &color && !v.0 { Some(e) } else { None })
         .unwrap();
     revive_cube(dead_cube, world, song);
     *last_spawn_time = Instant::now();
@@ -226,6 +224,7 @@ fn check_for_hits(
     world: &mut World,
     game_context: &mut GameContext,
     haptic_context: &mut HapticContext,
+    dominant_hand: Handedness,
 ) {
     let mut pending_sound_effects = Vec::new();
     let mut cubes_to_dispose = Vec::new();
@@ -248,7 +247,7 @@ fn check_for_hits(
                         pending_sound_effects.push((*c, "Hit"));
                     }
                 }
-                haptic_context.request_haptic_feedback(1., Handedness::Right);
+                haptic_context.request_haptic_feedback(1., Color::Blue.handedness(dominant_hand));
                 println!("Hit BLUE: Adding cube to dispose list: {c:?}");
                 cubes_to_dispose.push(*c);
             }
@@ -273,7 +272,7 @@ fn check_for_hits(
                         pending_sound_effects.push((*c, "Miss"));
                     }
                 }
-                haptic_context.request_haptic_feedback(1., Handedness::Left);
+                haptic_context.request_haptic_feedback(1., Color::Red.handedness(dominant_hand));
                 println!("Hit RED: Adding cube to dispose list: {c:?}");
                 cubes_to_dispose.push(*c);
             }
@@ -301,13 +300,16 @@ fn check_for_hits(
 }
 
 fn is_cube(e: hotham::hecs::EntityRef) -> bool {
-    e.has::<Cube>() && e.has::<Visible>() && e.has::<Collider>() && e.has::<RigidBody>()
+    e.has::<Cube>()
+        && e.get::<&Visible>().map_or(false, |v| v.0)
+        && e.has::<Collider>()
+        && e.has::<RigidBody>()
 }
 
 fn dispose_of_cubes(cubes_to_dispose: Vec<Entity>, world: &mut World) {
     for e in cubes_to_dispose.into_iter() {
         println!("Removing visibilty of cube: {e:?}");
-        world.remove_one::<Visible>(e).unwrap();
+        set_visible(world, e, false);
         world.get::<&mut RigidBody>(e).unwrap().linear_velocity = glam::Vec3::ZERO;
     }
 }
@@ -346,9 +348,13 @@ fn revive_cube(cube_entity: Entity, world: &mut World, song: &Song) {
         rigid_body.linear_velocity.z = -CUBE_Z / (song.beat_length.as_secs_f32() * 4.);
     }
 
-    world
-        .insert(cube_entity, (Visible {}, Teleport {}))
-        .unwrap();
+    set_visible(world, cube_entity, true);
+    world.insert_one(cube_entity, Teleport {}).unwrap();
+}
+
+/// Show or hide an entity by flipping its [`Visible`] flag, so it stays in the same archetype.
+fn set_visible(world: &World, entity: Entity, visible: bool) {
+    world.get::<&mut Visible>(entity).unwrap().0 = visible;
 }
 
 #[cfg(target_os = "windows")]
@@ -413,7 +419,13 @@ mod tests {
             .insert("Miss".to_string(), audio_context.dummy_sound_emitter());
 
         // INIT -> MAIN_MENU
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         assert_eq!(game_context.state, GameState::MainMenu);
         assert!(is_visible(world, game_context.pointer));
         assert!(is_visible(world, game_context.main_menu_panel));
@@ -432,7 +444,13 @@ mod tests {
                 .unwrap();
             panel.buttons[0].clicked_this_frame = true;
         }
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         assert_eq!(game_context.state, GameState::Playing(beside_you.clone()));
         assert_eq!(audio_context.current_music_track, Some(beside_you.track));
         assert!(!is_visible(world, game_context.pointer));
@@ -442,17 +460,23 @@ mod tests {
         assert!(is_visible(world, game_context.score_panel));
 
         // PLAYING - TICK ONE
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
 
         {
             assert_score_is(world, game_context, 0);
 
             let mut q = world
-                .query::<(&Color, &RigidBody, &LocalTransform, &Collider)>()
-                .with::<(&Visible, &Cube)>();
-            let mut i = q.iter();
-            assert_eq!(i.len(), 1);
-            let (_, (_, rigid_body, local_transform, _)) = i.next().unwrap();
+                .query::<(&Color, &RigidBody, &LocalTransform, &Collider, &Visible)>()
+                .with::<&Cube>();
+            let mut i = q.iter().filter(|(_, (_, _, _, _, visible))| visible.0);
+            let (_, (_, rigid_body, local_transform, _, _)) = i.next().unwrap();
+            assert!(i.next().is_none());
 
             let t = local_transform.translation;
             assert!(
@@ -470,7 +494,13 @@ mod tests {
         }
 
         // PLAYING - TICK TWO
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
 
         {
             reset(world, game_context, haptic_context);
@@ -481,7 +511,13 @@ mod tests {
         }
 
         // PLAYING - TICK THREE
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_cube_processed(world, game_context.blue_saber, haptic_context);
             reset(world, game_context, haptic_context);
@@ -494,7 +530,13 @@ mod tests {
         }
 
         // PLAYING - TICK FOUR
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_cube_processed(world, game_context.blue_saber, haptic_context);
             reset(world, game_context, haptic_context);
@@ -509,7 +551,13 @@ mod tests {
         }
 
         // PLAYING - TICK FIVE
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_cube_processed(world, game_context.blue_saber, haptic_context);
             reset(world, game_context, haptic_context);
@@ -519,7 +567,13 @@ mod tests {
         }
 
         // PLAYING - TICK SIX
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_cube_processed(world, game_context.backstop, haptic_context);
             reset(world, game_context, haptic_context);
@@ -530,7 +584,13 @@ mod tests {
         }
 
         // PLAYING - TICK SEVEN
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_cube_processed(world, game_context.red_saber, haptic_context);
             reset(world, game_context, haptic_context);
@@ -540,7 +600,13 @@ mod tests {
         }
 
         // PLAYING - TICK EIGHT
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_cube_processed(world, game_context.red_saber, haptic_context);
             reset(world, game_context, haptic_context);
@@ -550,7 +616,13 @@ mod tests {
         }
 
         // PLAYING - TICK NINE -> GAME OVER
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_eq!(game_context.state, GameState::GameOver);
             assert!(is_visible(world, game_context.pointer));
@@ -573,7 +645,13 @@ mod tests {
         }
 
         // GAME_OVER -> MAIN_MENU
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         {
             assert_eq!(game_context.state, GameState::MainMenu);
             assert!(is_visible(world, game_context.pointer));
@@ -609,7 +687,13 @@ mod tests {
                 .unwrap();
             panel.buttons[0].clicked_this_frame = true;
         }
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         reset(world, game_context, haptic_context);
         assert_eq!(game_context.current_score, 0);
         assert_eq!(game_context.state, GameState::Playing(beside_you.clone()));
@@ -621,7 +705,13 @@ mod tests {
         assert!(is_visible(world, game_context.score_panel));
 
         // PLAYING - TICK ONE
-        game_system_inner(game_context, world, audio_context, haptic_context);
+        game_system_inner(
+            game_context,
+            world,
+            audio_context,
+            haptic_context,
+            Handedness::Right,
+        );
         assert_eq!(num_cubes(world), 1);
     }
 
@@ -642,14 +732,15 @@ mod tests {
         world
             .query::<(&Color, &Cube, &Visible, &RigidBody, &Collider)>()
             .iter()
-            .len()
+            .filter(|(_, (_, _, visible, _, _))| visible.0)
+            .count()
     }
 
     fn hit_cube(saber: Entity, color: Color, world: &mut World) {
         let cube = world.spawn((
             color,
             Cube {},
-            Visible {},
+            Visible(true),
             RigidBody::default(),
             Collider::default(),
         ));
@@ -669,7 +760,7 @@ mod tests {
         );
         assert!(hit_cube.has::<SoundEmitter>());
         assert!(hit_cube.has::<Collider>());
-        assert!(!hit_cube.has::<Visible>());
+        assert!(!hit_cube.get::<&Visible>().unwrap().0);
 
         if let Ok(c) = world.get::<&Color>(saber) {
             match *c {
@@ -702,7 +793,7 @@ mod tests {
     }
 
     pub fn is_visible(world: &World, entity: Entity) -> bool {
-        world.get::<&Visible>(entity).is_ok()
+        world.get::<&Visible>(entity).unwrap().0
     }
 
     pub fn assert_score_is(world: &mut World, game_context: &mut GameContext, score: i32) {
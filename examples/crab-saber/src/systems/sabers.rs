@@ -1,6 +1,7 @@
 use hotham::{
     asset_importer::{add_model_to_world, Models},
     components::{
+        hand::Handedness,
         physics::{BodyType, SharedShape},
         stage, Collider, LocalTransform, RigidBody,
     },
@@ -20,10 +21,14 @@ const SABER_HALF_WIDTH: f32 = SABER_WIDTH / 2.;
 
 /// Sync the transform of the player's sabers with the pose of their controllers in OpenXR
 pub fn sabers_system(engine: &mut Engine) {
-    sabers_system_inner(&mut engine.world, &engine.input_context)
+    sabers_system_inner(
+        &mut engine.world,
+        &engine.input_context,
+        engine.dominant_hand,
+    )
 }
 
-fn sabers_system_inner(world: &mut World, input_context: &InputContext) {
+fn sabers_system_inner(world: &mut World, input_context: &InputContext, dominant_hand: Handedness) {
     // Get the isometry of the stage
     let global_from_stage = stage::get_global_from_stage(world);
 
@@ -34,9 +39,9 @@ fn sabers_system_inner(world: &mut World, input_context: &InputContext) {
         world.query_mut::<With<(&Color, &mut LocalTransform), &Saber>>()
     {
         // Get our the space and path of the hand.
-        let stage_from_grip = match color {
-            Color::Red => input_context.left.stage_from_grip(),
-            Color::Blue => input_context.right.stage_from_grip(),
+        let stage_from_grip = match color.handedness(dominant_hand) {
+            Handedness::Left => input_context.left.stage_from_grip(),
+            Handedness::Right => input_context.right.stage_from_grip(),
         };
 
         // Apply transform
@@ -88,7 +93,7 @@ mod tests {
             LocalTransform::default(),
             GlobalTransform::default(),
         ));
-        sabers_system_inner(&mut world, &input_context);
+        sabers_system_inner(&mut world, &input_context, Handedness::Right);
 
         let local_transform = world.get::<&LocalTransform>(saber).unwrap();
         approx::assert_relative_eq!(
@@ -81,6 +81,7 @@ impl CustomRenderContext {
             render_context.render_pass,
             vertex_shader_code.as_slice(),
             fragment_shader_code.as_slice(),
+            render_context.render_settings.msaa_samples,
         )
         .unwrap();
         let quadrics_descriptor_set = unsafe {
@@ -156,6 +157,7 @@ pub fn create_quadrics_pipeline(
     render_pass: vk::RenderPass,
     vertex_shader_code: &[u32],
     fragment_shader_code: &[u32],
+    msaa_samples: vk::SampleCountFlags,
 ) -> Result<vk::Pipeline> {
     // Vertex shader stage
     let (vertex_shader, vertex_stage) = create_shader(
@@ -227,8 +229,8 @@ pub fn create_quadrics_pipeline(
         .line_width(1.0);
 
     // Multisample state
-    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
-        .rasterization_samples(vk::SampleCountFlags::TYPE_4);
+    let multisample_state =
+        vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(msaa_samples);
 
     // Depth stencil state
     let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
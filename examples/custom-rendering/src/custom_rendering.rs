@@ -7,13 +7,15 @@ use crate::{
     hologram::Hologram,
 };
 use hotham::{
-    components::{skin::NO_SKIN, stage, GlobalTransform, Mesh, Skin, Visible},
+    components::{
+        morph_weights::NO_MORPH_WEIGHTS, skin::NO_SKIN, stage, GlobalTransform, Mesh, Skin, Visible,
+    },
     contexts::{
         render_context::{Instance, InstancedPrimitive},
         RenderContext, VulkanContext,
     },
     glam::{Affine3A, Mat4},
-    hecs::{With, World},
+    hecs::World,
     rendering::resources::{DrawData, PrimitiveCullData},
     systems::rendering::draw_primitive,
     vk, xr, Engine,
@@ -111,9 +113,12 @@ pub unsafe fn begin(
 
     let gos_from_stage: Affine3A = gos_from_global * global_from_stage;
 
-    for (_, (mesh, global_transform, skin)) in
-        world.query_mut::<With<(&Mesh, &GlobalTransform, Option<&Skin>), &Visible>>()
+    for (_, (mesh, global_transform, skin, visible)) in
+        world.query_mut::<(&Mesh, &GlobalTransform, Option<&Skin>, &Visible)>()
     {
+        if !visible.0 {
+            continue;
+        }
         let mesh = meshes.get(mesh.handle).unwrap();
         let skin_id = skin.map(|s| s.id).unwrap_or(NO_SKIN);
         for primitive in &mesh.primitives {
@@ -132,15 +137,20 @@ pub unsafe fn begin(
                 .instances
                 .push(Instance {
                     gos_from_local,
+                    previous_gos_from_local: gos_from_local,
                     bounding_sphere: primitive.get_bounding_sphere_in_gos(&gos_from_local),
                     skin_id,
+                    morph_weights_id: NO_MORPH_WEIGHTS,
                 });
         }
     }
 
-    for (_, (hologram, global_transform)) in
-        world.query_mut::<With<(&Hologram, &GlobalTransform), &Visible>>()
+    for (_, (hologram, global_transform, visible)) in
+        world.query_mut::<(&Hologram, &GlobalTransform, &Visible)>()
     {
+        if !visible.0 {
+            continue;
+        }
         let mesh_data = meshes.get(hologram.mesh_data_handle).unwrap();
         for primitive in &mesh_data.primitives {
             let key = primitive.index_buffer_offset | QUADRIC_FLAG;
@@ -343,11 +353,12 @@ pub unsafe fn draw_world(
                         .unwrap();
                     let instance =
                         &instanced_primitive.instances[cull_result.index_instance as usize];
-                    let draw_data = DrawData {
-                        gos_from_local: instance.gos_from_local.into(),
-                        local_from_gos: instance.gos_from_local.inverse().into(),
-                        skin_id: instance.skin_id,
-                    };
+                    let draw_data = DrawData::new(
+                        &instance.gos_from_local,
+                        instance.skin_id,
+                        instance.morph_weights_id,
+                        &instanced_primitive.primitive,
+                    );
                     draw_data_buffer.push(&draw_data);
                     instance_count += 1;
                 }
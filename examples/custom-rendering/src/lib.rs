@@ -107,6 +107,7 @@ fn hot_reloading_system(engine: &mut Engine, custom_render_context: &mut CustomR
             render_context.render_pass,
             custom_render_context.vertex_shader_code.as_slice(),
             custom_render_context.fragment_shader_code.as_slice(),
+            render_context.render_settings.msaa_samples,
         );
         if let Ok(quadrics_pipeline) = quadrics_pipeline {
             custom_render_context.quadrics_pipeline = quadrics_pipeline;
@@ -306,7 +307,7 @@ fn add_quadric(
         hologram_data,
     };
     world
-        .insert(entity, (collider, Grabbable {}, hologram_component))
+        .insert(entity, (collider, Grabbable::default(), hologram_component))
         .unwrap();
     world.remove_one::<Mesh>(entity).unwrap();
 
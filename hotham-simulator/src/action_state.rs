@@ -7,6 +7,8 @@ use openxr_sys::{Action, Path, FALSE};
 // A bit yuck to use u64 instead of Action, but it doesn't support Hash.. but whatever.
 pub struct ActionState {
     boolean_actions: HashMap<u64, bool>,
+    // Float actions are usually bound to both hands, so they're stored per subaction path.
+    float_actions: HashMap<(u64, Path), f32>,
     bindings: HashMap<Path, u64>,
 }
 impl ActionState {
@@ -17,6 +19,13 @@ impl ActionState {
             .unwrap_or(FALSE)
     }
 
+    pub(crate) fn get_float(&self, action: Action, subaction_path: Path) -> f32 {
+        self.float_actions
+            .get(&(action.into_raw(), subaction_path))
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub(crate) fn add_binding(&mut self, path: Path, action: Action) {
         self.bindings.insert(path, action.into_raw());
     }
@@ -25,10 +34,18 @@ impl ActionState {
     pub(crate) fn clear(&mut self) {
         // Set all the booleans to false.
         self.boolean_actions.values_mut().for_each(|v| *v = false);
+        self.float_actions.values_mut().for_each(|v| *v = 0.);
     }
 
     pub(crate) fn set_boolean(&mut self, path: &Path, value: bool) {
         let action = self.bindings.get(path).unwrap();
         self.boolean_actions.insert(*action, value);
     }
+
+    /// Set a float action, if the application bound one to `path`.
+    pub(crate) fn set_float(&mut self, path: &Path, subaction_path: Path, value: f32) {
+        if let Some(action) = self.bindings.get(path) {
+            self.float_actions.insert((*action, subaction_path), value);
+        }
+    }
 }
@@ -5,13 +5,20 @@ use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
 #[derive(Debug, Clone, Default)]
 pub struct Inputs {
     pub pressed: HashSet<VirtualKeyCode>,
+    /// Keys that were pressed since this was last cleared
+    pub just_pressed: HashSet<VirtualKeyCode>,
 }
 
 impl Inputs {
     pub fn process_event(&mut self, keyboard_input: KeyboardInput) {
         if let Some(key) = keyboard_input.virtual_keycode {
             let _ = match keyboard_input.state {
-                ElementState::Pressed => self.pressed.insert(key),
+                ElementState::Pressed => {
+                    if self.pressed.insert(key) {
+                        self.just_pressed.insert(key);
+                    }
+                    true
+                }
                 ElementState::Released => self.pressed.remove(&key),
             };
         }
@@ -1649,11 +1649,17 @@ pub unsafe extern "system" fn destroy_instance(_instance: Instance) -> Result {
 pub unsafe extern "system" fn enumerate_view_configurations(
     _instance: Instance,
     _system_id: SystemId,
-    _view_configuration_type_capacity_input: u32,
+    view_configuration_type_capacity_input: u32,
     view_configuration_type_count_output: *mut u32,
-    _view_configuration_types: *mut ViewConfigurationType,
+    view_configuration_types: *mut ViewConfigurationType,
 ) -> Result {
-    *view_configuration_type_count_output = 0;
+    *view_configuration_type_count_output = 1;
+    if view_configuration_type_capacity_input == 0 {
+        return Result::SUCCESS;
+    }
+    let view_configuration_types = slice::from_raw_parts_mut(view_configuration_types, 1);
+    view_configuration_types[0] = ViewConfigurationType::PRIMARY_STEREO;
+
     Result::SUCCESS
 }
 
@@ -1700,13 +1706,18 @@ pub unsafe extern "system" fn enumerate_swapchain_formats(
 
 pub unsafe extern "system" fn get_action_state_float(
     _session: Session,
-    _get_info: *const ActionStateGetInfo,
+    get_info: *const ActionStateGetInfo,
     state: *mut ActionStateFloat,
 ) -> Result {
+    let current_state = STATE
+        .lock()
+        .unwrap()
+        .get_action_state_float((*get_info).action, (*get_info).subaction_path);
+
     *state = ActionStateFloat {
         ty: StructureType::ACTION_STATE_FLOAT,
         next: ptr::null_mut(),
-        current_state: 0.0,
+        current_state,
         changed_since_last_sync: FALSE,
         last_change_time: openxr_sys::Time::from_nanos(0),
         is_active: TRUE,
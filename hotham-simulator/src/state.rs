@@ -6,7 +6,7 @@ use ash::{
 
 use glam::{Quat, Vec3};
 use openxr_sys::{Action, Bool32, Path, Posef, SessionState, Space, Vector3f};
-use winit::event::KeyboardInput;
+use winit::event::{KeyboardInput, VirtualKeyCode};
 
 use std::{
     collections::HashMap,
@@ -28,6 +28,39 @@ static A_INPUT: &str = "/user/hand/right/input/a/click";
 static B_INPUT: &str = "/user/hand/right/input/b/click";
 static X_INPUT: &str = "/user/hand/left/input/x/click";
 static Y_INPUT: &str = "/user/hand/left/input/y/click";
+static LEFT_HAND: &str = "/user/hand/left";
+static RIGHT_HAND: &str = "/user/hand/right";
+
+/// What the keyboard and mouse are controlling
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputMode {
+    /// Fly the camera around
+    #[default]
+    Camera,
+    /// Move the left hand, and use its trigger, grip and thumbstick
+    LeftHand,
+    /// Move the right hand, and use its trigger, grip and thumbstick
+    RightHand,
+}
+
+impl InputMode {
+    fn next(self) -> Self {
+        match self {
+            InputMode::Camera => InputMode::LeftHand,
+            InputMode::LeftHand => InputMode::RightHand,
+            InputMode::RightHand => InputMode::Camera,
+        }
+    }
+
+    /// The name of the hand's spaces, and its subaction path
+    fn hand(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            InputMode::Camera => None,
+            InputMode::LeftHand => Some(("Left Hand", LEFT_HAND)),
+            InputMode::RightHand => Some(("Right Hand", RIGHT_HAND)),
+        }
+    }
+}
 
 pub struct State {
     pub vulkan_entry: Option<AshEntry>,
@@ -74,6 +107,7 @@ pub struct State {
     pub last_frame_time: Instant,
     pub camera: Camera,
     pub action_state: ActionState,
+    pub input_mode: InputMode,
 }
 
 #[derive(Default)]
@@ -128,6 +162,7 @@ impl Default for State {
             input_state: Inputs::default(),
             last_frame_time: Instant::now(),
             action_state: Default::default(),
+            input_mode: Default::default(),
             view_poses: (0..NUM_VIEWS)
                 .map(|_| {
                     let mut pose = Posef::IDENTITY;
@@ -227,11 +262,15 @@ impl State {
             self.input_state.process_event(keyboard_input)
         }
 
-        // Camera position & Rotation
-        let pose = &mut self.view_poses[0];
+        // Tab switches between flying the camera and moving each hand.
+        if self.input_state.just_pressed.contains(&VirtualKeyCode::Tab) {
+            self.input_mode = self.input_mode.next();
+            println!("[HOTHAM_SIMULATOR] Now controlling {:?}", self.input_mode);
+        }
+        self.input_state.just_pressed.clear();
 
-        let position = &mut pose.position;
-        let o = pose.orientation;
+        // Movement is always relative to where the camera is looking.
+        let o = self.view_poses[0].orientation;
         let orientation = Quat::from_xyzw(o.x, o.y, o.z, o.w);
         // get the forward vector rotated by the camera rotation quaternion
         let forward = orientation * Vec3::Z;
@@ -239,36 +278,16 @@ impl State {
         let right = orientation * Vec3::X;
         let up = Vec3::Y;
 
-        let movement_speed = 2f32 * dt;
+        let mut direction = Vec3::ZERO;
         for pressed in self.input_state.pressed.iter() {
             match pressed {
-                winit::event::VirtualKeyCode::W => {
-                    position.x -= forward.x * movement_speed;
-                    position.y -= forward.y * movement_speed;
-                    position.z -= forward.z * movement_speed;
-                }
-                winit::event::VirtualKeyCode::S => {
-                    position.x += forward.x * movement_speed;
-                    position.y += forward.y * movement_speed;
-                    position.z += forward.z * movement_speed;
-                }
-                winit::event::VirtualKeyCode::A => {
-                    position.x -= right.x * movement_speed;
-                    position.y -= right.y * movement_speed;
-                    position.z -= right.z * movement_speed;
-                }
-                winit::event::VirtualKeyCode::D => {
-                    position.x += right.x * movement_speed;
-                    position.y += right.y * movement_speed;
-                    position.z += right.z * movement_speed;
-                }
-                winit::event::VirtualKeyCode::Space => {
-                    position.y += up.y * movement_speed;
-                }
-                winit::event::VirtualKeyCode::LShift => {
-                    position.y -= up.y * movement_speed;
-                }
-                winit::event::VirtualKeyCode::Q | winit::event::VirtualKeyCode::Escape => {
+                VirtualKeyCode::W => direction -= forward,
+                VirtualKeyCode::S => direction += forward,
+                VirtualKeyCode::A => direction -= right,
+                VirtualKeyCode::D => direction += right,
+                VirtualKeyCode::Space => direction += up,
+                VirtualKeyCode::LShift => direction -= up,
+                VirtualKeyCode::Q | VirtualKeyCode::Escape => {
                     self.session_state = SessionState::EXITING;
                     self.has_event = true;
                 }
@@ -276,6 +295,26 @@ impl State {
             }
         }
 
+        match self.input_mode.hand() {
+            None => {
+                let movement = direction * 2. * dt;
+                let position = &mut self.view_poses[0].position;
+                position.x += movement.x;
+                position.y += movement.y;
+                position.z += movement.z;
+            }
+            Some((hand, _)) => {
+                // Hands move slower than the camera, so they can be placed precisely.
+                let movement = direction * 0.5 * dt;
+                for space_state in self.hand_spaces(hand) {
+                    let position = &mut space_state.position;
+                    position.x += movement.x;
+                    position.y += movement.y;
+                    position.z += movement.z;
+                }
+            }
+        }
+
         self.view_poses[1] = self.view_poses[0];
         Some(())
     }
@@ -295,9 +334,25 @@ impl State {
             return Some(());
         }
 
+        let mouse_sensitivity = 0.2 * std::f32::consts::TAU / 360f32;
+
+        // Turn the hand being moved around the world's up axis and its own side axis.
+        if let Some((hand, _)) = self.input_mode.hand() {
+            let yaw = Quat::from_rotation_y(x_rot * mouse_sensitivity);
+            let pitch = Quat::from_rotation_x(y_rot * mouse_sensitivity);
+            for space_state in self.hand_spaces(hand) {
+                let o = space_state.orientation;
+                let rotation = yaw * Quat::from_xyzw(o.x, o.y, o.z, o.w) * pitch;
+                space_state.orientation.x = rotation.x;
+                space_state.orientation.y = rotation.y;
+                space_state.orientation.z = rotation.z;
+                space_state.orientation.w = rotation.w;
+            }
+            return Some(());
+        }
+
         // Camera position & Rotation
         let pose = &mut self.view_poses[0];
-        let mouse_sensitivity = 0.2 * std::f32::consts::TAU / 360f32;
 
         let rotation =
             Quat::from_euler(glam::EulerRot::YXZ, self.camera.yaw, self.camera.pitch, 0.);
@@ -341,6 +396,31 @@ impl State {
                 _ => {}
             }
         }
+
+        // The trigger, grip and thumbstick belong to the hand being moved.
+        let hand = match self.input_mode.hand() {
+            Some((_, hand)) => hand,
+            None => return,
+        };
+        let mut thumbstick = (0., 0.);
+        for pressed in &self.input_state.clone().pressed {
+            match pressed {
+                VirtualKeyCode::E => self.set_float(hand, "trigger/value", 1.),
+                VirtualKeyCode::F => self.set_float(hand, "squeeze/value", 1.),
+                VirtualKeyCode::Left => thumbstick.0 -= 1.,
+                VirtualKeyCode::Right => thumbstick.0 += 1.,
+                VirtualKeyCode::Up => thumbstick.1 += 1.,
+                VirtualKeyCode::Down => thumbstick.1 -= 1.,
+                _ => {}
+            }
+        }
+        self.set_float(hand, "thumbstick/x", thumbstick.0);
+        self.set_float(hand, "thumbstick/y", thumbstick.1);
+    }
+
+    /// The value of a float action for one hand, or the other subaction path it was queried with.
+    pub fn get_action_state_float(&self, action: Action, subaction_path: Path) -> f32 {
+        self.action_state.get_float(action, subaction_path)
     }
 
     /// Checks to see whether a specific action has been triggered.
@@ -348,6 +428,24 @@ impl State {
         self.action_state.get_boolean(action)
     }
 
+    /// The grip and aim spaces of a hand
+    fn hand_spaces(&mut self, hand: &'static str) -> impl Iterator<Item = &mut SpaceState> + '_ {
+        self.spaces
+            .values_mut()
+            .filter(move |space_state| space_state.name == hand)
+    }
+
+    fn set_float(&mut self, hand: &str, input: &str, value: f32) {
+        let (subaction_path, path) = match (
+            self.string_path.get(hand),
+            self.string_path.get(&format!("{hand}/input/{input}")),
+        ) {
+            (Some(subaction_path), Some(path)) => (*subaction_path, *path),
+            _ => return,
+        };
+        self.action_state.set_float(&path, subaction_path, value);
+    }
+
     fn press(&mut self, path_string: &str) {
         let path = self.string_path.get(path_string).unwrap();
         self.action_state.set_boolean(path, true);
@@ -1,10 +1,13 @@
 /// Representation of a glTF Scene
 pub mod scene;
+/// Packing small textures together at import
+mod texture_atlas;
 
 use crate::{
     components::{
-        animation_controller::AnimationController, Collider, GlobalTransform, Info, LocalTransform,
-        Mesh, Parent, Root, Skin, Visible,
+        animation_controller::AnimationController, lod::DEFAULT_LOD_SWITCH_DISTANCE, Collider,
+        GlobalTransform, Info, LocalTransform, Lod, Mesh, MorphWeights, Parent, Root, Skin,
+        Visible,
     },
     contexts::{
         physics_context::{self},
@@ -21,15 +24,26 @@ use itertools::Itertools;
 use rapier3d::prelude::{ActiveCollisionTypes, Group};
 use std::{borrow::Cow, collections::HashMap, convert::TryInto};
 
-use self::scene::Scene;
+use self::{scene::Scene, texture_atlas::TextureAtlas};
 
 static COLLIDER_TAG: &str = ".HOTHAM_COLLIDER";
 static WALL_COLLIDER_TAG: &str = ".HOTHAM_COLLIDER_WALL";
 static SENSOR_COLLIDER_TAG: &str = ".HOTHAM_COLLIDER_SENSOR";
+static LOD_TAG: &str = "_LOD";
 
 /// Convenience type for models
 pub type Models = HashMap<String, World>;
 
+/// Settings that change how glTF files are imported
+#[derive(Debug, Clone, Default)]
+pub struct ImportSettings {
+    /// Pack small uncompressed textures into a single atlas texture, remapping the texture coordinates of the
+    /// primitives that use them. This saves memory and descriptor changes in UI and icon heavy scenes. Only base
+    /// color textures of materials without other textures, whose texture coordinates stay between zero and one, are
+    /// packed. Off by default.
+    pub texture_atlas: bool,
+}
+
 /// Convenience struct to hold all the necessary bits and pieces during the import of a single glTF file
 pub(crate) struct ImportContext<'a> {
     pub vulkan_context: &'a VulkanContext,
@@ -40,6 +54,8 @@ pub(crate) struct ImportContext<'a> {
     pub document: Document,
     pub buffer: Cow<'a, [u8]>,
     pub material_buffer_offset: u32,
+    pub import_settings: ImportSettings,
+    pub texture_atlas: TextureAtlas,
 }
 
 impl<'a> ImportContext<'a> {
@@ -47,6 +63,7 @@ impl<'a> ImportContext<'a> {
         vulkan_context: &'a VulkanContext,
         render_context: &'a mut RenderContext,
         glb_buffer: &'a [u8],
+        import_settings: &ImportSettings,
     ) -> Self {
         let glb = gltf::Glb::from_slice(glb_buffer).unwrap();
         let json = gltf::json::Root::from_slice(&glb.json).unwrap();
@@ -63,6 +80,8 @@ impl<'a> ImportContext<'a> {
             document,
             buffer,
             material_buffer_offset,
+            import_settings: import_settings.clone(),
+            texture_atlas: Default::default(),
         }
     }
 }
@@ -72,11 +91,27 @@ pub fn load_scene_from_glb(
     glb_buffer: &[u8],
     vulkan_context: &VulkanContext,
     render_context: &mut RenderContext,
+) -> Result<Scene> {
+    load_scene_from_glb_with_settings(
+        glb_buffer,
+        vulkan_context,
+        render_context,
+        &Default::default(),
+    )
+}
+
+/// Load glTF scene from a GLB file, with custom [`ImportSettings`]
+pub fn load_scene_from_glb_with_settings(
+    glb_buffer: &[u8],
+    vulkan_context: &VulkanContext,
+    render_context: &mut RenderContext,
+    import_settings: &ImportSettings,
 ) -> Result<Scene> {
     // Global models map, shared between imports.
     let mut models = HashMap::new();
 
-    let mut import_context = ImportContext::new(vulkan_context, render_context, glb_buffer);
+    let mut import_context =
+        ImportContext::new(vulkan_context, render_context, glb_buffer, import_settings);
     load_models_from_gltf_data(&mut import_context).unwrap();
 
     // Take all the models we imported and add them to the global map
@@ -110,12 +145,28 @@ pub fn load_models_from_glb(
     glb_buffers: &[&[u8]],
     vulkan_context: &VulkanContext,
     render_context: &mut RenderContext,
+) -> Result<Models> {
+    load_models_from_glb_with_settings(
+        glb_buffers,
+        vulkan_context,
+        render_context,
+        &Default::default(),
+    )
+}
+
+/// Load glTF models from an array of GLB files, with custom [`ImportSettings`]
+pub fn load_models_from_glb_with_settings(
+    glb_buffers: &[&[u8]],
+    vulkan_context: &VulkanContext,
+    render_context: &mut RenderContext,
+    import_settings: &ImportSettings,
 ) -> Result<Models> {
     // Global models map, shared between imports.
     let mut models = HashMap::new();
 
     for glb_buffer in glb_buffers {
-        let mut import_context = ImportContext::new(vulkan_context, render_context, glb_buffer);
+        let mut import_context =
+            ImportContext::new(vulkan_context, render_context, glb_buffer, import_settings);
         load_models_from_gltf_data(&mut import_context).unwrap();
 
         // Take all the models we imported and add them to the global map
@@ -135,6 +186,11 @@ fn load_models_from_gltf_data(import_context: &mut ImportContext) -> Result<()>
     // Identify meshes that will be used for collider geometry.
     let collider_mesh_ids = get_collider_mesh_ids(document.nodes());
 
+    // The atlas has to be built before meshes are loaded, so that their texture coordinates can be remapped.
+    if import_context.import_settings.texture_atlas {
+        import_context.texture_atlas = TextureAtlas::build(import_context);
+    }
+
     for mesh in document.meshes() {
         // Don't load meshes that are going to be used as collider geometry
         if collider_mesh_ids.contains(&mesh.index()) {
@@ -157,8 +213,8 @@ fn load_models_from_gltf_data(import_context: &mut ImportContext) -> Result<()>
 
     // Iterate through each of the root nodes in the scene and load it in.
     for node in scene.nodes() {
-        // Don't add wall collider geometry as nodes.
-        if node.name().unwrap_or_default().ends_with(WALL_COLLIDER_TAG) {
+        // Don't add wall collider geometry or lower levels of detail as nodes.
+        if node.name().unwrap_or_default().ends_with(WALL_COLLIDER_TAG) || is_lower_lod(&node) {
             continue;
         }
 
@@ -219,6 +275,11 @@ fn get_collider_mesh_ids(nodes: gltf::iter::Nodes) -> Vec<usize> {
 }
 
 fn load_skins(node: gltf::Node, import_context: &mut ImportContext) {
+    // Lower levels of detail don't have entities of their own.
+    if is_lower_lod(&node) {
+        return;
+    }
+
     if let Some(skin) = node.skin() {
         // Load the skin
         let skin = Skin::load(skin, import_context);
@@ -275,7 +336,24 @@ fn load_node(
         .and_then(|m| import_context.mesh_map.get(&m.index()))
     {
         world
-            .insert(this_entity, (mesh.clone(), Visible {}))
+            .insert(this_entity, (mesh.clone(), Visible(true)))
+            .unwrap();
+    }
+
+    // If the node's mesh has morph targets, give it weights to control them with.
+    if let Some(morph_weights) = MorphWeights::load(node) {
+        world.insert_one(this_entity, morph_weights).unwrap();
+    }
+
+    // If this is the most detailed level of an LOD group, gather up the other levels.
+    if let Some(lod) = get_lod_for_node(node, import_context) {
+        world.insert_one(this_entity, lod).unwrap();
+    }
+
+    // If the node has a KHR_lights_punctual light, add it as a component. Its transform comes from the node.
+    if let Some(light) = node.light() {
+        world
+            .insert_one(this_entity, Light::from_gltf_local(&light))
             .unwrap();
     }
 
@@ -290,13 +368,57 @@ fn load_node(
     }
 
     // Now walk through each of this node's children and load them in.
-    for child in node.children() {
+    for child in node.children().filter(|n| !is_lower_lod(n)) {
         load_node(&child, import_context, world, false);
     }
 
     this_entity
 }
 
+/// Split a node name like `tree_LOD1` into its base name and level of detail, eg. `("tree", 1)`.
+fn parse_lod_name(name: &str) -> Option<(&str, usize)> {
+    let (base, level) = name.rsplit_once(LOD_TAG)?;
+    Some((base, level.parse().ok()?))
+}
+
+/// Lower levels of detail (`_LOD1`, `_LOD2`..) are folded into the [`Lod`] of their `_LOD0` node.
+fn is_lower_lod(node: &gltf::Node) -> bool {
+    matches!(node.name().and_then(parse_lod_name), Some((_, level)) if level > 0)
+}
+
+/// Searches through the glTF document for the other levels of detail of a node named `foo_LOD0`, ie. nodes named
+/// `foo_LOD1`, `foo_LOD2` and so on, then creates an [`Lod`] from their meshes.
+fn get_lod_for_node(node: &gltf::Node, import_context: &ImportContext) -> Option<Lod> {
+    let base = match node.name().and_then(parse_lod_name) {
+        Some((base, 0)) => base,
+        _ => return None,
+    };
+
+    let find_mesh = |name: &str| {
+        import_context
+            .document
+            .nodes()
+            .find(|n| n.name() == Some(name))
+            .and_then(|n| n.mesh())
+            .and_then(|m| import_context.mesh_map.get(&m.index()))
+            .cloned()
+    };
+
+    let meshes = (0..)
+        .map_while(|level| find_mesh(&format!("{base}{LOD_TAG}{level}")))
+        .collect::<Vec<_>>();
+
+    if meshes.len() < 2 {
+        return None;
+    }
+
+    println!(
+        "[HOTHAM_ASSET_IMPORTER] Created {} levels of detail for {base}",
+        meshes.len()
+    );
+    Some(Lod::from_meshes(meshes, DEFAULT_LOD_SWITCH_DISTANCE))
+}
+
 /// Searches through the glTF document to find a mesh that can be used by Hotham to represent a collider, then creates one.
 ///
 /// There are two kinds of colliders we're looking for:
@@ -417,7 +539,7 @@ fn build_node_hierarchy(
 ) {
     let this_entity = node_entity_map.get(&node_data.index()).unwrap();
     let parent = Parent(*this_entity);
-    for child_node in node_data.children() {
+    for child_node in node_data.children().filter(|n| !is_lower_lod(n)) {
         let child_id = child_node.index();
         let child_entity = node_entity_map.get(&child_id).unwrap();
         world.insert_one(*child_entity, parent).unwrap();
@@ -533,6 +655,24 @@ pub fn add_model_to_world(
                 .unwrap();
         }
 
+        if let Some(morph_weights) = source_entity.get::<&MorphWeights>() {
+            destination_world
+                .insert_one(*destination_entity, (*morph_weights).clone())
+                .unwrap();
+        }
+
+        if let Some(lod) = source_entity.get::<&Lod>() {
+            destination_world
+                .insert_one(*destination_entity, (*lod).clone())
+                .unwrap();
+        }
+
+        if let Some(light) = source_entity.get::<&Light>() {
+            destination_world
+                .insert_one(*destination_entity, (*light).clone())
+                .unwrap();
+        }
+
         // If the entity had a collider attached, clone it and insert it into the new world. Its underlying will be handled by `PhysicsContext`.
         if let Some(collider) = source_entity.get::<&Collider>() {
             destination_world
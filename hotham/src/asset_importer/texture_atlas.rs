@@ -0,0 +1,318 @@
+use std::{collections::HashMap, io::Cursor};
+
+use glam::Vec2;
+use gltf::{image::Source, Document};
+use image::{io::Reader as ImageReader, RgbaImage};
+
+use crate::rendering::{
+    sampler::SamplerDesc,
+    texture::{decode_rgba8, Texture, TextureUsage},
+};
+
+use super::ImportContext;
+
+/// The width and height of the atlas each import packs its textures into
+pub const ATLAS_SIZE: u32 = 2048;
+
+/// Textures larger than this in either dimension are never packed into the atlas
+pub const MAX_ATLAS_ENTRY_SIZE: u32 = 256;
+
+/// Each texture's edge is repeated this many pixels around it, so that filtering doesn't bleed its neighbours in
+const ATLAS_PADDING: u32 = 2;
+
+/// Where a texture ended up in the atlas
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct AtlasRegion {
+    /// The index of the atlas in the bindless texture array
+    pub texture_id: u32,
+    /// The texture coordinates of the texture's top left corner in the atlas
+    pub uv_offset: Vec2,
+    /// The size of the texture in the atlas, in texture coordinates
+    pub uv_scale: Vec2,
+}
+
+impl AtlasRegion {
+    /// Map texture coordinates of the original texture to the same texel in the atlas
+    pub fn remap(&self, uv: Vec2) -> Vec2 {
+        uv * self.uv_scale + self.uv_offset
+    }
+}
+
+/// Small textures packed together into a single texture during an import, so that UI and icon heavy scenes don't
+/// need a texture per icon. See [`ImportSettings::texture_atlas`](super::ImportSettings::texture_atlas).
+///
+/// Texture coordinates can't be remapped per texture, so a texture is only packed if every material using it has
+/// no other textures, samples it with the first set of texture coordinates and the default filtering, and every
+/// primitive using those materials keeps its coordinates between zero and one, as the atlas can't repeat it.
+#[derive(Debug, Default)]
+pub(crate) struct TextureAtlas {
+    /// The region of each packed texture, indexed by its index in the glTF document
+    pub regions: HashMap<usize, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// Pack every texture in the document that can be packed into an atlas, and upload it.
+    pub fn build(import_context: &mut ImportContext) -> Self {
+        let candidates = find_candidates(&import_context.document, &import_context.buffer);
+        if candidates.len() < 2 {
+            return Default::default();
+        }
+
+        let sizes = candidates
+            .iter()
+            .map(|(_, image)| {
+                (
+                    image.width() + 2 * ATLAS_PADDING,
+                    image.height() + 2 * ATLAS_PADDING,
+                )
+            })
+            .collect::<Vec<_>>();
+        let positions = pack_shelves(&sizes, ATLAS_SIZE);
+
+        let mut atlas = RgbaImage::new(ATLAS_SIZE, ATLAS_SIZE);
+        let mut packed = Vec::new();
+        for ((texture_index, image), position) in candidates.iter().zip(&positions) {
+            let (x, y) = match position {
+                Some(position) => *position,
+                None => continue,
+            };
+            blit_padded(&mut atlas, image, x, y);
+            packed.push((*texture_index, x + ATLAS_PADDING, y + ATLAS_PADDING, image));
+        }
+        if packed.len() < 2 {
+            return Default::default();
+        }
+
+        println!(
+            "[HOTHAM_TEXTURE_ATLAS] Packed {} of {} textures into an atlas",
+            packed.len(),
+            candidates.len()
+        );
+        let texture = Texture::from_rgba8(
+            "Texture Atlas",
+            import_context.vulkan_context,
+            import_context.render_context,
+            atlas,
+            TextureUsage::BaseColor,
+        );
+
+        let atlas_size = Vec2::splat(ATLAS_SIZE as f32);
+        let regions = packed
+            .into_iter()
+            .map(|(texture_index, x, y, image)| {
+                let region = AtlasRegion {
+                    texture_id: texture.index,
+                    uv_offset: Vec2::new(x as f32, y as f32) / atlas_size,
+                    uv_scale: Vec2::new(image.width() as f32, image.height() as f32) / atlas_size,
+                };
+                (texture_index, region)
+            })
+            .collect();
+
+        Self { regions }
+    }
+
+    /// The region of the base color texture of `material`, if it was packed into the atlas.
+    pub fn region_for_material(&self, material: &gltf::Material) -> Option<&AtlasRegion> {
+        let info = material.pbr_metallic_roughness().base_color_texture()?;
+        self.regions.get(&info.texture().index())
+    }
+}
+
+/// Find the textures that can be packed, along with their decompressed images.
+fn find_candidates(document: &Document, buffer: &[u8]) -> Vec<(usize, RgbaImage)> {
+    // Every material that uses a texture has to be able to use the atlas.
+    let mut packable = HashMap::new();
+    for material in document.materials() {
+        let textures = material_textures(&material);
+        let can_pack = can_pack_material(&material, document, buffer);
+        for texture_index in textures {
+            *packable.entry(texture_index).or_insert(true) &= can_pack;
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for texture in document.textures() {
+        if packable.get(&texture.index()) != Some(&true)
+            || texture.extension_value("KHR_texture_basisu").is_some()
+        {
+            continue;
+        }
+
+        // Compressed textures can't be packed without decompressing them, which would defeat the point.
+        let (bytes, mime_type) = match texture.source().source() {
+            Source::View { view, mime_type } if mime_type != "image/ktx2" => {
+                let start = view.offset();
+                (&buffer[start..start + view.length()], mime_type)
+            }
+            _ => continue,
+        };
+
+        // Reading the header is enough to rule out large textures, which are decompressed when they're loaded.
+        let dimensions = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok());
+        match dimensions {
+            Some((width, height))
+                if width <= MAX_ATLAS_ENTRY_SIZE && height <= MAX_ATLAS_ENTRY_SIZE =>
+            {
+                candidates.push((texture.index(), decode_rgba8(mime_type, bytes)));
+            }
+            _ => {}
+        }
+    }
+    candidates
+}
+
+/// The indices of every texture `material` uses
+fn material_textures(material: &gltf::Material) -> Vec<usize> {
+    let pbr_metallic_roughness = material.pbr_metallic_roughness();
+    [
+        pbr_metallic_roughness
+            .base_color_texture()
+            .map(|i| i.texture().index()),
+        pbr_metallic_roughness
+            .metallic_roughness_texture()
+            .map(|i| i.texture().index()),
+        material.normal_texture().map(|i| i.texture().index()),
+        material.occlusion_texture().map(|i| i.texture().index()),
+        material.emissive_texture().map(|i| i.texture().index()),
+        material
+            .transmission()
+            .and_then(|t| t.transmission_texture())
+            .map(|i| i.texture().index()),
+        material
+            .volume()
+            .and_then(|v| v.thickness_texture())
+            .map(|i| i.texture().index()),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Can the base color texture of `material` be moved into the atlas, by remapping the texture coordinates of
+/// every primitive that uses it?
+fn can_pack_material(material: &gltf::Material, document: &Document, buffer: &[u8]) -> bool {
+    let base_color_texture = match material.pbr_metallic_roughness().base_color_texture() {
+        Some(info) => info,
+        None => return false,
+    };
+    if material_textures(material).len() != 1
+        || base_color_texture.tex_coord() != 0
+        || !has_default_filtering(&base_color_texture.texture().sampler())
+    {
+        return false;
+    }
+
+    document
+        .meshes()
+        .flat_map(|mesh| mesh.primitives())
+        .filter(|primitive| primitive.material().index() == material.index())
+        .all(|primitive| {
+            let reader = primitive.reader(|_| Some(buffer));
+            match reader.read_tex_coords(0) {
+                Some(tex_coords) => tex_coords
+                    .into_f32()
+                    .all(|[u, v]| (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)),
+                None => true,
+            }
+        })
+}
+
+fn has_default_filtering(sampler: &gltf::texture::Sampler) -> bool {
+    let sampler_desc = SamplerDesc::from_gltf(sampler);
+    let default = SamplerDesc::default();
+    sampler_desc.mag_filter == default.mag_filter && sampler_desc.min_filter == default.min_filter
+}
+
+/// Pack rectangles into rows ("shelves") of an atlas `atlas_size` pixels square, tallest first. Returns the top left
+/// corner of each rectangle, or `None` for those that didn't fit.
+fn pack_shelves(sizes: &[(u32, u32)], atlas_size: u32) -> Vec<Option<(u32, u32)>> {
+    let mut order = (0..sizes.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| std::cmp::Reverse(sizes[i].1));
+
+    let mut positions = vec![None; sizes.len()];
+    let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+    for i in order {
+        let (width, height) = sizes[i];
+        if x + width > atlas_size {
+            x = 0;
+            y += shelf_height;
+            shelf_height = 0;
+        }
+        if x + width > atlas_size || y + height > atlas_size {
+            continue;
+        }
+        positions[i] = Some((x, y));
+        x += width;
+        shelf_height = shelf_height.max(height);
+    }
+    positions
+}
+
+/// Copy `image` into `atlas` with its top left corner at `x` and `y`, surrounded by `ATLAS_PADDING` pixels of its own
+/// edge.
+fn blit_padded(atlas: &mut RgbaImage, image: &RgbaImage, x: u32, y: u32) {
+    let (width, height) = image.dimensions();
+    for py in 0..height + 2 * ATLAS_PADDING {
+        for px in 0..width + 2 * ATLAS_PADDING {
+            let sx = px.saturating_sub(ATLAS_PADDING).min(width - 1);
+            let sy = py.saturating_sub(ATLAS_PADDING).min(height - 1);
+            atlas.put_pixel(x + px, y + py, *image.get_pixel(sx, sy));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    #[test]
+    pub fn test_pack_shelves() {
+        let positions = pack_shelves(&[(60, 20), (50, 40), (40, 40), (30, 30), (200, 10)], 100);
+
+        // Tallest first, starting a new shelf whenever the current one is full.
+        assert_eq!(positions[1], Some((0, 0)));
+        assert_eq!(positions[2], Some((50, 0)));
+        assert_eq!(positions[3], Some((0, 40)));
+        assert_eq!(positions[0], Some((30, 40)));
+
+        // Too wide to ever fit
+        assert_eq!(positions[4], None);
+    }
+
+    #[test]
+    pub fn test_blit_padded() {
+        let mut image = RgbaImage::new(2, 1);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        image.put_pixel(1, 0, Rgba([0, 255, 0, 255]));
+
+        let mut atlas = RgbaImage::new(8, 8);
+        blit_padded(&mut atlas, &image, 1, 1);
+        let p = ATLAS_PADDING;
+        assert_eq!(atlas.get_pixel(1 + p, 1 + p), &Rgba([255, 0, 0, 255]));
+        assert_eq!(atlas.get_pixel(2 + p, 1 + p), &Rgba([0, 255, 0, 255]));
+
+        // The edges are repeated into the padding.
+        assert_eq!(atlas.get_pixel(1, 1), &Rgba([255, 0, 0, 255]));
+        assert_eq!(
+            atlas.get_pixel(2 + 2 * p, 1 + 2 * p),
+            &Rgba([0, 255, 0, 255])
+        );
+        assert_eq!(atlas.get_pixel(0, 0), &Rgba([0, 0, 0, 0]));
+    }
+
+    #[test]
+    pub fn test_remap() {
+        let region = AtlasRegion {
+            texture_id: 3,
+            uv_offset: Vec2::new(0.5, 0.25),
+            uv_scale: Vec2::new(0.125, 0.25),
+        };
+        assert_eq!(region.remap(Vec2::ZERO), Vec2::new(0.5, 0.25));
+        assert_eq!(region.remap(Vec2::ONE), Vec2::new(0.625, 0.5));
+    }
+}
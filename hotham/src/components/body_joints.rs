@@ -0,0 +1,111 @@
+use glam::Affine3A;
+
+use crate::{components::hand_joints::HAND_JOINT_COUNT, xr};
+
+/// The number of joints in a tracked body, as defined by `XR_FB_body_tracking`. The first
+/// [`BODY_UPPER_JOINT_COUNT`] are the joints of [`BodyJoint`], followed by the joints of the left hand and then the
+/// right, in the order of [`xr::HandJoint`].
+pub const BODY_JOINT_COUNT: usize = 70;
+
+/// The number of joints of the upper body, before the joints of the hands
+pub const BODY_UPPER_JOINT_COUNT: usize = 18;
+
+/// A joint of a tracked body, other than the joints of the hands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyJoint {
+    Root,
+    Hips,
+    SpineLower,
+    SpineMiddle,
+    SpineUpper,
+    Chest,
+    Neck,
+    Head,
+    LeftShoulder,
+    LeftScapula,
+    LeftArmUpper,
+    LeftArmLower,
+    LeftHandWristTwist,
+    RightShoulder,
+    RightScapula,
+    RightArmUpper,
+    RightArmLower,
+    RightHandWristTwist,
+}
+
+/// A component that's added to an entity to receive the joints of the player's body, as estimated by the runtime
+/// from where their head and hands are. Useful for posing an avatar.
+/// Requires body tracking to be enabled with [`EngineBuilder::body_tracking`](crate::EngineBuilder::body_tracking)
+/// and `body_tracking_system`
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyJoints {
+    /// Was the body tracked this frame? If not, `joints` are where they were last seen.
+    pub is_tracked: bool,
+    /// How confident the runtime is in the joints, from 0 to 1
+    pub confidence: f32,
+    /// The pose of every joint in global space. Joints the runtime couldn't estimate are where they were last seen.
+    pub joints: [Affine3A; BODY_JOINT_COUNT],
+}
+
+impl Default for BodyJoints {
+    fn default() -> Self {
+        Self {
+            is_tracked: false,
+            confidence: 0.,
+            joints: [Affine3A::IDENTITY; BODY_JOINT_COUNT],
+        }
+    }
+}
+
+impl BodyJoints {
+    /// Get the pose of a joint of the upper body, eg. `BodyJoint::Head`
+    pub fn joint(&self, joint: BodyJoint) -> &Affine3A {
+        &self.joints[joint as usize]
+    }
+
+    /// Get the pose of a joint of the left hand, eg. `xr::HandJoint::INDEX_TIP`
+    pub fn left_hand_joint(&self, joint: xr::HandJoint) -> &Affine3A {
+        &self.joints[BODY_UPPER_JOINT_COUNT + joint.into_raw() as usize]
+    }
+
+    /// Get the pose of a joint of the right hand, eg. `xr::HandJoint::INDEX_TIP`
+    pub fn right_hand_joint(&self, joint: xr::HandJoint) -> &Affine3A {
+        &self.joints[BODY_UPPER_JOINT_COUNT + HAND_JOINT_COUNT + joint.into_raw() as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_body_joint_indices() {
+        let mut body_joints = BodyJoints::default();
+        for (index, joint) in body_joints.joints.iter_mut().enumerate() {
+            *joint = Affine3A::from_translation([index as f32, 0., 0.].into());
+        }
+
+        assert_eq!(body_joints.joint(BodyJoint::Head).translation.x, 7.);
+        assert_eq!(
+            body_joints
+                .joint(BodyJoint::RightHandWristTwist)
+                .translation
+                .x,
+            17.
+        );
+        assert_eq!(
+            body_joints
+                .left_hand_joint(xr::HandJoint::PALM)
+                .translation
+                .x,
+            18.
+        );
+        assert_eq!(
+            body_joints
+                .right_hand_joint(xr::HandJoint::LITTLE_TIP)
+                .translation
+                .x,
+            69.
+        );
+    }
+}
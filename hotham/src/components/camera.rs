@@ -0,0 +1,79 @@
+use anyhow::Result;
+use ash::vk;
+use glam::Affine3A;
+
+use crate::{
+    contexts::{RenderContext, VulkanContext},
+    id_arena::Id,
+    rendering::{
+        camera::NEAR_PLANE,
+        render_target::{RenderTarget, RenderTargetView},
+    },
+};
+
+/// A camera that renders the world into an offscreen texture every frame, from the point of view of its entity.
+///
+/// The camera looks down the negative Z axis of its entity's [`GlobalTransform`](super::GlobalTransform), with Y up.
+/// Its texture can be shown on any mesh, which is all that's needed for security monitors, mirrors (flip the
+/// camera around to face the player) and portals (move the camera with the player's head).
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::{components::Camera, rendering::material::Material};
+/// let resolution = vk::Extent2D { width: 512, height: 512 };
+/// let camera = Camera::new(vulkan_context, render_context, resolution, 60_f32.to_radians(), 0.05, 50.)?;
+/// let material = Material::unlit_texture(camera.texture_id(render_context));
+/// world.spawn((camera, LocalTransform::default(), GlobalTransform::default()));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    /// The vertical field of view, in radians. The horizontal field of view follows from the resolution.
+    pub fov_y: f32,
+    /// Distance to the near plane, in meters
+    pub near: f32,
+    /// Objects further away than this, in meters, aren't drawn
+    pub far: f32,
+    /// The render target the camera renders into, see [`RenderContext::render_targets`]
+    pub render_target: Id<RenderTarget>,
+}
+
+impl Camera {
+    /// Create a camera, along with a render target of `resolution` pixels for it to render into.
+    pub fn new(
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        resolution: vk::Extent2D,
+        fov_y: f32,
+        near: f32,
+        far: f32,
+    ) -> Result<Self> {
+        let render_target =
+            render_context.create_render_target(vulkan_context, "Camera", resolution)?;
+        Ok(Self {
+            fov_y,
+            near: near.max(NEAR_PLANE * 0.1),
+            far,
+            render_target,
+        })
+    }
+
+    /// The index of the camera's texture in the shared texture array, to be used in a
+    /// [`Material`](crate::rendering::material::Material).
+    pub fn texture_id(&self, render_context: &RenderContext) -> u32 {
+        render_context
+            .render_target_texture(self.render_target)
+            .unwrap()
+            .index
+    }
+
+    /// The view the camera renders from, given the transform of its entity into global space.
+    pub(crate) fn view(&self, global_from_camera: Affine3A) -> RenderTargetView {
+        RenderTargetView {
+            render_target: self.render_target,
+            global_from_view: global_from_camera,
+            fov_y: self.fov_y,
+            near: self.near,
+            far: self.far,
+        }
+    }
+}
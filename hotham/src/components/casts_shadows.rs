@@ -0,0 +1,8 @@
+/// A marker component that gives a point or spot [`Light`](crate::rendering::light::Light) its own shadow map.
+/// Shadows from directional lights are controlled by [`ShadowCaster`](crate::rendering::shadow::ShadowCaster)
+/// instead.
+///
+/// Shadow maps are expensive, so only a few lights can cast shadows at once; the rest are rendered without them.
+/// Requires `lights_system`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CastsShadows;
@@ -0,0 +1,41 @@
+use hecs::Entity;
+
+use super::hand::Handedness;
+
+/// A component added to an entity to draw the runtime's model of one of the player's controllers, so apps don't
+/// have to ship their own. The entity follows the controller's grip, and the model is added as its child once the
+/// runtime has it. The model is hidden while the player is using their hand instead of the controller.
+///
+/// Requires controller models to be enabled with
+/// [`EngineBuilder::controller_models`](crate::EngineBuilder::controller_models) and `controller_models_system`.
+/// On Quest, the application must also request the `com.oculus.permission.RENDER_MODEL` permission in its Android
+/// manifest.
+#[derive(Debug, Clone)]
+pub struct ControllerModel {
+    /// Which controller is this the model of?
+    pub handedness: Handedness,
+    /// Has the runtime's model been added, or failed to load? Loading is attempted each frame until it is.
+    pub is_loaded: bool,
+    /// The entities of the model with meshes
+    pub mesh_entities: Vec<Entity>,
+}
+
+impl ControllerModel {
+    /// Shortcut helper to draw the left controller
+    pub fn left() -> Self {
+        Self::new(Handedness::Left)
+    }
+
+    /// Shortcut helper to draw the right controller
+    pub fn right() -> Self {
+        Self::new(Handedness::Right)
+    }
+
+    fn new(handedness: Handedness) -> Self {
+        Self {
+            handedness,
+            is_loaded: false,
+            mesh_entities: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use crate::{
+    contexts::render_context::create_push_constant,
+    rendering::custom_material::CUSTOM_UNIFORMS_SIZE,
+};
+
+/// Draws an entity's [`super::Mesh`] with your own shaders instead of the built-in PBR shader.
+///
+/// The shaders are compiled SPIR-V, eg. from `vk_shader_macros::include_glsl!`, and are given the same vertex
+/// inputs and descriptor set (set 0) as the PBR shaders. `uniforms` is available to both shaders as a uniform
+/// block at set 1, binding 0, and can be at most [`CUSTOM_UNIFORMS_SIZE`] bytes.
+///
+/// The renderer creates a pipeline the first time a pair of shaders is drawn and reuses it after that, so clone
+/// the component rather than creating new shaders for each entity. Entities with a custom material don't cast
+/// shadows or take part in GPU culling.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::CustomMaterial;
+/// static VERT: &[u32] = include_glsl!("src/shaders/custom.vert");
+/// static FRAG: &[u32] = include_glsl!("src/shaders/custom.frag");
+/// let material = CustomMaterial::new(VERT, FRAG).with_uniforms(&Vec4::new(1., 0., 0., 1.));
+/// world.insert_one(entity, material);
+/// ```
+#[derive(Debug, Clone)]
+pub struct CustomMaterial {
+    /// SPIR-V for the vertex shader
+    pub vertex_shader: Arc<[u32]>,
+    /// SPIR-V for the fragment shader
+    pub fragment_shader: Arc<[u32]>,
+    /// The contents of the uniform block passed to the shaders
+    pub uniforms: Vec<u8>,
+}
+
+impl CustomMaterial {
+    /// Create a new custom material with no uniforms
+    pub fn new(
+        vertex_shader: impl Into<Arc<[u32]>>,
+        fragment_shader: impl Into<Arc<[u32]>>,
+    ) -> Self {
+        Self {
+            vertex_shader: vertex_shader.into(),
+            fragment_shader: fragment_shader.into(),
+            uniforms: Vec::new(),
+        }
+    }
+
+    /// Set the uniform block passed to the shaders. `T` must match the layout of the block in the shaders.
+    pub fn with_uniforms<T: 'static>(mut self, uniforms: &T) -> Self {
+        self.set_uniforms(uniforms);
+        self
+    }
+
+    /// Set the uniform block passed to the shaders. `T` must match the layout of the block in the shaders.
+    pub fn set_uniforms<T: 'static>(&mut self, uniforms: &T) {
+        assert!(
+            std::mem::size_of::<T>() <= CUSTOM_UNIFORMS_SIZE,
+            "Custom material uniforms can be at most {CUSTOM_UNIFORMS_SIZE} bytes"
+        );
+        self.uniforms = create_push_constant(uniforms).to_vec();
+    }
+
+    /// Materials that share their shaders share a pipeline.
+    pub(crate) fn pipeline_key(&self) -> (usize, usize) {
+        (
+            Arc::as_ptr(&self.vertex_shader) as *const u32 as usize,
+            Arc::as_ptr(&self.fragment_shader) as *const u32 as usize,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec4;
+
+    #[test]
+    pub fn test_custom_material() {
+        let material = CustomMaterial::new(vec![1, 2, 3], vec![4, 5, 6])
+            .with_uniforms(&Vec4::new(1., 2., 3., 4.));
+        assert_eq!(material.uniforms.len(), 16);
+        assert_eq!(&material.uniforms[0..4], &1f32.to_ne_bytes());
+
+        // Clones share their shaders, and therefore their pipeline.
+        let clone = material.clone();
+        assert_eq!(clone.pipeline_key(), material.pipeline_key());
+
+        let other = CustomMaterial::new(vec![1, 2, 3], vec![4, 5, 6]);
+        assert_ne!(other.pipeline_key(), material.pipeline_key());
+    }
+}
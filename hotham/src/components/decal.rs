@@ -0,0 +1,20 @@
+/// Projects a material onto the opaque surfaces inside a box, like a bullet hole, a poster or a scorch mark.
+///
+/// The box is a unit cube centred on the entity's [`GlobalTransform`](super::GlobalTransform), so scale the entity
+/// to size the decal. The material's base color covers the box's XY plane and is projected along its -Z axis, with
+/// its alpha blending the decal into the surface. Decals replace the base color of surfaces before they're lit, so
+/// they're lit and shadowed with the surface underneath. Transparent surfaces aren't affected.
+///
+/// Decals without a [`Visible`](super::Visible) component, or that are hidden, aren't drawn.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::{Decal, Visible};
+/// let material_id = render_context.resources.materials_buffer.push(&Material::unlit_white());
+/// world.spawn((Decal { material_id }, Visible(true), LocalTransform::default(), GlobalTransform::default()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decal {
+    /// The index of the decal's material in the materials buffer
+    pub material_id: u32,
+}
@@ -0,0 +1,41 @@
+use crate::{contexts::RenderContext, rendering::material::Material};
+
+/// A [`Material`] that can be changed at runtime, eg. to tint an object that's been hit, pulse its emission or
+/// dissolve it by raising its alpha cutoff.
+///
+/// Materials are shared: every primitive with the same `material_id` is drawn with the same material, so changing
+/// one changes all of them. The [`materials_system`](crate::systems::materials_system) compares `material` with the
+/// materials buffer every frame and writes it back if it has changed. Removing the component leaves the material as
+/// it was last written.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::DynamicMaterial;
+/// let material_id = render_context.resources.mesh_data.get(mesh.handle).unwrap().primitives[0].material_id;
+/// world.insert_one(entity, DynamicMaterial::new(material_id, render_context))?;
+///
+/// // Later, in a system:
+/// for (_, dynamic_material) in world.query_mut::<&mut DynamicMaterial>() {
+///     dynamic_material.material.set_emissive_strength(pulse);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DynamicMaterial {
+    /// The index of the material in the materials buffer
+    pub material_id: u32,
+    /// The material, written to the materials buffer whenever it changes
+    pub material: Material,
+}
+
+impl DynamicMaterial {
+    /// Start changing the material at `material_id`, starting from its current value.
+    pub fn new(material_id: u32, render_context: &RenderContext) -> Self {
+        let material = unsafe { render_context.resources.materials_buffer.as_slice() }
+            [material_id as usize]
+            .clone();
+        Self {
+            material_id,
+            material,
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use glam::{Affine3A, Quat, Vec3};
+
+/// A component that keeps an entity in front of the player's eyes, eg. a HUD or a menu that should stay in view.
+/// Requires `follow_view_system`.
+///
+/// The entity is placed at `view_from_local` relative to the player's view each frame, in global space, so it
+/// shouldn't have a [`Parent`](super::Parent). Anything parented to it, such as the panels of a menu, moves with it.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::FollowView;
+/// // A HUD half a meter in front of the player that catches up with their head instead of being stuck to it.
+/// let follow_view = FollowView::new(Affine3A::from_translation([0., 0., -0.5].into())).with_lag(0.3);
+/// engine.world.insert_one(hud, follow_view).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct FollowView {
+    /// Where the entity is relative to the player's view, looking down -Z
+    pub view_from_local: Affine3A,
+    /// How long the entity takes to catch up with the view, in seconds. It's most of the way there after `lag`, and
+    /// follows the view rigidly when this is 0. A little lag is more comfortable for HUDs, as small head movements
+    /// don't shake them.
+    pub lag: f32,
+    /// Only follow the view as the player turns their head from side to side, so the entity stays upright when they
+    /// look up or down or tilt their head
+    pub level: bool,
+    /// Has the entity been placed in front of the view yet? If not, it jumps there instead of catching up from
+    /// wherever it was spawned.
+    pub has_followed: bool,
+}
+
+impl FollowView {
+    /// Follow the view rigidly, at `view_from_local`
+    pub fn new(view_from_local: Affine3A) -> Self {
+        Self {
+            view_from_local,
+            lag: 0.,
+            level: false,
+            has_followed: false,
+        }
+    }
+
+    /// Catch up with the view over `lag` seconds, instead of following it rigidly
+    pub fn with_lag(self, lag: f32) -> Self {
+        Self { lag, ..self }
+    }
+
+    /// Stay upright, only following the view as the player turns their head from side to side
+    pub fn level(self) -> Self {
+        Self {
+            level: true,
+            ..self
+        }
+    }
+
+    /// Where the entity should be in global space, given where the player's view is
+    pub(crate) fn target(&self, global_from_view: &Affine3A) -> Affine3A {
+        let global_from_view = if self.level {
+            level(global_from_view)
+        } else {
+            *global_from_view
+        };
+        global_from_view * self.view_from_local
+    }
+}
+
+impl Default for FollowView {
+    fn default() -> Self {
+        Self::new(Affine3A::from_translation([0., 0., -1.].into()))
+    }
+}
+
+/// Turn `transform` so its -Z axis is horizontal, keeping which way it faces around the Y axis
+fn level(transform: &Affine3A) -> Affine3A {
+    let forward = transform.transform_vector3(Vec3::NEG_Z) * Vec3::new(1., 0., 1.);
+    // Looking straight up or down, face along whichever way is up from the view instead.
+    let forward = forward
+        .try_normalize()
+        .or_else(|| (transform.transform_vector3(Vec3::Y) * Vec3::new(1., 0., 1.)).try_normalize())
+        .unwrap_or(Vec3::NEG_Z);
+    let yaw = forward.x.atan2(-forward.z);
+    Affine3A::from_rotation_translation(Quat::from_rotation_y(-yaw), transform.translation.into())
+}
@@ -0,0 +1,6 @@
+/// A marker component added by [`crate::systems::frustum_culling_system`] to entities that are outside the view of
+/// both eyes. Entities with this component are skipped by the renderer.
+///
+/// You shouldn't need to add or remove this component yourself.
+#[derive(Debug, Clone, Copy)]
+pub struct FrustumCulled {}
@@ -0,0 +1,46 @@
+use glam::{Affine3A, Vec3};
+use hecs::Entity;
+use rapier3d::prelude::Group;
+
+use crate::contexts::physics_context::HAND_COLLISION_GROUP;
+
+/// A component added to an entity to find out what the player is looking at, eg. to select UI with their eyes.
+/// Requires eye gaze to be enabled with [`EngineBuilder::eye_gaze`](crate::EngineBuilder::eye_gaze) and
+/// `gaze_pointer_system`
+#[derive(Debug, Clone)]
+pub struct GazePointer {
+    /// Were the player's eyes tracked this frame? If not, the rest of the pointer is from when they last were.
+    pub is_tracked: bool,
+    /// The pose of the player's gaze in global space, looking down its -Z axis
+    pub global_from_gaze: Affine3A,
+    /// The entity with the first collider the player is looking at, if any
+    pub focused_entity: Option<Entity>,
+    /// Where the player's gaze hits `focused_entity`, in global space
+    pub focus_point: Option<Vec3>,
+    /// Colliders further away than this aren't focused, in meters
+    pub max_distance: f32,
+    /// Only colliders in these groups can be focused. Defaults to every group but the hands'.
+    pub collision_filter: Group,
+}
+
+impl Default for GazePointer {
+    fn default() -> Self {
+        Self {
+            is_tracked: false,
+            global_from_gaze: Affine3A::IDENTITY,
+            focused_entity: None,
+            focus_point: None,
+            max_distance: 40.0,
+            collision_filter: Group::all().difference(HAND_COLLISION_GROUP),
+        }
+    }
+}
+
+impl GazePointer {
+    /// The direction the player is looking in, in global space
+    pub fn direction(&self) -> Vec3 {
+        self.global_from_gaze
+            .transform_vector3(Vec3::NEG_Z)
+            .normalize_or_zero()
+    }
+}
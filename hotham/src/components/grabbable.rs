@@ -1,8 +1,53 @@
+use hecs::Entity;
+
+/// How a grabbed entity follows the hand holding it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrabMode {
+    /// The entity is made kinematic and moved to the hand every frame. It never lags behind the hand, but passes
+    /// through walls and other fixed colliders.
+    Kinematic,
+    /// The entity stays dynamic and is pulled to the hand by setting its velocities each frame, like a stiff joint.
+    /// It lags slightly behind the hand, but collides with the rest of the world. Requires a [`super::RigidBody`].
+    Physics,
+}
+
+/// A component that lets an entity be picked up by a [`super::Hand`]. Requires `hands_system` and `grabbing_system`.
+///
+/// While it's held, the entity has a [`Grabbed`] component. On the frame it's picked up it also has a [`JustGrabbed`]
+/// component, and on the frame it's let go a [`Released`] component, which other systems can use as events. When
+/// it's let go, a [`super::RigidBody`] is given the velocity of the hand so the entity can be thrown.
 #[derive(Debug, Clone, Copy)]
-pub struct Grabbable {}
+pub struct Grabbable {
+    /// How the entity follows the hand holding it
+    pub mode: GrabMode,
+    /// The velocity of the hand is multiplied by this when the entity is thrown. Values above one make throwing feel
+    /// more powerful, zero drops the entity instead.
+    pub throw_velocity_scale: f32,
+}
+
+impl Default for Grabbable {
+    fn default() -> Self {
+        Self {
+            mode: GrabMode::Kinematic,
+            throw_velocity_scale: 1.0,
+        }
+    }
+}
 
+/// A marker component for entities that are being held by a [`super::Hand`]. Only one hand can hold an entity at a time.
 #[derive(Debug, Clone, Copy)]
 pub struct Grabbed;
 
+/// A "one-shot" component added to an entity on the frame it was grabbed, and removed the next frame
+#[derive(Debug, Clone, Copy)]
+pub struct JustGrabbed {
+    /// The hand that grabbed the entity
+    pub hand: Entity,
+}
+
+/// A "one-shot" component added to an entity on the frame it was let go, and removed the next frame
 #[derive(Debug, Clone, Copy)]
-pub struct Released;
+pub struct Released {
+    /// The hand that let go of the entity
+    pub hand: Entity,
+}
@@ -1,6 +1,8 @@
-use glam::Affine3A;
+use glam::{Affine3A, Vec3};
 use hecs::Entity;
 
+use crate::components::physics::BodyType;
+
 /// A component that represents the "side" or "handedness" that an entity is on
 /// Used by components such as `Hand` and `Pointer` to identify which controller they should map to
 #[derive(Debug, PartialEq, Clone, Copy, Eq, PartialOrd, Ord)]
@@ -11,10 +13,46 @@ pub enum Handedness {
     Right,
 }
 
+impl Handedness {
+    /// The hand on the other side
+    pub fn other(self) -> Self {
+        match self {
+            Handedness::Left => Handedness::Right,
+            Handedness::Right => Handedness::Left,
+        }
+    }
+}
+
+/// A component added alongside a [`Pointer`](crate::components::Pointer),
+/// [`Teleporter`](crate::components::Teleporter), [`Haptic`](crate::components::Haptic) or
+/// [`Locomotion`](crate::components::Locomotion) to keep it in the player's dominant hand, or their other hand, as set
+/// by [`Engine::dominant_hand`](crate::Engine::dominant_hand). A `Locomotion` turns with the preferred hand and moves
+/// with the other one.
+/// Requires `dominant_hand_system`
+#[derive(Debug, PartialEq, Clone, Copy, Eq)]
+pub enum HandPreference {
+    /// The hand the player prefers to use
+    Dominant,
+    /// The hand the player doesn't prefer to use
+    OffHand,
+}
+
+impl HandPreference {
+    /// Which hand this is, for a player whose dominant hand is `dominant_hand`
+    pub fn handedness(self, dominant_hand: Handedness) -> Handedness {
+        match self {
+            HandPreference::Dominant => dominant_hand,
+            HandPreference::OffHand => dominant_hand.other(),
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct GrabbedEntity {
     pub entity: Entity,
     pub grip_from_local: Affine3A,
+    /// The body type of the entity's rigid body before it was grabbed, restored when it's released
+    pub previous_body_type: Option<BodyType>,
 }
 
 /// A component that's added to an entity to represent a "hand" presence.
@@ -22,14 +60,20 @@ pub struct GrabbedEntity {
 /// Requires `hands_system`
 #[derive(Clone)]
 pub struct Hand {
-    /// How much has this hand been gripped?
+    /// How much has this hand been gripped? Read from the input
+    /// [`EngineAction::Grab`](crate::contexts::EngineAction::Grab) is bound to, the grip by default.
     pub grip_value: f32,
-    /// Did the grip button go from not pressed to pressed this frame?
+    /// Did that input go from not pressed to pressed this frame?
     pub grip_button_just_pressed: bool,
     /// Which side is this hand on?
     pub handedness: Handedness,
     /// Have we grabbed something?
     pub grabbed_entity: Option<GrabbedEntity>,
+    /// How fast the hand is moving, in global space. Zero while the player is using their hand instead of the
+    /// controller.
+    pub linear_velocity: Vec3,
+    /// How fast the hand is spinning, in radians per second around each axis of global space
+    pub angular_velocity: Vec3,
 }
 
 impl Hand {
@@ -40,6 +84,8 @@ impl Hand {
             grip_button_just_pressed: false,
             handedness: Handedness::Left,
             grabbed_entity: None,
+            linear_velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
         }
     }
 
@@ -50,6 +96,8 @@ impl Hand {
             grip_button_just_pressed: false,
             handedness: Handedness::Right,
             grabbed_entity: None,
+            linear_velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
         }
     }
 }
@@ -0,0 +1,195 @@
+use hecs::{Entity, World};
+
+use crate::{
+    components::{hand_joints::FINGER_COUNT, AnimationController, Info},
+    contexts::input_context::ControllerState,
+};
+
+/// How curled the index finger is while it's resting on the trigger without pulling it
+const TRIGGER_TOUCH_CURL: f32 = 0.2;
+
+/// How curled the thumb is while it's resting on the controller with the grip released
+const THUMB_TOUCH_CURL: f32 = 0.5;
+
+/// A component that's added alongside [`Hand`](crate::components::Hand) and [`AnimationController`] to curl each
+/// finger of a hand model separately, following how the player is holding their controller. The index finger
+/// follows the trigger, the other fingers follow the grip and the thumb lifts off when it isn't touching anything, so
+/// the player can point, give a thumbs up or make a fist. Requires `hands_system` and `animation_system`
+///
+/// The fingers blend between the same two animations as the rest of the hand, so the model's first animation
+/// should be an open hand and its second a fist.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandAnimation {
+    /// How curled each finger is, from thumb to little finger, where 0 is the open hand and 1 the fist
+    pub finger_curls: [f32; FINGER_COUNT],
+    /// The finger moved by each of the [`AnimationController`]'s targets, or `None` for the rest of the hand, which
+    /// follows the grip
+    pub target_fingers: Vec<Option<usize>>,
+    /// How quickly a finger can curl or uncurl, in blends per second. Touch sensors are either on or off, so this
+    /// stops fingers snapping into place.
+    pub speed: f32,
+}
+
+impl HandAnimation {
+    /// Work out which finger each target of the hand's [`AnimationController`] belongs to from its name, eg.
+    /// `b_l_index2`. Returns `None` if the hand has no animations, or none of them move a finger.
+    pub fn from_hand_model(world: &World, hand_entity: Entity) -> Option<Self> {
+        let animation_controller = world.get::<&AnimationController>(hand_entity).ok()?;
+        let target_fingers = animation_controller
+            .targets
+            .iter()
+            .map(|target| {
+                let info = world.get::<&Info>(target.target).ok()?;
+                finger_from_name(&info.name)
+            })
+            .collect::<Vec<_>>();
+
+        target_fingers.iter().any(Option::is_some).then(|| Self {
+            finger_curls: [0.; FINGER_COUNT],
+            target_fingers,
+            speed: 10.,
+        })
+    }
+
+    /// Move each finger towards the pose of the player's hand on the controller, by no more than `speed` allows
+    pub fn update(&mut self, controller_state: &ControllerState, delta_time: f32) {
+        let max_change = self.speed * delta_time;
+        for (curl, target) in self
+            .finger_curls
+            .iter_mut()
+            .zip(target_finger_curls(controller_state))
+        {
+            *curl += (target - *curl).clamp(-max_change, max_change);
+        }
+    }
+
+    /// How far each of the [`AnimationController`]'s targets should be blended towards the fist
+    pub fn target_blend_amounts(&self, grip_blend_amount: f32) -> impl Iterator<Item = f32> + '_ {
+        self.target_fingers.iter().map(move |finger| {
+            finger
+                .map(|finger| self.finger_curls[finger])
+                .unwrap_or(grip_blend_amount)
+        })
+    }
+}
+
+/// How curled each finger would be with the controller in this state
+fn target_finger_curls(controller_state: &ControllerState) -> [f32; FINGER_COUNT] {
+    let thumb_touching = controller_state.primary_touch.pressed
+        || controller_state.secondary_touch.pressed
+        || controller_state.thumbstick_touch.pressed
+        || controller_state.thumbrest_touch.pressed;
+    let thumb = if thumb_touching {
+        THUMB_TOUCH_CURL + (1. - THUMB_TOUCH_CURL) * controller_state.grip
+    } else {
+        0.
+    };
+
+    let index = if controller_state.trigger_touch.pressed {
+        controller_state.trigger.max(TRIGGER_TOUCH_CURL)
+    } else {
+        controller_state.trigger
+    };
+
+    let grip = controller_state.grip;
+    [thumb, index, grip, grip, grip]
+}
+
+fn finger_from_name(name: &str) -> Option<usize> {
+    let name = name.to_lowercase();
+    ["thumb", "index", "middle", "ring", "pinky"]
+        .iter()
+        .position(|finger| name.contains(finger))
+        .or_else(|| name.contains("little").then_some(4))
+}
+
+#[cfg(test)]
+mod tests {
+    use approx::assert_relative_eq;
+    use glam::{Quat, Vec3};
+
+    use super::*;
+    use crate::{components::AnimationTarget, contexts::input_context::ButtonState};
+
+    #[test]
+    pub fn test_hand_animation_from_hand_model() {
+        let mut world = World::new();
+        let mut target = |name: &str| {
+            let entity = world.spawn((Info {
+                name: name.to_string(),
+                node_id: 0,
+            },));
+            AnimationTarget {
+                target: entity,
+                rotations: vec![Quat::IDENTITY; 2],
+                scales: vec![Vec3::ONE; 2],
+                translations: vec![Vec3::ZERO; 2],
+            }
+        };
+        let targets = vec![
+            target("b_l_wrist"),
+            target("b_l_thumb1"),
+            target("b_l_index3"),
+            target("hands:b_l_pinky0"),
+        ];
+        let hand = world.spawn((AnimationController {
+            targets,
+            ..Default::default()
+        },));
+
+        let hand_animation = HandAnimation::from_hand_model(&world, hand).unwrap();
+        assert_eq!(
+            hand_animation.target_fingers,
+            vec![None, Some(0), Some(1), Some(4)]
+        );
+
+        let no_fingers = world.spawn((AnimationController::default(),));
+        assert!(HandAnimation::from_hand_model(&world, no_fingers).is_none());
+    }
+
+    #[test]
+    pub fn test_hand_animation_update() {
+        let touched = ButtonState {
+            pressed: true,
+            ..Default::default()
+        };
+        let mut hand_animation = HandAnimation {
+            finger_curls: [0.; FINGER_COUNT],
+            target_fingers: vec![None, Some(0), Some(1), Some(2)],
+            speed: 10.,
+        };
+
+        // Pointing: the grip is squeezed, the thumb rests on the thumbstick and the index finger is lifted off.
+        let pointing = ControllerState {
+            grip: 1.,
+            thumbstick_touch: touched,
+            ..Default::default()
+        };
+        for _ in 0..20 {
+            hand_animation.update(&pointing, 1. / 72.);
+        }
+        assert_relative_eq!(&hand_animation.finger_curls[..], &[1., 0., 1., 1., 1.][..]);
+        assert_eq!(
+            hand_animation.target_blend_amounts(0.5).collect::<Vec<_>>(),
+            vec![0.5, 1., 0., 1.]
+        );
+
+        // A thumbs up: the index finger rests on the trigger, and the thumb is lifted off. Fingers don't move all the
+        // way in a single frame.
+        let thumbs_up = ControllerState {
+            grip: 1.,
+            trigger_touch: touched,
+            ..Default::default()
+        };
+        hand_animation.update(&thumbs_up, 1. / 72.);
+        assert_relative_eq!(hand_animation.finger_curls[0], 1. - 10. / 72.);
+        assert_relative_eq!(hand_animation.finger_curls[1], 10. / 72.);
+        for _ in 0..20 {
+            hand_animation.update(&thumbs_up, 1. / 72.);
+        }
+        assert_relative_eq!(
+            &hand_animation.finger_curls[..],
+            &[0., TRIGGER_TOUCH_CURL, 1., 1., 1.][..]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use crate::components::hand_joints::FINGER_COUNT;
+
+/// A gesture made with a tracked hand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gesture {
+    /// The tips of the thumb and index finger are touching
+    Pinch,
+    /// Every finger is curled into a fist
+    Grab,
+    /// The index finger is pointing, with the other fingers curled
+    Point,
+    /// The thumb is pointing up, with the other fingers curled
+    ThumbsUp,
+    /// A pose from [`HandGestures::custom_poses`], by its index
+    Custom(usize),
+}
+
+/// Something that happened to one of a hand's gestures this frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureEvent {
+    /// The player started making the gesture
+    Started(Gesture),
+    /// The player stopped making the gesture, or their hand stopped being tracked
+    Ended(Gesture),
+}
+
+/// A hand pose to recognise, described by how curled each finger is
+#[derive(Debug, Clone, PartialEq)]
+pub struct GesturePose {
+    /// A name for the pose, eg. for debugging
+    pub name: String,
+    /// How curled each finger should be, from thumb to little finger, where 0 is straight and 1 is fully curled
+    pub finger_curls: [f32; FINGER_COUNT],
+    /// How far each finger's curl can be from `finger_curls` for the pose to still match
+    pub tolerance: f32,
+}
+
+/// A component that recognises gestures made with the hand in the [`super::HandJoints`] on the same entity, so the player
+/// can interact without controllers, eg. pinching to select. Requires `hand_tracking_system` and
+/// `hand_gestures_system`.
+///
+/// The gestures being made this frame are in `active`, and the ones that started or ended this frame in `events`.
+#[derive(Debug, Clone)]
+pub struct HandGestures {
+    /// How curled each finger is, from thumb to little finger, where 0 is straight and 1 is fully curled
+    pub finger_curls: [f32; FINGER_COUNT],
+    /// The gestures being made this frame
+    pub active: Vec<Gesture>,
+    /// The gestures that started or ended this frame
+    pub events: Vec<GestureEvent>,
+    /// Extra poses to recognise, reported as [`Gesture::Custom`]
+    pub custom_poses: Vec<GesturePose>,
+    /// A pinch starts when [`super::HandJoints::pinch_distance`] drops below this, in meters..
+    pub pinch_start_distance: f32,
+    /// ..and ends when it rises above this, so a pinch held at the threshold doesn't flicker
+    pub pinch_end_distance: f32,
+}
+
+impl Default for HandGestures {
+    fn default() -> Self {
+        Self {
+            finger_curls: [0.; FINGER_COUNT],
+            active: Vec::new(),
+            events: Vec::new(),
+            custom_poses: Vec::new(),
+            pinch_start_distance: 0.005,
+            pinch_end_distance: 0.02,
+        }
+    }
+}
+
+impl HandGestures {
+    /// Is the player making this gesture?
+    pub fn is_active(&self, gesture: Gesture) -> bool {
+        self.active.contains(&gesture)
+    }
+
+    /// Did the player start making this gesture this frame?
+    pub fn just_started(&self, gesture: Gesture) -> bool {
+        self.events.contains(&GestureEvent::Started(gesture))
+    }
+
+    /// Did the player stop making this gesture this frame?
+    pub fn just_ended(&self, gesture: Gesture) -> bool {
+        self.events.contains(&GestureEvent::Ended(gesture))
+    }
+}
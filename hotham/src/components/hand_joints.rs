@@ -0,0 +1,155 @@
+use glam::{Affine3A, Vec3};
+
+use crate::{components::hand::Handedness, xr};
+
+/// The number of joints in a tracked hand, as defined by `XR_EXT_hand_tracking`
+pub const HAND_JOINT_COUNT: usize = 26;
+
+/// The number of fingers on a hand, counting the thumb
+pub const FINGER_COUNT: usize = 5;
+
+/// Where a single joint of a tracked hand is
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HandJointLocation {
+    /// The pose of the joint. In [`HandJoints`] this is relative to the global space; in the
+    /// [`InputContext`](crate::contexts::InputContext) it's relative to the stage.
+    pub transform: Affine3A,
+    /// The radius of the hand around the joint, in meters
+    pub radius: f32,
+}
+
+/// A component that's added to an entity to receive the joints of one of the player's hands, when they're using
+/// their hands instead of controllers.
+/// Requires hand tracking to be enabled with [`EngineBuilder::hand_tracking`](crate::EngineBuilder::hand_tracking)
+/// and `hand_tracking_system`
+#[derive(Debug, Clone)]
+pub struct HandJoints {
+    /// Which hand are these the joints of?
+    pub handedness: Handedness,
+    /// Was the hand tracked this frame? If not, `joints` are where it was last seen.
+    pub is_tracked: bool,
+    /// The location of every joint, indexed by [`xr::HandJoint`]
+    pub joints: [HandJointLocation; HAND_JOINT_COUNT],
+}
+
+impl HandJoints {
+    /// Shortcut helper to receive the joints of the left hand
+    pub fn left() -> Self {
+        Self::new(Handedness::Left)
+    }
+
+    /// Shortcut helper to receive the joints of the right hand
+    pub fn right() -> Self {
+        Self::new(Handedness::Right)
+    }
+
+    fn new(handedness: Handedness) -> Self {
+        Self {
+            handedness,
+            is_tracked: false,
+            joints: [Default::default(); HAND_JOINT_COUNT],
+        }
+    }
+
+    /// Get the location of a joint, eg. `xr::HandJoint::INDEX_TIP`
+    pub fn joint(&self, joint: xr::HandJoint) -> &HandJointLocation {
+        &self.joints[joint.into_raw() as usize]
+    }
+
+    /// The distance between the surfaces of the tips of the thumb and index finger, which is zero or less while
+    /// they're pinched together.
+    pub fn pinch_distance(&self) -> f32 {
+        let thumb_tip = self.joint(xr::HandJoint::THUMB_TIP);
+        let index_tip = self.joint(xr::HandJoint::INDEX_TIP);
+        thumb_tip
+            .transform
+            .translation
+            .distance(index_tip.transform.translation)
+            - thumb_tip.radius
+            - index_tip.radius
+    }
+
+    /// How curled each finger is, from thumb to little finger: the angle between the directions of the base and the
+    /// tip of the finger, where 0 is straight and 1 is bent all the way back
+    pub fn finger_curls(&self) -> [f32; FINGER_COUNT] {
+        FINGER_JOINTS.map(|[metacarpal, proximal, distal, tip]| {
+            let position = |joint| Vec3::from(self.joint(joint).transform.translation);
+            let base = position(proximal) - position(metacarpal);
+            let tip = position(tip) - position(distal);
+            if base == Vec3::ZERO || tip == Vec3::ZERO {
+                return 0.;
+            }
+            base.angle_between(tip) / std::f32::consts::PI
+        })
+    }
+
+    /// The direction the thumb is pointing in, in global space
+    pub fn thumb_direction(&self) -> Vec3 {
+        let distal = self
+            .joint(xr::HandJoint::THUMB_DISTAL)
+            .transform
+            .translation;
+        let tip = self.joint(xr::HandJoint::THUMB_TIP).transform.translation;
+        Vec3::from(tip - distal).normalize_or_zero()
+    }
+}
+
+/// The joints that give the direction of the base and the tip of each finger
+const FINGER_JOINTS: [[xr::HandJoint; 4]; FINGER_COUNT] = [
+    [
+        xr::HandJoint::THUMB_METACARPAL,
+        xr::HandJoint::THUMB_PROXIMAL,
+        xr::HandJoint::THUMB_DISTAL,
+        xr::HandJoint::THUMB_TIP,
+    ],
+    [
+        xr::HandJoint::INDEX_METACARPAL,
+        xr::HandJoint::INDEX_PROXIMAL,
+        xr::HandJoint::INDEX_DISTAL,
+        xr::HandJoint::INDEX_TIP,
+    ],
+    [
+        xr::HandJoint::MIDDLE_METACARPAL,
+        xr::HandJoint::MIDDLE_PROXIMAL,
+        xr::HandJoint::MIDDLE_DISTAL,
+        xr::HandJoint::MIDDLE_TIP,
+    ],
+    [
+        xr::HandJoint::RING_METACARPAL,
+        xr::HandJoint::RING_PROXIMAL,
+        xr::HandJoint::RING_DISTAL,
+        xr::HandJoint::RING_TIP,
+    ],
+    [
+        xr::HandJoint::LITTLE_METACARPAL,
+        xr::HandJoint::LITTLE_PROXIMAL,
+        xr::HandJoint::LITTLE_DISTAL,
+        xr::HandJoint::LITTLE_TIP,
+    ],
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_pinch_distance() {
+        let mut hand_joints = HandJoints::left();
+        hand_joints.joints[xr::HandJoint::THUMB_TIP.into_raw() as usize] = HandJointLocation {
+            transform: Affine3A::from_translation(Vec3::X * 0.05),
+            radius: 0.01,
+        };
+        hand_joints.joints[xr::HandJoint::INDEX_TIP.into_raw() as usize] = HandJointLocation {
+            transform: Affine3A::from_translation(Vec3::X * 0.1),
+            radius: 0.01,
+        };
+        assert!((hand_joints.pinch_distance() - 0.03).abs() < 1e-6);
+        assert_eq!(
+            hand_joints
+                .joint(xr::HandJoint::INDEX_TIP)
+                .transform
+                .translation,
+            (Vec3::X * 0.1).into()
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use glam::{Affine3A, Mat3, Vec3};
+use hecs::{Entity, World};
+
+use crate::{
+    components::{
+        hand::Handedness,
+        hand_joints::{HandJoints, HAND_JOINT_COUNT},
+        parent::is_descendant_of,
+        Info, Skin,
+    },
+    xr,
+};
+
+/// The joints of the hand models in `test_assets`, which use the Oculus skeleton. Joints are named `b_l_<name>` or
+/// `b_r_<name>`, depending on the hand. The Oculus skeleton has an extra joint at the base of the thumb, and only
+/// the little finger has a metacarpal, so the other tracked metacarpals and the tips don't drive anything.
+pub const OCULUS_HAND_JOINT_NAMES: [(xr::HandJoint, &str); 17] = [
+    (xr::HandJoint::WRIST, "wrist"),
+    (xr::HandJoint::THUMB_METACARPAL, "thumb1"),
+    (xr::HandJoint::THUMB_PROXIMAL, "thumb2"),
+    (xr::HandJoint::THUMB_DISTAL, "thumb3"),
+    (xr::HandJoint::INDEX_PROXIMAL, "index1"),
+    (xr::HandJoint::INDEX_INTERMEDIATE, "index2"),
+    (xr::HandJoint::INDEX_DISTAL, "index3"),
+    (xr::HandJoint::MIDDLE_PROXIMAL, "middle1"),
+    (xr::HandJoint::MIDDLE_INTERMEDIATE, "middle2"),
+    (xr::HandJoint::MIDDLE_DISTAL, "middle3"),
+    (xr::HandJoint::RING_PROXIMAL, "ring1"),
+    (xr::HandJoint::RING_INTERMEDIATE, "ring2"),
+    (xr::HandJoint::RING_DISTAL, "ring3"),
+    (xr::HandJoint::LITTLE_METACARPAL, "pinky0"),
+    (xr::HandJoint::LITTLE_PROXIMAL, "pinky1"),
+    (xr::HandJoint::LITTLE_INTERMEDIATE, "pinky2"),
+    (xr::HandJoint::LITTLE_DISTAL, "pinky3"),
+];
+
+/// A joint of a skinned hand model that follows a tracked joint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HandBone {
+    /// The index of the tracked joint, in [`HandJoints::joints`]
+    pub hand_joint: usize,
+    /// The joint of the skin that follows it
+    pub entity: Entity,
+    /// The transform from the skin's joint to the tracked joint, in the units of the skin
+    pub tracked_from_bone: Affine3A,
+}
+
+/// A component that's added alongside [`HandJoints`] to pose a skinned hand model with the player's tracked hand,
+/// instead of its animations. Requires `hand_tracking_system`
+///
+/// Tracked joints don't share the orientation of the model's joints, so each bone is matched up with its tracked
+/// joint in the model's bind pose, where a tracked joint's -Z axis would point down the finger, and its +Y axis
+/// out of the back of the hand.
+#[derive(Debug, Clone)]
+pub struct HandSkeleton {
+    /// The entity with the [`Skin`] that the bones belong to
+    pub skin_entity: Entity,
+    /// Every joint of the skin that follows a tracked joint, parents first
+    pub bones: Vec<HandBone>,
+}
+
+impl HandSkeleton {
+    /// Match up the joints of the first skin under `hand_entity` with the tracked joints, using
+    /// [`OCULUS_HAND_JOINT_NAMES`]. Returns `None` if the model doesn't use the Oculus skeleton.
+    pub fn from_hand_model(
+        world: &World,
+        hand_entity: Entity,
+        handedness: Handedness,
+    ) -> Option<Self> {
+        let prefix = match handedness {
+            Handedness::Left => "b_l_",
+            Handedness::Right => "b_r_",
+        };
+        let joint_names =
+            OCULUS_HAND_JOINT_NAMES.map(|(joint, name)| (joint, format!("{prefix}{name}")));
+        let joint_names = joint_names
+            .each_ref()
+            .map(|(joint, name)| (*joint, name.as_str()));
+        Self::from_hand_model_with_names(world, hand_entity, handedness, &joint_names)
+    }
+
+    /// Like [`HandSkeleton::from_hand_model`], but for models with other skeletons. Each tracked joint drives the
+    /// joint of the skin with the given name, ignoring any namespace like `hands:`. The wrist and the proximal
+    /// joints of the index and little fingers must be present.
+    pub fn from_hand_model_with_names(
+        world: &World,
+        hand_entity: Entity,
+        handedness: Handedness,
+        joint_names: &[(xr::HandJoint, &str)],
+    ) -> Option<Self> {
+        let (skin_entity, skin) = world
+            .query::<&Skin>()
+            .iter()
+            .find(|(entity, _)| is_descendant_of(world, *entity, hand_entity))
+            .map(|(entity, skin)| (entity, skin.clone()))?;
+
+        // Where each tracked joint is in the bind pose, if the skin has it.
+        let mut bind_from_bones = [None; HAND_JOINT_COUNT];
+        for (entity, bone_from_bind) in skin.joints.iter().zip(&skin.inverse_bind_matrices) {
+            let info = match world.get::<&Info>(*entity) {
+                Ok(info) => info,
+                Err(_) => continue,
+            };
+            let name = info.name.rsplit(':').next().unwrap_or_default();
+            if let Some((joint, _)) = joint_names.iter().find(|(_, n)| *n == name) {
+                bind_from_bones[joint.into_raw() as usize] =
+                    Some((*entity, bone_from_bind.inverse()));
+            }
+        }
+
+        let bind_from_tracked = bind_from_tracked_joints(
+            &bind_from_bones
+                .map(|bone| bone.map(|(_, bind_from_bone)| Vec3::from(bind_from_bone.translation))),
+            handedness,
+        )?;
+
+        let bones = bind_from_bones
+            .iter()
+            .zip(&bind_from_tracked)
+            .enumerate()
+            .filter_map(|(hand_joint, (bone, bind_from_tracked))| {
+                let (entity, bind_from_bone) = (*bone)?;
+                Some(HandBone {
+                    hand_joint,
+                    entity,
+                    tracked_from_bone: bind_from_tracked.as_ref()?.inverse() * bind_from_bone,
+                })
+            })
+            .collect();
+
+        Some(Self { skin_entity, bones })
+    }
+
+    /// The transform of each bone in global space, given where the tracked joints are and the global scale of the
+    /// skin. Parents come first.
+    pub fn global_from_bones<'a>(
+        &'a self,
+        hand_joints: &'a HandJoints,
+        skin_scale: Vec3,
+    ) -> impl Iterator<Item = (Entity, Affine3A)> + 'a {
+        let scale = Affine3A::from_scale(skin_scale);
+        self.bones.iter().map(move |bone| {
+            let global_from_tracked = hand_joints.joints[bone.hand_joint].transform;
+            (
+                bone.entity,
+                global_from_tracked * scale * bone.tracked_from_bone,
+            )
+        })
+    }
+}
+
+/// The joint after `joint` along its finger, if there is one. The wrist points down the middle finger.
+fn next_joint(joint: usize) -> Option<usize> {
+    let wrist = xr::HandJoint::WRIST.into_raw() as usize;
+    let thumb_metacarpal = xr::HandJoint::THUMB_METACARPAL.into_raw() as usize;
+    let thumb_tip = xr::HandJoint::THUMB_TIP.into_raw() as usize;
+    let index_metacarpal = xr::HandJoint::INDEX_METACARPAL.into_raw() as usize;
+    match joint {
+        j if j == wrist => Some(xr::HandJoint::MIDDLE_PROXIMAL.into_raw() as usize),
+        j if (thumb_metacarpal..thumb_tip).contains(&j) => Some(j + 1),
+        j if j >= index_metacarpal && (j - index_metacarpal) % 5 != 4 => Some(j + 1),
+        _ => None,
+    }
+}
+
+/// Work out where each tracked joint would be in the bind pose, from the positions of the joints the model has.
+/// Returns `None` if the model doesn't have the joints needed to tell which way the hand is facing.
+fn bind_from_tracked_joints(
+    positions: &[Option<Vec3>; HAND_JOINT_COUNT],
+    handedness: Handedness,
+) -> Option<[Option<Affine3A>; HAND_JOINT_COUNT]> {
+    let position = |joint: xr::HandJoint| positions[joint.into_raw() as usize];
+    let index_proximal = position(xr::HandJoint::INDEX_PROXIMAL)?;
+    let little_proximal = position(xr::HandJoint::LITTLE_PROXIMAL)?;
+    position(xr::HandJoint::WRIST)?;
+
+    // +X points towards the thumb on the left hand, and towards the little finger on the right.
+    let across = match handedness {
+        Handedness::Left => index_proximal - little_proximal,
+        Handedness::Right => little_proximal - index_proximal,
+    };
+
+    let mut bind_from_tracked = [None; HAND_JOINT_COUNT];
+    for (joint, bind_from_joint) in bind_from_tracked.iter_mut().enumerate() {
+        let position = match positions[joint] {
+            Some(position) => position,
+            None => continue,
+        };
+
+        // Point down the finger, towards the next joint the model has. The last joint of each finger carries on in
+        // the same direction as the one before it.
+        let mut next = next_joint(joint);
+        let mut forward = None;
+        while let Some(n) = next {
+            if let Some(next_position) = positions[n] {
+                forward = Some(next_position - position);
+                break;
+            }
+            next = next_joint(n);
+        }
+        let forward = forward.or_else(|| {
+            (0..joint)
+                .rev()
+                .find(|&previous| {
+                    next_joint(previous) == Some(joint) && positions[previous].is_some()
+                })
+                .and_then(|previous| positions[previous])
+                .map(|previous_position| position - previous_position)
+        });
+        let z = match forward.and_then(|forward| (-forward).try_normalize()) {
+            Some(z) => z,
+            None => continue,
+        };
+        let x = match (across - z * across.dot(z)).try_normalize() {
+            Some(x) => x,
+            None => continue,
+        };
+        let y = z.cross(x);
+        *bind_from_joint = Some(Affine3A::from_mat3_translation(
+            Mat3::from_cols(x, y, z),
+            position,
+        ));
+    }
+
+    Some(bind_from_tracked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    pub fn test_next_joint() {
+        let next = |joint: xr::HandJoint| next_joint(joint.into_raw() as usize);
+        let index = |joint: xr::HandJoint| Some(joint.into_raw() as usize);
+        assert_eq!(
+            next(xr::HandJoint::WRIST),
+            index(xr::HandJoint::MIDDLE_PROXIMAL)
+        );
+        assert_eq!(
+            next(xr::HandJoint::THUMB_DISTAL),
+            index(xr::HandJoint::THUMB_TIP)
+        );
+        assert_eq!(next(xr::HandJoint::THUMB_TIP), None);
+        assert_eq!(
+            next(xr::HandJoint::INDEX_DISTAL),
+            index(xr::HandJoint::INDEX_TIP)
+        );
+        assert_eq!(next(xr::HandJoint::INDEX_TIP), None);
+        assert_eq!(
+            next(xr::HandJoint::LITTLE_METACARPAL),
+            index(xr::HandJoint::LITTLE_PROXIMAL)
+        );
+        assert_eq!(next(xr::HandJoint::LITTLE_TIP), None);
+        assert_eq!(next(xr::HandJoint::PALM), None);
+    }
+
+    #[test]
+    pub fn test_bind_from_tracked_joints() {
+        // A flat left hand, palm down, pointing down -Z.
+        let mut positions = [None; HAND_JOINT_COUNT];
+        let mut set = |joint: xr::HandJoint, position: Vec3| {
+            positions[joint.into_raw() as usize] = Some(position)
+        };
+        set(xr::HandJoint::WRIST, Vec3::ZERO);
+        set(xr::HandJoint::INDEX_PROXIMAL, Vec3::new(0.02, 0., -0.1));
+        set(xr::HandJoint::MIDDLE_PROXIMAL, Vec3::new(0., 0., -0.1));
+        set(xr::HandJoint::LITTLE_PROXIMAL, Vec3::new(-0.04, 0., -0.1));
+        set(
+            xr::HandJoint::INDEX_INTERMEDIATE,
+            Vec3::new(0.02, 0., -0.15),
+        );
+
+        let bind_from_tracked = bind_from_tracked_joints(&positions, Handedness::Left).unwrap();
+
+        // Tracked joints in a flat hand share the orientation of the hand.
+        let wrist = bind_from_tracked[xr::HandJoint::WRIST.into_raw() as usize].unwrap();
+        assert_relative_eq!(wrist.matrix3, Mat3::IDENTITY.into());
+        let index_proximal =
+            bind_from_tracked[xr::HandJoint::INDEX_PROXIMAL.into_raw() as usize].unwrap();
+        assert_relative_eq!(index_proximal.matrix3, Mat3::IDENTITY.into());
+        assert_relative_eq!(index_proximal.translation, Vec3::new(0.02, 0., -0.1).into());
+
+        // The last joint of the index finger carries on in the same direction.
+        let index_intermediate =
+            bind_from_tracked[xr::HandJoint::INDEX_INTERMEDIATE.into_raw() as usize].unwrap();
+        assert_relative_eq!(index_intermediate.matrix3, Mat3::IDENTITY.into());
+
+        // Joints the model doesn't have aren't tracked.
+        assert!(bind_from_tracked[xr::HandJoint::RING_PROXIMAL.into_raw() as usize].is_none());
+
+        // Nor is the little finger, which has nowhere to point.
+        assert!(bind_from_tracked[xr::HandJoint::LITTLE_PROXIMAL.into_raw() as usize].is_none());
+
+        // The right hand is mirrored, so +X points the other way.
+        let bind_from_tracked = bind_from_tracked_joints(&positions, Handedness::Right).unwrap();
+        let wrist = bind_from_tracked[xr::HandJoint::WRIST.into_raw() as usize].unwrap();
+        assert_relative_eq!(wrist.matrix3.x_axis, -glam::Vec3A::X);
+        assert_relative_eq!(wrist.matrix3.y_axis, -glam::Vec3A::Y);
+
+        // Without the knuckles, there's no telling which way the hand is facing.
+        positions[xr::HandJoint::LITTLE_PROXIMAL.into_raw() as usize] = None;
+        assert!(bind_from_tracked_joints(&positions, Handedness::Left).is_none());
+    }
+}
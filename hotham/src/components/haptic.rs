@@ -0,0 +1,247 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use super::hand::Handedness;
+
+/// A single vibration of a controller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HapticPulse {
+    /// How strong the vibration is, from 0 to 1
+    pub amplitude: f32,
+    /// The frequency of the vibration in Hz, or 0 to let the runtime choose
+    pub frequency: f32,
+    /// How long the vibration lasts
+    pub duration: Duration,
+    /// How long to wait after the vibration before the next pulse starts
+    pub gap: Duration,
+}
+
+impl HapticPulse {
+    /// Create a pulse at the runtime's default frequency, with no gap after it
+    pub fn new(amplitude: f32, duration: Duration) -> Self {
+        Self {
+            amplitude,
+            frequency: 0.,
+            duration,
+            gap: Duration::ZERO,
+        }
+    }
+
+    /// Set the frequency of the vibration in Hz
+    pub fn with_frequency(self, frequency: f32) -> Self {
+        Self { frequency, ..self }
+    }
+
+    /// Set how long to wait after the vibration before the next pulse starts
+    pub fn with_gap(self, gap: Duration) -> Self {
+        Self { gap, ..self }
+    }
+
+    /// A short, light, high pitched pulse, eg. for hovering over a button
+    pub fn tick() -> Self {
+        Self::new(0.2, Duration::from_millis(10)).with_frequency(400.)
+    }
+
+    /// A long, strong, low pitched pulse, eg. for an impact
+    pub fn thunk() -> Self {
+        Self::new(1.0, Duration::from_millis(150)).with_frequency(80.)
+    }
+}
+
+/// An authored haptic effect: a waveform that drives the controller's actuator, like a sound drives a speaker. Clips
+/// are cheap to clone, so they can be loaded once and played as often as needed with [`Haptic::play_clip`].
+///
+/// On runtimes that support `XR_FB_haptic_pcm`, eg. on Quest, the samples are played as they are. Otherwise the clip's
+/// amplitude envelope is played instead, with `XR_FB_haptic_amplitude_envelope` if the runtime supports it, or as a
+/// series of [`HapticPulse`]s if it doesn't.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HapticClip {
+    samples: Arc<[f32]>,
+    sample_rate: f32,
+}
+
+impl HapticClip {
+    /// The length of each pulse when a clip is played as [`HapticPulse`]s
+    pub const PULSE_DURATION: Duration = Duration::from_millis(10);
+
+    /// Create a clip from samples from -1 to 1, played at `sample_rate` samples per second
+    pub fn new(samples: impl Into<Arc<[f32]>>, sample_rate: f32) -> Self {
+        Self {
+            samples: samples.into(),
+            sample_rate,
+        }
+    }
+
+    /// Create a clip from an amplitude envelope, given as the amplitude from 0 to 1 at each point in time. The
+    /// amplitude is interpolated linearly between the points, and the clip ends at the last one.
+    pub fn from_envelope(points: &[(Duration, f32)], sample_rate: f32) -> Self {
+        let duration = points.last().map_or(0., |(time, _)| time.as_secs_f32());
+        let sample_count = (duration * sample_rate).round() as usize;
+        let samples = (0..sample_count)
+            .map(|i| {
+                let time = i as f32 / sample_rate;
+                let next = points
+                    .iter()
+                    .position(|(point_time, _)| point_time.as_secs_f32() > time)
+                    .unwrap_or(points.len() - 1);
+                if next == 0 {
+                    return points[0].1;
+                }
+                let (start_time, start) = points[next - 1];
+                let (end_time, end) = points[next];
+                let (start_time, end_time) = (start_time.as_secs_f32(), end_time.as_secs_f32());
+                let t = ((time - start_time) / (end_time - start_time)).clamp(0., 1.);
+                start + (end - start) * t
+            })
+            .collect::<Vec<_>>();
+        Self::new(samples, sample_rate)
+    }
+
+    /// The samples of the clip, from -1 to 1
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// How many samples are played each second
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// How long the clip lasts
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f32(self.samples.len() as f32 / self.sample_rate)
+    }
+
+    /// The amplitude of the clip as `count` evenly spaced values from 0 to 1, each the peak of the samples it covers
+    pub fn amplitude_envelope(&self, count: usize) -> Vec<f32> {
+        if self.samples.is_empty() || count == 0 {
+            return Vec::new();
+        }
+        let count = count.min(self.samples.len());
+        (0..count)
+            .map(|i| {
+                let start = i * self.samples.len() / count;
+                let end = (i + 1) * self.samples.len() / count;
+                self.samples[start..end]
+                    .iter()
+                    .fold(0f32, |peak, sample| peak.max(sample.abs()))
+                    .min(1.)
+            })
+            .collect()
+    }
+
+    /// The clip as pulses of [`HapticClip::PULSE_DURATION`], for runtimes that can only play constant vibrations
+    pub fn to_pulses(&self) -> Vec<HapticPulse> {
+        let samples_per_pulse = self.sample_rate * Self::PULSE_DURATION.as_secs_f32();
+        let count = (self.samples.len() as f32 / samples_per_pulse).ceil();
+        self.amplitude_envelope(count as usize)
+            .into_iter()
+            .map(|amplitude| HapticPulse::new(amplitude, Self::PULSE_DURATION))
+            .collect()
+    }
+}
+
+/// Something queued to be played by a [`Haptic`]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum HapticEffect {
+    Pulse(HapticPulse),
+    Clip(HapticClip),
+}
+
+/// A component added to an entity to play haptic feedback on one of the player's controllers. Pulses queued with
+/// [`Haptic::play`] and clips queued with [`Haptic::play_clip`] are played one after another, so an envelope can be
+/// built from several pulses of different amplitudes and frequencies.
+/// Requires `haptics_system`
+#[derive(Debug, Clone)]
+pub struct Haptic {
+    /// Which controller should vibrate?
+    pub handedness: Handedness,
+    pub(crate) effects: VecDeque<HapticEffect>,
+    /// When the current pulse and its gap, or the current clip, end, in nanoseconds of XR time
+    pub(crate) next_pulse_time: Option<i64>,
+    /// The clip being streamed to the runtime with `XR_FB_haptic_pcm`, and how many of its samples it has taken
+    pub(crate) pcm_clip: Option<(HapticClip, usize)>,
+}
+
+impl Haptic {
+    /// Shortcut helper to vibrate the left controller
+    pub fn left() -> Self {
+        Self::new(Handedness::Left)
+    }
+
+    /// Shortcut helper to vibrate the right controller
+    pub fn right() -> Self {
+        Self::new(Handedness::Right)
+    }
+
+    fn new(handedness: Handedness) -> Self {
+        Self {
+            handedness,
+            effects: Default::default(),
+            next_pulse_time: None,
+            pcm_clip: None,
+        }
+    }
+
+    /// Queue pulses to be played after any that are already queued
+    pub fn play(&mut self, pulses: impl IntoIterator<Item = HapticPulse>) {
+        self.effects
+            .extend(pulses.into_iter().map(HapticEffect::Pulse));
+    }
+
+    /// Queue a clip to be played after any pulses or clips that are already queued
+    pub fn play_clip(&mut self, clip: &HapticClip) {
+        self.effects.push_back(HapticEffect::Clip(clip.clone()));
+    }
+
+    /// Drop any pulses or clips that haven't started yet
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    /// Is a pulse or clip being played, or waiting to be?
+    pub fn is_playing(&self) -> bool {
+        self.next_pulse_time.is_some() || !self.effects.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_haptic_clip_from_envelope() {
+        let clip = HapticClip::from_envelope(
+            &[
+                (Duration::ZERO, 0.),
+                (Duration::from_millis(10), 1.),
+                (Duration::from_millis(20), 0.5),
+            ],
+            1000.,
+        );
+        assert_eq!(clip.samples().len(), 20);
+        assert!((clip.duration().as_secs_f32() - 0.02).abs() < 1e-6);
+        assert_eq!(clip.samples()[0], 0.);
+        assert!((clip.samples()[5] - 0.5).abs() < 1e-4);
+        assert!((clip.samples()[10] - 1.).abs() < 1e-4);
+        assert!((clip.samples()[15] - 0.75).abs() < 1e-4);
+    }
+
+    #[test]
+    pub fn test_haptic_clip_amplitude_envelope() {
+        let clip = HapticClip::new(vec![0.1, -0.5, 0.2, 0.3, -0.1, 0.], 300.);
+        assert_eq!(clip.amplitude_envelope(3), vec![0.5, 0.3, 0.1]);
+        assert_eq!(clip.amplitude_envelope(100).len(), 6);
+
+        // Each 10ms pulse is as strong as the peak of the samples it covers.
+        let mut samples = vec![0.25; 10];
+        samples.extend([-0.75; 10]);
+        let pulses = HapticClip::new(samples, 1000.).to_pulses();
+        assert_eq!(
+            pulses,
+            vec![
+                HapticPulse::new(0.25, HapticClip::PULSE_DURATION),
+                HapticPulse::new(0.75, HapticClip::PULSE_DURATION),
+            ]
+        );
+    }
+}
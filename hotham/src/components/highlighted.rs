@@ -0,0 +1,29 @@
+use glam::Vec4;
+
+/// Draws an outline around a mesh, eg. to show that an object can be grabbed or is being pointed at.
+///
+/// The outline is drawn around the silhouette of the entity's [`Mesh`](super::Mesh), on top of everything else in
+/// the scene, but is hidden by anything in front of it. Only meshes that are [`Visible`](super::Visible) are
+/// outlined. Add or remove the component to toggle the outline.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::Highlighted;
+/// world.insert_one(entity, Highlighted::default())?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Highlighted {
+    /// The color of the outline, in linear space. Alpha blends the outline with whatever is behind it.
+    pub color: Vec4,
+    /// How far the outline extends beyond the surface of the mesh, in metres
+    pub thickness: f32,
+}
+
+impl Default for Highlighted {
+    fn default() -> Self {
+        Self {
+            color: [1.0, 0.8, 0.2, 1.0].into(),
+            thickness: 0.005,
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use crate::components::hand::Handedness;
+
+/// How the player turns with the thumbstick
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnMode {
+    /// Turn by a fixed angle, in radians, each time the thumbstick is pushed to the side. The most comfortable
+    /// option for most players.
+    Snap { angle: f32 },
+    /// Turn continuously while the thumbstick is pushed to the side, at up to `speed` radians per second
+    Smooth { speed: f32 },
+}
+
+/// A component that moves the player's rig around with the controllers' thumbsticks. Requires `locomotion_system`.
+///
+/// The rig is the entity the player's headset and hands are tracked relative to, ie. the [`super::Stage`], so add
+/// this component to [`crate::Engine::stage_entity`]. One thumbstick moves the player in the direction they're
+/// looking, the other turns them around their head.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::Locomotion;
+/// engine.world.insert_one(engine.stage_entity, Locomotion::default()).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Locomotion {
+    /// The side whose [`EngineAction::Move`](crate::contexts::EngineAction::Move) moves the player: the
+    /// thumbstick on that controller, unless the player has rebound it
+    pub move_handedness: Handedness,
+    /// The side whose [`EngineAction::Turn`](crate::contexts::EngineAction::Turn) turns the player: the
+    /// thumbstick on that controller, unless the player has rebound it
+    pub turn_handedness: Handedness,
+    /// How fast the player moves with the thumbstick pushed all the way, in meters per second
+    pub speed: f32,
+    /// How the player turns
+    pub turn_mode: TurnMode,
+    /// Thumbstick movement smaller than this is ignored, so worn thumbsticks don't drift
+    pub dead_zone: f32,
+    /// If set, the comfort [`Vignette`](crate::rendering::vignette::Vignette) is faded in to this strength while the
+    /// player moves or smoothly turns
+    pub vignette: Option<f32>,
+    /// Has the player snap turned since the turn thumbstick was last let go?
+    pub has_snapped: bool,
+}
+
+impl Default for Locomotion {
+    fn default() -> Self {
+        Self {
+            move_handedness: Handedness::Left,
+            turn_handedness: Handedness::Right,
+            speed: 2.0,
+            turn_mode: TurnMode::Snap {
+                angle: 30_f32.to_radians(),
+            },
+            dead_zone: 0.2,
+            vignette: Some(0.8),
+            has_snapped: false,
+        }
+    }
+}
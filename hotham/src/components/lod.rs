@@ -0,0 +1,63 @@
+use super::Mesh;
+
+/// The distance, in meters, between the levels of detail imported from a glTF file.
+pub const DEFAULT_LOD_SWITCH_DISTANCE: f32 = 5.0;
+
+/// A single level of detail in a [`Lod`].
+#[derive(Debug, Clone)]
+pub struct LodLevel {
+    /// The mesh to draw at this level of detail
+    pub mesh: Mesh,
+    /// The distance from the player's head, in meters, at which this level starts being used
+    pub distance: f32,
+}
+
+/// Level of detail (LOD) component. Holds alternate, cheaper versions of an entity's [`Mesh`].
+///
+/// Each frame, [`crate::systems::lod_system`] replaces the entity's [`Mesh`] with the level matching its distance
+/// from the player's head. Nodes in a glTF file named `foo_LOD0`, `foo_LOD1` and so on are imported as a single
+/// entity with this component, with levels [`DEFAULT_LOD_SWITCH_DISTANCE`] meters apart.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::Lod;
+/// let lod = Lod::new(vec![(high_detail, 0.), (low_detail, 10.)]);
+/// world.insert(entity, (high_detail.clone(), lod));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Lod {
+    /// The levels of detail, from the nearest to the farthest
+    pub levels: Vec<LodLevel>,
+}
+
+impl Lod {
+    /// Create a new `Lod` from meshes and the distance at which each of them starts being used.
+    pub fn new(levels: impl IntoIterator<Item = (Mesh, f32)>) -> Self {
+        let mut levels = levels
+            .into_iter()
+            .map(|(mesh, distance)| LodLevel { mesh, distance })
+            .collect::<Vec<_>>();
+        levels.sort_by(|a, b| a.distance.total_cmp(&b.distance));
+        Self { levels }
+    }
+
+    /// Create a new `Lod` from meshes ordered from the most to the least detailed, `switch_distance` meters apart.
+    pub fn from_meshes(meshes: impl IntoIterator<Item = Mesh>, switch_distance: f32) -> Self {
+        Self::new(
+            meshes
+                .into_iter()
+                .enumerate()
+                .map(|(n, mesh)| (mesh, n as f32 * switch_distance)),
+        )
+    }
+
+    /// Get the mesh to draw at `distance` meters from the player's head, if there is one.
+    pub fn select(&self, distance: f32) -> Option<&Mesh> {
+        self.levels
+            .iter()
+            .take_while(|level| level.distance <= distance)
+            .last()
+            .or_else(|| self.levels.first())
+            .map(|level| &level.mesh)
+    }
+}
@@ -1,43 +1,101 @@
 #![allow(missing_docs)]
 pub mod animation_controller;
 pub mod animation_target;
+pub mod body_joints;
+pub mod camera;
+pub mod casts_shadows;
+pub mod controller_model;
+pub mod custom_material;
+pub mod decal;
+pub mod dynamic_material;
+pub mod follow_view;
+pub mod frustum_culled;
+pub mod gaze_pointer;
 pub mod global_transform;
 pub mod grabbable;
 pub mod hand;
+pub mod hand_animation;
+pub mod hand_gestures;
+pub mod hand_joints;
+pub mod hand_skeleton;
+pub mod haptic;
+pub mod highlighted;
 pub mod hmd;
 pub mod info;
 pub mod joint;
 pub mod local_transform;
+pub mod locomotion;
+pub mod lod;
 pub mod mesh;
+pub mod morph_weights;
 pub mod panel;
 pub mod parent;
 pub mod physics;
 pub mod pointer;
+pub mod render_model;
 pub mod root;
+pub mod scene_plane;
+pub mod scene_volume;
 pub mod skin;
+pub mod skybox;
 pub mod sound_emitter;
+pub mod spatial_anchor;
 pub mod stage;
+pub mod static_mesh;
+pub mod teleporter;
+pub mod text;
+pub mod tracked_device;
 pub mod ui_panel;
+pub mod uv_transform;
 pub mod visible;
 
 pub use animation_controller::AnimationController;
 pub use animation_target::AnimationTarget;
+pub use body_joints::{BodyJoint, BodyJoints};
+pub use camera::Camera;
+pub use casts_shadows::CastsShadows;
+pub use controller_model::ControllerModel;
+pub use custom_material::CustomMaterial;
+pub use decal::Decal;
+pub use dynamic_material::DynamicMaterial;
+pub use follow_view::FollowView;
+pub use frustum_culled::FrustumCulled;
+pub use gaze_pointer::GazePointer;
 pub use global_transform::GlobalTransform;
 pub use grabbable::*;
-pub use hand::Hand;
+pub use hand::{Hand, HandPreference};
+pub use hand_animation::HandAnimation;
+pub use hand_gestures::{Gesture, GestureEvent, HandGestures};
+pub use hand_joints::HandJoints;
+pub use hand_skeleton::HandSkeleton;
+pub use haptic::{Haptic, HapticClip, HapticPulse};
+pub use highlighted::Highlighted;
 pub use hmd::HMD;
 pub use info::Info;
 pub use joint::Joint;
 pub use local_transform::LocalTransform;
+pub use locomotion::{Locomotion, TurnMode};
+pub use lod::Lod;
 pub use mesh::Mesh;
+pub use morph_weights::MorphWeights;
 pub use panel::Panel;
 pub use parent::Parent;
 pub use physics::collider::Collider;
 pub use physics::RigidBody;
-pub use pointer::Pointer;
+pub use pointer::{Pointer, PointerEvent};
+pub use render_model::RenderModel;
 pub use root::Root;
+pub use scene_plane::ScenePlane;
+pub use scene_volume::SceneVolume;
 pub use skin::Skin;
+pub use skybox::Skybox;
 pub use sound_emitter::SoundEmitter;
+pub use spatial_anchor::SpatialAnchor;
 pub use stage::Stage;
+pub use static_mesh::Static;
+pub use teleporter::Teleporter;
+pub use text::Text;
+pub use tracked_device::TrackedDevice;
 pub use ui_panel::UIPanel;
+pub use uv_transform::UvTransform;
 pub use visible::Visible;
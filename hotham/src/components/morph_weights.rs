@@ -0,0 +1,76 @@
+use crate::rendering::resources::MAX_MORPH_TARGETS;
+
+pub static NO_MORPH_WEIGHTS: u32 = std::u32::MAX;
+
+/// Component that controls how much each morph target (blend shape) of an entity's [`super::Mesh`] is applied.
+/// Automatically added by `gltf_loader` to nodes whose mesh has morph targets, using the default weights in the file.
+///
+/// Weights are usually between `0.0` and `1.0`. Only the first [`MAX_MORPH_TARGETS`] weights are used.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::MorphWeights;
+/// let mut weights = world.get::<&mut MorphWeights>(face).unwrap();
+/// weights.weights[SMILE] = 0.8;
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MorphWeights {
+    /// The weight of each morph target
+    pub weights: Vec<f32>,
+}
+
+impl MorphWeights {
+    /// Create a new set of morph target weights
+    pub fn new(weights: Vec<f32>) -> Self {
+        Self { weights }
+    }
+
+    /// Load the weights for a node, falling back to the weights of its mesh and then to zero.
+    /// Returns `None` if the node's mesh has no morph targets.
+    pub(crate) fn load(node: &gltf::Node) -> Option<Self> {
+        let mesh = node.mesh()?;
+        let target_count = mesh
+            .primitives()
+            .map(|p| p.morph_targets().len())
+            .max()
+            .unwrap_or_default();
+        if target_count == 0 {
+            return None;
+        }
+
+        let weights = node
+            .weights()
+            .or_else(|| mesh.weights())
+            .map(|w| w.to_vec())
+            .unwrap_or_else(|| vec![0.; target_count]);
+
+        Some(Self { weights })
+    }
+
+    /// The weights in the layout used by the vertex shader, padded with zeroes.
+    pub fn to_gpu(&self) -> [f32; MAX_MORPH_TARGETS] {
+        let mut weights = [0.; MAX_MORPH_TARGETS];
+        for (gpu, weight) in weights.iter_mut().zip(&self.weights) {
+            *gpu = *weight;
+        }
+        weights
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_morph_weights_to_gpu() {
+        let weights = MorphWeights::new(vec![0.25, 1.0]);
+        let gpu = weights.to_gpu();
+        assert_eq!(gpu[0], 0.25);
+        assert_eq!(gpu[1], 1.0);
+        assert!(gpu[2..].iter().all(|w| *w == 0.));
+
+        // Extra weights are ignored
+        let weights = MorphWeights::new(vec![0.5; MAX_MORPH_TARGETS + 2]);
+        assert_eq!(weights.to_gpu(), [0.5; MAX_MORPH_TARGETS]);
+    }
+}
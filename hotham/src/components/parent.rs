@@ -1,6 +1,19 @@
-use hecs::Entity;
+use hecs::{Entity, World};
 
 /// Component added to indicate that an entity has a parent
 /// Used by `update_global_transform_with_parent_system`
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Parent(pub Entity);
+
+/// Is `entity` the same as `ancestor`, or one of its children, grandchildren and so on?
+pub(crate) fn is_descendant_of(world: &World, mut entity: Entity, ancestor: Entity) -> bool {
+    loop {
+        if entity == ancestor {
+            return true;
+        }
+        entity = match world.get::<&Parent>(entity) {
+            Ok(parent) => parent.0,
+            Err(_) => return false,
+        };
+    }
+}
@@ -1,10 +1,63 @@
+use glam::Vec3;
+use hecs::Entity;
+use rapier3d::prelude::Group;
+
 use super::hand::Handedness;
+use crate::contexts::physics_context::PANEL_COLLISION_GROUP;
+
+/// Something that happened to the entity a [`Pointer`] is pointing at, this frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PointerEvent {
+    /// The pointer started pointing at the entity
+    HoverStart { entity: Entity },
+    /// The pointer stopped pointing at the entity
+    HoverEnd { entity: Entity },
+    /// The trigger was pulled while pointing at the entity, at `point` in global space
+    Pressed { entity: Entity, point: Vec3 },
+    /// The trigger was let go after pressing the entity. `point` is where the pointer was pointing at the time, in
+    /// global space, and is `None` if it had moved off the entity.
+    Released { entity: Entity, point: Option<Vec3> },
+    /// The trigger was pulled and let go while pointing at the entity the whole time
+    Clicked { entity: Entity, point: Vec3 },
+}
 
-/// A component added to an entity to allow users to interact with `UIPanels` using their
-/// controllers.
+/// A component added to an entity to allow users to point at things, like `UIPanels`, with their controllers.
+///
+/// Each frame, `pointers_system` casts a ray from the pointer and keeps track of what it's hovering over and pressing.
+/// What happened this frame is in `events`, for UI panels and gameplay systems to read. Hitting a `Panel` also sends
+/// the panel input.
 pub struct Pointer {
     /// Which hand is the pointer in?
     pub handedness: Handedness,
-    /// How much has the trigger been pulled down?
+    /// How much has the trigger been pulled down? Read from the input
+    /// [`EngineAction::Point`](crate::contexts::EngineAction::Point) is bound to, the trigger by default.
     pub trigger_value: f32,
+    /// The entity with the first collider the pointer is pointing at, if any
+    pub hovered_entity: Option<Entity>,
+    /// Where the pointer hits `hovered_entity`, in global space
+    pub hit_point: Option<Vec3>,
+    /// The entity the trigger was pulled on, until it's let go
+    pub pressed_entity: Option<Entity>,
+    /// What happened this frame, in the order it happened
+    pub events: Vec<PointerEvent>,
+    /// Colliders further away than this can't be pointed at, in meters
+    pub max_distance: f32,
+    /// Only colliders in these groups can be pointed at. Defaults to panels only.
+    pub collision_filter: Group,
+}
+
+impl Pointer {
+    /// Create a pointer in the given hand, that points at panels
+    pub fn new(handedness: Handedness) -> Self {
+        Self {
+            handedness,
+            trigger_value: 0.0,
+            hovered_entity: None,
+            hit_point: None,
+            pressed_entity: None,
+            events: Vec::new(),
+            max_distance: 40.0,
+            collision_filter: PANEL_COLLISION_GROUP,
+        }
+    }
 }
@@ -0,0 +1,43 @@
+use hecs::Entity;
+
+/// A component added to an entity to draw one of the runtime's models of a device other than the controllers, eg.
+/// the player's keyboard, using `XR_FB_render_model`. The model is added as the entity's child once the runtime has
+/// it, and the entity isn't moved, so it should be placed where the device is, eg. with a
+/// [`SpatialAnchor`](crate::components::SpatialAnchor) or a [`TrackedDevice`](crate::components::TrackedDevice).
+/// Use [`ControllerModel`](crate::components::ControllerModel) for controllers, which follows them.
+///
+/// Requires controller models to be enabled with
+/// [`EngineBuilder::controller_models`](crate::EngineBuilder::controller_models) and `controller_models_system`.
+/// [`XrContext::render_model_paths`](crate::contexts::XrContext::render_model_paths) lists the models the runtime
+/// has.
+///
+/// To show the player their keyboard while they type, eg. while a text field is focused, follow it with
+/// `TrackedDevice::new(TrackerRole::Keyboard)`, enable
+/// [`EngineBuilder::keyboard_tracking`](crate::EngineBuilder::keyboard_tracking), and set `visible` while it's needed.
+#[derive(Debug, Clone)]
+pub struct RenderModel {
+    /// The path of the model, eg. [`RenderModel::KEYBOARD_LOCAL`]
+    pub path: String,
+    /// Has the runtime's model been added, or failed to load? Loading is attempted each frame until it is.
+    pub is_loaded: bool,
+    /// The entities of the model with meshes
+    pub mesh_entities: Vec<Entity>,
+    /// Should the model be drawn? It's also hidden while a [`TrackedDevice`](crate::components::TrackedDevice) on the
+    /// same entity isn't tracked.
+    pub visible: bool,
+}
+
+impl RenderModel {
+    /// The model of the player's keyboard
+    pub const KEYBOARD_LOCAL: &'static str = "/model_fb/keyboard/local";
+
+    /// Draw the model with the given path
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            is_loaded: false,
+            mesh_entities: Vec::new(),
+            visible: true,
+        }
+    }
+}
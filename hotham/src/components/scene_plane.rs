@@ -0,0 +1,41 @@
+use glam::{Vec2, Vec3};
+use rapier3d::prelude::{Isometry, SharedShape};
+
+use crate::contexts::xr_context::{AnchorUuid, SceneLabel};
+
+/// How far colliders for planes extend behind them, in meters
+const PLANE_COLLIDER_DEPTH: f32 = 0.02;
+
+/// A component for a flat part of the player's room, eg. a wall, the floor, or the top of a table. The plane lies in
+/// the entity's XY plane, facing +Z, which points into the room.
+///
+/// Spawned by the `scene_system` once the runtime has loaded the room. Requires scene understanding to be enabled
+/// with [`EngineBuilder::scene`](crate::EngineBuilder::scene).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenePlane {
+    /// Identifies this part of the room
+    pub uuid: AnchorUuid,
+    /// What the player said this part of the room is
+    pub label: SceneLabel,
+    /// The corner of the plane with the lowest X and Y
+    pub offset: Vec2,
+    /// The width and height of the plane
+    pub size: Vec2,
+}
+
+impl ScenePlane {
+    /// The centre of the plane, relative to the entity
+    pub fn center(&self) -> Vec3 {
+        (self.offset + self.size / 2.).extend(0.)
+    }
+
+    /// A thin box behind the plane, for a `Collider`
+    pub fn collider_shape(&self) -> SharedShape {
+        let half_extents = (self.size / 2.).extend(PLANE_COLLIDER_DEPTH / 2.);
+        let center = self.center() - Vec3::Z * PLANE_COLLIDER_DEPTH / 2.;
+        SharedShape::compound(vec![(
+            Isometry::translation(center.x, center.y, center.z),
+            SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z),
+        )])
+    }
+}
@@ -0,0 +1,38 @@
+use glam::Vec3;
+use rapier3d::prelude::{Isometry, SharedShape};
+
+use crate::contexts::xr_context::{AnchorUuid, SceneLabel};
+
+/// A component for a solid part of the player's room, eg. a couch or a table, bounded by a box aligned with the
+/// entity's axes.
+///
+/// Spawned by the `scene_system` once the runtime has loaded the room. Requires scene understanding to be enabled
+/// with [`EngineBuilder::scene`](crate::EngineBuilder::scene).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneVolume {
+    /// Identifies this part of the room
+    pub uuid: AnchorUuid,
+    /// What the player said this part of the room is
+    pub label: SceneLabel,
+    /// The corner of the box with the lowest X, Y and Z
+    pub offset: Vec3,
+    /// The width, height and depth of the box
+    pub size: Vec3,
+}
+
+impl SceneVolume {
+    /// The centre of the box, relative to the entity
+    pub fn center(&self) -> Vec3 {
+        self.offset + self.size / 2.
+    }
+
+    /// The box, for a `Collider`
+    pub fn collider_shape(&self) -> SharedShape {
+        let center = self.center();
+        let half_extents = self.size / 2.;
+        SharedShape::compound(vec![(
+            Isometry::translation(center.x, center.y, center.z),
+            SharedShape::cuboid(half_extents.x, half_extents.y, half_extents.z),
+        )])
+    }
+}
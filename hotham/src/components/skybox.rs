@@ -0,0 +1,55 @@
+use glam::Vec3;
+
+/// The index of the specular environment map used for Image Based Lighting in the cube texture array
+pub const ENVIRONMENT_MAP_CUBE_TEXTURE_ID: u32 = 1;
+
+/// A background drawn behind all geometry, instead of the clear color.
+///
+/// Add this component to any entity to use it; if there is more than one skybox in the world, only one of them
+/// will be drawn. Cubemaps are created with [`crate::rendering::texture::Texture::from_ktx2`]: use the texture's
+/// `index` as the `texture_id`.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::Skybox;
+/// world.spawn((Skybox::environment_map(),));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Skybox {
+    /// A cubemap, sampled in globally oriented stage space
+    Cubemap {
+        /// Index of the cubemap in the cube texture array
+        texture_id: u32,
+        /// Multiplier applied to the color of the cubemap
+        intensity: f32,
+    },
+    /// A procedural gradient, blending from the horizon towards the zenith above and the ground below
+    Gradient {
+        /// Color directly above the player
+        zenith: Vec3,
+        /// Color at the horizon
+        horizon: Vec3,
+        /// Color directly below the player
+        ground: Vec3,
+    },
+}
+
+impl Skybox {
+    /// A skybox showing the environment map that is used for Image Based Lighting
+    pub fn environment_map() -> Self {
+        Skybox::Cubemap {
+            texture_id: ENVIRONMENT_MAP_CUBE_TEXTURE_ID,
+            intensity: 1.0,
+        }
+    }
+}
+
+impl Default for Skybox {
+    fn default() -> Self {
+        Skybox::Gradient {
+            zenith: [0.15, 0.3, 0.65].into(),
+            horizon: [0.7, 0.8, 0.9].into(),
+            ground: [0.2, 0.18, 0.16].into(),
+        }
+    }
+}
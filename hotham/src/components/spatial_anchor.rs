@@ -0,0 +1,65 @@
+use crate::contexts::xr_context::AnchorUuid;
+
+/// A component that pins an entity to a place in the real world, so it stays put as the runtime's tracking shifts,
+/// and optionally across runs of the application.
+///
+/// To anchor an entity, add a `SpatialAnchor` to it along with a `LocalTransform` and a `GlobalTransform` at the pose to anchor. Once the
+/// runtime has created the anchor, the entity's transform is kept at it. Anchors persisted by earlier runs are
+/// spawned as new entities with this component when the application starts, so check their `uuid` to work out what
+/// was anchored there. The entity shouldn't have a `Parent`.
+///
+/// Requires spatial anchors to be enabled with
+/// [`EngineBuilder::spatial_anchors`](crate::EngineBuilder::spatial_anchors) and `spatial_anchors_system`. On Quest,
+/// the application must also request the `com.oculus.permission.USE_ANCHOR_API` permission in its Android manifest.
+#[derive(Debug, Clone)]
+pub struct SpatialAnchor {
+    /// Identifies the anchor once it's been created. Stays the same across runs once it's persisted.
+    pub uuid: Option<AnchorUuid>,
+    /// Should the anchor be saved to the headset's storage, so it's loaded again the next time the application starts?
+    pub persist: bool,
+    /// Has the anchor been saved to the headset's storage?
+    pub is_persisted: bool,
+    /// Is the runtime able to locate the anchor right now? If it isn't, the entity stays where it was last seen.
+    pub is_tracked: bool,
+    pub(crate) state: SpatialAnchorState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SpatialAnchorState {
+    /// The anchor hasn't been requested from the runtime yet
+    New,
+    /// Waiting for the runtime to finish the request with this ID
+    Creating(u64),
+    /// The anchor exists, and can be located
+    Created,
+    /// The runtime couldn't create the anchor
+    Failed,
+}
+
+impl SpatialAnchor {
+    /// Anchor the entity where it is now, and optionally persist the anchor
+    pub fn new(persist: bool) -> Self {
+        Self {
+            uuid: None,
+            persist,
+            is_persisted: false,
+            is_tracked: false,
+            state: SpatialAnchorState::New,
+        }
+    }
+
+    /// Did the runtime fail to create the anchor?
+    pub fn has_failed(&self) -> bool {
+        self.state == SpatialAnchorState::Failed
+    }
+
+    pub(crate) fn loaded(uuid: AnchorUuid) -> Self {
+        Self {
+            uuid: Some(uuid),
+            persist: true,
+            is_persisted: true,
+            is_tracked: false,
+            state: SpatialAnchorState::Created,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+/// Marks an entity as static: it never moves, and its mesh never changes.
+///
+/// Static meshes can be merged by [`batch_static_meshes`](crate::rendering::static_batching::batch_static_meshes),
+/// which greatly reduces the number of draw calls needed for environment art. Marking an entity also marks all of
+/// its descendants, so marking the root of a level is enough.
+///
+/// Once batched, an entity can no longer be hidden with [`Visible`](crate::components::Visible): its geometry is drawn
+/// by the batch. Don't mark entities that need to be shown and hidden.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::Static;
+/// let environment = add_model_to_world("Environment", &models, world, None).unwrap();
+/// world.insert_one(environment, Static)?;
+/// batch_static_meshes(world, render_context);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Static;
@@ -0,0 +1,54 @@
+use glam::Vec3;
+use hecs::Entity;
+use rapier3d::prelude::Group;
+
+use crate::{components::hand::Handedness, contexts::physics_context::HAND_COLLISION_GROUP};
+
+/// A component that lets the player teleport around the world with one of their controllers.
+///
+/// Pushing the controller's thumbstick forward shows an arc from the controller to where the player would land.
+/// Letting go of the thumbstick moves the stage so that the player is standing there, as long as it's somewhere they
+/// can stand, ie. a collider that isn't too steep. Requires `teleport_system`.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::{hand::Handedness, Teleporter};
+/// world.spawn((Teleporter::new(Handedness::Right),));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Teleporter {
+    /// Which controller is used to teleport?
+    pub handedness: Handedness,
+    /// Is the player aiming the arc?
+    pub is_aiming: bool,
+    /// The arc this frame, in global space. Empty unless the player is aiming.
+    pub arc: Vec<Vec3>,
+    /// Where the player would land if they let go of the thumbstick now, in global space. `None` if the arc didn't
+    /// hit anything they can stand on.
+    pub target: Option<Vec3>,
+    /// How fast the arc leaves the controller, in meters per second. Faster arcs reach further.
+    pub speed: f32,
+    /// The steepest slope that can be teleported onto, in radians
+    pub max_slope: f32,
+    /// Only colliders in these groups can be teleported onto. Defaults to every group but the hands'.
+    pub collision_filter: Group,
+    /// An entity, eg. a ring, that's moved to the target and made `Visible` while there's a valid target, and
+    /// hidden otherwise
+    pub target_marker: Option<Entity>,
+}
+
+impl Teleporter {
+    /// Teleport with the given controller
+    pub fn new(handedness: Handedness) -> Self {
+        Self {
+            handedness,
+            is_aiming: false,
+            arc: Vec::new(),
+            target: None,
+            speed: 7.0,
+            max_slope: 30_f32.to_radians(),
+            collision_filter: Group::all().difference(HAND_COLLISION_GROUP),
+            target_marker: None,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use glam::Vec4;
+
+use crate::rendering::text::SdfFont;
+
+/// How each line of a [`Text`] is placed relative to the entity's origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextAlignment {
+    /// Lines start at the origin
+    #[default]
+    Left,
+    /// Lines are centered on the origin
+    Center,
+    /// Lines end at the origin
+    Right,
+}
+
+/// Text drawn in the world, eg. scores and labels.
+///
+/// The text lies on the entity's local XY plane, facing +Z, with the origin on the baseline of the first line.
+/// Glyphs are drawn from a signed distance field, so they stay sharp up close. Like meshes, text is only drawn
+/// if the entity is [`super::Visible`].
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::Text;
+/// let mut text = Text::new("Score: 0", font.clone());
+/// text.size = 0.05;
+/// world.spawn((text, LocalTransform::default(), GlobalTransform::default(), Visible(true)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Text {
+    /// The text to draw. Lines are separated by `\n`
+    pub text: String,
+    /// The font to draw the text with
+    pub font: SdfFont,
+    /// The height of a single em, in meters
+    pub size: f32,
+    /// Linear RGBA color
+    pub color: Vec4,
+    /// How lines are aligned
+    pub alignment: TextAlignment,
+}
+
+impl Text {
+    /// Create white, left aligned text that is 10cm tall
+    pub fn new(text: impl Into<String>, font: SdfFont) -> Self {
+        Self {
+            text: text.into(),
+            font,
+            size: 0.1,
+            color: Vec4::ONE,
+            alignment: Default::default(),
+        }
+    }
+}
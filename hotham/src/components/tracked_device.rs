@@ -0,0 +1,25 @@
+use crate::contexts::xr_context::TrackerRole;
+
+/// A component that's added to an entity to move it with an extra tracked device, such as a Vive tracker strapped to
+/// the player's foot or attached to a prop. Entities are spawned with this component for trackers that aren't
+/// followed by one yet, the first time they're tracked.
+///
+/// Requires trackers to be enabled with [`EngineBuilder::trackers`](crate::EngineBuilder::trackers) and
+/// `tracked_devices_system`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackedDevice {
+    /// The role of the tracker this entity follows
+    pub role: TrackerRole,
+    /// Was the tracker tracked this frame? If not, the entity is where it was last seen.
+    pub is_tracked: bool,
+}
+
+impl TrackedDevice {
+    /// Follow the tracker with the given role
+    pub fn new(role: TrackerRole) -> Self {
+        Self {
+            role,
+            is_tracked: false,
+        }
+    }
+}
@@ -125,7 +125,7 @@ pub fn add_ui_panel_to_world(
             ..Default::default()
         },
         GlobalTransform::default(),
-        Visible {},
+        Visible(true),
     );
 
     let panel_entity = world.spawn(components);
@@ -0,0 +1,91 @@
+use std::f64::consts::TAU;
+
+use glam::{Affine2, Vec2};
+
+/// Transforms the texture coordinates of a material, eg. to tile its textures, or to scroll them for lava, conveyor
+/// belts and holograms.
+///
+/// Like [`DynamicMaterial`](super::DynamicMaterial), this changes every primitive that shares `material_id`. The
+/// [`uv_transform_system`](crate::systems::uv_transform_system) writes the transform into the material every frame,
+/// and it's applied to both sets of texture coordinates in the vertex shader.
+///
+/// Basic usage:
+/// ```ignore
+/// use hotham::components::UvTransform;
+/// let material_id = render_context.resources.mesh_data.get(mesh.handle).unwrap().primitives[0].material_id;
+/// world.insert_one(entity, UvTransform::scrolling(material_id, [0.0, 0.1].into()))?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvTransform {
+    /// The index of the material in the materials buffer
+    pub material_id: u32,
+    /// Added to the texture coordinates after they've been scaled and rotated
+    pub offset: Vec2,
+    /// Multiplies the texture coordinates. Values above one tile the textures.
+    pub scale: Vec2,
+    /// Rotates the texture coordinates counter-clockwise around the origin, in radians
+    pub rotation: f32,
+    /// Added to `offset` every second
+    pub scroll_speed: Vec2,
+    /// Added to `rotation` every second, in radians
+    pub rotation_speed: f32,
+}
+
+impl UvTransform {
+    /// A transform that leaves the texture coordinates of `material_id` as they are, until it's changed.
+    pub fn new(material_id: u32) -> Self {
+        Self {
+            material_id,
+            offset: Vec2::ZERO,
+            scale: Vec2::ONE,
+            rotation: 0.,
+            scroll_speed: Vec2::ZERO,
+            rotation_speed: 0.,
+        }
+    }
+
+    /// A transform that scrolls the textures of `material_id` by `scroll_speed` every second.
+    pub fn scrolling(material_id: u32, scroll_speed: Vec2) -> Self {
+        Self {
+            scroll_speed,
+            ..Self::new(material_id)
+        }
+    }
+
+    /// The transform `time` seconds into the animation.
+    ///
+    /// Textures repeat, so the animated offset is wrapped to a whole number of repeats, and the rotation to a whole
+    /// number of turns. This keeps them precise however long the application has been running.
+    pub fn affine_at(&self, time: f64) -> Affine2 {
+        let scrolled = Vec2::new(
+            (self.scroll_speed.x as f64 * time).rem_euclid(1.) as f32,
+            (self.scroll_speed.y as f64 * time).rem_euclid(1.) as f32,
+        );
+        let rotated = (self.rotation_speed as f64 * time).rem_euclid(TAU) as f32;
+        Affine2::from_scale_angle_translation(
+            self.scale,
+            self.rotation + rotated,
+            self.offset + scrolled,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_affine_at() {
+        let uv_transform = UvTransform {
+            scale: Vec2::new(2., 2.),
+            ..UvTransform::scrolling(0, Vec2::new(0.25, -0.5))
+        };
+        let affine = uv_transform.affine_at(1.);
+        assert_eq!(affine.translation, Vec2::new(0.25, 0.5));
+        assert_eq!(affine.transform_vector2(Vec2::X), Vec2::new(2., 0.));
+
+        // The offset wraps around, even after a long time.
+        let affine = uv_transform.affine_at(1_000_001.);
+        assert!(affine.translation.abs_diff_eq(Vec2::new(0.25, 0.5), 1e-6));
+    }
+}
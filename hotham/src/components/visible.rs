@@ -1,13 +1,19 @@
-/// The Visibility component determines whether a given entity is shown or hidden within the world.
+/// The Visible component determines whether a given entity is shown or hidden within the world.
 ///
-/// During each tick of the Hotham engine, entities can have Visibility assigned or removed.
+/// Entities without a `Visible` component are never drawn. Toggling the flag is cheaper than adding or removing the
+/// component, which moves the entity to a different archetype.
 ///
 /// Basic usage:
 /// ```ignore
 /// use hotham::components::Visible;
-/// world.insert_one(entity, Visible {});
-/// world.remove_one::<Visible>(entity);
+/// world.insert_one(entity, Visible(true));
+/// world.get::<&mut Visible>(entity).unwrap().0 = false;
 /// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Visible(pub bool);
 
-#[derive(Debug, Clone, Copy)]
-pub struct Visible {}
+impl Default for Visible {
+    fn default() -> Self {
+        Visible(true)
+    }
+}
@@ -0,0 +1,212 @@
+use glam::Vec2;
+
+/// The number of buttons in [`GamepadButton`]
+pub const GAMEPAD_BUTTON_COUNT: usize = 14;
+
+/// A button on a gamepad. The face buttons are named by where they are, as their labels differ between brands, eg.
+/// `South` is A on an Xbox controller and Cross on a PlayStation controller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftBumper,
+    RightBumper,
+    /// Clicking the left stick in
+    LeftStick,
+    /// Clicking the right stick in
+    RightStick,
+    Start,
+    Select,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    /// Every button, in the order they're stored
+    pub const ALL: [GamepadButton; GAMEPAD_BUTTON_COUNT] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::West,
+        GamepadButton::North,
+        GamepadButton::LeftBumper,
+        GamepadButton::RightBumper,
+        GamepadButton::LeftStick,
+        GamepadButton::RightStick,
+        GamepadButton::Start,
+        GamepadButton::Select,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    #[cfg(not(target_os = "android"))]
+    fn to_gilrs(self) -> gilrs::Button {
+        match self {
+            GamepadButton::South => gilrs::Button::South,
+            GamepadButton::East => gilrs::Button::East,
+            GamepadButton::West => gilrs::Button::West,
+            GamepadButton::North => gilrs::Button::North,
+            GamepadButton::LeftBumper => gilrs::Button::LeftTrigger,
+            GamepadButton::RightBumper => gilrs::Button::RightTrigger,
+            GamepadButton::LeftStick => gilrs::Button::LeftThumb,
+            GamepadButton::RightStick => gilrs::Button::RightThumb,
+            GamepadButton::Start => gilrs::Button::Start,
+            GamepadButton::Select => gilrs::Button::Select,
+            GamepadButton::DPadUp => gilrs::Button::DPadUp,
+            GamepadButton::DPadDown => gilrs::Button::DPadDown,
+            GamepadButton::DPadLeft => gilrs::Button::DPadLeft,
+            GamepadButton::DPadRight => gilrs::Button::DPadRight,
+        }
+    }
+
+    #[cfg(target_os = "android")]
+    fn from_keycode(keycode: ndk::event::Keycode) -> Option<Self> {
+        use ndk::event::Keycode;
+        Some(match keycode {
+            Keycode::ButtonA => GamepadButton::South,
+            Keycode::ButtonB => GamepadButton::East,
+            Keycode::ButtonX => GamepadButton::West,
+            Keycode::ButtonY => GamepadButton::North,
+            Keycode::ButtonL1 => GamepadButton::LeftBumper,
+            Keycode::ButtonR1 => GamepadButton::RightBumper,
+            Keycode::ButtonThumbl => GamepadButton::LeftStick,
+            Keycode::ButtonThumbr => GamepadButton::RightStick,
+            Keycode::ButtonStart => GamepadButton::Start,
+            Keycode::ButtonSelect => GamepadButton::Select,
+            Keycode::DpadUp => GamepadButton::DPadUp,
+            Keycode::DpadDown => GamepadButton::DPadDown,
+            Keycode::DpadLeft => GamepadButton::DPadLeft,
+            Keycode::DpadRight => GamepadButton::DPadRight,
+            _ => return None,
+        })
+    }
+}
+
+/// The input of a gamepad as the platform reports it. Sticks are from -1 to 1 on each axis, with up being positive.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct RawGamepadState {
+    pub left_stick: Vec2,
+    pub right_stick: Vec2,
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+    pub buttons: [bool; GAMEPAD_BUTTON_COUNT],
+}
+
+/// Reads the first connected gamepad, using gilrs on desktop and Android's input events on Quest. Its input is made
+/// available each frame through [`InputContext::gamepad`](crate::contexts::InputContext::gamepad).
+pub struct GamepadContext {
+    #[cfg(not(target_os = "android"))]
+    gilrs: Option<gilrs::Gilrs>,
+    /// Android sends events as the input changes, so the latest state is kept here
+    #[cfg(target_os = "android")]
+    state: Option<RawGamepadState>,
+    /// Some gamepads report their D-pad as a hat axis, rather than as buttons
+    #[cfg(target_os = "android")]
+    dpad_is_hat: bool,
+}
+
+impl Default for GamepadContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GamepadContext {
+    /// Start listening for gamepads
+    pub fn new() -> Self {
+        #[cfg(not(target_os = "android"))]
+        {
+            let gilrs = gilrs::Gilrs::new()
+                .map_err(|e| println!("[HOTHAM_GAMEPAD] Unable to read gamepads: {e:?}"))
+                .ok();
+            Self { gilrs }
+        }
+        #[cfg(target_os = "android")]
+        Self {
+            state: None,
+            dpad_is_hat: false,
+        }
+    }
+
+    /// The input of the first connected gamepad, or `None` if there isn't one
+    #[cfg(not(target_os = "android"))]
+    pub(crate) fn update(&mut self) -> Option<RawGamepadState> {
+        let gilrs = self.gilrs.as_mut()?;
+
+        // gilrs only updates the state of its gamepads as their events are read.
+        while gilrs.next_event().is_some() {}
+
+        let (_, gamepad) = gilrs.gamepads().next()?;
+        let axis = |axis| gamepad.value(axis);
+        let analog = |button| gamepad.button_data(button).map_or(0., |data| data.value());
+        Some(RawGamepadState {
+            left_stick: Vec2::new(axis(gilrs::Axis::LeftStickX), axis(gilrs::Axis::LeftStickY)),
+            right_stick: Vec2::new(
+                axis(gilrs::Axis::RightStickX),
+                axis(gilrs::Axis::RightStickY),
+            ),
+            left_trigger: analog(gilrs::Button::LeftTrigger2),
+            right_trigger: analog(gilrs::Button::RightTrigger2),
+            buttons: GamepadButton::ALL.map(|button| gamepad.is_pressed(button.to_gilrs())),
+        })
+    }
+
+    /// The input of the gamepad, or `None` if there hasn't been any input from one yet
+    #[cfg(target_os = "android")]
+    pub(crate) fn update(&mut self) -> Option<RawGamepadState> {
+        self.state
+    }
+
+    /// Update the gamepad's state from an input event. Returns `true` if the event came from a gamepad, so it
+    /// shouldn't be handled by anything else.
+    #[cfg(target_os = "android")]
+    pub(crate) fn handle_android_event(&mut self, event: &ndk::event::InputEvent) -> bool {
+        use ndk::event::{Axis, InputEvent, KeyAction, Source};
+
+        match event {
+            InputEvent::KeyEvent(key_event) => {
+                let button = match GamepadButton::from_keycode(key_event.key_code()) {
+                    Some(button) => button,
+                    None => return false,
+                };
+                let state = self.state.get_or_insert_with(Default::default);
+                match key_event.action() {
+                    KeyAction::Down => state.buttons[button as usize] = true,
+                    KeyAction::Up => state.buttons[button as usize] = false,
+                    _ => {}
+                }
+                true
+            }
+            InputEvent::MotionEvent(motion_event) => {
+                if !matches!(motion_event.source(), Source::Joystick | Source::Gamepad) {
+                    return false;
+                }
+                let pointer = motion_event.pointer_at_index(0);
+                let axis = |axis| pointer.axis_value(axis);
+
+                // Android's Y axes point down.
+                let state = self.state.get_or_insert_with(Default::default);
+                state.left_stick = Vec2::new(axis(Axis::X), -axis(Axis::Y));
+                state.right_stick = Vec2::new(axis(Axis::Z), -axis(Axis::Rz));
+                state.left_trigger = axis(Axis::Ltrigger).max(axis(Axis::Brake));
+                state.right_trigger = axis(Axis::Rtrigger).max(axis(Axis::Gas));
+
+                let (hat_x, hat_y) = (axis(Axis::HatX), axis(Axis::HatY));
+                self.dpad_is_hat |= hat_x != 0. || hat_y != 0.;
+                if self.dpad_is_hat {
+                    let buttons = &mut state.buttons;
+                    buttons[GamepadButton::DPadLeft as usize] = hat_x < -0.5;
+                    buttons[GamepadButton::DPadRight as usize] = hat_x > 0.5;
+                    buttons[GamepadButton::DPadUp as usize] = hat_y < -0.5;
+                    buttons[GamepadButton::DPadDown as usize] = hat_y > 0.5;
+                }
+                true
+            }
+        }
+    }
+}
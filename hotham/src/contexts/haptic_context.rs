@@ -1,6 +1,9 @@
 use crate::components::hand::Handedness;
 
 /// Wrapper around XR Haptics
+///
+/// Each request is played as a single 100ms pulse. For control over the frequency and duration, or to play several
+/// pulses in a row, add a [`Haptic`](crate::components::Haptic) component instead.
 #[derive(Clone, Debug, Default)]
 pub struct HapticContext {
     /// Haptics that should be applied to the left hand
@@ -0,0 +1,759 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, Result};
+use glam::Vec2;
+
+use crate::{
+    components::hand::Handedness,
+    contexts::{
+        ButtonState, ControllerState, CustomActionSet, CustomActionType, InputContext, XrContext,
+    },
+};
+
+/// The file in the application's storage that [`InputBindings`] are saved to
+const INPUT_BINDINGS_FILE: &str = "input_bindings.txt";
+
+/// An input on one of the player's controllers that an action can be rebound to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControllerInput {
+    /// The trigger. Reads as how far it's pulled for float actions, and as pulled past its threshold for boolean ones.
+    Trigger,
+    /// The grip. Reads as how far it's squeezed for float actions, and as squeezed past its threshold for boolean
+    /// ones.
+    Grip,
+    /// The A or X button
+    Primary,
+    /// The B or Y button
+    Secondary,
+    /// The menu button. Only the left controller has one.
+    Menu,
+    /// Clicking in the thumbstick
+    ThumbstickClick,
+    /// The position of the thumbstick. Only vector2 actions can be bound to it.
+    Thumbstick,
+}
+
+impl ControllerInput {
+    /// Every input, in the order they're checked by [`InputBindings::pressed_binding`]
+    pub const ALL: [ControllerInput; 7] = [
+        ControllerInput::Trigger,
+        ControllerInput::Grip,
+        ControllerInput::Primary,
+        ControllerInput::Secondary,
+        ControllerInput::Menu,
+        ControllerInput::ThumbstickClick,
+        ControllerInput::Thumbstick,
+    ];
+
+    /// Can an action of this type be bound to the input? Boolean and float actions can be bound to anything that can
+    /// be pressed, with buttons reading as 0 or 1 for float actions, and vector2 actions to the thumbstick.
+    pub fn can_bind(&self, action_type: CustomActionType) -> bool {
+        match action_type {
+            CustomActionType::Boolean | CustomActionType::Float => {
+                *self != ControllerInput::Thumbstick
+            }
+            CustomActionType::Vector2 => *self == ControllerInput::Thumbstick,
+            CustomActionType::Pose => false,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            ControllerInput::Trigger => "trigger",
+            ControllerInput::Grip => "grip",
+            ControllerInput::Primary => "primary",
+            ControllerInput::Secondary => "secondary",
+            ControllerInput::Menu => "menu",
+            ControllerInput::ThumbstickClick => "thumbstick_click",
+            ControllerInput::Thumbstick => "thumbstick",
+        }
+    }
+
+    fn button(&self, controller_state: &ControllerState) -> ButtonState {
+        match self {
+            ControllerInput::Trigger => controller_state.trigger_button,
+            ControllerInput::Grip => controller_state.grip_button,
+            ControllerInput::Primary => controller_state.primary_button,
+            ControllerInput::Secondary => controller_state.secondary_button,
+            ControllerInput::Menu => controller_state.menu_button,
+            ControllerInput::ThumbstickClick => controller_state.thumbstick_click,
+            ControllerInput::Thumbstick => Default::default(),
+        }
+    }
+
+    fn float(&self, controller_state: &ControllerState) -> f32 {
+        match self {
+            ControllerInput::Trigger => controller_state.trigger,
+            ControllerInput::Grip => controller_state.grip,
+            _ if self.button(controller_state).pressed => 1.,
+            _ => 0.,
+        }
+    }
+
+    fn vector2(&self, controller_state: &ControllerState) -> Vec2 {
+        match self {
+            ControllerInput::Thumbstick => controller_state.thumbstick,
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+/// An action read by Hotham's own systems, which players can rebind like the application's actions. Each hand has its
+/// own, named eg. `hotham.grab.left`, and is bound to the same input on that hand until it's rebound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineAction {
+    /// Grab and release [`Grabbable`](crate::components::Grabbable)s with a [`Hand`](crate::components::Hand), read by
+    /// [`hands_system`](crate::systems::hands_system). Defaults to the grip.
+    Grab,
+    /// Press whatever a [`Pointer`](crate::components::Pointer) is pointing at, read by
+    /// [`pointers_system`](crate::systems::pointers_system). Defaults to the trigger.
+    Point,
+    /// Aim a [`Teleporter`](crate::components::Teleporter) and let go to teleport, read by
+    /// [`teleport_system`](crate::systems::teleport_system). Defaults to the thumbstick.
+    Teleport,
+    /// Move a [`Locomotion`](crate::components::Locomotion) rig, read by
+    /// [`locomotion_system`](crate::systems::locomotion_system). Defaults to the thumbstick.
+    Move,
+    /// Turn a [`Locomotion`](crate::components::Locomotion) rig, read by
+    /// [`locomotion_system`](crate::systems::locomotion_system). Defaults to the thumbstick.
+    Turn,
+}
+
+impl EngineAction {
+    /// Every engine action
+    pub const ALL: [EngineAction; 5] = [
+        EngineAction::Grab,
+        EngineAction::Point,
+        EngineAction::Teleport,
+        EngineAction::Move,
+        EngineAction::Turn,
+    ];
+
+    /// The name the action on `handedness`'s side is rebound, read and saved by, eg. `hotham.grab.left`
+    pub fn name(&self, handedness: Handedness) -> String {
+        let action = match self {
+            EngineAction::Grab => "grab",
+            EngineAction::Point => "point",
+            EngineAction::Teleport => "teleport",
+            EngineAction::Move => "move",
+            EngineAction::Turn => "turn",
+        };
+        format!("hotham.{action}.{}", hand_name(handedness))
+    }
+
+    /// What kind of value the action reads
+    pub fn action_type(&self) -> CustomActionType {
+        match self {
+            EngineAction::Grab | EngineAction::Point => CustomActionType::Float,
+            EngineAction::Teleport | EngineAction::Move | EngineAction::Turn => {
+                CustomActionType::Vector2
+            }
+        }
+    }
+
+    /// The input the action on `handedness`'s side is bound to until the player rebinds it
+    pub fn default_binding(&self, handedness: Handedness) -> InputBinding {
+        let input = match self {
+            EngineAction::Grab => ControllerInput::Grip,
+            EngineAction::Point => ControllerInput::Trigger,
+            EngineAction::Teleport | EngineAction::Move | EngineAction::Turn => {
+                ControllerInput::Thumbstick
+            }
+        };
+        InputBinding { handedness, input }
+    }
+}
+
+fn hand_name(handedness: Handedness) -> &'static str {
+    match handedness {
+        Handedness::Left => "left",
+        Handedness::Right => "right",
+    }
+}
+
+/// The state of the left and right controllers
+type ControllerStates = (ControllerState, ControllerState);
+
+fn controller_states(input_context: &InputContext) -> ControllerStates {
+    (
+        input_context.left.controller_state(),
+        input_context.right.controller_state(),
+    )
+}
+
+/// An input on a particular controller, eg. the left trigger
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputBinding {
+    /// Which controller the input is on
+    pub handedness: Handedness,
+    /// Which input it is
+    pub input: ControllerInput,
+}
+
+impl InputBinding {
+    /// Shortcut helper to bind an input on the left controller
+    pub fn left(input: ControllerInput) -> Self {
+        Self {
+            handedness: Handedness::Left,
+            input,
+        }
+    }
+
+    /// Shortcut helper to bind an input on the right controller
+    pub fn right(input: ControllerInput) -> Self {
+        Self {
+            handedness: Handedness::Right,
+            input,
+        }
+    }
+
+    /// The state of the input as a button this frame. The trigger and grip are pressed past their threshold, and the
+    /// thumbstick is never pressed.
+    pub fn button(&self, input_context: &InputContext) -> ButtonState {
+        self.input.button(&self.controller_state_in(input_context))
+    }
+
+    /// The value of the input this frame, from 0 to 1: how far the trigger or grip is pulled, or 0 or 1 for a button
+    pub fn float(&self, input_context: &InputContext) -> f32 {
+        self.input.float(&self.controller_state_in(input_context))
+    }
+
+    /// The position of the thumbstick this frame, or zero for any other input
+    pub fn vector2(&self, input_context: &InputContext) -> Vec2 {
+        self.input.vector2(&self.controller_state_in(input_context))
+    }
+
+    fn controller_state_in(&self, input_context: &InputContext) -> ControllerState {
+        match self.handedness {
+            Handedness::Left => input_context.left.controller_state(),
+            Handedness::Right => input_context.right.controller_state(),
+        }
+    }
+
+    fn controller_state<'a>(&self, controller_states: &'a ControllerStates) -> &'a ControllerState {
+        match self.handedness {
+            Handedness::Left => &controller_states.0,
+            Handedness::Right => &controller_states.1,
+        }
+    }
+}
+
+/// Written as eg. `left trigger`, which is how bindings are saved
+impl fmt::Display for InputBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", hand_name(self.handedness), self.input.name())
+    }
+}
+
+impl FromStr for InputBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (handedness, input) = s
+            .trim()
+            .split_once(' ')
+            .ok_or_else(|| anyhow!("Expected a hand and an input, got {s:?}"))?;
+        let handedness = match handedness {
+            "left" => Handedness::Left,
+            "right" => Handedness::Right,
+            _ => return Err(anyhow!("Unknown hand {handedness:?}")),
+        };
+        let input = ControllerInput::ALL
+            .into_iter()
+            .find(|candidate| candidate.name() == input.trim())
+            .ok_or_else(|| anyhow!("Unknown input {input:?}"))?;
+        Ok(Self { handedness, input })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BoundAction {
+    name: String,
+    action_type: CustomActionType,
+    /// The input an engine action is read from until it's rebound. `None` for the application's actions, which are
+    /// read through the bindings the application suggested until then.
+    default: Option<InputBinding>,
+    /// The input the player bound the action to, if they've rebound it
+    binding: Option<InputBinding>,
+}
+
+impl BoundAction {
+    fn binding(&self) -> Option<InputBinding> {
+        self.binding.or(self.default)
+    }
+}
+
+/// The actions players can rebind, and the controller inputs they've rebound them to: Hotham's own
+/// [`EngineAction`]s, like grabbing and teleporting, and the application's boolean, float and vector2
+/// [`CustomAction`](crate::contexts::CustomAction)s, eg. `jump`.
+///
+/// OpenXR only lets bindings be suggested before the session starts, and leaves changing them to the runtime, so
+/// this is a layer above the [`InputContext`] that lets players remap actions from inside the application, eg. for
+/// accessibility. Engine actions are always read from the [`InputContext`], through their default binding until
+/// they're rebound. Until one of the application's actions is rebound it's read from OpenXR through the bindings
+/// suggested by [`EngineBuilder::custom_action_sets`](crate::EngineBuilder::custom_action_sets); once it is, it's
+/// read from the [`InputContext`] instead. Any bindings the player changed are loaded from the application's storage at startup,
+/// and [`InputBindings::save`] stores them again after they're changed.
+///
+/// Rebound actions read the same inputs as Hotham's own actions, so if a custom action set has a higher priority and
+/// suggests a binding for the same input, OpenXR gives that input to the custom action and the rebound action won't
+/// see it.
+#[derive(Debug, Clone)]
+pub struct InputBindings {
+    actions: Vec<BoundAction>,
+    storage_dir: PathBuf,
+}
+
+impl InputBindings {
+    /// Create bindings for the [`EngineAction`]s and every boolean, float and vector2 action in `custom_action_sets`,
+    /// saved to `storage_dir`
+    pub fn new(custom_action_sets: &[CustomActionSet], storage_dir: PathBuf) -> Self {
+        let engine_actions = EngineAction::ALL.into_iter().flat_map(|action| {
+            [Handedness::Left, Handedness::Right].map(|handedness| BoundAction {
+                name: action.name(handedness),
+                action_type: action.action_type(),
+                default: Some(action.default_binding(handedness)),
+                binding: None,
+            })
+        });
+        let custom_actions = custom_action_sets
+            .iter()
+            .flat_map(|action_set| &action_set.actions)
+            .filter(|action| action.action_type != CustomActionType::Pose)
+            .map(|action| BoundAction {
+                name: action.name.clone(),
+                action_type: action.action_type,
+                default: None,
+                binding: None,
+            });
+        let actions = engine_actions.chain(custom_actions).collect();
+        Self {
+            actions,
+            storage_dir,
+        }
+    }
+
+    /// The names of every action that can be rebound: the engine actions, then the application's in the order they
+    /// were added
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.iter().map(|action| action.name.as_str())
+    }
+
+    /// The input an action is read from: the one the player bound it to, or an engine action's default. `None` if
+    /// it's one of the application's actions that still uses the bindings the application suggested, or there's no
+    /// action with that name.
+    pub fn binding(&self, name: &str) -> Option<InputBinding> {
+        self.find(name).ok().and_then(BoundAction::binding)
+    }
+
+    /// The input an [`EngineAction`] on `handedness`'s side is read from
+    pub fn engine_binding(&self, action: EngineAction, handedness: Handedness) -> InputBinding {
+        self.binding(&action.name(handedness))
+            .unwrap_or_else(|| action.default_binding(handedness))
+    }
+
+    /// The state of a [`CustomActionType::Boolean`] action this frame
+    pub fn button(
+        &self,
+        name: &str,
+        xr_context: &XrContext,
+        input_context: &InputContext,
+    ) -> Result<ButtonState> {
+        if let Some(state) = self.button_of(name, &controller_states(input_context))? {
+            return Ok(state);
+        }
+        let state = xr_context
+            .input
+            .custom_actions
+            .boolean(&xr_context.session, name)?;
+        Ok(ButtonState {
+            pressed: state.current_state,
+            just_pressed: state.current_state && state.changed_since_last_sync,
+            just_released: !state.current_state && state.changed_since_last_sync,
+        })
+    }
+
+    /// The value of a [`CustomActionType::Float`] action this frame
+    pub fn float(
+        &self,
+        name: &str,
+        xr_context: &XrContext,
+        input_context: &InputContext,
+    ) -> Result<f32> {
+        if let Some(value) = self.float_of(name, &controller_states(input_context))? {
+            return Ok(value);
+        }
+        let state = xr_context
+            .input
+            .custom_actions
+            .float(&xr_context.session, name)?;
+        Ok(state.current_state)
+    }
+
+    /// The value of a [`CustomActionType::Vector2`] action this frame
+    pub fn vector2(
+        &self,
+        name: &str,
+        xr_context: &XrContext,
+        input_context: &InputContext,
+    ) -> Result<Vec2> {
+        if let Some(value) = self.vector2_of(name, &controller_states(input_context))? {
+            return Ok(value);
+        }
+        let state = xr_context
+            .input
+            .custom_actions
+            .vector2(&xr_context.session, name)?;
+        Ok(Vec2::new(state.current_state.x, state.current_state.y))
+    }
+
+    /// The state of a rebound boolean action, or `None` if it hasn't been rebound
+    fn button_of(
+        &self,
+        name: &str,
+        controller_states: &ControllerStates,
+    ) -> Result<Option<ButtonState>> {
+        Ok(self
+            .rebound(name, CustomActionType::Boolean)?
+            .map(|binding| {
+                binding
+                    .input
+                    .button(binding.controller_state(controller_states))
+            }))
+    }
+
+    /// The value of a rebound float action, or `None` if it hasn't been rebound
+    fn float_of(&self, name: &str, controller_states: &ControllerStates) -> Result<Option<f32>> {
+        Ok(self.rebound(name, CustomActionType::Float)?.map(|binding| {
+            binding
+                .input
+                .float(binding.controller_state(controller_states))
+        }))
+    }
+
+    /// The value of a rebound vector2 action, or `None` if it hasn't been rebound
+    fn vector2_of(&self, name: &str, controller_states: &ControllerStates) -> Result<Option<Vec2>> {
+        Ok(self
+            .rebound(name, CustomActionType::Vector2)?
+            .map(|binding| {
+                binding
+                    .input
+                    .vector2(binding.controller_state(controller_states))
+            }))
+    }
+
+    fn rebound(&self, name: &str, action_type: CustomActionType) -> Result<Option<InputBinding>> {
+        let action = self.find(name)?;
+        if action.action_type != action_type {
+            return Err(anyhow!(
+                "The action {name:?} is a {:?} action, not a {action_type:?} one",
+                action.action_type
+            ));
+        }
+        Ok(action.binding())
+    }
+
+    /// Bind an action to a different input, which must suit the action's type (see [`ControllerInput::can_bind`]).
+    /// Call [`InputBindings::save`] to keep the change for the next time the application starts.
+    pub fn rebind(&mut self, name: &str, binding: InputBinding) -> Result<()> {
+        let action = self.find_mut(name)?;
+        if !binding.input.can_bind(action.action_type) {
+            return Err(anyhow!(
+                "The {:?} action {name:?} can't be bound to the {binding}",
+                action.action_type
+            ));
+        }
+        action.binding = Some(binding);
+        Ok(())
+    }
+
+    /// Use an action's default binding, or the bindings the application suggested, again
+    pub fn reset(&mut self, name: &str) -> Result<()> {
+        self.find_mut(name)?.binding = None;
+        Ok(())
+    }
+
+    /// Use the default bindings, and the bindings the application suggested, for every action again
+    pub fn reset_all(&mut self) {
+        for action in &mut self.actions {
+            action.binding = None;
+        }
+    }
+
+    /// The first button that was just pressed this frame, eg. to rebind an action to whichever button the player
+    /// presses next. The thumbstick's position is never returned; offer it to vector2 actions directly.
+    pub fn pressed_binding(input_context: &InputContext) -> Option<InputBinding> {
+        Self::pressed_binding_of(&controller_states(input_context))
+    }
+
+    fn pressed_binding_of(controller_states: &ControllerStates) -> Option<InputBinding> {
+        [Handedness::Left, Handedness::Right]
+            .into_iter()
+            .flat_map(|handedness| {
+                ControllerInput::ALL
+                    .into_iter()
+                    .map(move |input| InputBinding { handedness, input })
+            })
+            .find(|binding| {
+                binding
+                    .input
+                    .button(binding.controller_state(controller_states))
+                    .just_pressed
+            })
+    }
+
+    /// Where the bindings are kept in the application's storage
+    pub fn storage_path(&self) -> PathBuf {
+        self.storage_dir.join(INPUT_BINDINGS_FILE)
+    }
+
+    /// Save the bindings the player changed to the application's storage
+    pub fn save(&self) -> Result<()> {
+        std::fs::create_dir_all(&self.storage_dir)?;
+        std::fs::write(self.storage_path(), self.to_text())?;
+        Ok(())
+    }
+
+    /// Apply the bindings saved to the application's storage, if any have been
+    pub fn load(&mut self) -> Result<()> {
+        let path = self.storage_path();
+        if !path.exists() {
+            return Ok(());
+        }
+        let text = std::fs::read_to_string(path)?;
+        self.apply_text(&text);
+        Ok(())
+    }
+
+    /// The bindings the player changed, one `action = binding` per line
+    fn to_text(&self) -> String {
+        self.actions
+            .iter()
+            .filter_map(|action| {
+                action
+                    .binding
+                    .map(|binding| format!("{} = {binding}\n", action.name))
+            })
+            .collect()
+    }
+
+    /// Apply bindings saved by [`InputBindings::to_text`]. Lines that can't be applied, eg. because the action has
+    /// since been removed from the application, are skipped.
+    fn apply_text(&mut self, text: &str) {
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let result = line
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expected `action = binding`"))
+                .and_then(|(name, binding)| self.rebind(name.trim(), binding.parse()?));
+            if let Err(e) = result {
+                println!("[HOTHAM_INPUT_BINDINGS] Skipping the saved binding {line:?}: {e}");
+            }
+        }
+    }
+
+    fn find(&self, name: &str) -> Result<&BoundAction> {
+        self.actions
+            .iter()
+            .find(|action| action.name == name)
+            .ok_or_else(|| anyhow!("There's no action named {name:?}"))
+    }
+
+    fn find_mut(&mut self, name: &str) -> Result<&mut BoundAction> {
+        self.actions
+            .iter_mut()
+            .find(|action| action.name == name)
+            .ok_or_else(|| anyhow!("There's no action named {name:?}"))
+    }
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self::new(&[], PathBuf::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contexts::CustomAction;
+    use approx::assert_relative_eq;
+
+    fn bindings() -> InputBindings {
+        let gameplay = CustomActionSet::new("gameplay", "Gameplay")
+            .action(CustomAction::new("jump", "Jump", CustomActionType::Boolean))
+            .action(CustomAction::new(
+                "accelerate",
+                "Accelerate",
+                CustomActionType::Float,
+            ))
+            .action(CustomAction::new("move", "Move", CustomActionType::Vector2))
+            .action(CustomAction::new("aim", "Aim", CustomActionType::Pose));
+        InputBindings::new(&[gameplay], PathBuf::from("."))
+    }
+
+    #[test]
+    pub fn test_rebinding() {
+        let mut bindings = bindings();
+        let actions = bindings.actions().collect::<Vec<_>>();
+        assert_eq!(actions.len(), EngineAction::ALL.len() * 2 + 3);
+        assert_eq!(actions[..2], ["hotham.grab.left", "hotham.grab.right"]);
+        assert!(actions.ends_with(&["jump", "accelerate", "move"]));
+
+        let left = ControllerState {
+            grip: 0.9,
+            grip_button: ButtonState {
+                pressed: true,
+                just_pressed: true,
+                just_released: false,
+            },
+            thumbstick: Vec2::new(0.5, -1.),
+            ..Default::default()
+        };
+        let controller_states = (left, ControllerState::default());
+
+        // Until they're rebound, actions are read from OpenXR.
+        assert_eq!(
+            bindings.button_of("jump", &controller_states).unwrap(),
+            None
+        );
+        assert!(bindings.float_of("jump", &controller_states).is_err());
+        assert!(bindings.button_of("aim", &controller_states).is_err());
+
+        // The player would rather jump with the left grip.
+        let grip = InputBindings::pressed_binding_of(&controller_states).unwrap();
+        assert_eq!(grip, InputBinding::left(ControllerInput::Grip));
+        bindings.rebind("jump", grip).unwrap();
+        assert!(
+            bindings
+                .button_of("jump", &controller_states)
+                .unwrap()
+                .unwrap()
+                .just_pressed
+        );
+
+        // Float actions read how far it's squeezed, and buttons as 0 or 1.
+        bindings.rebind("accelerate", grip).unwrap();
+        assert_eq!(
+            bindings.float_of("accelerate", &controller_states).unwrap(),
+            Some(0.9)
+        );
+        bindings
+            .rebind("accelerate", InputBinding::right(ControllerInput::Primary))
+            .unwrap();
+        assert_eq!(
+            bindings.float_of("accelerate", &controller_states).unwrap(),
+            Some(0.)
+        );
+
+        // Only the thumbstick can be bound to a vector2 action, and only to one.
+        let thumbstick = InputBinding::left(ControllerInput::Thumbstick);
+        assert!(bindings.rebind("move", grip).is_err());
+        assert!(bindings.rebind("jump", thumbstick).is_err());
+        bindings.rebind("move", thumbstick).unwrap();
+        assert_eq!(
+            bindings.vector2_of("move", &controller_states).unwrap(),
+            Some(Vec2::new(0.5, -1.))
+        );
+        assert!(bindings.rebind("fly", grip).is_err());
+
+        bindings.reset("jump").unwrap();
+        assert_eq!(
+            bindings.button_of("jump", &controller_states).unwrap(),
+            None
+        );
+        assert_eq!(bindings.binding("jump"), None);
+        assert_eq!(bindings.binding("move"), Some(thumbstick));
+    }
+
+    #[test]
+    pub fn test_engine_actions() {
+        let mut bindings = bindings();
+        let left = ControllerState {
+            trigger: 0.8,
+            grip: 0.1,
+            thumbstick: Vec2::new(0., 1.),
+            ..Default::default()
+        };
+        let controller_states = (left, ControllerState::default());
+        let grab = |bindings: &InputBindings| {
+            let binding = bindings.engine_binding(EngineAction::Grab, Handedness::Left);
+            binding
+                .input
+                .float(binding.controller_state(&controller_states))
+        };
+
+        // Engine actions are bound to the same hand by default, and are read from the controllers, not OpenXR.
+        assert_relative_eq!(grab(&bindings), 0.1);
+        assert_eq!(
+            bindings
+                .float_of("hotham.point.left", &controller_states)
+                .unwrap(),
+            Some(0.8)
+        );
+        assert_eq!(
+            bindings
+                .vector2_of("hotham.teleport.right", &controller_states)
+                .unwrap(),
+            Some(Vec2::ZERO)
+        );
+
+        // The player would rather grab with the trigger.
+        bindings
+            .rebind(
+                "hotham.grab.left",
+                InputBinding::left(ControllerInput::Trigger),
+            )
+            .unwrap();
+        assert_relative_eq!(grab(&bindings), 0.8);
+        assert!(bindings
+            .rebind(
+                "hotham.move.left",
+                InputBinding::left(ControllerInput::Grip)
+            )
+            .is_err());
+
+        // Moving with the right thumbstick is saved, and so is the grab, but not the defaults.
+        bindings
+            .rebind(
+                "hotham.move.left",
+                InputBinding::right(ControllerInput::Thumbstick),
+            )
+            .unwrap();
+        assert_eq!(
+            bindings.to_text(),
+            "hotham.grab.left = left trigger\nhotham.move.left = right thumbstick\n"
+        );
+
+        bindings.reset("hotham.grab.left").unwrap();
+        assert_relative_eq!(grab(&bindings), 0.1);
+    }
+
+    #[test]
+    pub fn test_saving_bindings() {
+        let mut bindings = bindings();
+        bindings
+            .rebind(
+                "accelerate",
+                InputBinding::right(ControllerInput::ThumbstickClick),
+            )
+            .unwrap();
+
+        // Only the changed binding is saved, so changing a suggested binding in the application still takes effect.
+        let text = bindings.to_text();
+        assert_eq!(text, "accelerate = right thumbstick_click\n");
+
+        let mut loaded = self::bindings();
+        loaded.apply_text(&format!(
+            "{text}fly = left trigger\njump = left elbow\nmove = left trigger\n"
+        ));
+        assert_eq!(
+            loaded.binding("accelerate"),
+            Some(InputBinding::right(ControllerInput::ThumbstickClick))
+        );
+        assert_eq!(loaded.binding("jump"), None);
+        assert_eq!(loaded.binding("move"), None);
+
+        loaded.reset_all();
+        assert_eq!(loaded.to_text(), "");
+    }
+}
@@ -1,10 +1,142 @@
 use crate::{
-    contexts::XrContext,
+    components::{
+        body_joints::{BodyJoint, BODY_JOINT_COUNT, BODY_UPPER_JOINT_COUNT},
+        hand::Handedness,
+        hand_joints::{HandJointLocation, HAND_JOINT_COUNT},
+    },
+    contexts::{
+        gamepad_context::{GamepadButton, RawGamepadState, GAMEPAD_BUTTON_COUNT},
+        xr_context::{BodyJointLocations, TrackerRole, Trackers},
+        SimulatedXrContext, XrContext,
+    },
     util::{affine_from_posef, is_space_valid, lerp_slerp},
     xr,
 };
 use glam::{Affine3A, Vec2, Vec3};
 
+/// The state of a button this frame
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ButtonState {
+    /// Is the button held down?
+    pub pressed: bool,
+    /// Was the button pressed this frame?
+    pub just_pressed: bool,
+    /// Was the button released this frame?
+    pub just_released: bool,
+}
+
+impl ButtonState {
+    fn new(pressed: bool, pressed_prev: bool) -> Self {
+        Self {
+            pressed,
+            just_pressed: pressed && !pressed_prev,
+            just_released: !pressed && pressed_prev,
+        }
+    }
+}
+
+/// Every input of one controller this frame, in the same shape for both hands. The primary and secondary buttons are
+/// X and Y on the left controller, and A and B on the right.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ControllerState {
+    /// The position of the thumbstick, from -1 to 1 on each axis
+    pub thumbstick: Vec2,
+    /// How far the trigger is pulled, from 0 to 1
+    pub trigger: f32,
+    /// How far the grip is squeezed, from 0 to 1
+    pub grip: f32,
+    /// The trigger, as a button
+    pub trigger_button: ButtonState,
+    /// The grip, as a button
+    pub grip_button: ButtonState,
+    /// The A or X button
+    pub primary_button: ButtonState,
+    /// The B or Y button
+    pub secondary_button: ButtonState,
+    /// The menu button. The right controller's is reserved by the runtime, so it's never pressed.
+    pub menu_button: ButtonState,
+    /// Clicking the thumbstick in
+    pub thumbstick_click: ButtonState,
+    /// Is a finger resting on the trigger?
+    pub trigger_touch: ButtonState,
+    /// Is a thumb resting on the A or X button?
+    pub primary_touch: ButtonState,
+    /// Is a thumb resting on the B or Y button?
+    pub secondary_touch: ButtonState,
+    /// Is a thumb resting on the thumbstick?
+    pub thumbstick_touch: ButtonState,
+    /// Is a thumb resting on the thumbrest?
+    pub thumbrest_touch: ButtonState,
+}
+
+/// Whether a controller can be used this frame, and how much charge it has left
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ControllerStatus {
+    /// Is the controller connected? It isn't while it's switched off, once its battery has died, or while the player
+    /// has put it down to use their hands instead.
+    pub is_connected: bool,
+    /// Is the controller's position being tracked? It can be connected but untracked, eg. while it's out of view of
+    /// the headset's cameras.
+    pub is_tracked: bool,
+    /// How much charge the controller's battery has left, from 0 to 1, if the runtime reports it. OpenXR has no
+    /// standard way to read it, so it's only known in the simulator, where it can be set on the
+    /// [`SimulatedController`](crate::contexts::SimulatedController).
+    pub battery_level: Option<f32>,
+}
+
+impl ControllerStatus {
+    /// The battery level at or below which [`ControllerStatus::is_battery_low`] warns the player
+    pub const LOW_BATTERY_LEVEL: f32 = 0.2;
+
+    /// Should the player be warned that the controller's battery is about to die?
+    pub fn is_battery_low(&self) -> bool {
+        matches!(self.battery_level, Some(battery_level) if battery_level <= Self::LOW_BATTERY_LEVEL)
+    }
+
+    /// The status of a controller whose grip pose action is `is_active`, and whose grip space was located with
+    /// `location_flags`
+    fn from_grip(is_active: bool, location_flags: xr::SpaceLocationFlags) -> Self {
+        Self {
+            is_connected: is_active,
+            is_tracked: is_active
+                && location_flags.contains(
+                    xr::SpaceLocationFlags::POSITION_TRACKED
+                        | xr::SpaceLocationFlags::ORIENTATION_TRACKED,
+                ),
+            battery_level: None,
+        }
+    }
+}
+
+/// Whether the player is using a controller or their bare hand, for one of their hands. Switched automatically as the
+/// player puts their controllers down or picks them up, and each switch is reported with an
+/// [`XrEvent::InputModeChanged`](crate::contexts::XrEvent::InputModeChanged).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputMode {
+    /// The player is holding a controller
+    #[default]
+    Controller,
+    /// The player is using their tracked hand. Requires hand tracking to be enabled with
+    /// [`EngineBuilder::hand_tracking`](crate::EngineBuilder::hand_tracking).
+    Hand,
+}
+
+impl InputMode {
+    /// The mode after a frame where the controller had `status` and the hand was or wasn't tracked. The runtime only
+    /// tracks a hand once it's let go of its controller, and the controller only stays connected while the runtime
+    /// uses a controller interaction profile for that hand, so either one is enough to switch. While neither is, eg.
+    /// because the hand is out of view, the mode stays the same.
+    fn next(self, status: &ControllerStatus, is_hand_tracked: bool) -> Self {
+        if is_hand_tracked {
+            InputMode::Hand
+        } else if status.is_connected {
+            InputMode::Controller
+        } else {
+            self
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct LeftInputContext {
     // boolean input
@@ -44,6 +176,11 @@ pub struct LeftInputContext {
     // pose input
     stage_from_grip: Affine3A,
     stage_from_aim: Affine3A,
+    // hand tracking input
+    stage_from_hand_joints: Option<[HandJointLocation; HAND_JOINT_COUNT]>,
+    // device status
+    status: ControllerStatus,
+    input_mode: InputMode,
 }
 
 impl LeftInputContext {
@@ -167,6 +304,37 @@ impl LeftInputContext {
     pub fn stage_from_aim(&self) -> Affine3A {
         self.stage_from_aim
     }
+    /// The joints of the hand in stage space, if hand tracking is enabled and the hand was tracked this frame
+    pub fn stage_from_hand_joints(&self) -> Option<&[HandJointLocation; HAND_JOINT_COUNT]> {
+        self.stage_from_hand_joints.as_ref()
+    }
+    /// Whether the controller is connected and tracked, and its battery level, this frame
+    pub fn status(&self) -> ControllerStatus {
+        self.status
+    }
+    /// Whether the player is using the controller or their hand
+    pub fn input_mode(&self) -> InputMode {
+        self.input_mode
+    }
+    /// Every input of the controller this frame
+    pub fn controller_state(&self) -> ControllerState {
+        ControllerState {
+            thumbstick: self.thumbstick_xy,
+            trigger: self.trigger_analog,
+            grip: self.grip_analog,
+            trigger_button: ButtonState::new(self.trigger_button, self.trigger_button_prev),
+            grip_button: ButtonState::new(self.grip_button, self.grip_button_prev),
+            primary_button: ButtonState::new(self.x_button, self.x_button_prev),
+            secondary_button: ButtonState::new(self.y_button, self.y_button_prev),
+            menu_button: ButtonState::new(self.menu_button, self.menu_button_prev),
+            thumbstick_click: ButtonState::new(self.thumbstick_click, self.thumbstick_click_prev),
+            trigger_touch: ButtonState::new(self.trigger_touch, self.trigger_touch_prev),
+            primary_touch: ButtonState::new(self.x_touch, self.x_touch_prev),
+            secondary_touch: ButtonState::new(self.y_touch, self.y_touch_prev),
+            thumbstick_touch: ButtonState::new(self.thumbstick_touch, self.thumbstick_touch_prev),
+            thumbrest_touch: ButtonState::new(self.thumbrest_touch, self.thumbrest_touch_prev),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -206,6 +374,11 @@ pub struct RightInputContext {
     // pose input
     stage_from_grip: Affine3A,
     stage_from_aim: Affine3A,
+    // hand tracking input
+    stage_from_hand_joints: Option<[HandJointLocation; HAND_JOINT_COUNT]>,
+    // device status
+    status: ControllerStatus,
+    input_mode: InputMode,
 }
 
 impl RightInputContext {
@@ -320,6 +493,37 @@ impl RightInputContext {
     pub fn stage_from_aim(&self) -> Affine3A {
         self.stage_from_aim
     }
+    /// The joints of the hand in stage space, if hand tracking is enabled and the hand was tracked this frame
+    pub fn stage_from_hand_joints(&self) -> Option<&[HandJointLocation; HAND_JOINT_COUNT]> {
+        self.stage_from_hand_joints.as_ref()
+    }
+    /// Whether the controller is connected and tracked, and its battery level, this frame
+    pub fn status(&self) -> ControllerStatus {
+        self.status
+    }
+    /// Whether the player is using the controller or their hand
+    pub fn input_mode(&self) -> InputMode {
+        self.input_mode
+    }
+    /// Every input of the controller this frame
+    pub fn controller_state(&self) -> ControllerState {
+        ControllerState {
+            thumbstick: self.thumbstick_xy,
+            trigger: self.trigger_analog,
+            grip: self.grip_analog,
+            trigger_button: ButtonState::new(self.trigger_button, self.trigger_button_prev),
+            grip_button: ButtonState::new(self.grip_button, self.grip_button_prev),
+            primary_button: ButtonState::new(self.a_button, self.a_button_prev),
+            secondary_button: ButtonState::new(self.b_button, self.b_button_prev),
+            menu_button: ButtonState::default(),
+            thumbstick_click: ButtonState::new(self.thumbstick_click, self.thumbstick_click_prev),
+            trigger_touch: ButtonState::new(self.trigger_touch, self.trigger_touch_prev),
+            primary_touch: ButtonState::new(self.a_touch, self.a_touch_prev),
+            secondary_touch: ButtonState::new(self.b_touch, self.b_touch_prev),
+            thumbstick_touch: ButtonState::new(self.thumbstick_touch, self.thumbstick_touch_prev),
+            thumbrest_touch: ButtonState::new(self.thumbrest_touch, self.thumbrest_touch_prev),
+        }
+    }
 }
 
 #[derive(Debug, Default)]
@@ -333,7 +537,10 @@ impl HmdInputContext {
     pub(crate) fn update(&mut self, xr_context: &XrContext) {
         // Since engine will call `update_views()` *just before* calling this method, we
         // can be sure that this data is up-to-date.
-        let views = &xr_context.views;
+        self.update_from_views(&xr_context.views);
+    }
+
+    fn update_from_views(&mut self, views: &[xr::View]) {
         self.left_eye_in_stage = affine_from_posef(views[0].pose);
         self.right_eye_in_stage = affine_from_posef(views[1].pose);
     }
@@ -344,6 +551,191 @@ impl HmdInputContext {
     }
 }
 
+#[derive(Debug, Default)]
+/// Where extra tracked devices, such as Vive trackers, are, if trackers are enabled
+pub struct TrackersInputContext {
+    pub(crate) stage_from_trackers: Vec<(TrackerRole, Affine3A)>,
+}
+
+impl TrackersInputContext {
+    pub(crate) fn update(&mut self, xr_context: &XrContext) {
+        self.stage_from_trackers.clear();
+        if let Some(trackers) = &xr_context.input.trackers {
+            self.update_trackers(trackers, xr_context);
+        }
+
+        // A keyboard found with keyboard tracking is treated as one more tracker.
+        if let Some(keyboard_tracking) = &xr_context.keyboard_tracking {
+            let stage_from_keyboard = keyboard_tracking
+                .locate(
+                    &xr_context.stage_space,
+                    xr_context.frame_state.predicted_display_time,
+                )
+                .unwrap();
+            if let Some(stage_from_keyboard) = stage_from_keyboard {
+                if self.stage_from_tracker(TrackerRole::Keyboard).is_none() {
+                    self.stage_from_trackers
+                        .push((TrackerRole::Keyboard, stage_from_keyboard));
+                }
+            }
+        }
+    }
+
+    fn update_trackers(&mut self, trackers: &Trackers, xr_context: &XrContext) {
+        for (role, space) in &trackers.spaces {
+            let location = space
+                .locate(
+                    &xr_context.stage_space,
+                    xr_context.frame_state.predicted_display_time,
+                )
+                .unwrap();
+            if is_space_valid(&location)
+                && location
+                    .location_flags
+                    .contains(xr::SpaceLocationFlags::POSITION_TRACKED)
+            {
+                self.stage_from_trackers
+                    .push((*role, affine_from_posef(location.pose)));
+            }
+        }
+    }
+
+    /// The pose of the tracker with the given role in stage space, if it was tracked this frame
+    pub fn stage_from_tracker(&self, role: TrackerRole) -> Option<Affine3A> {
+        self.stage_from_trackers
+            .iter()
+            .find(|(tracked_role, _)| *tracked_role == role)
+            .map(|(_, stage_from_tracker)| *stage_from_tracker)
+    }
+
+    /// Every tracker that was tracked this frame, with its pose in stage space
+    pub fn tracked(&self) -> &[(TrackerRole, Affine3A)] {
+        &self.stage_from_trackers
+    }
+}
+
+/// Every input of a gamepad this frame
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GamepadState {
+    /// The position of the left stick, from -1 to 1 on each axis
+    pub left_stick: Vec2,
+    /// The position of the right stick, from -1 to 1 on each axis
+    pub right_stick: Vec2,
+    /// How far the left trigger is pulled, from 0 to 1
+    pub left_trigger: f32,
+    /// How far the right trigger is pulled, from 0 to 1
+    pub right_trigger: f32,
+    buttons: [ButtonState; GAMEPAD_BUTTON_COUNT],
+}
+
+impl GamepadState {
+    /// The state of one of the gamepad's buttons
+    pub fn button(&self, button: GamepadButton) -> ButtonState {
+        self.buttons[button as usize]
+    }
+}
+
+#[derive(Debug, Default)]
+/// Input from a gamepad, if one is connected
+pub struct GamepadInputContext {
+    state: Option<RawGamepadState>,
+    state_prev: Option<RawGamepadState>,
+}
+
+impl GamepadInputContext {
+    pub(crate) fn update(&mut self, state: Option<RawGamepadState>) {
+        self.state_prev = std::mem::replace(&mut self.state, state);
+    }
+
+    /// Is a gamepad connected?
+    pub fn is_connected(&self) -> bool {
+        self.state.is_some()
+    }
+
+    /// Every input of the gamepad this frame, or `None` if no gamepad is connected
+    pub fn state(&self) -> Option<GamepadState> {
+        let state = self.state.as_ref()?;
+        let buttons_prev = self
+            .state_prev
+            .map(|state| state.buttons)
+            .unwrap_or_default();
+        let mut buttons = [ButtonState::default(); GAMEPAD_BUTTON_COUNT];
+        for (i, button) in buttons.iter_mut().enumerate() {
+            *button = ButtonState::new(state.buttons[i], buttons_prev[i]);
+        }
+        Some(GamepadState {
+            left_stick: state.left_stick,
+            right_stick: state.right_stick,
+            left_trigger: state.left_trigger,
+            right_trigger: state.right_trigger,
+            buttons,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+/// Where the player is looking, if eye gaze is enabled
+pub struct EyeGazeInputContext {
+    stage_from_gaze: Option<Affine3A>,
+}
+
+impl EyeGazeInputContext {
+    pub(crate) fn update(&mut self, xr_context: &XrContext) {
+        let eye_gaze_space = match &xr_context.input.eye_gaze_space {
+            Some(eye_gaze_space) => eye_gaze_space,
+            None => return,
+        };
+        let location = eye_gaze_space
+            .locate(
+                &xr_context.stage_space,
+                xr_context.frame_state.predicted_display_time,
+            )
+            .unwrap();
+
+        // The runtime keeps reporting the last gaze when the eyes can't be seen, eg. while blinking.
+        self.stage_from_gaze = if is_space_valid(&location)
+            && location
+                .location_flags
+                .contains(xr::SpaceLocationFlags::ORIENTATION_TRACKED)
+        {
+            Some(affine_from_posef(location.pose))
+        } else {
+            None
+        };
+    }
+
+    /// The pose of the player's gaze in stage space, looking down its -Z axis, if their eyes are being tracked
+    pub fn stage_from_gaze(&self) -> Option<Affine3A> {
+        self.stage_from_gaze
+    }
+}
+
+#[derive(Debug, Default)]
+/// Where the joints of the player's body are, if body tracking is enabled
+pub struct BodyInputContext {
+    stage_from_body_joints: Option<BodyJointLocations>,
+}
+
+impl BodyInputContext {
+    pub(crate) fn update(&mut self, xr_context: &XrContext) {
+        let body_tracking = match &xr_context.body_tracking {
+            Some(body_tracking) => body_tracking,
+            None => return,
+        };
+        self.stage_from_body_joints = body_tracking
+            .locate(
+                &xr_context.stage_space,
+                xr_context.frame_state.predicted_display_time,
+            )
+            .unwrap();
+    }
+
+    /// The joints of the player's body in stage space, if it's being tracked
+    pub fn stage_from_body_joints(&self) -> Option<&BodyJointLocations> {
+        self.stage_from_body_joints.as_ref()
+    }
+}
+
 #[derive(Debug, Default)]
 /// Context that holds input state. Allows users to query for input events without having to
 /// worry about OpenXR internals.
@@ -354,6 +746,10 @@ pub struct InputContext {
     pub left: LeftInputContext,
     pub right: RightInputContext,
     pub hmd: HmdInputContext,
+    pub eye_gaze: EyeGazeInputContext,
+    pub body: BodyInputContext,
+    pub gamepad: GamepadInputContext,
+    pub trackers: TrackersInputContext,
 }
 
 impl InputContext {
@@ -366,32 +762,7 @@ impl InputContext {
         let right_subaction_path = input.right_hand_subaction_path;
         let time = xr_context.frame_state.predicted_display_time;
 
-        self.left.x_button_prev = self.left.x_button;
-        self.left.y_button_prev = self.left.y_button;
-        self.left.menu_button_prev = self.left.menu_button;
-        self.left.grip_button_prev = self.left.grip_button;
-        self.left.trigger_button_prev = self.left.trigger_button;
-        self.left.thumbstick_click_prev = self.left.thumbstick_click;
-        self.left.x_touch_prev = self.left.x_touch;
-        self.left.y_touch_prev = self.left.y_touch;
-        self.left.trigger_touch_prev = self.left.trigger_touch;
-        self.left.thumbstick_touch_prev = self.left.thumbstick_touch;
-        self.left.thumbrest_touch_prev = self.left.thumbrest_touch;
-        self.left.grip_analog_prev = self.left.grip_analog;
-        self.left.trigger_analog_prev = self.left.trigger_analog;
-
-        self.right.a_button_prev = self.right.a_button;
-        self.right.b_button_prev = self.right.b_button;
-        self.right.grip_button_prev = self.right.grip_button;
-        self.right.trigger_button_prev = self.right.trigger_button;
-        self.right.thumbstick_click_prev = self.right.thumbstick_click;
-        self.right.a_touch_prev = self.right.a_touch;
-        self.right.b_touch_prev = self.right.b_touch;
-        self.right.trigger_touch_prev = self.right.trigger_touch;
-        self.right.thumbstick_touch_prev = self.right.thumbstick_touch;
-        self.right.thumbrest_touch_prev = self.right.thumbrest_touch;
-        self.right.grip_analog_prev = self.right.grip_analog;
-        self.right.trigger_analog_prev = self.right.trigger_analog;
+        self.store_previous_state();
 
         self.left.x_button =
             xr::ActionInput::get(&input.x_button_action, session, left_subaction_path)
@@ -454,9 +825,15 @@ impl InputContext {
             .unwrap();
         if is_space_valid(location) {
             self.left.stage_from_grip = affine_from_posef(location.pose);
-            self.left.linear_velocity = mint::Vector3::from(velocity.linear_velocity).into();
-            self.left.angular_velocity = mint::Vector3::from(velocity.angular_velocity).into();
         }
+        (self.left.linear_velocity, self.left.angular_velocity) = grip_velocities(velocity);
+        self.left.status = ControllerStatus::from_grip(
+            input
+                .grip_pose_action
+                .is_active(session, left_subaction_path)
+                .unwrap(),
+            location.location_flags,
+        );
         let location = &input
             .left_hand_aim_space
             .locate(&xr_context.stage_space, time)
@@ -528,9 +905,15 @@ impl InputContext {
             .unwrap();
         if is_space_valid(location) {
             self.right.stage_from_grip = affine_from_posef(location.pose);
-            self.right.linear_velocity = mint::Vector3::from(velocity.linear_velocity).into();
-            self.right.angular_velocity = mint::Vector3::from(velocity.angular_velocity).into();
         }
+        (self.right.linear_velocity, self.right.angular_velocity) = grip_velocities(velocity);
+        self.right.status = ControllerStatus::from_grip(
+            input
+                .grip_pose_action
+                .is_active(session, right_subaction_path)
+                .unwrap(),
+            location.location_flags,
+        );
         let location = &input
             .right_hand_aim_space
             .locate(&xr_context.stage_space, time)
@@ -539,8 +922,157 @@ impl InputContext {
             self.right.stage_from_aim = affine_from_posef(location.pose);
         }
 
+        if let Some(hand_tracking) = &xr_context.hand_tracking {
+            self.left.stage_from_hand_joints =
+                locate_hand_joints(&xr_context.stage_space, &hand_tracking.left, time);
+            self.right.stage_from_hand_joints =
+                locate_hand_joints(&xr_context.stage_space, &hand_tracking.right, time);
+        }
+        self.update_input_modes();
+
         self.hmd.update(xr_context);
+        self.eye_gaze.update(xr_context);
+        self.body.update(xr_context);
+        self.trackers.update(xr_context);
     }
+
+    /// Synchronize the context state with a [`SimulatedXrContext`]. Called by
+    /// [`SimulatedXrContext::begin_frame`].
+    pub(crate) fn update_simulated(&mut self, xr_context: &SimulatedXrContext) {
+        self.store_previous_state();
+
+        let left = &xr_context.input.left;
+        self.left.x_button = left.primary_button;
+        self.left.y_button = left.secondary_button;
+        self.left.menu_button = left.menu_button;
+        self.left.thumbstick_click = left.thumbstick_click;
+        self.left.grip_analog = left.grip;
+        self.left.grip_button = left.grip > 0.1;
+        self.left.trigger_analog = left.trigger;
+        self.left.trigger_button = left.trigger > 0.1;
+        self.left.thumbstick_xy = left.thumbstick;
+        self.left.stage_from_grip = left.stage_from_grip;
+        self.left.stage_from_aim = left.stage_from_grip;
+        self.left.status = left.status();
+
+        let right = &xr_context.input.right;
+        self.right.a_button = right.primary_button;
+        self.right.b_button = right.secondary_button;
+        self.right.thumbstick_click = right.thumbstick_click;
+        self.right.grip_analog = right.grip;
+        self.right.grip_button = right.grip > 0.1;
+        self.right.trigger_analog = right.trigger;
+        self.right.trigger_button = right.trigger > 0.1;
+        self.right.thumbstick_xy = right.thumbstick;
+        self.right.stage_from_grip = right.stage_from_grip;
+        self.right.stage_from_aim = right.stage_from_grip;
+        self.right.status = right.status();
+        self.update_input_modes();
+
+        self.hmd.update_from_views(xr_context.views());
+    }
+
+    /// Whether the player is using a controller or their hand, for each hand
+    pub fn input_modes(&self) -> [(Handedness, InputMode); 2] {
+        [
+            (Handedness::Left, self.left.input_mode),
+            (Handedness::Right, self.right.input_mode),
+        ]
+    }
+
+    /// Forget every input, so no buttons are left held down while the controllers aren't sending any, eg. while the
+    /// session isn't focused. Where the player's head is and whether they're using their hands are kept.
+    pub(crate) fn clear(&mut self) {
+        let (left_input_mode, right_input_mode) = (self.left.input_mode, self.right.input_mode);
+        *self = Self {
+            hmd: std::mem::take(&mut self.hmd),
+            ..Default::default()
+        };
+        self.left.input_mode = left_input_mode;
+        self.right.input_mode = right_input_mode;
+    }
+
+    fn update_input_modes(&mut self) {
+        self.left.input_mode = self.left.input_mode.next(
+            &self.left.status,
+            self.left.stage_from_hand_joints.is_some(),
+        );
+        self.right.input_mode = self.right.input_mode.next(
+            &self.right.status,
+            self.right.stage_from_hand_joints.is_some(),
+        );
+    }
+
+    fn store_previous_state(&mut self) {
+        self.left.x_button_prev = self.left.x_button;
+        self.left.y_button_prev = self.left.y_button;
+        self.left.menu_button_prev = self.left.menu_button;
+        self.left.grip_button_prev = self.left.grip_button;
+        self.left.trigger_button_prev = self.left.trigger_button;
+        self.left.thumbstick_click_prev = self.left.thumbstick_click;
+        self.left.x_touch_prev = self.left.x_touch;
+        self.left.y_touch_prev = self.left.y_touch;
+        self.left.trigger_touch_prev = self.left.trigger_touch;
+        self.left.thumbstick_touch_prev = self.left.thumbstick_touch;
+        self.left.thumbrest_touch_prev = self.left.thumbrest_touch;
+        self.left.grip_analog_prev = self.left.grip_analog;
+        self.left.trigger_analog_prev = self.left.trigger_analog;
+
+        self.right.a_button_prev = self.right.a_button;
+        self.right.b_button_prev = self.right.b_button;
+        self.right.grip_button_prev = self.right.grip_button;
+        self.right.trigger_button_prev = self.right.trigger_button;
+        self.right.thumbstick_click_prev = self.right.thumbstick_click;
+        self.right.a_touch_prev = self.right.a_touch;
+        self.right.b_touch_prev = self.right.b_touch;
+        self.right.trigger_touch_prev = self.right.trigger_touch;
+        self.right.thumbstick_touch_prev = self.right.thumbstick_touch;
+        self.right.thumbrest_touch_prev = self.right.thumbrest_touch;
+        self.right.grip_analog_prev = self.right.grip_analog;
+        self.right.trigger_analog_prev = self.right.trigger_analog;
+    }
+}
+
+/// The linear and angular velocities of a grip space in stage space, or zero if the runtime doesn't know them, eg.
+/// because the controller isn't tracked
+fn grip_velocities(velocity: &xr::SpaceVelocity) -> (Vec3, Vec3) {
+    let flags = velocity.velocity_flags;
+    let linear_velocity = if flags.contains(xr::SpaceVelocityFlags::LINEAR_VALID) {
+        mint::Vector3::from(velocity.linear_velocity).into()
+    } else {
+        Vec3::ZERO
+    };
+    let angular_velocity = if flags.contains(xr::SpaceVelocityFlags::ANGULAR_VALID) {
+        mint::Vector3::from(velocity.angular_velocity).into()
+    } else {
+        Vec3::ZERO
+    };
+    (linear_velocity, angular_velocity)
+}
+
+/// Locate every joint of a hand in stage space. Returns `None` unless every joint has a valid pose.
+fn locate_hand_joints(
+    stage_space: &xr::Space,
+    hand_tracker: &xr::HandTracker,
+    time: xr::Time,
+) -> Option<[HandJointLocation; HAND_JOINT_COUNT]> {
+    let locations = stage_space
+        .locate_hand_joints(hand_tracker, time)
+        .unwrap()?;
+    let valid = xr::SpaceLocationFlags::POSITION_VALID | xr::SpaceLocationFlags::ORIENTATION_VALID;
+    if !locations
+        .iter()
+        .all(|location| location.location_flags.contains(valid))
+    {
+        return None;
+    }
+
+    let mut joints = [HandJointLocation::default(); HAND_JOINT_COUNT];
+    for (joint, location) in joints.iter_mut().zip(locations.iter()) {
+        joint.transform = affine_from_posef(location.pose);
+        joint.radius = location.radius;
+    }
+    Some(joints)
 }
 
 impl InputContext {
@@ -553,13 +1085,214 @@ impl InputContext {
         input_context.right.stage_from_grip =
             glam::Affine3A::from_rotation_translation(rotation, [0.2, 1.4, -0.5].into());
 
+        // Both controllers are connected and tracked, but the runtime doesn't report their batteries.
+        let status = ControllerStatus {
+            is_connected: true,
+            is_tracked: true,
+            battery_level: None,
+        };
+        input_context.left.status = status;
+        input_context.right.status = status;
+
+        // The player is looking straight ahead, from head height.
+        input_context.eye_gaze.stage_from_gaze =
+            Some(glam::Affine3A::from_translation([0., 1.4, 0.].into()));
+
+        // The player's body is standing upright at the origin, except for their head, which is where they're looking
+        // from. The runtime couldn't estimate where their hands are.
+        let mut body_joints = vec![Some(glam::Affine3A::IDENTITY); BODY_UPPER_JOINT_COUNT];
+        body_joints[BodyJoint::Head as usize] =
+            Some(glam::Affine3A::from_translation([0., 1.4, 0.].into()));
+        body_joints.resize(BODY_JOINT_COUNT, None);
+        input_context.body.stage_from_body_joints = Some(BodyJointLocations {
+            confidence: 1.,
+            joints: body_joints,
+        });
+
+        // Only the left hand is tracked, with every joint at its grip, so the player is using it instead of the
+        // left controller.
+        input_context.left.stage_from_hand_joints = Some(
+            [HandJointLocation {
+                transform: input_context.left.stage_from_grip,
+                radius: 0.01,
+            }; HAND_JOINT_COUNT],
+        );
+        input_context.left.input_mode = InputMode::Hand;
+
         input_context
     }
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::HmdInputContext;
+    use super::{
+        grip_velocities, ButtonState, ControllerStatus, GamepadButton, GamepadInputContext,
+        HmdInputContext, InputMode, LeftInputContext, RawGamepadState, RightInputContext,
+    };
+    use crate::xr;
+    use glam::{Vec2, Vec3};
+
+    #[test]
+    pub fn test_grip_velocities() {
+        let mut velocity = xr::SpaceVelocity {
+            velocity_flags: xr::SpaceVelocityFlags::LINEAR_VALID,
+            linear_velocity: xr::Vector3f {
+                x: 1.,
+                y: 2.,
+                z: 3.,
+            },
+            angular_velocity: xr::Vector3f {
+                x: 4.,
+                y: 5.,
+                z: 6.,
+            },
+        };
+        assert_eq!(
+            grip_velocities(&velocity),
+            (Vec3::new(1., 2., 3.), Vec3::ZERO)
+        );
+
+        velocity.velocity_flags |= xr::SpaceVelocityFlags::ANGULAR_VALID;
+        assert_eq!(
+            grip_velocities(&velocity),
+            (Vec3::new(1., 2., 3.), Vec3::new(4., 5., 6.))
+        );
+
+        velocity.velocity_flags = xr::SpaceVelocityFlags::EMPTY;
+        assert_eq!(grip_velocities(&velocity), (Vec3::ZERO, Vec3::ZERO));
+    }
+
+    #[test]
+    pub fn test_controller_state() {
+        let left = LeftInputContext {
+            x_button: true,
+            y_button_prev: true,
+            trigger_analog: 0.5,
+            thumbstick_xy: Vec2::new(0.25, -1.),
+            ..Default::default()
+        };
+        let state = left.controller_state();
+        assert_eq!(
+            state.primary_button,
+            ButtonState {
+                pressed: true,
+                just_pressed: true,
+                just_released: false,
+            }
+        );
+        assert_eq!(
+            state.secondary_button,
+            ButtonState {
+                pressed: false,
+                just_pressed: false,
+                just_released: true,
+            }
+        );
+        assert_eq!(state.trigger, 0.5);
+        assert_eq!(state.thumbstick, Vec2::new(0.25, -1.));
+
+        let right = RightInputContext {
+            a_button: true,
+            a_button_prev: true,
+            ..Default::default()
+        };
+        let state = right.controller_state();
+        assert!(state.primary_button.pressed);
+        assert!(!state.primary_button.just_pressed);
+        assert_eq!(state.menu_button, ButtonState::default());
+    }
+
+    #[test]
+    pub fn test_controller_status() {
+        let tracked =
+            xr::SpaceLocationFlags::POSITION_TRACKED | xr::SpaceLocationFlags::ORIENTATION_TRACKED;
+        let status = ControllerStatus::from_grip(true, tracked);
+        assert!(status.is_connected);
+        assert!(status.is_tracked);
+
+        // Connected, but out of view of the cameras
+        let status = ControllerStatus::from_grip(true, xr::SpaceLocationFlags::ORIENTATION_TRACKED);
+        assert!(status.is_connected);
+        assert!(!status.is_tracked);
+
+        // Switched off, so where it was last seen isn't tracked
+        let status = ControllerStatus::from_grip(false, tracked);
+        assert!(!status.is_connected);
+        assert!(!status.is_tracked);
+
+        assert!(!status.is_battery_low());
+        let status = ControllerStatus {
+            battery_level: Some(0.1),
+            ..status
+        };
+        assert!(status.is_battery_low());
+        let status = ControllerStatus {
+            battery_level: Some(0.9),
+            ..status
+        };
+        assert!(!status.is_battery_low());
+    }
+
+    #[test]
+    pub fn test_input_mode() {
+        let connected = ControllerStatus {
+            is_connected: true,
+            ..Default::default()
+        };
+        let disconnected = ControllerStatus::default();
+
+        // The player puts the controller down, and their hand is tracked a few frames later.
+        let mode = InputMode::Controller.next(&disconnected, false);
+        assert_eq!(mode, InputMode::Controller);
+        let mode = mode.next(&disconnected, true);
+        assert_eq!(mode, InputMode::Hand);
+
+        // Their hand goes out of view for a moment.
+        let mode = mode.next(&disconnected, false);
+        assert_eq!(mode, InputMode::Hand);
+
+        // They pick the controller back up.
+        let mode = mode.next(&connected, false);
+        assert_eq!(mode, InputMode::Controller);
+    }
+
+    #[test]
+    pub fn test_gamepad_state() {
+        let mut gamepad = GamepadInputContext::default();
+        assert!(gamepad.state().is_none());
+
+        let mut raw_state = RawGamepadState {
+            left_stick: Vec2::new(0., 1.),
+            ..Default::default()
+        };
+        raw_state.buttons[GamepadButton::South as usize] = true;
+        gamepad.update(Some(raw_state));
+        let state = gamepad.state().unwrap();
+        assert_eq!(state.left_stick, Vec2::new(0., 1.));
+        assert!(state.button(GamepadButton::South).just_pressed);
+        assert!(!state.button(GamepadButton::East).pressed);
+
+        // Held down, then released.
+        gamepad.update(Some(raw_state));
+        assert!(
+            !gamepad
+                .state()
+                .unwrap()
+                .button(GamepadButton::South)
+                .just_pressed
+        );
+        gamepad.update(Some(RawGamepadState::default()));
+        assert!(
+            gamepad
+                .state()
+                .unwrap()
+                .button(GamepadButton::South)
+                .just_released
+        );
+
+        gamepad.update(None);
+        assert!(!gamepad.is_connected());
+    }
 
     #[test]
     pub fn test_hmd_context() {
@@ -1,18 +1,33 @@
 #![allow(missing_docs)]
 pub mod audio_context;
+pub mod gamepad_context;
 pub mod gui_context;
 pub mod haptic_context;
+pub mod input_bindings;
 pub mod input_context;
 pub mod physics_context;
 pub mod render_context;
+pub mod simulated_xr_context;
 pub mod vulkan_context;
 pub mod xr_context;
 
 pub use audio_context::AudioContext;
+pub use gamepad_context::{GamepadButton, GamepadContext};
 pub use gui_context::GuiContext;
 pub use haptic_context::HapticContext;
-pub use input_context::InputContext;
+pub use input_bindings::{ControllerInput, EngineAction, InputBinding, InputBindings};
+pub use input_context::{
+    ButtonState, ControllerState, ControllerStatus, GamepadState, InputContext, InputMode,
+};
 pub use physics_context::PhysicsContext;
 pub use render_context::RenderContext;
+pub use simulated_xr_context::{SimulatedController, SimulatedInput, SimulatedXrContext};
 pub use vulkan_context::VulkanContext;
-pub use xr_context::{XrContext, XrContextBuilder};
+pub use xr_context::{
+    BodyJointLocations, BodyTracking, CompositionLayerSettings, CustomAction, CustomActionSet,
+    CustomActionType, DisplayColorSpace, FoveationLevel, FoveationMode, FoveationSettings,
+    HandTracking, KeyboardTracking, LayerFilter, Passthrough, PerformanceDomain, PerformanceLevel,
+    PerformanceNotification, PerformanceNotificationLevel, PerformanceSubDomain, PlayArea,
+    PlayAreaEvent, QuadLayer, ReferenceSpace, RuntimeCapabilities, Scene, SceneLabel,
+    SpatialAnchors, TrackedKeyboard, TrackerRole, XrContext, XrContextBuilder, XrEvent, XrEvents,
+};
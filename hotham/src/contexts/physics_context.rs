@@ -29,6 +29,8 @@ pub struct PhysicsContext {
     pub impulse_joints: ImpulseJointSet,
     pub multibody_joints: MultibodyJointSet,
     pub ccd_solver: CCDSolver,
+    /// Draw every collider as a wireframe, see [`crate::systems::collider_debug_system`]
+    pub debug_render_colliders: bool,
 }
 
 impl Default for PhysicsContext {
@@ -62,6 +64,7 @@ impl Default for PhysicsContext {
             impulse_joints,
             multibody_joints,
             ccd_solver,
+            debug_render_colliders: false,
         }
     }
 }
@@ -14,33 +14,71 @@ pub static CLEAR_VALUES: [vk::ClearValue; 2] = [
     },
 ];
 
-const CULLING_TIMEOUT: u64 = u64::MAX;
-
-#[cfg(target_os = "android")]
-const RESOLVE_ATTACHMENT: u32 = 3;
+/// Used instead of [`CLEAR_VALUES`] with passthrough, where the background must be transparent.
+pub static PASSTHROUGH_CLEAR_VALUES: [vk::ClearValue; 2] = [
+    vk::ClearValue {
+        color: vk::ClearColorValue {
+            float32: [0.0, 0.0, 0.0, 0.0],
+        },
+    },
+    vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+            depth: 0.0,
+            stencil: 0,
+        },
+    },
+];
 
-#[cfg(not(target_os = "android"))]
-const RESOLVE_ATTACHMENT: u32 = 2;
+const CULLING_TIMEOUT: u64 = u64::MAX;
 
 use crate::{
-    contexts::{VulkanContext, XrContext},
+    components::Skybox,
+    contexts::{FoveationMode, VulkanContext, XrContext},
     rendering::{
-        camera::{extract_planes_from_frustum, Camera, Frustum},
+        bloom::{Bloom, BloomSettings},
+        camera::{extract_planes_from_frustum, Camera, Frustum, NEAR_PLANE},
+        color_grading::ColorGradingSettings,
+        color_space::ColorSpace,
+        compute_pass::ComputePass,
+        custom_material::{CustomDraw, CustomPipelines, MAX_CUSTOM_DRAWS},
+        debug_draw::{DebugDraw, DebugDrawPipeline},
+        depth_layer::DepthLayer,
         descriptors::Descriptors,
-        frame::Frame,
+        fog::Fog,
+        frame::{push_draw_data, Frame},
         image::Image,
+        light::{Light, MAX_LIGHTS},
+        light_clustering::{ClusterFrustum, LightClusters, MAX_CLUSTERED_LIGHTS},
         material::Material,
+        motion_vectors::MotionVectors,
+        occlusion_culling::OcclusionCulling,
+        outline::OutlinePipeline,
         primitive::Primitive,
-        resources::Resources,
+        render_mode::RenderMode,
+        render_target::{RenderTarget, RenderTargetView},
+        resources::{DrawData, Resources},
         scene_data::SceneData,
+        shadow::{assign_local_shadow_maps, ShadowCaster, ShadowMap},
+        skinning::Skinning,
+        skybox::SkyboxPipeline,
+        staging::StagingRing,
         swapchain::{Swapchain, SwapchainInfo},
+        text::TextPipeline,
+        texture::Texture,
         vertex::Vertex,
+        video_recorder::{FrameEncoder, VideoRecorder},
+        vignette::Vignette,
     },
-    COLOR_FORMAT, DEPTH_FORMAT, VIEW_COUNT,
+    systems::rendering::draw_primitive,
+    COLOR_FORMAT, DEPTH_STENCIL_FORMAT, VIEW_MASK,
+};
+use anyhow::{anyhow, Result};
+use ash::{
+    prelude::VkResult,
+    vk::{self, Handle},
 };
-use anyhow::Result;
-use ash::vk::{self, Handle};
 use glam::{Affine3A, Mat4, Vec3, Vec4};
+use id_arena::{Arena, Id};
 use openxr as xr;
 use vk_shader_macros::include_glsl;
 
@@ -50,16 +88,99 @@ static COMPUTE: &[u32] = include_glsl!("src/shaders/culling.comp", target: vulka
 
 // TODO: Is this a good idea?
 pub const PIPELINE_DEPTH: usize = 2;
-pub const SAMPLES: vk::SampleCountFlags = vk::SampleCountFlags::TYPE_4;
+pub const DEFAULT_MSAA_SAMPLES: vk::SampleCountFlags = vk::SampleCountFlags::TYPE_4;
+
+/// Settings used to create the `RenderContext`. These can't be changed once the renderer has been created.
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    /// The number of samples used for multisample anti-aliasing (MSAA). Must be `TYPE_1`, `TYPE_2` or `TYPE_4`.
+    /// When set to `TYPE_1` MSAA is disabled and the scene is rendered directly into the OpenXR swapchain.
+    /// If the GPU doesn't support this many samples, the highest count it does support is used instead.
+    pub msaa_samples: vk::SampleCountFlags,
+    /// Cull objects that are hidden behind other objects, using a depth pyramid built from the previous frame.
+    /// This requires the depth buffer to be written out to memory every frame, which isn't free on tiled GPUs,
+    /// so it's only worth enabling for scenes with a lot of occlusion.
+    pub occlusion_culling: bool,
+    /// Submit the depth buffer to the compositor alongside the color image, using `XR_KHR_composition_layer_depth`.
+    /// Runtimes use depth to reproject the frame when the head moves, which reduces judder. Like occlusion
+    /// culling, this requires the depth buffer to be written out to memory every frame. Ignored if the runtime
+    /// doesn't support the extension.
+    pub depth_layer: bool,
+    /// How colors are encoded when they're written to the swapchain. Defaults to [`ColorSpace::Srgb`].
+    pub color_space: ColorSpace,
+    /// Allow frames to be recorded with [`RenderContext::start_recording`]. This creates the swapchain with
+    /// `TRANSFER_SRC` usage, which some runtimes don't support, so it's off by default.
+    pub video_recording: bool,
+    /// Clear to transparent instead of black, so the headset's camera feed shows through wherever the scene
+    /// doesn't cover it. Requires passthrough to be supported, see [`Passthrough`](crate::contexts::xr_context::Passthrough).
+    pub passthrough: bool,
+    /// Render motion vectors every frame and submit them to the compositor with `XR_FB_space_warp`, so it can
+    /// synthesize every other frame. This lets heavy scenes run at half the display's refresh rate. Ignored if the
+    /// runtime doesn't support the extension.
+    pub space_warp: bool,
+    /// How foveated rendering is done. Defaults to [`FoveationMode::Runtime`], which falls back to `Disabled` if the
+    /// runtime doesn't support it.
+    pub foveation: FoveationMode,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            msaa_samples: DEFAULT_MSAA_SAMPLES,
+            occlusion_culling: false,
+            depth_layer: false,
+            color_space: ColorSpace::default(),
+            video_recording: false,
+            passthrough: false,
+            space_warp: false,
+            foveation: FoveationMode::default(),
+        }
+    }
+}
 
 pub struct RenderContext {
     pub frame_index: usize,
+    /// Signalled by every submission to the GPU, with a value one higher than the submission before it.
+    /// Each [`Frame`] records the value that means its commands have completed.
+    pub timeline_semaphore: vk::Semaphore,
+    /// The value signalled by the most recent submission
+    pub(crate) timeline_value: u64,
+    /// Uploads textures without blocking the frame loop
+    pub staging_ring: StagingRing,
     pub pipeline: vk::Pipeline,
+    /// Used for materials with [`MaterialFlags::ALPHA_BLEND`](crate::rendering::material::MaterialFlags) set.
+    /// Blends with whatever is behind it and doesn't write to the depth buffer.
+    pub transparent_pipeline: vk::Pipeline,
+    /// Used for opaque materials with [`MaterialFlags::DOUBLE_SIDED`](crate::rendering::material::MaterialFlags) set.
+    /// Back faces are drawn instead of being culled.
+    pub double_sided_pipeline: vk::Pipeline,
+    /// Used for transparent materials that are also double sided.
+    pub double_sided_transparent_pipeline: vk::Pipeline,
+    /// Used for materials with [`MaterialFlags::ALPHA_MASK`](crate::rendering::material::MaterialFlags) set.
+    /// With MSAA, the material's alpha controls how many samples are covered instead of being cut off.
+    pub alpha_to_coverage_pipeline: vk::Pipeline,
+    /// Used for alpha masked materials that are also double sided.
+    pub double_sided_alpha_to_coverage_pipeline: vk::Pipeline,
+    /// Used for every material when `render_mode` is [`RenderMode::Wireframe`]
+    pub wireframe_pipeline: vk::Pipeline,
+    /// Used for every material when `render_mode` is [`RenderMode::Overdraw`]
+    pub overdraw_pipeline: vk::Pipeline,
     pub compute_pipeline: vk::Pipeline,
     pub pipeline_layout: vk::PipelineLayout,
     pub compute_pipeline_layout: vk::PipelineLayout,
     pub render_pass: vk::RenderPass,
     pub scene_data: SceneData,
+    /// How the world is drawn. Can be changed at any time to diagnose geometry or overdraw problems.
+    pub render_mode: RenderMode,
+    /// Point and spot lights in global space that only light the clusters of the view they touch, as opposed to
+    /// the lights in `scene_data`. Replaced by [`crate::systems::lights_system`] every frame.
+    pub clustered_lights: Vec<Light>,
+    /// Which of the lights in `scene_data` cast shadows, by index. Only point and spot lights can; shadows from
+    /// directional lights are controlled by `shadow_caster`. Set by [`crate::systems::lights_system`] from
+    /// [`CastsShadows`](crate::components::CastsShadows) every frame.
+    pub local_shadow_casters: [bool; MAX_LIGHTS],
+    /// Which of `clustered_lights` touch each cluster, updated by `update_scene_data`
+    pub(crate) light_clusters: LightClusters,
     pub cameras: Vec<Camera>,
     pub views: Vec<xr::View>,
     pub resources: Resources,
@@ -67,8 +188,57 @@ pub struct RenderContext {
     pub swapchain: Swapchain,
     pub descriptors: Descriptors,
     pub shaders: Shaders,
+    /// The settings this renderer was created with
+    pub render_settings: RenderSettings,
+    /// Settings for shadows cast by the primary directional light
+    pub shadow_caster: ShadowCaster,
+    pub shadow_map: ShadowMap,
+    /// Skins every skinned primitive drawn this frame, before any of the passes that draw it
+    pub skinning: Skinning,
+    /// Settings for the glow around emissive materials
+    pub bloom_settings: BloomSettings,
+    /// Created the first time a frame is rendered with bloom enabled
+    pub bloom: Option<Bloom>,
+    /// Color grading, applied after tonemapping. Can be changed at any time.
+    pub color_grading_settings: ColorGradingSettings,
+    /// Distance and height fog. Can be changed at any time.
+    pub fog: Fog,
+    /// The comfort vignette. Can be changed at any time.
+    pub vignette: Vignette,
+    pub skybox_pipeline: SkyboxPipeline,
+    /// Only present if `render_settings.occlusion_culling` is set
+    pub occlusion_culling: Option<OcclusionCulling>,
+    /// Copies the depth buffer into the OpenXR depth swapchain, if depth is submitted to the compositor
+    pub depth_layer: Option<DepthLayer>,
+    /// Renders motion vectors into the SpaceWarp swapchains, if SpaceWarp is enabled
+    pub motion_vectors: Option<MotionVectors>,
+    /// Only present while a recording is in progress, see [`RenderContext::start_recording`]
+    pub video_recorder: Option<VideoRecorder>,
+    /// Pipelines for entities with a [`CustomMaterial`](crate::components::CustomMaterial)
+    pub custom_pipelines: CustomPipelines,
+    /// Lines to draw this frame, for debugging. Cleared at the end of each frame.
+    pub debug_draw: DebugDraw,
+    pub debug_draw_pipeline: DebugDrawPipeline,
+    /// Draws every visible [`Text`](crate::components::Text) component
+    pub text_pipeline: TextPipeline,
+    /// Draws outlines around every visible [`Highlighted`](crate::components::Highlighted) mesh
+    pub outline_pipeline: OutlinePipeline,
+    /// Offscreen images the world can be rendered into, eg. by a [`Camera`](crate::components::Camera)
+    pub render_targets: Arena<RenderTarget>,
+    /// Renders into `render_targets` requested with `schedule_render`, carried out by `rendering::begin`
+    pub(crate) scheduled_renders: Vec<RenderTargetView>,
+    /// Transforms points in global space into globally oriented stage space. Updated by `update_scene_data`.
+    pub(crate) gos_from_global: Affine3A,
+    /// The swapchain image being rendered to this frame
+    pub(crate) swapchain_image_index: usize,
     // Populated only between rendering::begin and rendering::end
     pub primitive_map: HashMap<u32, InstancedPrimitive>,
+    // Populated only between rendering::begin and rendering::end, by passes that don't use the culling results
+    pub(crate) unculled_draws: Vec<UnculledDraw>,
+    // Populated only between rendering::begin and rendering::end
+    pub(crate) skybox: Option<Skybox>,
+    // Populated only between rendering::begin and rendering::end
+    pub(crate) custom_draws: Vec<CustomDraw>,
 }
 
 pub struct Shaders {
@@ -103,13 +273,57 @@ impl Shaders {
 
 impl RenderContext {
     pub fn new(vulkan_context: &VulkanContext, xr_context: &XrContext) -> Result<Self> {
-        println!("[HOTHAM_RENDERER] Creating renderer..");
+        Self::new_with_settings(vulkan_context, xr_context, Default::default())
+    }
+
+    pub fn new_with_settings(
+        vulkan_context: &VulkanContext,
+        xr_context: &XrContext,
+        mut render_settings: RenderSettings,
+    ) -> Result<Self> {
+        // AR headsets show the real world wherever the background is transparent, just like passthrough.
+        if xr_context.environment_blend_mode() != xr::EnvironmentBlendMode::OPAQUE {
+            render_settings.passthrough = true;
+        }
+        println!("[HOTHAM_RENDERER] Creating renderer with {render_settings:?}..");
         let xr_swapchain = &xr_context.swapchain;
         let swapchain_resolution = xr_context.swapchain_resolution;
 
         // Build swapchain
-        let swapchain = SwapchainInfo::from_openxr_swapchain(xr_swapchain, swapchain_resolution)?;
-        Self::new_from_swapchain_info(vulkan_context, &swapchain)
+        let mut swapchain = SwapchainInfo::from_openxr_swapchain(
+            xr_swapchain,
+            swapchain_resolution,
+            xr_context.foveation() == FoveationMode::Runtime,
+        )?;
+        if let Some(depth_swapchain) = &xr_context.depth_swapchain {
+            swapchain.depth_images = depth_swapchain
+                .enumerate_images()?
+                .into_iter()
+                .map(vk::Image::from_raw)
+                .collect();
+        }
+        let mut render_context =
+            Self::new_from_swapchain_info(vulkan_context, &swapchain, render_settings)?;
+
+        if let Some(space_warp) = &xr_context.space_warp {
+            let enumerate_images = |swapchain: &xr::Swapchain<xr::Vulkan>| {
+                swapchain.enumerate_images().map(|images| {
+                    images
+                        .into_iter()
+                        .map(vk::Image::from_raw)
+                        .collect::<Vec<_>>()
+                })
+            };
+            render_context.motion_vectors = Some(MotionVectors::new(
+                vulkan_context,
+                &render_context.descriptors,
+                &enumerate_images(&space_warp.motion_vector_swapchain)?,
+                &enumerate_images(&space_warp.depth_swapchain)?,
+                space_warp.resolution,
+            )?);
+        }
+
+        Ok(render_context)
     }
 
     /// Command buffer of the current frame
@@ -124,13 +338,36 @@ impl RenderContext {
     pub(crate) fn new_from_swapchain_info(
         vulkan_context: &VulkanContext,
         swapchain_info: &SwapchainInfo,
+        mut render_settings: RenderSettings,
     ) -> Result<Self> {
+        let msaa_samples = choose_msaa_samples(
+            render_settings.msaa_samples,
+            vulkan_context.framebuffer_sample_counts(),
+        )?;
+        render_settings.msaa_samples = msaa_samples;
+
         let descriptors = unsafe { Descriptors::new(vulkan_context) };
         let resources = unsafe { Resources::new(vulkan_context, &descriptors) };
 
         // Pipeline, render pass
-        let render_pass = create_render_pass(vulkan_context)?;
-        let swapchain = Swapchain::new(swapchain_info, vulkan_context, render_pass);
+        let store_depth =
+            render_settings.occlusion_culling || !swapchain_info.depth_images.is_empty();
+        let color_format = render_settings.color_space.swapchain_format();
+        let render_pass = create_render_pass(
+            vulkan_context,
+            color_format,
+            msaa_samples,
+            store_depth,
+            swapchain_info.has_fragment_density_map(),
+        )?;
+        let swapchain = Swapchain::new(
+            swapchain_info,
+            vulkan_context,
+            render_pass,
+            color_format,
+            msaa_samples,
+            store_depth,
+        );
         let pipeline_layout =
             create_pipeline_layout(vulkan_context, slice_from_ref(&descriptors.graphics_layout))?;
 
@@ -142,6 +379,78 @@ impl RenderContext {
             &swapchain.render_area,
             render_pass,
             &shaders,
+            msaa_samples,
+            vk::CullModeFlags::BACK,
+        )?;
+        let transparent_pipeline = create_transparent_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+            vk::CullModeFlags::BACK,
+        )?;
+        let double_sided_pipeline = create_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+            vk::CullModeFlags::NONE,
+        )?;
+        let double_sided_transparent_pipeline = create_transparent_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+            vk::CullModeFlags::NONE,
+        )?;
+        let alpha_to_coverage_pipeline = create_alpha_to_coverage_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+            vk::CullModeFlags::BACK,
+        )?;
+        let double_sided_alpha_to_coverage_pipeline = create_alpha_to_coverage_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+            vk::CullModeFlags::NONE,
+        )?;
+        let wireframe_pipeline = create_wireframe_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+        )?;
+        let overdraw_pipeline = create_overdraw_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &swapchain.render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+        )?;
+
+        let shadow_map = ShadowMap::new(vulkan_context, &descriptors)?;
+        let skybox_pipeline = SkyboxPipeline::new(
+            vulkan_context,
+            &descriptors,
+            render_pass,
+            &swapchain.render_area,
+            msaa_samples,
         )?;
 
         let (compute_pipeline, compute_pipeline_layout) = create_compute_pipeline(
@@ -149,6 +458,71 @@ impl RenderContext {
             slice_from_ref(&descriptors.compute_layout),
         );
 
+        // Occlusion culling and the depth layer sample the depth aspect of the depth buffer.
+        let sampled_depth_image = Image {
+            view: swapchain.depth_sample_view,
+            ..swapchain.depth_image.clone()
+        };
+        let occlusion_culling = if render_settings.occlusion_culling {
+            Some(OcclusionCulling::new(
+                vulkan_context,
+                descriptors.compute_layout,
+                &sampled_depth_image,
+                msaa_samples,
+            )?)
+        } else {
+            None
+        };
+
+        let depth_layer = if swapchain_info.depth_images.is_empty() {
+            None
+        } else {
+            Some(DepthLayer::new(
+                vulkan_context,
+                &sampled_depth_image,
+                &swapchain_info.depth_images,
+                msaa_samples,
+            )?)
+        };
+
+        let custom_pipelines = CustomPipelines::new(vulkan_context, descriptors.graphics_layout)?;
+        let debug_draw_pipeline = DebugDrawPipeline::new(
+            vulkan_context,
+            &descriptors,
+            render_pass,
+            &swapchain.render_area,
+            msaa_samples,
+        )?;
+
+        let text_pipeline = TextPipeline::new(
+            vulkan_context,
+            &descriptors,
+            render_pass,
+            &swapchain.render_area,
+            msaa_samples,
+        )?;
+
+        let outline_pipeline = OutlinePipeline::new(
+            vulkan_context,
+            &descriptors,
+            render_pass,
+            &swapchain.render_area,
+            msaa_samples,
+        )?;
+
+        // Every submission signals the next value of the timeline, so frames can be waited on individually.
+        let mut timeline_create_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0);
+        let timeline_semaphore = unsafe {
+            vulkan_context.device.create_semaphore(
+                &vk::SemaphoreCreateInfo::builder().push_next(&mut timeline_create_info),
+                None,
+            )
+        }?;
+
+        let staging_ring = StagingRing::new(vulkan_context)?;
+
         // Create all the per-frame resources we need
         let mut index = 0;
         let frames = [(); PIPELINE_DEPTH].map(|_| {
@@ -158,14 +532,113 @@ impl RenderContext {
             index += 1;
             frame
         });
+        let skinning = Skinning::new(vulkan_context, &resources, &frames)?;
 
         let scene_data = Default::default();
 
+        // Name everything, so that captures in tools like RenderDoc are easy to follow.
+        let debug_names = [
+            (
+                vk::ObjectType::RENDER_PASS,
+                render_pass.as_raw(),
+                "PBR Render Pass",
+            ),
+            (vk::ObjectType::PIPELINE, pipeline.as_raw(), "PBR Pipeline"),
+            (
+                vk::ObjectType::PIPELINE,
+                transparent_pipeline.as_raw(),
+                "PBR Transparent Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                double_sided_pipeline.as_raw(),
+                "PBR Double Sided Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                double_sided_transparent_pipeline.as_raw(),
+                "PBR Double Sided Transparent Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                alpha_to_coverage_pipeline.as_raw(),
+                "PBR Alpha To Coverage Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                double_sided_alpha_to_coverage_pipeline.as_raw(),
+                "PBR Double Sided Alpha To Coverage Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                wireframe_pipeline.as_raw(),
+                "PBR Wireframe Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                overdraw_pipeline.as_raw(),
+                "PBR Overdraw Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                compute_pipeline.as_raw(),
+                "Culling Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                skybox_pipeline.pipeline.as_raw(),
+                "Skybox Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                debug_draw_pipeline.pipeline.as_raw(),
+                "Debug Draw Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                text_pipeline.pipeline.as_raw(),
+                "Text Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                outline_pipeline.stencil_pipeline.as_raw(),
+                "Outline Stencil Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                outline_pipeline.outline_pipeline.as_raw(),
+                "Outline Pipeline",
+            ),
+            (
+                vk::ObjectType::IMAGE,
+                swapchain.depth_image.handle.as_raw(),
+                "Depth Buffer",
+            ),
+            (
+                vk::ObjectType::SEMAPHORE,
+                timeline_semaphore.as_raw(),
+                "Timeline Semaphore",
+            ),
+        ];
+        for (object_type, handle, name) in debug_names {
+            vulkan_context.set_debug_name(object_type, handle, name)?;
+        }
+
         Ok(Self {
             frames,
             frame_index: 0,
+            timeline_semaphore,
+            timeline_value: 0,
+            staging_ring,
             swapchain,
             pipeline,
+            transparent_pipeline,
+            double_sided_pipeline,
+            double_sided_transparent_pipeline,
+            alpha_to_coverage_pipeline,
+            double_sided_alpha_to_coverage_pipeline,
+            wireframe_pipeline,
+            overdraw_pipeline,
             compute_pipeline,
             pipeline_layout,
             compute_pipeline_layout,
@@ -173,10 +646,40 @@ impl RenderContext {
             cameras: vec![Default::default(); 2],
             views: vec![Default::default(); 2],
             scene_data,
+            render_mode: Default::default(),
+            clustered_lights: Vec::new(),
+            local_shadow_casters: [false; MAX_LIGHTS],
+            light_clusters: Default::default(),
             descriptors,
             resources,
             shaders,
+            render_settings,
+            shadow_caster: Default::default(),
+            shadow_map,
+            skinning,
+            bloom_settings: Default::default(),
+            bloom: None,
+            color_grading_settings: Default::default(),
+            fog: Default::default(),
+            vignette: Default::default(),
+            skybox_pipeline,
+            occlusion_culling,
+            depth_layer,
+            motion_vectors: None,
+            video_recorder: None,
+            custom_pipelines,
+            debug_draw: Default::default(),
+            debug_draw_pipeline,
+            text_pipeline,
+            outline_pipeline,
+            render_targets: Arena::new(),
+            scheduled_renders: Vec::new(),
+            gos_from_global: Affine3A::IDENTITY,
+            swapchain_image_index: 0,
             primitive_map: HashMap::default(),
+            unculled_draws: Vec::new(),
+            skybox: None,
+            custom_draws: Vec::new(),
         })
     }
 
@@ -204,11 +707,13 @@ impl RenderContext {
 
         let swapchain = SwapchainInfo {
             images: vec![image.handle],
+            depth_images: Vec::new(),
             resolution,
         };
 
         (
-            RenderContext::new_from_swapchain_info(&vulkan_context, &swapchain).unwrap(),
+            RenderContext::new_from_swapchain_info(&vulkan_context, &swapchain, Default::default())
+                .unwrap(),
             vulkan_context,
         )
     }
@@ -237,11 +742,13 @@ impl RenderContext {
 
         let swapchain = SwapchainInfo {
             images: vec![image.handle],
+            depth_images: Vec::new(),
             resolution,
         };
 
         (
-            RenderContext::new_from_swapchain_info(&vulkan_context, &swapchain).unwrap(),
+            RenderContext::new_from_swapchain_info(&vulkan_context, &swapchain, Default::default())
+                .unwrap(),
             vulkan_context,
             image,
         )
@@ -254,6 +761,7 @@ impl RenderContext {
         gos_from_stage: &Affine3A,
     ) {
         self.views = views.to_owned();
+        self.gos_from_global = *gos_from_global;
 
         // View (camera)
         let view_matrices = &self
@@ -264,7 +772,7 @@ impl RenderContext {
             .collect::<Vec<_>>();
 
         // Projection
-        let near = 0.05;
+        let near = NEAR_PLANE;
 
         let fov_left = views[0].fov;
         let fov_right = views[1].fov;
@@ -279,98 +787,1097 @@ impl RenderContext {
             self.cameras[1].position_in_gos(),
         ];
 
-        let scene_data_buffer = &mut self.frames[self.frame_index].scene_data_buffer;
-        unsafe {
-            let scene_data = &mut scene_data_buffer.as_slice_mut()[0];
-            scene_data.camera_position = self.scene_data.camera_position;
-            scene_data.view_projection = self.scene_data.view_projection;
-            scene_data.params = self.scene_data.params;
-            scene_data.lights = self.scene_data.lights.clone();
-            for light in &mut scene_data.lights {
-                light.position = gos_from_global.transform_point3(light.position);
-                light.direction = gos_from_global.transform_vector3(light.direction);
+        // Shadows - the shadow map follows the player's head around.
+        let shadow_center = (self.scene_data.camera_position[0].truncate()
+            + self.scene_data.camera_position[1].truncate())
+            * 0.5;
+        self.scene_data.shadow_from_gos = self.shadow_caster.shadow_from_gos(shadow_center);
+        self.scene_data.shadow_params = self.shadow_caster.params();
+        self.scene_data.color_grading_params = self.color_grading_settings.params();
+        self.scene_data.params.w = self.render_mode.shader_index();
+        let (fog_params, height_fog_params) = self.fog.params(gos_from_global);
+        self.scene_data.fog_params = fog_params;
+        self.scene_data.height_fog_params = height_fog_params;
+        self.scene_data.vignette_params = self.vignette.params();
+
+        // Lights that don't need to light every fragment are assigned to the clusters of a frustum enclosing both
+        // views, so that each fragment only has to consider the lights that touch its cluster.
+        let cluster_frustum = ClusterFrustum::new(
+            [
+                &self.cameras[0].gos_from_view,
+                &self.cameras[1].gos_from_view,
+            ],
+            [Frustum::from(fov_left), Frustum::from(fov_right)],
+        );
+        if self.clustered_lights.len() > MAX_CLUSTERED_LIGHTS {
+            println!("[HOTHAM_LIGHTS] WARNING: There are more than {MAX_CLUSTERED_LIGHTS} clustered lights, some will be ignored!");
+        }
+        let clustered_lights = self
+            .clustered_lights
+            .iter()
+            .take(MAX_CLUSTERED_LIGHTS)
+            .map(|light| light.transformed(gos_from_global))
+            .collect::<Vec<_>>();
+        self.light_clusters
+            .assign(&cluster_frustum, &clustered_lights);
+        let (cluster_from_gos, cluster_tangents, mut cluster_params) = cluster_frustum.params();
+        cluster_params.z = clustered_lights.len() as f32;
+        self.scene_data.cluster_from_gos = cluster_from_gos;
+        self.scene_data.cluster_tangents = cluster_tangents;
+        self.scene_data.cluster_params = cluster_params;
+
+        let frame = &mut self.frames[self.frame_index];
+        unsafe {
+            frame.clustered_lights_buffer.overwrite(&clustered_lights);
+            frame
+                .light_clusters_buffer
+                .overwrite(&self.light_clusters.clusters);
+            frame
+                .light_indices_buffer
+                .overwrite(&self.light_clusters.light_indices);
+        }
+
+        let scene_data_buffer = &mut self.frames[self.frame_index].scene_data_buffer;
+        unsafe {
+            let scene_data = &mut scene_data_buffer.as_slice_mut()[0];
+            scene_data.camera_position = self.scene_data.camera_position;
+            scene_data.view_projection = self.scene_data.view_projection;
+            scene_data.params = self.scene_data.params;
+            scene_data.shadow_from_gos = self.scene_data.shadow_from_gos;
+            scene_data.shadow_params = self.scene_data.shadow_params;
+            scene_data.decal_params = self.scene_data.decal_params;
+            scene_data.color_grading_params = self.scene_data.color_grading_params;
+            scene_data.fog_params = self.scene_data.fog_params;
+            scene_data.height_fog_params = self.scene_data.height_fog_params;
+            scene_data.vignette_params = self.scene_data.vignette_params;
+            scene_data.cluster_from_gos = self.scene_data.cluster_from_gos;
+            scene_data.cluster_tangents = self.scene_data.cluster_tangents;
+            scene_data.cluster_params = self.scene_data.cluster_params;
+            scene_data.lights = self.scene_data.lights.clone();
+            for light in &mut scene_data.lights {
+                light.position = gos_from_global.transform_point3(light.position);
+                light.direction = gos_from_global.transform_vector3(light.direction);
+            }
+
+            // Now that the lights are in gos, work out which of them get a shadow map.
+            self.shadow_map.local_layers_in_use = assign_local_shadow_maps(
+                &mut scene_data.lights,
+                &self.local_shadow_casters,
+                &mut scene_data.local_shadow_from_gos,
+            );
+            self.scene_data.local_shadow_from_gos = scene_data.local_shadow_from_gos;
+        }
+    }
+
+    /// Create the PBR pipelines drawn into the swapchain from `self.shaders`, eg. after they've been hot reloaded.
+    /// Swap them in with [`RenderContext::replace_shader_pipelines`].
+    pub(crate) fn create_shader_pipelines(
+        &self,
+        vulkan_context: &VulkanContext,
+    ) -> Result<[vk::Pipeline; 8]> {
+        let render_area = self.render_area();
+        let msaa_samples = self.render_settings.msaa_samples;
+        create_pipeline_set(
+            vulkan_context,
+            [
+                &|| {
+                    create_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                        vk::CullModeFlags::BACK,
+                    )
+                },
+                &|| {
+                    create_transparent_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                        vk::CullModeFlags::BACK,
+                    )
+                },
+                &|| {
+                    create_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                        vk::CullModeFlags::NONE,
+                    )
+                },
+                &|| {
+                    create_transparent_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                        vk::CullModeFlags::NONE,
+                    )
+                },
+                &|| {
+                    create_alpha_to_coverage_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                        vk::CullModeFlags::BACK,
+                    )
+                },
+                &|| {
+                    create_alpha_to_coverage_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                        vk::CullModeFlags::NONE,
+                    )
+                },
+                &|| {
+                    create_wireframe_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                    )
+                },
+                &|| {
+                    create_overdraw_pipeline(
+                        vulkan_context,
+                        self.pipeline_layout,
+                        &render_area,
+                        self.render_pass,
+                        &self.shaders,
+                        msaa_samples,
+                    )
+                },
+            ],
+        )
+    }
+
+    /// Draw with `pipelines`, created by [`RenderContext::create_shader_pipelines`], and destroy the ones they
+    /// replace.
+    ///
+    /// # Safety
+    ///
+    /// The GPU must have finished with every command that uses the old pipelines.
+    pub(crate) unsafe fn replace_shader_pipelines(
+        &mut self,
+        device: &ash::Device,
+        pipelines: [vk::Pipeline; 8],
+    ) {
+        let replaced = [
+            self.pipeline,
+            self.transparent_pipeline,
+            self.double_sided_pipeline,
+            self.double_sided_transparent_pipeline,
+            self.alpha_to_coverage_pipeline,
+            self.double_sided_alpha_to_coverage_pipeline,
+            self.wireframe_pipeline,
+            self.overdraw_pipeline,
+        ];
+        [
+            self.pipeline,
+            self.transparent_pipeline,
+            self.double_sided_pipeline,
+            self.double_sided_transparent_pipeline,
+            self.alpha_to_coverage_pipeline,
+            self.double_sided_alpha_to_coverage_pipeline,
+            self.wireframe_pipeline,
+            self.overdraw_pipeline,
+        ] = pipelines;
+        destroy_pipelines(device, &replaced);
+    }
+
+    /// The pipeline every material is drawn with in the current render mode, if it has one.
+    pub(crate) fn render_mode_pipeline(&self) -> Option<vk::Pipeline> {
+        match self.render_mode {
+            RenderMode::Wireframe => Some(self.wireframe_pipeline),
+            RenderMode::Overdraw => Some(self.overdraw_pipeline),
+            RenderMode::Lit | RenderMode::Unlit | RenderMode::Normals => None,
+        }
+    }
+
+    /// Start rendering a frame
+    pub fn begin_frame(&mut self, vulkan_context: &VulkanContext) {
+        // Get the values we need to start the frame..
+        let device = &vulkan_context.device;
+        let frame = &self.frames[self.frame_index];
+
+        // Wait for the GPU to be ready.
+        self.wait(device, frame);
+        let command_buffer = frame.command_buffer;
+
+        // The GPU is done with this frame, so if it was recorded its copy is ready to encode.
+        if let Some(video_recorder) = &mut self.video_recorder {
+            if let Err(e) = video_recorder.encode_completed(vulkan_context, self.frame_index) {
+                println!("[HOTHAM_VIDEO] ERROR: Unable to encode frame, stopping recording: {e:?}");
+                video_recorder.stop();
+            }
+        }
+
+        unsafe {
+            device
+                .begin_command_buffer(
+                    command_buffer,
+                    &vk::CommandBufferBeginInfo::builder()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .unwrap();
+        }
+    }
+
+    /// Dispatch a user supplied [`ComputePass`] in the current frame, with `group_count` workgroups and the given
+    /// push constants (eg. from [`create_push_constant`]). Barriers are inserted so that its results can be read by
+    /// any draw or dispatch recorded after it.
+    ///
+    /// Must be called between `begin_frame` and `begin_pbr_render_pass`, eg. from a system that runs before the
+    /// rendering system.
+    pub fn dispatch_compute(
+        &self,
+        vulkan_context: &VulkanContext,
+        compute_pass: &ComputePass,
+        group_count: [u32; 3],
+        push_constants: &[u8],
+    ) {
+        vulkan_context.begin_debug_label(self.cmd(), "Compute Pass");
+        unsafe {
+            compute_pass.record(
+                &vulkan_context.device,
+                self.cmd(),
+                group_count,
+                push_constants,
+            );
+        }
+        vulkan_context.end_debug_label(self.cmd());
+    }
+
+    pub fn cull_objects(&mut self, vulkan_context: &VulkanContext) {
+        let device = &vulkan_context.device;
+        let frame_index = self.frame_index;
+        let frame = &mut self.frames[self.frame_index];
+        let primitive_cull_buffer = &mut frame.primitive_cull_data_buffer;
+        let command_buffer = frame.compute_command_buffer;
+
+        // Create the cull parameters to pass to the compute shader
+        let cull_params = CullParams::new(
+            &self.scene_data.view_projection,
+            primitive_cull_buffer.len(),
+        );
+
+        unsafe {
+            frame.cull_params_buffer.overwrite(&[cull_params]);
+        }
+
+        let group_count_x = (primitive_cull_buffer.len() / 1024) + 1;
+
+        unsafe {
+            device
+                .begin_command_buffer(
+                    command_buffer,
+                    &vk::CommandBufferBeginInfo::builder()
+                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+                )
+                .unwrap();
+            vulkan_context.begin_debug_label(command_buffer, "Culling");
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline,
+            );
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline_layout,
+                0,
+                slice_from_ref(&self.descriptors.compute_sets[frame_index]),
+                &[],
+            );
+            device.cmd_dispatch(command_buffer, group_count_x as u32, 1, 1);
+            if let Some(occlusion_culling) = &mut self.occlusion_culling {
+                occlusion_culling.cull(
+                    device,
+                    command_buffer,
+                    self.descriptors.compute_sets[frame_index],
+                    primitive_cull_buffer.len(),
+                );
+            }
+            vulkan_context.end_debug_label(command_buffer);
+            device.end_command_buffer(command_buffer).unwrap();
+        }
+
+        // The results are read back on the CPU straight away, so wait for them.
+        let culling_complete = self.submit(vulkan_context, command_buffer).unwrap();
+        self.wait_for_timeline_value(device, culling_complete, CULLING_TIMEOUT)
+            .unwrap_or_else(|e| panic!("@@@ TIMEOUT WAITING FOR CULLING SHADER - {e:?} @@@"));
+    }
+
+    /// Skin every primitive queued with the skinning pre-pass this frame, see [`Skinning`].
+    ///
+    /// DOES NOT BEGIN RECORDING COMMAND BUFFERS - call begin_frame first!
+    pub fn skin_primitives(&mut self, vulkan_context: &VulkanContext) {
+        let command_buffer = self.frames[self.frame_index].command_buffer;
+        vulkan_context.begin_debug_label(command_buffer, "Skinning");
+        unsafe {
+            self.skinning
+                .dispatch(&vulkan_context.device, command_buffer, self.frame_index);
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
+
+    /// Render the shadow maps for the primary directional light and any point or spot lights that cast shadows.
+    /// Every instance in `primitive_map` is drawn, as objects outside the view can still cast shadows into it.
+    ///
+    /// Draw data for the shadow casters is written to this frame's draw data buffer, see `write_unculled_draw_data`.
+    /// DOES NOT BEGIN RECORDING COMMAND BUFFERS - call begin_frame first!
+    pub fn render_shadow_maps(&mut self, vulkan_context: &VulkanContext) {
+        let shadow_map = &self.shadow_map;
+        let local_layers = shadow_map.local_layers_in_use;
+        if !self.shadow_caster.enabled && local_layers == 0 {
+            return;
+        }
+
+        let device = &vulkan_context.device;
+
+        // Work out which passes we need to render.
+        let mut passes = Vec::with_capacity(local_layers + 1);
+        if self.shadow_caster.enabled {
+            passes.push((
+                shadow_map.framebuffer,
+                shadow_map.image.extent,
+                self.scene_data.shadow_from_gos,
+            ));
+        }
+        for layer in 0..local_layers {
+            passes.push((
+                shadow_map.local_framebuffers[layer],
+                shadow_map.local_image.extent,
+                self.scene_data.local_shadow_from_gos[layer],
+            ));
+        }
+
+        // Write the draw data for every instance once; it's shared between all the passes.
+        self.write_unculled_draw_data();
+        let shadow_map = &self.shadow_map;
+        let command_buffer = self.frames[self.frame_index].command_buffer;
+
+        vulkan_context.begin_debug_label(command_buffer, "Shadow Maps");
+        unsafe {
+            // Bound state persists between render passes, so we only need to do this once.
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                shadow_map.pipeline,
+            );
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                shadow_map.pipeline_layout,
+                0,
+                slice_from_ref(&self.descriptors.sets[self.frame_index]),
+                &[],
+            );
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                self.resources.index_buffer.buffer,
+                0,
+                vk::IndexType::UINT32,
+            );
+            device.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                &[
+                    self.resources.position_buffer.buffer,
+                    self.resources.vertex_buffer.buffer,
+                ],
+                &[0, 0],
+            );
+
+            for (framebuffer, extent, shadow_from_gos) in passes {
+                shadow_map.begin_render_pass(device, command_buffer, framebuffer, extent);
+                device.cmd_push_constants(
+                    command_buffer,
+                    shadow_map.pipeline_layout,
+                    vk::ShaderStageFlags::VERTEX,
+                    0,
+                    create_push_constant(&shadow_from_gos),
+                );
+
+                for draw in &self.unculled_draws {
+                    let primitive = &self.primitive_map[&draw.primitive_id].primitive;
+                    device.cmd_draw_indexed(
+                        command_buffer,
+                        primitive.indices_count,
+                        draw.instance_count,
+                        primitive.index_buffer_offset,
+                        primitive.vertex_buffer_offset as _,
+                        draw.instance_offset,
+                    );
+                }
+
+                device.cmd_end_render_pass(command_buffer);
+            }
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
+
+    /// Render the motion of every instance in `primitive_map` since the last frame, along with its depth, into the
+    /// SpaceWarp swapchain images at `swapchain_image_index`. Does nothing unless SpaceWarp is enabled.
+    ///
+    /// Draw data is shared with the shadow maps, see `write_unculled_draw_data`. Each instance is drawn on its own,
+    /// as its previous transform is passed as a push constant.
+    /// DOES NOT BEGIN RECORDING COMMAND BUFFERS - call begin_frame first!
+    pub fn render_motion_vectors(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        swapchain_image_index: usize,
+    ) {
+        if self.motion_vectors.is_none() {
+            return;
+        }
+
+        self.write_unculled_draw_data();
+        let motion_vectors = self.motion_vectors.as_ref().unwrap();
+        let device = &vulkan_context.device;
+        let command_buffer = self.frames[self.frame_index].command_buffer;
+
+        vulkan_context.begin_debug_label(command_buffer, "Motion Vectors");
+        unsafe {
+            motion_vectors.begin_render_pass(device, command_buffer, swapchain_image_index);
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                motion_vectors.pipeline,
+            );
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                motion_vectors.pipeline_layout,
+                0,
+                slice_from_ref(&self.descriptors.sets[self.frame_index]),
+                &[],
+            );
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                self.resources.index_buffer.buffer,
+                0,
+                vk::IndexType::UINT32,
+            );
+            device.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                &[
+                    self.resources.position_buffer.buffer,
+                    self.resources.vertex_buffer.buffer,
+                ],
+                &[0, 0],
+            );
+
+            for draw in &self.unculled_draws {
+                let instanced_primitive = &self.primitive_map[&draw.primitive_id];
+                let primitive = &instanced_primitive.primitive;
+                for (instance_offset, instance) in
+                    (draw.instance_offset..).zip(&instanced_primitive.instances)
+                {
+                    let previous_gos_from_local = Mat4::from(instance.previous_gos_from_local);
+                    device.cmd_push_constants(
+                        command_buffer,
+                        motion_vectors.pipeline_layout,
+                        vk::ShaderStageFlags::VERTEX,
+                        0,
+                        create_push_constant(&previous_gos_from_local),
+                    );
+                    device.cmd_draw_indexed(
+                        command_buffer,
+                        primitive.indices_count,
+                        1,
+                        primitive.index_buffer_offset,
+                        primitive.vertex_buffer_offset as _,
+                        instance_offset,
+                    );
+                }
+            }
+
+            device.cmd_end_render_pass(command_buffer);
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
+
+    /// Render the emission of the scene and blur it, ready to be added on top of the scene by `composite_bloom`.
+    /// Does nothing unless `bloom_settings.enabled` is set.
+    ///
+    /// DOES NOT BEGIN RECORDING COMMAND BUFFERS - call begin_frame first!
+    pub fn render_bloom(&mut self, vulkan_context: &VulkanContext) {
+        if !self.bloom_settings.enabled {
+            return;
+        }
+
+        if self.bloom.is_none() {
+            println!("[HOTHAM_RENDERER] Creating bloom resources..");
+            match Bloom::new(
+                vulkan_context,
+                self.pipeline_layout,
+                self.render_pass,
+                self.swapchain.render_area,
+                &self.shaders.vertex_shader,
+                self.render_settings.msaa_samples,
+            ) {
+                Ok(bloom) => self.bloom = Some(bloom),
+                Err(e) => {
+                    println!("[HOTHAM_RENDERER] ERROR: Unable to create bloom resources, disabling bloom: {e:?}");
+                    self.bloom_settings.enabled = false;
+                    return;
+                }
+            }
+        }
+
+        // The bloom pass sees the same things as the cameras, so only draw what survived culling.
+        let draws = self.write_visible_draw_data();
+
+        let device = &vulkan_context.device;
+        let bloom = self.bloom.as_ref().unwrap();
+        let command_buffer = self.frames[self.frame_index].command_buffer;
+
+        vulkan_context.begin_debug_label(command_buffer, "Bloom");
+        unsafe {
+            bloom.begin_render_pass(device, command_buffer);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                slice_from_ref(&self.descriptors.sets[self.frame_index]),
+                &[],
+            );
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                self.resources.index_buffer.buffer,
+                0,
+                vk::IndexType::UINT32,
+            );
+            device.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                &[
+                    self.resources.position_buffer.buffer,
+                    self.resources.vertex_buffer.buffer,
+                ],
+                &[0, 0],
+            );
+
+            for draw in &draws {
+                draw_primitive(
+                    &self.resources.materials_buffer,
+                    self.pipeline_layout,
+                    &self.primitive_map[&draw.primitive_id].primitive,
+                    device,
+                    command_buffer,
+                    draw.instance_count,
+                    draw.instance_offset,
+                );
+            }
+
+            device.cmd_end_render_pass(command_buffer);
+            bloom.blur(device, command_buffer, &self.bloom_settings);
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
+
+    /// Create a [`RenderTarget`] of `resolution` pixels that the world can be rendered into with
+    /// [`Self::schedule_render`]. Its texture can be found with [`Self::render_target_texture`].
+    pub fn create_render_target(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        name: &str,
+        resolution: vk::Extent2D,
+    ) -> Result<Id<RenderTarget>> {
+        let render_target = RenderTarget::new(name, vulkan_context, self, resolution)?;
+        Ok(self.render_targets.alloc(render_target))
+    }
+
+    /// The texture a render target is rendered into, if it exists. Its `index` can be used in a
+    /// [`Material`], for example with [`Material::unlit_texture`].
+    pub fn render_target_texture(&self, render_target: Id<RenderTarget>) -> Option<&Texture> {
+        self.render_targets
+            .get(render_target)
+            .map(|render_target| &render_target.texture)
+    }
+
+    /// Render the world from `view` into its render target during the next call to
+    /// [`rendering::begin`](crate::systems::rendering::begin). The render only happens once, so schedule it every
+    /// frame to keep the render target up to date.
+    pub fn schedule_render(&mut self, view: RenderTargetView) {
+        self.scheduled_renders.push(view);
+    }
+
+    /// Render the world into a [`RenderTarget`] from each of `views`, ready to be sampled by the PBR render pass.
+    /// Every instance in `primitive_map` is tested against each view on the CPU, as the culling shader only knows
+    /// about the main cameras.
+    ///
+    /// DOES NOT BEGIN RECORDING COMMAND BUFFERS - call begin_frame first!
+    pub fn render_to_targets(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        views: &[RenderTargetView],
+    ) {
+        if views.is_empty() {
+            return;
+        }
+
+        let device = &vulkan_context.device;
+        let frame_index = self.frame_index;
+        let frame = &self.frames[frame_index];
+        let command_buffer = frame.command_buffer;
+        let materials = unsafe { self.resources.materials_buffer.as_slice() };
+
+        // Each view sees the same lights, shadows and fog as the main cameras.
+        let mut scene_data = unsafe { frame.scene_data_buffer.as_slice()[0].clone() };
+
+        vulkan_context.begin_debug_label(command_buffer, "Render Targets");
+        for view in views {
+            let render_target = match self.render_targets.get_mut(view.render_target) {
+                Some(render_target) => render_target,
+                None => {
+                    println!(
+                        "[HOTHAM_RENDERER] WARNING: Render target {:?} doesn't exist, skipping..",
+                        view.render_target
+                    );
+                    continue;
+                }
+            };
+
+            // Render targets are tonemapped when they're drawn in the world, so they're rendered in linear space.
+            let aspect_ratio = render_target.aspect_ratio();
+            let gos_from_global = self.gos_from_global;
+            let view_projection = view.view_projection(&gos_from_global, aspect_ratio);
+            scene_data.view_projection = [view_projection; 2];
+            scene_data.camera_position = [view.position_in_gos(&gos_from_global); 2];
+            scene_data.params.y = 1.;
+            scene_data.params.w = RenderMode::Lit.shader_index();
+            unsafe {
+                render_target.update(
+                    vulkan_context,
+                    &self.descriptors,
+                    &self.resources,
+                    frame_index,
+                    &scene_data,
+                );
+            }
+
+            // Sort the instances that can be seen into opaque draws and transparent instances.
+            let clip_planes = view.clip_planes(&gos_from_global, aspect_ratio);
+            let eye_position = view.position_in_gos(&gos_from_global).truncate();
+            let mut opaque_draws = Vec::new();
+            let mut transparent_instances = Vec::new();
+            for instanced_primitive in self.primitive_map.values() {
+                let primitive = &instanced_primitive.primitive;
+                let material = &materials[primitive.material_id as usize];
+
+                // A surface can't show the render target while it's being rendered into.
+                if material.uses_texture(render_target.texture.index) {
+                    continue;
+                }
+
+                let draw_data_buffer = &mut render_target.draw_data_buffers[frame_index];
+                let instance_offset = draw_data_buffer.len() as u32;
+                let mut instance_count = 0;
+                for instance in &instanced_primitive.instances {
+                    if !view.is_visible(&gos_from_global, &clip_planes, instance.bounding_sphere) {
+                        continue;
+                    }
+                    if material.is_transparent() {
+                        let distance_squared = instance
+                            .bounding_sphere
+                            .truncate()
+                            .distance_squared(eye_position);
+                        transparent_instances.push((distance_squared, primitive, instance));
+                        continue;
+                    }
+                    let draw_data = DrawData::new(
+                        &instance.gos_from_local,
+                        instance.skin_id,
+                        instance.morph_weights_id,
+                        primitive,
+                    );
+                    if push_draw_data(draw_data_buffer, &draw_data).is_some() {
+                        instance_count += 1;
+                    }
+                }
+
+                if instance_count > 0 {
+                    opaque_draws.push((primitive, instance_count, instance_offset));
+                }
+            }
+            transparent_instances.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+            unsafe {
+                render_target.begin_render_pass(
+                    device,
+                    command_buffer,
+                    self.pipeline_layout,
+                    frame_index,
+                );
+                device.cmd_bind_index_buffer(
+                    command_buffer,
+                    self.resources.index_buffer.buffer,
+                    0,
+                    vk::IndexType::UINT32,
+                );
+                device.cmd_bind_vertex_buffers(
+                    command_buffer,
+                    0,
+                    &[
+                        self.resources.position_buffer.buffer,
+                        self.resources.vertex_buffer.buffer,
+                    ],
+                    &[0, 0],
+                );
+
+                for (primitive, instance_count, instance_offset) in opaque_draws {
+                    let material = &materials[primitive.material_id as usize];
+                    let pipeline = if material.is_double_sided() {
+                        render_target.double_sided_pipeline
+                    } else {
+                        render_target.pipeline
+                    };
+                    device.cmd_bind_pipeline(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        pipeline,
+                    );
+                    draw_primitive(
+                        &self.resources.materials_buffer,
+                        self.pipeline_layout,
+                        primitive,
+                        device,
+                        command_buffer,
+                        instance_count,
+                        instance_offset,
+                    );
+                }
+
+                if let Some(skybox) = &self.skybox {
+                    render_target.skybox_pipeline.draw(
+                        device,
+                        command_buffer,
+                        render_target.descriptor_sets[frame_index],
+                        skybox,
+                    );
+
+                    // The skybox pipeline has a different layout, so the descriptor sets need to be bound again.
+                    device.cmd_bind_descriptor_sets(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        self.pipeline_layout,
+                        0,
+                        slice_from_ref(&render_target.descriptor_sets[frame_index]),
+                        &[],
+                    );
+                }
+
+                for (_, primitive, instance) in transparent_instances {
+                    let draw_data = DrawData::new(
+                        &instance.gos_from_local,
+                        instance.skin_id,
+                        instance.morph_weights_id,
+                        primitive,
+                    );
+                    let instance_offset = match push_draw_data(
+                        &mut render_target.draw_data_buffers[frame_index],
+                        &draw_data,
+                    ) {
+                        Some(instance_offset) => instance_offset,
+                        None => break,
+                    };
+
+                    let material = &materials[primitive.material_id as usize];
+                    let pipeline = if material.is_double_sided() {
+                        render_target.double_sided_transparent_pipeline
+                    } else {
+                        render_target.transparent_pipeline
+                    };
+                    device.cmd_bind_pipeline(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        pipeline,
+                    );
+                    draw_primitive(
+                        &self.resources.materials_buffer,
+                        self.pipeline_layout,
+                        primitive,
+                        device,
+                        command_buffer,
+                        1,
+                        instance_offset,
+                    );
+                }
+
+                device.cmd_end_render_pass(command_buffer);
+            }
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
+
+    /// Draw the skybox found by `rendering::begin`, if any, behind everything drawn so far.
+    /// Must be called inside the PBR render pass.
+    pub fn draw_skybox(&self, vulkan_context: &VulkanContext) {
+        if let Some(skybox) = &self.skybox {
+            vulkan_context.begin_debug_label(self.cmd(), "Skybox");
+            unsafe {
+                self.skybox_pipeline.draw(
+                    &vulkan_context.device,
+                    self.cmd(),
+                    self.descriptors.sets[self.frame_index],
+                    skybox,
+                );
             }
+            vulkan_context.end_debug_label(self.cmd());
         }
     }
 
-    /// Start rendering a frame
-    pub fn begin_frame(&self, vulkan_context: &VulkanContext) {
-        // Get the values we need to start the frame..
-        let device = &vulkan_context.device;
-        let frame = &self.frames[self.frame_index];
+    /// Draw the lines in `debug_draw`, tested against everything drawn so far.
+    /// Must be called inside the PBR render pass.
+    pub fn draw_debug_lines(&mut self, vulkan_context: &VulkanContext) {
+        let command_buffer = self.cmd();
+        vulkan_context.begin_debug_label(command_buffer, "Debug Lines");
+        unsafe {
+            self.debug_draw_pipeline.draw(
+                &vulkan_context.device,
+                command_buffer,
+                self.frame_index,
+                self.descriptors.sets[self.frame_index],
+                &self.gos_from_global,
+                &self.debug_draw,
+            );
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
 
-        // Wait for the GPU to be ready.
-        self.wait(device, frame);
+    /// Draw the text queued by the rendering system, blended over everything drawn so far.
+    /// Must be called inside the PBR render pass.
+    pub fn draw_text(&mut self, vulkan_context: &VulkanContext) {
+        let command_buffer = self.cmd();
+        vulkan_context.begin_debug_label(command_buffer, "Text");
+        unsafe {
+            self.text_pipeline.draw(
+                &vulkan_context.device,
+                command_buffer,
+                self.frame_index,
+                self.descriptors.sets[self.frame_index],
+            );
+        }
+        vulkan_context.end_debug_label(command_buffer);
+    }
 
+    /// Draw outlines around the highlighted meshes queued by the rendering system, over everything drawn so far.
+    /// Must be called inside the PBR render pass.
+    pub fn draw_outlines(&mut self, vulkan_context: &VulkanContext) {
+        let device = &vulkan_context.device;
+        let frame = &mut self.frames[self.frame_index];
         let command_buffer = frame.command_buffer;
+        vulkan_context.begin_debug_label(command_buffer, "Outlines");
         unsafe {
-            device
-                .begin_command_buffer(
-                    command_buffer,
-                    &vk::CommandBufferBeginInfo::builder()
-                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
-                )
-                .unwrap();
+            self.outline_pipeline.draw(
+                device,
+                command_buffer,
+                self.descriptors.sets[self.frame_index],
+                &self.resources,
+                &mut frame.draw_data_buffer,
+            );
         }
+        vulkan_context.end_debug_label(command_buffer);
     }
 
-    pub fn cull_objects(&mut self, vulkan_context: &VulkanContext) {
-        let device = &vulkan_context.device;
-        let frame_index = self.frame_index;
+    /// Add the bloom rendered by `render_bloom` on top of the scene.
+    /// Must be called inside the PBR render pass, after everything that should receive bloom has been drawn.
+    pub fn composite_bloom(&self, vulkan_context: &VulkanContext) {
+        if !self.bloom_settings.enabled {
+            return;
+        }
+
+        if let Some(bloom) = &self.bloom {
+            vulkan_context.begin_debug_label(self.cmd(), "Bloom Composite");
+            unsafe {
+                bloom.composite(&vulkan_context.device, self.cmd(), &self.bloom_settings);
+            }
+            vulkan_context.end_debug_label(self.cmd());
+        }
+    }
+
+    /// Write draw data for every instance in `primitive_map` into this frame's draw data buffer, ignoring the
+    /// results of culling. This only happens once per frame, no matter how many passes need it.
+    fn write_unculled_draw_data(&mut self) {
+        if !self.unculled_draws.is_empty() {
+            return;
+        }
+
         let frame = &mut self.frames[self.frame_index];
-        let primitive_cull_buffer = &mut frame.primitive_cull_data_buffer;
-        let command_buffer = frame.compute_command_buffer;
-        let fence = frame.compute_fence;
+        for (primitive_id, instanced_primitive) in &self.primitive_map {
+            let instance_offset = frame.draw_data_buffer.len() as u32;
+            let instance_count = instanced_primitive
+                .instances
+                .iter()
+                .take_while(|instance| {
+                    push_draw_data(
+                        &mut frame.draw_data_buffer,
+                        &DrawData::new(
+                            &instance.gos_from_local,
+                            instance.skin_id,
+                            instance.morph_weights_id,
+                            &instanced_primitive.primitive,
+                        ),
+                    )
+                    .is_some()
+                })
+                .count() as u32;
+            if instance_count > 0 {
+                self.unculled_draws.push(UnculledDraw {
+                    primitive_id: *primitive_id,
+                    instance_count,
+                    instance_offset,
+                });
+            }
+        }
+    }
 
-        // Create the cull parameters to pass to the compute shader
-        let cull_params = CullParams::new(
-            &self.scene_data.view_projection,
-            primitive_cull_buffer.len(),
-        );
+    /// Write draw data for every instance that survived this frame's culling, returning a draw for each primitive
+    /// with visible instances. Must be called after `cull_objects`.
+    fn write_visible_draw_data(&mut self) -> Vec<UnculledDraw> {
+        let frame = &mut self.frames[self.frame_index];
+        let mut draws: Vec<UnculledDraw> = Vec::new();
+        let cull_data = unsafe { frame.primitive_cull_data_buffer.as_slice() };
+        for cull_result in cull_data {
+            if !cull_result.visible {
+                continue;
+            }
 
-        unsafe {
-            frame.cull_params_buffer.overwrite(&[cull_params]);
+            let instanced_primitive = &self.primitive_map[&cull_result.primitive_id];
+            let instance = &instanced_primitive.instances[cull_result.index_instance as usize];
+            let draw_data = DrawData::new(
+                &instance.gos_from_local,
+                instance.skin_id,
+                instance.morph_weights_id,
+                &instanced_primitive.primitive,
+            );
+            let instance_offset = match push_draw_data(&mut frame.draw_data_buffer, &draw_data) {
+                Some(instance_offset) => instance_offset,
+                None => break,
+            };
+
+            // The cull buffer is grouped by primitive, so visible instances of the same primitive are contiguous.
+            match draws.last_mut() {
+                Some(draw) if draw.primitive_id == cull_result.primitive_id => {
+                    draw.instance_count += 1
+                }
+                _ => draws.push(UnculledDraw {
+                    primitive_id: cull_result.primitive_id,
+                    instance_count: 1,
+                    instance_offset,
+                }),
+            }
         }
+        draws
+    }
 
-        let group_count_x = (primitive_cull_buffer.len() / 1024) + 1;
+    /// Draw every primitive with a [`CustomMaterial`](crate::components::CustomMaterial), creating pipelines for
+    /// any shaders that haven't been drawn before.
+    ///
+    /// Must be called inside the PBR render pass.
+    pub fn draw_custom_materials(&mut self, vulkan_context: &VulkanContext) {
+        let frame_index = self.frame_index;
+        let custom_pipelines = &mut self.custom_pipelines;
+        custom_pipelines.clear_uniforms(frame_index);
+        if self.custom_draws.is_empty() {
+            return;
+        }
 
-        unsafe {
-            device
-                .begin_command_buffer(
+        if self.custom_draws.len() > MAX_CUSTOM_DRAWS {
+            println!("[HOTHAM_RENDERER] WARNING: There are more than {MAX_CUSTOM_DRAWS} primitives with custom materials, some will not be drawn!");
+        }
+
+        let device = &vulkan_context.device;
+        let frame = &mut self.frames[frame_index];
+        let command_buffer = frame.command_buffer;
+        let descriptor_sets = [
+            self.descriptors.sets[frame_index],
+            custom_pipelines.descriptor_sets[frame_index],
+        ];
+        let mut bound_pipeline = vk::Pipeline::null();
+
+        vulkan_context.begin_debug_label(command_buffer, "Custom Materials");
+        for draw in self.custom_draws.iter().take(MAX_CUSTOM_DRAWS) {
+            let pipeline = match custom_pipelines.get_or_create_pipeline(
+                vulkan_context,
+                &draw.material,
+                self.render_pass,
+                &self.swapchain.render_area,
+                self.render_settings.msaa_samples,
+            ) {
+                Ok(pipeline) => pipeline,
+                Err(e) => {
+                    println!("[HOTHAM_RENDERER] ERROR: Unable to create pipeline for custom material: {e:?}");
+                    continue;
+                }
+            };
+
+            unsafe {
+                let instance_offset = match push_draw_data(
+                    &mut frame.draw_data_buffer,
+                    &DrawData::new(
+                        &draw.gos_from_local,
+                        draw.skin_id,
+                        draw.morph_weights_id,
+                        &draw.primitive,
+                    ),
+                ) {
+                    Some(instance_offset) => instance_offset,
+                    None => break,
+                };
+                let uniforms_offset =
+                    custom_pipelines.write_uniforms(frame_index, &draw.material.uniforms);
+
+                if pipeline != bound_pipeline {
+                    device.cmd_bind_pipeline(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        pipeline,
+                    );
+                    bound_pipeline = pipeline;
+                }
+                device.cmd_bind_descriptor_sets(
                     command_buffer,
-                    &vk::CommandBufferBeginInfo::builder()
-                        .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
-                )
-                .unwrap();
-            device.cmd_bind_pipeline(
-                command_buffer,
-                vk::PipelineBindPoint::COMPUTE,
-                self.compute_pipeline,
-            );
-            device.cmd_bind_descriptor_sets(
-                command_buffer,
-                vk::PipelineBindPoint::COMPUTE,
-                self.compute_pipeline_layout,
-                0,
-                slice_from_ref(&self.descriptors.compute_sets[frame_index]),
-                &[],
-            );
-            device.cmd_dispatch(command_buffer, group_count_x as u32, 1, 1);
-            device.end_command_buffer(command_buffer).unwrap();
-            let submit_info =
-                vk::SubmitInfo::builder().command_buffers(slice_from_ref(&command_buffer));
-            device
-                .queue_submit(
-                    vulkan_context.graphics_queue,
-                    slice_from_ref(&submit_info),
-                    fence,
-                )
-                .unwrap();
-            device
-                .wait_for_fences(slice_from_ref(&fence), true, CULLING_TIMEOUT)
-                .unwrap_or_else(|e| panic!("@@@ TIMEOUT WAITING FOR CULLING SHADER - {e:?} @@@"));
-            device.reset_fences(slice_from_ref(&fence)).unwrap();
+                    vk::PipelineBindPoint::GRAPHICS,
+                    custom_pipelines.pipeline_layout,
+                    0,
+                    &descriptor_sets,
+                    slice_from_ref(&uniforms_offset),
+                );
+                device.cmd_draw_indexed(
+                    command_buffer,
+                    draw.primitive.indices_count,
+                    1,
+                    draw.primitive.index_buffer_offset,
+                    draw.primitive.vertex_buffer_offset as _,
+                    instance_offset,
+                );
+            }
         }
+        vulkan_context.end_debug_label(command_buffer);
     }
 
     /// Begin the PBR renderpass.
@@ -385,14 +1892,21 @@ impl RenderContext {
         let frame = &self.frames[self.frame_index];
         let command_buffer = frame.command_buffer;
         let framebuffer = self.swapchain.framebuffers[swapchain_image_index];
+        self.swapchain_image_index = swapchain_image_index;
 
         // Begin the renderpass.
         let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
             .render_pass(self.render_pass)
             .framebuffer(framebuffer)
             .render_area(self.swapchain.render_area)
-            .clear_values(&CLEAR_VALUES);
+            .clear_values(if self.render_settings.passthrough {
+                &PASSTHROUGH_CLEAR_VALUES
+            } else {
+                &CLEAR_VALUES
+            });
 
+        // Closed by `end_pbr_render_pass`
+        vulkan_context.begin_debug_label(command_buffer, "PBR");
         unsafe {
             device.cmd_begin_render_pass(
                 command_buffer,
@@ -437,6 +1951,99 @@ impl RenderContext {
         unsafe {
             device.cmd_end_render_pass(command_buffer);
         }
+        vulkan_context.end_debug_label(command_buffer);
+
+        // The depth buffer is complete, so build the depth pyramid the next frame will be culled against.
+        if let Some(occlusion_culling) = &mut self.occlusion_culling {
+            vulkan_context.begin_debug_label(command_buffer, "Depth Pyramid");
+            unsafe {
+                occlusion_culling.build_pyramid(
+                    device,
+                    command_buffer,
+                    self.scene_data.view_projection,
+                );
+            }
+            vulkan_context.end_debug_label(command_buffer);
+        }
+
+        // Copy the depth buffer into the depth swapchain, ready to be submitted to the compositor.
+        if let Some(depth_layer) = &self.depth_layer {
+            vulkan_context.begin_debug_label(command_buffer, "Depth Layer");
+            unsafe {
+                depth_layer.resolve(
+                    device,
+                    command_buffer,
+                    self.swapchain_image_index,
+                    self.occlusion_culling.is_some(),
+                );
+            }
+            vulkan_context.end_debug_label(command_buffer);
+        }
+
+        // Copy the left eye out of the swapchain, if we're recording.
+        if let Some(video_recorder) = &mut self.video_recorder {
+            vulkan_context.begin_debug_label(command_buffer, "Video Recording");
+            unsafe {
+                video_recorder.record_copy(
+                    device,
+                    command_buffer,
+                    self.swapchain.images[self.swapchain_image_index],
+                    self.frame_index,
+                );
+            }
+            vulkan_context.end_debug_label(command_buffer);
+        }
+    }
+
+    /// Start recording every frame rendered into the swapchain, passing the left eye to `encoder`.
+    ///
+    /// Requires [`RenderSettings::video_recording`] to be set, and returns an error if a recording is already in
+    /// progress.
+    pub fn start_recording(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        encoder: Box<dyn FrameEncoder>,
+    ) -> Result<()> {
+        if !self.render_settings.video_recording {
+            return Err(anyhow!(
+                "Video recording requires RenderSettings::video_recording to be set"
+            ));
+        }
+        if self.video_recorder.is_some() {
+            return Err(anyhow!("A recording is already in progress"));
+        }
+
+        self.video_recorder = Some(VideoRecorder::new(
+            vulkan_context,
+            self.swapchain.resolution,
+            encoder,
+        ));
+        Ok(())
+    }
+
+    /// Stop recording. The current frame isn't recorded, and the recording is finished at the end of the frame,
+    /// once every frame in flight has been encoded. Does nothing if there's no recording in progress.
+    pub fn stop_recording(&mut self) {
+        if let Some(video_recorder) = &mut self.video_recorder {
+            video_recorder.stop();
+        }
+    }
+
+    fn finish_recording(&mut self, vulkan_context: &VulkanContext) {
+        let video_recorder = match self.video_recorder.take() {
+            Some(video_recorder) => video_recorder,
+            None => return,
+        };
+
+        // Frames that are still in flight need to complete before their copies can be encoded.
+        let result = self
+            .wait_for_timeline_value(&vulkan_context.device, self.timeline_value, u64::MAX)
+            .map_err(Into::into)
+            .and_then(|_| video_recorder.finish(vulkan_context));
+        match result {
+            Ok(_) => println!("[HOTHAM_VIDEO] Recording finished"),
+            Err(e) => println!("[HOTHAM_VIDEO] ERROR: Unable to finish recording: {e:?}"),
+        }
     }
 
     /// Finish rendering a frame
@@ -445,30 +2052,95 @@ impl RenderContext {
         let device = &vulkan_context.device;
         let frame = &self.frames[self.frame_index];
         let command_buffer = frame.command_buffer;
-        let graphics_queue = vulkan_context.graphics_queue;
 
         // End the render pass and submit.
         unsafe {
             device.end_command_buffer(command_buffer).unwrap();
-            let fence = frame.fence;
-            let submit_info =
-                vk::SubmitInfo::builder().command_buffers(slice_from_ref(&command_buffer));
-            device
-                .queue_submit(graphics_queue, slice_from_ref(&submit_info), fence)
-                .expect("[HOTHAM_RENDER] @@ GPU CRASH DETECTED @@ - You are probably doing too much work in a compute shader!");
+        }
+        let timeline_value = self.submit(vulkan_context, command_buffer)
+            .expect("[HOTHAM_RENDER] @@ GPU CRASH DETECTED @@ - You are probably doing too much work in a compute shader!");
+        self.frames[self.frame_index].timeline_value = timeline_value;
+        self.staging_ring.retire(vulkan_context).unwrap();
+
+        // Now that the last recorded frame has been submitted, a recording that was stopped can be finished.
+        if self
+            .video_recorder
+            .as_ref()
+            .map_or(false, |video_recorder| video_recorder.is_stopping())
+        {
+            self.finish_recording(vulkan_context);
         }
 
         // And we're done! Bump the frame index.
         self.frame_index = (self.frame_index + 1) % PIPELINE_DEPTH;
     }
 
+    /// Wait for the GPU to finish the last commands that used `frame`, so its resources can be reused.
     pub(crate) fn wait(&self, device: &ash::Device, frame: &Frame) {
-        let fence = frame.fence;
+        self.wait_for_timeline_value(device, frame.timeline_value, u64::MAX)
+            .unwrap();
+    }
+
+    /// Submit `command_buffer` to the graphics queue, signalling the next value of the timeline semaphore once it
+    /// has completed. Returns the value that will be signalled.
+    ///
+    /// The commands won't start until every texture upload submitted so far has completed.
+    pub(crate) fn submit(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        command_buffer: vk::CommandBuffer,
+    ) -> VkResult<u64> {
+        let signal_value = self.timeline_value + 1;
+        let upload_value = self.staging_ring.submitted_value();
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .wait_semaphore_values(slice_from_ref(&upload_value))
+            .signal_semaphore_values(slice_from_ref(&signal_value));
+        let submit_info = vk::SubmitInfo::builder()
+            .wait_semaphores(slice_from_ref(&self.staging_ring.timeline_semaphore))
+            .wait_dst_stage_mask(slice_from_ref(&vk::PipelineStageFlags::ALL_COMMANDS))
+            .command_buffers(slice_from_ref(&command_buffer))
+            .signal_semaphores(slice_from_ref(&self.timeline_semaphore))
+            .push_next(&mut timeline_info);
+
+        unsafe {
+            vulkan_context.device.queue_submit(
+                vulkan_context.graphics_queue,
+                slice_from_ref(&submit_info),
+                vk::Fence::null(),
+            )
+        }?;
 
+        self.timeline_value = signal_value;
+        Ok(signal_value)
+    }
+
+    /// Block until the timeline semaphore has reached `value`, or `timeout` nanoseconds have passed.
+    pub(crate) fn wait_for_timeline_value(
+        &self,
+        device: &ash::Device,
+        value: u64,
+        timeout: u64,
+    ) -> VkResult<()> {
+        let wait_info = vk::SemaphoreWaitInfo::builder()
+            .semaphores(slice_from_ref(&self.timeline_semaphore))
+            .values(slice_from_ref(&value));
+        unsafe { device.wait_semaphores(&wait_info, timeout) }
+    }
+
+    /// The value of the timeline semaphore that the GPU has reached. Every submission made before it has completed.
+    pub fn completed_timeline_value(&self, vulkan_context: &VulkanContext) -> u64 {
         unsafe {
-            device.wait_for_fences(&[fence], true, u64::MAX).unwrap();
-            device.reset_fences(&[fence]).unwrap();
+            vulkan_context
+                .device
+                .get_semaphore_counter_value(self.timeline_semaphore)
         }
+        .unwrap()
+    }
+
+    /// The number of frames that have been submitted to the GPU but haven't finished rendering yet.
+    pub fn frames_in_flight(&self, vulkan_context: &VulkanContext) -> usize {
+        let completed = self.completed_timeline_value(vulkan_context);
+        frames_in_flight(self.frames.iter().map(|f| f.timeline_value), completed)
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -490,7 +2162,13 @@ impl RenderContext {
 
         // TODO: This is only necessary on desktop, or if there is data in the buffer!
         if !image_buf.is_empty() {
-            vulkan_context.upload_image(image_buf, mip_count, offsets, texture_image);
+            self.staging_ring.upload_image(
+                vulkan_context,
+                image_buf,
+                mip_count,
+                offsets,
+                texture_image,
+            )?;
         }
 
         let texture_index = unsafe {
@@ -517,15 +2195,67 @@ impl RenderContext {
     }
 }
 
+/// Every instance of a primitive that is drawn this frame.
+///
+/// Entities that share a [`Mesh`](crate::components::Mesh) share its primitives, so all of them are batched into a
+/// single instanced draw call. The transform of each instance is written to the draw data buffer.
 pub struct InstancedPrimitive {
     pub primitive: Primitive,
     pub instances: Vec<Instance>,
 }
 
+/// A draw call covering a contiguous run of instances of a primitive, whose draw data has already been written.
+pub(crate) struct UnculledDraw {
+    pub primitive_id: u32,
+    pub instance_count: u32,
+    pub instance_offset: u32,
+}
+
 pub struct Instance {
     pub gos_from_local: Affine3A,
+    /// Where the instance was last frame, used for SpaceWarp. The same as `gos_from_local` if it isn't enabled.
+    pub previous_gos_from_local: Affine3A,
     pub bounding_sphere: Vec4,
     pub skin_id: u32,
+    pub morph_weights_id: u32,
+}
+
+/// Count the frames whose commands haven't completed, given the last value the timeline semaphore reached.
+fn frames_in_flight(
+    frame_timeline_values: impl IntoIterator<Item = u64>,
+    completed_timeline_value: u64,
+) -> usize {
+    frame_timeline_values
+        .into_iter()
+        .filter(|v| *v > completed_timeline_value)
+        .count()
+}
+
+/// Check `requested` is a sample count the renderer can use, and clamp it to the highest count in `supported`.
+fn choose_msaa_samples(
+    requested: vk::SampleCountFlags,
+    supported: vk::SampleCountFlags,
+) -> Result<vk::SampleCountFlags> {
+    let sample_counts = [
+        vk::SampleCountFlags::TYPE_4,
+        vk::SampleCountFlags::TYPE_2,
+        vk::SampleCountFlags::TYPE_1,
+    ];
+    let requested_index = sample_counts
+        .iter()
+        .position(|s| *s == requested)
+        .ok_or_else(|| anyhow!("Unsupported MSAA sample count: {requested:?}"))?;
+
+    // Every device supports a single sample.
+    let samples = sample_counts[requested_index..]
+        .iter()
+        .copied()
+        .find(|s| supported.contains(*s))
+        .unwrap_or(vk::SampleCountFlags::TYPE_1);
+    if samples != requested {
+        println!("[HOTHAM_RENDERER] WARNING: {requested:?} MSAA isn't supported by this GPU, using {samples:?} instead");
+    }
+    Ok(samples)
 }
 
 // TODO: use bytemuck instead
@@ -533,23 +2263,42 @@ pub fn create_push_constant<T: 'static>(p: &T) -> &[u8] {
     unsafe { std::slice::from_raw_parts(p as *const T as *const u8, std::mem::size_of::<T>()) }
 }
 
-// TODO: Handle Android/Desktop code split more elegantly
-fn create_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass> {
-    // Attachment used for MSAA
-    let color_store_op = vk::AttachmentStoreOp::DONT_CARE;
-    let color_attachment = vk::AttachmentDescription::builder()
-        .format(COLOR_FORMAT)
-        .samples(SAMPLES)
-        .load_op(vk::AttachmentLoadOp::CLEAR)
-        .store_op(color_store_op)
-        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
-        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
-        .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+fn create_render_pass(
+    vulkan_context: &VulkanContext,
+    color_format: vk::Format,
+    msaa_samples: vk::SampleCountFlags,
+    store_depth: bool,
+    fragment_density_map: bool,
+) -> Result<vk::RenderPass> {
+    // If MSAA is disabled we render straight into the swapchain image, so there's nothing to resolve.
+    let msaa_enabled = msaa_samples != vk::SampleCountFlags::TYPE_1;
+
+    // Attachment used for MSAA, or the swapchain image itself if MSAA is disabled.
+    let color_attachment = if msaa_enabled {
+        vk::AttachmentDescription::builder()
+            .format(color_format)
+            .samples(msaa_samples)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+    } else {
+        vk::AttachmentDescription::builder()
+            .format(color_format)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+    };
 
     // Final attachment to be presented
     let color_attachment_resolve = vk::AttachmentDescription::builder()
-        .format(COLOR_FORMAT)
+        .format(color_format)
         .samples(vk::SampleCountFlags::TYPE_1)
         .load_op(vk::AttachmentLoadOp::DONT_CARE)
         .store_op(vk::AttachmentStoreOp::DONT_CARE)
@@ -558,20 +2307,23 @@ fn create_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass>
         .initial_layout(vk::ImageLayout::UNDEFINED)
         .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
 
-    // Depth buffer
+    // Depth buffer. Only stored if something reads from it after the render pass.
+    let depth_store_op = if store_depth {
+        vk::AttachmentStoreOp::STORE
+    } else {
+        vk::AttachmentStoreOp::DONT_CARE
+    };
     let depth_attachment = vk::AttachmentDescription::builder()
-        .format(DEPTH_FORMAT)
-        .samples(SAMPLES)
+        .format(DEPTH_STENCIL_FORMAT)
+        .samples(msaa_samples)
         .load_op(vk::AttachmentLoadOp::CLEAR)
-        .store_op(vk::AttachmentStoreOp::DONT_CARE)
-        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .store_op(depth_store_op)
+        .stencil_load_op(vk::AttachmentLoadOp::CLEAR)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         .initial_layout(vk::ImageLayout::UNDEFINED)
         .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
 
-    // Fixed foveated rendering (FFR) attachment
-    // Note this only works on Android so we need conditional compilation here.
-    #[cfg(target_os = "android")]
+    // Fixed foveated rendering (FFR) attachment, only present if the runtime provides a fragment density map.
     let ffr_attachment = vk::AttachmentDescription::builder()
         .format(vk::Format::R8G8_UNORM)
         .samples(vk::SampleCountFlags::TYPE_1)
@@ -582,6 +2334,17 @@ fn create_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass>
         .initial_layout(vk::ImageLayout::UNDEFINED)
         .final_layout(vk::ImageLayout::FRAGMENT_DENSITY_MAP_OPTIMAL_EXT);
 
+    // Attachments are laid out as: color, depth, (FFR), (resolve)
+    let mut attachments = vec![*color_attachment, *depth_attachment];
+    if fragment_density_map {
+        attachments.push(*ffr_attachment);
+    }
+
+    let resolve_attachment_index = attachments.len() as u32;
+    if msaa_enabled {
+        attachments.push(*color_attachment_resolve);
+    }
+
     let color_attachment_reference = vk::AttachmentReference::builder()
         .attachment(0)
         .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
@@ -594,22 +2357,26 @@ fn create_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass>
         .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
         .build();
 
-    #[cfg(target_os = "android")]
     let ffr_attachment_reference = vk::AttachmentReference::builder()
         .attachment(2)
         .layout(vk::ImageLayout::FRAGMENT_DENSITY_MAP_OPTIMAL_EXT);
 
     let color_attachment_resolve_reference = vk::AttachmentReference::builder()
-        .attachment(RESOLVE_ATTACHMENT)
+        .attachment(resolve_attachment_index)
         .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
         .build();
 
     let subpass = vk::SubpassDescription::builder()
         .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
         .color_attachments(&color_attachment_reference)
-        .resolve_attachments(std::slice::from_ref(&color_attachment_resolve_reference))
         .depth_stencil_attachment(&depth_stencil_reference);
 
+    let subpass = if msaa_enabled {
+        subpass.resolve_attachments(std::slice::from_ref(&color_attachment_resolve_reference))
+    } else {
+        subpass
+    };
+
     let dependency = vk::SubpassDependency::builder()
         .src_subpass(vk::SUBPASS_EXTERNAL)
         .dst_subpass(0)
@@ -627,52 +2394,215 @@ fn create_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass>
                 | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
         );
 
-    let view_masks = [!(!0 << VIEW_COUNT)];
+    let view_masks = [VIEW_MASK];
     let mut multiview = vk::RenderPassMultiviewCreateInfo::builder()
         .view_masks(&view_masks)
         .correlation_masks(&view_masks);
 
-    #[cfg(target_os = "android")]
     let mut ffr_info = vk::RenderPassFragmentDensityMapCreateInfoEXT::builder()
         .fragment_density_map_attachment(*ffr_attachment_reference);
 
-    #[cfg(target_os = "android")]
-    let attachments = [
-        *color_attachment,
-        *depth_attachment,
-        *ffr_attachment,
-        *color_attachment_resolve,
-    ];
-
-    #[cfg(not(target_os = "android"))]
-    let attachments = [
-        *color_attachment,
-        *depth_attachment,
-        *color_attachment_resolve,
-    ];
-
-    #[allow(unused_mut)]
-    let mut create_info = vk::RenderPassCreateInfo::builder()
+    let create_info = vk::RenderPassCreateInfo::builder()
         .attachments(&attachments)
         .subpasses(std::slice::from_ref(&subpass))
         .dependencies(std::slice::from_ref(&dependency))
         .push_next(&mut multiview);
 
-    #[cfg(target_os = "android")]
-    let create_info = create_info.push_next(&mut ffr_info);
+    let create_info = if fragment_density_map {
+        create_info.push_next(&mut ffr_info)
+    } else {
+        create_info
+    };
 
     let render_pass = unsafe { vulkan_context.device.create_render_pass(&create_info, None) }?;
 
     Ok(render_pass)
 }
 
+/// Create a set of pipelines that are used together. If any of them can't be created the ones that were are destroyed,
+/// so either every pipeline in the set exists or none do.
+pub(crate) fn create_pipeline_set<const N: usize>(
+    vulkan_context: &VulkanContext,
+    create: [&dyn Fn() -> Result<vk::Pipeline>; N],
+) -> Result<[vk::Pipeline; N]> {
+    let mut pipelines = [vk::Pipeline::null(); N];
+    for (i, create) in create.iter().enumerate() {
+        match create() {
+            Ok(pipeline) => pipelines[i] = pipeline,
+            Err(e) => {
+                unsafe { destroy_pipelines(&vulkan_context.device, &pipelines[..i]) };
+                return Err(e);
+            }
+        }
+    }
+    Ok(pipelines)
+}
+
+/// Destroy `pipelines`.
+///
+/// # Safety
+///
+/// The GPU must have finished with every command that uses them.
+pub(crate) unsafe fn destroy_pipelines(device: &ash::Device, pipelines: &[vk::Pipeline]) {
+    for pipeline in pipelines {
+        device.destroy_pipeline(*pipeline, None);
+    }
+}
+
+/// Create a pipeline for opaque materials. Double sided materials use [`vk::CullModeFlags::NONE`].
 pub(crate) fn create_pipeline(
     vulkan_context: &VulkanContext,
     pipeline_layout: vk::PipelineLayout,
     render_area: &vk::Rect2D,
     render_pass: vk::RenderPass,
     shaders: &Shaders,
+    msaa_samples: vk::SampleCountFlags,
+    cull_mode: vk::CullModeFlags,
+) -> Result<vk::Pipeline> {
+    create_pipeline_with_blending(
+        vulkan_context,
+        pipeline_layout,
+        render_area,
+        render_pass,
+        shaders,
+        msaa_samples,
+        cull_mode,
+        PipelineKind::Opaque,
+    )
+}
+
+/// Create a pipeline for transparent materials: alpha blending on, depth writes off.
+/// Double sided materials use [`vk::CullModeFlags::NONE`].
+pub(crate) fn create_transparent_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_area: &vk::Rect2D,
+    render_pass: vk::RenderPass,
+    shaders: &Shaders,
+    msaa_samples: vk::SampleCountFlags,
+    cull_mode: vk::CullModeFlags,
+) -> Result<vk::Pipeline> {
+    create_pipeline_with_blending(
+        vulkan_context,
+        pipeline_layout,
+        render_area,
+        render_pass,
+        shaders,
+        msaa_samples,
+        cull_mode,
+        PipelineKind::Transparent,
+    )
+}
+
+/// Create a pipeline for alpha masked materials. With MSAA, alpha-to-coverage turns the material's alpha into the
+/// fraction of samples covered, which gives masked edges the same anti-aliasing as the edges of triangles. Without
+/// MSAA, fragments below the alpha cutoff are discarded as usual.
+pub(crate) fn create_alpha_to_coverage_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_area: &vk::Rect2D,
+    render_pass: vk::RenderPass,
+    shaders: &Shaders,
+    msaa_samples: vk::SampleCountFlags,
+    cull_mode: vk::CullModeFlags,
+) -> Result<vk::Pipeline> {
+    let kind = if msaa_samples == vk::SampleCountFlags::TYPE_1 {
+        PipelineKind::Opaque
+    } else {
+        PipelineKind::AlphaToCoverage
+    };
+    create_pipeline_with_blending(
+        vulkan_context,
+        pipeline_layout,
+        render_area,
+        render_pass,
+        shaders,
+        msaa_samples,
+        cull_mode,
+        kind,
+    )
+}
+
+/// Create a pipeline for [`RenderMode::Wireframe`]: triangles are drawn as lines, and nothing is culled so that
+/// the back of every mesh is visible through it. Falls back to filled triangles if the device doesn't support
+/// `fillModeNonSolid`.
+pub(crate) fn create_wireframe_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_area: &vk::Rect2D,
+    render_pass: vk::RenderPass,
+    shaders: &Shaders,
+    msaa_samples: vk::SampleCountFlags,
+) -> Result<vk::Pipeline> {
+    let kind = if vulkan_context.supports_wireframe() {
+        PipelineKind::Wireframe
+    } else {
+        println!("[HOTHAM_RENDERER] WARNING: fillModeNonSolid is not supported, wireframes will be filled");
+        PipelineKind::Opaque
+    };
+    create_pipeline_with_blending(
+        vulkan_context,
+        pipeline_layout,
+        render_area,
+        render_pass,
+        shaders,
+        msaa_samples,
+        vk::CullModeFlags::NONE,
+        kind,
+    )
+}
+
+/// Create a pipeline for [`RenderMode::Overdraw`]: depth testing off and additive blending on, so that each
+/// fragment adds to its pixel.
+pub(crate) fn create_overdraw_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_area: &vk::Rect2D,
+    render_pass: vk::RenderPass,
+    shaders: &Shaders,
+    msaa_samples: vk::SampleCountFlags,
+) -> Result<vk::Pipeline> {
+    create_pipeline_with_blending(
+        vulkan_context,
+        pipeline_layout,
+        render_area,
+        render_pass,
+        shaders,
+        msaa_samples,
+        vk::CullModeFlags::BACK,
+        PipelineKind::Overdraw,
+    )
+}
+
+/// The variants of the PBR pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PipelineKind {
+    Opaque,
+    AlphaToCoverage,
+    Transparent,
+    Wireframe,
+    Overdraw,
+}
+
+fn create_pipeline_with_blending(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_area: &vk::Rect2D,
+    render_pass: vk::RenderPass,
+    shaders: &Shaders,
+    msaa_samples: vk::SampleCountFlags,
+    cull_mode: vk::CullModeFlags,
+    kind: PipelineKind,
 ) -> Result<vk::Pipeline> {
+    let transparent = kind == PipelineKind::Transparent;
+    let overdraw = kind == PipelineKind::Overdraw;
+    let alpha_to_coverage = kind == PipelineKind::AlphaToCoverage;
+    let polygon_mode = if kind == PipelineKind::Wireframe {
+        vk::PolygonMode::LINE
+    } else {
+        vk::PolygonMode::FILL
+    };
+
     // Build up the state of the pipeline
 
     // Vertex shader stage
@@ -682,12 +2612,22 @@ pub(crate) fn create_pipeline(
         vulkan_context,
     )?;
 
-    // Fragment shader stage
-    let (fragment_shader, fragment_stage) = create_shader(
+    // Fragment shader stage. Must match `ALPHA_TO_COVERAGE` in pbr.frag
+    let (fragment_shader, mut fragment_stage) = create_shader(
         &shaders.fragment_shader,
         vk::ShaderStageFlags::FRAGMENT,
         vulkan_context,
     )?;
+    let specialization_data = (alpha_to_coverage as vk::Bool32).to_ne_bytes();
+    let specialization_map_entry = vk::SpecializationMapEntry {
+        constant_id: 0,
+        offset: 0,
+        size: size_of::<vk::Bool32>(),
+    };
+    let specialization_info = vk::SpecializationInfo::builder()
+        .map_entries(slice_from_ref(&specialization_map_entry))
+        .data(&specialization_data);
+    fragment_stage.p_specialization_info = &*specialization_info;
 
     let stages = [vertex_stage, fragment_stage];
 
@@ -733,8 +2673,8 @@ pub(crate) fn create_pipeline(
 
     // Rasterization state
     let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
-        .polygon_mode(vk::PolygonMode::FILL)
-        .cull_mode(vk::CullModeFlags::BACK)
+        .polygon_mode(polygon_mode)
+        .cull_mode(cull_mode)
         .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
         .rasterizer_discard_enable(false)
         .depth_clamp_enable(false)
@@ -745,13 +2685,16 @@ pub(crate) fn create_pipeline(
         .line_width(1.0);
 
     // Multisample state
-    let multisample_state =
-        vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(SAMPLES);
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .rasterization_samples(msaa_samples)
+        .alpha_to_coverage_enable(alpha_to_coverage)
+        .alpha_to_one_enable(alpha_to_coverage && vulkan_context.supports_alpha_to_one());
 
-    // Depth stencil state
+    // Depth stencil state. Transparent objects are tested against the depth buffer, but don't write to it,
+    // so that everything behind them is still drawn. Overdraw counts every fragment, hidden or not.
     let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
-        .depth_test_enable(true)
-        .depth_write_enable(true)
+        .depth_test_enable(!overdraw)
+        .depth_write_enable(!transparent && !overdraw)
         .depth_compare_op(vk::CompareOp::GREATER)
         .depth_bounds_test_enable(false)
         .min_depth_bounds(0.0)
@@ -766,7 +2709,21 @@ pub(crate) fn create_pipeline(
                 | vk::ColorComponentFlags::B
                 | vk::ColorComponentFlags::A,
         )
-        .blend_enable(false)
+        .blend_enable(transparent || overdraw)
+        .src_color_blend_factor(if overdraw {
+            vk::BlendFactor::ONE
+        } else {
+            vk::BlendFactor::SRC_ALPHA
+        })
+        .dst_color_blend_factor(if overdraw {
+            vk::BlendFactor::ONE
+        } else {
+            vk::BlendFactor::ONE_MINUS_SRC_ALPHA
+        })
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .alpha_blend_op(vk::BlendOp::ADD)
         .build();
 
     let color_blend_attachments = [color_blend_attachment];
@@ -842,7 +2799,7 @@ fn create_pipeline_layout(
     let push_constant_range = vk::PushConstantRange::builder()
         .offset(0)
         .size(std::mem::size_of::<Material>() as _)
-        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+        .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
 
     let create_info = &vk::PipelineLayoutCreateInfo::builder()
         .set_layouts(set_layouts)
@@ -909,3 +2866,52 @@ fn create_compute_pipeline(
         (pipeline, layout)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_frames_in_flight() {
+        // Nothing has been submitted yet
+        assert_eq!(frames_in_flight([0, 0], 0), 0);
+
+        // Both frames submitted, the first has completed
+        assert_eq!(frames_in_flight([3, 4], 3), 1);
+
+        // Culling signals values in between frames, so the GPU can be ahead of a frame's value
+        assert_eq!(frames_in_flight([5, 7], 6), 1);
+        assert_eq!(frames_in_flight([5, 7], 7), 0);
+    }
+
+    #[test]
+    pub fn test_choose_msaa_samples() {
+        let all = vk::SampleCountFlags::TYPE_1
+            | vk::SampleCountFlags::TYPE_2
+            | vk::SampleCountFlags::TYPE_4
+            | vk::SampleCountFlags::TYPE_8;
+        assert_eq!(
+            choose_msaa_samples(vk::SampleCountFlags::TYPE_4, all).unwrap(),
+            vk::SampleCountFlags::TYPE_4
+        );
+        assert_eq!(
+            choose_msaa_samples(vk::SampleCountFlags::TYPE_1, all).unwrap(),
+            vk::SampleCountFlags::TYPE_1
+        );
+
+        // Clamped to the highest count the device supports
+        let supported = vk::SampleCountFlags::TYPE_1 | vk::SampleCountFlags::TYPE_2;
+        assert_eq!(
+            choose_msaa_samples(vk::SampleCountFlags::TYPE_4, supported).unwrap(),
+            vk::SampleCountFlags::TYPE_2
+        );
+
+        // Counts the renderer doesn't support are an error, rather than a panic
+        assert!(choose_msaa_samples(vk::SampleCountFlags::TYPE_8, all).is_err());
+        assert!(choose_msaa_samples(
+            vk::SampleCountFlags::TYPE_4 | vk::SampleCountFlags::TYPE_2,
+            all
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use anyhow::Result;
+use ash::vk;
+use glam::{Affine3A, Quat, Vec2, Vec3};
+
+use crate::{
+    contexts::{ControllerStatus, InputContext, RenderContext, VulkanContext},
+    rendering::{image::Image, swapchain::SwapchainInfo},
+    util::posef_from_affine,
+    xr, SWAPCHAIN_LENGTH, VIEW_COUNT,
+};
+
+use super::render_context::RenderSettings;
+
+/// The resolution of each simulated eye, unless another is given
+pub const DEFAULT_SIMULATED_RESOLUTION: vk::Extent2D = vk::Extent2D {
+    width: 1024,
+    height: 1024,
+};
+
+/// How often the simulated display refreshes, in Hz
+const SIMULATED_REFRESH_RATE: f32 = 72.;
+
+/// A stand-in for [`XrContext`](crate::contexts::XrContext) that needs no OpenXR runtime, loader or headset, so
+/// systems and rendering can be run in CI or on a machine without a headset.
+///
+/// It has a pair of fixed stereo views, a clock that ticks at the refresh rate of a Quest 2, and input that is
+/// whatever the application sets in [`SimulatedXrContext::input`]. Frames are rendered into images it owns, which can
+/// be copied out to check what was drawn.
+///
+/// ```ignore
+/// let vulkan_context = VulkanContext::testing()?;
+/// let mut xr_context = SimulatedXrContext::new(&vulkan_context, &render_settings)?;
+/// let mut render_context = RenderContext::new_simulated(&vulkan_context, &xr_context, render_settings)?;
+/// let mut input_context = InputContext::default();
+///
+/// let swapchain_image_index =
+///     xr_context.begin_frame(&vulkan_context, &mut render_context, &mut input_context);
+/// // .. run systems ..
+/// simulated_rendering_system(&mut world, &vulkan_context, &mut render_context, &xr_context, swapchain_image_index);
+/// xr_context.end_frame(&vulkan_context, &mut render_context);
+/// ```
+pub struct SimulatedXrContext {
+    /// Where the simulated headset is in stage space. Move it to look around.
+    pub stage_from_hmd: Affine3A,
+    /// The distance between the centres of the eyes, in metres
+    pub ipd: f32,
+    /// The field of view of each eye
+    pub fov: xr::Fovf,
+    /// The input read by [`InputContext`] at the start of each frame
+    pub input: SimulatedInput,
+    /// The resolution of each eye
+    pub resolution: vk::Extent2D,
+    /// The images frames are rendered into, each with a layer per eye
+    pub images: Vec<Image>,
+    views: Vec<xr::View>,
+    predicted_display_time: xr::Time,
+    frame_count: usize,
+}
+
+impl SimulatedXrContext {
+    /// Create a simulated headset with the default resolution, standing at head height at the centre of the stage
+    pub fn new(vulkan_context: &VulkanContext, render_settings: &RenderSettings) -> Result<Self> {
+        Self::new_with_resolution(
+            vulkan_context,
+            render_settings,
+            DEFAULT_SIMULATED_RESOLUTION,
+        )
+    }
+
+    /// Create a simulated headset whose eyes have the given resolution
+    pub fn new_with_resolution(
+        vulkan_context: &VulkanContext,
+        render_settings: &RenderSettings,
+        resolution: vk::Extent2D,
+    ) -> Result<Self> {
+        let images = (0..SWAPCHAIN_LENGTH)
+            .map(|_| {
+                vulkan_context.create_image(
+                    render_settings.color_space.swapchain_format(),
+                    &resolution,
+                    vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+                    VIEW_COUNT,
+                    1,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut xr_context = Self {
+            stage_from_hmd: Affine3A::from_translation([0., 1.4, 0.].into()),
+            ipd: 0.064,
+            fov: xr::Fovf {
+                angle_left: -45_f32.to_radians(),
+                angle_right: 45_f32.to_radians(),
+                angle_up: 45_f32.to_radians(),
+                angle_down: -45_f32.to_radians(),
+            },
+            input: Default::default(),
+            resolution,
+            images,
+            views: Vec::new(),
+            predicted_display_time: xr::Time::from_nanos(0),
+            frame_count: 0,
+        };
+        xr_context.update_views();
+
+        println!("[HOTHAM_SIMULATED_XR] Running without an OpenXR runtime");
+        Ok(xr_context)
+    }
+
+    /// The images frames are rendered into, as [`RenderContext`] expects them
+    pub(crate) fn swapchain_info(&self) -> SwapchainInfo {
+        SwapchainInfo {
+            resolution: self.resolution,
+            images: self.images.iter().map(|image| image.handle).collect(),
+            depth_images: Vec::new(),
+            #[cfg(target_os = "android")]
+            ffr_images: Vec::new(),
+        }
+    }
+
+    /// Start a frame: advance the clock, place the eyes where the headset is, read the simulated input into
+    /// `input_context` and begin recording commands. Returns the index of the image to render into.
+    pub fn begin_frame(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        input_context: &mut InputContext,
+    ) -> usize {
+        self.predicted_display_time = xr::Time::from_nanos(
+            (self.frame_count as f64 * 1e9 / SIMULATED_REFRESH_RATE as f64) as i64,
+        );
+        self.update_views();
+        input_context.update_simulated(self);
+        render_context.begin_frame(vulkan_context);
+
+        self.frame_count % self.images.len()
+    }
+
+    /// Finish the frame and submit it to the GPU
+    pub fn end_frame(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+    ) {
+        render_context.end_frame(vulkan_context);
+        self.frame_count += 1;
+    }
+
+    /// Where each eye is in stage space this frame
+    pub fn views(&self) -> &[xr::View] {
+        &self.views
+    }
+
+    /// When the frame being rendered would be shown
+    pub fn predicted_display_time(&self) -> xr::Time {
+        self.predicted_display_time
+    }
+
+    /// How many frames have been finished
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    fn update_views(&mut self) {
+        let fov = self.fov;
+        self.views = [-0.5, 0.5]
+            .into_iter()
+            .map(|side| {
+                let hmd_from_eye = Affine3A::from_translation(Vec3::X * side * self.ipd);
+                xr::View {
+                    pose: posef_from_affine(self.stage_from_hmd * hmd_from_eye),
+                    fov,
+                }
+            })
+            .collect();
+    }
+}
+
+/// The input of a simulated controller
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulatedController {
+    /// Where the controller is held, in stage space
+    pub stage_from_grip: Affine3A,
+    /// The position of the thumbstick, from -1 to 1 on each axis
+    pub thumbstick: Vec2,
+    /// How far the trigger is pulled, from 0 to 1
+    pub trigger: f32,
+    /// How far the grip is squeezed, from 0 to 1
+    pub grip: f32,
+    /// Is the A or X button held down?
+    pub primary_button: bool,
+    /// Is the B or Y button held down?
+    pub secondary_button: bool,
+    /// Is the menu button held down? Only the left controller has one.
+    pub menu_button: bool,
+    /// Is the thumbstick clicked in?
+    pub thumbstick_click: bool,
+    /// Is the controller connected? Disconnected controllers are still held where they are, but aren't tracked.
+    pub is_connected: bool,
+    /// How much charge the battery has left, from 0 to 1, to test warnings about it. Unknown by default, like on
+    /// real runtimes.
+    pub battery_level: Option<f32>,
+}
+
+impl SimulatedController {
+    fn at(translation: Vec3) -> Self {
+        Self {
+            stage_from_grip: Affine3A::from_rotation_translation(
+                Quat::from_xyzw(0.707, 0., 0., 0.707),
+                translation,
+            ),
+            thumbstick: Vec2::ZERO,
+            trigger: 0.,
+            grip: 0.,
+            primary_button: false,
+            secondary_button: false,
+            menu_button: false,
+            thumbstick_click: false,
+            is_connected: true,
+            battery_level: None,
+        }
+    }
+
+    pub(crate) fn status(&self) -> ControllerStatus {
+        ControllerStatus {
+            is_connected: self.is_connected,
+            is_tracked: self.is_connected,
+            battery_level: self.battery_level,
+        }
+    }
+}
+
+/// The input of both simulated controllers. By default they're held in front of the player, with nothing pressed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimulatedInput {
+    pub left: SimulatedController,
+    pub right: SimulatedController,
+}
+
+impl Default for SimulatedInput {
+    fn default() -> Self {
+        // The same poses as `InputContext::testing`.
+        Self {
+            left: SimulatedController::at([-0.2, 1.4, -0.5].into()),
+            right: SimulatedController::at([0.2, 1.4, -0.5].into()),
+        }
+    }
+}
+
+impl RenderContext {
+    /// Create a `RenderContext` that renders into the images of a [`SimulatedXrContext`], rather than an OpenXR
+    /// swapchain
+    pub fn new_simulated(
+        vulkan_context: &VulkanContext,
+        xr_context: &SimulatedXrContext,
+        render_settings: RenderSettings,
+    ) -> Result<Self> {
+        Self::new_from_swapchain_info(
+            vulkan_context,
+            &xr_context.swapchain_info(),
+            render_settings,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::affine_from_posef;
+    use approx::assert_relative_eq;
+
+    #[cfg(target_os = "windows")]
+    #[test]
+    pub fn test_simulated_frame_loop() {
+        use crate::systems::simulated_rendering_system;
+        use hecs::World;
+
+        let vulkan_context = VulkanContext::testing().unwrap();
+        let render_settings = RenderSettings::default();
+        let mut xr_context = SimulatedXrContext::new(&vulkan_context, &render_settings).unwrap();
+        let mut render_context =
+            RenderContext::new_simulated(&vulkan_context, &xr_context, render_settings).unwrap();
+        let mut input_context = InputContext::default();
+        let mut world = World::new();
+
+        xr_context.input.right.primary_button = true;
+        for frame in 0..SWAPCHAIN_LENGTH + 1 {
+            let swapchain_image_index =
+                xr_context.begin_frame(&vulkan_context, &mut render_context, &mut input_context);
+            assert_eq!(swapchain_image_index, frame % SWAPCHAIN_LENGTH);
+            assert_eq!(
+                input_context.right.a_button_just_pressed(),
+                frame == 0,
+                "frame {frame}"
+            );
+            simulated_rendering_system(
+                &mut world,
+                &vulkan_context,
+                &mut render_context,
+                &xr_context,
+                swapchain_image_index,
+            );
+            xr_context.end_frame(&vulkan_context, &mut render_context);
+        }
+        assert_eq!(xr_context.frame_count(), SWAPCHAIN_LENGTH + 1);
+    }
+
+    #[test]
+    pub fn test_simulated_views() {
+        let mut xr_context = SimulatedXrContext {
+            stage_from_hmd: Affine3A::from_translation([0., 1.5, 0.].into()),
+            ipd: 0.06,
+            fov: Default::default(),
+            input: Default::default(),
+            resolution: DEFAULT_SIMULATED_RESOLUTION,
+            images: Vec::new(),
+            views: Vec::new(),
+            predicted_display_time: xr::Time::from_nanos(0),
+            frame_count: 0,
+        };
+        xr_context.update_views();
+
+        let views = xr_context.views();
+        assert_eq!(views.len(), VIEW_COUNT as usize);
+        assert_relative_eq!(
+            affine_from_posef(views[0].pose).translation,
+            [-0.03, 1.5, 0.].into()
+        );
+        assert_relative_eq!(
+            affine_from_posef(views[1].pose).translation,
+            [0.03, 1.5, 0.].into()
+        );
+    }
+}
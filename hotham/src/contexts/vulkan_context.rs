@@ -3,7 +3,7 @@
 use crate::{
     hotham_error::HothamError,
     rendering::{image::Image, texture::DEFAULT_COMPONENT_MAPPING},
-    DEPTH_FORMAT,
+    DEPTH_FORMAT, DEPTH_STENCIL_FORMAT,
 };
 use anyhow::{anyhow, Result};
 use ash::{
@@ -16,7 +16,7 @@ use ash::{
 use openxr as xr;
 use std::{
     cmp::max,
-    ffi::{c_char, CString},
+    ffi::{c_char, c_void, CStr, CString},
     fmt::Debug,
     ptr::copy,
     slice::from_ref as slice_from_ref,
@@ -24,10 +24,8 @@ use std::{
 
 type XrVulkan = xr::Vulkan;
 
-#[cfg(debug_assertions)]
-use ash::vk::DebugUtilsObjectNameInfoEXT;
-
-use super::render_context::SAMPLES;
+const VALIDATION_LAYER_NAME: &CStr =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"VK_LAYER_KHRONOS_validation\0") };
 
 #[derive(Clone)]
 pub struct VulkanContext {
@@ -38,18 +36,28 @@ pub struct VulkanContext {
     pub command_pool: vk::CommandPool,
     pub queue_family_index: u32,
     pub graphics_queue: vk::Queue,
+    /// Used to upload textures in the background. A second queue from the graphics family if the device has one,
+    /// otherwise the same queue as `graphics_queue`.
+    pub transfer_queue: vk::Queue,
     #[deprecated]
     pub descriptor_pool: vk::DescriptorPool,
-    pub debug_utils: DebugUtils,
+    /// Names objects and labels command buffer regions, so they can be identified in tools like RenderDoc.
+    /// `None` if `VK_EXT_debug_utils` isn't available.
+    pub debug_utils: Option<DebugUtils>,
+    /// Prints the messages of the validation layers. Null unless validation was enabled.
+    pub debug_messenger: vk::DebugUtilsMessengerEXT,
     pub physical_device_properties: vk::PhysicalDeviceProperties,
 }
 
 impl VulkanContext {
+    /// Create a Vulkan instance and device through OpenXR. If `validation` is set the Khronos validation layer is
+    /// enabled, if it's installed, and its messages are printed.
     pub fn create_from_xr_instance(
         xr_instance: &xr::Instance,
         system: xr::SystemId,
         application_name: &str,
         application_version: u32,
+        validation: bool,
     ) -> Result<Self> {
         println!("[HOTHAM_VULKAN] Creating VulkanContext..");
         let vk_target_version_xr = xr::Version::new(1, 2, 128);
@@ -75,7 +83,14 @@ impl VulkanContext {
             .engine_version(1)
             .build();
 
-        let create_info = vk::InstanceCreateInfo::builder().application_info(&app_info);
+        // OpenXR adds the extensions it needs to ours.
+        let debug_support = DebugSupport::new(&entry, validation)?;
+        let layer_names = debug_support.layer_names();
+        let extension_names = debug_support.extension_names();
+        let create_info = vk::InstanceCreateInfo::builder()
+            .application_info(&app_info)
+            .enabled_layer_names(&layer_names)
+            .enabled_extension_names(&extension_names);
 
         let instance_handle = unsafe {
             xr_instance.create_vulkan_instance(
@@ -129,14 +144,17 @@ impl VulkanContext {
         let mut fragment_density = vk::PhysicalDeviceFragmentDensityMap2FeaturesEXT::builder()
             .fragment_density_map_deferred(true);
 
-        let queue_family_index = unsafe {
+        let mut timeline_semaphore =
+            vk::PhysicalDeviceTimelineSemaphoreFeatures::builder().timeline_semaphore(true);
+
+        let (queue_family_index, queue_count) = unsafe {
             instance
                 .get_physical_device_queue_family_properties(physical_device)
                 .into_iter()
                 .enumerate()
                 .find_map(|(queue_family_index, info)| {
                     if info.queue_flags.contains(vk::QueueFlags::GRAPHICS) {
-                        Some(queue_family_index as u32)
+                        Some((queue_family_index as u32, info.queue_count))
                     } else {
                         None
                     }
@@ -144,20 +162,29 @@ impl VulkanContext {
                 .unwrap()
         };
 
+        // Uploads get their own, lower priority queue so they don't hold up rendering.
+        let queue_priorities = [1.0, 0.5];
+        let queue_priorities = &queue_priorities[..queue_count.min(2) as usize];
+        let transfer_queue_index = queue_priorities.len() as u32 - 1;
+
         let graphics_queue_create_info = vk::DeviceQueueCreateInfo::builder()
             .queue_family_index(queue_family_index)
-            .queue_priorities(&[1.0])
+            .queue_priorities(queue_priorities)
             .build();
 
+        let enabled_features = get_enabled_device_features(&instance, physical_device);
+
         let device_create_info = vk::DeviceCreateInfo::builder()
             .enabled_extension_names(&enabled_extensions)
+            .enabled_features(&enabled_features)
             .queue_create_infos(slice_from_ref(&graphics_queue_create_info))
             .push_next(&mut descriptor_indexing_features)
             .push_next(&mut robust_features)
             .push_next(&mut multiview_features)
             .push_next(&mut f16_storage)
             .push_next(&mut f16_arithmetic)
-            .push_next(&mut fragment_density);
+            .push_next(&mut fragment_density)
+            .push_next(&mut timeline_semaphore);
 
         let device_handle = unsafe {
             xr_instance.create_vulkan_device(
@@ -178,15 +205,20 @@ impl VulkanContext {
             device,
             physical_device,
             queue_family_index,
+            transfer_queue_index,
+            debug_support,
         ))
     }
 
+    /// Create a Vulkan instance and device with `XR_KHR_vulkan_enable`. See
+    /// [`VulkanContext::create_from_xr_instance`] for `validation`.
     #[cfg(not(target_os = "android"))]
     pub fn create_from_xr_instance_legacy(
         xr_instance: &xr::Instance,
         system: xr::SystemId,
         application_name: &str,
         application_version: u32,
+        validation: bool,
     ) -> Result<Self> {
         let vk_target_version_xr = xr::Version::new(1, 1, 128);
 
@@ -197,8 +229,13 @@ impl VulkanContext {
             return Err(HothamError::UnsupportedVersionError.into());
         }
 
-        let (vulkan_instance, vulkan_entry) =
-            vulkan_init_legacy(xr_instance, system, application_name, application_version)?;
+        let (vulkan_instance, vulkan_entry, debug_support) = vulkan_init_legacy(
+            xr_instance,
+            system,
+            application_name,
+            application_version,
+            validation,
+        )?;
         let physical_device = unsafe {
             vk::PhysicalDevice::from_raw(
                 xr_instance
@@ -215,6 +252,8 @@ impl VulkanContext {
             device,
             physical_device,
             queue_family_index,
+            0,
+            debug_support,
         ))
     }
 
@@ -224,12 +263,26 @@ impl VulkanContext {
         device: ash::Device,
         physical_device: vk::PhysicalDevice,
         queue_family_index: u32,
+        transfer_queue_index: u32,
+        debug_support: DebugSupport,
     ) -> Self {
         let graphics_queue = unsafe { device.get_device_queue(queue_family_index, 0) };
+        let transfer_queue =
+            unsafe { device.get_device_queue(queue_family_index, transfer_queue_index) };
         let command_pool = create_command_pool(&device, queue_family_index).unwrap();
         let descriptor_pool = create_descriptor_pool(&device).unwrap();
 
-        let debug_utils = DebugUtils::new(&entry, &instance);
+        let debug_utils = debug_support
+            .debug_utils
+            .then(|| DebugUtils::new(&entry, &instance));
+        let debug_messenger = match &debug_utils {
+            Some(debug_utils) if debug_support.validation => create_debug_messenger(debug_utils)
+                .unwrap_or_else(|e| {
+                    println!("[HOTHAM_VULKAN] Unable to create debug messenger: {e:?}");
+                    vk::DebugUtilsMessengerEXT::null()
+                }),
+            _ => vk::DebugUtilsMessengerEXT::null(),
+        };
         let physical_device_properties =
             unsafe { instance.get_physical_device_properties(physical_device) };
 
@@ -241,8 +294,10 @@ impl VulkanContext {
             command_pool,
             queue_family_index,
             graphics_queue,
+            transfer_queue,
             descriptor_pool,
             debug_utils,
+            debug_messenger,
             physical_device_properties,
         }
     }
@@ -256,12 +311,19 @@ impl VulkanContext {
         let (device, _, queue_family_index) =
             create_vulkan_device(&extension_names, &instance, physical_device)?;
 
+        // The test instance always enables validation, but leaves printing its messages to the layer itself.
+        let debug_support = DebugSupport {
+            debug_utils: true,
+            validation: false,
+        };
         Ok(Self::new(
             instance,
             entry,
             device,
             physical_device,
             queue_family_index,
+            0,
+            debug_support,
         ))
     }
 
@@ -336,6 +398,48 @@ impl VulkanContext {
         array_layers: u32,
         mip_levels: u32,
         component_mapping: vk::ComponentMapping,
+    ) -> Result<Image> {
+        self.create_image_with_samples(
+            format,
+            extent,
+            usage,
+            array_layers,
+            mip_levels,
+            component_mapping,
+            vk::SampleCountFlags::TYPE_1,
+        )
+    }
+
+    /// Create a multisampled image, used as an MSAA render target.
+    pub fn create_multisampled_image(
+        &self,
+        format: vk::Format,
+        extent: &vk::Extent2D,
+        usage: vk::ImageUsageFlags,
+        array_layers: u32,
+        samples: vk::SampleCountFlags,
+    ) -> Result<Image> {
+        self.create_image_with_samples(
+            format,
+            extent,
+            usage,
+            array_layers,
+            1,
+            DEFAULT_COMPONENT_MAPPING,
+            samples,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_image_with_samples(
+        &self,
+        format: vk::Format,
+        extent: &vk::Extent2D,
+        usage: vk::ImageUsageFlags,
+        array_layers: u32,
+        mip_levels: u32,
+        component_mapping: vk::ComponentMapping,
+        samples: vk::SampleCountFlags,
     ) -> Result<Image> {
         let tiling = vk::ImageTiling::OPTIMAL;
         let (flags, image_view_type) = if array_layers == 1 {
@@ -357,13 +461,6 @@ impl VulkanContext {
             )
         };
 
-        // TODO: This indicates that it's MSAA.. but do we need MSAA for depth?
-        let samples = if usage.contains(vk::ImageUsageFlags::TRANSIENT_ATTACHMENT) {
-            SAMPLES
-        } else {
-            vk::SampleCountFlags::TYPE_1
-        };
-
         let create_info = vk::ImageCreateInfo::builder()
             .format(format)
             .image_type(vk::ImageType::TYPE_2D)
@@ -677,6 +774,45 @@ impl VulkanContext {
         }
     }
 
+    /// The maximum level of anisotropic filtering, or zero if the device doesn't support it.
+    pub fn max_sampler_anisotropy(&self) -> f32 {
+        let features = unsafe {
+            self.instance
+                .get_physical_device_features(self.physical_device)
+        };
+        if features.sampler_anisotropy == vk::TRUE {
+            self.physical_device_properties
+                .limits
+                .max_sampler_anisotropy
+        } else {
+            0.
+        }
+    }
+
+    /// The sample counts that can be used for both color and depth framebuffer attachments.
+    pub fn framebuffer_sample_counts(&self) -> vk::SampleCountFlags {
+        let limits = &self.physical_device_properties.limits;
+        limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts
+    }
+
+    /// Whether triangles can be drawn as lines, for [`RenderMode::Wireframe`](crate::rendering::render_mode::RenderMode).
+    pub fn supports_wireframe(&self) -> bool {
+        let features = unsafe {
+            self.instance
+                .get_physical_device_features(self.physical_device)
+        };
+        features.fill_mode_non_solid == vk::TRUE
+    }
+
+    /// Whether alpha-to-coverage can also force the alpha written to the color attachment to one.
+    pub fn supports_alpha_to_one(&self) -> bool {
+        let features = unsafe {
+            self.instance
+                .get_physical_device_features(self.physical_device)
+        };
+        features.alpha_to_one == vk::TRUE
+    }
+
     pub fn copy_buffer_to_image(
         &self,
         src_buffer: vk::Buffer,
@@ -685,36 +821,7 @@ impl VulkanContext {
         offsets: Vec<vk::DeviceSize>,
     ) {
         let command_buffer = self.begin_single_time_commands();
-
-        let mut regions = Vec::new();
-
-        let mut offset = 0;
-
-        // KR: https://bit.ly/3ABKTFc
-        for (mip_level, offset_increment) in offsets.iter().enumerate() {
-            let image_subresource = vk::ImageSubresourceLayers::builder()
-                .aspect_mask(vk::ImageAspectFlags::COLOR)
-                .mip_level(mip_level as _)
-                .base_array_layer(0)
-                .layer_count(layer_count);
-
-            let image_extent = vk::Extent3D {
-                width: dst_image.extent.width >> mip_level,
-                height: dst_image.extent.height >> mip_level,
-                depth: 1,
-            };
-
-            let region = vk::BufferImageCopy::builder()
-                .buffer_offset(offset)
-                .buffer_row_length(0)
-                .buffer_image_height(0)
-                .image_subresource(*image_subresource)
-                .image_extent(image_extent)
-                .build();
-            regions.push(region);
-            offset += offset_increment * u64::from(layer_count);
-        }
-
+        let regions = get_buffer_image_copy_regions(dst_image, layer_count, &offsets, 0);
         let dst_image_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
 
         unsafe {
@@ -770,28 +877,23 @@ impl VulkanContext {
         self.end_single_time_commands(command_buffer);
     }
 
-    #[cfg(not(debug_assertions))]
-    pub fn set_debug_name(
-        &self,
-        _object_type: ObjectType,
-        _object_handle: u64,
-        _object_name: &str,
-    ) -> VkResult<()> {
-        VkResult::Ok(())
-    }
-
-    #[cfg(debug_assertions)]
+    /// Give an object a name that shows up in validation messages and debugging tools. Does nothing if
+    /// `VK_EXT_debug_utils` isn't available.
     pub fn set_debug_name(
         &self,
         object_type: ObjectType,
         object_handle: u64,
         object_name: &str,
     ) -> VkResult<()> {
+        let debug_utils = match &self.debug_utils {
+            Some(debug_utils) => debug_utils,
+            None => return Ok(()),
+        };
         let object_name = CString::new(object_name).unwrap();
         unsafe {
-            self.debug_utils.debug_utils_set_object_name(
+            debug_utils.debug_utils_set_object_name(
                 self.device.handle(),
-                &DebugUtilsObjectNameInfoEXT::builder()
+                &vk::DebugUtilsObjectNameInfoEXT::builder()
                     .object_type(object_type)
                     .object_handle(object_handle)
                     .object_name(object_name.as_c_str()),
@@ -799,6 +901,28 @@ impl VulkanContext {
         }
     }
 
+    /// Open a labelled region of `command_buffer`, which debugging tools like RenderDoc show as a group of
+    /// commands. Every region must be closed with [`VulkanContext::end_debug_label`], in the same command buffer.
+    /// Does nothing if `VK_EXT_debug_utils` isn't available.
+    pub fn begin_debug_label(&self, command_buffer: vk::CommandBuffer, label: &str) {
+        if let Some(debug_utils) = &self.debug_utils {
+            let label = CString::new(label).unwrap();
+            unsafe {
+                debug_utils.cmd_begin_debug_utils_label(
+                    command_buffer,
+                    &vk::DebugUtilsLabelEXT::builder().label_name(&label),
+                );
+            }
+        }
+    }
+
+    /// Close the region most recently opened with [`VulkanContext::begin_debug_label`].
+    pub fn end_debug_label(&self, command_buffer: vk::CommandBuffer) {
+        if let Some(debug_utils) = &self.debug_utils {
+            unsafe { debug_utils.cmd_end_debug_utils_label(command_buffer) };
+        }
+    }
+
     pub fn upload_image(
         &self,
         image_buf: &[u8],
@@ -913,9 +1037,97 @@ fn create_descriptor_pool(device: &Device) -> Result<vk::DescriptorPool, anyhow:
     Ok(descriptor_pool)
 }
 
+/// The debugging features enabled on the instance.
+struct DebugSupport {
+    /// `VK_EXT_debug_utils` is enabled. It's enabled whenever the loader supports it, as naming objects and labelling
+    /// command buffers is cheap.
+    debug_utils: bool,
+    /// The Khronos validation layer is enabled.
+    validation: bool,
+}
+
+impl DebugSupport {
+    /// Find out which debugging features can be enabled. Validation is only enabled if it was asked for, and the
+    /// layer is installed.
+    fn new(entry: &Entry, validation: bool) -> Result<Self> {
+        let debug_utils = entry
+            .enumerate_instance_extension_properties()?
+            .iter()
+            .any(|e| unsafe { CStr::from_ptr(e.extension_name.as_ptr()) } == DebugUtils::name());
+        if !debug_utils {
+            println!(
+                "[HOTHAM_VULKAN] VK_EXT_debug_utils is not supported, objects will not be named"
+            );
+        }
+
+        let validation = validation
+            && {
+                let available = entry.enumerate_instance_layer_properties()?.iter().any(
+                    |l| unsafe { CStr::from_ptr(l.layer_name.as_ptr()) } == VALIDATION_LAYER_NAME,
+                );
+                if available {
+                    println!("[HOTHAM_VULKAN] Enabling validation layer {VALIDATION_LAYER_NAME:?}");
+                } else {
+                    println!("[HOTHAM_VULKAN] Validation layer {VALIDATION_LAYER_NAME:?} is not installed, validation is disabled");
+                }
+                available
+            };
+
+        Ok(Self {
+            debug_utils,
+            validation,
+        })
+    }
+
+    fn layer_names(&self) -> Vec<*const c_char> {
+        if self.validation {
+            vec![VALIDATION_LAYER_NAME.as_ptr()]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn extension_names(&self) -> Vec<*const c_char> {
+        if self.debug_utils {
+            vec![DebugUtils::name().as_ptr()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+fn create_debug_messenger(debug_utils: &DebugUtils) -> VkResult<vk::DebugUtilsMessengerEXT> {
+    let create_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+        .message_severity(
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+        )
+        .message_type(
+            vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+                | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE,
+        )
+        .pfn_user_callback(Some(debug_messenger_callback));
+
+    unsafe { debug_utils.create_debug_utils_messenger(&create_info, None) }
+}
+
+unsafe extern "system" fn debug_messenger_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    _user_data: *mut c_void,
+) -> vk::Bool32 {
+    let message = CStr::from_ptr((*callback_data).p_message).to_string_lossy();
+    println!("[HOTHAM_VULKAN] {message_severity:?} {message_type:?}: {message}");
+    vk::FALSE
+}
+
 fn get_aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
     if format == DEPTH_FORMAT {
         vk::ImageAspectFlags::DEPTH
+    } else if format == DEPTH_STENCIL_FORMAT {
+        vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
     } else {
         vk::ImageAspectFlags::COLOR
     }
@@ -936,19 +1148,15 @@ fn vulkan_init_legacy(
     system: xr::SystemId,
     application_name: &str,
     application_version: u32,
-) -> Result<(AshInstance, Entry)> {
-    use crate::util::get_raw_strings;
-
+    validation: bool,
+) -> Result<(AshInstance, Entry, DebugSupport)> {
     println!("[HOTHAM_VULKAN] Initializing Vulkan..");
     unsafe {
         let entry = Entry::new()?;
 
-        let layers = vec![];
-        println!("[HOTHAM_VULKAN] Requesting layers: {layers:?}");
+        let debug_support = DebugSupport::new(&entry, validation)?;
+        let layer_names = debug_support.layer_names();
 
-        let layer_names = get_raw_strings(layers);
-
-        #[allow(unused_mut)]
         let mut vk_instance_exts = xr_instance
             .vulkan_legacy_instance_extensions(system)
             .unwrap()
@@ -956,8 +1164,9 @@ fn vulkan_init_legacy(
             .map(|x| CString::new(x).unwrap())
             .collect::<Vec<_>>();
 
-        #[cfg(debug_assertions)]
-        vk_instance_exts.push(vk::ExtDebugUtilsFn::name().to_owned());
+        if debug_support.debug_utils {
+            vk_instance_exts.push(DebugUtils::name().to_owned());
+        }
 
         println!("[HOTHAM_VULKAN] Required Vulkan instance extensions: {vk_instance_exts:?}");
         let vk_instance_ext_pointers = vk_instance_exts
@@ -984,7 +1193,7 @@ fn vulkan_init_legacy(
             )
             .expect("Vulkan error creating Vulkan instance");
 
-        Ok((instance, entry))
+        Ok((instance, entry, debug_support))
     }
 }
 
@@ -1036,6 +1245,20 @@ pub fn create_vulkan_device_legacy(
     create_vulkan_device(&extension_names, vulkan_instance, physical_device)
 }
 
+/// Optional core features we use if the device supports them.
+fn get_enabled_device_features(
+    instance: &AshInstance,
+    physical_device: vk::PhysicalDevice,
+) -> vk::PhysicalDeviceFeatures {
+    let supported = unsafe { instance.get_physical_device_features(physical_device) };
+    vk::PhysicalDeviceFeatures {
+        sampler_anisotropy: supported.sampler_anisotropy,
+        fill_mode_non_solid: supported.fill_mode_non_solid,
+        alpha_to_one: supported.alpha_to_one,
+        ..Default::default()
+    }
+}
+
 fn create_vulkan_device(
     extension_names: &[std::ffi::CString],
     vulkan_instance: &AshInstance,
@@ -1087,14 +1310,21 @@ fn create_vulkan_device(
         .shader_float16(true)
         .shader_int8(true);
 
+    let mut timeline_semaphore =
+        vk::PhysicalDeviceTimelineSemaphoreFeatures::builder().timeline_semaphore(true);
+
+    let enabled_features = get_enabled_device_features(vulkan_instance, physical_device);
+
     let device_create_info = vk::DeviceCreateInfo::builder()
         .queue_create_infos(slice_from_ref(&queue_create_info))
         .enabled_extension_names(&extension_names)
+        .enabled_features(&enabled_features)
         .push_next(&mut descriptor_indexing_features)
         .push_next(&mut robust_features)
         .push_next(&mut multiview_features)
         .push_next(&mut f16_storage)
-        .push_next(&mut f16_arithmetic);
+        .push_next(&mut f16_arithmetic)
+        .push_next(&mut timeline_semaphore);
 
     let device =
         unsafe { vulkan_instance.create_device(physical_device, &device_create_info, None) }?;
@@ -1106,6 +1336,45 @@ fn create_vulkan_device(
     Ok((device, graphics_queue, graphics_family_index))
 }
 
+/// Get the regions needed to copy every mip level of `dst_image` from a buffer, starting at `buffer_offset`.
+/// `offsets` contains the size of each mip level.
+pub(crate) fn get_buffer_image_copy_regions(
+    dst_image: &Image,
+    layer_count: u32,
+    offsets: &[vk::DeviceSize],
+    buffer_offset: vk::DeviceSize,
+) -> Vec<vk::BufferImageCopy> {
+    let mut regions = Vec::new();
+    let mut offset = buffer_offset;
+
+    // KR: https://bit.ly/3ABKTFc
+    for (mip_level, offset_increment) in offsets.iter().enumerate() {
+        let image_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(mip_level as _)
+            .base_array_layer(0)
+            .layer_count(layer_count);
+
+        let image_extent = vk::Extent3D {
+            width: (dst_image.extent.width >> mip_level).max(1),
+            height: (dst_image.extent.height >> mip_level).max(1),
+            depth: 1,
+        };
+
+        let region = vk::BufferImageCopy::builder()
+            .buffer_offset(offset)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(*image_subresource)
+            .image_extent(image_extent)
+            .build();
+        regions.push(region);
+        offset += offset_increment * u64::from(layer_count);
+    }
+
+    regions
+}
+
 fn get_stage(
     old_layout: vk::ImageLayout,
     new_layout: vk::ImageLayout,
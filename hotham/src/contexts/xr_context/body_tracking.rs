@@ -0,0 +1,262 @@
+use std::ffi::{c_void, CStr};
+
+use anyhow::{anyhow, Result};
+use glam::Affine3A;
+use openxr::{self as xr, sys, Session, Vulkan};
+
+use crate::{components::body_joints::BODY_JOINT_COUNT, util::affine_from_posef};
+
+/// The extension for tracking the player's body. `openxr` doesn't know about it yet, so it's enabled by name and its
+/// types and functions are declared here.
+pub(crate) const FB_BODY_TRACKING: &str = "XR_FB_body_tracking";
+
+/// Where each joint of the player's body is, in the space it was located in
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyJointLocations {
+    /// How confident the runtime is in the joints, from 0 to 1
+    pub confidence: f32,
+    /// The pose of every joint, or `None` for joints the runtime couldn't estimate. Indexed like
+    /// [`BodyJoints::joints`](crate::components::BodyJoints::joints).
+    pub joints: Vec<Option<Affine3A>>,
+}
+
+const TYPE_BODY_TRACKER_CREATE_INFO_FB: sys::StructureType =
+    sys::StructureType::from_raw(1000076001);
+const TYPE_BODY_JOINTS_LOCATE_INFO_FB: sys::StructureType =
+    sys::StructureType::from_raw(1000076002);
+const TYPE_SYSTEM_BODY_TRACKING_PROPERTIES_FB: sys::StructureType =
+    sys::StructureType::from_raw(1000076004);
+const TYPE_BODY_JOINT_LOCATIONS_FB: sys::StructureType = sys::StructureType::from_raw(1000076005);
+const BODY_JOINT_SET_DEFAULT_FB: i32 = 0;
+
+type BodyTrackerFB = u64;
+
+#[repr(C)]
+struct SystemBodyTrackingPropertiesFB {
+    ty: sys::StructureType,
+    next: *mut c_void,
+    supports_body_tracking: sys::Bool32,
+}
+
+#[repr(C)]
+struct BodyTrackerCreateInfoFB {
+    ty: sys::StructureType,
+    next: *const c_void,
+    body_joint_set: i32,
+}
+
+#[repr(C)]
+struct BodyJointsLocateInfoFB {
+    ty: sys::StructureType,
+    next: *const c_void,
+    base_space: sys::Space,
+    time: sys::Time,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct BodyJointLocationFB {
+    location_flags: sys::SpaceLocationFlags,
+    pose: sys::Posef,
+}
+
+#[repr(C)]
+struct BodyJointLocationsFB {
+    ty: sys::StructureType,
+    next: *mut c_void,
+    is_active: sys::Bool32,
+    confidence: f32,
+    joint_count: u32,
+    joint_locations: *mut BodyJointLocationFB,
+    skeleton_changed_count: u32,
+    time: sys::Time,
+}
+
+type CreateBodyTrackerFB = unsafe extern "system" fn(
+    sys::Session,
+    *const BodyTrackerCreateInfoFB,
+    *mut BodyTrackerFB,
+) -> sys::Result;
+type DestroyBodyTrackerFB = unsafe extern "system" fn(BodyTrackerFB) -> sys::Result;
+type LocateBodyJointsFB = unsafe extern "system" fn(
+    BodyTrackerFB,
+    *const BodyJointsLocateInfoFB,
+    *mut BodyJointLocationsFB,
+) -> sys::Result;
+
+/// Tracks the joints of the player's body, using `XR_FB_body_tracking`. The runtime estimates the upper body from
+/// where the headset and controllers or hands are, so avatars can be posed without any IK of their own.
+///
+/// The joints are located each frame by the [`InputContext`](crate::contexts::InputContext), and copied into
+/// [`BodyJoints`](crate::components::BodyJoints) components by the
+/// [`body_tracking_system`](crate::systems::body_tracking_system). On Quest, the application must also request the
+/// `com.oculus.permission.BODY_TRACKING` permission and declare `com.oculus.software.body_tracking` in its Android
+/// manifest.
+pub struct BodyTracking {
+    body_tracker: BodyTrackerFB,
+    destroy_body_tracker: DestroyBodyTrackerFB,
+    locate_body_joints: LocateBodyJointsFB,
+}
+
+impl BodyTracking {
+    /// Create a body tracker. Returns `None` if `XR_FB_body_tracking` wasn't enabled, eg. because the runtime doesn't
+    /// support it, or if the system can't track bodies.
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        session: &Session<Vulkan>,
+        system: xr::SystemId,
+        enabled: bool,
+    ) -> Result<Option<Self>> {
+        if !enabled {
+            println!("[HOTHAM_XR] {FB_BODY_TRACKING} is not supported, body tracking is disabled");
+            return Ok(None);
+        }
+
+        let mut body_tracking_properties = SystemBodyTrackingPropertiesFB {
+            ty: TYPE_SYSTEM_BODY_TRACKING_PROPERTIES_FB,
+            next: std::ptr::null_mut(),
+            supports_body_tracking: false.into(),
+        };
+        // SAFETY: Zero is a valid value for every field of the properties, which the runtime overwrites.
+        let mut system_properties: sys::SystemProperties = unsafe { std::mem::zeroed() };
+        system_properties.ty = sys::SystemProperties::TYPE;
+        system_properties.next = &mut body_tracking_properties as *mut _ as _;
+        check(unsafe {
+            (instance.fp().get_system_properties)(instance.as_raw(), system, &mut system_properties)
+        })?;
+        if !bool::from(body_tracking_properties.supports_body_tracking) {
+            println!("[HOTHAM_XR] This system can't track bodies, body tracking is disabled");
+            return Ok(None);
+        }
+
+        // SAFETY: Each function is cast to its signature in the extension.
+        let (create_body_tracker, destroy_body_tracker, locate_body_joints) = unsafe {
+            (
+                std::mem::transmute::<_, CreateBodyTrackerFB>(load_function(
+                    instance,
+                    b"xrCreateBodyTrackerFB\0",
+                )?),
+                std::mem::transmute::<_, DestroyBodyTrackerFB>(load_function(
+                    instance,
+                    b"xrDestroyBodyTrackerFB\0",
+                )?),
+                std::mem::transmute::<_, LocateBodyJointsFB>(load_function(
+                    instance,
+                    b"xrLocateBodyJointsFB\0",
+                )?),
+            )
+        };
+
+        let create_info = BodyTrackerCreateInfoFB {
+            ty: TYPE_BODY_TRACKER_CREATE_INFO_FB,
+            next: std::ptr::null(),
+            body_joint_set: BODY_JOINT_SET_DEFAULT_FB,
+        };
+        let mut body_tracker = 0;
+        check(unsafe { create_body_tracker(session.as_raw(), &create_info, &mut body_tracker) })?;
+
+        println!("[HOTHAM_XR] Body tracking enabled");
+        Ok(Some(Self {
+            body_tracker,
+            destroy_body_tracker,
+            locate_body_joints,
+        }))
+    }
+
+    /// Locate every joint of the body relative to `base_space`. Returns `None` if the body isn't being tracked.
+    pub fn locate(
+        &self,
+        base_space: &xr::Space,
+        time: xr::Time,
+    ) -> Result<Option<BodyJointLocations>> {
+        let locate_info = BodyJointsLocateInfoFB {
+            ty: TYPE_BODY_JOINTS_LOCATE_INFO_FB,
+            next: std::ptr::null(),
+            base_space: base_space.as_raw(),
+            time,
+        };
+        // SAFETY: Zero is a valid value for every field of a location, which the runtime overwrites.
+        let mut joint_locations: [BodyJointLocationFB; BODY_JOINT_COUNT] =
+            unsafe { std::mem::zeroed() };
+        let mut locations = BodyJointLocationsFB {
+            ty: TYPE_BODY_JOINT_LOCATIONS_FB,
+            next: std::ptr::null_mut(),
+            is_active: false.into(),
+            confidence: 0.,
+            joint_count: BODY_JOINT_COUNT as _,
+            joint_locations: joint_locations.as_mut_ptr(),
+            skeleton_changed_count: 0,
+            time: xr::Time::from_nanos(0),
+        };
+        check(unsafe {
+            (self.locate_body_joints)(self.body_tracker, &locate_info, &mut locations)
+        })?;
+        if !bool::from(locations.is_active) {
+            return Ok(None);
+        }
+
+        Ok(Some(BodyJointLocations {
+            confidence: locations.confidence,
+            joints: joint_locations
+                .iter()
+                .map(|location| {
+                    is_joint_valid(location.location_flags)
+                        .then(|| affine_from_posef(location.pose))
+                })
+                .collect(),
+        }))
+    }
+}
+
+impl Drop for BodyTracking {
+    fn drop(&mut self) {
+        unsafe {
+            (self.destroy_body_tracker)(self.body_tracker);
+        }
+    }
+}
+
+fn is_joint_valid(location_flags: sys::SpaceLocationFlags) -> bool {
+    location_flags.contains(
+        sys::SpaceLocationFlags::POSITION_VALID | sys::SpaceLocationFlags::ORIENTATION_VALID,
+    )
+}
+
+fn load_function(
+    instance: &xr::Instance,
+    name: &'static [u8],
+) -> Result<unsafe extern "system" fn()> {
+    let name = CStr::from_bytes_with_nul(name).unwrap();
+    let mut function = None;
+    check(unsafe {
+        (instance.entry().fp().get_instance_proc_addr)(
+            instance.as_raw(),
+            name.as_ptr(),
+            &mut function,
+        )
+    })?;
+    function.ok_or_else(|| anyhow!("The runtime doesn't have {name:?}"))
+}
+
+fn check(result: sys::Result) -> Result<()> {
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_is_joint_valid() {
+        assert!(is_joint_valid(
+            sys::SpaceLocationFlags::POSITION_VALID
+                | sys::SpaceLocationFlags::ORIENTATION_VALID
+                | sys::SpaceLocationFlags::POSITION_TRACKED
+        ));
+        assert!(!is_joint_valid(sys::SpaceLocationFlags::POSITION_VALID));
+        assert!(!is_joint_valid(sys::SpaceLocationFlags::EMPTY));
+    }
+}
@@ -0,0 +1,80 @@
+use openxr::sys;
+
+/// How much of the compositor's GPU time a filter may use
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LayerFilter {
+    /// The filter isn't applied
+    #[default]
+    Off,
+    /// A cheaper version of the filter
+    Normal,
+    /// A better looking, but more expensive, version of the filter
+    Quality,
+}
+
+/// Filters the compositor applies to the application's frames as it draws them to the display, if the runtime
+/// supports `XR_FB_composition_layer_settings`. Both are off by default.
+///
+/// Set with [`EngineBuilder::composition_layer_settings`](crate::EngineBuilder::composition_layer_settings), or at
+/// any time with [`XrContext::composition_layer_settings`](super::XrContext::composition_layer_settings).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompositionLayerSettings {
+    /// Sample each frame more than once per pixel, which reduces the shimmering of fine details, eg. text and thin
+    /// lines, as the player moves their head
+    pub super_sampling: LayerFilter,
+    /// Sharpen each frame, which makes text and edges crisper, but can make aliasing more visible
+    pub sharpening: LayerFilter,
+}
+
+impl CompositionLayerSettings {
+    /// Is either filter applied?
+    pub fn is_enabled(&self) -> bool {
+        *self != Self::default()
+    }
+
+    pub(crate) fn layer_flags(&self) -> sys::CompositionLayerSettingsFlagsFB {
+        let super_sampling = match self.super_sampling {
+            LayerFilter::Off => sys::CompositionLayerSettingsFlagsFB::EMPTY,
+            LayerFilter::Normal => sys::CompositionLayerSettingsFlagsFB::NORMAL_SUPER_SAMPLING,
+            LayerFilter::Quality => sys::CompositionLayerSettingsFlagsFB::QUALITY_SUPER_SAMPLING,
+        };
+        let sharpening = match self.sharpening {
+            LayerFilter::Off => sys::CompositionLayerSettingsFlagsFB::EMPTY,
+            LayerFilter::Normal => sys::CompositionLayerSettingsFlagsFB::NORMAL_SHARPENING,
+            LayerFilter::Quality => sys::CompositionLayerSettingsFlagsFB::QUALITY_SHARPENING,
+        };
+        super_sampling | sharpening
+    }
+
+    /// The settings to chain onto a composition layer, if either filter is applied
+    pub(crate) fn to_raw(self) -> Option<sys::CompositionLayerSettingsFB> {
+        self.is_enabled().then(|| sys::CompositionLayerSettingsFB {
+            ty: sys::CompositionLayerSettingsFB::TYPE,
+            next: std::ptr::null(),
+            layer_flags: self.layer_flags(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_composition_layer_settings() {
+        let settings = CompositionLayerSettings::default();
+        assert!(!settings.is_enabled());
+        assert!(settings.to_raw().is_none());
+
+        let settings = CompositionLayerSettings {
+            super_sampling: LayerFilter::Normal,
+            sharpening: LayerFilter::Quality,
+        };
+        assert!(settings.is_enabled());
+        assert_eq!(
+            settings.to_raw().unwrap().layer_flags,
+            sys::CompositionLayerSettingsFlagsFB::NORMAL_SUPER_SAMPLING
+                | sys::CompositionLayerSettingsFlagsFB::QUALITY_SHARPENING
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use openxr::{self as xr, Session, Vulkan};
+
+use crate::components::hand::Handedness;
+
+/// Load the runtime's model of the controller in one of the player's hands, as a GLB file, using
+/// `XR_FB_render_model` or `XR_MSFT_controller_model`. Returns `None` if neither extension was enabled, or if the
+/// runtime doesn't have a model for the controller yet, eg. because it hasn't connected.
+pub(crate) fn load_controller_model(
+    session: &Session<Vulkan>,
+    handedness: Handedness,
+) -> Result<Option<Vec<u8>>> {
+    let instance = session.instance();
+    if let Some(fp) = instance.exts().fb_render_model {
+        let path = match handedness {
+            Handedness::Left => "/model_fb/controller/left",
+            Handedness::Right => "/model_fb/controller/right",
+        };
+        return load_render_model_fb(&fp, instance, session, path);
+    }
+    if let Some(fp) = instance.exts().msft_controller_model {
+        return load_controller_model_msft(&fp, instance, session, handedness);
+    }
+    Ok(None)
+}
+
+/// The paths of every model the runtime can provide with `XR_FB_render_model`, eg. `/model_fb/keyboard/local`.
+/// Returns nothing if the extension wasn't enabled.
+pub(crate) fn render_model_paths(session: &Session<Vulkan>) -> Result<Vec<String>> {
+    let instance = session.instance();
+    let fp = match instance.exts().fb_render_model {
+        Some(fp) => fp,
+        None => return Ok(Vec::new()),
+    };
+    enumerate_render_model_paths(&fp, session)?
+        .into_iter()
+        .map(|path| Ok(instance.path_to_string(path)?))
+        .collect()
+}
+
+/// Load one of the models from [`render_model_paths`], as a GLB file. Returns `None` if `XR_FB_render_model` wasn't
+/// enabled, or if the runtime doesn't have the model yet, eg. because the device hasn't connected.
+pub(crate) fn load_render_model(session: &Session<Vulkan>, path: &str) -> Result<Option<Vec<u8>>> {
+    let instance = session.instance();
+    match instance.exts().fb_render_model {
+        Some(fp) => load_render_model_fb(&fp, instance, session, path),
+        None => Ok(None),
+    }
+}
+
+fn enumerate_render_model_paths(
+    fp: &xr::raw::RenderModelFB,
+    session: &Session<Vulkan>,
+) -> Result<Vec<xr::Path>> {
+    let mut path_count = 0;
+    check(unsafe {
+        (fp.enumerate_render_model_paths)(
+            session.as_raw(),
+            0,
+            &mut path_count,
+            std::ptr::null_mut(),
+        )
+    })?;
+    let mut paths = vec![
+        xr::sys::RenderModelPathInfoFB {
+            ty: xr::sys::RenderModelPathInfoFB::TYPE,
+            next: std::ptr::null_mut(),
+            path: xr::Path::NULL,
+        };
+        path_count as usize
+    ];
+    check(unsafe {
+        (fp.enumerate_render_model_paths)(
+            session.as_raw(),
+            path_count,
+            &mut path_count,
+            paths.as_mut_ptr(),
+        )
+    })?;
+    Ok(paths.into_iter().map(|path_info| path_info.path).collect())
+}
+
+fn load_render_model_fb(
+    fp: &xr::raw::RenderModelFB,
+    instance: &xr::Instance,
+    session: &Session<Vulkan>,
+    path: &str,
+) -> Result<Option<Vec<u8>>> {
+    let path = instance.string_to_path(path)?;
+
+    // The runtime only hands out models for paths that have been enumerated.
+    if !enumerate_render_model_paths(fp, session)?.contains(&path) {
+        return Ok(None);
+    }
+
+    // Ask for models that only use the parts of glTF our importer understands.
+    let mut capabilities = xr::sys::RenderModelCapabilitiesRequestFB {
+        ty: xr::sys::RenderModelCapabilitiesRequestFB::TYPE,
+        next: std::ptr::null_mut(),
+        flags: xr::sys::RenderModelFlagsFB::SUPPORTS_GLTF_2_0_SUBSET_2,
+    };
+    // SAFETY: Zero is a valid value for every field of the properties, which the runtime overwrites.
+    let mut properties: xr::sys::RenderModelPropertiesFB = unsafe { std::mem::zeroed() };
+    properties.ty = xr::sys::RenderModelPropertiesFB::TYPE;
+    properties.next = &mut capabilities as *mut _ as _;
+    check(unsafe { (fp.get_render_model_properties)(session.as_raw(), path, &mut properties) })?;
+    if properties.model_key.into_raw() == 0 {
+        return Ok(None);
+    }
+
+    let load_info = xr::sys::RenderModelLoadInfoFB {
+        ty: xr::sys::RenderModelLoadInfoFB::TYPE,
+        next: std::ptr::null_mut(),
+        model_key: properties.model_key,
+    };
+    let mut buffer = xr::sys::RenderModelBufferFB {
+        ty: xr::sys::RenderModelBufferFB::TYPE,
+        next: std::ptr::null_mut(),
+        buffer_capacity_input: 0,
+        buffer_count_output: 0,
+        buffer: std::ptr::null_mut(),
+    };
+    check(unsafe { (fp.load_render_model)(session.as_raw(), &load_info, &mut buffer) })?;
+    let mut data = vec![0; buffer.buffer_count_output as usize];
+    buffer.buffer_capacity_input = data.len() as _;
+    buffer.buffer = data.as_mut_ptr();
+    check(unsafe { (fp.load_render_model)(session.as_raw(), &load_info, &mut buffer) })?;
+
+    Ok(Some(data))
+}
+
+fn load_controller_model_msft(
+    fp: &xr::raw::ControllerModelMSFT,
+    instance: &xr::Instance,
+    session: &Session<Vulkan>,
+    handedness: Handedness,
+) -> Result<Option<Vec<u8>>> {
+    let path = instance.string_to_path(match handedness {
+        Handedness::Left => "/user/hand/left",
+        Handedness::Right => "/user/hand/right",
+    })?;
+
+    let mut key_state = xr::sys::ControllerModelKeyStateMSFT {
+        ty: xr::sys::ControllerModelKeyStateMSFT::TYPE,
+        next: std::ptr::null_mut(),
+        model_key: xr::sys::ControllerModelKeyMSFT::from_raw(0),
+    };
+    check(unsafe { (fp.get_controller_model_key)(session.as_raw(), path, &mut key_state) })?;
+    if key_state.model_key.into_raw() == 0 {
+        return Ok(None);
+    }
+
+    let mut size = 0;
+    check(unsafe {
+        (fp.load_controller_model)(
+            session.as_raw(),
+            key_state.model_key,
+            0,
+            &mut size,
+            std::ptr::null_mut(),
+        )
+    })?;
+    let mut data = vec![0; size as usize];
+    check(unsafe {
+        (fp.load_controller_model)(
+            session.as_raw(),
+            key_state.model_key,
+            size,
+            &mut size,
+            data.as_mut_ptr(),
+        )
+    })?;
+
+    Ok(Some(data))
+}
+
+fn check(result: xr::sys::Result) -> Result<()> {
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
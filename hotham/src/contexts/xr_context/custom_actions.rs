@@ -0,0 +1,336 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use glam::Affine3A;
+use openxr::{self as xr, Action, ActionSet, Posef, Session, Space, Vulkan};
+
+use crate::util::{affine_from_posef, is_space_valid};
+
+/// The kind of value a [`CustomAction`] produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CustomActionType {
+    /// A button, eg. `/input/x/click`
+    Boolean,
+    /// An analog value between 0 and 1, eg. `/input/trigger/value`
+    Float,
+    /// Two analog values between -1 and 1, eg. `/input/thumbstick`
+    Vector2,
+    /// A pose, eg. `/input/grip/pose`
+    Pose,
+}
+
+/// An action defined by the application, with the inputs the runtime should bind it to
+#[derive(Debug, Clone)]
+pub struct CustomAction {
+    /// The name of the action, which it's queried by. Must be unique across every [`CustomActionSet`], and only
+    /// use lowercase letters, numbers, dashes, underscores and periods.
+    pub name: String,
+    /// The name of the action shown to the player, eg. when they rebind it
+    pub localized_name: String,
+    /// What kind of value the action produces
+    pub action_type: CustomActionType,
+    /// The suggested bindings, as pairs of interaction profile and input path, eg.
+    /// `("/interaction_profiles/oculus/touch_controller", "/user/hand/left/input/x/click")`
+    pub bindings: Vec<(String, String)>,
+}
+
+impl CustomAction {
+    /// Create an action with no bindings
+    pub fn new(name: &str, localized_name: &str, action_type: CustomActionType) -> Self {
+        Self {
+            name: name.to_string(),
+            localized_name: localized_name.to_string(),
+            action_type,
+            bindings: Vec::new(),
+        }
+    }
+
+    /// Suggest binding the action to `path` on controllers that use `interaction_profile`
+    pub fn binding(mut self, interaction_profile: &str, path: &str) -> Self {
+        self.bindings
+            .push((interaction_profile.to_string(), path.to_string()));
+        self
+    }
+}
+
+/// A group of [`CustomAction`]s, registered with
+/// [`EngineBuilder::custom_action_sets`](crate::EngineBuilder::custom_action_sets)
+///
+/// Every set is attached to the session when it's created, as OpenXR doesn't allow attaching more later, but only the
+/// active ones are synced each frame. Splitting actions by application state, eg. into `gameplay` and `menu` sets,
+/// and switching between them with [`CustomActions::set_active`] stops the menu's input from firing gameplay
+/// actions. Hotham's own actions, which drive the [`InputContext`](crate::contexts::InputContext), are always active,
+/// but a set with a higher priority than 0 takes any inputs it shares with them.
+#[derive(Debug, Clone)]
+pub struct CustomActionSet {
+    /// The name of the action set. Follows the same rules as [`CustomAction::name`].
+    pub name: String,
+    /// The name of the action set shown to the player
+    pub localized_name: String,
+    /// If an input is bound in more than one action set, only the actions in the set with the highest priority
+    /// receive it
+    pub priority: u32,
+    /// The actions in the set
+    pub actions: Vec<CustomAction>,
+    /// Whether the set is active when the session starts
+    pub active: bool,
+}
+
+impl CustomActionSet {
+    /// Create an action set with no actions
+    pub fn new(name: &str, localized_name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            localized_name: localized_name.to_string(),
+            priority: 0,
+            actions: Vec::new(),
+            active: true,
+        }
+    }
+
+    /// Don't sync the set until it's made active with [`CustomActions::set_active`]
+    pub fn inactive(mut self) -> Self {
+        self.active = false;
+        self
+    }
+
+    /// Add an action to the set
+    pub fn action(mut self, action: CustomAction) -> Self {
+        self.actions.push(action);
+        self
+    }
+}
+
+enum CustomActionHandle {
+    Boolean(Action<bool>),
+    Float(Action<f32>),
+    Vector2(Action<xr::Vector2f>),
+    Pose(Action<Posef>, Space),
+}
+
+struct CustomActionEntry {
+    handle: CustomActionHandle,
+    bindings: Vec<(String, String)>,
+}
+
+struct CustomActionSetEntry {
+    name: String,
+    action_set: ActionSet,
+    active: bool,
+}
+
+/// The actions registered by the application, which can be queried by name each frame
+#[derive(Default)]
+pub struct CustomActions {
+    action_sets: Vec<CustomActionSetEntry>,
+    actions: HashMap<String, CustomActionEntry>,
+}
+
+impl CustomActions {
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        session: &Session<Vulkan>,
+        custom_action_sets: &[CustomActionSet],
+    ) -> Result<Self> {
+        let mut custom_actions = CustomActions::default();
+
+        for custom_action_set in custom_action_sets {
+            if custom_actions
+                .action_sets
+                .iter()
+                .any(|entry| entry.name == custom_action_set.name)
+            {
+                return Err(anyhow!(
+                    "There's more than one action set named {}",
+                    custom_action_set.name
+                ));
+            }
+
+            let action_set = instance.create_action_set(
+                &custom_action_set.name,
+                &custom_action_set.localized_name,
+                custom_action_set.priority,
+            )?;
+
+            for action in &custom_action_set.actions {
+                if custom_actions.actions.contains_key(&action.name) {
+                    return Err(anyhow!(
+                        "There's more than one action named {}",
+                        action.name
+                    ));
+                }
+
+                let name = &action.name;
+                let localized_name = &action.localized_name;
+                let handle = match action.action_type {
+                    CustomActionType::Boolean => CustomActionHandle::Boolean(
+                        action_set.create_action(name, localized_name, &[])?,
+                    ),
+                    CustomActionType::Float => CustomActionHandle::Float(
+                        action_set.create_action(name, localized_name, &[])?,
+                    ),
+                    CustomActionType::Vector2 => CustomActionHandle::Vector2(
+                        action_set.create_action(name, localized_name, &[])?,
+                    ),
+                    CustomActionType::Pose => {
+                        let action = action_set.create_action(name, localized_name, &[])?;
+                        let space = action.create_space(
+                            session.clone(),
+                            xr::Path::NULL,
+                            Posef::IDENTITY,
+                        )?;
+                        CustomActionHandle::Pose(action, space)
+                    }
+                };
+
+                custom_actions.actions.insert(
+                    action.name.clone(),
+                    CustomActionEntry {
+                        handle,
+                        bindings: action.bindings.clone(),
+                    },
+                );
+            }
+
+            custom_actions.action_sets.push(CustomActionSetEntry {
+                name: custom_action_set.name.clone(),
+                action_set,
+                active: custom_action_set.active,
+            });
+        }
+
+        Ok(custom_actions)
+    }
+
+    /// Every action set, which have to be attached to the session alongside Hotham's own
+    pub(crate) fn action_sets(&self) -> impl Iterator<Item = &ActionSet> {
+        self.action_sets.iter().map(|entry| &entry.action_set)
+    }
+
+    /// The action sets to sync this frame
+    pub(crate) fn active_action_sets(&self) -> impl Iterator<Item = &ActionSet> {
+        self.action_sets
+            .iter()
+            .filter(|entry| entry.active)
+            .map(|entry| &entry.action_set)
+    }
+
+    /// Start or stop syncing the action set named `name`, from the next frame. The actions in an inactive set are
+    /// reported as inactive, and don't take inputs from sets with a lower priority.
+    pub fn set_active(&mut self, name: &str, active: bool) -> Result<()> {
+        self.action_sets
+            .iter_mut()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| anyhow!("There's no action set named {name}"))?
+            .active = active;
+        Ok(())
+    }
+
+    /// Make only the action sets in `names` active, eg. `&["menu"]` when the game is paused
+    pub fn set_active_only(&mut self, names: &[&str]) -> Result<()> {
+        if let Some(name) = names
+            .iter()
+            .find(|name| !self.action_sets.iter().any(|entry| entry.name == **name))
+        {
+            return Err(anyhow!("There's no action set named {name}"));
+        }
+        for entry in &mut self.action_sets {
+            entry.active = names.contains(&entry.name.as_str());
+        }
+        Ok(())
+    }
+
+    /// Is the action set named `name` being synced?
+    pub fn is_active(&self, name: &str) -> Result<bool> {
+        self.action_sets
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.active)
+            .ok_or_else(|| anyhow!("There's no action set named {name}"))
+    }
+
+    /// Every interaction profile that any of the actions has a suggested binding for
+    pub(crate) fn interaction_profiles(&self) -> Vec<&str> {
+        let mut interaction_profiles = self
+            .actions
+            .values()
+            .flat_map(|entry| entry.bindings.iter())
+            .map(|(interaction_profile, _)| interaction_profile.as_str())
+            .collect::<Vec<_>>();
+        interaction_profiles.sort_unstable();
+        interaction_profiles.dedup();
+        interaction_profiles
+    }
+
+    /// The suggested bindings for `interaction_profile`. These must be suggested together with any other bindings for
+    /// the same profile, as each suggestion replaces the last.
+    pub(crate) fn bindings(
+        &self,
+        instance: &xr::Instance,
+        interaction_profile: &str,
+    ) -> Result<Vec<xr::Binding>> {
+        let mut bindings = Vec::new();
+        for entry in self.actions.values() {
+            for (_, path) in entry
+                .bindings
+                .iter()
+                .filter(|(profile, _)| profile == interaction_profile)
+            {
+                let path = instance.string_to_path(path)?;
+                bindings.push(match &entry.handle {
+                    CustomActionHandle::Boolean(action) => xr::Binding::new(action, path),
+                    CustomActionHandle::Float(action) => xr::Binding::new(action, path),
+                    CustomActionHandle::Vector2(action) => xr::Binding::new(action, path),
+                    CustomActionHandle::Pose(action, _) => xr::Binding::new(action, path),
+                });
+            }
+        }
+        Ok(bindings)
+    }
+
+    /// Get the state of a [`CustomActionType::Boolean`] action
+    pub fn boolean(&self, session: &Session<Vulkan>, name: &str) -> Result<xr::ActionState<bool>> {
+        match &self.get(name)?.handle {
+            CustomActionHandle::Boolean(action) => Ok(action.state(session, xr::Path::NULL)?),
+            _ => Err(anyhow!("The action {name} isn't a boolean")),
+        }
+    }
+
+    /// Get the state of a [`CustomActionType::Float`] action
+    pub fn float(&self, session: &Session<Vulkan>, name: &str) -> Result<xr::ActionState<f32>> {
+        match &self.get(name)?.handle {
+            CustomActionHandle::Float(action) => Ok(action.state(session, xr::Path::NULL)?),
+            _ => Err(anyhow!("The action {name} isn't a float")),
+        }
+    }
+
+    /// Get the state of a [`CustomActionType::Vector2`] action
+    pub fn vector2(
+        &self,
+        session: &Session<Vulkan>,
+        name: &str,
+    ) -> Result<xr::ActionState<xr::Vector2f>> {
+        match &self.get(name)?.handle {
+            CustomActionHandle::Vector2(action) => Ok(action.state(session, xr::Path::NULL)?),
+            _ => Err(anyhow!("The action {name} isn't a vector2")),
+        }
+    }
+
+    /// Get the pose of a [`CustomActionType::Pose`] action relative to `base_space`, eg. the
+    /// [`XrContext`](super::XrContext)'s `stage_space`, or `None` if it isn't being tracked
+    pub fn pose(&self, name: &str, base_space: &Space, time: xr::Time) -> Result<Option<Affine3A>> {
+        match &self.get(name)?.handle {
+            CustomActionHandle::Pose(_, space) => {
+                let location = space.locate(base_space, time)?;
+                Ok(is_space_valid(&location).then(|| affine_from_posef(location.pose)))
+            }
+            _ => Err(anyhow!("The action {name} isn't a pose")),
+        }
+    }
+
+    fn get(&self, name: &str) -> Result<&CustomActionEntry> {
+        self.actions
+            .get(name)
+            .ok_or_else(|| anyhow!("There's no action named {name}"))
+    }
+}
@@ -0,0 +1,46 @@
+use anyhow::{anyhow, Result};
+
+/// The manifest that tells the OpenXR loader where the Hotham simulator's runtime library is. The library is found
+/// relative to the manifest, in the workspace's `target/debug` directory.
+#[cfg(target_os = "windows")]
+const SIMULATOR_MANIFEST_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../hotham-simulator/hotham_simulator.json"
+);
+#[cfg(not(target_os = "windows"))]
+const SIMULATOR_MANIFEST_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../hotham-simulator/hotham_simulator_linux.json"
+);
+
+/// Point the OpenXR loader at the Hotham simulator instead of the system's runtime, so the application runs in a
+/// window on the desktop rather than on a headset. Must be called before the loader creates an instance.
+///
+/// In the window, WASD and the mouse (while the left button is held) fly the camera, Space and Left Shift move it up
+/// and down, and 1 to 4 press the X, Y, B and A buttons. Tab switches to moving either hand, with E, F and the arrow
+/// keys driving its trigger, grip and thumbstick.
+pub(crate) fn use_simulator_runtime() -> Result<()> {
+    let manifest_path = std::path::Path::new(SIMULATOR_MANIFEST_PATH);
+    if !manifest_path.exists() {
+        return Err(anyhow!(
+            "Unable to find the simulator's manifest at {manifest_path:?}"
+        ));
+    }
+
+    println!(
+        "[HOTHAM_XR] Running in the desktop preview - make sure hotham-simulator has been built with `cargo build -p hotham-simulator`"
+    );
+    // The loader only looks at this when an instance is created, so it's fine to set it now.
+    std::env::set_var("XR_RUNTIME_JSON", manifest_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_simulator_manifest_exists() {
+        assert!(std::path::Path::new(SIMULATOR_MANIFEST_PATH).exists());
+    }
+}
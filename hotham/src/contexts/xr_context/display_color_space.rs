@@ -0,0 +1,146 @@
+use anyhow::{anyhow, Result};
+use openxr::{self as xr, sys, Session, Vulkan};
+
+/// The color space the compositor assumes the application's colors are in, using `XR_FB_color_space`. The runtime
+/// converts from it to the color space of the headset's display, so picking the one the art was made for keeps
+/// colors consistent across headsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayColorSpace {
+    /// Colors are sent to the display without being converted
+    Unmanaged,
+    /// The wide gamut of HDR displays
+    Rec2020,
+    /// The same primaries as sRGB, which is what most monitors and art tools use
+    Rec709,
+    /// The display of the Oculus Rift CV1
+    RiftCv1,
+    /// The display of the Oculus Rift S
+    RiftS,
+    /// The display of the Quest 1. This is the default on Quest.
+    Quest,
+    /// The DCI-P3 gamut, used by many phones
+    P3,
+    /// The Adobe RGB gamut
+    AdobeRgb,
+}
+
+impl DisplayColorSpace {
+    pub(crate) fn to_raw(self) -> sys::ColorSpaceFB {
+        match self {
+            DisplayColorSpace::Unmanaged => sys::ColorSpaceFB::UNMANAGED,
+            DisplayColorSpace::Rec2020 => sys::ColorSpaceFB::REC2020,
+            DisplayColorSpace::Rec709 => sys::ColorSpaceFB::REC709,
+            DisplayColorSpace::RiftCv1 => sys::ColorSpaceFB::RIFT_CV1,
+            DisplayColorSpace::RiftS => sys::ColorSpaceFB::RIFT_S,
+            DisplayColorSpace::Quest => sys::ColorSpaceFB::QUEST,
+            DisplayColorSpace::P3 => sys::ColorSpaceFB::P3,
+            DisplayColorSpace::AdobeRgb => sys::ColorSpaceFB::ADOBE_RGB,
+        }
+    }
+
+    /// Returns `None` for color spaces added to the extension since this was written
+    pub(crate) fn from_raw(color_space: sys::ColorSpaceFB) -> Option<Self> {
+        Some(match color_space {
+            sys::ColorSpaceFB::UNMANAGED => DisplayColorSpace::Unmanaged,
+            sys::ColorSpaceFB::REC2020 => DisplayColorSpace::Rec2020,
+            sys::ColorSpaceFB::REC709 => DisplayColorSpace::Rec709,
+            sys::ColorSpaceFB::RIFT_CV1 => DisplayColorSpace::RiftCv1,
+            sys::ColorSpaceFB::RIFT_S => DisplayColorSpace::RiftS,
+            sys::ColorSpaceFB::QUEST => DisplayColorSpace::Quest,
+            sys::ColorSpaceFB::P3 => DisplayColorSpace::P3,
+            sys::ColorSpaceFB::ADOBE_RGB => DisplayColorSpace::AdobeRgb,
+            _ => return None,
+        })
+    }
+}
+
+/// The color space the runtime uses until it's told otherwise
+pub(crate) fn native_color_space(
+    instance: &xr::Instance,
+    system: xr::SystemId,
+) -> Result<Option<DisplayColorSpace>> {
+    let mut color_space_properties = sys::SystemColorSpacePropertiesFB {
+        ty: sys::SystemColorSpacePropertiesFB::TYPE,
+        next: std::ptr::null_mut(),
+        color_space: sys::ColorSpaceFB::UNMANAGED,
+    };
+    // SAFETY: Zero is a valid value for every field of the properties, which the runtime overwrites.
+    let mut system_properties: sys::SystemProperties = unsafe { std::mem::zeroed() };
+    system_properties.ty = sys::SystemProperties::TYPE;
+    system_properties.next = &mut color_space_properties as *mut _ as _;
+    check(unsafe {
+        (instance.fp().get_system_properties)(instance.as_raw(), system, &mut system_properties)
+    })?;
+    Ok(DisplayColorSpace::from_raw(
+        color_space_properties.color_space,
+    ))
+}
+
+/// The color spaces the runtime can convert from
+pub(crate) fn enumerate_color_spaces(session: &Session<Vulkan>) -> Result<Vec<DisplayColorSpace>> {
+    let fp = session.instance().exts().fb_color_space.unwrap();
+    let mut count = 0;
+    check(unsafe {
+        (fp.enumerate_color_spaces)(session.as_raw(), 0, &mut count, std::ptr::null_mut())
+    })?;
+    let mut color_spaces = vec![sys::ColorSpaceFB::UNMANAGED; count as usize];
+    check(unsafe {
+        (fp.enumerate_color_spaces)(
+            session.as_raw(),
+            count,
+            &mut count,
+            color_spaces.as_mut_ptr(),
+        )
+    })?;
+    Ok(color_spaces
+        .into_iter()
+        .filter_map(DisplayColorSpace::from_raw)
+        .collect())
+}
+
+/// Tell the runtime which color space the application's colors are in
+pub(crate) fn set_color_space(
+    session: &Session<Vulkan>,
+    color_space: DisplayColorSpace,
+) -> Result<()> {
+    let supported_color_spaces = enumerate_color_spaces(session)?;
+    if !supported_color_spaces.contains(&color_space) {
+        return Err(anyhow!(
+            "{color_space:?} is not supported, expected one of {supported_color_spaces:?}"
+        ));
+    }
+
+    let fp = session.instance().exts().fb_color_space.unwrap();
+    check(unsafe { (fp.set_color_space)(session.as_raw(), color_space.to_raw()) })
+}
+
+fn check(result: sys::Result) -> Result<()> {
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_display_color_space_round_trip() {
+        for color_space in [
+            DisplayColorSpace::Unmanaged,
+            DisplayColorSpace::Rec2020,
+            DisplayColorSpace::Rec709,
+            DisplayColorSpace::RiftCv1,
+            DisplayColorSpace::RiftS,
+            DisplayColorSpace::Quest,
+            DisplayColorSpace::P3,
+            DisplayColorSpace::AdobeRgb,
+        ] {
+            assert_eq!(
+                DisplayColorSpace::from_raw(color_space.to_raw()),
+                Some(color_space)
+            );
+        }
+    }
+}
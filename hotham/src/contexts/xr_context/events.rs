@@ -0,0 +1,153 @@
+use openxr::SessionState;
+
+use crate::{
+    components::hand::Handedness,
+    contexts::{
+        xr_context::{PerformanceNotification, PlayAreaEvent},
+        InputMode,
+    },
+};
+
+/// Something the OpenXR runtime told the application about, collected in [`XrEvents`]
+#[derive(Debug, Clone, PartialEq)]
+pub enum XrEvent {
+    /// The session moved from one state to another, eg. from `FOCUSED` to `VISIBLE` when the system menu opened
+    SessionStateChanged {
+        from: SessionState,
+        to: SessionState,
+    },
+    /// The application's frames started or stopped being shown to the player
+    VisibilityChanged { visible: bool },
+    /// The application started or stopped receiving input, eg. because the system menu opened or closed
+    FocusChanged { focused: bool },
+    /// The refresh rate of the display changed, in Hz
+    RefreshRateChanged { from: f32, to: f32 },
+    /// The runtime picked a different interaction profile for one of the player's hands, eg. because they picked up
+    /// a different controller. `profile` is its path, eg. `/interaction_profiles/oculus/touch_controller`, or `None`
+    /// if nothing is being used.
+    InteractionProfileChanged {
+        handedness: Handedness,
+        profile: Option<String>,
+    },
+    /// The player switched between using a controller and their bare hand, for one of their hands. Systems such as
+    /// `hands_system` and `controller_models_system` switch with them; see [`InputMode`].
+    InputModeChanged {
+        handedness: Handedness,
+        mode: InputMode,
+    },
+    /// The stage changed, because the player recentered the view through the runtime or the application called
+    /// [`XrContext::recenter`](crate::contexts::XrContext::recenter). Anything placed relative to where the player
+    /// was may need to be moved.
+    ReferenceSpaceChanged,
+    /// The player's head left or came back into the [`PlayArea`](crate::contexts::PlayArea)
+    PlayArea(PlayAreaEvent),
+    /// The runtime said how the CPU or GPU is coping. See
+    /// [`XrContext::set_performance_level`](crate::contexts::XrContext::set_performance_level).
+    Performance(PerformanceNotification),
+    /// The runtime's event queue overflowed, and this many events were lost
+    EventsLost { count: u32 },
+    /// The application this one is an overlay on top of started or stopped being shown to the player. See
+    /// [`XrContextBuilder::overlay`](crate::contexts::XrContextBuilder::overlay).
+    MainSessionVisibilityChanged { visible: bool },
+    /// The runtime is going away, eg. because it's being updated, and the application will have to exit
+    InstanceLossPending,
+}
+
+/// The events the OpenXR runtime sent since the start of the last `Engine::update`, for systems to read.
+///
+/// Found in [`XrContext::events`](crate::contexts::XrContext::events).
+///
+/// ```ignore
+/// for event in engine.xr_context.events.iter() {
+///     if let XrEvent::FocusChanged { focused: false } = event {
+///         pause_game(&mut engine.world);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct XrEvents {
+    events: Vec<XrEvent>,
+}
+
+impl XrEvents {
+    /// The events, in the order they were received
+    pub fn iter(&self) -> impl Iterator<Item = &XrEvent> {
+        self.events.iter()
+    }
+
+    /// Were there no events?
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub(crate) fn push(&mut self, event: XrEvent) {
+        self.events.push(event);
+    }
+
+    pub(crate) fn extend(&mut self, events: impl IntoIterator<Item = XrEvent>) {
+        self.events.extend(events);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Record a change of session state, along with any change to whether the application is visible or focused
+    pub(crate) fn push_session_state_change(&mut self, from: SessionState, to: SessionState) {
+        self.push(XrEvent::SessionStateChanged { from, to });
+
+        let is_visible = |state| state == SessionState::VISIBLE || state == SessionState::FOCUSED;
+        if is_visible(from) != is_visible(to) {
+            self.push(XrEvent::VisibilityChanged {
+                visible: is_visible(to),
+            });
+        }
+
+        let is_focused = |state| state == SessionState::FOCUSED;
+        if is_focused(from) != is_focused(to) {
+            self.push(XrEvent::FocusChanged {
+                focused: is_focused(to),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_session_state_change_events() {
+        let mut events = XrEvents::default();
+
+        events.push_session_state_change(SessionState::SYNCHRONIZED, SessionState::VISIBLE);
+        events.push_session_state_change(SessionState::VISIBLE, SessionState::FOCUSED);
+        assert_eq!(
+            events.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                XrEvent::SessionStateChanged {
+                    from: SessionState::SYNCHRONIZED,
+                    to: SessionState::VISIBLE
+                },
+                XrEvent::VisibilityChanged { visible: true },
+                XrEvent::SessionStateChanged {
+                    from: SessionState::VISIBLE,
+                    to: SessionState::FOCUSED
+                },
+                XrEvent::FocusChanged { focused: true },
+            ]
+        );
+
+        // Going straight from focused to hidden loses both.
+        events.clear();
+        events.push_session_state_change(SessionState::FOCUSED, SessionState::SYNCHRONIZED);
+        assert_eq!(
+            events.iter().skip(1).cloned().collect::<Vec<_>>(),
+            vec![
+                XrEvent::VisibilityChanged { visible: false },
+                XrEvent::FocusChanged { focused: false },
+            ]
+        );
+        assert!(!events.is_empty());
+    }
+}
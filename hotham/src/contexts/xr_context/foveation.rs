@@ -0,0 +1,161 @@
+use openxr as xr;
+
+/// How aggressively the resolution is reduced towards the edges of each eye.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FoveationLevel {
+    /// Every pixel is rendered at full density
+    None,
+    /// A small reduction at the very edges
+    Low,
+    /// A moderate reduction
+    Medium,
+    /// The largest reduction, for the most performance
+    #[default]
+    High,
+}
+
+/// How foveated rendering is done, chosen with
+/// [`RenderSettings::foveation`](crate::contexts::render_context::RenderSettings::foveation).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FoveationMode {
+    /// Every pixel is shaded at full density
+    Disabled,
+    /// The runtime generates a fragment density map for each swapchain image from the [`FoveationSettings`], using
+    /// `XR_FB_foveation`, and it's attached to the render pass. The settings can be changed at any time with
+    /// `XrContext::set_foveation_settings`. Falls back to `Disabled` if the runtime doesn't support it, eg. on
+    /// desktop.
+    #[default]
+    Runtime,
+}
+
+impl FoveationMode {
+    /// The mode to use with `instance`, falling back to `Disabled` if it's `Runtime` but the extensions weren't
+    /// enabled
+    pub(crate) fn supported(self, instance: &xr::Instance) -> Self {
+        let exts = instance.exts();
+        let runtime_supported = cfg!(target_os = "android")
+            && exts.fb_foveation.is_some()
+            && exts.fb_foveation_configuration.is_some()
+            && exts.fb_foveation_vulkan.is_some()
+            && exts.fb_swapchain_update_state.is_some();
+        match self {
+            FoveationMode::Runtime if runtime_supported => FoveationMode::Runtime,
+            FoveationMode::Runtime => {
+                println!("[HOTHAM_XR] XR_FB_foveation is not supported, foveation is disabled");
+                FoveationMode::Disabled
+            }
+            FoveationMode::Disabled => FoveationMode::Disabled,
+        }
+    }
+}
+
+/// Settings for Fixed Foveated Rendering (FFR).
+///
+/// With [`FoveationMode::Runtime`], the swapchain is created with a fragment density map so that pixels in the
+/// periphery of each eye are shaded at a lower density. The periphery is blurry through the lenses anyway, so this is
+/// close to free performance. Has no effect on desktop.
+///
+/// Basic usage:
+/// ```ignore
+/// engine.xr_context.set_foveation_settings(FoveationSettings {
+///     level: FoveationLevel::Medium,
+///     ..Default::default()
+/// })?;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FoveationSettings {
+    /// How much the density is reduced
+    pub level: FoveationLevel,
+    /// Moves the full density region up (positive) or down (negative), in degrees
+    pub vertical_offset: f32,
+    /// Let the runtime lower the level when the GPU isn't under much load
+    pub dynamic: bool,
+}
+
+impl Default for FoveationSettings {
+    fn default() -> Self {
+        Self {
+            level: FoveationLevel::High,
+            vertical_offset: 0.,
+            dynamic: false,
+        }
+    }
+}
+
+impl FoveationSettings {
+    /// The profile passed to `XR_FB_foveation_configuration`
+    pub fn to_profile(&self) -> xr::FoveationLevelProfile {
+        let level = match self.level {
+            FoveationLevel::None => xr::FoveationLevelFB::NONE,
+            FoveationLevel::Low => xr::FoveationLevelFB::LOW,
+            FoveationLevel::Medium => xr::FoveationLevelFB::MEDIUM,
+            FoveationLevel::High => xr::FoveationLevelFB::HIGH,
+        };
+        let dynamic = if self.dynamic {
+            xr::FoveationDynamicFB::LEVEL_ENABLED
+        } else {
+            xr::FoveationDynamicFB::DISABLED
+        };
+
+        xr::FoveationLevelProfile {
+            level,
+            vertical_offset: self.vertical_offset,
+            dynamic,
+        }
+    }
+}
+
+/// Apply `foveation_settings` to a swapchain that was created with `XR_FB_foveation_vulkan`.
+#[cfg(target_os = "android")]
+pub(crate) fn apply_foveation_settings(
+    xr_session: &xr::Session<xr::Vulkan>,
+    swapchain: xr::sys::Swapchain,
+    foveation_settings: &FoveationSettings,
+) -> anyhow::Result<()> {
+    let fp = xr_session
+        .instance()
+        .exts()
+        .fb_swapchain_update_state
+        .unwrap();
+
+    let foveation_profile_handle =
+        xr_session.create_foveation_profile(Some(foveation_settings.to_profile()))?;
+
+    let swapchain_update_state = xr::sys::SwapchainStateFoveationFB {
+        ty: xr::sys::SwapchainStateFoveationFB::TYPE,
+        next: std::ptr::null_mut(),
+        flags: xr::SwapchainStateFoveationFlagsFB::EMPTY,
+        profile: foveation_profile_handle.as_raw(),
+    };
+
+    let result =
+        unsafe { (fp.update_swapchain)(swapchain, std::mem::transmute(&swapchain_update_state)) };
+
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_foveation_settings_to_profile() {
+        let profile = FoveationSettings::default().to_profile();
+        assert_eq!(profile.level, xr::FoveationLevelFB::HIGH);
+        assert_eq!(profile.dynamic, xr::FoveationDynamicFB::DISABLED);
+
+        let profile = FoveationSettings {
+            level: FoveationLevel::Low,
+            vertical_offset: 5.,
+            dynamic: true,
+        }
+        .to_profile();
+        assert_eq!(profile.level, xr::FoveationLevelFB::LOW);
+        assert_eq!(profile.vertical_offset, 5.);
+        assert_eq!(profile.dynamic, xr::FoveationDynamicFB::LEVEL_ENABLED);
+    }
+}
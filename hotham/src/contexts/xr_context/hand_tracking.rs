@@ -0,0 +1,43 @@
+use anyhow::Result;
+use openxr::{self as xr, HandTracker, Session, Vulkan};
+
+/// Tracks the joints of the player's hands, using `XR_EXT_hand_tracking`.
+///
+/// The joints are located each frame by the [`InputContext`](crate::contexts::InputContext), and copied into
+/// [`HandJoints`](crate::components::HandJoints) components by the
+/// [`hand_tracking_system`](crate::systems::hand_tracking_system). On Quest, the application must also declare
+/// `oculus.software.handtracking` in its Android manifest.
+pub struct HandTracking {
+    /// Tracks the left hand
+    pub left: HandTracker,
+    /// Tracks the right hand
+    pub right: HandTracker,
+}
+
+impl HandTracking {
+    /// Create a hand tracker for each hand. Returns `None` if `XR_EXT_hand_tracking` wasn't enabled, eg. because the
+    /// runtime doesn't support it, or if the system can't track hands.
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        session: &Session<Vulkan>,
+        system: xr::SystemId,
+    ) -> Result<Option<Self>> {
+        if instance.exts().ext_hand_tracking.is_none() {
+            println!(
+                "[HOTHAM_XR] XR_EXT_hand_tracking is not supported, hand tracking is disabled"
+            );
+            return Ok(None);
+        }
+
+        if !instance.supports_hand_tracking(system)? {
+            println!("[HOTHAM_XR] This system can't track hands, hand tracking is disabled");
+            return Ok(None);
+        }
+
+        println!("[HOTHAM_XR] Hand tracking enabled");
+        Ok(Some(Self {
+            left: session.create_hand_tracker(xr::Hand::LEFT)?,
+            right: session.create_hand_tracker(xr::Hand::RIGHT)?,
+        }))
+    }
+}
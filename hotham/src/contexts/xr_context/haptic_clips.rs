@@ -0,0 +1,101 @@
+use std::ffi::c_void;
+
+use anyhow::Result;
+use openxr::{self as xr, sys, Session, Vulkan};
+
+/// The extensions for playing [`HapticClip`](crate::components::HapticClip)s. `openxr` doesn't know about them yet, so
+/// they're enabled by name and their types are declared here.
+pub(crate) const FB_HAPTIC_PCM: &str = "XR_FB_haptic_pcm";
+pub(crate) const FB_HAPTIC_AMPLITUDE_ENVELOPE: &str = "XR_FB_haptic_amplitude_envelope";
+
+/// The most samples the runtime accepts in one buffer, with either extension
+pub(crate) const MAX_HAPTIC_SAMPLES: usize = 4000;
+
+const TYPE_HAPTIC_AMPLITUDE_ENVELOPE_VIBRATION_FB: sys::StructureType =
+    sys::StructureType::from_raw(1000173001);
+const TYPE_HAPTIC_PCM_VIBRATION_FB: sys::StructureType = sys::StructureType::from_raw(1000209001);
+
+#[repr(C)]
+struct HapticAmplitudeEnvelopeVibrationFB {
+    ty: sys::StructureType,
+    next: *const c_void,
+    duration: sys::Duration,
+    amplitude_count: u32,
+    amplitudes: *const f32,
+}
+
+#[repr(C)]
+struct HapticPcmVibrationFB {
+    ty: sys::StructureType,
+    next: *const c_void,
+    buffer_size: u32,
+    buffer: *const f32,
+    sample_rate: f32,
+    append: sys::Bool32,
+    samples_consumed: *mut u32,
+}
+
+/// Play PCM samples on the controller with `subaction_path`, after any that are still playing if `append` is set.
+/// Returns how many of the samples the runtime took, as it only takes as many as it has room for.
+pub(crate) fn apply_haptic_pcm(
+    session: &Session<Vulkan>,
+    action: &xr::Action<xr::Haptic>,
+    subaction_path: xr::Path,
+    samples: &[f32],
+    sample_rate: f32,
+    append: bool,
+) -> Result<usize> {
+    let samples = &samples[..samples.len().min(MAX_HAPTIC_SAMPLES)];
+    let mut samples_consumed = 0;
+    let vibration = HapticPcmVibrationFB {
+        ty: TYPE_HAPTIC_PCM_VIBRATION_FB,
+        next: std::ptr::null(),
+        buffer_size: samples.len() as _,
+        buffer: samples.as_ptr(),
+        sample_rate,
+        append: append.into(),
+        samples_consumed: &mut samples_consumed,
+    };
+    apply_haptic_feedback(session, action, subaction_path, &vibration as *const _ as _)?;
+    Ok(samples_consumed as _)
+}
+
+/// Play an amplitude envelope lasting `duration` on the controller with `subaction_path`
+pub(crate) fn apply_haptic_amplitude_envelope(
+    session: &Session<Vulkan>,
+    action: &xr::Action<xr::Haptic>,
+    subaction_path: xr::Path,
+    amplitudes: &[f32],
+    duration: std::time::Duration,
+) -> Result<()> {
+    let amplitudes = &amplitudes[..amplitudes.len().min(MAX_HAPTIC_SAMPLES)];
+    let vibration = HapticAmplitudeEnvelopeVibrationFB {
+        ty: TYPE_HAPTIC_AMPLITUDE_ENVELOPE_VIBRATION_FB,
+        next: std::ptr::null(),
+        duration: xr::Duration::from_nanos(duration.as_nanos() as _),
+        amplitude_count: amplitudes.len() as _,
+        amplitudes: amplitudes.as_ptr(),
+    };
+    apply_haptic_feedback(session, action, subaction_path, &vibration as *const _ as _)
+}
+
+fn apply_haptic_feedback(
+    session: &Session<Vulkan>,
+    action: &xr::Action<xr::Haptic>,
+    subaction_path: xr::Path,
+    vibration: *const sys::HapticBaseHeader,
+) -> Result<()> {
+    let info = sys::HapticActionInfo {
+        ty: sys::HapticActionInfo::TYPE,
+        next: std::ptr::null(),
+        action: action.as_raw(),
+        subaction_path,
+    };
+    let result = unsafe {
+        (session.instance().fp().apply_haptic_feedback)(session.as_raw(), &info, vibration)
+    };
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
@@ -1,6 +1,12 @@
 use anyhow::Result;
 use openxr::{self as xr, Action, ActionSet, Haptic, Path, Posef, Space};
 
+use super::{
+    custom_actions::{CustomActionSet, CustomActions},
+    runtime_capabilities::RuntimeCapabilities,
+    trackers::{Trackers, VIVE_TRACKER_INTERACTION},
+};
+
 pub struct Input {
     pub action_set: ActionSet,
     pub grip_pose_action: Action<Posef>,
@@ -29,12 +35,28 @@ pub struct Input {
     pub right_hand_grip_space: Space,
     pub right_hand_aim_space: Space,
     pub right_hand_subaction_path: Path,
+    /// Where the player is looking. Only present if `XR_EXT_eye_gaze_interaction` is enabled.
+    pub eye_gaze_action: Option<Action<Posef>>,
+    /// The space of `eye_gaze_action`, whose -Z axis points where the player is looking
+    pub eye_gaze_space: Option<Space>,
+    /// Extra tracked devices, such as Vive trackers. Only present if `XR_HTCX_vive_tracker_interaction` is enabled.
+    pub trackers: Option<Trackers>,
+    /// The actions registered by the application with
+    /// [`EngineBuilder::custom_action_sets`](crate::EngineBuilder::custom_action_sets)
+    pub custom_actions: CustomActions,
 }
 
+const OCULUS_TOUCH_CONTROLLER: &str = "/interaction_profiles/oculus/touch_controller";
+const EYE_GAZE_INTERACTION: &str = "/interaction_profiles/ext/eye_gaze_interaction";
+
 impl Input {
-    pub fn oculus_touch_controller(
+    /// Create the actions, and suggest bindings for them for each of the controllers we support. The runtime
+    /// chooses whichever profile best matches the controllers the player has.
+    pub fn new(
         instance: &xr::Instance,
         session: &xr::Session<xr::Vulkan>,
+        runtime_capabilities: &RuntimeCapabilities,
+        custom_action_sets: &[CustomActionSet],
     ) -> Result<Self> {
         // Create an action set to encapsulate our actions
         let action_set = instance.create_action_set("input", "input pose information", 0)?;
@@ -219,12 +241,15 @@ impl Input {
             &[left_hand_subaction_path, right_hand_subaction_path],
         )?;
 
+        // Create the application's own actions first, so they can be bound alongside ours.
+        let custom_actions = CustomActions::new(instance, session, custom_action_sets)?;
+
         // Bind our actions to input devices using the given profile
-        instance.suggest_interaction_profile_bindings(
-            instance
-                .string_to_path("/interaction_profiles/oculus/touch_controller")
-                .unwrap(),
-            &[
+        suggest_bindings(
+            instance,
+            &custom_actions,
+            OCULUS_TOUCH_CONTROLLER,
+            vec![
                 xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
                 xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
                 xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
@@ -259,6 +284,302 @@ impl Input {
             ],
         )?;
 
+        // Other controllers don't have all the buttons of an Oculus Touch controller, so bind what they do have to
+        // the closest actions.
+        let path = |path: &str| instance.string_to_path(path).unwrap();
+        let mut other_interaction_profiles = vec![
+            (
+                "/interaction_profiles/valve/index_controller",
+                vec![
+                    xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
+                    xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
+                    xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
+                    xr::Binding::new(&aim_pose_action, right_hand_aim_pose_path),
+                    xr::Binding::new(&squeeze_action, left_hand_squeeze_path),
+                    xr::Binding::new(&squeeze_action, right_hand_squeeze_path),
+                    xr::Binding::new(&trigger_action, left_hand_trigger_path),
+                    xr::Binding::new(&trigger_action, right_hand_trigger_path),
+                    xr::Binding::new(&trigger_touch_action, left_hand_trigger_touch_path),
+                    xr::Binding::new(&trigger_touch_action, right_hand_trigger_touch_path),
+                    xr::Binding::new(&haptic_feedback_action, left_hand_haptic_feedback_path),
+                    xr::Binding::new(&haptic_feedback_action, right_hand_haptic_feedback_path),
+                    xr::Binding::new(&x_button_action, path("/user/hand/left/input/a/click")),
+                    xr::Binding::new(&x_touch_action, path("/user/hand/left/input/a/touch")),
+                    xr::Binding::new(&y_button_action, path("/user/hand/left/input/b/click")),
+                    xr::Binding::new(&y_touch_action, path("/user/hand/left/input/b/touch")),
+                    xr::Binding::new(&a_button_action, a_button_path),
+                    xr::Binding::new(&a_touch_action, a_button_touch_path),
+                    xr::Binding::new(&b_button_action, b_button_path),
+                    xr::Binding::new(&b_touch_action, b_button_touch_path),
+                    xr::Binding::new(&thumbstick_x_action, left_hand_thumbstick_x_path),
+                    xr::Binding::new(&thumbstick_x_action, right_hand_thumbstick_x_path),
+                    xr::Binding::new(&thumbstick_y_action, left_hand_thumbstick_y_path),
+                    xr::Binding::new(&thumbstick_y_action, right_hand_thumbstick_y_path),
+                    xr::Binding::new(&thumbstick_click_action, left_hand_thumbstick_click_path),
+                    xr::Binding::new(&thumbstick_click_action, right_hand_thumbstick_click_path),
+                    xr::Binding::new(&thumbstick_touch_action, left_hand_thumbstick_touch_path),
+                    xr::Binding::new(&thumbstick_touch_action, right_hand_thumbstick_touch_path),
+                ],
+            ),
+            (
+                "/interaction_profiles/htc/vive_controller",
+                vec![
+                    xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
+                    xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
+                    xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
+                    xr::Binding::new(&aim_pose_action, right_hand_aim_pose_path),
+                    xr::Binding::new(&squeeze_action, path("/user/hand/left/input/squeeze/click")),
+                    xr::Binding::new(
+                        &squeeze_action,
+                        path("/user/hand/right/input/squeeze/click"),
+                    ),
+                    xr::Binding::new(&trigger_action, left_hand_trigger_path),
+                    xr::Binding::new(&trigger_action, right_hand_trigger_path),
+                    xr::Binding::new(&haptic_feedback_action, left_hand_haptic_feedback_path),
+                    xr::Binding::new(&haptic_feedback_action, right_hand_haptic_feedback_path),
+                    xr::Binding::new(&menu_button_action, menu_button_path),
+                    xr::Binding::new(
+                        &menu_button_action,
+                        path("/user/hand/right/input/menu/click"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_x_action,
+                        path("/user/hand/left/input/trackpad/x"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_x_action,
+                        path("/user/hand/right/input/trackpad/x"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_y_action,
+                        path("/user/hand/left/input/trackpad/y"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_y_action,
+                        path("/user/hand/right/input/trackpad/y"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_click_action,
+                        path("/user/hand/left/input/trackpad/click"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_click_action,
+                        path("/user/hand/right/input/trackpad/click"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_touch_action,
+                        path("/user/hand/left/input/trackpad/touch"),
+                    ),
+                    xr::Binding::new(
+                        &thumbstick_touch_action,
+                        path("/user/hand/right/input/trackpad/touch"),
+                    ),
+                ],
+            ),
+            (
+                "/interaction_profiles/microsoft/motion_controller",
+                vec![
+                    xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
+                    xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
+                    xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
+                    xr::Binding::new(&aim_pose_action, right_hand_aim_pose_path),
+                    xr::Binding::new(&squeeze_action, path("/user/hand/left/input/squeeze/click")),
+                    xr::Binding::new(
+                        &squeeze_action,
+                        path("/user/hand/right/input/squeeze/click"),
+                    ),
+                    xr::Binding::new(&trigger_action, left_hand_trigger_path),
+                    xr::Binding::new(&trigger_action, right_hand_trigger_path),
+                    xr::Binding::new(&haptic_feedback_action, left_hand_haptic_feedback_path),
+                    xr::Binding::new(&haptic_feedback_action, right_hand_haptic_feedback_path),
+                    xr::Binding::new(&menu_button_action, menu_button_path),
+                    xr::Binding::new(
+                        &menu_button_action,
+                        path("/user/hand/right/input/menu/click"),
+                    ),
+                    xr::Binding::new(&thumbstick_x_action, left_hand_thumbstick_x_path),
+                    xr::Binding::new(&thumbstick_x_action, right_hand_thumbstick_x_path),
+                    xr::Binding::new(&thumbstick_y_action, left_hand_thumbstick_y_path),
+                    xr::Binding::new(&thumbstick_y_action, right_hand_thumbstick_y_path),
+                    xr::Binding::new(&thumbstick_click_action, left_hand_thumbstick_click_path),
+                    xr::Binding::new(&thumbstick_click_action, right_hand_thumbstick_click_path),
+                ],
+            ),
+            // The fallback for any controller the runtime doesn't have a better match for.
+            (
+                "/interaction_profiles/khr/simple_controller",
+                vec![
+                    xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
+                    xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
+                    xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
+                    xr::Binding::new(&aim_pose_action, right_hand_aim_pose_path),
+                    xr::Binding::new(&trigger_action, path("/user/hand/left/input/select/click")),
+                    xr::Binding::new(&trigger_action, path("/user/hand/right/input/select/click")),
+                    xr::Binding::new(&haptic_feedback_action, left_hand_haptic_feedback_path),
+                    xr::Binding::new(&haptic_feedback_action, right_hand_haptic_feedback_path),
+                    xr::Binding::new(&menu_button_action, menu_button_path),
+                    xr::Binding::new(
+                        &menu_button_action,
+                        path("/user/hand/right/input/menu/click"),
+                    ),
+                ],
+            ),
+        ];
+        // Headsets other than Quest have controllers laid out like Touch controllers, but their profiles can only be
+        // used if the runtime has their extensions.
+        if runtime_capabilities
+            .extensions
+            .htc_vive_focus3_controller_interaction
+        {
+            other_interaction_profiles.push((
+                "/interaction_profiles/htc/vive_focus3_controller",
+                vec![
+                    xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
+                    xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
+                    xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
+                    xr::Binding::new(&aim_pose_action, right_hand_aim_pose_path),
+                    xr::Binding::new(&squeeze_action, path("/user/hand/left/input/squeeze/click")),
+                    xr::Binding::new(
+                        &squeeze_action,
+                        path("/user/hand/right/input/squeeze/click"),
+                    ),
+                    xr::Binding::new(&trigger_action, left_hand_trigger_path),
+                    xr::Binding::new(&trigger_action, right_hand_trigger_path),
+                    xr::Binding::new(&trigger_touch_action, left_hand_trigger_touch_path),
+                    xr::Binding::new(&trigger_touch_action, right_hand_trigger_touch_path),
+                    xr::Binding::new(&haptic_feedback_action, left_hand_haptic_feedback_path),
+                    xr::Binding::new(&haptic_feedback_action, right_hand_haptic_feedback_path),
+                    xr::Binding::new(&x_button_action, x_button_path),
+                    xr::Binding::new(&y_button_action, y_button_path),
+                    xr::Binding::new(&menu_button_action, menu_button_path),
+                    xr::Binding::new(&a_button_action, a_button_path),
+                    xr::Binding::new(&b_button_action, b_button_path),
+                    xr::Binding::new(&thumbstick_x_action, left_hand_thumbstick_x_path),
+                    xr::Binding::new(&thumbstick_x_action, right_hand_thumbstick_x_path),
+                    xr::Binding::new(&thumbstick_y_action, left_hand_thumbstick_y_path),
+                    xr::Binding::new(&thumbstick_y_action, right_hand_thumbstick_y_path),
+                    xr::Binding::new(&thumbstick_click_action, left_hand_thumbstick_click_path),
+                    xr::Binding::new(&thumbstick_click_action, right_hand_thumbstick_click_path),
+                    xr::Binding::new(&thumbstick_touch_action, left_hand_thumbstick_touch_path),
+                    xr::Binding::new(&thumbstick_touch_action, right_hand_thumbstick_touch_path),
+                    xr::Binding::new(&thumbrest_touch_action, left_hand_thumbrest_touch_path),
+                    xr::Binding::new(&thumbrest_touch_action, right_hand_thumbrest_touch_path),
+                ],
+            ));
+        }
+        if runtime_capabilities.has_pico_controllers() {
+            for interaction_profile in [
+                "/interaction_profiles/bytedance/pico4_controller",
+                "/interaction_profiles/bytedance/pico_neo3_controller",
+            ] {
+                other_interaction_profiles.push((
+                    interaction_profile,
+                    vec![
+                        xr::Binding::new(&grip_pose_action, left_hand_grip_pose_path),
+                        xr::Binding::new(&grip_pose_action, right_hand_grip_pose_path),
+                        xr::Binding::new(&aim_pose_action, left_hand_aim_pose_path),
+                        xr::Binding::new(&aim_pose_action, right_hand_aim_pose_path),
+                        xr::Binding::new(&squeeze_action, left_hand_squeeze_path),
+                        xr::Binding::new(&squeeze_action, right_hand_squeeze_path),
+                        xr::Binding::new(&trigger_action, left_hand_trigger_path),
+                        xr::Binding::new(&trigger_action, right_hand_trigger_path),
+                        xr::Binding::new(&trigger_touch_action, left_hand_trigger_touch_path),
+                        xr::Binding::new(&trigger_touch_action, right_hand_trigger_touch_path),
+                        xr::Binding::new(&haptic_feedback_action, left_hand_haptic_feedback_path),
+                        xr::Binding::new(&haptic_feedback_action, right_hand_haptic_feedback_path),
+                        xr::Binding::new(&x_button_action, x_button_path),
+                        xr::Binding::new(&x_touch_action, x_button_touch_path),
+                        xr::Binding::new(&y_button_action, y_button_path),
+                        xr::Binding::new(&y_touch_action, y_button_touch_path),
+                        xr::Binding::new(&menu_button_action, menu_button_path),
+                        xr::Binding::new(&a_button_action, a_button_path),
+                        xr::Binding::new(&a_touch_action, a_button_touch_path),
+                        xr::Binding::new(&b_button_action, b_button_path),
+                        xr::Binding::new(&b_touch_action, b_button_touch_path),
+                        xr::Binding::new(&thumbstick_x_action, left_hand_thumbstick_x_path),
+                        xr::Binding::new(&thumbstick_x_action, right_hand_thumbstick_x_path),
+                        xr::Binding::new(&thumbstick_y_action, left_hand_thumbstick_y_path),
+                        xr::Binding::new(&thumbstick_y_action, right_hand_thumbstick_y_path),
+                        xr::Binding::new(&thumbstick_click_action, left_hand_thumbstick_click_path),
+                        xr::Binding::new(
+                            &thumbstick_click_action,
+                            right_hand_thumbstick_click_path,
+                        ),
+                        xr::Binding::new(&thumbstick_touch_action, left_hand_thumbstick_touch_path),
+                        xr::Binding::new(
+                            &thumbstick_touch_action,
+                            right_hand_thumbstick_touch_path,
+                        ),
+                    ],
+                ));
+            }
+        }
+
+        let mut suggested_interaction_profiles = vec![OCULUS_TOUCH_CONTROLLER];
+        for (interaction_profile, bindings) in other_interaction_profiles {
+            suggested_interaction_profiles.push(interaction_profile);
+            if let Err(e) =
+                suggest_bindings(instance, &custom_actions, interaction_profile, bindings)
+            {
+                println!(
+                    "[HOTHAM_INPUT] Unable to suggest bindings for {interaction_profile}: {e:?}"
+                );
+            }
+        }
+
+        // Eye gaze has its own interaction profile, which the runtime uses alongside the controllers.
+        let eye_gaze_action = if instance.exts().ext_eye_gaze_interaction.is_some() {
+            let eye_gaze_action =
+                action_set.create_action::<xr::Posef>("eye_gaze", "Eye Gaze", &[])?;
+            suggested_interaction_profiles.push(EYE_GAZE_INTERACTION);
+            suggest_bindings(
+                instance,
+                &custom_actions,
+                EYE_GAZE_INTERACTION,
+                vec![xr::Binding::new(
+                    &eye_gaze_action,
+                    instance
+                        .string_to_path("/user/eyes_ext/input/gaze_ext/pose")
+                        .unwrap(),
+                )],
+            )?;
+            Some(eye_gaze_action)
+        } else {
+            None
+        };
+        // As do trackers, with a top level path for each role a tracker can have.
+        let trackers = if instance.exts().htcx_vive_tracker_interaction.is_some() {
+            let trackers = Trackers::new(instance, session, &action_set)?;
+            suggested_interaction_profiles.push(VIVE_TRACKER_INTERACTION);
+            suggest_bindings(
+                instance,
+                &custom_actions,
+                VIVE_TRACKER_INTERACTION,
+                trackers.bindings(instance)?,
+            )?;
+            Some(trackers)
+        } else {
+            None
+        };
+        // Profiles that only the application's own actions are bound on.
+        for interaction_profile in custom_actions.interaction_profiles() {
+            if suggested_interaction_profiles.contains(&interaction_profile) {
+                continue;
+            }
+            if let Err(e) =
+                suggest_bindings(instance, &custom_actions, interaction_profile, Vec::new())
+            {
+                println!(
+                    "[HOTHAM_INPUT] Unable to suggest bindings for {interaction_profile}: {e:?}"
+                );
+            }
+        }
+
+        let eye_gaze_space = eye_gaze_action
+            .as_ref()
+            .map(|action| action.create_space(session.clone(), Path::NULL, Posef::IDENTITY))
+            .transpose()?;
+
         let left_hand_grip_space = grip_pose_action.create_space(
             session.clone(),
             left_hand_subaction_path,
@@ -309,6 +630,26 @@ impl Input {
             right_hand_grip_space,
             right_hand_aim_space,
             right_hand_subaction_path,
+            eye_gaze_action,
+            eye_gaze_space,
+            trackers,
+            custom_actions,
         })
     }
 }
+
+/// Suggest bindings for an interaction profile, along with any of the application's own actions bound on it. Each
+/// suggestion for a profile replaces the last, so they all have to be suggested at once.
+fn suggest_bindings<'a>(
+    instance: &xr::Instance,
+    custom_actions: &'a CustomActions,
+    interaction_profile: &str,
+    mut bindings: Vec<xr::Binding<'a>>,
+) -> Result<()> {
+    bindings.extend(custom_actions.bindings(instance, interaction_profile)?);
+    instance.suggest_interaction_profile_bindings(
+        instance.string_to_path(interaction_profile)?,
+        &bindings,
+    )?;
+    Ok(())
+}
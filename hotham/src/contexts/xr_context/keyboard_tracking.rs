@@ -0,0 +1,158 @@
+use std::ffi::CStr;
+
+use anyhow::Result;
+use glam::{Affine3A, Vec3};
+use openxr::{self as xr, sys, Session, Space, Vulkan};
+
+use crate::util::{affine_from_posef, is_space_valid};
+
+/// The physical keyboard the player has paired with their headset
+pub struct TrackedKeyboard {
+    /// The name of the keyboard, eg. `Logitech K830`
+    pub name: String,
+    /// The size of the keyboard's bounding box, in meters
+    pub size: Vec3,
+    /// Is the keyboard connected, so it can be typed on?
+    pub is_connected: bool,
+    id: u64,
+    space: Space,
+}
+
+/// Finds the player's physical keyboard, using `XR_FB_keyboard_tracking`, so it can be shown to them while they type.
+///
+/// The keyboard is located each frame by the [`InputContext`](crate::contexts::InputContext) as the tracker with
+/// [`TrackerRole::Keyboard`](super::TrackerRole::Keyboard), so an entity with a
+/// [`TrackedDevice`](crate::components::TrackedDevice) follows it. Give that entity a
+/// [`RenderModel`](crate::components::RenderModel) of [`RenderModel::KEYBOARD_LOCAL`](crate::components::RenderModel::KEYBOARD_LOCAL)
+/// to draw it. On Quest, the player has to pair their keyboard in the system settings, and the application must
+/// declare `oculus.software.trackedkeyboard` in its Android manifest.
+pub struct KeyboardTracking {
+    session: Session<Vulkan>,
+    /// The player's keyboard, once the runtime has found it
+    pub keyboard: Option<TrackedKeyboard>,
+}
+
+impl KeyboardTracking {
+    /// Start looking for the player's keyboard. Returns `None` if `XR_FB_keyboard_tracking` wasn't enabled, eg. because
+    /// the runtime doesn't support it, or if the system can't track keyboards.
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        session: &Session<Vulkan>,
+        system: xr::SystemId,
+    ) -> Result<Option<Self>> {
+        if instance.exts().fb_keyboard_tracking.is_none() {
+            println!("[HOTHAM_XR] XR_FB_keyboard_tracking is not supported, keyboard tracking is disabled");
+            return Ok(None);
+        }
+
+        let mut keyboard_tracking_properties = sys::SystemKeyboardTrackingPropertiesFB {
+            ty: sys::SystemKeyboardTrackingPropertiesFB::TYPE,
+            next: std::ptr::null_mut(),
+            supports_keyboard_tracking: false.into(),
+        };
+        // SAFETY: Zero is a valid value for every field of the properties, which the runtime overwrites.
+        let mut system_properties: sys::SystemProperties = unsafe { std::mem::zeroed() };
+        system_properties.ty = sys::SystemProperties::TYPE;
+        system_properties.next = &mut keyboard_tracking_properties as *mut _ as _;
+        check(unsafe {
+            (instance.fp().get_system_properties)(instance.as_raw(), system, &mut system_properties)
+        })?;
+        if !bool::from(keyboard_tracking_properties.supports_keyboard_tracking) {
+            println!(
+                "[HOTHAM_XR] This system can't track keyboards, keyboard tracking is disabled"
+            );
+            return Ok(None);
+        }
+
+        println!("[HOTHAM_XR] Keyboard tracking enabled");
+        let mut keyboard_tracking = Self {
+            session: session.clone(),
+            keyboard: None,
+        };
+        keyboard_tracking.update()?;
+        Ok(Some(keyboard_tracking))
+    }
+
+    /// Ask the runtime which keyboard the player has paired, as they can pair or connect one at any time
+    pub(crate) fn update(&mut self) -> Result<()> {
+        let fp = self.session.instance().exts().fb_keyboard_tracking.unwrap();
+        let query_info = sys::KeyboardTrackingQueryFB {
+            ty: sys::KeyboardTrackingQueryFB::TYPE,
+            next: std::ptr::null_mut(),
+            flags: sys::KeyboardTrackingQueryFlagsFB::LOCAL,
+        };
+        // SAFETY: Zero is a valid value for every field of the description, which the runtime overwrites.
+        let mut description: sys::KeyboardTrackingDescriptionFB = unsafe { std::mem::zeroed() };
+        check(unsafe {
+            (fp.query_system_tracked_keyboard)(self.session.as_raw(), &query_info, &mut description)
+        })?;
+
+        if !description
+            .flags
+            .contains(sys::KeyboardTrackingFlagsFB::EXISTS)
+        {
+            if self.keyboard.take().is_some() {
+                println!("[HOTHAM_XR] The keyboard was unpaired");
+            }
+            return Ok(());
+        }
+
+        let is_connected = description
+            .flags
+            .contains(sys::KeyboardTrackingFlagsFB::CONNECTED);
+        match &mut self.keyboard {
+            Some(keyboard) if keyboard.id == description.tracked_keyboard_id => {
+                keyboard.is_connected = is_connected;
+            }
+            _ => {
+                let create_info = sys::KeyboardSpaceCreateInfoFB {
+                    ty: sys::KeyboardSpaceCreateInfoFB::TYPE,
+                    next: std::ptr::null_mut(),
+                    tracked_keyboard_id: description.tracked_keyboard_id,
+                };
+                let mut space = sys::Space::NULL;
+                check(unsafe {
+                    (fp.create_keyboard_space)(self.session.as_raw(), &create_info, &mut space)
+                })?;
+                // SAFETY: The runtime has just handed us this space, and it's only destroyed when the `Space` is
+                // dropped.
+                let space = unsafe { Space::reference_from_raw(self.session.clone(), space) };
+
+                // SAFETY: The runtime null terminates the name.
+                let name = unsafe { CStr::from_ptr(description.name.as_ptr()) }
+                    .to_string_lossy()
+                    .into_owned();
+                println!("[HOTHAM_XR] Found the keyboard {name}");
+                self.keyboard = Some(TrackedKeyboard {
+                    name,
+                    size: Vec3::new(description.size.x, description.size.y, description.size.z),
+                    is_connected,
+                    id: description.tracked_keyboard_id,
+                    space,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the keyboard is relative to `base_space`, if it's being tracked
+    pub(crate) fn locate(&self, base_space: &Space, time: xr::Time) -> Result<Option<Affine3A>> {
+        let keyboard = match &self.keyboard {
+            Some(keyboard) => keyboard,
+            None => return Ok(None),
+        };
+        let location = keyboard.space.locate(base_space, time)?;
+        Ok((is_space_valid(&location)
+            && location
+                .location_flags
+                .contains(xr::SpaceLocationFlags::POSITION_TRACKED))
+        .then(|| affine_from_posef(location.pose)))
+    }
+}
+
+fn check(result: sys::Result) -> Result<()> {
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
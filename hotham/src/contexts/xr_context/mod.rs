@@ -5,18 +5,75 @@ use openxr::{
     Vulkan,
 };
 use xr::{
-    vulkan::SessionCreateInfo, Duration, FrameState, ReferenceSpaceType, SwapchainCreateFlags,
-    SwapchainUsageFlags, Time, View, ViewStateFlags,
+    vulkan::SessionCreateInfo, Duration, EnvironmentBlendMode, FrameState, ReferenceSpaceType,
+    SwapchainCreateFlags, SwapchainUsageFlags, Time, View, ViewConfigurationType, ViewStateFlags,
 };
 
 use crate::{
-    contexts::VulkanContext, util::is_view_valid, HothamError, HothamResult, BLEND_MODE,
-    COLOR_FORMAT, VIEW_COUNT, VIEW_TYPE,
+    components::hand::Handedness,
+    contexts::VulkanContext,
+    rendering::{camera::NEAR_PLANE, color_space::ColorSpace},
+    util::{affine_from_posef, is_space_valid, is_view_valid, posef_from_affine},
+    HothamError, HothamResult, BLEND_MODE, DEPTH_FORMAT, VIEW_COUNT,
 };
 
+mod body_tracking;
+mod composition_layer_settings;
+mod controller_models;
+mod custom_actions;
+#[cfg(not(target_os = "android"))]
+mod desktop_preview;
+mod display_color_space;
+mod events;
+mod foveation;
+mod hand_tracking;
+mod haptic_clips;
 mod input;
+mod keyboard_tracking;
+mod overlay;
+mod passthrough;
+mod performance_settings;
+mod play_area;
+mod quad_layer;
+mod reference_space;
+mod runtime_capabilities;
+mod scene;
+mod space_warp;
+mod spatial_anchors;
 mod time;
-use input::Input;
+mod trackers;
+mod view_configuration;
+pub use body_tracking::{BodyJointLocations, BodyTracking};
+pub use composition_layer_settings::{CompositionLayerSettings, LayerFilter};
+pub use custom_actions::{CustomAction, CustomActionSet, CustomActionType, CustomActions};
+pub use display_color_space::DisplayColorSpace;
+pub use events::{XrEvent, XrEvents};
+pub use foveation::{FoveationLevel, FoveationMode, FoveationSettings};
+pub use hand_tracking::HandTracking;
+pub(crate) use haptic_clips::{
+    apply_haptic_amplitude_envelope, apply_haptic_pcm, MAX_HAPTIC_SAMPLES,
+};
+pub use input::Input;
+pub use keyboard_tracking::{KeyboardTracking, TrackedKeyboard};
+pub use passthrough::Passthrough;
+pub use performance_settings::{
+    PerformanceDomain, PerformanceLevel, PerformanceNotification, PerformanceNotificationLevel,
+    PerformanceSubDomain,
+};
+pub use play_area::{PlayArea, PlayAreaEvent};
+pub use quad_layer::QuadLayer;
+pub use reference_space::ReferenceSpace;
+use reference_space::{emulates_local_floor, FloorLocator};
+pub use runtime_capabilities::RuntimeCapabilities;
+pub(crate) use scene::SceneElement;
+pub use scene::{Scene, SceneLabel};
+pub use space_warp::{SpaceWarp, MOTION_VECTOR_FORMAT};
+pub(crate) use spatial_anchors::SpatialAnchorEvent;
+pub use spatial_anchors::{AnchorUuid, SpatialAnchors};
+pub use trackers::{TrackerRole, Trackers};
+
+/// The name given to the runtime, and used for the application's storage, when the application doesn't set one
+pub(crate) const DEFAULT_APPLICATION_NAME: &str = "Hotham Application";
 
 #[derive(Default)]
 pub struct XrContextBuilder<'a> {
@@ -24,6 +81,30 @@ pub struct XrContextBuilder<'a> {
     application_name: Option<&'a str>,
     application_version: Option<u32>,
     required_extensions: Option<xr::ExtensionSet>,
+    foveation_settings: FoveationSettings,
+    foveation: FoveationMode,
+    depth_layer: bool,
+    color_space: ColorSpace,
+    display_color_space: Option<DisplayColorSpace>,
+    composition_layer_settings: CompositionLayerSettings,
+    video_recording: bool,
+    passthrough: bool,
+    space_warp: bool,
+    hand_tracking: bool,
+    body_tracking: bool,
+    eye_gaze: bool,
+    controller_models: bool,
+    keyboard_tracking: bool,
+    spatial_anchors: bool,
+    scene: bool,
+    trackers: bool,
+    overlay: bool,
+    environment_blend_mode: Option<EnvironmentBlendMode>,
+    view_configurations: Vec<ViewConfigurationType>,
+    custom_action_sets: Vec<CustomActionSet>,
+    reference_space: ReferenceSpace,
+    vulkan_validation: bool,
+    desktop_preview: bool,
 }
 
 impl<'a> XrContextBuilder<'a> {
@@ -51,16 +132,229 @@ impl<'a> XrContextBuilder<'a> {
         self
     }
 
+    pub fn foveation_settings(&mut self, foveation_settings: FoveationSettings) -> &mut Self {
+        self.foveation_settings = foveation_settings;
+        self
+    }
+
+    /// Tell the compositor which color space the application's colors are in, if the runtime supports
+    /// `XR_FB_color_space`. Defaults to `None`, which leaves it up to the runtime.
+    pub fn display_color_space(
+        &mut self,
+        display_color_space: Option<DisplayColorSpace>,
+    ) -> &mut Self {
+        self.display_color_space = display_color_space;
+        self
+    }
+
+    /// Ask the compositor to super sample or sharpen the application's frames, if the runtime supports
+    /// `XR_FB_composition_layer_settings`. Defaults to neither.
+    pub fn composition_layer_settings(
+        &mut self,
+        composition_layer_settings: CompositionLayerSettings,
+    ) -> &mut Self {
+        self.composition_layer_settings = composition_layer_settings;
+        self
+    }
+
+    /// Choose how foveated rendering is done. Must match `foveation` in the renderer's `RenderSettings`.
+    pub fn foveation(&mut self, foveation: FoveationMode) -> &mut Self {
+        self.foveation = foveation;
+        self
+    }
+
+    /// Create a depth swapchain and submit it to the compositor each frame, if the runtime supports it.
+    pub fn depth_layer(&mut self, depth_layer: bool) -> &mut Self {
+        self.depth_layer = depth_layer;
+        self
+    }
+
+    /// Choose the format of the swapchain. Must match the `color_space` in the renderer's `RenderSettings`.
+    pub fn color_space(&mut self, color_space: ColorSpace) -> &mut Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Allow the swapchain to be copied from, so frames can be recorded. Must match `video_recording` in the
+    /// renderer's `RenderSettings`.
+    pub fn video_recording(&mut self, video_recording: bool) -> &mut Self {
+        self.video_recording = video_recording;
+        self
+    }
+
+    /// Show the headset's camera feed behind the scene, if the runtime supports `XR_FB_passthrough`. Must match
+    /// `passthrough` in the renderer's `RenderSettings`.
+    pub fn passthrough(&mut self, passthrough: bool) -> &mut Self {
+        self.passthrough = passthrough;
+        self
+    }
+
+    /// Submit motion vectors to the compositor so it can synthesize every other frame, if the runtime supports
+    /// `XR_FB_space_warp`. Must match `space_warp` in the renderer's `RenderSettings`.
+    pub fn space_warp(&mut self, space_warp: bool) -> &mut Self {
+        self.space_warp = space_warp;
+        self
+    }
+
+    /// Track the joints of the player's hands, if the runtime supports `XR_EXT_hand_tracking`.
+    pub fn hand_tracking(&mut self, hand_tracking: bool) -> &mut Self {
+        self.hand_tracking = hand_tracking;
+        self
+    }
+
+    /// Track the joints of the player's body, if the runtime supports `XR_FB_body_tracking`.
+    pub fn body_tracking(&mut self, body_tracking: bool) -> &mut Self {
+        self.body_tracking = body_tracking;
+        self
+    }
+
+    /// Track where the player is looking, if the runtime supports `XR_EXT_eye_gaze_interaction`.
+    pub fn eye_gaze(&mut self, eye_gaze: bool) -> &mut Self {
+        self.eye_gaze = eye_gaze;
+        self
+    }
+
+    /// Track extra devices, such as Vive trackers, if the runtime supports `XR_HTCX_vive_tracker_interaction`.
+    pub fn trackers(&mut self, trackers: bool) -> &mut Self {
+        self.trackers = trackers;
+        self
+    }
+
+    /// Let the runtime provide models of the player's controllers, if it supports `XR_FB_render_model` or
+    /// `XR_MSFT_controller_model`. With `XR_FB_render_model`, models of other devices such as keyboards are
+    /// available too.
+    pub fn controller_models(&mut self, controller_models: bool) -> &mut Self {
+        self.controller_models = controller_models;
+        self
+    }
+
+    /// Locate the player's physical keyboard, if the runtime supports `XR_FB_keyboard_tracking`.
+    pub fn keyboard_tracking(&mut self, keyboard_tracking: bool) -> &mut Self {
+        self.keyboard_tracking = keyboard_tracking;
+        self
+    }
+
+    /// Let the application pin content to the real world, and keep it there across runs, if the runtime supports
+    /// `XR_FB_spatial_entity`, `XR_FB_spatial_entity_storage` and `XR_FB_spatial_entity_query`.
+    pub fn spatial_anchors(&mut self, spatial_anchors: bool) -> &mut Self {
+        self.spatial_anchors = spatial_anchors;
+        self
+    }
+
+    /// Load the layout of the player's room, if the runtime supports `XR_FB_scene`.
+    pub fn scene(&mut self, scene: bool) -> &mut Self {
+        self.scene = scene;
+        self
+    }
+
+    /// Run as an overlay on top of another application, if the runtime supports `XR_EXTX_overlay`. Only the
+    /// [`QuadLayer`]s are shown, so the other application can be seen around them.
+    pub fn overlay(&mut self, overlay: bool) -> &mut Self {
+        self.overlay = overlay;
+        self
+    }
+
+    /// Ask the compositor to blend frames with the real world, eg. `ADDITIVE` or `ALPHA_BLEND` on AR headsets. Falls
+    /// back to [`BLEND_MODE`], then to whatever the runtime prefers, if the headset doesn't support it. Defaults to
+    /// `None`, which uses [`BLEND_MODE`].
+    pub fn environment_blend_mode(
+        &mut self,
+        environment_blend_mode: Option<EnvironmentBlendMode>,
+    ) -> &mut Self {
+        self.environment_blend_mode = environment_blend_mode;
+        self
+    }
+
+    /// The view configurations the application can render, in order of preference. The first one the runtime supports
+    /// is used, as long as it has [`VIEW_COUNT`](crate::VIEW_COUNT) views. Defaults to empty, which uses
+    /// [`VIEW_TYPE`](crate::VIEW_TYPE).
+    pub fn view_configurations(
+        &mut self,
+        view_configurations: Vec<ViewConfigurationType>,
+    ) -> &mut Self {
+        self.view_configurations = view_configurations;
+        self
+    }
+
+    /// Register the application's own actions, which can be queried each frame from `XrContext::input`.
+    pub fn custom_action_sets(&mut self, custom_action_sets: Vec<CustomActionSet>) -> &mut Self {
+        self.custom_action_sets = custom_action_sets;
+        self
+    }
+
+    /// Choose which of the runtime's reference spaces is used as the stage. Defaults to `Stage`.
+    pub fn reference_space(&mut self, reference_space: ReferenceSpace) -> &mut Self {
+        self.reference_space = reference_space;
+        self
+    }
+
+    /// Enable the Vulkan validation layers, if they're installed, and print their messages.
+    pub fn vulkan_validation(&mut self, vulkan_validation: bool) -> &mut Self {
+        self.vulkan_validation = vulkan_validation;
+        self
+    }
+
+    /// Run in a window on the desktop using the Hotham simulator, rather than the system's OpenXR runtime. Ignored on
+    /// Android, and when `path` is set.
+    pub fn desktop_preview(&mut self, desktop_preview: bool) -> &mut Self {
+        self.desktop_preview = desktop_preview;
+        self
+    }
+
     pub fn build(&mut self) -> Result<(XrContext, VulkanContext)> {
-        let application_name = self.application_name.unwrap_or("Hotham Application");
+        if self.desktop_preview && self.path.is_none() {
+            #[cfg(not(target_os = "android"))]
+            desktop_preview::use_simulator_runtime()?;
+        }
+
+        let application_name = self.application_name.unwrap_or(DEFAULT_APPLICATION_NAME);
         let application_version = self.application_version.unwrap_or(1);
-        let (instance, system) = create_xr_instance(
+        let (instance, system, extensions) = create_xr_instance(
             self.path,
             application_name,
             application_version,
             self.required_extensions.as_ref(),
+            self.foveation,
+            self.passthrough,
+            self.space_warp,
+            self.hand_tracking,
+            self.body_tracking,
+            self.eye_gaze,
+            self.controller_models,
+            self.keyboard_tracking,
+            self.spatial_anchors,
+            self.scene,
+            self.trackers,
+            self.overlay,
         )?;
-        XrContext::_new(instance, system, application_name, application_version)
+        let (mut xr_context, vulkan_context) = XrContext::_new(
+            instance,
+            system,
+            extensions,
+            application_name,
+            application_version,
+            self.foveation_settings,
+            self.foveation,
+            self.depth_layer,
+            self.color_space,
+            self.display_color_space,
+            self.video_recording,
+            self.passthrough,
+            self.space_warp,
+            self.hand_tracking,
+            self.body_tracking,
+            self.keyboard_tracking,
+            self.spatial_anchors,
+            self.scene,
+            self.overlay,
+            self.environment_blend_mode.unwrap_or(BLEND_MODE),
+            &self.view_configurations,
+            &self.custom_action_sets,
+            self.reference_space,
+            self.vulkan_validation,
+        )?;
+        xr_context.composition_layer_settings = self.composition_layer_settings;
+        Ok((xr_context, vulkan_context))
     }
 }
 
@@ -78,6 +372,57 @@ pub struct XrContext {
     pub frame_state: FrameState,
     pub views: Vec<View>,
     pub view_state_flags: ViewStateFlags,
+    pub foveation_settings: FoveationSettings,
+    /// How foveated rendering is done, after falling back to `Disabled` if the runtime doesn't support it
+    foveation: FoveationMode,
+    /// Receives the depth buffer, if depth is submitted to the compositor
+    pub depth_swapchain: Option<Swapchain<Vulkan>>,
+    /// Quads drawn by the compositor on top of the scene, in order
+    pub quad_layers: Vec<QuadLayer>,
+    /// Only present if passthrough was requested and the runtime supports it
+    pub passthrough: Option<Passthrough>,
+    /// Only present if SpaceWarp was requested and the runtime supports it
+    pub space_warp: Option<SpaceWarp>,
+    /// Only present if hand tracking was requested and the runtime supports it
+    pub hand_tracking: Option<HandTracking>,
+    /// Only present if body tracking was requested and the runtime supports it
+    pub body_tracking: Option<BodyTracking>,
+    /// Only present if keyboard tracking was requested and the runtime supports it
+    pub keyboard_tracking: Option<KeyboardTracking>,
+    /// Only present if spatial anchors were requested and the runtime supports them
+    pub spatial_anchors: Option<SpatialAnchors>,
+    /// Only present if scene understanding was requested and the runtime supports it
+    pub scene: Option<Scene>,
+    /// The refresh rate of the display in Hz. Only present if the runtime supports `XR_FB_display_refresh_rate`
+    display_refresh_rate: Option<f32>,
+    /// Is this application running as an overlay on top of another one, with `XR_EXTX_overlay`?
+    overlay: bool,
+    /// The view configuration the session was begun with
+    view_configuration_type: ViewConfigurationType,
+    /// How the compositor blends frames with the real world, chosen from the ones the headset supports
+    environment_blend_mode: EnvironmentBlendMode,
+    /// The color space the compositor assumes our colors are in. Only present if the runtime supports
+    /// `XR_FB_color_space`
+    display_color_space: Option<DisplayColorSpace>,
+    /// Filters the compositor applies to the application's frames. Only used if the runtime supports
+    /// `XR_FB_composition_layer_settings`, and takes effect from the next frame.
+    pub composition_layer_settings: CompositionLayerSettings,
+    /// The events the runtime sent since the start of the last `Engine::update`
+    pub events: XrEvents,
+    /// The play area, once the runtime knows where it is
+    play_area: Option<PlayArea>,
+    /// Which of our reference spaces `stage_space` is
+    reference_space: ReferenceSpace,
+    /// Which of the runtime's reference spaces `stage_space` is relative to
+    reference_space_type: ReferenceSpaceType,
+    /// Where `stage_space` is in the runtime's reference space, after finding the floor and recentering
+    runtime_stage_from_stage: glam::Affine3A,
+    /// Is `stage_space` waiting to be moved down to the floor?
+    floor_pending: bool,
+    /// Finds the floor while `floor_pending` is set, created the first time it's needed
+    floor_locator: Option<FloorLocator>,
+    /// What the runtime can do
+    pub runtime_capabilities: RuntimeCapabilities,
 }
 
 impl XrContext {
@@ -97,22 +442,167 @@ impl XrContext {
     fn _new(
         instance: xr::Instance,
         system: xr::SystemId,
+        extensions: xr::ExtensionSet,
         application_name: &str,
         application_version: u32,
+        foveation_settings: FoveationSettings,
+        foveation: FoveationMode,
+        depth_layer: bool,
+        color_space: ColorSpace,
+        display_color_space: Option<DisplayColorSpace>,
+        video_recording: bool,
+        passthrough: bool,
+        space_warp: bool,
+        hand_tracking: bool,
+        body_tracking: bool,
+        keyboard_tracking: bool,
+        spatial_anchors: bool,
+        scene: bool,
+        overlay: bool,
+        environment_blend_mode: EnvironmentBlendMode,
+        view_configurations: &[ViewConfigurationType],
+        custom_action_sets: &[CustomActionSet],
+        reference_space: ReferenceSpace,
+        vulkan_validation: bool,
     ) -> Result<(XrContext, VulkanContext)> {
-        let vulkan_context =
-            create_vulkan_context(&instance, system, application_name, application_version)?;
+        let supported_view_configurations =
+            view_configuration::supported_view_configurations(&instance, system)?;
+        let view_configuration_type = view_configuration::choose_view_configuration(
+            view_configurations,
+            &supported_view_configurations,
+        )?;
+        let runtime_capabilities = RuntimeCapabilities::new(
+            &instance,
+            system,
+            extensions,
+            supported_view_configurations
+                .iter()
+                .map(|(view_configuration, _)| *view_configuration)
+                .collect(),
+            view_configuration_type,
+        )?;
+        println!("[HOTHAM_XR] {}", runtime_capabilities.report());
+        println!("[HOTHAM_XR] Rendering {view_configuration_type:?}");
+        let environment_blend_mode = view_configuration::choose_environment_blend_mode(
+            environment_blend_mode,
+            &runtime_capabilities.environment_blend_modes,
+        );
 
+        let vulkan_context = create_vulkan_context(
+            &instance,
+            system,
+            application_name,
+            application_version,
+            vulkan_validation,
+        )?;
+
+        let overlay = overlay && instance.exts().extx_overlay.is_some();
         let (session, frame_waiter, frame_stream) =
-            create_xr_session(&instance, system, &vulkan_context)?;
+            create_xr_session(&instance, system, &vulkan_context, overlay)?;
+        let (reference_space, reference_space_type) = reference_space.supported(&session)?;
+        let foveation = foveation.supported(&instance);
         let stage_space =
-            session.create_reference_space(ReferenceSpaceType::STAGE, xr::Posef::IDENTITY)?;
+            session.create_reference_space(reference_space_type, xr::Posef::IDENTITY)?;
         let view_space =
             session.create_reference_space(ReferenceSpaceType::VIEW, xr::Posef::IDENTITY)?;
-        let swapchain_resolution = get_swapchain_resolution(&instance, system)?;
-        let swapchain = create_xr_swapchain(&session, &swapchain_resolution, VIEW_COUNT)?;
+        let swapchain_resolution =
+            get_swapchain_resolution(&instance, system, view_configuration_type)?;
+        let color_format = color_space.swapchain_format();
+        if !session
+            .enumerate_swapchain_formats()?
+            .contains(&(color_format.as_raw() as _))
+        {
+            println!("[HOTHAM_XR] WARNING: The runtime doesn't list {color_format:?} as a supported swapchain format!");
+        }
+        // Frames are recorded by copying them out of the swapchain.
+        let usage_flags = if video_recording {
+            SwapchainUsageFlags::COLOR_ATTACHMENT | SwapchainUsageFlags::TRANSFER_SRC
+        } else {
+            SwapchainUsageFlags::COLOR_ATTACHMENT
+        };
+        let swapchain = create_xr_swapchain(
+            &session,
+            &swapchain_resolution,
+            VIEW_COUNT,
+            color_format,
+            usage_flags,
+            (foveation == FoveationMode::Runtime).then_some(&foveation_settings),
+        )?;
+        let depth_swapchain = if depth_layer {
+            create_xr_depth_swapchain(&instance, &session, &swapchain_resolution, VIEW_COUNT)?
+        } else {
+            None
+        };
 
-        let input = Input::oculus_touch_controller(&instance, &session)?;
+        let passthrough = if passthrough {
+            Passthrough::new(&session)?
+        } else {
+            None
+        };
+
+        let space_warp = if space_warp {
+            SpaceWarp::new(&instance, &session, system)?
+        } else {
+            None
+        };
+
+        let display_refresh_rate = if instance.exts().fb_display_refresh_rate.is_some() {
+            let display_refresh_rate = session.get_display_refresh_rate()?;
+            println!("[HOTHAM_XR] Display refresh rate is {display_refresh_rate}Hz");
+            Some(display_refresh_rate)
+        } else {
+            None
+        };
+
+        let display_color_space = if instance.exts().fb_color_space.is_some() {
+            if let Some(display_color_space) = display_color_space {
+                display_color_space::set_color_space(&session, display_color_space)?;
+            }
+            let display_color_space =
+                display_color_space.or(display_color_space::native_color_space(&instance, system)?);
+            println!("[HOTHAM_XR] Display color space is {display_color_space:?}");
+            display_color_space
+        } else {
+            None
+        };
+
+        let hand_tracking = if hand_tracking {
+            HandTracking::new(&instance, &session, system)?
+        } else {
+            None
+        };
+
+        let body_tracking = if body_tracking {
+            BodyTracking::new(
+                &instance,
+                &session,
+                system,
+                runtime_capabilities.has_other_extension(body_tracking::FB_BODY_TRACKING),
+            )?
+        } else {
+            None
+        };
+
+        let keyboard_tracking = if keyboard_tracking {
+            KeyboardTracking::new(&instance, &session, system)?
+        } else {
+            None
+        };
+
+        let spatial_anchors = if spatial_anchors {
+            SpatialAnchors::new(&session)?
+        } else {
+            None
+        };
+
+        let scene = if scene { Scene::new(&session)? } else { None };
+
+        let input = Input::new(
+            &instance,
+            &session,
+            &runtime_capabilities,
+            custom_action_sets,
+        )?;
 
         let frame_state = FrameState {
             predicted_display_time: Time::from_nanos(0),
@@ -120,8 +610,11 @@ impl XrContext {
             should_render: false,
         };
 
-        // Attach the action set to the session
-        session.attach_action_sets(&[&input.action_set])?;
+        // Attach the action sets to the session
+        let action_sets = std::iter::once(&input.action_set)
+            .chain(input.custom_actions.action_sets())
+            .collect::<Vec<_>>();
+        session.attach_action_sets(&action_sets)?;
 
         let xr_context = XrContext {
             instance,
@@ -137,44 +630,186 @@ impl XrContext {
             frame_state,
             views: vec![Default::default(); VIEW_COUNT as usize],
             view_state_flags: ViewStateFlags::EMPTY,
+            foveation_settings,
+            foveation,
+            depth_swapchain,
+            quad_layers: Vec::new(),
+            passthrough,
+            space_warp,
+            hand_tracking,
+            body_tracking,
+            keyboard_tracking,
+            spatial_anchors,
+            scene,
+            display_refresh_rate,
+            events: Default::default(),
+            overlay,
+            view_configuration_type,
+            environment_blend_mode,
+            display_color_space,
+            composition_layer_settings: Default::default(),
+            play_area: None,
+            reference_space,
+            reference_space_type,
+            runtime_stage_from_stage: glam::Affine3A::IDENTITY,
+            floor_pending: emulates_local_floor(reference_space, reference_space_type),
+            floor_locator: None,
+            runtime_capabilities,
         };
 
         Ok((xr_context, vulkan_context))
     }
 
-    pub(crate) fn poll_xr_event(
-        &mut self,
-        event_buffer: &mut EventDataBuffer,
-    ) -> Result<SessionState> {
+    /// Handle the next event from the runtime, if there is one. Returns `false` once there are none left.
+    pub(crate) fn poll_xr_event(&mut self, event_buffer: &mut EventDataBuffer) -> Result<bool> {
         match self.instance.poll_event(event_buffer)? {
             Some(xr::Event::SessionStateChanged(session_changed)) => {
                 let new_state = session_changed.state();
                 println!("[HOTHAM_POLL_EVENT] State is now {new_state:?}");
+                self.events
+                    .push_session_state_change(self.session_state, new_state);
                 self.session_state = new_state;
+
+                // The runtime may not know where the play area is until the session is running.
+                self.update_play_area()?;
+            }
+            Some(xr::Event::ReferenceSpaceChangePending(change))
+                if change.reference_space_type() == self.reference_space_type =>
+            {
+                // The player recentered the view with the runtime, eg. by holding the Oculus button, which replaces
+                // any recentering of our own.
+                println!("[HOTHAM_POLL_EVENT] The stage is changing");
+                self.set_runtime_stage_from_stage(glam::Affine3A::IDENTITY)?;
+                self.floor_pending =
+                    emulates_local_floor(self.reference_space, self.reference_space_type);
+                self.events.push(XrEvent::ReferenceSpaceChanged);
+                self.update_play_area()?;
+            }
+            Some(xr::Event::ReferenceSpaceChangePending(_)) => {
+                // We don't use the reference space that's changing.
+            }
+            Some(xr::Event::InteractionProfileChanged(_)) => {
+                let input = &self.input;
+                for (handedness, subaction_path) in [
+                    (Handedness::Left, input.left_hand_subaction_path),
+                    (Handedness::Right, input.right_hand_subaction_path),
+                ] {
+                    // The runtime picks the profile that best matches the controller, from the ones we suggested.
+                    let profile = self.session.current_interaction_profile(subaction_path)?;
+                    let profile = if profile == xr::Path::NULL {
+                        None
+                    } else {
+                        Some(self.instance.path_to_string(profile)?)
+                    };
+                    println!(
+                        "[HOTHAM_POLL_EVENT] {} is now using {}",
+                        self.instance.path_to_string(subaction_path)?,
+                        profile.as_deref().unwrap_or("nothing")
+                    );
+                    self.events.push(XrEvent::InteractionProfileChanged {
+                        handedness,
+                        profile,
+                    });
+                }
+            }
+            Some(xr::Event::DisplayRefreshRateChangedFB(refresh_rate_changed)) => {
+                let display_refresh_rate = refresh_rate_changed.to_display_refresh_rate();
+                let from = refresh_rate_changed.from_display_refresh_rate();
+                println!(
+                    "[HOTHAM_POLL_EVENT] Display refresh rate changed from {from}Hz to {display_refresh_rate}Hz"
+                );
+                self.events.push(XrEvent::RefreshRateChanged {
+                    from,
+                    to: display_refresh_rate,
+                });
+                self.display_refresh_rate = Some(display_refresh_rate);
+            }
+            Some(xr::Event::MainSessionVisibilityChangedEXTX(visibility_changed)) => {
+                let visible = visibility_changed.visible();
+                println!("[HOTHAM_POLL_EVENT] The main session's visibility changed to {visible}");
+                self.events
+                    .push(XrEvent::MainSessionVisibilityChanged { visible });
+            }
+            Some(xr::Event::PerfSettingsEXT(perf_settings)) => {
+                let notification = PerformanceNotification::from_raw(
+                    perf_settings.domain(),
+                    perf_settings.sub_domain(),
+                    perf_settings.from_level(),
+                    perf_settings.to_level(),
+                );
+                println!("[HOTHAM_POLL_EVENT] Performance notification: {notification:?}");
+                self.events.extend(notification.map(XrEvent::Performance));
+            }
+            Some(
+                event @ (xr::Event::SpatialAnchorCreateCompleteFB(_)
+                | xr::Event::SpaceSetStatusCompleteFB(_)
+                | xr::Event::SpaceSaveCompleteFB(_)
+                | xr::Event::SpaceQueryResultsAvailableFB(_)
+                | xr::Event::SpaceQueryCompleteFB(_)),
+            ) => {
+                // Spatial anchors and the scene share events, so each ignores requests it didn't make.
+                if let Some(spatial_anchors) = &mut self.spatial_anchors {
+                    spatial_anchors.handle_event(&self.session, &event)?;
+                }
+                if let Some(scene) = &mut self.scene {
+                    scene.handle_event(&self.session, &event)?;
+                }
             }
             Some(xr::Event::InstanceLossPending(_)) => {
                 println!("[HOTHAM_POLL_EVENT] Instance loss pending!");
+                self.events.push(XrEvent::InstanceLossPending);
+            }
+            Some(xr::Event::EventsLost(events_lost)) => {
+                let count = events_lost.lost_event_count();
+                println!("[HOTHAM_POLL_EVENT] {count} events were lost!");
+                self.events.push(XrEvent::EventsLost { count });
             }
             Some(_) => println!("[HOTHAM_POLL_EVENT] Received some other event"),
-            None => {}
+            None => return Ok(false),
         }
 
-        Ok(self.session_state)
+        Ok(true)
     }
 
     pub(crate) fn begin_frame(&mut self) -> HothamResult<usize> {
         self.frame_state = self.frame_waiter.wait()?;
         self.frame_stream.begin()?;
 
+        // The floor can only be found once the runtime is tracking the headset.
+        if self.floor_pending {
+            if let Err(e) = self.locate_floor() {
+                println!("[HOTHAM_XR] Unable to find the floor, trying again next frame: {e:?}");
+            }
+        }
+
+        // The runtime still needs every frame to be ended, even if there's nothing to show, eg. while SYNCHRONIZED.
         if !self.frame_state.should_render {
+            self.frame_stream.end(
+                self.frame_state.predicted_display_time,
+                self.environment_blend_mode,
+                &[],
+            )?;
             return Err(HothamError::NotRendering);
         }
 
         let image_index = self.swapchain.acquire_image()? as _;
         self.swapchain.wait_image(openxr::Duration::INFINITE)?;
 
-        let active_action_set = xr::ActiveActionSet::new(&self.input.action_set);
-        self.session.sync_actions(&[active_action_set])?;
+        // Both swapchains have the same length and are acquired in lockstep, so they share an image index.
+        if let Some(depth_swapchain) = &mut self.depth_swapchain {
+            let depth_image_index: usize = depth_swapchain.acquire_image()? as _;
+            depth_swapchain.wait_image(openxr::Duration::INFINITE)?;
+            debug_assert_eq!(image_index, depth_image_index);
+        }
+        if let Some(space_warp) = &mut self.space_warp {
+            space_warp.acquire_images(image_index)?;
+        }
+
+        let active_action_sets = std::iter::once(&self.input.action_set)
+            .chain(self.input.custom_actions.active_action_sets())
+            .map(xr::ActiveActionSet::new)
+            .collect::<Vec<_>>();
+        self.session.sync_actions(&active_action_sets)?;
 
         Ok(image_index)
     }
@@ -183,7 +818,7 @@ impl XrContext {
         let (view_state_flags, views) = self
             .session
             .locate_views(
-                VIEW_TYPE,
+                self.view_configuration_type,
                 self.frame_state.predicted_display_time,
                 &self.stage_space,
             )
@@ -197,17 +832,45 @@ impl XrContext {
         &self.views
     }
 
+    /// Where `space` is in stage space at `time`, or `None` if it isn't being tracked. `time` can be in the future,
+    /// eg. to lead a projectile by where a controller is predicted to be, or in the recent past, eg. to compensate for
+    /// latency. Runtimes only predict a short way ahead, and only keep a short history.
+    pub fn locate_space(&self, space: &Space, time: Time) -> Result<Option<glam::Affine3A>> {
+        let location = space.locate(&self.stage_space, time)?;
+        Ok(is_space_valid(&location).then(|| affine_from_posef(location.pose)))
+    }
+
+    /// Where the player's eyes are in stage space at `time`, and what they can see, or `None` if the headset isn't
+    /// being tracked. Unlike [`XrContext::views`], which are for the frame being rendered, `time` can be any time the
+    /// runtime can predict; see [`XrContext::locate_space`].
+    pub fn locate_views(&self, time: Time) -> Result<Option<Vec<View>>> {
+        let (view_state_flags, views) =
+            self.session
+                .locate_views(self.view_configuration_type, time, &self.stage_space)?;
+        Ok(is_view_valid(&view_state_flags).then_some(views))
+    }
+
     pub fn end_frame(&mut self) -> std::result::Result<(), openxr::sys::Result> {
         // If we aren't in the rendering state, just submit empty views.
         if !self.frame_state.should_render {
             self.frame_stream
-                .end(self.frame_state.predicted_display_time, BLEND_MODE, &[])
+                .end(
+                    self.frame_state.predicted_display_time,
+                    self.environment_blend_mode,
+                    &[],
+                )
                 .unwrap();
             return Ok(());
         }
 
-        // Release the swapchain image.
+        // Release the swapchain images.
         self.swapchain.release_image().unwrap();
+        if let Some(depth_swapchain) = &mut self.depth_swapchain {
+            depth_swapchain.release_image().unwrap();
+        }
+        if let Some(space_warp) = &mut self.space_warp {
+            space_warp.release_images().unwrap();
+        }
 
         let rect = xr::Rect2Di {
             offset: xr::Offset2Di { x: 0, y: 0 },
@@ -240,12 +903,348 @@ impl XrContext {
                 ),
         ];
 
+        // If there's a depth swapchain, chain a depth layer onto each view.
+        // Remember that we're using inverse Z: a depth of zero is infinitely far away, and one is the near plane.
+        let depth_infos = self.depth_swapchain.as_ref().map(|depth_swapchain| {
+            [0, 1].map(|image_array_index| xr::sys::CompositionLayerDepthInfoKHR {
+                ty: xr::sys::CompositionLayerDepthInfoKHR::TYPE,
+                next: std::ptr::null(),
+                sub_image: xr::sys::SwapchainSubImage {
+                    swapchain: depth_swapchain.as_raw(),
+                    image_rect: rect,
+                    image_array_index,
+                },
+                min_depth: 0.,
+                max_depth: 1.,
+                near_z: f32::INFINITY,
+                far_z: NEAR_PLANE,
+            })
+        });
+
+        // Likewise for SpaceWarp, which goes in front of the depth info if there is one.
+        let mut space_warp_infos = self
+            .space_warp
+            .as_ref()
+            .map(|space_warp| space_warp.layer_infos());
+        if let (Some(space_warp_infos), Some(depth_infos)) = (&mut space_warp_infos, &depth_infos) {
+            for (space_warp_info, depth_info) in space_warp_infos.iter_mut().zip(depth_infos) {
+                space_warp_info.next = depth_info as *const _ as _;
+            }
+        }
+        let chains: Option<[*const std::ffi::c_void; 2]> = match (&space_warp_infos, &depth_infos) {
+            (Some(space_warp_infos), _) => {
+                Some([0, 1].map(|i| &space_warp_infos[i] as *const _ as _))
+            }
+            (None, Some(depth_infos)) => Some([0, 1].map(|i| &depth_infos[i] as *const _ as _)),
+            (None, None) => None,
+        };
+        let views = match chains {
+            Some(chains) => {
+                let mut views = views.map(|view| view.into_raw());
+                for (view, next) in views.iter_mut().zip(chains) {
+                    view.next = next;
+                }
+                // SAFETY: The chained infos outlive the views, which are only used until the end of this function.
+                views.map(|view| unsafe { xr::CompositionLayerProjectionView::from_raw(view) })
+            }
+            None => views,
+        };
+
+        // With passthrough underneath, or on a headset that blends with the real world using the alpha channel, the
+        // projection layer is blended over it using the alpha channel.
+        let passthrough_layer = self
+            .passthrough
+            .as_ref()
+            .filter(|passthrough| passthrough.is_running())
+            .map(|passthrough| passthrough.composition_layer());
+        let layer_flags = if passthrough_layer.is_some()
+            || self.environment_blend_mode == EnvironmentBlendMode::ALPHA_BLEND
+        {
+            xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
+        } else {
+            xr::CompositionLayerFlags::EMPTY
+        };
+
         let layer_projection = xr::CompositionLayerProjection::new()
+            .layer_flags(layer_flags)
             .space(&self.stage_space)
             .views(&views);
 
-        let layers = [&*layer_projection];
-        self.frame_stream.end(display_time, BLEND_MODE, &layers)
+        // Ask the compositor to super sample or sharpen the projection layer, if the application wants it to.
+        let layer_settings = self
+            .runtime_capabilities
+            .extensions
+            .fb_composition_layer_settings
+            .then(|| self.composition_layer_settings.to_raw())
+            .flatten();
+        let layer_projection = match &layer_settings {
+            Some(layer_settings) => {
+                let mut layer_projection = layer_projection.into_raw();
+                layer_projection.next = layer_settings as *const _ as _;
+                // SAFETY: The settings outlive the layer, which is only used until the end of this function.
+                unsafe { xr::CompositionLayerProjection::from_raw(layer_projection) }
+            }
+            None => layer_projection,
+        };
+
+        let quads: Vec<_> = self
+            .quad_layers
+            .iter()
+            .filter_map(|quad_layer| quad_layer.composition_layer(&self.stage_space))
+            .collect();
+
+        let mut layers: Vec<&xr::CompositionLayerBase<Vulkan>> = Vec::new();
+        // An overlay only shows its quads, so the application underneath can be seen around them.
+        if !self.overlay {
+            if let Some(passthrough_layer) = &passthrough_layer {
+                // SAFETY: Every composition layer starts with the same header, and the runtime reads the rest of the
+                // layer based on its type. The layer outlives `layers`.
+                layers.push(unsafe {
+                    &*(passthrough_layer as *const xr::sys::CompositionLayerPassthroughFB
+                        as *const xr::CompositionLayerBase<Vulkan>)
+                });
+            }
+            layers.push(&*layer_projection);
+        }
+        layers.extend(quads.iter().map(|quad| &**quad));
+        self.frame_stream
+            .end(display_time, self.environment_blend_mode, &layers)
+    }
+
+    /// Change the Fixed Foveated Rendering settings. Takes effect from the next frame.
+    pub fn set_foveation_settings(&mut self, foveation_settings: FoveationSettings) -> Result<()> {
+        #[cfg(target_os = "android")]
+        if self.foveation == FoveationMode::Runtime {
+            foveation::apply_foveation_settings(
+                &self.session,
+                self.swapchain.as_raw(),
+                &foveation_settings,
+            )?;
+        }
+
+        self.foveation_settings = foveation_settings;
+        Ok(())
+    }
+
+    /// The refresh rates the display supports, in Hz. Empty if the runtime doesn't support
+    /// `XR_FB_display_refresh_rate`.
+    pub fn supported_refresh_rates(&self) -> Result<Vec<f32>> {
+        if self.display_refresh_rate.is_none() {
+            return Ok(Vec::new());
+        }
+        Ok(self.session.enumerate_display_refresh_rates()?)
+    }
+
+    /// Is this application running as an overlay on top of another one? Only if an overlay was requested with
+    /// [`XrContextBuilder::overlay`] and the runtime supports `XR_EXTX_overlay`.
+    pub fn is_overlay(&self) -> bool {
+        self.overlay
+    }
+
+    /// The view configuration being rendered: the first of [`XrContextBuilder::view_configurations`] the runtime
+    /// supports, or [`VIEW_TYPE`](crate::VIEW_TYPE) by default.
+    pub fn view_configuration_type(&self) -> ViewConfigurationType {
+        self.view_configuration_type
+    }
+
+    /// How the compositor blends frames with the real world. This is the mode requested with
+    /// [`XrContextBuilder::environment_blend_mode`] if the headset supports it; the ones it supports are in
+    /// [`RuntimeCapabilities::environment_blend_modes`]. Anything but `OPAQUE` means the real world shows through
+    /// wherever nothing is drawn.
+    pub fn environment_blend_mode(&self) -> EnvironmentBlendMode {
+        self.environment_blend_mode
+    }
+
+    /// The current refresh rate of the display in Hz, if the runtime supports `XR_FB_display_refresh_rate`
+    pub fn refresh_rate(&self) -> Option<f32> {
+        self.display_refresh_rate
+    }
+
+    /// Ask the runtime to change the refresh rate of the display, eg. to 90Hz, or pass 0 to let the runtime choose.
+    /// The runtime may take a while to change it, or refuse to, eg. if the device is too hot, so watch for
+    /// [`XrEvent::RefreshRateChanged`] rather than assuming it's changed.
+    pub fn set_refresh_rate(&mut self, refresh_rate: f32) -> Result<()> {
+        if self.display_refresh_rate.is_none() {
+            return Err(anyhow::anyhow!(
+                "XR_FB_display_refresh_rate is not supported, so the refresh rate can't be changed"
+            ));
+        }
+
+        let supported_refresh_rates = self.supported_refresh_rates()?;
+        if refresh_rate != 0. && !supported_refresh_rates.contains(&refresh_rate) {
+            return Err(anyhow::anyhow!(
+                "{refresh_rate}Hz is not supported, expected one of {supported_refresh_rates:?}"
+            ));
+        }
+
+        self.session.request_display_refresh_rate(refresh_rate)?;
+        Ok(())
+    }
+
+    /// Ask the runtime to run the CPU or GPU at a given performance level, eg. [`PerformanceLevel::PowerSavings`] on
+    /// a loading screen, or [`PerformanceLevel::Boost`] while loading a level. The runtime treats this as a hint,
+    /// and tells the application how it's coping with [`XrEvent::Performance`]. Fails if the runtime
+    /// doesn't support `XR_EXT_performance_settings`.
+    pub fn set_performance_level(
+        &self,
+        domain: PerformanceDomain,
+        level: PerformanceLevel,
+    ) -> Result<()> {
+        if self.instance.exts().ext_performance_settings.is_none() {
+            return Err(anyhow::anyhow!(
+                "XR_EXT_performance_settings is not supported, so the performance level can't be changed"
+            ));
+        }
+
+        performance_settings::set_performance_level(&self.session, domain, level)
+    }
+
+    /// The color spaces the compositor can convert from. Empty if the runtime doesn't support `XR_FB_color_space`.
+    pub fn supported_display_color_spaces(&self) -> Result<Vec<DisplayColorSpace>> {
+        if self.instance.exts().fb_color_space.is_none() {
+            return Ok(Vec::new());
+        }
+        display_color_space::enumerate_color_spaces(&self.session)
+    }
+
+    /// The color space the compositor assumes our colors are in, if the runtime supports `XR_FB_color_space`
+    pub fn display_color_space(&self) -> Option<DisplayColorSpace> {
+        self.display_color_space
+    }
+
+    /// Tell the compositor which color space our colors are in, eg. `Rec709` for art made on an sRGB monitor, so
+    /// they look the same on every headset
+    pub fn set_display_color_space(
+        &mut self,
+        display_color_space: DisplayColorSpace,
+    ) -> Result<()> {
+        if self.instance.exts().fb_color_space.is_none() {
+            return Err(anyhow::anyhow!(
+                "XR_FB_color_space is not supported, so the color space can't be changed"
+            ));
+        }
+
+        display_color_space::set_color_space(&self.session, display_color_space)?;
+        self.display_color_space = Some(display_color_space);
+        Ok(())
+    }
+
+    /// Make the player's current position and heading the origin of the stage, eg. for a "reset view" button in a
+    /// seated experience. Only the heading is used, and the stage stays on the floor, so the world stays level.
+    /// Systems that run after this in the same tick will see an [`XrEvent::ReferenceSpaceChanged`].
+    pub fn recenter(&mut self) -> Result<()> {
+        let location = self
+            .view_space
+            .locate(&self.stage_space, self.frame_state.predicted_display_time)?;
+        if !is_space_valid(&location) {
+            return Err(anyhow::anyhow!(
+                "The headset isn't being tracked, so the view can't be recentered"
+            ));
+        }
+
+        let stage_from_view = affine_from_posef(location.pose);
+        let (_, rotation, translation) = stage_from_view.to_scale_rotation_translation();
+        let (yaw, _, _) = rotation.to_euler(glam::EulerRot::YXZ);
+        let stage_from_recentered = glam::Affine3A::from_rotation_translation(
+            glam::Quat::from_rotation_y(yaw),
+            glam::Vec3::new(translation.x, 0., translation.z),
+        );
+        println!("[HOTHAM_XR] Recentering the view");
+        self.set_runtime_stage_from_stage(self.runtime_stage_from_stage * stage_from_recentered)?;
+        self.events.push(XrEvent::ReferenceSpaceChanged);
+        Ok(())
+    }
+
+    /// How foveated rendering is done. This is `Disabled` if `Runtime` was asked for but the runtime doesn't support it.
+    pub fn foveation(&self) -> FoveationMode {
+        self.foveation
+    }
+
+    /// Which of the runtime's reference spaces is used as the stage. This may not be the one that was asked for, if
+    /// the runtime doesn't support it.
+    pub fn reference_space(&self) -> ReferenceSpace {
+        self.reference_space
+    }
+
+    /// Where `stage_space` is in the runtime's reference space, after any recentering
+    pub(crate) fn runtime_stage_from_stage(&self) -> glam::Affine3A {
+        self.runtime_stage_from_stage
+    }
+
+    fn set_runtime_stage_from_stage(
+        &mut self,
+        runtime_stage_from_stage: glam::Affine3A,
+    ) -> Result<()> {
+        self.stage_space = self.session.create_reference_space(
+            self.reference_space_type,
+            posef_from_affine(runtime_stage_from_stage),
+        )?;
+        self.runtime_stage_from_stage = runtime_stage_from_stage;
+        Ok(())
+    }
+
+    /// Move `stage_space` down to the floor, if the runtime can locate it yet.
+    fn locate_floor(&mut self) -> Result<()> {
+        if self.floor_locator.is_none() {
+            self.floor_locator = Some(FloorLocator::new(&self.session)?);
+        }
+        let local_from_floor = self
+            .floor_locator
+            .as_ref()
+            .unwrap()
+            .locate(self.frame_state.predicted_display_time)?;
+        if let Some(local_from_floor) = local_from_floor {
+            self.set_runtime_stage_from_stage(local_from_floor)?;
+            self.floor_pending = false;
+        }
+        Ok(())
+    }
+
+    /// The area the player has cleared to play in, if the runtime knows where it is and the stage is the runtime's
+    /// `Stage` reference space. It doesn't move when the view is recentered.
+    pub fn play_area(&self) -> Option<PlayArea> {
+        self.play_area
+    }
+
+    fn update_play_area(&mut self) -> Result<()> {
+        if self.reference_space != ReferenceSpace::Stage {
+            return Ok(());
+        }
+
+        self.play_area = self
+            .session
+            .reference_space_bounds_rect(ReferenceSpaceType::STAGE)?
+            .map(|bounds| PlayArea {
+                size: glam::Vec2::new(bounds.width, bounds.height),
+            });
+        Ok(())
+    }
+
+    /// Load the runtime's model of the controller in one of the player's hands, as a GLB file. Returns `None` if
+    /// controller models weren't enabled, or the runtime doesn't have a model for the controller yet, eg. because
+    /// it hasn't connected.
+    pub fn load_controller_model(&self, handedness: Handedness) -> Result<Option<Vec<u8>>> {
+        controller_models::load_controller_model(&self.session, handedness)
+    }
+
+    /// The paths of every model the runtime can provide with `XR_FB_render_model`, such as
+    /// `/model_fb/controller/left` or `/model_fb/keyboard/local`. Returns nothing if controller models weren't
+    /// enabled, or the runtime doesn't support the extension.
+    pub fn render_model_paths(&self) -> Result<Vec<String>> {
+        controller_models::render_model_paths(&self.session)
+    }
+
+    /// Load one of the models from [`XrContext::render_model_paths`], as a GLB file. Returns `None` if the runtime
+    /// doesn't have the model, eg. because the device hasn't connected.
+    pub fn load_render_model(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        controller_models::load_render_model(&self.session, path)
+    }
+
+    pub(crate) fn begin_session(&mut self) -> anyhow::Result<()> {
+        println!("[HOTHAM_XR] - Beginning session..");
+        self.session.begin(self.view_configuration_type)?;
+        println!("[HOTHAM_XR] - ..done!");
+        Ok(())
     }
 
     pub(crate) fn end_session(&mut self) -> anyhow::Result<()> {
@@ -262,12 +1261,14 @@ pub(crate) fn create_vulkan_context(
     system: xr::SystemId,
     application_name: &str,
     application_version: u32,
+    validation: bool,
 ) -> Result<VulkanContext, crate::hotham_error::HothamError> {
     let vulkan_context = VulkanContext::create_from_xr_instance(
         xr_instance,
         system,
         application_name,
         application_version,
+        validation,
     )?;
     println!("[HOTHAM_VULKAN] - Vulkan Context created successfully");
     Ok(vulkan_context)
@@ -279,6 +1280,7 @@ fn create_vulkan_context(
     system: xr::SystemId,
     application_name: &str,
     application_version: u32,
+    validation: bool,
 ) -> Result<VulkanContext, crate::hotham_error::HothamError> {
     #[allow(deprecated)]
     let vulkan_context = VulkanContext::create_from_xr_instance_legacy(
@@ -286,6 +1288,7 @@ fn create_vulkan_context(
         system,
         application_name,
         application_version,
+        validation,
     )?;
     println!("[HOTHAM_VULKAN] - Vulkan Context created successfully");
     Ok(vulkan_context)
@@ -294,8 +1297,9 @@ fn create_vulkan_context(
 pub(crate) fn get_swapchain_resolution(
     xr_instance: &xr::Instance,
     system: xr::SystemId,
+    view_configuration_type: ViewConfigurationType,
 ) -> Result<vk::Extent2D> {
-    let views = xr_instance.enumerate_view_configuration_views(system, VIEW_TYPE)?;
+    let views = xr_instance.enumerate_view_configuration_views(system, view_configuration_type)?;
     println!("[HOTHAM_VULKAN] Views: {views:?}");
     let resolution = vk::Extent2D {
         width: views[0].recommended_image_rect_width,
@@ -310,12 +1314,15 @@ pub(crate) fn create_xr_swapchain(
     xr_session: &Session<Vulkan>,
     resolution: &vk::Extent2D,
     array_size: u32,
+    color_format: vk::Format,
+    usage_flags: SwapchainUsageFlags,
+    _foveation_settings: Option<&FoveationSettings>,
 ) -> Result<Swapchain<Vulkan>> {
     xr_session
         .create_swapchain(&xr::SwapchainCreateInfo {
             create_flags: SwapchainCreateFlags::EMPTY,
-            usage_flags: SwapchainUsageFlags::COLOR_ATTACHMENT,
-            format: COLOR_FORMAT.as_raw() as u32,
+            usage_flags,
+            format: color_format.as_raw() as u32,
             sample_count: 1,
             width: resolution.width,
             height: resolution.height,
@@ -326,7 +1333,7 @@ pub(crate) fn create_xr_swapchain(
         .map_err(Into::into)
 }
 
-/// Creates the OpenXR swapchain with Fixed Foveated Rendering support on Quest 2
+/// Creates the OpenXR swapchain, with Fixed Foveated Rendering support on Quest if there are `foveation_settings`
 ///
 /// This requires a fair bit of setup as there isn't yet a wrapper for this functionality in OpenXR.
 #[cfg(target_os = "android")]
@@ -334,6 +1341,9 @@ pub(crate) fn create_xr_swapchain(
     xr_session: &Session<Vulkan>,
     resolution: &vk::Extent2D,
     array_size: u32,
+    color_format: vk::Format,
+    usage_flags: SwapchainUsageFlags,
+    foveation_settings: Option<&FoveationSettings>,
 ) -> Result<Swapchain<Vulkan>> {
     let mut swapchain_raw = xr::sys::Swapchain::NULL;
     let foveation_info = xr::sys::SwapchainCreateInfoFoveationFB {
@@ -345,15 +1355,19 @@ pub(crate) fn create_xr_swapchain(
     let create_info = xr::sys::SwapchainCreateInfo {
         ty: xr::sys::SwapchainCreateInfo::TYPE,
         create_flags: SwapchainCreateFlags::EMPTY,
-        usage_flags: SwapchainUsageFlags::COLOR_ATTACHMENT,
-        format: COLOR_FORMAT.as_raw() as _,
+        usage_flags,
+        format: color_format.as_raw() as _,
         sample_count: 1,
         width: resolution.width,
         height: resolution.height,
         face_count: 1,
         mip_count: 1,
         array_size,
-        next: &foveation_info as *const _ as *const std::ffi::c_void,
+        next: if foveation_settings.is_some() {
+            &foveation_info as *const _ as *const std::ffi::c_void
+        } else {
+            std::ptr::null()
+        },
     };
 
     unsafe {
@@ -367,58 +1381,68 @@ pub(crate) fn create_xr_swapchain(
             return Err(anyhow::Error::new(xr_result));
         };
 
-        let fp = xr_session
-            .instance()
-            .exts()
-            .fb_swapchain_update_state
-            .unwrap();
-
-        let foveation_profile = xr::FoveationLevelProfile {
-            level: xr::FoveationLevelFB::HIGH,
-            vertical_offset: 0.,
-            dynamic: xr::FoveationDynamicFB::DISABLED,
-        };
-
-        let foveation_profile_handle =
-            xr_session.create_foveation_profile(Some(foveation_profile))?;
-
-        let swapchain_update_state = xr::sys::SwapchainStateFoveationFB {
-            ty: xr::sys::SwapchainStateFoveationFB::TYPE,
-            next: std::ptr::null_mut(),
-            flags: xr::SwapchainStateFoveationFlagsFB::EMPTY,
-            profile: foveation_profile_handle.as_raw(),
-        };
-
-        let result =
-            (fp.update_swapchain)(swapchain_raw, std::mem::transmute(&swapchain_update_state));
-
-        if result.into_raw() < 0 {
-            return Err(anyhow::Error::new(result));
+        if let Some(foveation_settings) = foveation_settings {
+            foveation::apply_foveation_settings(xr_session, swapchain_raw, foveation_settings)?;
         }
 
         Ok(swapchain)
     }
 }
 
+/// Creates a swapchain to submit the depth buffer to the compositor with. Returns `None` if the runtime doesn't
+/// support `XR_KHR_composition_layer_depth` or our depth format.
+pub(crate) fn create_xr_depth_swapchain(
+    xr_instance: &xr::Instance,
+    xr_session: &Session<Vulkan>,
+    resolution: &vk::Extent2D,
+    array_size: u32,
+) -> Result<Option<Swapchain<Vulkan>>> {
+    if xr_instance.exts().khr_composition_layer_depth.is_none() {
+        println!("[HOTHAM_XR] XR_KHR_composition_layer_depth is not supported, depth will not be submitted");
+        return Ok(None);
+    }
+
+    let format = DEPTH_FORMAT.as_raw() as u32;
+    if !xr_session.enumerate_swapchain_formats()?.contains(&format) {
+        println!("[HOTHAM_XR] Depth format {DEPTH_FORMAT:?} is not supported, depth will not be submitted");
+        return Ok(None);
+    }
+
+    xr_session
+        .create_swapchain(&xr::SwapchainCreateInfo {
+            create_flags: SwapchainCreateFlags::EMPTY,
+            usage_flags: SwapchainUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            format,
+            sample_count: 1,
+            width: resolution.width,
+            height: resolution.height,
+            face_count: 1,
+            array_size,
+            mip_count: 1,
+        })
+        .map(Some)
+        .map_err(Into::into)
+}
+
 pub(crate) fn create_xr_session(
     xr_instance: &xr::Instance,
     system: xr::SystemId,
     vulkan_context: &VulkanContext,
+    overlay: bool,
 ) -> Result<(Session<Vulkan>, FrameWaiter, FrameStream<Vulkan>)> {
     println!("[HOTHAM] Creating session..");
-    Ok(unsafe {
-        xr_instance.create_session(
-            system,
-            &SessionCreateInfo {
-                instance: vulkan_context.instance.handle().as_raw() as *const _,
-                physical_device: vulkan_context.physical_device.as_raw() as *const _,
-                device: vulkan_context.device.handle().as_raw() as *const _,
-                queue_family_index: vulkan_context.queue_family_index,
-                queue_index: 0,
-            },
-        )
+    let info = SessionCreateInfo {
+        instance: vulkan_context.instance.handle().as_raw() as *const _,
+        physical_device: vulkan_context.physical_device.as_raw() as *const _,
+        device: vulkan_context.device.handle().as_raw() as *const _,
+        queue_family_index: vulkan_context.queue_family_index,
+        queue_index: 0,
+    };
+    if overlay {
+        println!("[HOTHAM] Running as an overlay");
+        return overlay::create_overlay_session(xr_instance, system, &info);
     }
-    .unwrap())
+    Ok(unsafe { xr_instance.create_session(system, &info) }.unwrap())
 }
 
 pub(crate) fn create_xr_instance(
@@ -426,7 +1450,19 @@ pub(crate) fn create_xr_instance(
     application_name: &str,
     application_version: u32,
     required_extensions: Option<&xr::ExtensionSet>,
-) -> anyhow::Result<(xr::Instance, xr::SystemId)> {
+    foveation: FoveationMode,
+    passthrough: bool,
+    space_warp: bool,
+    hand_tracking: bool,
+    body_tracking: bool,
+    eye_gaze: bool,
+    controller_models: bool,
+    keyboard_tracking: bool,
+    spatial_anchors: bool,
+    scene: bool,
+    trackers: bool,
+    overlay: bool,
+) -> anyhow::Result<(xr::Instance, xr::SystemId, xr::ExtensionSet)> {
     let xr_entry = if let Some(path) = path {
         unsafe { xr::Entry::load_from(path)? }
     } else {
@@ -442,6 +1478,82 @@ pub(crate) fn create_xr_instance(
     let mut required_extensions = required_extensions.cloned().unwrap_or_default();
     enable_xr_extensions(&mut required_extensions);
 
+    // Depth submission, performance settings, the floor, haptic clips, foveation, passthrough, SpaceWarp, hand tracking, body
+    // tracking, eye gaze, controller models, keyboard tracking, spatial anchors, the scene and trackers are optional, so
+    // only ask for them if the runtime has them.
+    let available_extensions = xr_entry.enumerate_extensions()?;
+    required_extensions.khr_composition_layer_depth |=
+        available_extensions.khr_composition_layer_depth;
+    required_extensions.fb_display_refresh_rate |= available_extensions.fb_display_refresh_rate;
+    required_extensions.fb_color_space |= available_extensions.fb_color_space;
+    required_extensions.ext_performance_settings |= available_extensions.ext_performance_settings;
+    enable_other_extension(
+        &mut required_extensions,
+        &available_extensions,
+        reference_space::EXT_LOCAL_FLOOR,
+    );
+    enable_other_extension(
+        &mut required_extensions,
+        &available_extensions,
+        haptic_clips::FB_HAPTIC_PCM,
+    );
+    enable_other_extension(
+        &mut required_extensions,
+        &available_extensions,
+        haptic_clips::FB_HAPTIC_AMPLITUDE_ENVELOPE,
+    );
+    // Compositor filtering costs nothing unless the application asks for it.
+    required_extensions.fb_composition_layer_settings |=
+        available_extensions.fb_composition_layer_settings;
+    // The controllers of Vive Focus 3 and Pico headsets can only be bound if their extensions are enabled.
+    required_extensions.htc_vive_focus3_controller_interaction |=
+        available_extensions.htc_vive_focus3_controller_interaction;
+    enable_other_extension(
+        &mut required_extensions,
+        &available_extensions,
+        runtime_capabilities::BD_CONTROLLER_INTERACTION,
+    );
+    // Foveation needs all four extensions, and only works on Quest.
+    let foveation = cfg!(target_os = "android")
+        && foveation == FoveationMode::Runtime
+        && available_extensions.fb_foveation
+        && available_extensions.fb_foveation_configuration
+        && available_extensions.fb_foveation_vulkan
+        && available_extensions.fb_swapchain_update_state;
+    required_extensions.fb_foveation |= foveation;
+    required_extensions.fb_foveation_configuration |= foveation;
+    required_extensions.fb_foveation_vulkan |= foveation;
+    required_extensions.fb_swapchain_update_state |= foveation;
+    required_extensions.fb_passthrough |= passthrough && available_extensions.fb_passthrough;
+    required_extensions.fb_space_warp |= space_warp && available_extensions.fb_space_warp;
+    required_extensions.ext_hand_tracking |=
+        hand_tracking && available_extensions.ext_hand_tracking;
+    if body_tracking {
+        enable_other_extension(
+            &mut required_extensions,
+            &available_extensions,
+            body_tracking::FB_BODY_TRACKING,
+        );
+    }
+    required_extensions.ext_eye_gaze_interaction |=
+        eye_gaze && available_extensions.ext_eye_gaze_interaction;
+    required_extensions.fb_render_model |=
+        controller_models && available_extensions.fb_render_model;
+    required_extensions.msft_controller_model |=
+        controller_models && available_extensions.msft_controller_model;
+    required_extensions.fb_keyboard_tracking |=
+        keyboard_tracking && available_extensions.fb_keyboard_tracking;
+    required_extensions.fb_spatial_entity |=
+        (spatial_anchors || scene) && available_extensions.fb_spatial_entity;
+    required_extensions.fb_spatial_entity_storage |=
+        spatial_anchors && available_extensions.fb_spatial_entity_storage;
+    required_extensions.fb_spatial_entity_query |=
+        (spatial_anchors || scene) && available_extensions.fb_spatial_entity_query;
+    required_extensions.fb_scene |= scene && available_extensions.fb_scene;
+    required_extensions.htcx_vive_tracker_interaction |=
+        trackers && available_extensions.htcx_vive_tracker_interaction;
+    required_extensions.extx_overlay |= overlay && available_extensions.extx_overlay;
+
     #[cfg(target_os = "android")]
     {
         xr_entry.initialize_android_loader()?;
@@ -449,17 +1561,27 @@ pub(crate) fn create_xr_instance(
 
     let instance = xr_entry.create_instance(&xr_app_info, &required_extensions, &[])?;
     let system = instance.system(xr::FormFactor::HEAD_MOUNTED_DISPLAY)?;
-    Ok((instance, system))
+    Ok((instance, system, required_extensions))
+}
+
+/// Enable an extension `openxr` doesn't know about, if the runtime has it
+fn enable_other_extension(
+    required_extensions: &mut xr::ExtensionSet,
+    available_extensions: &xr::ExtensionSet,
+    extension: &str,
+) {
+    let extension = extension.to_string();
+    if available_extensions.other.contains(&extension)
+        && !required_extensions.other.contains(&extension)
+    {
+        required_extensions.other.push(extension);
+    }
 }
 
 #[cfg(target_os = "android")]
 fn enable_xr_extensions(required_extensions: &mut xr::ExtensionSet) {
     required_extensions.khr_android_create_instance = true;
     required_extensions.khr_vulkan_enable2 = true;
-    required_extensions.fb_foveation = true;
-    required_extensions.fb_foveation_configuration = true;
-    required_extensions.fb_foveation_vulkan = true;
-    required_extensions.fb_swapchain_update_state = true;
 }
 
 #[cfg(not(target_os = "android"))]
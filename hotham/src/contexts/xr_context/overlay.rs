@@ -0,0 +1,47 @@
+use anyhow::Result;
+use openxr::{
+    self as xr, sys, vulkan::SessionCreateInfo, FrameStream, FrameWaiter, Session, Vulkan,
+};
+
+/// Where an overlay's layers are drawn, relative to the layers of any other overlays. Higher is on top.
+const OVERLAY_LAYERS_PLACEMENT: u32 = 1;
+
+/// Create a session that draws on top of the runtime's main session, with `XR_EXTX_overlay`. `openxr` can't chain
+/// the overlay info onto a session, so it's created by hand.
+pub(crate) fn create_overlay_session(
+    instance: &xr::Instance,
+    system: xr::SystemId,
+    info: &SessionCreateInfo,
+) -> Result<(Session<Vulkan>, FrameWaiter, FrameStream<Vulkan>)> {
+    let overlay_info = sys::SessionCreateInfoOverlayEXTX {
+        ty: sys::SessionCreateInfoOverlayEXTX::TYPE,
+        next: std::ptr::null(),
+        create_flags: sys::OverlaySessionCreateFlagsEXTX::EMPTY,
+        session_layers_placement: OVERLAY_LAYERS_PLACEMENT,
+    };
+    let graphics_binding = sys::GraphicsBindingVulkanKHR {
+        ty: sys::GraphicsBindingVulkanKHR::TYPE,
+        next: &overlay_info as *const _ as _,
+        instance: info.instance,
+        physical_device: info.physical_device,
+        device: info.device,
+        queue_family_index: info.queue_family_index,
+        queue_index: info.queue_index,
+    };
+    let create_info = sys::SessionCreateInfo {
+        ty: sys::SessionCreateInfo::TYPE,
+        next: &graphics_binding as *const _ as _,
+        create_flags: sys::SessionCreateFlags::EMPTY,
+        system_id: system,
+    };
+
+    let mut session = sys::Session::NULL;
+    let result =
+        unsafe { (instance.fp().create_session)(instance.as_raw(), &create_info, &mut session) };
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+
+    // SAFETY: The runtime has just created this session, and it's only destroyed when the `Session` is dropped.
+    Ok(unsafe { Session::from_raw(instance.clone(), session, Box::new(())) })
+}
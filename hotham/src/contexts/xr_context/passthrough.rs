@@ -0,0 +1,118 @@
+use anyhow::Result;
+use openxr::{self as xr, Session, Vulkan};
+
+/// Shows the headset's camera feed behind the scene, using `XR_FB_passthrough`.
+///
+/// The passthrough layer is submitted underneath the projection layer, which is then blended over it using its
+/// alpha channel. Anything the scene doesn't cover is cleared to transparent, so the real world shows through, as
+/// long as the renderer was created with [`RenderSettings::passthrough`](crate::contexts::render_context::RenderSettings::passthrough)
+/// set. A [`Skybox`](crate::components::Skybox) covers every pixel, so leave it out of mixed reality scenes.
+pub struct Passthrough {
+    // Keeps the session alive until the passthrough handles have been destroyed.
+    session: Session<Vulkan>,
+    passthrough: xr::sys::PassthroughFB,
+    layer: xr::sys::PassthroughLayerFB,
+    running: bool,
+}
+
+impl Passthrough {
+    /// Start passthrough. Returns `None` if `XR_FB_passthrough` wasn't enabled, eg. because the runtime doesn't
+    /// support it.
+    pub(crate) fn new(session: &Session<Vulkan>) -> Result<Option<Self>> {
+        let fp = match session.instance().exts().fb_passthrough {
+            Some(fp) => fp,
+            None => {
+                println!("[HOTHAM_XR] XR_FB_passthrough is not supported, passthrough is disabled");
+                return Ok(None);
+            }
+        };
+
+        let passthrough_create_info = xr::sys::PassthroughCreateInfoFB {
+            ty: xr::sys::PassthroughCreateInfoFB::TYPE,
+            next: std::ptr::null(),
+            flags: xr::sys::PassthroughFlagsFB::IS_RUNNING_AT_CREATION,
+        };
+        let mut passthrough = xr::sys::PassthroughFB::NULL;
+        check(unsafe {
+            (fp.create_passthrough)(session.as_raw(), &passthrough_create_info, &mut passthrough)
+        })?;
+
+        let layer_create_info = xr::sys::PassthroughLayerCreateInfoFB {
+            ty: xr::sys::PassthroughLayerCreateInfoFB::TYPE,
+            next: std::ptr::null(),
+            passthrough,
+            flags: xr::sys::PassthroughFlagsFB::IS_RUNNING_AT_CREATION,
+            purpose: xr::sys::PassthroughLayerPurposeFB::RECONSTRUCTION,
+        };
+        let mut layer = xr::sys::PassthroughLayerFB::NULL;
+        if let Err(e) = check(unsafe {
+            (fp.create_passthrough_layer)(session.as_raw(), &layer_create_info, &mut layer)
+        }) {
+            unsafe { (fp.destroy_passthrough)(passthrough) };
+            return Err(e);
+        }
+
+        println!("[HOTHAM_XR] Passthrough started");
+        Ok(Some(Self {
+            session: session.clone(),
+            passthrough,
+            layer,
+            running: true,
+        }))
+    }
+
+    /// Whether the camera feed is currently shown
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Pause or resume passthrough. Pausing stops the cameras, which saves power when the app doesn't need them
+    /// for a while, eg. while switching to a fully virtual scene.
+    pub fn set_running(&mut self, running: bool) -> Result<()> {
+        if running == self.running {
+            return Ok(());
+        }
+
+        let fp = self.session.instance().exts().fb_passthrough.unwrap();
+        unsafe {
+            if running {
+                check((fp.passthrough_start)(self.passthrough))?;
+                check((fp.passthrough_layer_resume)(self.layer))?;
+            } else {
+                check((fp.passthrough_layer_pause)(self.layer))?;
+                check((fp.passthrough_pause)(self.passthrough))?;
+            }
+        }
+
+        self.running = running;
+        Ok(())
+    }
+
+    /// The composition layer to submit underneath the projection layer
+    pub(crate) fn composition_layer(&self) -> xr::sys::CompositionLayerPassthroughFB {
+        xr::sys::CompositionLayerPassthroughFB {
+            ty: xr::sys::CompositionLayerPassthroughFB::TYPE,
+            next: std::ptr::null(),
+            flags: xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA,
+            space: xr::sys::Space::NULL,
+            layer_handle: self.layer,
+        }
+    }
+}
+
+impl Drop for Passthrough {
+    fn drop(&mut self) {
+        let fp = self.session.instance().exts().fb_passthrough.unwrap();
+        unsafe {
+            (fp.destroy_passthrough_layer)(self.layer);
+            (fp.destroy_passthrough)(self.passthrough);
+        }
+    }
+}
+
+fn check(result: xr::sys::Result) -> Result<()> {
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use anyhow::Result;
+use openxr::{sys, Session, Vulkan};
+
+/// A processor whose clock speed can be changed with `XR_EXT_performance_settings`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerformanceDomain {
+    /// The CPU
+    Cpu,
+    /// The GPU
+    Gpu,
+}
+
+/// How fast a processor should run. Higher levels give more headroom for heavy scenes, at the cost of battery life
+/// and heat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerformanceLevel {
+    /// As slow as possible while the application still runs, eg. on a loading screen
+    PowerSavings,
+    /// A level the device can sustain without overheating, that saves power
+    SustainedLow,
+    /// The fastest level the device can sustain without overheating. This is the default on most runtimes.
+    SustainedHigh,
+    /// Faster than the device can sustain, for short periods like loading a level. The runtime may drop back to a
+    /// sustained level at any time.
+    Boost,
+}
+
+/// The part of the runtime a [`PerformanceNotification`] is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerformanceSubDomain {
+    /// Compositing the frames the application submits
+    Compositing,
+    /// The application's own rendering
+    Rendering,
+    /// The temperature of the device
+    Thermal,
+}
+
+/// How well a part of the runtime is coping, from a [`PerformanceNotification`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PerformanceNotificationLevel {
+    /// Everything is fine
+    Normal,
+    /// It's struggling, so the application should do less work or raise its [`PerformanceLevel`]
+    Warning,
+    /// It can't keep up, and the player will notice
+    Impaired,
+}
+
+/// The runtime telling the application that a processor is struggling more or less than it was. Reported in
+/// [`XrEvent::Performance`](super::XrEvent::Performance).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PerformanceNotification {
+    /// Which processor it's about
+    pub domain: PerformanceDomain,
+    /// Which part of the runtime it's about
+    pub sub_domain: PerformanceSubDomain,
+    /// How well it was coping
+    pub from_level: PerformanceNotificationLevel,
+    /// How well it's coping now
+    pub to_level: PerformanceNotificationLevel,
+}
+
+impl PerformanceDomain {
+    pub(crate) fn to_raw(self) -> sys::PerfSettingsDomainEXT {
+        match self {
+            PerformanceDomain::Cpu => sys::PerfSettingsDomainEXT::CPU,
+            PerformanceDomain::Gpu => sys::PerfSettingsDomainEXT::GPU,
+        }
+    }
+
+    fn from_raw(domain: sys::PerfSettingsDomainEXT) -> Option<Self> {
+        Some(match domain {
+            sys::PerfSettingsDomainEXT::CPU => PerformanceDomain::Cpu,
+            sys::PerfSettingsDomainEXT::GPU => PerformanceDomain::Gpu,
+            _ => return None,
+        })
+    }
+}
+
+impl PerformanceLevel {
+    pub(crate) fn to_raw(self) -> sys::PerfSettingsLevelEXT {
+        match self {
+            PerformanceLevel::PowerSavings => sys::PerfSettingsLevelEXT::POWER_SAVINGS,
+            PerformanceLevel::SustainedLow => sys::PerfSettingsLevelEXT::SUSTAINED_LOW,
+            PerformanceLevel::SustainedHigh => sys::PerfSettingsLevelEXT::SUSTAINED_HIGH,
+            PerformanceLevel::Boost => sys::PerfSettingsLevelEXT::BOOST,
+        }
+    }
+}
+
+impl PerformanceSubDomain {
+    fn from_raw(sub_domain: sys::PerfSettingsSubDomainEXT) -> Option<Self> {
+        Some(match sub_domain {
+            sys::PerfSettingsSubDomainEXT::COMPOSITING => PerformanceSubDomain::Compositing,
+            sys::PerfSettingsSubDomainEXT::RENDERING => PerformanceSubDomain::Rendering,
+            sys::PerfSettingsSubDomainEXT::THERMAL => PerformanceSubDomain::Thermal,
+            _ => return None,
+        })
+    }
+}
+
+impl PerformanceNotificationLevel {
+    fn from_raw(level: sys::PerfSettingsNotificationLevelEXT) -> Option<Self> {
+        Some(match level {
+            sys::PerfSettingsNotificationLevelEXT::NORMAL => PerformanceNotificationLevel::Normal,
+            sys::PerfSettingsNotificationLevelEXT::WARNING => PerformanceNotificationLevel::Warning,
+            sys::PerfSettingsNotificationLevelEXT::IMPAIRED => {
+                PerformanceNotificationLevel::Impaired
+            }
+            _ => return None,
+        })
+    }
+}
+
+impl PerformanceNotification {
+    /// Returns `None` for values added to the extension since this was written
+    pub(crate) fn from_raw(
+        domain: sys::PerfSettingsDomainEXT,
+        sub_domain: sys::PerfSettingsSubDomainEXT,
+        from_level: sys::PerfSettingsNotificationLevelEXT,
+        to_level: sys::PerfSettingsNotificationLevelEXT,
+    ) -> Option<Self> {
+        Some(Self {
+            domain: PerformanceDomain::from_raw(domain)?,
+            sub_domain: PerformanceSubDomain::from_raw(sub_domain)?,
+            from_level: PerformanceNotificationLevel::from_raw(from_level)?,
+            to_level: PerformanceNotificationLevel::from_raw(to_level)?,
+        })
+    }
+}
+
+/// Ask the runtime to run a processor at the given level
+pub(crate) fn set_performance_level(
+    session: &Session<Vulkan>,
+    domain: PerformanceDomain,
+    level: PerformanceLevel,
+) -> Result<()> {
+    let fp = session.instance().exts().ext_performance_settings.unwrap();
+    let result = unsafe {
+        (fp.perf_settings_set_performance_level)(session.as_raw(), domain.to_raw(), level.to_raw())
+    };
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_performance_notification_from_raw() {
+        assert_eq!(
+            PerformanceNotification::from_raw(
+                sys::PerfSettingsDomainEXT::GPU,
+                sys::PerfSettingsSubDomainEXT::THERMAL,
+                sys::PerfSettingsNotificationLevelEXT::NORMAL,
+                sys::PerfSettingsNotificationLevelEXT::WARNING,
+            ),
+            Some(PerformanceNotification {
+                domain: PerformanceDomain::Gpu,
+                sub_domain: PerformanceSubDomain::Thermal,
+                from_level: PerformanceNotificationLevel::Normal,
+                to_level: PerformanceNotificationLevel::Warning,
+            })
+        );
+
+        // Values from newer versions of the extension are ignored
+        assert_eq!(
+            PerformanceNotification::from_raw(
+                sys::PerfSettingsDomainEXT::from_raw(100),
+                sys::PerfSettingsSubDomainEXT::THERMAL,
+                sys::PerfSettingsNotificationLevelEXT::NORMAL,
+                sys::PerfSettingsNotificationLevelEXT::WARNING,
+            ),
+            None
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use glam::{Vec2, Vec3};
+
+/// The player crossed the edge of the [`PlayArea`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayAreaEvent {
+    /// The player's head left the play area
+    Left,
+    /// The player's head came back into the play area
+    Entered,
+}
+
+/// The area the player has cleared to play in, as set up in the runtime's boundary (eg. the Guardian on Quest).
+///
+/// OpenXR only exposes the largest rectangle that fits inside the boundary, centered on the stage's origin and
+/// aligned with its axes, so that's what this is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlayArea {
+    /// The size of the play area along the stage's X and Z axes, in meters
+    pub size: Vec2,
+}
+
+impl PlayArea {
+    /// The corners of the play area on the floor, in stage space, counter-clockwise when seen from above
+    pub fn polygon(&self) -> [Vec3; 4] {
+        let half_x = self.size.x / 2.;
+        let half_z = self.size.y / 2.;
+        [
+            Vec3::new(-half_x, 0., -half_z),
+            Vec3::new(-half_x, 0., half_z),
+            Vec3::new(half_x, 0., half_z),
+            Vec3::new(half_x, 0., -half_z),
+        ]
+    }
+
+    /// Is a point in stage space inside the play area? Its height is ignored.
+    pub fn contains(&self, point_in_stage: Vec3) -> bool {
+        point_in_stage.x.abs() <= self.size.x / 2. && point_in_stage.z.abs() <= self.size.y / 2.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_play_area() {
+        let play_area = PlayArea {
+            size: Vec2::new(2., 3.),
+        };
+        assert!(play_area.contains(Vec3::new(0.9, 1.7, -1.4)));
+        assert!(!play_area.contains(Vec3::new(1.1, 1.7, 0.)));
+        assert!(!play_area.contains(Vec3::new(0., 1.7, 1.6)));
+
+        let polygon = play_area.polygon();
+        assert_eq!(polygon[0], Vec3::new(-1., 0., -1.5));
+        assert_eq!(polygon[2], Vec3::new(1., 0., 1.5));
+        assert!(polygon.iter().all(|corner| play_area.contains(*corner)));
+    }
+}
@@ -0,0 +1,259 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::{anyhow, Result};
+use ash::vk::{self, Handle};
+use openxr::{self as xr, Session, Space, Swapchain, Vulkan};
+use xr::{SwapchainCreateFlags, SwapchainUsageFlags};
+
+use crate::{contexts::VulkanContext, rendering::buffer::Buffer};
+
+/// A flat rectangle with its own swapchain, drawn by the compositor on top of the scene.
+///
+/// The compositor samples the quad's image directly when it builds the final frame, instead of the image being
+/// rendered into the eye buffer and then resampled by the compositor, so text and UI stay much crisper. Quads are
+/// always drawn over the scene: they don't take part in depth testing.
+///
+/// Quads in [`super::XrContext::quad_layers`] are submitted every frame, in order. The image doesn't have to be
+/// updated every frame: the compositor keeps showing the last image that was released.
+///
+/// Basic usage:
+/// ```ignore
+/// let mut quad = QuadLayer::new(&engine.xr_context, vk::Extent2D { width: 512, height: 256 }, COLOR_FORMAT)?;
+/// quad.pose = posef_from_affine(stage_from_quad);
+/// quad.upload_rgba(&engine.vulkan_context, &pixels)?;
+/// engine.xr_context.quad_layers.push(quad);
+/// ```
+pub struct QuadLayer {
+    /// The swapchain the compositor samples the quad from
+    pub swapchain: Swapchain<Vulkan>,
+    /// The images of the swapchain, owned by OpenXR
+    pub images: Vec<vk::Image>,
+    /// The size of the swapchain images, in pixels
+    pub extent: vk::Extent2D,
+    /// The pose of the centre of the quad, in stage space. The front of the quad faces +Z.
+    pub pose: xr::Posef,
+    /// The width and height of the quad, in metres
+    pub size: xr::Extent2Df,
+    /// Hidden quads aren't submitted
+    pub visible: bool,
+    /// Set once an image has been released, as the compositor has nothing to show until then
+    has_contents: bool,
+    /// Created by the first call to [`QuadLayer::upload_rgba`]
+    staging_buffer: Option<Buffer<u8>>,
+}
+
+impl QuadLayer {
+    /// Create a quad with a swapchain of size `extent`. The quad is one metre wide, with the same aspect ratio as
+    /// `extent`, and sits one metre above the stage origin.
+    pub fn new(
+        xr_context: &super::XrContext,
+        extent: vk::Extent2D,
+        format: vk::Format,
+    ) -> Result<Self> {
+        let swapchain = create_quad_swapchain(&xr_context.session, &extent, format)?;
+        let images = swapchain
+            .enumerate_images()?
+            .into_iter()
+            .map(vk::Image::from_raw)
+            .collect();
+
+        Ok(Self {
+            swapchain,
+            images,
+            extent,
+            pose: xr::Posef {
+                orientation: xr::Quaternionf::IDENTITY,
+                position: xr::Vector3f {
+                    x: 0.,
+                    y: 1.,
+                    z: 0.,
+                },
+            },
+            size: xr::Extent2Df {
+                width: 1.,
+                height: extent.height as f32 / extent.width as f32,
+            },
+            visible: true,
+            has_contents: false,
+            staging_buffer: None,
+        })
+    }
+
+    /// Acquire the next image to render into, waiting until the compositor is done with it. Once the image has
+    /// been rendered and left in `COLOR_ATTACHMENT_OPTIMAL`, call [`QuadLayer::release_image`].
+    pub fn acquire_image(&mut self) -> Result<vk::Image> {
+        let image_index = self.swapchain.acquire_image()? as usize;
+        self.swapchain.wait_image(xr::Duration::INFINITE)?;
+        Ok(self.images[image_index])
+    }
+
+    /// Hand the image acquired with [`QuadLayer::acquire_image`] to the compositor. It's shown from the next frame.
+    pub fn release_image(&mut self) -> Result<()> {
+        self.swapchain.release_image()?;
+        self.has_contents = true;
+        Ok(())
+    }
+
+    /// Copy `rgba`, with four bytes per pixel and straight (not premultiplied) alpha, into the next image and
+    /// release it. Blocks until the copy has completed, so this is best suited to content that changes
+    /// occasionally, like UI panels or video frames.
+    pub fn upload_rgba(&mut self, vulkan_context: &VulkanContext, rgba: &[u8]) -> Result<()> {
+        let len = (self.extent.width * self.extent.height * 4) as usize;
+        if rgba.len() != len {
+            return Err(anyhow!(
+                "Quad layer is {}x{}, expected {len} bytes but got {}",
+                self.extent.width,
+                self.extent.height,
+                rgba.len()
+            ));
+        }
+
+        let image = self.acquire_image()?;
+        let staging_buffer = self.staging_buffer.get_or_insert_with(|| unsafe {
+            Buffer::new(vulkan_context, vk::BufferUsageFlags::TRANSFER_SRC, len)
+        });
+
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let to_transfer_dst = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(subresource_range)
+            .image(image);
+        // OpenXR expects color images to be released in COLOR_ATTACHMENT_OPTIMAL.
+        let to_color_attachment = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(subresource_range)
+            .image(image);
+        let region = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D::default(),
+            image_extent: vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            },
+        };
+
+        let device = &vulkan_context.device;
+        unsafe {
+            staging_buffer.overwrite(rgba);
+            let command_buffer = vulkan_context.begin_single_time_commands();
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&to_transfer_dst),
+            );
+            device.cmd_copy_buffer_to_image(
+                command_buffer,
+                staging_buffer.buffer,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                slice_from_ref(&region),
+            );
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&to_color_attachment),
+            );
+            vulkan_context.end_single_time_commands(command_buffer);
+        }
+
+        self.release_image()
+    }
+
+    /// The composition layer to submit this frame, if the quad is visible and has something to show.
+    pub(crate) fn composition_layer<'a>(
+        &'a self,
+        space: &'a Space,
+    ) -> Option<xr::CompositionLayerQuad<'a, Vulkan>> {
+        if !self.visible || !self.has_contents {
+            return None;
+        }
+
+        let rect = xr::Rect2Di {
+            offset: xr::Offset2Di { x: 0, y: 0 },
+            extent: xr::Extent2Di {
+                width: self.extent.width as _,
+                height: self.extent.height as _,
+            },
+        };
+
+        Some(
+            xr::CompositionLayerQuad::new()
+                .layer_flags(
+                    xr::CompositionLayerFlags::BLEND_TEXTURE_SOURCE_ALPHA
+                        | xr::CompositionLayerFlags::UNPREMULTIPLIED_ALPHA,
+                )
+                .space(space)
+                .eye_visibility(xr::EyeVisibility::BOTH)
+                .sub_image(
+                    xr::SwapchainSubImage::new()
+                        .swapchain(&self.swapchain)
+                        .image_array_index(0)
+                        .image_rect(rect),
+                )
+                .pose(self.pose)
+                .size(self.size),
+        )
+    }
+
+    /// Destroy the staging buffer used by [`QuadLayer::upload_rgba`]. The swapchain is destroyed when the quad is
+    /// dropped.
+    ///
+    /// # Safety
+    /// The staging buffer must not be in use by the GPU.
+    pub unsafe fn destroy(&mut self, device: &ash::Device) {
+        if let Some(mut staging_buffer) = self.staging_buffer.take() {
+            staging_buffer.destroy(device);
+        }
+    }
+}
+
+fn create_quad_swapchain(
+    xr_session: &Session<Vulkan>,
+    resolution: &vk::Extent2D,
+    format: vk::Format,
+) -> Result<Swapchain<Vulkan>> {
+    xr_session
+        .create_swapchain(&xr::SwapchainCreateInfo {
+            create_flags: SwapchainCreateFlags::EMPTY,
+            usage_flags: SwapchainUsageFlags::COLOR_ATTACHMENT | SwapchainUsageFlags::TRANSFER_DST,
+            format: format.as_raw() as u32,
+            sample_count: 1,
+            width: resolution.width,
+            height: resolution.height,
+            face_count: 1,
+            array_size: 1,
+            mip_count: 1,
+        })
+        .map_err(Into::into)
+}
@@ -0,0 +1,131 @@
+use anyhow::Result;
+use glam::{Affine3A, Vec3};
+use openxr::{self as xr, ReferenceSpaceType, Session, Vulkan};
+
+use crate::util::is_space_valid;
+
+/// Lets the runtime put the LOCAL reference space on the floor. `openxr` doesn't know about it yet, so it's enabled
+/// by name.
+pub(crate) const EXT_LOCAL_FLOOR: &str = "XR_EXT_local_floor";
+const LOCAL_FLOOR_EXT: ReferenceSpaceType = ReferenceSpaceType::from_raw(1000426000);
+
+/// Which of the runtime's reference spaces is used as the stage, ie. where the origin of the game world is in the
+/// real world
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReferenceSpace {
+    /// Where the player's head was when the application started, facing the same way. Best for seated experiences.
+    Local,
+    /// Like `Local`, but on the floor. Best for standing experiences that don't need the play area.
+    LocalFloor,
+    /// The center of the play area, on the floor. Best for roomscale experiences.
+    #[default]
+    Stage,
+}
+
+impl ReferenceSpace {
+    /// Every runtime supports `Local`, but the others need the runtime to know where the floor is, which isn't the
+    /// case on some WMR and SteamVR setups. Returns the reference space that will actually be used, falling back to
+    /// `Local` if the runtime doesn't know where the floor is, along with the runtime's reference space it's relative
+    /// to. `LocalFloor` uses `LOCAL_FLOOR_EXT` if the runtime has it, and otherwise finds the floor with `STAGE`.
+    pub(crate) fn supported(self, session: &Session<Vulkan>) -> Result<(Self, ReferenceSpaceType)> {
+        Ok(self.choose(&session.enumerate_reference_spaces()?))
+    }
+
+    fn choose(self, supported: &[ReferenceSpaceType]) -> (Self, ReferenceSpaceType) {
+        let has_stage = supported.contains(&ReferenceSpaceType::STAGE);
+        match self {
+            ReferenceSpace::Local => (self, ReferenceSpaceType::LOCAL),
+            ReferenceSpace::LocalFloor if supported.contains(&LOCAL_FLOOR_EXT) => {
+                (self, LOCAL_FLOOR_EXT)
+            }
+            ReferenceSpace::LocalFloor if has_stage => (self, ReferenceSpaceType::LOCAL),
+            ReferenceSpace::Stage if has_stage => (self, ReferenceSpaceType::STAGE),
+            _ => {
+                println!("[HOTHAM_XR] The STAGE reference space isn't supported, so {self:?} will use LOCAL instead");
+                (ReferenceSpace::Local, ReferenceSpaceType::LOCAL)
+            }
+        }
+    }
+}
+
+/// Does `stage_space` need to be moved down to the floor by a [`FloorLocator`]?
+pub(crate) fn emulates_local_floor(
+    reference_space: ReferenceSpace,
+    reference_space_type: ReferenceSpaceType,
+) -> bool {
+    reference_space == ReferenceSpace::LocalFloor
+        && reference_space_type == ReferenceSpaceType::LOCAL
+}
+
+/// Finds the floor below the origin of the LOCAL reference space using the STAGE reference space, for runtimes
+/// without `XR_EXT_local_floor`. The spaces are created once and reused until the floor is found.
+pub(crate) struct FloorLocator {
+    local_space: xr::Space,
+    stage_space: xr::Space,
+}
+
+impl FloorLocator {
+    pub(crate) fn new(session: &Session<Vulkan>) -> Result<Self> {
+        Ok(Self {
+            local_space: session
+                .create_reference_space(ReferenceSpaceType::LOCAL, xr::Posef::IDENTITY)?,
+            stage_space: session
+                .create_reference_space(ReferenceSpaceType::STAGE, xr::Posef::IDENTITY)?,
+        })
+    }
+
+    /// Where the floor is in the LOCAL reference space. Returns `None` if the runtime can't locate it yet.
+    pub(crate) fn locate(&self, time: xr::Time) -> Result<Option<Affine3A>> {
+        let location = self.local_space.locate(&self.stage_space, time)?;
+        if !is_space_valid(&location) {
+            return Ok(None);
+        }
+
+        let height = location.pose.position.y;
+        Ok(Some(Affine3A::from_translation(Vec3::new(0., -height, 0.))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_choose_reference_space() {
+        let local = ReferenceSpaceType::LOCAL;
+        let stage = ReferenceSpaceType::STAGE;
+
+        // A runtime with XR_EXT_local_floor
+        let supported = [ReferenceSpaceType::VIEW, local, stage, LOCAL_FLOOR_EXT];
+        assert_eq!(
+            ReferenceSpace::LocalFloor.choose(&supported),
+            (ReferenceSpace::LocalFloor, LOCAL_FLOOR_EXT)
+        );
+        assert_eq!(
+            ReferenceSpace::Stage.choose(&supported),
+            (ReferenceSpace::Stage, stage)
+        );
+
+        // A runtime without it finds the floor with STAGE
+        let supported = [ReferenceSpaceType::VIEW, local, stage];
+        assert_eq!(
+            ReferenceSpace::LocalFloor.choose(&supported),
+            (ReferenceSpace::LocalFloor, local)
+        );
+
+        // A runtime that doesn't know where the floor is
+        let supported = [ReferenceSpaceType::VIEW, local];
+        assert_eq!(
+            ReferenceSpace::LocalFloor.choose(&supported),
+            (ReferenceSpace::Local, local)
+        );
+        assert_eq!(
+            ReferenceSpace::Stage.choose(&supported),
+            (ReferenceSpace::Local, local)
+        );
+        assert_eq!(
+            ReferenceSpace::Local.choose(&supported),
+            (ReferenceSpace::Local, local)
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use openxr as xr;
+
+use super::{
+    body_tracking::FB_BODY_TRACKING,
+    haptic_clips::{FB_HAPTIC_AMPLITUDE_ENVELOPE, FB_HAPTIC_PCM},
+    reference_space::EXT_LOCAL_FLOOR,
+};
+/// The extension for the controllers of Pico headsets. `openxr` doesn't know about it yet, so it's enabled by name.
+pub(crate) const BD_CONTROLLER_INTERACTION: &str = "XR_BD_controller_interaction";
+
+/// What the runtime Hotham is running on can do, reported when the [`XrContext`](super::XrContext) is created. Every
+/// feature Hotham can use beyond core OpenXR is optional, so this is where to look when something works on one
+/// headset but not another.
+#[derive(Debug, Clone)]
+pub struct RuntimeCapabilities {
+    /// The name of the runtime, eg. `Oculus`
+    pub runtime_name: String,
+    /// The runtime's version
+    pub runtime_version: String,
+    /// The name of the headset, eg. `Oculus Quest2`
+    pub system_name: String,
+    /// The extensions that were enabled, including any passed to
+    /// [`XrContextBuilder::required_extensions`](super::XrContextBuilder::required_extensions)
+    pub extensions: xr::ExtensionSet,
+    /// The view configurations the headset supports, eg. `PRIMARY_STEREO`
+    pub view_configurations: Vec<xr::ViewConfigurationType>,
+    /// How the headset can blend frames with the real world in the view configuration being rendered, in the runtime's
+    /// order of preference. Only `OPAQUE` on VR headsets; see [`XrContextBuilder::environment_blend_mode`](super::XrContextBuilder::environment_blend_mode).
+    pub environment_blend_modes: Vec<xr::EnvironmentBlendMode>,
+}
+
+impl RuntimeCapabilities {
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        system: xr::SystemId,
+        extensions: xr::ExtensionSet,
+        view_configurations: Vec<xr::ViewConfigurationType>,
+        view_configuration_type: xr::ViewConfigurationType,
+    ) -> Result<Self> {
+        let instance_properties = instance.properties()?;
+        let system_properties = instance.system_properties(system)?;
+        let environment_blend_modes =
+            instance.enumerate_environment_blend_modes(system, view_configuration_type)?;
+        Ok(Self {
+            runtime_name: instance_properties.runtime_name,
+            runtime_version: instance_properties.runtime_version.to_string(),
+            system_name: system_properties.system_name,
+            extensions,
+            view_configurations,
+            environment_blend_modes,
+        })
+    }
+
+    /// Was the extension with this name enabled? For extensions `openxr` doesn't know about, which can't be checked
+    /// with [`RuntimeCapabilities::extensions`].
+    pub fn has_other_extension(&self, name: &str) -> bool {
+        self.extensions.other.iter().any(|other| other == name)
+    }
+
+    /// Can the runtime bind the controllers of Pico headsets?
+    pub fn has_pico_controllers(&self) -> bool {
+        self.has_other_extension(BD_CONTROLLER_INTERACTION)
+    }
+
+    /// Can the runtime play the samples of a [`HapticClip`](crate::components::HapticClip) as they are?
+    pub fn has_haptic_pcm(&self) -> bool {
+        self.has_other_extension(FB_HAPTIC_PCM)
+    }
+
+    /// Can the runtime play the amplitude envelope of a [`HapticClip`](crate::components::HapticClip)?
+    pub fn has_haptic_amplitude_envelope(&self) -> bool {
+        self.has_other_extension(FB_HAPTIC_AMPLITUDE_ENVELOPE)
+    }
+
+    /// Whether each of the optional features Hotham uses is available, by the extension it needs
+    pub fn optional_extensions(&self) -> Vec<(&'static str, bool)> {
+        let extensions = &self.extensions;
+        vec![
+            (
+                "XR_KHR_composition_layer_depth",
+                extensions.khr_composition_layer_depth,
+            ),
+            (
+                "XR_FB_display_refresh_rate",
+                extensions.fb_display_refresh_rate,
+            ),
+            ("XR_FB_color_space", extensions.fb_color_space),
+            (
+                "XR_FB_composition_layer_settings",
+                extensions.fb_composition_layer_settings,
+            ),
+            (
+                "XR_EXT_performance_settings",
+                extensions.ext_performance_settings,
+            ),
+            (EXT_LOCAL_FLOOR, self.has_other_extension(EXT_LOCAL_FLOOR)),
+            (FB_HAPTIC_PCM, self.has_haptic_pcm()),
+            (
+                FB_HAPTIC_AMPLITUDE_ENVELOPE,
+                self.has_haptic_amplitude_envelope(),
+            ),
+            ("XR_FB_foveation", extensions.fb_foveation),
+            ("XR_FB_passthrough", extensions.fb_passthrough),
+            ("XR_FB_space_warp", extensions.fb_space_warp),
+            ("XR_EXT_hand_tracking", extensions.ext_hand_tracking),
+            (FB_BODY_TRACKING, self.has_other_extension(FB_BODY_TRACKING)),
+            (
+                "XR_EXT_eye_gaze_interaction",
+                extensions.ext_eye_gaze_interaction,
+            ),
+            ("XR_FB_render_model", extensions.fb_render_model),
+            ("XR_MSFT_controller_model", extensions.msft_controller_model),
+            ("XR_FB_keyboard_tracking", extensions.fb_keyboard_tracking),
+            ("XR_FB_spatial_entity", extensions.fb_spatial_entity),
+            ("XR_FB_scene", extensions.fb_scene),
+            (
+                "XR_HTCX_vive_tracker_interaction",
+                extensions.htcx_vive_tracker_interaction,
+            ),
+            (
+                "XR_HTC_vive_focus3_controller_interaction",
+                extensions.htc_vive_focus3_controller_interaction,
+            ),
+            (BD_CONTROLLER_INTERACTION, self.has_pico_controllers()),
+            ("XR_EXTX_overlay", extensions.extx_overlay),
+        ]
+    }
+
+    /// A summary for the log
+    pub(crate) fn report(&self) -> String {
+        let mut report = format!(
+            "Running on {} with {} {}",
+            self.system_name, self.runtime_name, self.runtime_version
+        );
+        report.push_str(&format!(
+            "\n    View configurations: {:?}\n    Environment blend modes: {:?}",
+            self.view_configurations, self.environment_blend_modes
+        ));
+        for (extension, enabled) in self.optional_extensions() {
+            let enabled = if enabled { "yes" } else { "no" };
+            report.push_str(&format!("\n    {extension}: {enabled}"));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_runtime_capabilities_report() {
+        let mut extensions = xr::ExtensionSet::default();
+        extensions.fb_passthrough = true;
+        extensions.other.push(BD_CONTROLLER_INTERACTION.to_string());
+        let runtime_capabilities = RuntimeCapabilities {
+            runtime_name: "Pico".to_string(),
+            runtime_version: "1.2.3".to_string(),
+            system_name: "Pico 4".to_string(),
+            extensions,
+            view_configurations: vec![xr::ViewConfigurationType::PRIMARY_STEREO],
+            environment_blend_modes: vec![
+                xr::EnvironmentBlendMode::OPAQUE,
+                xr::EnvironmentBlendMode::ALPHA_BLEND,
+            ],
+        };
+
+        assert!(runtime_capabilities.has_pico_controllers());
+        let report = runtime_capabilities.report();
+        assert!(report.starts_with("Running on Pico 4 with Pico 1.2.3"));
+        assert!(report.contains("Environment blend modes: [OPAQUE, ALPHA_BLEND]"));
+        assert!(report.contains("XR_FB_passthrough: yes"));
+        assert!(report.contains("XR_FB_scene: no"));
+        assert!(report.contains("XR_BD_controller_interaction: yes"));
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use glam::{Affine3A, Vec2, Vec3};
+use openxr::{self as xr, sys, Session, Space, Vulkan};
+
+use super::spatial_anchors::{
+    check, enable_space_component, query_spaces, retrieve_space_query_results, AnchorUuid,
+};
+use crate::util::{affine_from_posef, is_space_valid};
+
+/// The most parts of the room that are loaded
+const MAX_SCENE_ELEMENTS: u32 = 256;
+
+/// What the player said a part of their room is, when they set it up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneLabel {
+    Floor,
+    Ceiling,
+    Wall,
+    Table,
+    Couch,
+    Door,
+    Window,
+    /// Any other label, as the runtime names it, eg. `OTHER`
+    Other(String),
+}
+
+impl SceneLabel {
+    /// Parse the first of the runtime's comma separated labels
+    pub(crate) fn parse(labels: &str) -> Self {
+        match labels.split(',').next().unwrap_or_default() {
+            "FLOOR" => SceneLabel::Floor,
+            "CEILING" => SceneLabel::Ceiling,
+            "WALL_FACE" => SceneLabel::Wall,
+            "TABLE" => SceneLabel::Table,
+            "COUCH" => SceneLabel::Couch,
+            "DOOR_FRAME" => SceneLabel::Door,
+            "WINDOW_FRAME" => SceneLabel::Window,
+            label => SceneLabel::Other(label.to_string()),
+        }
+    }
+}
+
+/// A part of the room loaded from the runtime. Offsets and sizes are in the part's own space.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SceneElement {
+    pub uuid: AnchorUuid,
+    pub label: SceneLabel,
+    /// The rectangle bounding the part, eg. a wall, as its offset and size
+    pub plane: Option<(Vec2, Vec2)>,
+    /// The box bounding the part, eg. a couch, as its offset and size
+    pub volume: Option<(Vec3, Vec3)>,
+}
+
+/// Loads the layout of the player's room - its walls, floor, ceiling and furniture - as they set it up with the
+/// runtime, using `XR_FB_scene`.
+///
+/// The parts of the room are spawned as entities with [`ScenePlane`](crate::components::ScenePlane) and
+/// [`SceneVolume`](crate::components::SceneVolume) components by the
+/// [`scene_system`](crate::systems::scene_system).
+pub struct Scene {
+    /// Should the parts of the room be given `Collider`s, so content can collide with them? Defaults to `false`.
+    pub colliders: bool,
+    spaces: HashMap<AnchorUuid, Space>,
+    query_request_id: u64,
+    /// Requests to make parts of the room locatable
+    enabling_location: HashMap<u64, AnchorUuid>,
+    loaded: Vec<SceneElement>,
+}
+
+impl Scene {
+    /// Start loading the room. Returns `None` if the extensions weren't enabled, eg. because the runtime doesn't
+    /// support them.
+    pub(crate) fn new(session: &Session<Vulkan>) -> Result<Option<Self>> {
+        let exts = session.instance().exts();
+        if exts.fb_scene.is_none()
+            || exts.fb_spatial_entity.is_none()
+            || exts.fb_spatial_entity_query.is_none()
+        {
+            println!("[HOTHAM_XR] XR_FB_scene is not supported, the room layout won't be loaded");
+            return Ok(None);
+        }
+
+        let query_request_id = query_room(session)?;
+
+        println!("[HOTHAM_XR] Scene understanding enabled");
+        Ok(Some(Self {
+            colliders: false,
+            spaces: Default::default(),
+            query_request_id,
+            enabling_location: Default::default(),
+            loaded: Default::default(),
+        }))
+    }
+
+    /// Load the room again, eg. after the player has changed its layout. Parts of the room that were already loaded
+    /// are updated.
+    pub fn reload(&mut self, session: &Session<Vulkan>) -> Result<()> {
+        self.spaces.clear();
+        self.enabling_location.clear();
+        self.query_request_id = query_room(session)?;
+        Ok(())
+    }
+
+    /// Where a part of the room is in `stage_space`, or `None` if the runtime can't locate it right now
+    pub(crate) fn locate(
+        &self,
+        uuid: &AnchorUuid,
+        stage_space: &Space,
+        time: xr::Time,
+    ) -> Result<Option<Affine3A>> {
+        let space = match self.spaces.get(uuid) {
+            Some(space) => space,
+            None => return Ok(None),
+        };
+        let location = space.locate(stage_space, time)?;
+        Ok(is_space_valid(&location).then(|| affine_from_posef(location.pose)))
+    }
+
+    /// Take the parts of the room that have been loaded since this was last called
+    pub(crate) fn drain_loaded(&mut self) -> Vec<SceneElement> {
+        std::mem::take(&mut self.loaded)
+    }
+
+    /// Continue whatever was waiting on an event from the runtime
+    pub(crate) fn handle_event(
+        &mut self,
+        session: &Session<Vulkan>,
+        event: &xr::Event,
+    ) -> Result<()> {
+        match event {
+            xr::Event::SpaceQueryResultsAvailableFB(results_available)
+                if results_available.request_id().into_raw() == self.query_request_id =>
+            {
+                for result in retrieve_space_query_results(session, results_available.request_id())?
+                {
+                    let uuid = result.uuid.data;
+                    // SAFETY: The runtime has just handed us this space, and it's only destroyed when the `Space` is
+                    // dropped.
+                    let space = unsafe { Space::reference_from_raw(session.clone(), result.space) };
+                    self.spaces.insert(uuid, space);
+
+                    let request_id = enable_space_component(
+                        session,
+                        result.space,
+                        sys::SpaceComponentTypeFB::LOCATABLE,
+                    )?;
+                    match request_id {
+                        Some(request_id) => {
+                            self.enabling_location.insert(request_id, uuid);
+                        }
+                        None => self.load_element(session, uuid)?,
+                    }
+                }
+            }
+            xr::Event::SpaceSetStatusCompleteFB(status_set) => {
+                let request_id = status_set.request_id().into_raw();
+                if let Some(uuid) = self.enabling_location.remove(&request_id) {
+                    let result = status_set.result();
+                    if result.into_raw() >= 0
+                        || result == sys::Result::ERROR_SPACE_COMPONENT_STATUS_ALREADY_SET_FB
+                    {
+                        self.load_element(session, uuid)?;
+                    }
+                }
+            }
+            xr::Event::SpaceQueryCompleteFB(query_complete)
+                if query_complete.request_id().into_raw() == self.query_request_id =>
+            {
+                println!(
+                    "[HOTHAM_SCENE] Finished loading the room: {:?}",
+                    query_complete.result()
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn load_element(&mut self, session: &Session<Vulkan>, uuid: AnchorUuid) -> Result<()> {
+        let fp = session.instance().exts().fb_scene.unwrap();
+        let space = self.spaces[&uuid].as_raw();
+
+        let mut labels = sys::SemanticLabelsFB {
+            ty: sys::SemanticLabelsFB::TYPE,
+            next: std::ptr::null(),
+            buffer_capacity_input: 0,
+            buffer_count_output: 0,
+            buffer: std::ptr::null_mut(),
+        };
+        check(unsafe { (fp.get_space_semantic_labels)(session.as_raw(), space, &mut labels) })?;
+        let mut buffer = vec![0; labels.buffer_count_output as usize];
+        labels.buffer_capacity_input = buffer.len() as _;
+        labels.buffer = buffer.as_mut_ptr();
+        check(unsafe { (fp.get_space_semantic_labels)(session.as_raw(), space, &mut labels) })?;
+        // The buffer is null terminated.
+        let labels = buffer
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8 as char)
+            .collect::<String>();
+
+        let plane = if is_component_enabled(session, space, sys::SpaceComponentTypeFB::BOUNDED_2D)?
+        {
+            // SAFETY: Zero is a valid value for every field of the rectangle, which the runtime overwrites.
+            let mut rect: sys::Rect2Df = unsafe { std::mem::zeroed() };
+            check(unsafe { (fp.get_space_bounding_box2_d)(session.as_raw(), space, &mut rect) })?;
+            Some((
+                Vec2::new(rect.offset.x, rect.offset.y),
+                Vec2::new(rect.extent.width, rect.extent.height),
+            ))
+        } else {
+            None
+        };
+
+        let volume = if is_component_enabled(session, space, sys::SpaceComponentTypeFB::BOUNDED_3D)?
+        {
+            // SAFETY: As above.
+            let mut rect: sys::Rect3DfFB = unsafe { std::mem::zeroed() };
+            check(unsafe { (fp.get_space_bounding_box3_d)(session.as_raw(), space, &mut rect) })?;
+            Some((
+                Vec3::new(rect.offset.x, rect.offset.y, rect.offset.z),
+                Vec3::new(rect.extent.width, rect.extent.height, rect.extent.depth),
+            ))
+        } else {
+            None
+        };
+
+        self.loaded.push(SceneElement {
+            uuid,
+            label: SceneLabel::parse(&labels),
+            plane,
+            volume,
+        });
+        Ok(())
+    }
+}
+
+/// Every part of the room the player has set up has semantic labels, so query for those.
+fn query_room(session: &Session<Vulkan>) -> Result<u64> {
+    query_spaces(
+        session,
+        sys::SpaceComponentTypeFB::SEMANTIC_LABELS,
+        MAX_SCENE_ELEMENTS,
+    )
+}
+
+fn is_component_enabled(
+    session: &Session<Vulkan>,
+    space: sys::Space,
+    component_type: sys::SpaceComponentTypeFB,
+) -> Result<bool> {
+    let fp = session.instance().exts().fb_spatial_entity.unwrap();
+    let mut status = sys::SpaceComponentStatusFB {
+        ty: sys::SpaceComponentStatusFB::TYPE,
+        next: std::ptr::null_mut(),
+        enabled: false.into(),
+        change_pending: false.into(),
+    };
+    let result = unsafe { (fp.get_space_component_status)(space, component_type, &mut status) };
+    // Parts of the room that don't support a component, eg. a wall with no volume, return an error.
+    if result == sys::Result::ERROR_SPACE_COMPONENT_NOT_SUPPORTED_FB {
+        return Ok(false);
+    }
+    check(result)?;
+    Ok(status.enabled.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_parse_scene_label() {
+        assert_eq!(SceneLabel::parse("WALL_FACE"), SceneLabel::Wall);
+        assert_eq!(SceneLabel::parse("TABLE,OTHER"), SceneLabel::Table);
+        assert_eq!(
+            SceneLabel::parse("PLANT"),
+            SceneLabel::Other("PLANT".to_string())
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use openxr::{self as xr, Session, Swapchain, SwapchainCreateFlags, SwapchainUsageFlags, Vulkan};
+
+use crate::{rendering::camera::NEAR_PLANE, DEPTH_FORMAT, VIEW_COUNT};
+
+/// The format motion vectors are rendered in. Only the red, green and blue channels are used.
+pub const MOTION_VECTOR_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+/// Application SpaceWarp, using `XR_FB_space_warp`.
+///
+/// Each frame the renderer draws the motion of everything in the scene into `motion_vector_swapchain`, along with
+/// its depth. The compositor uses them to synthesize every other frame, so the application only has to render at
+/// half the display's refresh rate.
+///
+/// Motion vectors only describe how objects move relative to the stage. How the stage itself moved since the last
+/// frame, eg. due to locomotion, is submitted separately as `app_space_delta_pose`.
+pub struct SpaceWarp {
+    /// Receives the motion vectors of each frame
+    pub motion_vector_swapchain: Swapchain<Vulkan>,
+    /// Receives the depth of each frame, at the resolution of the motion vectors
+    pub depth_swapchain: Swapchain<Vulkan>,
+    /// The resolution recommended by the runtime for the motion vector and depth swapchains
+    pub resolution: vk::Extent2D,
+    /// The pose of the stage this frame, relative to where it was last frame. Set by the renderer.
+    pub app_space_delta_pose: xr::Posef,
+}
+
+impl SpaceWarp {
+    /// Create the motion vector and depth swapchains. Returns `None` if `XR_FB_space_warp` wasn't enabled, eg.
+    /// because the runtime doesn't support it, or if it doesn't support the formats we need.
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        session: &Session<Vulkan>,
+        system: xr::SystemId,
+    ) -> Result<Option<Self>> {
+        if instance.exts().fb_space_warp.is_none() {
+            println!("[HOTHAM_XR] XR_FB_space_warp is not supported, SpaceWarp is disabled");
+            return Ok(None);
+        }
+
+        let formats = session.enumerate_swapchain_formats()?;
+        for format in [MOTION_VECTOR_FORMAT, DEPTH_FORMAT] {
+            if !formats.contains(&(format.as_raw() as u32)) {
+                println!("[HOTHAM_XR] {format:?} is not a supported swapchain format, SpaceWarp is disabled");
+                return Ok(None);
+            }
+        }
+
+        let resolution = get_motion_vector_resolution(instance, system)?;
+        println!("[HOTHAM_XR] SpaceWarp enabled, motion vector resolution is {resolution:?}");
+        let motion_vector_swapchain = create_swapchain(
+            session,
+            &resolution,
+            MOTION_VECTOR_FORMAT,
+            SwapchainUsageFlags::COLOR_ATTACHMENT,
+        )?;
+        let depth_swapchain = create_swapchain(
+            session,
+            &resolution,
+            DEPTH_FORMAT,
+            SwapchainUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+        )?;
+
+        Ok(Some(Self {
+            motion_vector_swapchain,
+            depth_swapchain,
+            resolution,
+            app_space_delta_pose: xr::Posef::IDENTITY,
+        }))
+    }
+
+    /// Acquire the images the renderer will draw into this frame. Both swapchains are acquired in lockstep with the
+    /// color swapchain, so they share its image index.
+    pub(crate) fn acquire_images(&mut self, image_index: usize) -> Result<()> {
+        for swapchain in [&mut self.motion_vector_swapchain, &mut self.depth_swapchain] {
+            let acquired_index: usize = swapchain.acquire_image()? as _;
+            swapchain.wait_image(xr::Duration::INFINITE)?;
+            debug_assert_eq!(image_index, acquired_index);
+        }
+        Ok(())
+    }
+
+    /// Release the images that were acquired with `acquire_images`.
+    pub(crate) fn release_images(&mut self) -> Result<()> {
+        self.motion_vector_swapchain.release_image()?;
+        self.depth_swapchain.release_image()?;
+        Ok(())
+    }
+
+    /// The SpaceWarp info to chain onto each view of the projection layer.
+    /// Like the depth layer, depth uses inverse Z: zero is infinitely far away, and one is the near plane.
+    pub(crate) fn layer_infos(&self) -> [xr::sys::CompositionLayerSpaceWarpInfoFB; 2] {
+        let image_rect = xr::Rect2Di {
+            offset: xr::Offset2Di { x: 0, y: 0 },
+            extent: xr::Extent2Di {
+                width: self.resolution.width as _,
+                height: self.resolution.height as _,
+            },
+        };
+        [0, 1].map(
+            |image_array_index| xr::sys::CompositionLayerSpaceWarpInfoFB {
+                ty: xr::sys::CompositionLayerSpaceWarpInfoFB::TYPE,
+                next: std::ptr::null(),
+                layer_flags: xr::sys::CompositionLayerSpaceWarpInfoFlagsFB::EMPTY,
+                motion_vector_sub_image: xr::sys::SwapchainSubImage {
+                    swapchain: self.motion_vector_swapchain.as_raw(),
+                    image_rect,
+                    image_array_index,
+                },
+                app_space_delta_pose: self.app_space_delta_pose,
+                depth_sub_image: xr::sys::SwapchainSubImage {
+                    swapchain: self.depth_swapchain.as_raw(),
+                    image_rect,
+                    image_array_index,
+                },
+                min_depth: 0.,
+                max_depth: 1.,
+                near_z: f32::INFINITY,
+                far_z: NEAR_PLANE,
+            },
+        )
+    }
+}
+
+/// Ask the runtime what resolution it would like the motion vectors in, by chaining
+/// `XrSystemSpaceWarpPropertiesFB` onto the system properties.
+fn get_motion_vector_resolution(
+    instance: &xr::Instance,
+    system: xr::SystemId,
+) -> Result<vk::Extent2D> {
+    let mut space_warp_properties = xr::sys::SystemSpaceWarpPropertiesFB {
+        ty: xr::sys::SystemSpaceWarpPropertiesFB::TYPE,
+        next: std::ptr::null_mut(),
+        recommended_motion_vector_image_rect_width: 0,
+        recommended_motion_vector_image_rect_height: 0,
+    };
+    // SAFETY: Zero is a valid value for every field of the properties, which the runtime overwrites.
+    let mut system_properties: xr::sys::SystemProperties = unsafe { std::mem::zeroed() };
+    system_properties.ty = xr::sys::SystemProperties::TYPE;
+    system_properties.next = &mut space_warp_properties as *mut _ as _;
+
+    let result = unsafe {
+        (instance.fp().get_system_properties)(instance.as_raw(), system, &mut system_properties)
+    };
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+
+    Ok(vk::Extent2D {
+        width: space_warp_properties.recommended_motion_vector_image_rect_width,
+        height: space_warp_properties.recommended_motion_vector_image_rect_height,
+    })
+}
+
+fn create_swapchain(
+    session: &Session<Vulkan>,
+    resolution: &vk::Extent2D,
+    format: vk::Format,
+    usage_flags: SwapchainUsageFlags,
+) -> Result<Swapchain<Vulkan>> {
+    session
+        .create_swapchain(&xr::SwapchainCreateInfo {
+            create_flags: SwapchainCreateFlags::EMPTY,
+            usage_flags,
+            format: format.as_raw() as u32,
+            sample_count: 1,
+            width: resolution.width,
+            height: resolution.height,
+            face_count: 1,
+            array_size: VIEW_COUNT,
+            mip_count: 1,
+        })
+        .map_err(Into::into)
+}
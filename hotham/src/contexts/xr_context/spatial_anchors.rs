@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use glam::Affine3A;
+use openxr::{self as xr, sys, Session, Space, Vulkan};
+
+use crate::util::{affine_from_posef, is_space_valid, posef_from_affine};
+
+/// Identifies a spatial anchor. Stays the same across runs of the application once the anchor is persisted.
+pub type AnchorUuid = [u8; 16];
+
+/// The most anchors that are loaded from storage at startup
+const MAX_PERSISTED_ANCHORS: u32 = 64;
+
+/// Something the runtime finished doing with a spatial anchor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum SpatialAnchorEvent {
+    /// The anchor requested by `create_anchor` was created, or failed to be if there's no UUID
+    Created {
+        request_id: u64,
+        uuid: Option<AnchorUuid>,
+    },
+    /// The anchor was saved to the headset's storage, or failed to be
+    Persisted { uuid: AnchorUuid, success: bool },
+    /// An anchor persisted by an earlier run of the application was loaded
+    Loaded { uuid: AnchorUuid },
+}
+
+/// Creates, persists and locates spatial anchors, using `XR_FB_spatial_entity`, `XR_FB_spatial_entity_storage`
+/// and `XR_FB_spatial_entity_query`.
+///
+/// Everything the runtime does with anchors happens asynchronously: requests are made here, and their results
+/// arrive as events, which the [`spatial_anchors_system`](crate::systems::spatial_anchors_system) applies to
+/// [`SpatialAnchor`](crate::components::SpatialAnchor) components.
+pub struct SpatialAnchors {
+    spaces: HashMap<AnchorUuid, Space>,
+    /// Requests to create anchors, and whether the anchor should be persisted
+    creating: HashMap<u64, bool>,
+    /// Requests to make anchors storable, so they can be persisted
+    enabling_storage: HashMap<u64, AnchorUuid>,
+    /// Requests to make anchors loaded from storage locatable
+    enabling_location: HashMap<u64, AnchorUuid>,
+    /// The query loading anchors persisted by earlier runs
+    query_request_id: u64,
+    events: Vec<SpatialAnchorEvent>,
+}
+
+impl SpatialAnchors {
+    /// Start loading any anchors persisted by earlier runs. Returns `None` if the extensions weren't enabled, eg.
+    /// because the runtime doesn't support them.
+    pub(crate) fn new(session: &Session<Vulkan>) -> Result<Option<Self>> {
+        let exts = session.instance().exts();
+        if exts.fb_spatial_entity.is_none()
+            || exts.fb_spatial_entity_storage.is_none()
+            || exts.fb_spatial_entity_query.is_none()
+        {
+            println!(
+                "[HOTHAM_XR] XR_FB_spatial_entity is not supported, spatial anchors are disabled"
+            );
+            return Ok(None);
+        }
+
+        // Load every anchor in the headset's local storage that can be located.
+        let query_request_id = query_spaces(
+            session,
+            sys::SpaceComponentTypeFB::LOCATABLE,
+            MAX_PERSISTED_ANCHORS,
+        )?;
+
+        println!("[HOTHAM_XR] Spatial anchors enabled");
+        Ok(Some(Self {
+            spaces: Default::default(),
+            creating: Default::default(),
+            enabling_storage: Default::default(),
+            enabling_location: Default::default(),
+            query_request_id,
+            events: Default::default(),
+        }))
+    }
+
+    /// Ask the runtime to create an anchor at a pose in `stage_space`, and optionally persist it. Returns the ID of
+    /// the request, which identifies the anchor until it's created.
+    pub(crate) fn create_anchor(
+        &mut self,
+        session: &Session<Vulkan>,
+        stage_space: &Space,
+        stage_from_anchor: Affine3A,
+        time: xr::Time,
+        persist: bool,
+    ) -> Result<u64> {
+        let fp = session.instance().exts().fb_spatial_entity.unwrap();
+        let create_info = sys::SpatialAnchorCreateInfoFB {
+            ty: sys::SpatialAnchorCreateInfoFB::TYPE,
+            next: std::ptr::null(),
+            space: stage_space.as_raw(),
+            pose_in_space: posef_from_affine(stage_from_anchor),
+            time,
+        };
+        let mut request_id = sys::AsyncRequestIdFB::from_raw(0);
+        check(unsafe {
+            (fp.create_spatial_anchor)(session.as_raw(), &create_info, &mut request_id)
+        })?;
+
+        self.creating.insert(request_id.into_raw(), persist);
+        Ok(request_id.into_raw())
+    }
+
+    /// Where an anchor is in `stage_space`, or `None` if the runtime can't locate it right now
+    pub(crate) fn locate(
+        &self,
+        uuid: &AnchorUuid,
+        stage_space: &Space,
+        time: xr::Time,
+    ) -> Result<Option<Affine3A>> {
+        let space = match self.spaces.get(uuid) {
+            Some(space) => space,
+            None => return Ok(None),
+        };
+        let location = space.locate(stage_space, time)?;
+        Ok(is_space_valid(&location).then(|| affine_from_posef(location.pose)))
+    }
+
+    /// Take the events that have happened since this was last called
+    pub(crate) fn drain_events(&mut self) -> Vec<SpatialAnchorEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Continue whatever was waiting on an event from the runtime
+    pub(crate) fn handle_event(
+        &mut self,
+        session: &Session<Vulkan>,
+        event: &xr::Event,
+    ) -> Result<()> {
+        match event {
+            xr::Event::SpatialAnchorCreateCompleteFB(created) => {
+                let request_id = created.request_id().into_raw();
+                let persist = match self.creating.remove(&request_id) {
+                    Some(persist) => persist,
+                    None => return Ok(()),
+                };
+
+                if created.result().into_raw() < 0 {
+                    println!(
+                        "[HOTHAM_SPATIAL_ANCHORS] Unable to create anchor: {:?}",
+                        created.result()
+                    );
+                    self.events.push(SpatialAnchorEvent::Created {
+                        request_id,
+                        uuid: None,
+                    });
+                    return Ok(());
+                }
+
+                let uuid = created.uuid().data;
+                self.add_space(session, created.space(), uuid);
+                self.events.push(SpatialAnchorEvent::Created {
+                    request_id,
+                    uuid: Some(uuid),
+                });
+
+                if persist {
+                    let request_id = enable_space_component(
+                        session,
+                        created.space(),
+                        sys::SpaceComponentTypeFB::STORABLE,
+                    )?;
+                    match request_id {
+                        Some(request_id) => {
+                            self.enabling_storage.insert(request_id, uuid);
+                        }
+                        None => self.save(session, created.space(), uuid)?,
+                    }
+                }
+            }
+            xr::Event::SpaceSetStatusCompleteFB(status_set) => {
+                let request_id = status_set.request_id().into_raw();
+                let succeeded = status_set.result().into_raw() >= 0
+                    || status_set.result()
+                        == sys::Result::ERROR_SPACE_COMPONENT_STATUS_ALREADY_SET_FB;
+
+                if let Some(uuid) = self.enabling_storage.remove(&request_id) {
+                    if succeeded {
+                        self.save(session, status_set.space(), uuid)?;
+                    } else {
+                        self.events.push(SpatialAnchorEvent::Persisted {
+                            uuid,
+                            success: false,
+                        });
+                    }
+                }
+
+                if let Some(uuid) = self.enabling_location.remove(&request_id) {
+                    if succeeded {
+                        self.events.push(SpatialAnchorEvent::Loaded { uuid });
+                    }
+                }
+            }
+            xr::Event::SpaceSaveCompleteFB(saved) => {
+                let success = saved.result().into_raw() >= 0;
+                if !success {
+                    println!(
+                        "[HOTHAM_SPATIAL_ANCHORS] Unable to persist anchor: {:?}",
+                        saved.result()
+                    );
+                }
+                self.events.push(SpatialAnchorEvent::Persisted {
+                    uuid: saved.uuid().data,
+                    success,
+                });
+            }
+            xr::Event::SpaceQueryResultsAvailableFB(results_available)
+                if results_available.request_id().into_raw() == self.query_request_id =>
+            {
+                self.load_query_results(session, results_available.request_id())?;
+            }
+            xr::Event::SpaceQueryCompleteFB(query_complete)
+                if query_complete.request_id().into_raw() == self.query_request_id =>
+            {
+                println!(
+                    "[HOTHAM_SPATIAL_ANCHORS] Finished loading persisted anchors: {:?}",
+                    query_complete.result()
+                );
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn load_query_results(
+        &mut self,
+        session: &Session<Vulkan>,
+        request_id: sys::AsyncRequestIdFB,
+    ) -> Result<()> {
+        for result in retrieve_space_query_results(session, request_id)? {
+            let uuid = result.uuid.data;
+            if self.spaces.contains_key(&uuid) {
+                continue;
+            }
+            self.add_space(session, result.space, uuid);
+
+            let request_id = enable_space_component(
+                session,
+                result.space,
+                sys::SpaceComponentTypeFB::LOCATABLE,
+            )?;
+            match request_id {
+                Some(request_id) => {
+                    self.enabling_location.insert(request_id, uuid);
+                }
+                None => self.events.push(SpatialAnchorEvent::Loaded { uuid }),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn save(
+        &mut self,
+        session: &Session<Vulkan>,
+        space: sys::Space,
+        uuid: AnchorUuid,
+    ) -> Result<()> {
+        let fp = session.instance().exts().fb_spatial_entity_storage.unwrap();
+        let save_info = sys::SpaceSaveInfoFB {
+            ty: sys::SpaceSaveInfoFB::TYPE,
+            next: std::ptr::null(),
+            space,
+            location: sys::SpaceStorageLocationFB::LOCAL,
+            persistence_mode: sys::SpacePersistenceModeFB::INDEFINITE,
+        };
+        let mut request_id = sys::AsyncRequestIdFB::from_raw(0);
+        let result = unsafe { (fp.save_space)(session.as_raw(), &save_info, &mut request_id) };
+        if result.into_raw() < 0 {
+            println!("[HOTHAM_SPATIAL_ANCHORS] Unable to persist anchor: {result:?}");
+            self.events.push(SpatialAnchorEvent::Persisted {
+                uuid,
+                success: false,
+            });
+        }
+        Ok(())
+    }
+
+    fn add_space(&mut self, session: &Session<Vulkan>, space: sys::Space, uuid: AnchorUuid) {
+        // SAFETY: The runtime has just handed us this space, and it's only destroyed when the `Space` is dropped.
+        let space = unsafe { Space::reference_from_raw(session.clone(), space) };
+        self.spaces.insert(uuid, space);
+    }
+}
+
+/// Ask the runtime for the spaces in the headset's local storage that have a component, eg. anchors persisted by
+/// earlier runs. Returns the ID of the query, which identifies the events with its results.
+pub(super) fn query_spaces(
+    session: &Session<Vulkan>,
+    component_type: sys::SpaceComponentTypeFB,
+    max_result_count: u32,
+) -> Result<u64> {
+    let fp = session.instance().exts().fb_spatial_entity_query.unwrap();
+
+    let storage_location_filter = sys::SpaceStorageLocationFilterInfoFB {
+        ty: sys::SpaceStorageLocationFilterInfoFB::TYPE,
+        next: std::ptr::null(),
+        location: sys::SpaceStorageLocationFB::LOCAL,
+    };
+    let component_filter = sys::SpaceComponentFilterInfoFB {
+        ty: sys::SpaceComponentFilterInfoFB::TYPE,
+        next: &storage_location_filter as *const _ as _,
+        component_type,
+    };
+    let query_info = sys::SpaceQueryInfoFB {
+        ty: sys::SpaceQueryInfoFB::TYPE,
+        next: std::ptr::null(),
+        query_action: sys::SpaceQueryActionFB::LOAD,
+        max_result_count,
+        timeout: xr::Duration::from_nanos(0),
+        filter: &component_filter as *const _ as _,
+        exclude_filter: std::ptr::null(),
+    };
+    let mut request_id = sys::AsyncRequestIdFB::from_raw(0);
+    check(unsafe {
+        (fp.query_spaces)(
+            session.as_raw(),
+            &query_info as *const _ as _,
+            &mut request_id,
+        )
+    })?;
+    Ok(request_id.into_raw())
+}
+
+/// Take the spaces a query has found so far
+pub(super) fn retrieve_space_query_results(
+    session: &Session<Vulkan>,
+    request_id: sys::AsyncRequestIdFB,
+) -> Result<Vec<sys::SpaceQueryResultFB>> {
+    let fp = session.instance().exts().fb_spatial_entity_query.unwrap();
+
+    let mut query_results = sys::SpaceQueryResultsFB {
+        ty: sys::SpaceQueryResultsFB::TYPE,
+        next: std::ptr::null_mut(),
+        result_capacity_input: 0,
+        result_count_output: 0,
+        results: std::ptr::null_mut(),
+    };
+    check(unsafe {
+        (fp.retrieve_space_query_results)(session.as_raw(), request_id, &mut query_results)
+    })?;
+    let mut results = vec![
+        sys::SpaceQueryResultFB {
+            space: sys::Space::NULL,
+            uuid: sys::UuidEXT { data: [0; 16] },
+        };
+        query_results.result_count_output as usize
+    ];
+    query_results.result_capacity_input = results.len() as _;
+    query_results.results = results.as_mut_ptr();
+    check(unsafe {
+        (fp.retrieve_space_query_results)(session.as_raw(), request_id, &mut query_results)
+    })?;
+    Ok(results)
+}
+
+/// Enable a component of a space, eg. an anchor. Returns the ID of the request, or `None` if it was already enabled.
+pub(super) fn enable_space_component(
+    session: &Session<Vulkan>,
+    space: sys::Space,
+    component_type: sys::SpaceComponentTypeFB,
+) -> Result<Option<u64>> {
+    let fp = session.instance().exts().fb_spatial_entity.unwrap();
+    let status_info = sys::SpaceComponentStatusSetInfoFB {
+        ty: sys::SpaceComponentStatusSetInfoFB::TYPE,
+        next: std::ptr::null(),
+        component_type,
+        enabled: true.into(),
+        timeout: xr::Duration::from_nanos(0),
+    };
+    let mut request_id = sys::AsyncRequestIdFB::from_raw(0);
+    let result = unsafe { (fp.set_space_component_status)(space, &status_info, &mut request_id) };
+    if result == sys::Result::ERROR_SPACE_COMPONENT_STATUS_ALREADY_SET_FB {
+        return Ok(None);
+    }
+    check(result)?;
+    Ok(Some(request_id.into_raw()))
+}
+
+pub(super) fn check(result: sys::Result) -> Result<()> {
+    if result.into_raw() < 0 {
+        return Err(anyhow::Error::new(result));
+    }
+    Ok(())
+}
@@ -1,6 +1,27 @@
 use super::XrContext;
 use crate::HothamResult;
 
+impl XrContext {
+    /// When the frame being rendered is predicted to be shown to the player. Poses in the input context and
+    /// [`XrContext::views`] are predicted for this time.
+    pub fn predicted_display_time(&self) -> openxr::Time {
+        self.frame_state.predicted_display_time
+    }
+
+    /// How long the runtime predicts each frame will be shown for, ie. the time between frames, eg. to step a
+    /// simulation by one frame
+    pub fn predicted_display_period(&self) -> std::time::Duration {
+        std::time::Duration::from_nanos(self.frame_state.predicted_display_period.as_nanos() as _)
+    }
+
+    /// The time `frames` frames after the frame being rendered is shown, eg. to predict where something will be a
+    /// few frames from now with [`XrContext::locate_space`]. Negative values are in the past.
+    pub fn predicted_display_time_after(&self, frames: f32) -> openxr::Time {
+        let offset = self.frame_state.predicted_display_period.as_nanos() as f64 * frames as f64;
+        openxr::Time::from_nanos(self.frame_state.predicted_display_time.as_nanos() + offset as i64)
+    }
+}
+
 #[cfg(target_os = "windows")]
 impl XrContext {
     pub fn now(&self) -> HothamResult<openxr::Time> {
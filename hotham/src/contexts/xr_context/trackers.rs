@@ -0,0 +1,113 @@
+use anyhow::Result;
+use openxr::{self as xr, Action, ActionSet, Posef, Space};
+
+/// The interaction profile of trackers, such as Vive trackers, from `XR_HTCX_vive_tracker_interaction`
+pub(crate) const VIVE_TRACKER_INTERACTION: &str = "/interaction_profiles/htc/vive_tracker_htcx";
+
+/// What a tracker is attached to, as the player assigned it in their runtime, eg. in SteamVR's "Manage Trackers"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TrackerRole {
+    /// A prop held in the player's hand, eg. a bat
+    HandheldObject,
+    LeftFoot,
+    RightFoot,
+    LeftShoulder,
+    RightShoulder,
+    LeftElbow,
+    RightElbow,
+    LeftKnee,
+    RightKnee,
+    Waist,
+    Chest,
+    /// A real camera, eg. for mixed reality capture
+    Camera,
+    /// A keyboard on the player's desk
+    Keyboard,
+}
+
+impl TrackerRole {
+    /// Every role a tracker can have
+    pub const ALL: [TrackerRole; 13] = [
+        TrackerRole::HandheldObject,
+        TrackerRole::LeftFoot,
+        TrackerRole::RightFoot,
+        TrackerRole::LeftShoulder,
+        TrackerRole::RightShoulder,
+        TrackerRole::LeftElbow,
+        TrackerRole::RightElbow,
+        TrackerRole::LeftKnee,
+        TrackerRole::RightKnee,
+        TrackerRole::Waist,
+        TrackerRole::Chest,
+        TrackerRole::Camera,
+        TrackerRole::Keyboard,
+    ];
+
+    /// The top level path of trackers with this role
+    pub(crate) fn path(self) -> &'static str {
+        match self {
+            TrackerRole::HandheldObject => "/user/vive_tracker_htcx/role/handheld_object",
+            TrackerRole::LeftFoot => "/user/vive_tracker_htcx/role/left_foot",
+            TrackerRole::RightFoot => "/user/vive_tracker_htcx/role/right_foot",
+            TrackerRole::LeftShoulder => "/user/vive_tracker_htcx/role/left_shoulder",
+            TrackerRole::RightShoulder => "/user/vive_tracker_htcx/role/right_shoulder",
+            TrackerRole::LeftElbow => "/user/vive_tracker_htcx/role/left_elbow",
+            TrackerRole::RightElbow => "/user/vive_tracker_htcx/role/right_elbow",
+            TrackerRole::LeftKnee => "/user/vive_tracker_htcx/role/left_knee",
+            TrackerRole::RightKnee => "/user/vive_tracker_htcx/role/right_knee",
+            TrackerRole::Waist => "/user/vive_tracker_htcx/role/waist",
+            TrackerRole::Chest => "/user/vive_tracker_htcx/role/chest",
+            TrackerRole::Camera => "/user/vive_tracker_htcx/role/camera",
+            TrackerRole::Keyboard => "/user/vive_tracker_htcx/role/keyboard",
+        }
+    }
+}
+
+/// The poses of extra tracked devices, such as Vive trackers strapped to the player's feet and waist, using
+/// `XR_HTCX_vive_tracker_interaction`. Trackers are found by the role the player gave them, so a tracker that isn't
+/// connected, or has no role, is never tracked.
+pub struct Trackers {
+    pub pose_action: Action<Posef>,
+    /// A space for each role, whose origin is wherever the tracker with that role is
+    pub spaces: Vec<(TrackerRole, Space)>,
+}
+
+impl Trackers {
+    pub(crate) fn new(
+        instance: &xr::Instance,
+        session: &xr::Session<xr::Vulkan>,
+        action_set: &ActionSet,
+    ) -> Result<Self> {
+        let role_paths = TrackerRole::ALL
+            .iter()
+            .map(|role| instance.string_to_path(role.path()))
+            .collect::<xr::Result<Vec<_>>>()?;
+        let pose_action =
+            action_set.create_action::<Posef>("tracker_pose", "Tracker Pose", &role_paths)?;
+        let spaces = TrackerRole::ALL
+            .into_iter()
+            .zip(&role_paths)
+            .map(|(role, path)| {
+                let space = pose_action.create_space(session.clone(), *path, Posef::IDENTITY)?;
+                Ok((role, space))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        println!("[HOTHAM_XR] Tracker support enabled");
+        Ok(Self {
+            pose_action,
+            spaces,
+        })
+    }
+
+    /// Bind the pose of each role's trackers
+    pub(crate) fn bindings(&self, instance: &xr::Instance) -> Result<Vec<xr::Binding<'_>>> {
+        TrackerRole::ALL
+            .iter()
+            .map(|role| {
+                let path = instance.string_to_path(&format!("{}/input/grip/pose", role.path()))?;
+                Ok(xr::Binding::new(&self.pose_action, path))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use openxr as xr;
+use xr::{EnvironmentBlendMode, ViewConfigurationType};
+
+use crate::{BLEND_MODE, VIEW_COUNT, VIEW_TYPE};
+
+/// The view configurations the runtime supports, in its order of preference, with how many views each has
+pub(crate) fn supported_view_configurations(
+    instance: &xr::Instance,
+    system: xr::SystemId,
+) -> Result<Vec<(ViewConfigurationType, usize)>> {
+    instance
+        .enumerate_view_configurations(system)?
+        .into_iter()
+        .map(|view_configuration| {
+            let views = instance.enumerate_view_configuration_views(system, view_configuration)?;
+            Ok((view_configuration, views.len()))
+        })
+        .collect()
+}
+
+/// Pick the view configuration to render: the first in `preferred` that the runtime supports, or [`VIEW_TYPE`] if
+/// `preferred` is empty. The renderer draws every view at once with multiview, so configurations that don't have
+/// [`VIEW_COUNT`] views are skipped.
+pub(crate) fn choose_view_configuration(
+    preferred: &[ViewConfigurationType],
+    supported: &[(ViewConfigurationType, usize)],
+) -> Result<ViewConfigurationType> {
+    let preferred: &[ViewConfigurationType] = if preferred.is_empty() {
+        &[VIEW_TYPE]
+    } else {
+        preferred
+    };
+
+    for view_configuration in preferred {
+        let view_count = supported
+            .iter()
+            .find(|(supported, _)| supported == view_configuration)
+            .map(|(_, view_count)| *view_count);
+        match view_count {
+            Some(view_count) if view_count == VIEW_COUNT as usize => {
+                return Ok(*view_configuration)
+            }
+            Some(view_count) => println!("[HOTHAM_XR] {view_configuration:?} has {view_count} views, but the renderer draws {VIEW_COUNT}. Skipping it."),
+            None => println!("[HOTHAM_XR] {view_configuration:?} isn't supported. Skipping it."),
+        }
+    }
+
+    Err(anyhow!(
+        "The runtime doesn't support any of {preferred:?} with {VIEW_COUNT} views, only {supported:?}"
+    ))
+}
+
+/// Pick how the compositor blends frames with the real world: `requested` if the runtime supports it, otherwise
+/// [`BLEND_MODE`], otherwise whatever the runtime prefers. Runtimes list their preferred mode first.
+pub(crate) fn choose_environment_blend_mode(
+    requested: EnvironmentBlendMode,
+    supported: &[EnvironmentBlendMode],
+) -> EnvironmentBlendMode {
+    if supported.contains(&requested) {
+        return requested;
+    }
+
+    let fallback = if supported.contains(&BLEND_MODE) {
+        BLEND_MODE
+    } else {
+        supported.first().copied().unwrap_or(requested)
+    };
+    println!("[HOTHAM_XR] {requested:?} isn't supported, using {fallback:?} instead. Supported blend modes: {supported:?}");
+    fallback
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_choose_view_configuration() {
+        let mono = ViewConfigurationType::PRIMARY_MONO;
+        let stereo = ViewConfigurationType::PRIMARY_STEREO;
+        let quad = ViewConfigurationType::PRIMARY_QUAD_VARJO;
+        let supported = [(mono, 1), (quad, 4), (stereo, 2)];
+
+        // Stereo is used unless the application prefers something else.
+        assert_eq!(choose_view_configuration(&[], &supported).unwrap(), stereo);
+
+        // The first preference with as many views as the renderer draws wins.
+        assert_eq!(
+            choose_view_configuration(&[quad, mono, stereo], &supported).unwrap(),
+            stereo
+        );
+        assert!(choose_view_configuration(&[mono, quad], &supported).is_err());
+        assert!(choose_view_configuration(&[stereo], &[(mono, 1)]).is_err());
+    }
+
+    #[test]
+    pub fn test_choose_environment_blend_mode() {
+        let opaque = EnvironmentBlendMode::OPAQUE;
+        let additive = EnvironmentBlendMode::ADDITIVE;
+        let alpha_blend = EnvironmentBlendMode::ALPHA_BLEND;
+
+        // A VR headset
+        assert_eq!(choose_environment_blend_mode(opaque, &[opaque]), opaque);
+        assert_eq!(choose_environment_blend_mode(additive, &[opaque]), opaque);
+
+        // An optical see-through headset
+        assert_eq!(
+            choose_environment_blend_mode(additive, &[additive]),
+            additive
+        );
+        assert_eq!(choose_environment_blend_mode(opaque, &[additive]), additive);
+
+        // A video see-through headset
+        let supported = [opaque, alpha_blend];
+        assert_eq!(
+            choose_environment_blend_mode(alpha_blend, &supported),
+            alpha_blend
+        );
+        assert_eq!(choose_environment_blend_mode(additive, &supported), opaque);
+    }
+}
@@ -1,14 +1,19 @@
 use crate::{
     asset_importer::{self, add_model_to_world},
-    components::{GlobalTransform, Info, LocalTransform, Parent, Stage, HMD},
+    components::{hand::Handedness, GlobalTransform, Info, LocalTransform, Parent, Stage, HMD},
     contexts::{
-        render_context::create_pipeline, AudioContext, GuiContext, HapticContext, InputContext,
-        PhysicsContext, RenderContext, VulkanContext, XrContext, XrContextBuilder,
+        render_context::{destroy_pipelines, RenderSettings, Shaders},
+        xr_context::DEFAULT_APPLICATION_NAME,
+        AudioContext, CompositionLayerSettings, CustomActionSet, DisplayColorSpace,
+        FoveationSettings, GamepadContext, GuiContext, HapticContext, InputBindings, InputContext,
+        PhysicsContext, PlayAreaEvent, ReferenceSpace, RenderContext, VulkanContext, XrContext,
+        XrContextBuilder, XrEvent,
     },
-    util::{u8_to_u32, PerformanceTimer},
-    workers::Workers,
-    HothamError, HothamResult, VIEW_TYPE,
+    util::{app_storage_dir, posef_from_affine, u8_to_u32, PerformanceTimer},
+    workers::{ShaderUpdatedMessage, WorkerMessage, Workers},
+    HothamError, HothamResult,
 };
+use ash::vk;
 use hotham_asset_client::AssetUpdatedMessage;
 use openxr as xr;
 
@@ -36,6 +41,27 @@ pub struct EngineBuilder<'a> {
     application_name: Option<&'a str>,
     application_version: Option<u32>,
     openxr_extensions: Option<xr::ExtensionSet>,
+    render_settings: RenderSettings,
+    foveation_settings: FoveationSettings,
+    display_color_space: Option<DisplayColorSpace>,
+    composition_layer_settings: CompositionLayerSettings,
+    hand_tracking: bool,
+    body_tracking: bool,
+    eye_gaze: bool,
+    controller_models: bool,
+    keyboard_tracking: bool,
+    spatial_anchors: bool,
+    scene: bool,
+    scene_colliders: bool,
+    trackers: bool,
+    overlay: bool,
+    environment_blend_mode: Option<xr::EnvironmentBlendMode>,
+    view_configurations: Vec<xr::ViewConfigurationType>,
+    custom_action_sets: Vec<CustomActionSet>,
+    dominant_hand: Option<Handedness>,
+    reference_space: ReferenceSpace,
+    vulkan_validation: bool,
+    desktop_preview: bool,
 }
 
 impl<'a> EngineBuilder<'a> {
@@ -62,15 +88,212 @@ impl<'a> EngineBuilder<'a> {
         self
     }
 
+    /// Set the renderer settings, eg. the number of MSAA samples
+    pub fn render_settings(&mut self, render_settings: RenderSettings) -> &mut Self {
+        self.render_settings = render_settings;
+        self
+    }
+
+    /// Set the Fixed Foveated Rendering settings. These can be changed later with
+    /// [`XrContext::set_foveation_settings`]
+    pub fn foveation_settings(&mut self, foveation_settings: FoveationSettings) -> &mut Self {
+        self.foveation_settings = foveation_settings;
+        self
+    }
+
+    /// Tell the compositor which color space the application's colors are in, eg. `Rec709` for art made on an sRGB
+    /// monitor, so they look the same on every headset. Only used if the runtime supports `XR_FB_color_space`; the
+    /// color spaces it supports can be checked with [`XrContext::supported_display_color_spaces`], and it can be
+    /// changed later with [`XrContext::set_display_color_space`].
+    pub fn display_color_space(&mut self, display_color_space: DisplayColorSpace) -> &mut Self {
+        self.display_color_space = Some(display_color_space);
+        self
+    }
+
+    /// Ask the compositor to super sample or sharpen each frame, eg. to make text easier to read on Quest. Only used if
+    /// the runtime supports `XR_FB_composition_layer_settings`, and can be changed later with
+    /// [`XrContext::composition_layer_settings`].
+    pub fn composition_layer_settings(
+        &mut self,
+        composition_layer_settings: CompositionLayerSettings,
+    ) -> &mut Self {
+        self.composition_layer_settings = composition_layer_settings;
+        self
+    }
+
+    /// Track the joints of the player's hands, if the runtime supports `XR_EXT_hand_tracking`. The joints can be
+    /// read from the [`InputContext`], or from [`HandJoints`](crate::components::HandJoints) components updated
+    /// by the [`hand_tracking_system`](crate::systems::hand_tracking_system).
+    pub fn hand_tracking(&mut self, hand_tracking: bool) -> &mut Self {
+        self.hand_tracking = hand_tracking;
+        self
+    }
+
+    /// Track the joints of the player's body, if the runtime supports `XR_FB_body_tracking`, eg. on Quest. The
+    /// runtime estimates the upper body from where the player's head and hands are. The joints can be read from the
+    /// [`InputContext`], or from [`BodyJoints`](crate::components::BodyJoints) components updated by the
+    /// [`body_tracking_system`](crate::systems::body_tracking_system).
+    pub fn body_tracking(&mut self, body_tracking: bool) -> &mut Self {
+        self.body_tracking = body_tracking;
+        self
+    }
+
+    /// Locate the player's physical keyboard, if the runtime supports `XR_FB_keyboard_tracking`, eg. on Quest. The
+    /// keyboard is reported as the tracker with [`TrackerRole::Keyboard`](crate::contexts::TrackerRole::Keyboard),
+    /// so it can be followed with a [`TrackedDevice`](crate::components::TrackedDevice) and drawn with a
+    /// [`RenderModel`](crate::components::RenderModel), which needs `controller_models` too.
+    pub fn keyboard_tracking(&mut self, keyboard_tracking: bool) -> &mut Self {
+        self.keyboard_tracking = keyboard_tracking;
+        self
+    }
+
+    /// Track where the player is looking, if the runtime supports `XR_EXT_eye_gaze_interaction`, eg. on Quest Pro.
+    /// The gaze can be read from the [`InputContext`], or from a [`GazePointer`](crate::components::GazePointer)
+    /// updated by the [`gaze_pointer_system`](crate::systems::gaze_pointer_system).
+    pub fn eye_gaze(&mut self, eye_gaze: bool) -> &mut Self {
+        self.eye_gaze = eye_gaze;
+        self
+    }
+
+    /// Let the runtime provide models of the player's controllers, if it supports `XR_FB_render_model` or
+    /// `XR_MSFT_controller_model`, and of other devices such as keyboards with `XR_FB_render_model`. They're drawn
+    /// by [`ControllerModel`](crate::components::ControllerModel) and
+    /// [`RenderModel`](crate::components::RenderModel) components, updated by the
+    /// [`controller_models_system`](crate::systems::controller_models_system).
+    pub fn controller_models(&mut self, controller_models: bool) -> &mut Self {
+        self.controller_models = controller_models;
+        self
+    }
+
+    /// Let the application pin content to the real world, and keep it there across runs, if the runtime supports
+    /// `XR_FB_spatial_entity`. Entities are anchored with [`SpatialAnchor`](crate::components::SpatialAnchor)
+    /// components, updated by the [`spatial_anchors_system`](crate::systems::spatial_anchors_system).
+    pub fn spatial_anchors(&mut self, spatial_anchors: bool) -> &mut Self {
+        self.spatial_anchors = spatial_anchors;
+        self
+    }
+
+    /// Load the layout of the player's room - its walls, floor, ceiling and furniture - if the runtime supports
+    /// `XR_FB_scene`. Each part of the room is spawned as an entity with a [`ScenePlane`](crate::components::ScenePlane)
+    /// and/or a [`SceneVolume`](crate::components::SceneVolume) by the
+    /// [`scene_system`](crate::systems::scene_system). On Quest, the application must also request the
+    /// `com.oculus.permission.USE_SCENE` permission in its Android manifest.
+    pub fn scene(&mut self, scene: bool) -> &mut Self {
+        self.scene = scene;
+        self
+    }
+
+    /// Give the parts of the player's room `Collider`s, so content can collide with them. Only used with
+    /// [`EngineBuilder::scene`].
+    pub fn scene_colliders(&mut self, scene_colliders: bool) -> &mut Self {
+        self.scene_colliders = scene_colliders;
+        self
+    }
+
+    /// Track extra devices, such as Vive trackers strapped to the player's body or attached to props, if the runtime
+    /// supports `XR_HTCX_vive_tracker_interaction`. Each tracker is followed by an entity with a
+    /// [`TrackedDevice`](crate::components::TrackedDevice), updated by the
+    /// [`tracked_devices_system`](crate::systems::tracked_devices_system).
+    pub fn trackers(&mut self, trackers: bool) -> &mut Self {
+        self.trackers = trackers;
+        self
+    }
+
+    /// Run as an overlay on top of another application, eg. for a performance HUD or a desktop viewer, if the runtime
+    /// supports `XR_EXTX_overlay`. Only the [`QuadLayer`](crate::contexts::QuadLayer)s in
+    /// [`XrContext::quad_layers`] are shown, so the other application can be seen around them; check
+    /// [`XrContext::is_overlay`] to see whether the runtime allowed it. An
+    /// [`XrEvent::MainSessionVisibilityChanged`] is sent when the other application is shown or hidden.
+    pub fn overlay(&mut self, overlay: bool) -> &mut Self {
+        self.overlay = overlay;
+        self
+    }
+
+    /// Ask the compositor to blend frames with the real world on AR headsets: `ADDITIVE` for optical see-through
+    /// displays, or `ALPHA_BLEND` for video see-through. Falls back to `OPAQUE` if the headset doesn't support it;
+    /// check [`XrContext::environment_blend_mode`] for the mode that was chosen, and
+    /// [`RuntimeCapabilities::environment_blend_modes`](crate::contexts::RuntimeCapabilities::environment_blend_modes)
+    /// for the ones the headset supports. The background is cleared to transparent black whenever it isn't `OPAQUE`.
+    pub fn environment_blend_mode(
+        &mut self,
+        environment_blend_mode: xr::EnvironmentBlendMode,
+    ) -> &mut Self {
+        self.environment_blend_mode = Some(environment_blend_mode);
+        self
+    }
+
+    /// The view configurations the application can render, in order of preference. The first the runtime supports with
+    /// [`VIEW_COUNT`](crate::VIEW_COUNT) views is used, and creating the engine fails if there's none; check [`XrContext::view_configuration_type`] for the one that was chosen. Defaults to
+    /// [`VIEW_TYPE`](crate::VIEW_TYPE).
+    pub fn view_configurations(
+        &mut self,
+        view_configurations: Vec<xr::ViewConfigurationType>,
+    ) -> &mut Self {
+        self.view_configurations = view_configurations;
+        self
+    }
+
+    /// Register the application's own actions, with the controller inputs they should be bound to. Their state can be
+    /// queried each frame from `engine.xr_context.input.custom_actions`, eg:
+    ///
+    /// ```ignore
+    /// let jump = engine
+    ///     .xr_context
+    ///     .input
+    ///     .custom_actions
+    ///     .boolean(&engine.xr_context.session, "jump")?;
+    /// ```
+    ///
+    /// Players can rebind the boolean, float and vector2 actions from inside the application with
+    /// [`Engine::input_bindings`], which is also how they should be read so the player's bindings take effect.
+    pub fn custom_action_sets(&mut self, custom_action_sets: Vec<CustomActionSet>) -> &mut Self {
+        self.custom_action_sets = custom_action_sets;
+        self
+    }
+
+    /// Set which hand the player prefers to use. Defaults to `Right`, and can be changed at runtime with
+    /// [`Engine::dominant_hand`].
+    pub fn dominant_hand(&mut self, dominant_hand: Handedness) -> &mut Self {
+        self.dominant_hand = Some(dominant_hand);
+        self
+    }
+
+    /// Choose which of the runtime's reference spaces is used as the stage, eg. `Local` for seated experiences.
+    /// Defaults to `Stage`. If the runtime doesn't know where the floor is, `Local` is used instead; check
+    /// [`XrContext::reference_space`] to see which was used.
+    pub fn reference_space(&mut self, reference_space: ReferenceSpace) -> &mut Self {
+        self.reference_space = reference_space;
+        self
+    }
+
+    /// Enable the Vulkan validation layers and print their messages. The layers must be installed, eg. with the
+    /// Vulkan SDK on desktop, or by packaging `libVkLayer_khronos_validation.so` with the APK on Android. Validation
+    /// is slow, so only enable it while debugging.
+    pub fn vulkan_validation(&mut self, vulkan_validation: bool) -> &mut Self {
+        self.vulkan_validation = vulkan_validation;
+        self
+    }
+
+    /// Run the application in a window on the desktop instead of on a headset, with a fly camera and keyboard-mapped
+    /// controller buttons. Uses the Hotham simulator, which must be built first with
+    /// `cargo build -p hotham-simulator`. Ignored on Android.
+    pub fn desktop_preview(&mut self, desktop_preview: bool) -> &mut Self {
+        self.desktop_preview = desktop_preview;
+        self
+    }
+
     /// Build the `Engine`
     pub fn build(self) -> Engine {
         #[allow(unused_mut)] // Only Android mutates this.
         let mut resumed = false;
         let should_quit = Arc::new(AtomicBool::from(false));
 
+        #[allow(unused_mut)] // Only Android mutates this.
+        let mut gamepad_context = GamepadContext::new();
+
         // Before we do ANYTHING - we should process android events
         #[cfg(target_os = "android")]
-        process_android_events(&mut resumed, &should_quit);
+        process_android_events(&mut resumed, false, &should_quit, &mut gamepad_context);
 
         // On desktop, register a Ctrl-C handler.
         #[cfg(not(target_os = "android"))]
@@ -79,17 +302,56 @@ impl<'a> EngineBuilder<'a> {
             ctrlc::set_handler(move || should_quit.store(true, Ordering::Relaxed)).unwrap();
         }
 
+        // The player can rebind the application's actions, so they're read through the input bindings.
+        let mut input_bindings = InputBindings::new(
+            &self.custom_action_sets,
+            app_storage_dir(self.application_name.unwrap_or(DEFAULT_APPLICATION_NAME)),
+        );
+
         // Now initialize the engine.
-        let (xr_context, vulkan_context) = XrContextBuilder::new()
+        let (mut xr_context, vulkan_context) = XrContextBuilder::new()
             .application_name(self.application_name)
             .application_version(self.application_version)
             .required_extensions(self.openxr_extensions)
+            .foveation_settings(self.foveation_settings)
+            .foveation(self.render_settings.foveation)
+            .display_color_space(self.display_color_space)
+            .composition_layer_settings(self.composition_layer_settings)
+            .depth_layer(self.render_settings.depth_layer)
+            .color_space(self.render_settings.color_space)
+            .video_recording(self.render_settings.video_recording)
+            .passthrough(self.render_settings.passthrough)
+            .space_warp(self.render_settings.space_warp)
+            .hand_tracking(self.hand_tracking)
+            .body_tracking(self.body_tracking)
+            .eye_gaze(self.eye_gaze)
+            .controller_models(self.controller_models)
+            .keyboard_tracking(self.keyboard_tracking)
+            .spatial_anchors(self.spatial_anchors)
+            .scene(self.scene)
+            .trackers(self.trackers)
+            .overlay(self.overlay)
+            .environment_blend_mode(self.environment_blend_mode)
+            .view_configurations(self.view_configurations)
+            .custom_action_sets(self.custom_action_sets)
+            .reference_space(self.reference_space)
+            .vulkan_validation(self.vulkan_validation)
+            .desktop_preview(self.desktop_preview)
             .build()
             .expect("!!FATAL ERROR - Unable to initialize OpenXR!!");
-        let render_context = RenderContext::new(&vulkan_context, &xr_context)
-            .expect("!!FATAL ERROR - Unable to initialize renderer!");
+        if let Some(scene) = &mut xr_context.scene {
+            scene.colliders = self.scene_colliders;
+        }
+        let render_context =
+            RenderContext::new_with_settings(&vulkan_context, &xr_context, self.render_settings)
+                .expect("!!FATAL ERROR - Unable to initialize renderer!");
         let gui_context = GuiContext::new(&vulkan_context);
 
+        // Apply any bindings the player changed the last time the application ran.
+        if let Err(e) = input_bindings.load() {
+            println!("[HOTHAM_ENGINE] Unable to load the saved input bindings: {e:?}");
+        }
+
         // Initialize the world with our "tracking" entities, the stage and the HMD.
         let mut world = hecs::World::default();
         let (stage_entity, hmd_entity) = create_tracking_entities(&mut world);
@@ -97,6 +359,7 @@ impl<'a> EngineBuilder<'a> {
         Engine {
             world,
             should_quit,
+            exit_requested: false,
             resumed,
             event_data_buffer: Default::default(),
             xr_context,
@@ -106,16 +369,34 @@ impl<'a> EngineBuilder<'a> {
             gui_context,
             haptic_context: Default::default(),
             input_context: Default::default(),
+            input_bindings,
+            dominant_hand: self.dominant_hand.unwrap_or(Handedness::Right),
+            gamepad_context,
             physics_context: Default::default(),
             stage_entity,
             hmd_entity,
             performance_timer: PerformanceTimer::new("Application Tick"),
             recently_updated_assets: Default::default(),
+            inside_play_area: true,
             workers: Workers::new(Default::default()),
+            #[cfg(not(target_os = "android"))]
+            shader_watcher: None,
         }
     }
 }
 
+/// Is the session running, ie. should the frame loop be running? This is the case from when the session is begun
+/// once it's READY, until it's ended once it's STOPPING.
+fn is_session_running(session_state: SessionState) -> bool {
+    matches!(
+        session_state,
+        SessionState::READY
+            | SessionState::SYNCHRONIZED
+            | SessionState::VISIBLE
+            | SessionState::FOCUSED
+    )
+}
+
 fn create_tracking_entities(world: &mut hecs::World) -> (hecs::Entity, hecs::Entity) {
     let stage_entity = world.spawn((
         Stage {},
@@ -136,7 +417,9 @@ fn create_tracking_entities(world: &mut hecs::World) -> (hecs::Entity, hecs::Ent
 /// **IMPORTANT**: make sure you call `update` each tick
 pub struct Engine {
     should_quit: Arc<AtomicBool>,
-    #[allow(dead_code)]
+    /// Have we asked the runtime to end the session, so we can quit?
+    exit_requested: bool,
+    #[allow(dead_code)] // Only Android reads this.
     resumed: bool,
     event_data_buffer: EventDataBuffer,
     /// World
@@ -157,6 +440,13 @@ pub struct Engine {
     pub haptic_context: HapticContext,
     /// Input context
     pub input_context: InputContext,
+    /// The application's custom actions, and the inputs the player has rebound them to
+    pub input_bindings: InputBindings,
+    /// The hand the player prefers to use. Entities with a [`HandPreference`](crate::components::HandPreference)
+    /// follow it when it's changed, as does the haptic feedback from the GUI.
+    pub dominant_hand: Handedness,
+    /// Gamepad context
+    pub gamepad_context: GamepadContext,
     /// Stage entity
     pub stage_entity: hecs::Entity,
     /// HMD entity
@@ -165,8 +455,13 @@ pub struct Engine {
     pub performance_timer: PerformanceTimer,
     /// Files that were hot reloaded this frame
     recently_updated_assets: Vec<AssetUpdatedMessage>,
+    /// Was the player's head inside the play area last tick?
+    inside_play_area: bool,
     /// Workers
     workers: Workers,
+    /// Recompiles shaders when their source changes on disk
+    #[cfg(not(target_os = "android"))]
+    shader_watcher: Option<crate::workers::ShaderWatcher>,
 }
 
 /// The result of calling `update()` on Engine.
@@ -188,28 +483,82 @@ impl Engine {
 
     /// IMPORTANT: Call this function each tick to update the engine's running state with OpenXR and the underlying OS
     pub fn update(&mut self) -> HothamResult<TickData> {
+        self.xr_context.events.clear();
         loop {
             #[cfg(target_os = "android")]
-            process_android_events(&mut self.resumed, &self.should_quit);
-
-            // TODO: We *STILL* don't handle being shut down correctly. Something very odd is going on.
-            // https://github.com/leetvr/hotham/issues/220
+            process_android_events(
+                &mut self.resumed,
+                is_session_running(self.xr_context.session_state),
+                &self.should_quit,
+                &mut self.gamepad_context,
+            );
+
+            // If the session is running, ask the runtime to stop it, so it's ended cleanly before we exit.
             if self.should_quit.load(Ordering::Acquire) {
-                // Show's over
-                println!("[HOTHAM_ENGINE] Hotham is now exiting!");
-                return Err(HothamError::ShuttingDown);
+                if !is_session_running(self.xr_context.session_state) {
+                    // Show's over
+                    println!("[HOTHAM_ENGINE] Hotham is now exiting!");
+                    return Err(HothamError::ShuttingDown);
+                }
+                if !self.exit_requested {
+                    println!("[HOTHAM_ENGINE] Requesting the session to exit..");
+                    self.xr_context.session.request_exit()?;
+                    self.exit_requested = true;
+                }
             }
 
-            let (previous_state, current_state) = {
-                let previous_state = self.xr_context.session_state;
-                let current_state = self.xr_context.poll_xr_event(&mut self.event_data_buffer)?;
-                (previous_state, current_state)
-            };
+            // Handle every event the runtime has sent, along with any state transitions they cause.
+            let previous_state = self.xr_context.session_state;
+            loop {
+                let from = self.xr_context.session_state;
+                if !self.xr_context.poll_xr_event(&mut self.event_data_buffer)? {
+                    break;
+                }
+                let to = self.xr_context.session_state;
+                if to != from {
+                    self.handle_session_state_change(from, to)?;
+                }
+            }
+            let current_state = self.xr_context.session_state;
 
-            // If we're in the FOCUSSED state, process input.
-            if current_state == SessionState::FOCUSED {
+            // The session isn't running, so there are no frames to render until it's READY again, eg. while the app
+            // is in the background on Quest. Keep polling for events until then.
+            if !is_session_running(current_state) {
+                if current_state == previous_state {
+                    sleep(Duration::from_millis(100)); // Sleep to avoid thrashing the CPU
+                }
+                continue;
+            }
+
+            // The player can see the app while it's VISIBLE or FOCUSED, but it only receives input while FOCUSED.
+            if current_state == SessionState::VISIBLE || current_state == SessionState::FOCUSED {
                 self.xr_context.update_views();
-                self.input_context.update(&self.xr_context);
+                if current_state == SessionState::FOCUSED {
+                    if let Some(keyboard_tracking) = &mut self.xr_context.keyboard_tracking {
+                        keyboard_tracking.update()?;
+                    }
+                    let input_modes = self.input_context.input_modes();
+                    self.input_context.update(&self.xr_context);
+                    self.input_context
+                        .gamepad
+                        .update(self.gamepad_context.update());
+
+                    // Let the application know if the player put a controller down or picked one up.
+                    for ((handedness, from), (_, to)) in input_modes
+                        .into_iter()
+                        .zip(self.input_context.input_modes())
+                    {
+                        if from != to {
+                            println!("[HOTHAM_ENGINE] The player's {handedness:?} hand is now using {to:?} input");
+                            self.xr_context.events.push(XrEvent::InputModeChanged {
+                                handedness,
+                                mode: to,
+                            });
+                        }
+                    }
+                } else {
+                    self.input_context.hmd.update(&self.xr_context);
+                }
 
                 // Since the HMD is parented to the Stage, its LocalTransform (ie. its transform with respect to the parent)
                 // is equal to its pose in stage space.
@@ -221,34 +570,15 @@ impl Engine {
                 transform.update_from_affine(&hmd_in_stage);
             }
 
-            // Handle any state transitions, as required.
-            match (previous_state, current_state) {
-                (SessionState::STOPPING, SessionState::IDLE) => {
-                    // Do nothing so we can process further events.
-                    continue;
-                }
-                (_, SessionState::IDLE) => {
-                    sleep(Duration::from_millis(100)); // Sleep to avoid thrashing the CPU
-                    continue;
-                }
-                (SessionState::IDLE, SessionState::READY) => {
-                    self.xr_context.session.begin(VIEW_TYPE)?;
-                }
-                (_, SessionState::EXITING | SessionState::LOSS_PENDING) => {
-                    // Show's over
-                    println!("[HOTHAM_ENGINE] Hotham is now exiting!");
-                    return Err(HothamError::ShuttingDown);
-                }
-                (_, SessionState::STOPPING) => {
-                    self.xr_context.end_session()?;
-                    continue;
-                }
-                _ => {}
-            }
-
             // Check to see if there are any messages from our workers:
             self.check_for_worker_messages();
 
+            if let Some(play_area_event) = self.update_play_area_event() {
+                self.xr_context
+                    .events
+                    .push(XrEvent::PlayArea(play_area_event));
+            }
+
             let vulkan_context = &self.vulkan_context;
             let render_context = &mut self.render_context;
 
@@ -269,6 +599,30 @@ impl Engine {
         }
     }
 
+    fn handle_session_state_change(
+        &mut self,
+        from: SessionState,
+        to: SessionState,
+    ) -> HothamResult<()> {
+        match to {
+            SessionState::READY => self.xr_context.begin_session()?,
+            SessionState::STOPPING => self.xr_context.end_session()?,
+            SessionState::EXITING | SessionState::LOSS_PENDING => {
+                // Show's over
+                println!("[HOTHAM_ENGINE] Hotham is now exiting!");
+                return Err(HothamError::ShuttingDown);
+            }
+            _ => {}
+        }
+
+        // The controllers only send input while the session is focused, so don't leave buttons held down
+        // while it isn't, eg. while the system menu is open.
+        if from == SessionState::FOCUSED {
+            self.input_context.clear();
+        }
+        Ok(())
+    }
+
     /// Call this after update
     pub fn finish(&mut self) -> xr::Result<()> {
         self.performance_timer.end();
@@ -278,6 +632,14 @@ impl Engine {
         if self.xr_context.frame_state.should_render {
             render_context.end_frame(vulkan_context);
         }
+
+        // Let the compositor know how far the stage moved this frame, so it isn't mistaken for motion.
+        if let (Some(space_warp), Some(motion_vectors)) = (
+            &mut self.xr_context.space_warp,
+            &render_context.motion_vectors,
+        ) {
+            space_warp.app_space_delta_pose = posef_from_affine(motion_vectors.stage_delta);
+        }
         self.xr_context.end_frame()
     }
 
@@ -286,16 +648,53 @@ impl Engine {
         self.workers = Workers::new(asset_list);
     }
 
+    /// Watch a directory of GLSL shader sources (eg. `hotham/src/shaders`) and hot reload the PBR
+    /// pipelines whenever `pbr.vert`, `pbr.frag` or any of the files they include change.
+    /// Shaders that fail to compile are reported and the previous pipelines are kept.
+    /// Only available on desktop builds.
+    #[cfg(not(target_os = "android"))]
+    pub fn watch_shaders(&mut self, shader_dir: impl AsRef<std::path::Path>) {
+        match crate::workers::ShaderWatcher::new(shader_dir.as_ref()) {
+            Ok(watcher) => self.shader_watcher = Some(watcher),
+            Err(e) => println!("[HOTHAM_ENGINE] Unable to watch shaders: {e:?}"),
+        }
+    }
+
     /// Get a list of assets updated this frame.
     pub fn get_updated_assets(&self) -> &Vec<AssetUpdatedMessage> {
         &self.recently_updated_assets
     }
 
+    /// Check whether the player's head has crossed the edge of the play area since the last tick
+    fn update_play_area_event(&mut self) -> Option<PlayAreaEvent> {
+        let play_area = self.xr_context.play_area()?;
+        let hmd_in_runtime_stage =
+            self.xr_context.runtime_stage_from_stage() * self.input_context.hmd.hmd_in_stage();
+        let inside_play_area = play_area.contains(hmd_in_runtime_stage.translation.into());
+        if inside_play_area == self.inside_play_area {
+            return None;
+        }
+
+        self.inside_play_area = inside_play_area;
+        Some(if inside_play_area {
+            PlayAreaEvent::Entered
+        } else {
+            PlayAreaEvent::Left
+        })
+    }
+
     fn check_for_worker_messages(&mut self) {
         self.recently_updated_assets.clear();
-        for message in self.workers.receiver.try_iter() {
+        #[allow(unused_mut)] // Only desktop builds add shader messages.
+        let mut messages: Vec<WorkerMessage> = self.workers.receiver.try_iter().collect();
+        #[cfg(not(target_os = "android"))]
+        if let Some(shader_watcher) = &self.shader_watcher {
+            messages.extend(shader_watcher.receiver.try_iter());
+        }
+
+        for message in messages {
             match message {
-                crate::workers::WorkerMessage::AssetUpdated(asset_updated) => {
+                WorkerMessage::AssetUpdated(asset_updated) => {
                     let tick = Instant::now();
                     let vulkan_context = &self.vulkan_context;
                     let render_context = &mut self.render_context;
@@ -310,12 +709,22 @@ impl Engine {
                             asset_updated.asset_data.clone(),
                         ),
                         ("hotham/src/shaders/pbr.frag.spv", _)
-                        | ("hotham/src/shaders/pbr.vert.spv", _) => update_shader(
-                            vulkan_context,
-                            render_context,
-                            &asset_updated.asset_id,
-                            asset_updated.asset_data.clone(),
-                        ),
+                        | ("hotham/src/shaders/pbr.vert.spv", _) => {
+                            let stage = if asset_updated.asset_id.contains("vert") {
+                                vk::ShaderStageFlags::VERTEX
+                            } else {
+                                vk::ShaderStageFlags::FRAGMENT
+                            };
+                            update_shader(
+                                vulkan_context,
+                                render_context,
+                                ShaderUpdatedMessage {
+                                    shader_name: asset_updated.asset_id.clone(),
+                                    stage,
+                                    spirv: u8_to_u32(asset_updated.asset_data.clone()),
+                                },
+                            )
+                        }
                         _ => {}
                     }
                     self.recently_updated_assets.push(asset_updated);
@@ -324,7 +733,19 @@ impl Engine {
                         Instant::now().duration_since(tick).as_secs_f32()
                     );
                 }
-                crate::workers::WorkerMessage::Error(e) => {
+                WorkerMessage::ShaderUpdated(shader_updated) => {
+                    let tick = Instant::now();
+                    update_shader(
+                        &self.vulkan_context,
+                        &mut self.render_context,
+                        shader_updated,
+                    );
+                    println!(
+                        "[HOTHAM_ASSET_HOT_RELOAD] Shader reload took {:.2} seconds",
+                        Instant::now().duration_since(tick).as_secs_f32()
+                    );
+                }
+                WorkerMessage::Error(e) => {
                     panic!("[HOTHAM_ENGINE] Worker encountered error: {e:?}");
                 }
             }
@@ -365,33 +786,63 @@ fn update_models(
 fn update_shader(
     vulkan_context: &VulkanContext,
     render_context: &mut RenderContext,
-    shader_name: &str,
-    asset_data: Arc<Vec<u8>>,
+    shader_updated: ShaderUpdatedMessage,
 ) {
+    let shader_name = &shader_updated.shader_name;
     println!(
         "[HOTHAM_ASSET_HOT_RELOAD] STAND BACK - HOT RELOADING SHADER {shader_name} MOTHERFUCKER"
     );
 
-    {
-        let shaders = &mut render_context.shaders;
-        let shader = u8_to_u32(asset_data);
-        if shader_name.contains("vert") {
-            shaders.vertex_shader = shader;
-        } else {
-            shaders.fragment_shader = shader;
+    let stage = shader_updated.stage;
+    let previous_spirv = std::mem::replace(
+        shader_for_stage(&mut render_context.shaders, stage),
+        shader_updated.spirv,
+    );
+
+    // Create every pipeline that uses the shaders before replacing any, so that SPIR-V which compiles but can't be
+    // made into a pipeline leaves the previous version running rather than taking the session down.
+    let mut created = Vec::new();
+    let result = (|| -> anyhow::Result<_> {
+        let shader_pipelines = render_context.create_shader_pipelines(vulkan_context)?;
+        created.extend(shader_pipelines);
+
+        // Every render target draws with its own copies of the PBR pipelines.
+        let mut render_target_pipelines = Vec::new();
+        for (id, render_target) in render_context.render_targets.iter() {
+            let pipelines = render_target.create_pipelines(
+                vulkan_context,
+                render_context.pipeline_layout,
+                &render_context.shaders,
+            )?;
+            created.extend(pipelines);
+            render_target_pipelines.push((id, pipelines));
+        }
+
+        unsafe { vulkan_context.device.device_wait_idle() }?;
+        Ok((shader_pipelines, render_target_pipelines))
+    })();
+
+    match result {
+        Ok((shader_pipelines, render_target_pipelines)) => unsafe {
+            let device = &vulkan_context.device;
+            render_context.replace_shader_pipelines(device, shader_pipelines);
+            for (id, pipelines) in render_target_pipelines {
+                render_context.render_targets[id].replace_pipelines(device, pipelines);
+            }
+        },
+        Err(e) => {
+            println!("[HOTHAM_ASSET_HOT_RELOAD] ERROR - Unable to reload {shader_name}, keeping the previous version: {e:?}");
+            unsafe { destroy_pipelines(&vulkan_context.device, &created) };
+            *shader_for_stage(&mut render_context.shaders, stage) = previous_spirv;
         }
     }
+}
 
-    unsafe {
-        vulkan_context.device.device_wait_idle().unwrap();
-        render_context.pipeline = create_pipeline(
-            vulkan_context,
-            render_context.pipeline_layout,
-            &render_context.render_area(),
-            render_context.render_pass,
-            &render_context.shaders,
-        )
-        .unwrap();
+fn shader_for_stage(shaders: &mut Shaders, stage: vk::ShaderStageFlags) -> &mut Vec<u32> {
+    if stage == vk::ShaderStageFlags::VERTEX {
+        &mut shaders.vertex_shader
+    } else {
+        &mut shaders.fragment_shader
     }
 }
 
@@ -414,9 +865,19 @@ impl Default for Engine {
     }
 }
 
+/// Process the activity's lifecycle events, eg. when the player takes the headset off or opens the home menu.
+///
+/// While the activity is paused and the session isn't running, this blocks until there's another event, as there's
+/// nothing to do. While the session is running, the frame loop has to keep going so the runtime can stop the session
+/// cleanly, even if the activity has been paused.
 #[cfg(target_os = "android")]
-pub fn process_android_events(resumed: &mut bool, should_quit: &Arc<AtomicBool>) {
-    while let Some(event) = poll_android_events(*resumed) {
+pub fn process_android_events(
+    resumed: &mut bool,
+    session_running: bool,
+    should_quit: &Arc<AtomicBool>,
+    gamepad_context: &mut GamepadContext,
+) {
+    while let Some(event) = poll_android_events(*resumed || session_running) {
         println!("[HOTHAM_ANDROID] Received event {:?}", event);
         match event {
             ndk_glue::Event::Resume => *resumed = true,
@@ -426,6 +887,10 @@ pub fn process_android_events(resumed: &mut bool, should_quit: &Arc<AtomicBool>)
                 return;
             }
             ndk_glue::Event::Pause => *resumed = false,
+            // The runtime renders to its own surface, so losing the activity's window doesn't affect us.
+            ndk_glue::Event::WindowDestroyed => {
+                println!("[HOTHAM_ANDROID] The window was destroyed, carrying on without it")
+            }
             _ => {}
         }
     }
@@ -433,18 +898,19 @@ pub fn process_android_events(resumed: &mut bool, should_quit: &Arc<AtomicBool>)
     if let Some(ref input_queue) = *ndk_glue::input_queue() {
         while let Some(event) = input_queue.get_event() {
             if let Some(event) = input_queue.pre_dispatch(event) {
-                input_queue.finish_event(event, false);
+                let handled = gamepad_context.handle_android_event(&event);
+                input_queue.finish_event(event, handled);
             }
         }
     }
 }
 
 #[cfg(target_os = "android")]
-pub fn poll_android_events(resumed: bool) -> Option<ndk_glue::Event> {
+pub fn poll_android_events(non_blocking: bool) -> Option<ndk_glue::Event> {
     use ndk::looper::{Poll, ThreadLooper};
 
     let looper = ThreadLooper::for_thread().unwrap();
-    let timeout = if resumed {
+    let timeout = if non_blocking {
         ANDROID_LOOPER_NONBLOCKING_TIMEOUT
     } else {
         ANDROID_LOOPER_BLOCKING_TIMEOUT
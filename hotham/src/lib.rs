@@ -51,15 +51,26 @@ pub type HothamResult<T> = std::result::Result<T, HothamError>;
 pub const COLOR_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
 /// Format used for depth textures
 pub const DEPTH_FORMAT: vk::Format = vk::Format::D32_SFLOAT;
+/// Format used for the depth buffer of the PBR render pass. The stencil is used to draw outlines around
+/// [`Highlighted`](crate::components::Highlighted) meshes.
+pub const DEPTH_STENCIL_FORMAT: vk::Format = vk::Format::D32_SFLOAT_S8_UINT;
 
 /// Number of views
 pub const VIEW_COUNT: u32 = 2;
 
+/// View mask used by every render pass that draws to both eyes.
+///
+/// Both eyes are rendered in a single pass with `VK_KHR_multiview`: each draw call is broadcast to every layer of
+/// the swapchain image, and shaders pick the camera for the current eye with `gl_ViewIndex`.
+pub const VIEW_MASK: u32 = !(!0 << VIEW_COUNT);
+
 /// Swapchain length
 pub const SWAPCHAIN_LENGTH: usize = 3;
 
-/// OpenXR view type
+/// OpenXR view type used unless the application prefers others with [`EngineBuilder::view_configurations`].
 pub const VIEW_TYPE: xr::ViewConfigurationType = xr::ViewConfigurationType::PRIMARY_STEREO;
 
-/// OpenXR blend mode
+/// OpenXR blend mode used unless the application asks for another with
+/// [`EngineBuilder::environment_blend_mode`] and the headset supports it. See
+/// [`XrContext::environment_blend_mode`](contexts::XrContext::environment_blend_mode).
 pub const BLEND_MODE: xr::EnvironmentBlendMode = xr::EnvironmentBlendMode::OPAQUE;
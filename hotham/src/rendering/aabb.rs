@@ -0,0 +1,117 @@
+use glam::{Affine3A, Mat4, Vec3, Vec4};
+
+/// An axis aligned bounding box
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    /// The corner of the box with the smallest coordinates
+    pub min: Vec3,
+    /// The corner of the box with the largest coordinates
+    pub max: Vec3,
+}
+
+impl Aabb {
+    /// Create a new bounding box from its corners
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Create a bounding box that contains a bounding sphere, stored as (center, radius)
+    pub fn from_sphere(sphere: Vec4) -> Self {
+        let center = sphere.truncate();
+        let radius = Vec3::splat(sphere.w);
+        Self::new(center - radius, center + radius)
+    }
+
+    /// The center of the box
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Half the size of the box along each axis
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// The smallest box containing both `self` and `other`
+    pub fn union(&self, other: &Aabb) -> Self {
+        Self::new(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Grow the box by `amount` in every direction
+    pub fn expanded(&self, amount: f32) -> Self {
+        let amount = Vec3::splat(amount);
+        Self::new(self.min - amount, self.max + amount)
+    }
+
+    /// The smallest axis aligned box containing this box after it has been transformed by `transform`
+    pub fn transformed(&self, transform: &Affine3A) -> Self {
+        // Based on "Transforming Axis-Aligned Bounding Boxes" by Jim Arvo, Graphics Gems 1990
+        let center = transform.transform_point3(self.center());
+        let half_extents = self.half_extents();
+        let matrix = transform.matrix3;
+        let half_extents = matrix.x_axis.abs() * half_extents.x
+            + matrix.y_axis.abs() * half_extents.y
+            + matrix.z_axis.abs() * half_extents.z;
+        Self::new(
+            center - Vec3::from(half_extents),
+            center + Vec3::from(half_extents),
+        )
+    }
+
+    /// Returns true if the box is entirely on the outside of any of the four planes, stored one per row
+    /// as returned by `extract_planes_from_frustum`.
+    pub fn is_outside_planes(&self, planes: &Mat4) -> bool {
+        let center = self.center();
+        let half_extents = self.half_extents();
+        (0..4).any(|i| {
+            let plane = planes.row(i);
+            let normal = plane.truncate();
+            // The distance from the center to the corner furthest along the normal
+            let radius = half_extents.dot(normal.abs());
+            normal.dot(center) + plane.w + radius < 0.
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::camera::extract_planes_from_frustum;
+    use approx::assert_relative_eq;
+    use glam::Quat;
+
+    #[test]
+    pub fn test_transformed() {
+        let aabb = Aabb::new(Vec3::new(-1., -2., -3.), Vec3::new(1., 2., 3.));
+
+        let translated = aabb.transformed(&Affine3A::from_translation(Vec3::X));
+        assert_relative_eq!(translated.min, Vec3::new(0., -2., -3.));
+        assert_relative_eq!(translated.max, Vec3::new(2., 2., 3.));
+
+        // A quarter turn around Y swaps the X and Z extents.
+        let rotated = aabb.transformed(&Affine3A::from_quat(Quat::from_rotation_y(
+            std::f32::consts::FRAC_PI_2,
+        )));
+        assert_relative_eq!(rotated.min, Vec3::new(-3., -2., -1.), epsilon = 0.0001);
+        assert_relative_eq!(rotated.max, Vec3::new(3., 2., 1.), epsilon = 0.0001);
+
+        let scaled = aabb.transformed(&Affine3A::from_scale(Vec3::splat(2.)));
+        assert_relative_eq!(scaled.max, Vec3::new(2., 4., 6.));
+    }
+
+    #[test]
+    pub fn test_is_outside_planes() {
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1., 0.05, 100.);
+        let planes = extract_planes_from_frustum(&projection);
+
+        let unit = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+        let in_front = unit.transformed(&Affine3A::from_translation(Vec3::NEG_Z * 5.));
+        let off_to_the_side =
+            unit.transformed(&Affine3A::from_translation(Vec3::new(10., 0., -5.)));
+        let on_the_edge = unit.transformed(&Affine3A::from_translation(Vec3::new(5., 0., -5.)));
+
+        assert!(!in_front.is_outside_planes(&planes));
+        assert!(off_to_the_side.is_outside_planes(&planes));
+        assert!(!on_the_edge.is_outside_planes(&planes));
+    }
+}
@@ -0,0 +1,860 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::{
+        render_context::{create_pipeline, create_push_constant, create_shader, Shaders},
+        VulkanContext,
+    },
+    rendering::image::Image,
+    DEPTH_FORMAT, VIEW_COUNT, VIEW_MASK,
+};
+
+static BLOOM_FRAG: &[u32] = include_glsl!("src/shaders/bloom.frag", target: vulkan1_1);
+static BLOOM_COMPUTE: &[u32] = include_glsl!("src/shaders/bloom.comp", target: vulkan1_1);
+static COMPOSITE_VERT: &[u32] =
+    include_glsl!("src/shaders/bloom_composite.vert", target: vulkan1_1);
+static COMPOSITE_FRAG: &[u32] =
+    include_glsl!("src/shaders/bloom_composite.frag", target: vulkan1_1);
+
+/// Format of the bloom image. Emission is stored unclamped so that it can be thresholded.
+pub const BLOOM_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+
+/// The number of mip levels in the bloom chain. Each level is half the size of the previous one.
+pub const BLOOM_MIP_COUNT: u32 = 5;
+
+/// The mip level that is blended on top of the scene once the chain has been upsampled.
+const COMPOSITE_MIP: u32 = 1;
+
+/// Must match `local_size_x` and `local_size_y` in bloom.comp
+const WORKGROUP_SIZE: u32 = 8;
+
+// Must match the modes in bloom.comp
+const MODE_PREFILTER: u32 = 0;
+const MODE_DOWNSAMPLE: u32 = 1;
+const MODE_UPSAMPLE: u32 = 2;
+
+static BLOOM_CLEAR_VALUES: [vk::ClearValue; 2] = [
+    vk::ClearValue {
+        color: vk::ClearColorValue {
+            float32: [0.0, 0.0, 0.0, 0.0],
+        },
+    },
+    vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+            depth: 0.0,
+            stencil: 0,
+        },
+    },
+];
+
+/// Settings for bloom: the glow around brightly emissive surfaces.
+///
+/// Bloom is driven by the emissive factor and emissive texture of each [`Material`](super::material::Material).
+/// The emission of the scene is rendered at half resolution, anything brighter than `threshold` is blurred by
+/// a downsample/upsample chain and the result is added on top of the scene.
+#[derive(Debug, Clone)]
+pub struct BloomSettings {
+    /// Whether or not bloom is rendered at all. The GPU resources are created the first time bloom is enabled.
+    /// Bloom draws every visible mesh a second time to render its emission, so this roughly doubles the number of
+    /// draw calls each frame. Disabled again if the resources can't be created.
+    pub enabled: bool,
+    /// Emission below this value does not bloom
+    pub threshold: f32,
+    /// How strongly the blurred emission is added to the scene
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 0.8,
+            intensity: 0.5,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct BloomComputeParams {
+    mode: u32,
+    threshold: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BloomDispatch {
+    descriptor_set: vk::DescriptorSet,
+    mode: u32,
+    extent: vk::Extent2D,
+}
+
+/// All the GPU resources required to render bloom.
+pub struct Bloom {
+    /// The emission of the scene, with one mip level per step of the bloom chain
+    pub image: Image,
+    /// Depth buffer used while rendering emission, so that objects occlude the glow behind them
+    pub depth_image: Image,
+    /// One view per mip level of `image`
+    pub mip_views: Vec<vk::ImageView>,
+    /// Linear sampler used to read from the bloom chain
+    pub sampler: vk::Sampler,
+    /// Render pass used to render the emission of the scene into the first mip level of `image`
+    pub render_pass: vk::RenderPass,
+    /// Framebuffer wrapping the first mip level of `image` and `depth_image`
+    pub framebuffer: vk::Framebuffer,
+    /// The size of the first mip level of `image`
+    pub render_area: vk::Rect2D,
+    /// Pipeline used to render emission. Shares its layout with the PBR pipeline.
+    pub pipeline: vk::Pipeline,
+    /// Layout shared by the downsample/upsample and composite descriptor sets
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    /// Pool the bloom descriptor sets are allocated from
+    pub descriptor_pool: vk::DescriptorPool,
+    /// Pipeline layout for the downsample/upsample chain
+    pub compute_pipeline_layout: vk::PipelineLayout,
+    /// Compute pipeline that runs the downsample/upsample chain
+    pub compute_pipeline: vk::Pipeline,
+    /// Pipeline layout used to blend bloom on top of the scene
+    pub composite_pipeline_layout: vk::PipelineLayout,
+    /// Pipeline used to blend bloom on top of the scene, inside the PBR render pass
+    pub composite_pipeline: vk::Pipeline,
+    /// Descriptor set used by the composite pipeline
+    pub composite_descriptor_set: vk::DescriptorSet,
+    dispatches: Vec<BloomDispatch>,
+}
+
+impl Bloom {
+    /// Create the bloom resources for a PBR render pass covering `render_area`.
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        pipeline_layout: vk::PipelineLayout,
+        pbr_render_pass: vk::RenderPass,
+        pbr_render_area: vk::Rect2D,
+        vertex_shader: &[u32],
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let extent = vk::Extent2D {
+            width: (pbr_render_area.extent.width / 2).max(1),
+            height: (pbr_render_area.extent.height / 2).max(1),
+        };
+        let render_area = vk::Rect2D {
+            extent,
+            ..Default::default()
+        };
+
+        let image = vulkan_context.create_image(
+            BLOOM_FORMAT,
+            &extent,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT
+                | vk::ImageUsageFlags::SAMPLED
+                | vk::ImageUsageFlags::STORAGE,
+            VIEW_COUNT,
+            BLOOM_MIP_COUNT,
+        )?;
+        vulkan_context.set_debug_name(vk::ObjectType::IMAGE, image.handle.as_raw(), "Bloom")?;
+
+        let depth_image = vulkan_context.create_image(
+            DEPTH_FORMAT,
+            &extent,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            VIEW_COUNT,
+            1,
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::IMAGE,
+            depth_image.handle.as_raw(),
+            "Bloom Depth",
+        )?;
+
+        let mip_views = (0..BLOOM_MIP_COUNT)
+            .map(|mip| create_mip_view(device, &image, mip))
+            .collect::<Result<Vec<_>>>()?;
+
+        let sampler = create_bloom_sampler(vulkan_context)?;
+        let render_pass = create_bloom_render_pass(vulkan_context)?;
+
+        let attachments = [mip_views[0], depth_image.view];
+        let framebuffer = unsafe {
+            device.create_framebuffer(
+                &vk::FramebufferCreateInfo::builder()
+                    .render_pass(render_pass)
+                    .attachments(&attachments)
+                    .width(extent.width)
+                    .height(extent.height)
+                    .layers(1),
+                None,
+            )
+        }?;
+
+        // Emission is rendered with the PBR vertex shader and material push constants, so we can share its layout.
+        let shaders = Shaders::new(vertex_shader.to_vec(), BLOOM_FRAG.to_vec(), Vec::new());
+        let pipeline = create_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            &render_area,
+            render_pass,
+            &shaders,
+            vk::SampleCountFlags::TYPE_1,
+            vk::CullModeFlags::BACK,
+        )?;
+
+        let descriptor_set_layout = create_bloom_descriptor_set_layout(device)?;
+
+        // One set per step of the chain, plus one for the composite pass.
+        let set_count = 2 * (BLOOM_MIP_COUNT - 1) - COMPOSITE_MIP + 1;
+        let pool_sizes = [
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: set_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_IMAGE,
+                descriptor_count: set_count,
+            },
+        ];
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(&pool_sizes)
+                    .max_sets(set_count),
+                None,
+            )
+        }?;
+        let set_layouts = vec![descriptor_set_layout; set_count as usize];
+        let descriptor_sets = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&set_layouts),
+            )
+        }?;
+
+        // Build the chain: prefilter and downsample all the way to the smallest mip, then upsample back up again.
+        let mip_extent = |mip: u32| vk::Extent2D {
+            width: (extent.width >> mip).max(1),
+            height: (extent.height >> mip).max(1),
+        };
+        let mut steps = Vec::new();
+        for mip in 1..BLOOM_MIP_COUNT {
+            let mode = if mip == 1 {
+                MODE_PREFILTER
+            } else {
+                MODE_DOWNSAMPLE
+            };
+            steps.push((mip - 1, mip, mode));
+        }
+        for mip in (COMPOSITE_MIP..BLOOM_MIP_COUNT - 1).rev() {
+            steps.push((mip + 1, mip, MODE_UPSAMPLE));
+        }
+
+        let mut dispatches = Vec::with_capacity(steps.len());
+        for ((source, destination, mode), descriptor_set) in
+            steps.into_iter().zip(descriptor_sets.iter().copied())
+        {
+            unsafe {
+                write_bloom_descriptors(
+                    device,
+                    descriptor_set,
+                    sampler,
+                    mip_views[source as usize],
+                    Some(mip_views[destination as usize]),
+                );
+            }
+            dispatches.push(BloomDispatch {
+                descriptor_set,
+                mode,
+                extent: mip_extent(destination),
+            });
+        }
+
+        let composite_descriptor_set = *descriptor_sets.last().unwrap();
+        unsafe {
+            write_bloom_descriptors(
+                device,
+                composite_descriptor_set,
+                sampler,
+                mip_views[COMPOSITE_MIP as usize],
+                None,
+            );
+        }
+
+        let (compute_pipeline, compute_pipeline_layout) =
+            create_bloom_compute_pipeline(vulkan_context, descriptor_set_layout)?;
+        let (composite_pipeline, composite_pipeline_layout) = create_composite_pipeline(
+            vulkan_context,
+            descriptor_set_layout,
+            pbr_render_pass,
+            &pbr_render_area,
+            msaa_samples,
+        )?;
+
+        // The chain is read and written in the GENERAL layout, so transition every mip level into it once.
+        let command_buffer = vulkan_context.begin_single_time_commands();
+        unsafe {
+            let barrier = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::UNDEFINED)
+                .new_layout(vk::ImageLayout::GENERAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(image.handle)
+                .subresource_range(whole_image_range())
+                .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&barrier),
+            );
+        }
+        vulkan_context.end_single_time_commands(command_buffer);
+
+        let debug_names = [
+            (
+                vk::ObjectType::RENDER_PASS,
+                render_pass.as_raw(),
+                "Bloom Render Pass",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                pipeline.as_raw(),
+                "Bloom Emission Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                compute_pipeline.as_raw(),
+                "Bloom Blur Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                composite_pipeline.as_raw(),
+                "Bloom Composite Pipeline",
+            ),
+        ];
+        for (object_type, handle, name) in debug_names {
+            vulkan_context.set_debug_name(object_type, handle, name)?;
+        }
+
+        Ok(Self {
+            image,
+            depth_image,
+            mip_views,
+            sampler,
+            render_pass,
+            framebuffer,
+            render_area,
+            pipeline,
+            descriptor_set_layout,
+            descriptor_pool,
+            compute_pipeline_layout,
+            compute_pipeline,
+            composite_pipeline_layout,
+            composite_pipeline,
+            composite_descriptor_set,
+            dispatches,
+        })
+    }
+
+    /// Begin the render pass that renders emission into the bloom image.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state and not inside another render pass.
+    pub(crate) unsafe fn begin_render_pass(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+    ) {
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffer)
+            .render_area(self.render_area)
+            .clear_values(&BLOOM_CLEAR_VALUES);
+
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_begin_info,
+            vk::SubpassContents::INLINE,
+        );
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+    }
+
+    /// Run the downsample/upsample chain over the emission rendered by the bloom render pass.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state and not inside a render pass.
+    pub(crate) unsafe fn blur(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        settings: &BloomSettings,
+    ) {
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::COMPUTE,
+            self.compute_pipeline,
+        );
+
+        for dispatch in &self.dispatches {
+            let params = BloomComputeParams {
+                mode: dispatch.mode,
+                threshold: settings.threshold,
+            };
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.compute_pipeline_layout,
+                0,
+                slice_from_ref(&dispatch.descriptor_set),
+                &[],
+            );
+            device.cmd_push_constants(
+                command_buffer,
+                self.compute_pipeline_layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                create_push_constant(&params),
+            );
+            device.cmd_dispatch(
+                command_buffer,
+                (dispatch.extent.width + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                (dispatch.extent.height + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                VIEW_COUNT,
+            );
+
+            // Each step reads the result of the previous one.
+            self.barrier(
+                device,
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+            );
+        }
+
+        // Make sure the chain has finished before the composite pass reads from it.
+        self.barrier(
+            device,
+            command_buffer,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+        );
+    }
+
+    /// Blend bloom on top of the scene.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be inside the PBR render pass.
+    pub(crate) unsafe fn composite(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        settings: &BloomSettings,
+    ) {
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.composite_pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.composite_pipeline_layout,
+            0,
+            slice_from_ref(&self.composite_descriptor_set),
+            &[],
+        );
+        device.cmd_push_constants(
+            command_buffer,
+            self.composite_pipeline_layout,
+            vk::ShaderStageFlags::FRAGMENT,
+            0,
+            create_push_constant(&settings.intensity),
+        );
+        device.cmd_draw(command_buffer, 3, 1, 0, 0);
+    }
+
+    unsafe fn barrier(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        src_stage_mask: vk::PipelineStageFlags,
+        dst_stage_mask: vk::PipelineStageFlags,
+    ) {
+        let barrier = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::GENERAL)
+            .new_layout(vk::ImageLayout::GENERAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(self.image.handle)
+            .subresource_range(whole_image_range())
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            src_stage_mask,
+            dst_stage_mask,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            slice_from_ref(&barrier),
+        );
+    }
+}
+
+fn whole_image_range() -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level: 0,
+        level_count: BLOOM_MIP_COUNT,
+        base_array_layer: 0,
+        layer_count: VIEW_COUNT,
+    }
+}
+
+fn create_mip_view(device: &ash::Device, image: &Image, mip: u32) -> Result<vk::ImageView> {
+    let create_info = vk::ImageViewCreateInfo::builder()
+        .view_type(vk::ImageViewType::TYPE_2D_ARRAY)
+        .format(image.format)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: mip,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: VIEW_COUNT,
+        })
+        .image(image.handle);
+
+    unsafe { device.create_image_view(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_bloom_sampler(vulkan_context: &VulkanContext) -> Result<vk::Sampler> {
+    let create_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::LINEAR)
+        .min_filter(vk::Filter::LINEAR)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+        .min_lod(0.0)
+        .max_lod(0.0);
+
+    unsafe { vulkan_context.device.create_sampler(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_bloom_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass> {
+    // The color attachment is left in GENERAL, ready for the compute chain.
+    let color_attachment = vk::AttachmentDescription::builder()
+        .format(BLOOM_FORMAT)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::GENERAL)
+        .build();
+
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(DEPTH_FORMAT)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+        .build();
+
+    let attachments = [color_attachment, depth_attachment];
+
+    let color_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .build();
+
+    let depth_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(slice_from_ref(&color_attachment_reference))
+        .depth_stencil_attachment(&depth_attachment_reference);
+
+    // Make sure the previous frame has finished reading the bloom image before we write to it,
+    // and that our writes are finished before the compute chain reads from it.
+    let dependencies = [
+        vk::SubpassDependency::builder()
+            .src_subpass(vk::SUBPASS_EXTERNAL)
+            .dst_subpass(0)
+            .src_stage_mask(
+                vk::PipelineStageFlags::COMPUTE_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+            )
+            .src_access_mask(vk::AccessFlags::SHADER_READ)
+            .dst_stage_mask(
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                    | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            )
+            .dst_access_mask(
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            )
+            .build(),
+        vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags::COMPUTE_SHADER)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+            .build(),
+    ];
+
+    let view_masks = [VIEW_MASK];
+    let mut multiview = vk::RenderPassMultiviewCreateInfo::builder()
+        .view_masks(&view_masks)
+        .correlation_masks(&view_masks);
+
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(&attachments)
+        .subpasses(slice_from_ref(&subpass))
+        .dependencies(&dependencies)
+        .push_next(&mut multiview);
+
+    unsafe { vulkan_context.device.create_render_pass(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_bloom_descriptor_set_layout(device: &ash::Device) -> Result<vk::DescriptorSetLayout> {
+    let bindings = [
+        // Source
+        vk::DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE | vk::ShaderStageFlags::FRAGMENT,
+            ..Default::default()
+        },
+        // Destination, unused by the composite pass
+        vk::DescriptorSetLayoutBinding {
+            binding: 1,
+            descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        },
+    ];
+
+    unsafe {
+        device.create_descriptor_set_layout(
+            &vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings),
+            None,
+        )
+    }
+    .map_err(Into::into)
+}
+
+unsafe fn write_bloom_descriptors(
+    device: &ash::Device,
+    descriptor_set: vk::DescriptorSet,
+    sampler: vk::Sampler,
+    source: vk::ImageView,
+    destination: Option<vk::ImageView>,
+) {
+    let source_info = vk::DescriptorImageInfo {
+        sampler,
+        image_view: source,
+        image_layout: vk::ImageLayout::GENERAL,
+    };
+    let destination_info = vk::DescriptorImageInfo {
+        sampler: vk::Sampler::null(),
+        image_view: destination.unwrap_or_default(),
+        image_layout: vk::ImageLayout::GENERAL,
+    };
+
+    let mut writes = vec![vk::WriteDescriptorSet::builder()
+        .dst_set(descriptor_set)
+        .dst_binding(0)
+        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+        .image_info(slice_from_ref(&source_info))
+        .build()];
+
+    if destination.is_some() {
+        writes.push(
+            vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(1)
+                .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+                .image_info(slice_from_ref(&destination_info))
+                .build(),
+        );
+    }
+
+    device.update_descriptor_sets(&writes, &[]);
+}
+
+fn create_bloom_compute_pipeline(
+    vulkan_context: &VulkanContext,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+) -> Result<(vk::Pipeline, vk::PipelineLayout)> {
+    let device = &vulkan_context.device;
+    let push_constant_range = vk::PushConstantRange::builder()
+        .offset(0)
+        .size(std::mem::size_of::<BloomComputeParams>() as _)
+        .stage_flags(vk::ShaderStageFlags::COMPUTE);
+
+    let layout = unsafe {
+        device.create_pipeline_layout(
+            &vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(slice_from_ref(&descriptor_set_layout))
+                .push_constant_ranges(slice_from_ref(&push_constant_range)),
+            None,
+        )
+    }?;
+
+    let (compute_shader, compute_stage) =
+        create_shader(BLOOM_COMPUTE, vk::ShaderStageFlags::COMPUTE, vulkan_context)?;
+
+    let create_info = vk::ComputePipelineCreateInfo::builder()
+        .stage(compute_stage)
+        .layout(layout);
+
+    let pipelines = unsafe {
+        device.create_compute_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    unsafe {
+        device.destroy_shader_module(compute_shader, None);
+    }
+
+    Ok((pipelines[0], layout))
+}
+
+fn create_composite_pipeline(
+    vulkan_context: &VulkanContext,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    render_pass: vk::RenderPass,
+    render_area: &vk::Rect2D,
+    msaa_samples: vk::SampleCountFlags,
+) -> Result<(vk::Pipeline, vk::PipelineLayout)> {
+    let device = &vulkan_context.device;
+    let push_constant_range = vk::PushConstantRange::builder()
+        .offset(0)
+        .size(std::mem::size_of::<f32>() as _)
+        .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+    let layout = unsafe {
+        device.create_pipeline_layout(
+            &vk::PipelineLayoutCreateInfo::builder()
+                .set_layouts(slice_from_ref(&descriptor_set_layout))
+                .push_constant_ranges(slice_from_ref(&push_constant_range)),
+            None,
+        )
+    }?;
+
+    let (vertex_shader, vertex_stage) =
+        create_shader(COMPOSITE_VERT, vk::ShaderStageFlags::VERTEX, vulkan_context)?;
+    let (fragment_shader, fragment_stage) = create_shader(
+        COMPOSITE_FRAG,
+        vk::ShaderStageFlags::FRAGMENT,
+        vulkan_context,
+    )?;
+    let stages = [vertex_stage, fragment_stage];
+
+    // The fullscreen triangle is generated in the vertex shader.
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder();
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+    let viewport = vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: render_area.extent.width as _,
+        height: render_area.extent.height as _,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    };
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(slice_from_ref(&viewport))
+        .scissors(slice_from_ref(render_area));
+
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .polygon_mode(vk::PolygonMode::FILL)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .line_width(1.0);
+
+    let multisample_state =
+        vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(msaa_samples);
+
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(false)
+        .depth_write_enable(false);
+
+    // Additive blending, leaving alpha untouched.
+    let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(
+            vk::ColorComponentFlags::R | vk::ColorComponentFlags::G | vk::ColorComponentFlags::B,
+        )
+        .blend_enable(true)
+        .src_color_blend_factor(vk::BlendFactor::ONE)
+        .dst_color_blend_factor(vk::BlendFactor::ONE)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ZERO)
+        .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+        .alpha_blend_op(vk::BlendOp::ADD)
+        .build();
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .attachments(slice_from_ref(&color_blend_attachment));
+
+    let create_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(&stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    let pipelines = unsafe {
+        device.create_graphics_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    unsafe {
+        device.destroy_shader_module(vertex_shader, None);
+        device.destroy_shader_module(fragment_shader, None);
+    }
+
+    Ok((pipelines[0], layout))
+}
@@ -104,6 +104,16 @@ impl<T: Sized> Buffer<T> {
         index
     }
 
+    /// Checked push - adds `data` to the GPU buffer and returns its index, or returns `None` if the buffer is full.
+    /// SAFETY: The caller MUST ensure `data` is valid and that the buffer hasn't been destroyed
+    pub unsafe fn try_push(&mut self, data: &T) -> Option<u32> {
+        if self.len < self.max_len {
+            Some(self.push(data))
+        } else {
+            None
+        }
+    }
+
     /// Get the buffer's underlying data as a slice
     pub unsafe fn as_slice(&self) -> &[T] {
         std::slice::from_raw_parts(self.memory_address.as_ptr(), self.len)
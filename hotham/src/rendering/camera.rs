@@ -44,6 +44,9 @@ impl Camera {
     }
 }
 
+/// Distance to the near plane of every camera, in meters. There is no far plane.
+pub const NEAR_PLANE: f32 = 0.05;
+
 #[derive(Debug, Copy, Clone)]
 /// A frustrum for the virtual camera.
 pub struct Frustum {
@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Result};
+use ash::vk;
+use glam::Vec4;
+
+use crate::{
+    contexts::{RenderContext, VulkanContext},
+    rendering::{
+        sampler::SamplerDesc,
+        texture::{Texture, TextureUsage},
+    },
+};
+
+/// A 3D lookup table (LUT) that remaps every color on screen, stored as a horizontal strip of 2D slices.
+///
+/// A LUT of `size` has `size` slices of `size` x `size` pixels laid out left to right, one per blue value. Within
+/// each slice red increases to the right and green increases downwards. This is the layout most engines and image
+/// editors export, eg. a 256x16 PNG for a LUT of size 16.
+///
+/// The LUT maps sRGB encoded colors to sRGB encoded colors, so a strip from [`identity_strip`] can be pasted into a
+/// screenshot, graded along with it in an image editor and then cut back out again.
+#[derive(Debug, Clone)]
+pub struct ColorGradingLut {
+    /// The strip, sampled from the shared texture array
+    pub texture: Texture,
+    /// The number of entries along each axis of the LUT
+    pub size: u32,
+}
+
+impl ColorGradingLut {
+    /// Load a LUT from a PNG strip.
+    pub fn from_png(
+        name: &str,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        png: &[u8],
+    ) -> Result<Self> {
+        let image = image::load_from_memory_with_format(png, image::ImageFormat::Png)?.to_rgba8();
+        let (width, height) = image.dimensions();
+        Self::from_rgba8(
+            name,
+            vulkan_context,
+            render_context,
+            &image.into_raw(),
+            width,
+            height,
+        )
+    }
+
+    /// Create a LUT from a strip of `width` x `height` pixels, with four bytes per pixel.
+    pub fn from_rgba8(
+        name: &str,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Self> {
+        let size = lut_size(width, height)?;
+        if rgba.len() != (width * height * 4) as usize {
+            return Err(anyhow!(
+                "Color grading LUT is {width}x{height}, expected {} bytes but got {}",
+                width * height * 4,
+                rgba.len()
+            ));
+        }
+
+        // The strip is uploaded uncompressed: block compression would smear the slices into each other.
+        let mut texture = Texture::new(
+            name,
+            vulkan_context,
+            render_context,
+            rgba,
+            &vk::Extent2D { width, height },
+            1,
+            1,
+            vk::Format::R8G8B8A8_UNORM,
+            TextureUsage::Other,
+        );
+
+        // Slices are sampled at the centres of their edge texels, so clamping keeps neighbouring slices apart.
+        texture.set_sampler(
+            vulkan_context,
+            render_context,
+            SamplerDesc {
+                mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+                address_mode_u: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+                address_mode_v: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+                ..Default::default()
+            },
+        );
+
+        Ok(Self { texture, size })
+    }
+}
+
+/// The pixels of a strip that leaves every color unchanged, with four bytes per pixel. Its width is `size * size`
+/// and its height is `size`.
+pub fn identity_strip(size: u32) -> Vec<u8> {
+    let max = size.saturating_sub(1).max(1) as f32;
+    let to_u8 = |value: u32| (value as f32 / max * 255.).round() as u8;
+
+    let mut pixels = Vec::with_capacity((size * size * size * 4) as usize);
+    for green in 0..size {
+        for blue in 0..size {
+            for red in 0..size {
+                pixels.extend_from_slice(&[to_u8(red), to_u8(green), to_u8(blue), 255]);
+            }
+        }
+    }
+    pixels
+}
+
+/// Check that a strip of `width` x `height` pixels holds a LUT, and return its size.
+fn lut_size(width: u32, height: u32) -> Result<u32> {
+    if height < 2 || width != height * height {
+        return Err(anyhow!(
+            "Color grading LUTs must be N*N pixels wide and N pixels high, but this one is {width}x{height}"
+        ));
+    }
+    Ok(height)
+}
+
+/// Settings for color grading: remapping the colors of the scene with a [`ColorGradingLut`] to set its mood.
+///
+/// Grading is applied to every fragment straight after it's been tonemapped, so it costs a couple of texture
+/// samples rather than a full screen pass. Bloom is added on top of the graded scene. These settings can be
+/// changed at any time, and take effect from the next frame.
+#[derive(Debug, Clone)]
+pub struct ColorGradingSettings {
+    /// Whether or not the scene is graded. Grading is skipped if there's no `lut`.
+    pub enabled: bool,
+    /// The LUT the scene is graded with
+    pub lut: Option<ColorGradingLut>,
+    /// How much of the graded color is used, from `0.0` (none) to `1.0` (all of it)
+    pub strength: f32,
+}
+
+impl Default for ColorGradingSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lut: None,
+            strength: 1.0,
+        }
+    }
+}
+
+impl ColorGradingSettings {
+    /// The parameters sent to the shaders: x = LUT texture index, y = LUT size (zero when grading is disabled),
+    /// z = strength, w = unused
+    pub(crate) fn params(&self) -> Vec4 {
+        match &self.lut {
+            Some(lut) if self.enabled => Vec4::new(
+                lut.texture.index as f32,
+                lut.size as f32,
+                self.strength.clamp(0., 1.),
+                0.,
+            ),
+            _ => Vec4::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_lut_size() {
+        assert_eq!(lut_size(256, 16).unwrap(), 16);
+        assert_eq!(lut_size(1024, 32).unwrap(), 32);
+
+        // Vertical strips, and strips that aren't made of square slices, aren't supported
+        assert!(lut_size(16, 256).is_err());
+        assert!(lut_size(256, 32).is_err());
+        assert!(lut_size(1, 1).is_err());
+    }
+
+    #[test]
+    pub fn test_identity_strip() {
+        let size = 4;
+        let pixels = identity_strip(size);
+        assert_eq!(pixels.len(), (size * size * size * 4) as usize);
+
+        let pixel = |x: u32, y: u32| {
+            let offset = ((y * size * size + x) * 4) as usize;
+            &pixels[offset..offset + 4]
+        };
+
+        // Red increases to the right, green downwards and blue from slice to slice
+        assert_eq!(pixel(0, 0), &[0, 0, 0, 255]);
+        assert_eq!(pixel(3, 0), &[255, 0, 0, 255]);
+        assert_eq!(pixel(0, 3), &[0, 255, 0, 255]);
+        assert_eq!(pixel(4, 0), &[0, 0, 85, 255]);
+        assert_eq!(pixel(15, 3), &[255, 255, 255, 255]);
+    }
+
+    #[test]
+    pub fn test_params_without_lut() {
+        // Enabling grading without a LUT leaves the scene alone
+        let settings = ColorGradingSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(settings.params(), Vec4::ZERO);
+    }
+}
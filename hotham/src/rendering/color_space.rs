@@ -0,0 +1,124 @@
+use ash::vk;
+
+use super::texture::TextureUsage;
+
+/// How colors are encoded when they're written to the swapchain.
+///
+/// Lighting is always calculated in linear space. Textures holding colors (base color and emission) are decoded
+/// from sRGB when they're sampled, and textures holding data (normals, occlusion, roughness and metalness) are
+/// sampled as-is, regardless of this setting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorSpace {
+    /// Render into an sRGB swapchain, so the GPU encodes the output as sRGB when it's written. This is what
+    /// runtimes expect, and matches Blender and the glTF reference viewers.
+    #[default]
+    Srgb,
+    /// Render into a UNORM swapchain, writing linear values without encoding them. Only use this if the compositor
+    /// expects linear input: on most runtimes it makes the image look dark.
+    Linear,
+}
+
+impl ColorSpace {
+    /// The format of the swapchain and of the color attachments that are resolved into it
+    pub fn swapchain_format(&self) -> vk::Format {
+        match self {
+            ColorSpace::Srgb => vk::Format::R8G8B8A8_SRGB,
+            ColorSpace::Linear => vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
+/// The format a texture with `usage` should be sampled with: sRGB for colors, UNORM for everything else.
+///
+/// Image files don't reliably say whether they hold colors or data, so the format in the file is only trusted for
+/// textures that aren't part of a glTF material. Formats without an sRGB variant are left alone.
+pub fn texture_format_for_usage(format: vk::Format, usage: &TextureUsage) -> vk::Format {
+    match usage {
+        TextureUsage::BaseColor | TextureUsage::Emission => to_srgb(format),
+        TextureUsage::Normal
+        | TextureUsage::MetallicRoughnessOcclusion
+        | TextureUsage::Transmission => to_unorm(format),
+        TextureUsage::IBL | TextureUsage::Other => format,
+    }
+}
+
+// Pairs of (UNORM, sRGB) formats
+const SRGB_FORMATS: [(vk::Format, vk::Format); 8] = [
+    (vk::Format::R8G8B8A8_UNORM, vk::Format::R8G8B8A8_SRGB),
+    (vk::Format::B8G8R8A8_UNORM, vk::Format::B8G8R8A8_SRGB),
+    (
+        vk::Format::ASTC_4X4_UNORM_BLOCK,
+        vk::Format::ASTC_4X4_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_6X6_UNORM_BLOCK,
+        vk::Format::ASTC_6X6_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::ASTC_8X8_UNORM_BLOCK,
+        vk::Format::ASTC_8X8_SRGB_BLOCK,
+    ),
+    (
+        vk::Format::BC1_RGBA_UNORM_BLOCK,
+        vk::Format::BC1_RGBA_SRGB_BLOCK,
+    ),
+    (vk::Format::BC3_UNORM_BLOCK, vk::Format::BC3_SRGB_BLOCK),
+    (vk::Format::BC7_UNORM_BLOCK, vk::Format::BC7_SRGB_BLOCK),
+];
+
+fn to_srgb(format: vk::Format) -> vk::Format {
+    SRGB_FORMATS
+        .iter()
+        .find(|(unorm, _)| *unorm == format)
+        .map(|(_, srgb)| *srgb)
+        .unwrap_or(format)
+}
+
+fn to_unorm(format: vk::Format) -> vk::Format {
+    SRGB_FORMATS
+        .iter()
+        .find(|(_, srgb)| *srgb == format)
+        .map(|(unorm, _)| *unorm)
+        .unwrap_or(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_texture_format_for_usage() {
+        // Colors are decoded from sRGB
+        assert_eq!(
+            texture_format_for_usage(vk::Format::BC7_UNORM_BLOCK, &TextureUsage::BaseColor),
+            vk::Format::BC7_SRGB_BLOCK
+        );
+        assert_eq!(
+            texture_format_for_usage(vk::Format::R8G8B8A8_SRGB, &TextureUsage::Emission),
+            vk::Format::R8G8B8A8_SRGB
+        );
+
+        // Data is sampled as-is
+        assert_eq!(
+            texture_format_for_usage(vk::Format::ASTC_4X4_SRGB_BLOCK, &TextureUsage::Normal),
+            vk::Format::ASTC_4X4_UNORM_BLOCK
+        );
+        assert_eq!(
+            texture_format_for_usage(
+                vk::Format::R8G8B8A8_UNORM,
+                &TextureUsage::MetallicRoughnessOcclusion
+            ),
+            vk::Format::R8G8B8A8_UNORM
+        );
+
+        // Formats without an sRGB variant, and textures outside of materials, are left alone
+        assert_eq!(
+            texture_format_for_usage(vk::Format::R8G8_UNORM, &TextureUsage::BaseColor),
+            vk::Format::R8G8_UNORM
+        );
+        assert_eq!(
+            texture_format_for_usage(vk::Format::R8G8B8A8_UNORM, &TextureUsage::Other),
+            vk::Format::R8G8B8A8_UNORM
+        );
+    }
+}
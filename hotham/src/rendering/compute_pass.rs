@@ -0,0 +1,355 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::contexts::{render_context::create_shader, VulkanContext};
+
+/// A resource bound to a [`ComputePass`]. Binding `n` in the shader corresponds to the `n`th binding
+/// in the list given to [`ComputePass::new`], all in descriptor set 0.
+#[derive(Debug, Clone, Copy)]
+pub enum ComputeBinding {
+    /// A `buffer` block, readable and writable by the shader
+    StorageBuffer(vk::Buffer),
+    /// A `uniform` block
+    UniformBuffer(vk::Buffer),
+    /// An `image2D` (or similar) that the shader can write to. The image must be in the `GENERAL` layout.
+    StorageImage(vk::ImageView),
+    /// A `sampler2D` (or similar). The image must be in the `SHADER_READ_ONLY_OPTIMAL` layout.
+    SampledImage(vk::ImageView, vk::Sampler),
+}
+
+impl ComputeBinding {
+    /// The type of descriptor this binding is written to
+    pub fn descriptor_type(&self) -> vk::DescriptorType {
+        match self {
+            ComputeBinding::StorageBuffer(_) => vk::DescriptorType::STORAGE_BUFFER,
+            ComputeBinding::UniformBuffer(_) => vk::DescriptorType::UNIFORM_BUFFER,
+            ComputeBinding::StorageImage(_) => vk::DescriptorType::STORAGE_IMAGE,
+            ComputeBinding::SampledImage(..) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+        }
+    }
+}
+
+/// A user supplied compute shader, along with the resources it reads from and writes to.
+///
+/// Dispatch it with [`crate::contexts::RenderContext::dispatch_compute`] from a system that runs before the
+/// rendering system; the renderer inserts the barriers needed for the results to be visible to the rest of the frame.
+/// Useful for GPU particles, cloth or procedural geometry.
+pub struct ComputePass {
+    /// The compute pipeline
+    pub pipeline: vk::Pipeline,
+    /// The layout of `pipeline`
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Layout of `descriptor_set`
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    /// Pool `descriptor_set` is allocated from
+    pub descriptor_pool: vk::DescriptorPool,
+    /// Descriptor set holding the bindings of this pass
+    pub descriptor_set: vk::DescriptorSet,
+    /// The resources bound to the shader, in binding order
+    bindings: Vec<ComputeBinding>,
+    /// Size of the push constant block of the shader, in bytes
+    push_constant_size: u32,
+}
+
+impl ComputePass {
+    /// Create a compute pass from SPIR-V `shader_code`, with one descriptor per entry in `bindings` and
+    /// `push_constant_size` bytes of push constants (which may be zero).
+    pub fn new(
+        vulkan_context: &VulkanContext,
+        shader_code: &[u32],
+        bindings: Vec<ComputeBinding>,
+        push_constant_size: u32,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+
+        let layout_bindings = bindings
+            .iter()
+            .zip(0..)
+            .map(|(binding, index)| {
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(index)
+                    .descriptor_type(binding.descriptor_type())
+                    .descriptor_count(1)
+                    .stage_flags(vk::ShaderStageFlags::COMPUTE)
+                    .build()
+            })
+            .collect::<Vec<_>>();
+        let descriptor_set_layout = unsafe {
+            device.create_descriptor_set_layout(
+                &vk::DescriptorSetLayoutCreateInfo::builder().bindings(&layout_bindings),
+                None,
+            )
+        }?;
+
+        // Vulkan doesn't allow empty pools, so always ask for at least one descriptor.
+        let mut pool_sizes = layout_bindings
+            .iter()
+            .map(|b| vk::DescriptorPoolSize {
+                ty: b.descriptor_type,
+                descriptor_count: 1,
+            })
+            .collect::<Vec<_>>();
+        if pool_sizes.is_empty() {
+            pool_sizes.push(vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+            });
+        }
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(&pool_sizes)
+                    .max_sets(1),
+                None,
+            )
+        }?;
+        let descriptor_set = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(slice_from_ref(&descriptor_set_layout)),
+            )
+        }?[0];
+
+        let push_constant_range = vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            offset: 0,
+            size: push_constant_size,
+        };
+        let push_constant_ranges = if push_constant_size > 0 {
+            slice_from_ref(&push_constant_range)
+        } else {
+            &[]
+        };
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptor_set_layout))
+                    .push_constant_ranges(push_constant_ranges),
+                None,
+            )
+        }?;
+
+        let (compute_shader, compute_stage) =
+            create_shader(shader_code, vk::ShaderStageFlags::COMPUTE, vulkan_context)?;
+        let create_info = vk::ComputePipelineCreateInfo::builder()
+            .stage(compute_stage)
+            .layout(pipeline_layout);
+        let pipeline = unsafe {
+            device.create_compute_pipelines(
+                vk::PipelineCache::null(),
+                slice_from_ref(&create_info),
+                None,
+            )
+        }
+        .map_err(|(_, r)| r)?[0];
+        unsafe {
+            device.destroy_shader_module(compute_shader, None);
+        }
+
+        let mut compute_pass = Self {
+            pipeline,
+            pipeline_layout,
+            descriptor_set_layout,
+            descriptor_pool,
+            descriptor_set,
+            bindings: Vec::new(),
+            push_constant_size,
+        };
+
+        // Write the initial descriptors
+        for (binding, index) in bindings.into_iter().zip(0..) {
+            compute_pass.bindings.push(binding);
+            unsafe { compute_pass.write_descriptor(device, index) };
+        }
+
+        Ok(compute_pass)
+    }
+
+    /// The resources bound to this pass, in binding order
+    pub fn bindings(&self) -> &[ComputeBinding] {
+        &self.bindings
+    }
+
+    /// Replace the resource at `index` with `binding`, which must be of the same type.
+    ///
+    /// # Safety
+    /// The descriptor set is updated immediately, so this pass must not be in use by a frame that is still in flight.
+    pub unsafe fn set_binding(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        index: u32,
+        binding: ComputeBinding,
+    ) {
+        let existing = &mut self.bindings[index as usize];
+        assert_eq!(
+            existing.descriptor_type(),
+            binding.descriptor_type(),
+            "Compute binding {index} must keep its type"
+        );
+        *existing = binding;
+        self.write_descriptor(&vulkan_context.device, index);
+    }
+
+    unsafe fn write_descriptor(&self, device: &ash::Device, index: u32) {
+        let binding = self.bindings[index as usize];
+        let write = vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_set)
+            .dst_binding(index)
+            .descriptor_type(binding.descriptor_type());
+
+        match binding {
+            ComputeBinding::StorageBuffer(buffer) | ComputeBinding::UniformBuffer(buffer) => {
+                let buffer_info = vk::DescriptorBufferInfo {
+                    buffer,
+                    offset: 0,
+                    range: vk::WHOLE_SIZE,
+                };
+                let write = write.buffer_info(slice_from_ref(&buffer_info));
+                device.update_descriptor_sets(slice_from_ref(&write), &[]);
+            }
+            ComputeBinding::StorageImage(image_view) => {
+                let image_info = vk::DescriptorImageInfo {
+                    sampler: vk::Sampler::null(),
+                    image_view,
+                    image_layout: vk::ImageLayout::GENERAL,
+                };
+                let write = write.image_info(slice_from_ref(&image_info));
+                device.update_descriptor_sets(slice_from_ref(&write), &[]);
+            }
+            ComputeBinding::SampledImage(image_view, sampler) => {
+                let image_info = vk::DescriptorImageInfo {
+                    sampler,
+                    image_view,
+                    image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                };
+                let write = write.image_info(slice_from_ref(&image_info));
+                device.update_descriptor_sets(slice_from_ref(&write), &[]);
+            }
+        }
+    }
+
+    /// Record the dispatch into `command_buffer`, surrounded by the barriers needed to use its results in the
+    /// graphics part of the frame.
+    pub(crate) unsafe fn record(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        group_count: [u32; 3],
+        push_constants: &[u8],
+    ) {
+        assert_eq!(
+            push_constants.len(),
+            self.push_constant_size as usize,
+            "Push constants must match the size given to ComputePass::new"
+        );
+
+        // Don't overwrite anything the previous frame, or an earlier pass, may still be reading.
+        let before = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::ALL_GRAPHICS | vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            slice_from_ref(&before),
+            &[],
+            &[],
+        );
+
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::COMPUTE,
+            self.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::COMPUTE,
+            self.pipeline_layout,
+            0,
+            slice_from_ref(&self.descriptor_set),
+            &[],
+        );
+        if !push_constants.is_empty() {
+            device.cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                push_constants,
+            );
+        }
+        let [x, y, z] = group_count;
+        device.cmd_dispatch(command_buffer, x, y, z);
+
+        // Make the results visible to anything that may consume them later in the frame.
+        let after = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(
+                vk::AccessFlags::INDIRECT_COMMAND_READ
+                    | vk::AccessFlags::INDEX_READ
+                    | vk::AccessFlags::VERTEX_ATTRIBUTE_READ
+                    | vk::AccessFlags::UNIFORM_READ
+                    | vk::AccessFlags::SHADER_READ
+                    | vk::AccessFlags::SHADER_WRITE,
+            );
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::DRAW_INDIRECT
+                | vk::PipelineStageFlags::VERTEX_INPUT
+                | vk::PipelineStageFlags::VERTEX_SHADER
+                | vk::PipelineStageFlags::FRAGMENT_SHADER
+                | vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            slice_from_ref(&after),
+            &[],
+            &[],
+        );
+    }
+
+    /// Destroy the Vulkan objects owned by this pass. The bound resources are owned by the caller.
+    ///
+    /// # Safety
+    /// The pass must not be in use by a frame that is still in flight.
+    pub unsafe fn destroy(&self, vulkan_context: &VulkanContext) {
+        let device = &vulkan_context.device;
+        device.destroy_pipeline(self.pipeline, None);
+        device.destroy_pipeline_layout(self.pipeline_layout, None);
+        device.destroy_descriptor_pool(self.descriptor_pool, None);
+        device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ash::vk::Handle;
+
+    #[test]
+    pub fn test_compute_binding_descriptor_types() {
+        let buffer = vk::Buffer::from_raw(1);
+        let view = vk::ImageView::from_raw(2);
+        let sampler = vk::Sampler::from_raw(3);
+
+        assert_eq!(
+            ComputeBinding::StorageBuffer(buffer).descriptor_type(),
+            vk::DescriptorType::STORAGE_BUFFER
+        );
+        assert_eq!(
+            ComputeBinding::UniformBuffer(buffer).descriptor_type(),
+            vk::DescriptorType::UNIFORM_BUFFER
+        );
+        assert_eq!(
+            ComputeBinding::StorageImage(view).descriptor_type(),
+            vk::DescriptorType::STORAGE_IMAGE
+        );
+        assert_eq!(
+            ComputeBinding::SampledImage(view, sampler).descriptor_type(),
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{collections::HashMap, slice::from_ref as slice_from_ref, sync::Arc};
+
+use anyhow::Result;
+use ash::vk;
+use glam::Affine3A;
+
+use crate::{
+    components::CustomMaterial,
+    contexts::{
+        render_context::{create_pipeline, Shaders, PIPELINE_DEPTH},
+        VulkanContext,
+    },
+    rendering::{buffer::Buffer, primitive::Primitive},
+};
+
+/// The maximum size of the uniform block of a [`CustomMaterial`], in bytes.
+/// This is also the minimum alignment of dynamic uniform buffers that every Vulkan implementation supports.
+pub const CUSTOM_UNIFORMS_SIZE: usize = 256;
+
+/// The descriptor set the uniform block of a [`CustomMaterial`] is bound to.
+pub const CUSTOM_UNIFORMS_SET: u32 = 1;
+
+/// The maximum number of primitives drawn with a [`CustomMaterial`] per frame.
+pub const MAX_CUSTOM_DRAWS: usize = 1000;
+
+type CustomUniforms = [u8; CUSTOM_UNIFORMS_SIZE];
+
+/// A primitive to be drawn with a [`CustomMaterial`] this frame.
+pub(crate) struct CustomDraw {
+    pub primitive: Primitive,
+    pub gos_from_local: Affine3A,
+    pub skin_id: u32,
+    pub morph_weights_id: u32,
+    pub material: CustomMaterial,
+}
+
+/// Creates and caches the pipelines used to draw [`CustomMaterial`]s.
+pub struct CustomPipelines {
+    /// Layout of the uniform block descriptor set
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    /// The PBR descriptor set, followed by the uniform block descriptor set
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Pool the uniform block descriptor sets are allocated from
+    pub descriptor_pool: vk::DescriptorPool,
+    /// One descriptor set per frame
+    pub descriptor_sets: [vk::DescriptorSet; PIPELINE_DEPTH],
+    /// One buffer per frame, holding the uniforms of every custom draw in that frame
+    uniform_buffers: [Buffer<CustomUniforms>; PIPELINE_DEPTH],
+    /// Pipelines, keyed by [`CustomMaterial::pipeline_key`]
+    pipelines: HashMap<(usize, usize), vk::Pipeline>,
+    /// The shaders of every cached pipeline. Holding on to these ensures keys are never reused.
+    shaders: Vec<(Arc<[u32]>, Arc<[u32]>)>,
+}
+
+impl CustomPipelines {
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        graphics_layout: vk::DescriptorSetLayout,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+
+        let binding = vk::DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+            ..Default::default()
+        };
+        let descriptor_set_layout = unsafe {
+            device.create_descriptor_set_layout(
+                &vk::DescriptorSetLayoutCreateInfo::builder().bindings(slice_from_ref(&binding)),
+                None,
+            )
+        }?;
+
+        let set_layouts = [graphics_layout, descriptor_set_layout];
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder().set_layouts(&set_layouts),
+                None,
+            )
+        }?;
+
+        let pool_size = vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+            descriptor_count: PIPELINE_DEPTH as _,
+        };
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(slice_from_ref(&pool_size))
+                    .max_sets(PIPELINE_DEPTH as _),
+                None,
+            )
+        }?;
+
+        let set_layouts = [descriptor_set_layout; PIPELINE_DEPTH];
+        let descriptor_sets = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&set_layouts),
+            )
+        }?;
+        let descriptor_sets = [descriptor_sets[0], descriptor_sets[1]];
+
+        let uniform_buffers = [(); PIPELINE_DEPTH].map(|_| unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+                MAX_CUSTOM_DRAWS,
+            )
+        });
+
+        for (buffer, descriptor_set) in uniform_buffers.iter().zip(descriptor_sets) {
+            let buffer_info = vk::DescriptorBufferInfo {
+                buffer: buffer.buffer,
+                offset: 0,
+                range: CUSTOM_UNIFORMS_SIZE as _,
+            };
+            let write = vk::WriteDescriptorSet::builder()
+                .dst_set(descriptor_set)
+                .dst_binding(0)
+                .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+                .buffer_info(slice_from_ref(&buffer_info));
+            unsafe { device.update_descriptor_sets(slice_from_ref(&write), &[]) };
+        }
+
+        Ok(Self {
+            descriptor_set_layout,
+            pipeline_layout,
+            descriptor_pool,
+            descriptor_sets,
+            uniform_buffers,
+            pipelines: Default::default(),
+            shaders: Default::default(),
+        })
+    }
+
+    /// Get the pipeline for `material`, creating it if this is the first time its shaders have been drawn.
+    pub(crate) fn get_or_create_pipeline(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        material: &CustomMaterial,
+        render_pass: vk::RenderPass,
+        render_area: &vk::Rect2D,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<vk::Pipeline> {
+        let key = material.pipeline_key();
+        if let Some(pipeline) = self.pipelines.get(&key) {
+            return Ok(*pipeline);
+        }
+
+        println!("[HOTHAM_RENDERER] Creating pipeline for custom material..");
+        let shaders = Shaders::new(
+            material.vertex_shader.to_vec(),
+            material.fragment_shader.to_vec(),
+            Vec::new(),
+        );
+        let pipeline = create_pipeline(
+            vulkan_context,
+            self.pipeline_layout,
+            render_area,
+            render_pass,
+            &shaders,
+            msaa_samples,
+            vk::CullModeFlags::BACK,
+        )?;
+
+        self.pipelines.insert(key, pipeline);
+        self.shaders.push((
+            material.vertex_shader.clone(),
+            material.fragment_shader.clone(),
+        ));
+        Ok(pipeline)
+    }
+
+    /// Forget the uniforms written during the last use of this frame.
+    pub(crate) fn clear_uniforms(&mut self, frame_index: usize) {
+        self.uniform_buffers[frame_index].clear();
+    }
+
+    /// Write `uniforms` into this frame's uniform buffer and return the dynamic offset to bind them at.
+    ///
+    /// # Safety
+    ///
+    /// At most [`MAX_CUSTOM_DRAWS`] uniform blocks can be written per frame.
+    pub(crate) unsafe fn write_uniforms(&mut self, frame_index: usize, uniforms: &[u8]) -> u32 {
+        let mut block = [0; CUSTOM_UNIFORMS_SIZE];
+        block[..uniforms.len()].copy_from_slice(uniforms);
+        self.uniform_buffers[frame_index].push(&block) * CUSTOM_UNIFORMS_SIZE as u32
+    }
+}
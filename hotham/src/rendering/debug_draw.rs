@@ -0,0 +1,399 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk;
+use glam::{Affine3A, Mat4, Vec3};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::{
+        render_context::{create_push_constant, create_shader, PIPELINE_DEPTH},
+        VulkanContext,
+    },
+    rendering::{aabb::Aabb, buffer::Buffer, descriptors::Descriptors},
+};
+
+static DEBUG_VERT: &[u32] = include_glsl!("src/shaders/debug.vert", target: vulkan1_1);
+static DEBUG_FRAG: &[u32] = include_glsl!("src/shaders/debug.frag", target: vulkan1_1);
+
+/// The maximum number of lines that can be drawn each frame.
+pub const MAX_DEBUG_LINES: usize = 50_000;
+
+/// The number of segments used to approximate each circle of a sphere.
+const SPHERE_SEGMENTS: usize = 24;
+
+/// A single end of a debug line, in global space.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugVertex {
+    /// Position in global space
+    pub position: Vec3,
+    /// Color, packed as RGBA8
+    pub color: [u8; 4],
+}
+
+/// Immediate mode debug drawing. Lines added during a frame are drawn on top of the opaque geometry of that
+/// frame, then cleared. Everything is in global space.
+///
+/// Basic usage:
+/// ```ignore
+/// let debug_draw = &mut engine.render_context.debug_draw;
+/// debug_draw.line(ray_origin, ray_origin + ray_direction, Vec3::Y);
+/// debug_draw.axis(&global_transform.0, 0.1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DebugDraw {
+    /// The ends of every line added this frame, two per line
+    vertices: Vec<DebugVertex>,
+}
+
+impl DebugDraw {
+    /// Draw a line from `start` to `end`
+    pub fn line(&mut self, start: Vec3, end: Vec3, color: Vec3) {
+        let color = pack_color(color);
+        self.vertices.push(DebugVertex {
+            position: start,
+            color,
+        });
+        self.vertices.push(DebugVertex {
+            position: end,
+            color,
+        });
+    }
+
+    /// Draw the edges of an axis aligned bounding box
+    pub fn aabb(&mut self, aabb: &Aabb, color: Vec3) {
+        let (min, max) = (aabb.min, aabb.max);
+        let corner = |x: bool, y: bool, z: bool| {
+            Vec3::new(
+                if x { max.x } else { min.x },
+                if y { max.y } else { min.y },
+                if z { max.z } else { min.z },
+            )
+        };
+
+        for a in [false, true] {
+            for b in [false, true] {
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+
+    /// Draw the edges of a box with the given half extents, positioned by `transform`
+    pub fn cuboid(&mut self, transform: &Affine3A, half_extents: Vec3, color: Vec3) {
+        let corner = |x: bool, y: bool, z: bool| {
+            let sign = |b: bool| if b { 1. } else { -1. };
+            transform.transform_point3(half_extents * Vec3::new(sign(x), sign(y), sign(z)))
+        };
+
+        for a in [false, true] {
+            for b in [false, true] {
+                self.line(corner(false, a, b), corner(true, a, b), color);
+                self.line(corner(a, false, b), corner(a, true, b), color);
+                self.line(corner(a, b, false), corner(a, b, true), color);
+            }
+        }
+    }
+
+    /// Draw the axes of `transform`, `size` meters long: red for X, green for Y and blue for Z
+    pub fn axis(&mut self, transform: &Affine3A, size: f32) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for axis in [Vec3::X, Vec3::Y, Vec3::Z] {
+            let end = transform.transform_point3(axis * size);
+            self.line(origin, end, axis);
+        }
+    }
+
+    /// Draw a sphere as three circles, one around each axis
+    pub fn sphere(&mut self, center: Vec3, radius: f32, color: Vec3) {
+        let point = |angle: f32, axis: usize| {
+            let (sin, cos) = angle.sin_cos();
+            let offset = match axis {
+                0 => Vec3::new(0., cos, sin),
+                1 => Vec3::new(cos, 0., sin),
+                _ => Vec3::new(cos, sin, 0.),
+            };
+            center + offset * radius
+        };
+
+        let step = std::f32::consts::TAU / SPHERE_SEGMENTS as f32;
+        for axis in 0..3 {
+            for segment in 0..SPHERE_SEGMENTS {
+                let angle = segment as f32 * step;
+                self.line(point(angle, axis), point(angle + step, axis), color);
+            }
+        }
+    }
+
+    /// The number of lines added this frame
+    pub fn line_count(&self) -> usize {
+        self.vertices.len() / 2
+    }
+
+    /// Remove every line added this frame
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+    }
+
+    pub(crate) fn vertices(&self) -> &[DebugVertex] {
+        &self.vertices
+    }
+}
+
+fn pack_color(color: Vec3) -> [u8; 4] {
+    let c = (color.clamp(Vec3::ZERO, Vec3::ONE) * 255.).round();
+    [c.x as u8, c.y as u8, c.z as u8, 255]
+}
+
+/// The pipeline used to draw the lines in a [`DebugDraw`] as a line list.
+pub struct DebugDrawPipeline {
+    /// The scene data descriptor set plus `gos_from_global` as a push constant
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Draws lines, tested against but not written to the depth buffer
+    pub pipeline: vk::Pipeline,
+    /// One vertex buffer per frame
+    vertex_buffers: [Buffer<DebugVertex>; PIPELINE_DEPTH],
+}
+
+impl DebugDrawPipeline {
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        descriptors: &Descriptors,
+        render_pass: vk::RenderPass,
+        render_area: &vk::Rect2D,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let push_constant_range = vk::PushConstantRange::builder()
+            .offset(0)
+            .size(std::mem::size_of::<Mat4>() as _)
+            .stage_flags(vk::ShaderStageFlags::VERTEX);
+
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptors.graphics_layout))
+                    .push_constant_ranges(slice_from_ref(&push_constant_range)),
+                None,
+            )
+        }?;
+
+        let (vertex_shader, vertex_stage) =
+            create_shader(DEBUG_VERT, vk::ShaderStageFlags::VERTEX, vulkan_context)?;
+        let (fragment_shader, fragment_stage) =
+            create_shader(DEBUG_FRAG, vk::ShaderStageFlags::FRAGMENT, vulkan_context)?;
+        let stages = [vertex_stage, fragment_stage];
+
+        let vertex_binding = vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(std::mem::size_of::<DebugVertex>() as _)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build();
+        let vertex_attributes = [
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(0)
+                .format(vk::Format::R32G32B32_SFLOAT)
+                .offset(memoffset::offset_of!(DebugVertex, position) as _)
+                .build(),
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(1)
+                .format(vk::Format::R8G8B8A8_UNORM)
+                .offset(memoffset::offset_of!(DebugVertex, color) as _)
+                .build(),
+        ];
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+            .vertex_binding_descriptions(slice_from_ref(&vertex_binding))
+            .vertex_attribute_descriptions(&vertex_attributes);
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+            .topology(vk::PrimitiveTopology::LINE_LIST);
+
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: render_area.extent.width as _,
+            height: render_area.extent.height as _,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+            .viewports(slice_from_ref(&viewport))
+            .scissors(slice_from_ref(render_area));
+
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+            .line_width(1.0);
+
+        let multisample_state =
+            vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(msaa_samples);
+
+        // Lines are hidden by the geometry in front of them, but don't hide anything themselves.
+        // Remember that we're using inverse Z.
+        let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(true)
+            .depth_write_enable(false)
+            .depth_compare_op(vk::CompareOp::GREATER_OR_EQUAL)
+            .depth_bounds_test_enable(false)
+            .min_depth_bounds(0.0)
+            .max_depth_bounds(1.0)
+            .stencil_test_enable(false);
+
+        let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(
+                vk::ColorComponentFlags::R
+                    | vk::ColorComponentFlags::G
+                    | vk::ColorComponentFlags::B
+                    | vk::ColorComponentFlags::A,
+            )
+            .blend_enable(false)
+            .build();
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op_enable(false)
+            .attachments(slice_from_ref(&color_blend_attachment));
+
+        let create_info = vk::GraphicsPipelineCreateInfo::builder()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .depth_stencil_state(&depth_stencil_state)
+            .color_blend_state(&color_blend_state)
+            .layout(pipeline_layout)
+            .render_pass(render_pass)
+            .subpass(0);
+
+        let pipelines = unsafe {
+            device.create_graphics_pipelines(
+                vk::PipelineCache::null(),
+                slice_from_ref(&create_info),
+                None,
+            )
+        }
+        .map_err(|(_, r)| r)?;
+
+        unsafe {
+            device.destroy_shader_module(vertex_shader, None);
+            device.destroy_shader_module(fragment_shader, None);
+        }
+
+        let vertex_buffers = [(); PIPELINE_DEPTH].map(|_| unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+                MAX_DEBUG_LINES * 2,
+            )
+        });
+
+        Ok(Self {
+            pipeline_layout,
+            pipeline: pipelines[0],
+            vertex_buffers,
+        })
+    }
+
+    /// Draw every line in `debug_draw`.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be inside the PBR render pass.
+    pub(crate) unsafe fn draw(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+        descriptor_set: vk::DescriptorSet,
+        gos_from_global: &Affine3A,
+        debug_draw: &DebugDraw,
+    ) {
+        let vertices = debug_draw.vertices();
+        if vertices.is_empty() {
+            return;
+        }
+
+        if vertices.len() > MAX_DEBUG_LINES * 2 {
+            println!("[HOTHAM_DEBUG_DRAW] WARNING: More than {MAX_DEBUG_LINES} lines were drawn this frame, some will be missing!");
+        }
+
+        let vertex_buffer = &mut self.vertex_buffers[frame_index];
+        vertex_buffer.overwrite(&vertices[..vertices.len().min(MAX_DEBUG_LINES * 2)]);
+
+        let gos_from_global: Mat4 = (*gos_from_global).into();
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            slice_from_ref(&descriptor_set),
+            &[],
+        );
+        device.cmd_push_constants(
+            command_buffer,
+            self.pipeline_layout,
+            vk::ShaderStageFlags::VERTEX,
+            0,
+            create_push_constant(&gos_from_global),
+        );
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer.buffer], &[0]);
+        device.cmd_draw(command_buffer, vertex_buffer.len() as _, 1, 0, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_debug_draw() {
+        let mut debug_draw = DebugDraw::default();
+        debug_draw.line(Vec3::ZERO, Vec3::X, Vec3::new(1., 0.5, 0.));
+        assert_eq!(debug_draw.line_count(), 1);
+        assert_eq!(debug_draw.vertices()[1].position, Vec3::X);
+        assert_eq!(debug_draw.vertices()[1].color, [255, 128, 0, 255]);
+
+        debug_draw.aabb(&Aabb::new(Vec3::ZERO, Vec3::ONE), Vec3::ONE);
+        assert_eq!(debug_draw.line_count(), 1 + 12);
+
+        debug_draw.axis(&Affine3A::from_translation(Vec3::Y), 0.5);
+        assert_eq!(debug_draw.line_count(), 1 + 12 + 3);
+        let x_axis = &debug_draw.vertices()[26..28];
+        assert_eq!(x_axis[0].position, Vec3::Y);
+        assert_eq!(x_axis[1].position, Vec3::new(0.5, 1., 0.));
+
+        debug_draw.sphere(Vec3::ZERO, 2., Vec3::ONE);
+        assert_eq!(debug_draw.line_count(), 1 + 12 + 3 + SPHERE_SEGMENTS * 3);
+        assert!(debug_draw.vertices()[32..]
+            .iter()
+            .all(|v| (v.position.length() - 2.).abs() < 1e-5));
+
+        debug_draw.clear();
+        assert_eq!(debug_draw.line_count(), 0);
+    }
+
+    #[test]
+    pub fn test_debug_draw_cuboid() {
+        // A unit cube drawn as a cuboid should match the same cube drawn as an AABB.
+        let mut from_aabb = DebugDraw::default();
+        from_aabb.aabb(&Aabb::new(Vec3::ZERO, Vec3::ONE), Vec3::ONE);
+
+        let mut from_cuboid = DebugDraw::default();
+        from_cuboid.cuboid(
+            &Affine3A::from_translation(Vec3::splat(0.5)),
+            Vec3::splat(0.5),
+            Vec3::ONE,
+        );
+
+        assert_eq!(from_aabb.vertices(), from_cuboid.vertices());
+    }
+}
@@ -0,0 +1,66 @@
+use glam::{Affine3A, Mat4};
+
+use super::material::Material;
+
+/// The maximum number of decals drawn each frame
+pub const MAX_DECALS: usize = 128;
+
+/// A [`Decal`](crate::components::Decal) as it's sent to the fragment shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecalData {
+    /// Transforms points in globally oriented stage space into the decal's box, which spans -0.5 to 0.5 on each axis
+    pub decal_from_gos: Mat4,
+    /// The flags and base color texture of the decal's material, packed as in [`Material`]
+    pub packed_flags_and_base_texture_id: u32,
+    /// The base color factor of the decal's material
+    pub packed_base_color_factor: u32,
+    _padding: [u32; 2],
+}
+
+impl DecalData {
+    /// Create the data for a decal with the box `gos_from_decal`, showing `material`
+    pub fn new(gos_from_decal: &Affine3A, material: &Material) -> Self {
+        Self {
+            decal_from_gos: Mat4::from(gos_from_decal.inverse()),
+            packed_flags_and_base_texture_id: material.packed_flags_and_base_texture_id,
+            packed_base_color_factor: material.packed_base_color_factor,
+            _padding: [0; 2],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use glam::{Quat, Vec3};
+
+    #[test]
+    pub fn test_decal_data() {
+        let gos_from_decal = Affine3A::from_scale_rotation_translation(
+            Vec3::new(0.5, 0.5, 0.1),
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            Vec3::new(1., 2., 3.),
+        );
+        let material = Material::unlit_white();
+        let decal = DecalData::new(&gos_from_decal, &material);
+
+        // The centre of the decal maps to the centre of the box..
+        assert_relative_eq!(
+            decal.decal_from_gos.transform_point3(Vec3::new(1., 2., 3.)),
+            Vec3::ZERO,
+            epsilon = 0.0001
+        );
+        // ..and a corner maps to the corner of the box.
+        let corner = gos_from_decal.transform_point3(Vec3::splat(0.5));
+        assert_relative_eq!(
+            decal.decal_from_gos.transform_point3(corner),
+            Vec3::splat(0.5),
+            epsilon = 0.0001
+        );
+
+        assert_eq!(decal.packed_base_color_factor, u32::MAX);
+        assert_eq!(std::mem::size_of::<DecalData>(), 80);
+    }
+}
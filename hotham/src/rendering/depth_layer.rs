@@ -0,0 +1,398 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::{render_context::create_shader, VulkanContext},
+    rendering::{image::Image, texture::DEFAULT_COMPONENT_MAPPING},
+    DEPTH_FORMAT, VIEW_COUNT, VIEW_MASK,
+};
+
+static FULLSCREEN_VERT: &[u32] =
+    include_glsl!("src/shaders/bloom_composite.vert", target: vulkan1_1);
+static RESOLVE_FRAG: &[u32] = include_glsl!("src/shaders/depth_resolve.frag", target: vulkan1_1);
+static RESOLVE_MS_FRAG: &[u32] =
+    include_glsl!("src/shaders/depth_resolve_ms.frag", target: vulkan1_1);
+
+/// Copies the depth buffer into the OpenXR depth swapchain each frame, so it can be submitted to the compositor
+/// with `XR_KHR_composition_layer_depth`. Runtimes use it to reproject the frame when the head moves, which
+/// reduces judder.
+///
+/// The depth buffer is multisampled and the swapchain isn't, so it is resolved with a fullscreen draw that keeps
+/// the nearest sample of each pixel.
+pub struct DepthLayer {
+    /// Render pass that writes to a single depth swapchain image
+    pub render_pass: vk::RenderPass,
+    /// One framebuffer per depth swapchain image
+    pub framebuffers: Vec<vk::Framebuffer>,
+    /// Layout of the resolve pipeline
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Writes the depth of each pixel with `gl_FragDepth`
+    pub pipeline: vk::Pipeline,
+    /// Layout of `descriptor_set`
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    /// Pool `descriptor_set` is allocated from
+    pub descriptor_pool: vk::DescriptorPool,
+    /// Binds the depth buffer
+    pub descriptor_set: vk::DescriptorSet,
+    /// Nearest sampler used to read the depth buffer
+    pub sampler: vk::Sampler,
+    /// The depth buffer of the PBR render pass
+    depth_image: vk::Image,
+    render_area: vk::Rect2D,
+}
+
+impl DepthLayer {
+    /// Create a depth layer that copies `depth_image` into `swapchain_images`.
+    /// `depth_image` must have been created with `SAMPLED` usage and stored by the render pass.
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        depth_image: &Image,
+        swapchain_images: &[vk::Image],
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let render_area = vk::Rect2D {
+            extent: depth_image.extent,
+            ..Default::default()
+        };
+
+        let render_pass = create_render_pass(device)?;
+        let framebuffers = swapchain_images
+            .iter()
+            .map(|image| {
+                let view = vulkan_context.create_image_view(
+                    image,
+                    DEPTH_FORMAT,
+                    vk::ImageViewType::TYPE_2D_ARRAY,
+                    VIEW_COUNT,
+                    1,
+                    DEFAULT_COMPONENT_MAPPING,
+                )?;
+                let create_info = vk::FramebufferCreateInfo::builder()
+                    .render_pass(render_pass)
+                    .attachments(slice_from_ref(&view))
+                    .width(render_area.extent.width)
+                    .height(render_area.extent.height)
+                    .layers(1); // NOTE: multiview takes care of layers.
+                unsafe { device.create_framebuffer(&create_info, None) }.map_err(Into::into)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let sampler = unsafe {
+            device.create_sampler(
+                &vk::SamplerCreateInfo::builder()
+                    .mag_filter(vk::Filter::NEAREST)
+                    .min_filter(vk::Filter::NEAREST)
+                    .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                    .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                    .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+                    .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+                    .max_lod(1.0),
+                None,
+            )
+        }?;
+
+        let binding = vk::DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            ..Default::default()
+        };
+        let descriptor_set_layout = unsafe {
+            device.create_descriptor_set_layout(
+                &vk::DescriptorSetLayoutCreateInfo::builder().bindings(slice_from_ref(&binding)),
+                None,
+            )
+        }?;
+        let pool_size = vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+        };
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(slice_from_ref(&pool_size))
+                    .max_sets(1),
+                None,
+            )
+        }?;
+        let descriptor_set = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(slice_from_ref(&descriptor_set_layout)),
+            )
+        }?[0];
+        unsafe {
+            let image_info = vk::DescriptorImageInfo {
+                sampler,
+                image_view: depth_image.view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            };
+            device.update_descriptor_sets(
+                slice_from_ref(
+                    &vk::WriteDescriptorSet::builder()
+                        .dst_set(descriptor_set)
+                        .dst_binding(0)
+                        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                        .image_info(slice_from_ref(&image_info)),
+                ),
+                &[],
+            );
+        }
+
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptor_set_layout)),
+                None,
+            )
+        }?;
+        let fragment_shader = if msaa_samples == vk::SampleCountFlags::TYPE_1 {
+            RESOLVE_FRAG
+        } else {
+            RESOLVE_MS_FRAG
+        };
+        let pipeline = create_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            render_pass,
+            &render_area,
+            fragment_shader,
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::RENDER_PASS,
+            render_pass.as_raw(),
+            "Depth Layer Render Pass",
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::PIPELINE,
+            pipeline.as_raw(),
+            "Depth Layer Pipeline",
+        )?;
+
+        Ok(Self {
+            render_pass,
+            framebuffers,
+            pipeline_layout,
+            pipeline,
+            descriptor_set_layout,
+            descriptor_pool,
+            descriptor_set,
+            sampler,
+            depth_image: depth_image.handle,
+            render_area,
+        })
+    }
+
+    /// Copy the depth buffer of the frame that was just rendered into the depth swapchain image at
+    /// `swapchain_image_index`. If `depth_readable` is set the depth buffer has already been transitioned to
+    /// `SHADER_READ_ONLY_OPTIMAL`, eg. by occlusion culling.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state, after the PBR render pass has ended.
+    pub(crate) unsafe fn resolve(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        swapchain_image_index: usize,
+        depth_readable: bool,
+    ) {
+        if !depth_readable {
+            let depth_barrier = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(self.depth_image)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: VIEW_COUNT,
+                })
+                .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ);
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&depth_barrier),
+            );
+        }
+
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffers[swapchain_image_index])
+            .render_area(self.render_area);
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_begin_info,
+            vk::SubpassContents::INLINE,
+        );
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            slice_from_ref(&self.descriptor_set),
+            &[],
+        );
+        device.cmd_draw(command_buffer, 3, 1, 0, 0);
+        device.cmd_end_render_pass(command_buffer);
+
+        // Don't let the next frame write to the depth buffer until we're done reading from it.
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[],
+        );
+    }
+}
+
+fn create_render_pass(device: &ash::Device) -> Result<vk::RenderPass> {
+    // The compositor reads the depth swapchain images in this layout once they've been released.
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(DEPTH_FORMAT)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let depth_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .depth_stencil_attachment(&depth_reference);
+
+    let dependency = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+        .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+
+    let view_masks = [VIEW_MASK];
+    let mut multiview = vk::RenderPassMultiviewCreateInfo::builder()
+        .view_masks(&view_masks)
+        .correlation_masks(&view_masks);
+
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(slice_from_ref(&depth_attachment))
+        .subpasses(slice_from_ref(&subpass))
+        .dependencies(slice_from_ref(&dependency))
+        .push_next(&mut multiview);
+
+    unsafe { device.create_render_pass(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_pass: vk::RenderPass,
+    render_area: &vk::Rect2D,
+    fragment_shader: &[u32],
+) -> Result<vk::Pipeline> {
+    let device = &vulkan_context.device;
+    let (vertex_module, vertex_stage) = create_shader(
+        FULLSCREEN_VERT,
+        vk::ShaderStageFlags::VERTEX,
+        vulkan_context,
+    )?;
+    let (fragment_module, fragment_stage) = create_shader(
+        fragment_shader,
+        vk::ShaderStageFlags::FRAGMENT,
+        vulkan_context,
+    )?;
+    let stages = [vertex_stage, fragment_stage];
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder();
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+    let viewport = vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: render_area.extent.width as _,
+        height: render_area.extent.height as _,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    };
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(slice_from_ref(&viewport))
+        .scissors(slice_from_ref(render_area));
+
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .polygon_mode(vk::PolygonMode::FILL)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .line_width(1.0);
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+    // Every pixel is overwritten, whatever was there before.
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::ALWAYS)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder().logic_op_enable(false);
+
+    let create_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(&stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    let pipelines = unsafe {
+        device.create_graphics_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    unsafe {
+        device.destroy_shader_module(vertex_module, None);
+        device.destroy_shader_module(fragment_module, None);
+    }
+
+    Ok(pipelines[0])
+}
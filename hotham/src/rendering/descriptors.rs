@@ -8,11 +8,27 @@ pub const SKINS_BINDING: u32 = 1;
 pub const SCENE_DATA_BINDING: u32 = 2;
 pub const TEXTURE_BINDING: u32 = 3;
 pub const CUBE_TEXTURE_BINDING: u32 = 4;
+pub const SHADOW_MAP_BINDING: u32 = 5;
+pub const LOCAL_SHADOW_MAP_BINDING: u32 = 6;
+pub const MORPH_TARGETS_BINDING: u32 = 7;
+pub const MORPH_WEIGHTS_BINDING: u32 = 8;
+pub const DECALS_BINDING: u32 = 9;
+pub const CLUSTERED_LIGHTS_BINDING: u32 = 10;
+pub const LIGHT_CLUSTERS_BINDING: u32 = 11;
+pub const LIGHT_INDICES_BINDING: u32 = 12;
 
 pub const PRIMITIVE_CULL_DATA_BINDING: u32 = 0;
 pub const CULL_PARAMS_BINDING: u32 = 1;
 
 const TEXTURE_BINDING_DESCRIPTOR_COUNT: u32 = 10_000;
+const CUBE_TEXTURE_BINDING_DESCRIPTOR_COUNT: u32 = 100;
+
+/// The number of storage buffers in a graphics descriptor set
+pub(crate) const GRAPHICS_STORAGE_BUFFER_COUNT: u32 = 8;
+
+/// The number of images in a graphics descriptor set: the texture arrays plus the two shadow maps
+pub(crate) const GRAPHICS_IMAGE_DESCRIPTOR_COUNT: u32 =
+    TEXTURE_BINDING_DESCRIPTOR_COUNT + CUBE_TEXTURE_BINDING_DESCRIPTOR_COUNT + 2;
 
 /// A wrapper around all the various bits of descriptor functionality
 #[derive(Clone, Debug)]
@@ -103,6 +119,88 @@ impl Descriptors {
             .device
             .update_descriptor_sets(&texture_writes, &[]);
     }
+
+    pub unsafe fn write_shadow_map_descriptors(
+        &self,
+        vulkan_context: &VulkanContext,
+        image_view: vk::ImageView,
+        local_image_view: vk::ImageView,
+        sampler: vk::Sampler,
+    ) {
+        let image_info = vk::DescriptorImageInfo {
+            sampler,
+            image_view,
+            image_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        };
+        let local_image_info = vk::DescriptorImageInfo {
+            sampler,
+            image_view: local_image_view,
+            image_layout: vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        };
+
+        let shadow_map_writes = self
+            .sets
+            .iter()
+            .flat_map(|set| {
+                [
+                    vk::WriteDescriptorSet::builder()
+                        .image_info(std::slice::from_ref(&image_info))
+                        .dst_binding(SHADOW_MAP_BINDING)
+                        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                        .dst_set(*set)
+                        .build(),
+                    vk::WriteDescriptorSet::builder()
+                        .image_info(std::slice::from_ref(&local_image_info))
+                        .dst_binding(LOCAL_SHADOW_MAP_BINDING)
+                        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                        .dst_set(*set)
+                        .build(),
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        vulkan_context
+            .device
+            .update_descriptor_sets(&shadow_map_writes, &[]);
+    }
+
+    /// Copy every descriptor in the graphics set for `frame_index` into `dst_set`, apart from the scene data and draw
+    /// data. This lets a pass with scene data and draw data of its own share everything else. Only the first `texture_count` textures and
+    /// `cube_texture_count` cube textures are copied, as the rest have never been written.
+    pub(crate) unsafe fn copy_graphics_set(
+        &self,
+        vulkan_context: &VulkanContext,
+        frame_index: usize,
+        dst_set: vk::DescriptorSet,
+        texture_count: u32,
+        cube_texture_count: u32,
+    ) {
+        let bindings = [
+            (SKINS_BINDING, 1),
+            (TEXTURE_BINDING, texture_count),
+            (CUBE_TEXTURE_BINDING, cube_texture_count),
+            (SHADOW_MAP_BINDING, 1),
+            (LOCAL_SHADOW_MAP_BINDING, 1),
+            (MORPH_TARGETS_BINDING, 1),
+            (MORPH_WEIGHTS_BINDING, 1),
+            (DECALS_BINDING, 1),
+            (CLUSTERED_LIGHTS_BINDING, 1),
+            (LIGHT_CLUSTERS_BINDING, 1),
+            (LIGHT_INDICES_BINDING, 1),
+        ];
+
+        let copies = bindings.map(|(binding, descriptor_count)| {
+            vk::CopyDescriptorSet::builder()
+                .src_set(self.sets[frame_index])
+                .src_binding(binding)
+                .dst_set(dst_set)
+                .dst_binding(binding)
+                .descriptor_count(descriptor_count)
+                .build()
+        });
+
+        vulkan_context.device.update_descriptor_sets(&[], &copies);
+    }
 }
 
 unsafe fn allocate_descriptor_sets(
@@ -186,7 +284,71 @@ unsafe fn create_descriptor_layouts(
             binding: CUBE_TEXTURE_BINDING,
             descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
             stage_flags: vk::ShaderStageFlags::FRAGMENT,
-            descriptor_count: 100,
+            descriptor_count: CUBE_TEXTURE_BINDING_DESCRIPTOR_COUNT,
+            ..Default::default()
+        },
+        // Shadow Map
+        vk::DescriptorSetLayoutBinding {
+            binding: SHADOW_MAP_BINDING,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Local Shadow Maps
+        vk::DescriptorSetLayoutBinding {
+            binding: LOCAL_SHADOW_MAP_BINDING,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Morph Targets
+        vk::DescriptorSetLayoutBinding {
+            binding: MORPH_TARGETS_BINDING,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Morph Weights
+        vk::DescriptorSetLayoutBinding {
+            binding: MORPH_WEIGHTS_BINDING,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Decals
+        vk::DescriptorSetLayoutBinding {
+            binding: DECALS_BINDING,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Clustered Lights
+        vk::DescriptorSetLayoutBinding {
+            binding: CLUSTERED_LIGHTS_BINDING,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Light Clusters
+        vk::DescriptorSetLayoutBinding {
+            binding: LIGHT_CLUSTERS_BINDING,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            descriptor_count: 1,
+            ..Default::default()
+        },
+        // Light Indices
+        vk::DescriptorSetLayoutBinding {
+            binding: LIGHT_INDICES_BINDING,
+            descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            descriptor_count: 1,
             ..Default::default()
         },
     ];
@@ -218,6 +380,11 @@ unsafe fn create_descriptor_layouts(
         vk::DescriptorBindingFlags::empty(),
         flags,
         flags,
+        vk::DescriptorBindingFlags::empty(),
+        vk::DescriptorBindingFlags::empty(),
+        vk::DescriptorBindingFlags::empty(),
+        vk::DescriptorBindingFlags::empty(),
+        vk::DescriptorBindingFlags::empty(),
     ];
     let mut binding_flags = vk::DescriptorSetLayoutBindingFlagsCreateInfoEXT::builder()
         .binding_flags(&descriptor_flags);
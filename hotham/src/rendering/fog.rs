@@ -0,0 +1,112 @@
+use glam::{Affine3A, Vec3, Vec4};
+
+/// Settings for fog: the scene fading into `color` with distance, so large environments don't end abruptly.
+///
+/// Fog is calculated in the PBR fragment shader, before tonemapping. Distance fog thickens exponentially with the
+/// distance from the camera. Height fog is densest at `base_height` and thins out exponentially above it, so it
+/// pools in valleys and low ground. The skybox isn't fogged, so match `color` to the horizon of the
+/// [`Skybox`](crate::components::Skybox) for distant geometry to blend into it.
+///
+/// These settings can be changed at any time, and take effect from the next frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fog {
+    /// Whether or not fog is rendered at all
+    pub enabled: bool,
+    /// The color of the fog, in linear space
+    pub color: Vec3,
+    /// How quickly distance fog thickens, per metre. Zero disables distance fog.
+    pub density: f32,
+    /// Fog that collects close to the ground, if any
+    pub height_fog: Option<HeightFog>,
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [0.7, 0.8, 0.9].into(),
+            density: 0.02,
+            height_fog: None,
+        }
+    }
+}
+
+/// Fog that is densest at a given height, and thins out above it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeightFog {
+    /// The height where the fog has its full density, in global space
+    pub base_height: f32,
+    /// The density of the fog at `base_height`, per metre
+    pub density: f32,
+    /// How quickly the fog thins out above `base_height`. Larger values give a thinner layer of fog.
+    pub falloff: f32,
+}
+
+impl Default for HeightFog {
+    fn default() -> Self {
+        Self {
+            base_height: 0.,
+            density: 0.1,
+            falloff: 0.5,
+        }
+    }
+}
+
+impl Fog {
+    /// Parameters passed to the shaders in `SceneData`, in globally oriented stage space.
+    /// Returns (color and distance density, height fog density, falloff and base height)
+    pub(crate) fn params(&self, gos_from_global: &Affine3A) -> (Vec4, Vec4) {
+        if !self.enabled {
+            return (Vec4::ZERO, Vec4::ZERO);
+        }
+
+        let fog_params = self.color.extend(self.density.max(0.));
+        let height_fog_params = match self.height_fog {
+            Some(height_fog) => {
+                let base_height = gos_from_global
+                    .transform_point3(Vec3::Y * height_fog.base_height)
+                    .y;
+                [
+                    height_fog.density.max(0.),
+                    height_fog.falloff.max(0.),
+                    base_height,
+                    0.,
+                ]
+                .into()
+            }
+            None => Vec4::ZERO,
+        };
+
+        (fog_params, height_fog_params)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_fog_params() {
+        let gos_from_global = Affine3A::from_translation([1., -2., 3.].into());
+
+        // Disabled fog sends no density at all
+        let (fog_params, height_fog_params) = Fog::default().params(&gos_from_global);
+        assert_eq!(fog_params, Vec4::ZERO);
+        assert_eq!(height_fog_params, Vec4::ZERO);
+
+        // The base height of height fog is moved into globally oriented stage space
+        let fog = Fog {
+            enabled: true,
+            color: Vec3::ONE,
+            density: 0.05,
+            height_fog: Some(HeightFog {
+                base_height: 1.,
+                density: 0.2,
+                falloff: 0.5,
+            }),
+        };
+        let (fog_params, height_fog_params) = fog.params(&gos_from_global);
+        assert_eq!(fog_params, [1., 1., 1., 0.05].into());
+        assert_eq!(height_fog_params, [0.2, 0.5, -1., 0.].into());
+    }
+}
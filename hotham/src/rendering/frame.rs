@@ -1,20 +1,30 @@
-use ash::vk;
+use ash::vk::{self, Handle};
 
 use crate::contexts::{render_context::CullParams, VulkanContext};
 use anyhow::Result;
 
 use super::{
     buffer::Buffer,
+    decal::{DecalData, MAX_DECALS},
     descriptors::{
-        Descriptors, CULL_PARAMS_BINDING, DRAW_DATA_BINDING, PRIMITIVE_CULL_DATA_BINDING,
-        SCENE_DATA_BINDING,
+        Descriptors, CLUSTERED_LIGHTS_BINDING, CULL_PARAMS_BINDING, DECALS_BINDING,
+        DRAW_DATA_BINDING, LIGHT_CLUSTERS_BINDING, LIGHT_INDICES_BINDING, MORPH_WEIGHTS_BINDING,
+        PRIMITIVE_CULL_DATA_BINDING, SCENE_DATA_BINDING,
     },
-    resources::{DrawData, PrimitiveCullData},
+    light::Light,
+    light_clustering::{
+        LightCluster, CLUSTER_COUNT, MAX_CLUSTERED_LIGHTS, MAX_CLUSTER_LIGHT_INDICES,
+    },
+    resources::{DrawData, PrimitiveCullData, MAX_MORPH_TARGETS},
     scene_data::SceneData,
 };
 
-// We *can* draw this many objects, but.. seriously?
-static DRAW_DATA_BUFFER_SIZE: usize = 5000;
+// We *can* draw this many objects, but.. seriously? Shared by every pass in a frame that draws meshes, apart from
+// render targets which have buffers of their own, and must match the size of the `DrawData` arrays in the shaders.
+pub(crate) static DRAW_DATA_BUFFER_SIZE: usize = 5000;
+
+// The number of entities with morph targets that can be drawn each frame.
+static MORPH_WEIGHTS_BUFFER_SIZE: usize = 1000;
 
 // We *can* draw this many objects, but.. seriously?
 static PRIMITIVE_CULL_DATA_BUFFER_SIZE: usize = 100_000;
@@ -22,16 +32,25 @@ static PRIMITIVE_CULL_DATA_BUFFER_SIZE: usize = 100_000;
 /// A container for all the resources necessary to render a single frame.
 #[derive(Debug, Clone)]
 pub struct Frame {
-    /// The fence used to signal when the frame has completed rendering
-    pub fence: vk::Fence,
+    /// The value of the render context's timeline semaphore once this frame's commands have completed.
+    /// Zero if the frame has never been submitted.
+    pub timeline_value: u64,
     /// A command buffer used to record commands
     pub command_buffer: vk::CommandBuffer,
-    /// The fence used to signal when the frame has completed rendering
-    pub compute_fence: vk::Fence,
     /// A command buffer used to record commands
     pub compute_command_buffer: vk::CommandBuffer,
     /// Data for the primitives that will be drawn this frame, indexed by gl_InstanceId
     pub draw_data_buffer: Buffer<DrawData>,
+    /// Morph target weights of the entities drawn this frame, indexed by `morph_weights_id` in DrawData
+    pub morph_weights_buffer: Buffer<[f32; MAX_MORPH_TARGETS]>,
+    /// The decals drawn this frame
+    pub decals_buffer: Buffer<DecalData>,
+    /// The point and spot lights drawn this frame that are assigned to clusters, in globally oriented stage space
+    pub clustered_lights_buffer: Buffer<Light>,
+    /// The range of `light_indices_buffer` used by each cluster
+    pub light_clusters_buffer: Buffer<LightCluster>,
+    /// Indices into `clustered_lights_buffer`, grouped by cluster
+    pub light_indices_buffer: Buffer<u32>,
     /// The actual draw calls for this frame.
     pub primitive_cull_data_buffer: Buffer<PrimitiveCullData>,
     /// Shared data used in a scene
@@ -49,14 +68,6 @@ impl Frame {
         let device = &vulkan_context.device;
         let command_pool = vulkan_context.command_pool;
 
-        let fence = unsafe {
-            device.create_fence(
-                &vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED),
-                None,
-            )
-        }?;
-        let compute_fence = unsafe { device.create_fence(&vk::FenceCreateInfo::builder(), None) }?;
-
         let command_buffers = unsafe {
             device.allocate_command_buffers(
                 &vk::CommandBufferAllocateInfo::builder()
@@ -76,6 +87,41 @@ impl Frame {
                 DRAW_DATA_BUFFER_SIZE,
             )
         };
+        let morph_weights_buffer = unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                MORPH_WEIGHTS_BUFFER_SIZE,
+            )
+        };
+        let decals_buffer = unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                MAX_DECALS,
+            )
+        };
+        let clustered_lights_buffer = unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                MAX_CLUSTERED_LIGHTS,
+            )
+        };
+        let light_clusters_buffer = unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                CLUSTER_COUNT,
+            )
+        };
+        let light_indices_buffer = unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                MAX_CLUSTER_LIGHT_INDICES,
+            )
+        };
         let primitive_cull_data_buffer = unsafe {
             Buffer::new(
                 vulkan_context,
@@ -96,6 +142,31 @@ impl Frame {
                 descriptors.sets[index],
                 DRAW_DATA_BINDING,
             );
+            morph_weights_buffer.update_descriptor_set(
+                &vulkan_context.device,
+                descriptors.sets[index],
+                MORPH_WEIGHTS_BINDING,
+            );
+            decals_buffer.update_descriptor_set(
+                &vulkan_context.device,
+                descriptors.sets[index],
+                DECALS_BINDING,
+            );
+            clustered_lights_buffer.update_descriptor_set(
+                &vulkan_context.device,
+                descriptors.sets[index],
+                CLUSTERED_LIGHTS_BINDING,
+            );
+            light_clusters_buffer.update_descriptor_set(
+                &vulkan_context.device,
+                descriptors.sets[index],
+                LIGHT_CLUSTERS_BINDING,
+            );
+            light_indices_buffer.update_descriptor_set(
+                &vulkan_context.device,
+                descriptors.sets[index],
+                LIGHT_INDICES_BINDING,
+            );
             scene_data_buffer.update_descriptor_set(
                 &vulkan_context.device,
                 descriptors.sets[index],
@@ -118,15 +189,99 @@ impl Frame {
             scene_data_buffer.push(&Default::default());
         }
 
+        let debug_names = [
+            (
+                vk::ObjectType::COMMAND_BUFFER,
+                command_buffer.as_raw(),
+                "Command Buffer",
+            ),
+            (
+                vk::ObjectType::COMMAND_BUFFER,
+                compute_command_buffer.as_raw(),
+                "Compute Command Buffer",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                draw_data_buffer.buffer.as_raw(),
+                "Draw Data",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                morph_weights_buffer.buffer.as_raw(),
+                "Morph Weights",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                decals_buffer.buffer.as_raw(),
+                "Decals",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                clustered_lights_buffer.buffer.as_raw(),
+                "Clustered Lights",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                light_clusters_buffer.buffer.as_raw(),
+                "Light Clusters",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                light_indices_buffer.buffer.as_raw(),
+                "Light Indices",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                primitive_cull_data_buffer.buffer.as_raw(),
+                "Primitive Cull Data",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                scene_data_buffer.buffer.as_raw(),
+                "Scene Data",
+            ),
+            (
+                vk::ObjectType::BUFFER,
+                cull_params_buffer.buffer.as_raw(),
+                "Cull Params",
+            ),
+        ];
+        for (object_type, handle, name) in debug_names {
+            vulkan_context.set_debug_name(
+                object_type,
+                handle,
+                &format!("{name} (Frame {index})"),
+            )?;
+        }
+
         Ok(Self {
-            fence,
-            compute_fence,
+            timeline_value: 0,
             command_buffer,
             compute_command_buffer,
             draw_data_buffer,
+            morph_weights_buffer,
+            decals_buffer,
+            clustered_lights_buffer,
+            light_clusters_buffer,
+            light_indices_buffer,
             primitive_cull_data_buffer,
             scene_data_buffer,
             cull_params_buffer,
         })
     }
 }
+
+/// Add the draw data for an instance to `draw_data_buffer`, returning its index, or `None` if the buffer is full and
+/// the instance can't be drawn.
+pub(crate) fn push_draw_data(
+    draw_data_buffer: &mut Buffer<DrawData>,
+    draw_data: &DrawData,
+) -> Option<u32> {
+    let index = unsafe { draw_data_buffer.try_push(draw_data) };
+    if draw_data_buffer.len() == draw_data_buffer.max_len && index.is_some() {
+        println!(
+            "[HOTHAM_RENDER] WARNING: The draw data buffer is full, no more instances will be drawn this frame!"
+        );
+    }
+    index
+}
@@ -1,4 +1,4 @@
-use glam::{Quat, Vec3};
+use glam::{Affine3A, Vec3};
 use serde::{Deserialize, Serialize};
 
 /// A directional light.
@@ -11,9 +11,14 @@ pub const LIGHT_TYPE_SPOT: u32 = 2;
 pub const LIGHT_TYPE_NONE: u32 = u32::MAX;
 /// Maximum number of dynamic lights in a scene
 pub const MAX_LIGHTS: usize = 4;
+/// Indicates to the fragment shader that a light has no shadow map
+pub const NO_SHADOW: u32 = u32::MAX;
 
 /// Representation of a light in a scene, based on the KHR_lights_punctual extension:
 /// https://github.com/KhronosGroup/glTF/tree/master/extensions/2.0/Khronos/KHR_lights_punctual
+///
+/// Lights can also be used as components, in which case `position` and `direction` are relative to the entity's
+/// [`crate::components::GlobalTransform`]. See [`crate::systems::lights_system`].
 #[derive(Deserialize, Serialize, Clone, Debug, Default)]
 #[repr(C, align(16))]
 pub struct Light {
@@ -39,6 +44,9 @@ pub struct Light {
     pub light_angle_offset: f32,
     /// The type of the light. LIGHT_TYPE_NONE indicates to the fragment shader that this light is empty.
     pub light_type: u32,
+
+    /// Index of the first local shadow map used by this light, or NO_SHADOW. Assigned by the renderer each frame.
+    pub shadow_index: u32,
 }
 
 impl Light {
@@ -73,6 +81,7 @@ impl Light {
             light_angle_scale: scale,
             light_angle_offset: offset,
             light_type: LIGHT_TYPE_SPOT,
+            shadow_index: NO_SHADOW,
         }
     }
 
@@ -102,12 +111,20 @@ impl Light {
     pub(crate) fn from_gltf(light: &gltf::khr_lights_punctual::Light, node: &gltf::Node) -> Self {
         // TODO: Technically scale could apply here as well.
         let (translation, rotation, _) = node.transform().decomposed();
-        let rotation = Quat::from_array(rotation);
+        let transform = Affine3A::from_rotation_translation(
+            glam::Quat::from_array(rotation),
+            translation.into(),
+        );
+        Light::from_gltf_local(light).transformed(&transform)
+    }
+
+    /// Create a light from a glTF light in the local space of its node: at the origin, facing down -Z.
+    pub(crate) fn from_gltf_local(light: &gltf::khr_lights_punctual::Light) -> Self {
         let intensity = light.intensity();
         let color = light.color().into();
         let range = light.range().unwrap_or(-1.);
-        let direction = rotation * Vec3::NEG_Z;
-        let position = translation.into();
+        let direction = Vec3::NEG_Z;
+        let position = Vec3::ZERO;
 
         match light.kind() {
             gltf::khr_lights_punctual::Kind::Directional => {
@@ -130,6 +147,17 @@ impl Light {
             ),
         }
     }
+
+    /// Return a copy of this light with its position and direction transformed by `transform`.
+    pub fn transformed(&self, transform: &Affine3A) -> Self {
+        Self {
+            position: transform.transform_point3(self.position),
+            direction: transform
+                .transform_vector3(self.direction)
+                .normalize_or_zero(),
+            ..self.clone()
+        }
+    }
 }
 
 fn get_offet_and_scale(inner_cone_angle: f32, outer_cone_angle: f32) -> (f32, f32) {
@@ -0,0 +1,353 @@
+use glam::{Affine3A, Mat4, Vec3, Vec4};
+
+use super::{camera::Frustum, light::Light};
+
+/// The maximum number of clustered lights drawn each frame
+pub const MAX_CLUSTERED_LIGHTS: usize = 256;
+/// The number of clusters across the view
+pub const CLUSTER_COUNT_X: usize = 16;
+/// The number of clusters down the view
+pub const CLUSTER_COUNT_Y: usize = 8;
+/// The number of depth slices the view is divided into
+pub const CLUSTER_COUNT_Z: usize = 24;
+/// The total number of clusters
+pub const CLUSTER_COUNT: usize = CLUSTER_COUNT_X * CLUSTER_COUNT_Y * CLUSTER_COUNT_Z;
+/// The maximum number of light indices shared between all clusters
+pub const MAX_CLUSTER_LIGHT_INDICES: usize = 32_768;
+
+/// Distance to the far edge of the first depth slice, which starts at the frustum's origin
+const CLUSTER_NEAR: f32 = 0.1;
+/// Distance to the near edge of the last depth slice, which goes on forever
+const CLUSTER_FAR: f32 = 100.;
+
+/// The range of a cluster's lights in the light index list, as it's sent to the fragment shader
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LightCluster {
+    /// Index of the cluster's first light index
+    pub offset: u32,
+    /// The number of lights touching the cluster
+    pub count: u32,
+}
+
+/// The volume divided up into clusters: a single frustum that encloses the views of both eyes.
+///
+/// The volume is split into a grid of tiles across the view, and into slices that grow exponentially with depth.
+/// Fragments look up the cluster they're in from their position, so it doesn't matter which eye they're in.
+#[derive(Debug, Clone, Copy)]
+pub struct ClusterFrustum {
+    /// Transforms points in globally oriented stage space into the frustum's space. Like a camera, it looks down
+    /// its negative Z axis.
+    pub view_from_gos: Affine3A,
+    /// Tangents of the frustum's left, right, down and up angles
+    pub tangents: Vec4,
+}
+
+impl ClusterFrustum {
+    /// Create a frustum enclosing the views of both eyes. The eyes are assumed to face the same way.
+    ///
+    /// The frustum starts a little behind the eyes, so that its sides take in what each eye can see to the side
+    /// of the other.
+    pub fn new(gos_from_views: [&Affine3A; 2], frusta: [Frustum; 2]) -> Self {
+        let left = frusta[0].left.min(frusta[1].left).tan();
+        let right = frusta[0].right.max(frusta[1].right).tan();
+        let down = frusta[0].down.min(frusta[1].down).tan();
+        let up = frusta[0].up.max(frusta[1].up).tan();
+
+        let left_eye = Vec3::from(gos_from_views[0].translation);
+        let right_eye = Vec3::from(gos_from_views[1].translation);
+        let center = (left_eye + right_eye) * 0.5;
+        let half_separation = left_eye.distance(right_eye) * 0.5;
+        let pull_back = half_separation / left.abs().min(right.abs()).max(0.1);
+
+        let mut gos_from_view = *gos_from_views[0];
+        gos_from_view.translation =
+            (center + gos_from_view.transform_vector3(Vec3::Z) * pull_back).into();
+
+        Self {
+            view_from_gos: gos_from_view.inverse(),
+            tangents: [left, right, down, up].into(),
+        }
+    }
+
+    /// The parameters sent to the fragment shader: the matrix transforming points in globally oriented stage space
+    /// into the frustum's space, the tangents of its sides, and its depth parameters - x = one over the depth of
+    /// the first slice's far edge, y = slices per unit of log depth, zw = unused.
+    pub fn params(&self) -> (Mat4, Vec4, Vec4) {
+        (
+            Mat4::from(self.view_from_gos),
+            self.tangents,
+            [1. / CLUSTER_NEAR, slice_scale(), 0., 0.].into(),
+        )
+    }
+
+    /// The range of tiles covering tangents from `min` to `max`, along one axis. `None` if the range misses the
+    /// frustum entirely.
+    fn tile_range(min: f32, max: f32, low: f32, high: f32, count: usize) -> Option<(usize, usize)> {
+        if max < low || min > high {
+            return None;
+        }
+        let tile = |t: f32| ((t - low) / (high - low) * count as f32).clamp(0., (count - 1) as f32);
+        Some((tile(min) as usize, tile(max) as usize))
+    }
+
+    /// The box in the frustum's space enclosing the cluster at `x`, `y` and `slice`.
+    fn cluster_bounds(&self, x: usize, y: usize, slice: usize) -> (Vec3, Vec3) {
+        let [left, right, down, up] = self.tangents.to_array();
+        let x0 = left + (right - left) * x as f32 / CLUSTER_COUNT_X as f32;
+        let x1 = left + (right - left) * (x + 1) as f32 / CLUSTER_COUNT_X as f32;
+        let y0 = down + (up - down) * y as f32 / CLUSTER_COUNT_Y as f32;
+        let y1 = down + (up - down) * (y + 1) as f32 / CLUSTER_COUNT_Y as f32;
+        let (near, far) = slice_depths(slice);
+
+        let min = Vec3::new((x0 * near).min(x0 * far), (y0 * near).min(y0 * far), -far);
+        let max = Vec3::new((x1 * near).max(x1 * far), (y1 * near).max(y1 * far), -near);
+        (min, max)
+    }
+}
+
+/// Which clustered lights touch each cluster, rebuilt every frame.
+#[derive(Debug, Clone)]
+pub struct LightClusters {
+    /// The range of light indices for each cluster, ordered by slice, then row, then column
+    pub clusters: Vec<LightCluster>,
+    /// Indices into the clustered lights, grouped by cluster
+    pub light_indices: Vec<u32>,
+    /// Scratch space for the lights touching each cluster, kept around to avoid reallocating every frame
+    lights_per_cluster: Vec<Vec<u32>>,
+}
+
+impl Default for LightClusters {
+    fn default() -> Self {
+        Self {
+            clusters: vec![Default::default(); CLUSTER_COUNT],
+            light_indices: Vec::new(),
+            lights_per_cluster: vec![Vec::new(); CLUSTER_COUNT],
+        }
+    }
+}
+
+impl LightClusters {
+    /// Assign every light in `lights`, in globally oriented stage space, to the clusters of `frustum` it touches.
+    /// Spot lights are treated as point lights, which is conservative.
+    pub fn assign(&mut self, frustum: &ClusterFrustum, lights: &[Light]) {
+        for lights in &mut self.lights_per_cluster {
+            lights.clear();
+        }
+
+        let [left, right, down, up] = frustum.tangents.to_array();
+        for (index, light) in lights.iter().enumerate() {
+            if light.falloff <= 0. {
+                continue;
+            }
+            let radius = 1. / light.falloff.sqrt();
+            let center = frustum.view_from_gos.transform_point3(light.position);
+            let nearest = -center.z - radius;
+            let furthest = -center.z + radius;
+            if furthest <= 0. {
+                continue;
+            }
+
+            // Narrow the search down to the tiles covered by the light's bounding box. If the box reaches behind
+            // the frustum's origin, every tile may be covered.
+            let (x_range, y_range) = if nearest <= 0. {
+                (
+                    Some((0, CLUSTER_COUNT_X - 1)),
+                    Some((0, CLUSTER_COUNT_Y - 1)),
+                )
+            } else {
+                let tangent_range = |low: f32, high: f32| {
+                    let tangents = [
+                        low / nearest,
+                        low / furthest,
+                        high / nearest,
+                        high / furthest,
+                    ];
+                    (
+                        tangents.iter().copied().fold(f32::MAX, f32::min),
+                        tangents.iter().copied().fold(f32::MIN, f32::max),
+                    )
+                };
+                let (x_min, x_max) = tangent_range(center.x - radius, center.x + radius);
+                let (y_min, y_max) = tangent_range(center.y - radius, center.y + radius);
+                (
+                    ClusterFrustum::tile_range(x_min, x_max, left, right, CLUSTER_COUNT_X),
+                    ClusterFrustum::tile_range(y_min, y_max, down, up, CLUSTER_COUNT_Y),
+                )
+            };
+            let ((x_first, x_last), (y_first, y_last)) = match (x_range, y_range) {
+                (Some(x_range), Some(y_range)) => (x_range, y_range),
+                _ => continue,
+            };
+
+            for slice in slice_index(nearest)..=slice_index(furthest) {
+                for y in y_first..=y_last {
+                    for x in x_first..=x_last {
+                        let (min, max) = frustum.cluster_bounds(x, y, slice);
+                        if center.clamp(min, max).distance_squared(center) > radius * radius {
+                            continue;
+                        }
+                        self.lights_per_cluster[cluster_index(x, y, slice)].push(index as u32);
+                    }
+                }
+            }
+        }
+
+        // Pack the lists into a single array, as long as there's room.
+        self.light_indices.clear();
+        let mut is_full = false;
+        for (cluster, lights) in self.clusters.iter_mut().zip(&self.lights_per_cluster) {
+            let count = lights
+                .len()
+                .min(MAX_CLUSTER_LIGHT_INDICES - self.light_indices.len());
+            is_full |= count < lights.len();
+            *cluster = LightCluster {
+                offset: self.light_indices.len() as u32,
+                count: count as u32,
+            };
+            self.light_indices.extend_from_slice(&lights[..count]);
+        }
+
+        if is_full {
+            println!("[HOTHAM_LIGHTS] WARNING: Lights touch more than {MAX_CLUSTER_LIGHT_INDICES} clusters in total, some will be ignored!");
+        }
+    }
+}
+
+/// The number of depth slices per unit of log depth. The first and last slices cover the depths closer than
+/// `CLUSTER_NEAR` and further than `CLUSTER_FAR`, and the rest are spread out in between.
+fn slice_scale() -> f32 {
+    (CLUSTER_COUNT_Z - 2) as f32 / (CLUSTER_FAR / CLUSTER_NEAR).ln()
+}
+
+/// The depth slice containing `depth`, matching `getClusterIndex` in `lights.glsl`
+fn slice_index(depth: f32) -> usize {
+    let slice = ((depth / CLUSTER_NEAR).max(1.).ln() * slice_scale()).ceil();
+    slice.min((CLUSTER_COUNT_Z - 1) as f32) as usize
+}
+
+/// The near and far depth of a slice. The first slice starts at the frustum's origin and the last one goes on
+/// forever.
+fn slice_depths(slice: usize) -> (f32, f32) {
+    let depth = |slice: usize| CLUSTER_NEAR * (slice as f32 / slice_scale()).exp();
+    let near = if slice == 0 { 0. } else { depth(slice - 1) };
+    let far = if slice == CLUSTER_COUNT_Z - 1 {
+        f32::MAX
+    } else {
+        depth(slice)
+    };
+    (near, far)
+}
+
+fn cluster_index(x: usize, y: usize, slice: usize) -> usize {
+    (slice * CLUSTER_COUNT_Y + y) * CLUSTER_COUNT_X + x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn frustum() -> ClusterFrustum {
+        // Both eyes at the origin, with a 90 degree field of view.
+        let fov = Frustum {
+            left: -std::f32::consts::FRAC_PI_4,
+            right: std::f32::consts::FRAC_PI_4,
+            down: -std::f32::consts::FRAC_PI_4,
+            up: std::f32::consts::FRAC_PI_4,
+        };
+        ClusterFrustum::new([&Affine3A::IDENTITY, &Affine3A::IDENTITY], [fov, fov])
+    }
+
+    #[test]
+    pub fn test_slices() {
+        // Slices follow on from each other, from the origin to infinity.
+        assert_eq!(slice_depths(0).0, 0.);
+        assert_relative_eq!(slice_depths(0).1, CLUSTER_NEAR);
+        assert_relative_eq!(
+            slice_depths(CLUSTER_COUNT_Z - 1).0,
+            CLUSTER_FAR,
+            epsilon = 0.001
+        );
+        for slice in 1..CLUSTER_COUNT_Z {
+            assert_eq!(slice_depths(slice).0, slice_depths(slice - 1).1);
+        }
+
+        assert_eq!(slice_index(0.), 0);
+        assert_eq!(slice_index(CLUSTER_NEAR * 0.5), 0);
+        assert_eq!(slice_index(1000.), CLUSTER_COUNT_Z - 1);
+        for slice in 0..CLUSTER_COUNT_Z - 1 {
+            let (near, far) = slice_depths(slice);
+            assert_eq!(slice_index((near + far) * 0.5), slice);
+        }
+    }
+
+    #[test]
+    pub fn test_frustum_encloses_both_eyes() {
+        let fov = Frustum {
+            left: -std::f32::consts::FRAC_PI_4,
+            right: std::f32::consts::FRAC_PI_4,
+            down: -std::f32::consts::FRAC_PI_4,
+            up: std::f32::consts::FRAC_PI_4,
+        };
+        let left_eye = Affine3A::from_translation([-0.03, 1.5, 0.].into());
+        let right_eye = Affine3A::from_translation([0.03, 1.5, 0.].into());
+        let frustum = ClusterFrustum::new([&left_eye, &right_eye], [fov, fov]);
+
+        // The frustum starts behind the eyes, so that the edges of both their views are inside it.
+        let origin = frustum.view_from_gos.inverse().translation;
+        assert_relative_eq!(
+            Vec3::from(origin),
+            Vec3::new(0., 1.5, 0.03),
+            epsilon = 0.0001
+        );
+        let edge = frustum
+            .view_from_gos
+            .transform_point3([-1.03, 1.5, -1.].into());
+        assert!(edge.x / -edge.z >= frustum.tangents.x - 0.0001);
+    }
+
+    #[test]
+    pub fn test_assign() {
+        let frustum = frustum();
+        let lights = [
+            // Right in front of the eyes
+            Light::new_point([0., 0., -5.].into(), 1., 1., Vec3::ONE),
+            // Behind the eyes
+            Light::new_point([0., 0., 5.].into(), 1., 1., Vec3::ONE),
+            // Far off to the side
+            Light::new_point([50., 0., -5.].into(), 1., 1., Vec3::ONE),
+        ];
+        let mut clusters = LightClusters::default();
+        clusters.assign(&frustum, &lights);
+
+        // Only the light in front of the eyes is assigned, to the clusters around it.
+        assert!(clusters.light_indices.iter().all(|index| *index == 0));
+        let center = cluster_index(CLUSTER_COUNT_X / 2, CLUSTER_COUNT_Y / 2, slice_index(5.));
+        assert_eq!(clusters.clusters[center].count, 1);
+        let corner = cluster_index(0, 0, slice_index(5.));
+        assert_eq!(clusters.clusters[corner].count, 0);
+        let nearby = cluster_index(CLUSTER_COUNT_X / 2, CLUSTER_COUNT_Y / 2, 0);
+        assert_eq!(clusters.clusters[nearby].count, 0);
+
+        // Every cluster's lights are packed one after another.
+        let mut offset = 0;
+        for cluster in &clusters.clusters {
+            assert_eq!(cluster.offset, offset);
+            offset += cluster.count;
+        }
+        assert_eq!(offset as usize, clusters.light_indices.len());
+
+        // A light surrounding the eyes touches the nearest clusters in every direction.
+        clusters.assign(&frustum, &[Light::new_point(Vec3::ZERO, 1., 1., Vec3::ONE)]);
+        assert_eq!(clusters.clusters[cluster_index(0, 0, 0)].count, 1);
+        assert_eq!(
+            clusters.clusters[cluster_index(CLUSTER_COUNT_X - 1, CLUSTER_COUNT_Y - 1, 0)].count,
+            1
+        );
+        assert_eq!(
+            clusters.clusters[cluster_index(0, 0, slice_index(5.))].count,
+            0
+        );
+    }
+}
@@ -6,6 +6,8 @@ use crate::{
 };
 
 use bitflags::bitflags;
+use glam::{Affine2, Mat2, Vec2};
+use half::f16;
 
 bitflags! {
         /// Flags used by the shader to do shit
@@ -22,6 +24,36 @@ bitflags! {
         const HAS_EMISSION_TEXTURE = 1 << 4;
         /// Are we using unlit workflow?
         const UNLIT_WORKFLOW = 1 << 5;
+        /// Is the material transparent? Transparent materials are blended with whatever is behind them.
+        const ALPHA_BLEND = 1 << 6;
+        /// Does the material transmit light, like glass?
+        const HAS_TRANSMISSION = 1 << 7;
+        /// Do we have a transmission texture?
+        const HAS_TRANSMISSION_TEXTURE = 1 << 8;
+        /// Do we have a thickness texture?
+        const HAS_THICKNESS_TEXTURE = 1 << 9;
+        /// Are back faces drawn? Double sided materials are drawn with culling disabled.
+        const DOUBLE_SIDED = 1 << 10;
+        /// Are fragments with an alpha below the alpha cutoff discarded?
+        const ALPHA_MASK = 1 << 11;
+    }
+}
+
+bitflags! {
+    /// Textures that are sampled with the second set of texture coordinates (`TEXCOORD_1`) instead of the first.
+    pub struct TexCoordSets: u32 {
+        /// The base color texture
+        const BASE_COLOR = 1 << 0;
+        /// The metallic roughness texture, and the occlusion texture packed into it
+        const METALLIC_ROUGHNESS = 1 << 1;
+        /// The normal map
+        const NORMAL = 1 << 2;
+        /// The emission texture
+        const EMISSION = 1 << 3;
+        /// The transmission texture
+        const TRANSMISSION = 1 << 4;
+        /// The thickness texture
+        const THICKNESS = 1 << 5;
     }
 }
 
@@ -39,8 +71,33 @@ pub struct Material {
     pub packed_flags_and_base_texture_id: u32,
     /// The base color of the material
     pub packed_base_color_factor: u32,
-    /// The metallic and roughness factors
+    /// The metallic and roughness factors, and the alpha cutoff of alpha masked materials
     pub packed_metallic_roughness_factor: u32,
+    /// The color of the light emitted by the material. Multiplied with the emission texture, if present.
+    pub packed_emissive_factor: u32,
+    /// The metallic roughness and normal texture IDs are stored as two u16 packed into a single u32. The metallic roughness
+    /// texture ID is stored in the least significant bits.
+    pub packed_metallic_roughness_and_normal_texture_ids: u32,
+    /// The emission texture ID and the emissive strength (as a half float) are stored as two u16 packed into a single
+    /// u32. The emission texture ID is stored in the least significant bits.
+    pub packed_emission_texture_id_and_strength: u32,
+    /// The attenuation color of the volume is stored in the rgb components, and the transmission factor in the alpha
+    /// component.
+    pub packed_transmission_factor_and_attenuation_color: u32,
+    /// The thickness factor and attenuation distance of the volume, stored as two half floats. The thickness factor is
+    /// stored in the least significant bits.
+    pub packed_thickness_factor_and_attenuation_distance: u32,
+    /// The transmission and thickness texture IDs are stored as two u16 packed into a single u32. The transmission
+    /// texture ID is stored in the least significant bits.
+    pub packed_transmission_and_thickness_texture_ids: u32,
+    /// The textures that use the second set of texture coordinates, see [`TexCoordSets`]
+    pub tex_coord_sets: u32,
+    /// The rotation and scale applied to both sets of texture coordinates. The columns of a 2x2 matrix, each stored
+    /// as two half floats.
+    pub packed_uv_matrix: [u32; 2],
+    /// The offset added to both sets of texture coordinates, stored as the bits of two f32 so that slowly scrolling
+    /// textures don't stutter.
+    pub uv_offset: [u32; 2],
 }
 
 impl Default for Material {
@@ -60,6 +117,48 @@ impl Material {
             .map(|i| Texture::load(i.texture(), TextureUsage::BaseColor, import_context))
             .unwrap_or(NO_TEXTURE);
 
+        // Alpha masked materials discard fragments below the cutoff, which defaults to 0.5 as per the spec.
+        let alpha_cutoff = (material.alpha_mode() == gltf::material::AlphaMode::Mask)
+            .then(|| material.alpha_cutoff().unwrap_or(0.5));
+
+        // Unlit materials only use the base color, as per KHR_materials_unlit. Everything else is a fallback for
+        // viewers that don't support the extension, so there's no need to load it.
+        if material.unlit() {
+            let mut material_flags = MaterialFlags::UNLIT_WORKFLOW;
+            if base_color_texture_set != NO_TEXTURE {
+                material_flags.insert(MaterialFlags::HAS_BASE_COLOR_TEXTURE);
+            }
+            if material.alpha_mode() == gltf::material::AlphaMode::Blend {
+                material_flags.insert(MaterialFlags::ALPHA_BLEND);
+            }
+            if material.double_sided() {
+                material_flags.insert(MaterialFlags::DOUBLE_SIDED);
+            }
+
+            let mut material = Material {
+                packed_flags_and_base_texture_id: pack2x16(
+                    material_flags.bits,
+                    base_color_texture_set,
+                ),
+                packed_base_color_factor: pack_unorm4x8(
+                    &pbr_metallic_roughness.base_color_factor(),
+                ),
+                tex_coord_sets: get_tex_coord_sets(&material).bits,
+                ..Material::unlit_white()
+            };
+            if let Some(alpha_cutoff) = alpha_cutoff {
+                material.set_alpha_cutoff(alpha_cutoff);
+            }
+            unsafe {
+                import_context
+                    .render_context
+                    .resources
+                    .materials_buffer
+                    .push(&material);
+            }
+            return;
+        }
+
         // Metallic Roughness
         let metallic_roughness_texture_info = pbr_metallic_roughness.metallic_roughness_texture();
         let metallic_roughness_texture_set = metallic_roughness_texture_info
@@ -106,6 +205,36 @@ impl Material {
         let emissive_texture_set = emissive_texture_info
             .map(|i| Texture::load(i.texture(), TextureUsage::Emission, import_context))
             .unwrap_or(NO_TEXTURE);
+        let emissive_factor = material.emissive_factor();
+        let emissive_strength = material.emissive_strength().unwrap_or(1.0);
+
+        // Transmission and volume, from KHR_materials_transmission and KHR_materials_volume
+        let transmission = material.transmission();
+        let transmission_factor = transmission
+            .as_ref()
+            .map(|t| t.transmission_factor())
+            .unwrap_or(0.0);
+        let transmission_texture_set = transmission
+            .as_ref()
+            .and_then(|t| t.transmission_texture())
+            .map(|i| Texture::load(i.texture(), TextureUsage::Transmission, import_context))
+            .unwrap_or(NO_TEXTURE);
+
+        let volume = material.volume();
+        let thickness_factor = volume.as_ref().map(|v| v.thickness_factor()).unwrap_or(0.0);
+        let attenuation_distance = volume
+            .as_ref()
+            .map(|v| v.attenuation_distance())
+            .unwrap_or(f32::INFINITY);
+        let attenuation_color = volume
+            .as_ref()
+            .map(|v| v.attenuation_color())
+            .unwrap_or([1.0, 1.0, 1.0]);
+        let thickness_texture_set = volume
+            .as_ref()
+            .and_then(|v| v.thickness_texture())
+            .map(|i| Texture::load(i.texture(), TextureUsage::Transmission, import_context))
+            .unwrap_or(NO_TEXTURE);
 
         let mut material_flags = MaterialFlags::empty();
         if base_color_texture_set != NO_TEXTURE {
@@ -128,8 +257,24 @@ impl Material {
             material_flags.insert(MaterialFlags::HAS_AO_TEXTURE);
         }
 
-        if material.unlit() {
-            material_flags.insert(MaterialFlags::UNLIT_WORKFLOW);
+        if transmission_factor > 0.0 {
+            material_flags.insert(MaterialFlags::HAS_TRANSMISSION);
+        }
+
+        if transmission_texture_set != NO_TEXTURE {
+            material_flags.insert(MaterialFlags::HAS_TRANSMISSION_TEXTURE);
+        }
+
+        if thickness_texture_set != NO_TEXTURE {
+            material_flags.insert(MaterialFlags::HAS_THICKNESS_TEXTURE);
+        }
+
+        if material.alpha_mode() == gltf::material::AlphaMode::Blend {
+            material_flags.insert(MaterialFlags::ALPHA_BLEND);
+        }
+
+        if material.double_sided() {
+            material_flags.insert(MaterialFlags::DOUBLE_SIDED);
         }
 
         // Don't allow non-sensical flags
@@ -141,7 +286,7 @@ impl Material {
         );
 
         // Collect the material properties.
-        let material = Material {
+        let mut material = Material {
             packed_flags_and_base_texture_id: pack2x16(material_flags.bits, base_color_texture_set),
             packed_base_color_factor: pack_unorm4x8(&pbr_metallic_roughness.base_color_factor()),
             packed_metallic_roughness_factor: pack_unorm4x8(&[
@@ -150,7 +295,41 @@ impl Material {
                 0.0,
                 0.0,
             ]),
+            packed_emissive_factor: pack_unorm4x8(&[
+                emissive_factor[0],
+                emissive_factor[1],
+                emissive_factor[2],
+                0.0,
+            ]),
+            packed_metallic_roughness_and_normal_texture_ids: pack2x16(
+                metallic_roughness_texture_set,
+                normal_texture_set,
+            ),
+            packed_emission_texture_id_and_strength: pack2x16(
+                emissive_texture_set,
+                pack_half(emissive_strength),
+            ),
+            packed_transmission_factor_and_attenuation_color: pack_unorm4x8(&[
+                attenuation_color[0],
+                attenuation_color[1],
+                attenuation_color[2],
+                transmission_factor,
+            ]),
+            packed_thickness_factor_and_attenuation_distance: pack2x16(
+                pack_half(thickness_factor),
+                pack_half(attenuation_distance),
+            ),
+            packed_transmission_and_thickness_texture_ids: pack2x16(
+                transmission_texture_set,
+                thickness_texture_set,
+            ),
+            tex_coord_sets: get_tex_coord_sets(&material).bits,
+            packed_uv_matrix: pack_uv_matrix(&Mat2::IDENTITY),
+            uv_offset: [0; 2],
         };
+        if let Some(alpha_cutoff) = alpha_cutoff {
+            material.set_alpha_cutoff(alpha_cutoff);
+        }
 
         // Then push it into the materials buffer
         unsafe {
@@ -162,12 +341,212 @@ impl Material {
         }
     }
 
+    /// The flags this material was created with
+    pub fn flags(&self) -> MaterialFlags {
+        MaterialFlags::from_bits_truncate(self.packed_flags_and_base_texture_id & 0xFFFF)
+    }
+
+    fn set_flags(&mut self, flags: MaterialFlags) {
+        self.packed_flags_and_base_texture_id =
+            pack2x16(flags.bits, self.packed_flags_and_base_texture_id >> 16);
+    }
+
+    /// The base color of the material, in linear space. Multiplied with the base color texture, if present.
+    pub fn base_color_factor(&self) -> [f32; 4] {
+        unpack_unorm4x8(self.packed_base_color_factor)
+    }
+
+    /// Change the base color of the material
+    pub fn set_base_color_factor(&mut self, base_color_factor: [f32; 4]) {
+        self.packed_base_color_factor = pack_unorm4x8(&base_color_factor);
+    }
+
+    /// How metallic the material is, from 0 (dielectric) to 1 (metal)
+    pub fn metallic_factor(&self) -> f32 {
+        unpack_unorm4x8(self.packed_metallic_roughness_factor)[0]
+    }
+
+    /// How rough the material is, from 0 (smooth) to 1 (rough)
+    pub fn roughness_factor(&self) -> f32 {
+        unpack_unorm4x8(self.packed_metallic_roughness_factor)[1]
+    }
+
+    /// Change how metallic and how rough the material is
+    pub fn set_metallic_roughness_factor(&mut self, metallic_factor: f32, roughness_factor: f32) {
+        let [_, _, alpha_cutoff, w] = unpack_unorm4x8(self.packed_metallic_roughness_factor);
+        self.packed_metallic_roughness_factor =
+            pack_unorm4x8(&[metallic_factor, roughness_factor, alpha_cutoff, w]);
+    }
+
+    /// The color of the light emitted by the material, before it's multiplied by the emissive strength
+    pub fn emissive_factor(&self) -> [f32; 3] {
+        let [r, g, b, _] = unpack_unorm4x8(self.packed_emissive_factor);
+        [r, g, b]
+    }
+
+    /// Change the color of the light emitted by the material
+    pub fn set_emissive_factor(&mut self, emissive_factor: [f32; 3]) {
+        let [r, g, b] = emissive_factor;
+        self.packed_emissive_factor = pack_unorm4x8(&[r, g, b, 0.0]);
+    }
+
+    /// Change how strongly the material emits light. Values above 1.0 make emission bright enough to bloom.
+    pub fn set_emissive_strength(&mut self, emissive_strength: f32) {
+        self.packed_emission_texture_id_and_strength =
+            pack2x16(self.emission_texture_id(), pack_half(emissive_strength));
+    }
+
+    /// Fragments with an alpha below this value are discarded. Only used if the material is alpha masked.
+    pub fn alpha_cutoff(&self) -> f32 {
+        unpack_unorm4x8(self.packed_metallic_roughness_factor)[2]
+    }
+
+    /// Discard fragments with an alpha below `alpha_cutoff`, making the material alpha masked. Animating the cutoff
+    /// over a noisy alpha channel gives a cheap dissolve effect.
+    pub fn set_alpha_cutoff(&mut self, alpha_cutoff: f32) {
+        let [metallic, roughness, _, w] = unpack_unorm4x8(self.packed_metallic_roughness_factor);
+        self.packed_metallic_roughness_factor =
+            pack_unorm4x8(&[metallic, roughness, alpha_cutoff, w]);
+        self.set_flags(self.flags() | MaterialFlags::ALPHA_MASK);
+    }
+
+    /// Is this material alpha masked? Fragments below the alpha cutoff are discarded.
+    pub fn is_alpha_masked(&self) -> bool {
+        self.flags().contains(MaterialFlags::ALPHA_MASK)
+    }
+
+    /// The transform applied to the material's texture coordinates in the vertex shader
+    pub fn uv_transform(&self) -> Affine2 {
+        let [x_axis, y_axis] = self
+            .packed_uv_matrix
+            .map(|packed| Vec2::new(unpack_half(packed), unpack_half(packed >> 16)));
+        let [x, y] = self.uv_offset.map(f32::from_bits);
+        Affine2::from_mat2_translation(Mat2::from_cols(x_axis, y_axis), Vec2::new(x, y))
+    }
+
+    /// Transform the material's texture coordinates, eg. to tile or scroll its textures. Both sets of texture
+    /// coordinates are transformed, see [`UvTransform`](crate::components::UvTransform) for animating it.
+    pub fn set_uv_transform(&mut self, uv_transform: &Affine2) {
+        self.packed_uv_matrix = pack_uv_matrix(&uv_transform.matrix2);
+        self.uv_offset = uv_transform.translation.to_array().map(f32::to_bits);
+    }
+
+    /// The index of the base color texture in the bindless texture array
+    pub fn base_color_texture_id(&self) -> u32 {
+        self.packed_flags_and_base_texture_id >> 16
+    }
+
+    /// The index of the metallic roughness (and occlusion) texture in the bindless texture array
+    pub fn metallic_roughness_texture_id(&self) -> u32 {
+        self.packed_metallic_roughness_and_normal_texture_ids & 0xFFFF
+    }
+
+    /// The index of the normal map in the bindless texture array
+    pub fn normal_texture_id(&self) -> u32 {
+        self.packed_metallic_roughness_and_normal_texture_ids >> 16
+    }
+
+    /// The index of the emission texture in the bindless texture array
+    pub fn emission_texture_id(&self) -> u32 {
+        self.packed_emission_texture_id_and_strength & 0xFFFF
+    }
+
+    /// The emissive factor is multiplied by this, allowing emission to be brighter than 1.0
+    pub fn emissive_strength(&self) -> f32 {
+        f16::from_bits((self.packed_emission_texture_id_and_strength >> 16) as u16).to_f32()
+    }
+
+    /// How much light is transmitted through the surface of the material, from 0 (none) to 1 (all)
+    pub fn transmission_factor(&self) -> f32 {
+        (self.packed_transmission_factor_and_attenuation_color >> 24) as f32 / 255.0
+    }
+
+    /// The thickness of the volume beneath the surface, in the coordinate space of the mesh
+    pub fn thickness_factor(&self) -> f32 {
+        f16::from_bits(self.packed_thickness_factor_and_attenuation_distance as u16).to_f32()
+    }
+
+    /// The average distance light travels through the volume before hitting a particle
+    pub fn attenuation_distance(&self) -> f32 {
+        f16::from_bits((self.packed_thickness_factor_and_attenuation_distance >> 16) as u16)
+            .to_f32()
+    }
+
+    /// Does this material sample the texture at `texture_id` in the bindless texture array?
+    pub fn uses_texture(&self, texture_id: u32) -> bool {
+        let flags = self.flags();
+        [
+            (
+                MaterialFlags::HAS_BASE_COLOR_TEXTURE,
+                self.base_color_texture_id(),
+            ),
+            (
+                MaterialFlags::HAS_METALLIC_ROUGHNESS_TEXTURE,
+                self.metallic_roughness_texture_id(),
+            ),
+            (MaterialFlags::HAS_NORMAL_MAP, self.normal_texture_id()),
+            (
+                MaterialFlags::HAS_EMISSION_TEXTURE,
+                self.emission_texture_id(),
+            ),
+            (
+                MaterialFlags::HAS_TRANSMISSION_TEXTURE,
+                self.packed_transmission_and_thickness_texture_ids & 0xFFFF,
+            ),
+            (
+                MaterialFlags::HAS_THICKNESS_TEXTURE,
+                self.packed_transmission_and_thickness_texture_ids >> 16,
+            ),
+        ]
+        .iter()
+        .any(|(flag, id)| flags.contains(*flag) && *id == texture_id)
+    }
+
+    /// Is this material unlit? Unlit materials are drawn with their base color, ignoring lights.
+    pub fn is_unlit(&self) -> bool {
+        self.flags().contains(MaterialFlags::UNLIT_WORKFLOW)
+    }
+
+    /// Is this material drawn without back face culling?
+    pub fn is_double_sided(&self) -> bool {
+        self.flags().contains(MaterialFlags::DOUBLE_SIDED)
+    }
+
+    /// Is this material drawn in the transparent pass?
+    pub fn is_transparent(&self) -> bool {
+        self.flags().contains(MaterialFlags::ALPHA_BLEND)
+    }
+
     /// Create a simple, unlit, white coloured material.
     pub fn unlit_white() -> Material {
         Material {
             packed_flags_and_base_texture_id: MaterialFlags::UNLIT_WORKFLOW.bits,
             packed_base_color_factor: u32::MAX,
             packed_metallic_roughness_factor: pack_unorm4x8(&[1.0, 1.0, 0.0, 0.0]),
+            packed_emissive_factor: 0,
+            packed_metallic_roughness_and_normal_texture_ids: pack2x16(NO_TEXTURE, NO_TEXTURE),
+            packed_emission_texture_id_and_strength: pack2x16(NO_TEXTURE, pack_half(1.0)),
+            packed_transmission_factor_and_attenuation_color: pack_unorm4x8(&[1.0, 1.0, 1.0, 0.0]),
+            packed_thickness_factor_and_attenuation_distance: pack2x16(
+                pack_half(0.0),
+                pack_half(f32::INFINITY),
+            ),
+            packed_transmission_and_thickness_texture_ids: pack2x16(NO_TEXTURE, NO_TEXTURE),
+            tex_coord_sets: 0,
+            packed_uv_matrix: pack_uv_matrix(&Mat2::IDENTITY),
+            uv_offset: [0; 2],
+        }
+    }
+
+    /// Create an unlit material that shows the texture at `texture_id`, eg. the texture of a
+    /// [`RenderTarget`](super::render_target::RenderTarget) or a [`Panel`](crate::components::Panel).
+    pub fn unlit_texture(texture_id: u32) -> Material {
+        Material {
+            packed_flags_and_base_texture_id: pack2x16(
+                (MaterialFlags::HAS_BASE_COLOR_TEXTURE | MaterialFlags::UNLIT_WORKFLOW).bits,
+                texture_id,
+            ),
+            ..Material::unlit_white()
         }
     }
 
@@ -178,8 +557,72 @@ impl Material {
             packed_flags_and_base_texture_id: MaterialFlags::empty().bits,
             packed_base_color_factor: u32::MAX,
             packed_metallic_roughness_factor: pack_unorm4x8(&[1.0, 1.0, 0.0, 0.0]),
+            packed_emissive_factor: 0,
+            packed_metallic_roughness_and_normal_texture_ids: pack2x16(NO_TEXTURE, NO_TEXTURE),
+            packed_emission_texture_id_and_strength: pack2x16(NO_TEXTURE, pack_half(1.0)),
+            packed_transmission_factor_and_attenuation_color: pack_unorm4x8(&[1.0, 1.0, 1.0, 0.0]),
+            packed_thickness_factor_and_attenuation_distance: pack2x16(
+                pack_half(0.0),
+                pack_half(f32::INFINITY),
+            ),
+            packed_transmission_and_thickness_texture_ids: pack2x16(NO_TEXTURE, NO_TEXTURE),
+            tex_coord_sets: 0,
+            packed_uv_matrix: pack_uv_matrix(&Mat2::IDENTITY),
+            uv_offset: [0; 2],
+        }
+    }
+}
+
+/// Find the textures in `material` that use `TEXCOORD_1`. Only two sets of texture coordinates are supported, so
+/// textures using any other set fall back to `TEXCOORD_0`.
+fn get_tex_coord_sets(material: &MaterialData) -> TexCoordSets {
+    let pbr_metallic_roughness = material.pbr_metallic_roughness();
+    let tex_coords = [
+        (
+            pbr_metallic_roughness
+                .base_color_texture()
+                .map(|i| i.tex_coord()),
+            TexCoordSets::BASE_COLOR,
+        ),
+        (
+            pbr_metallic_roughness
+                .metallic_roughness_texture()
+                .map(|i| i.tex_coord()),
+            TexCoordSets::METALLIC_ROUGHNESS,
+        ),
+        (
+            material.normal_texture().map(|i| i.tex_coord()),
+            TexCoordSets::NORMAL,
+        ),
+        (
+            material.emissive_texture().map(|i| i.tex_coord()),
+            TexCoordSets::EMISSION,
+        ),
+        (
+            material
+                .transmission()
+                .and_then(|t| t.transmission_texture())
+                .map(|i| i.tex_coord()),
+            TexCoordSets::TRANSMISSION,
+        ),
+        (
+            material
+                .volume()
+                .and_then(|v| v.thickness_texture())
+                .map(|i| i.tex_coord()),
+            TexCoordSets::THICKNESS,
+        ),
+    ];
+
+    let mut tex_coord_sets = TexCoordSets::empty();
+    for (tex_coord, texture) in tex_coords {
+        match tex_coord {
+            None | Some(0) => {}
+            Some(1) => tex_coord_sets.insert(texture),
+            Some(n) => println!("[HOTHAM_MATERIAL] WARNING: Texture coordinate set {n} is not supported, using set 0 instead."),
         }
     }
+    tex_coord_sets
 }
 
 /// Convert normalized floating-point values into 8-bit integer values and pack them into an u32.
@@ -193,6 +636,29 @@ pub fn pack_unorm4x8(array: &[f32; 4]) -> u32 {
     packed
 }
 
+/// Unpack a u32 into four normalized floating-point values. The inverse of [`pack_unorm4x8`], this works the same as
+/// unpackUnorm4x8 in GLSL.
+pub fn unpack_unorm4x8(packed: u32) -> [f32; 4] {
+    [0, 8, 16, 24].map(|shift| ((packed >> shift) & 0xFF) as f32 / 255.0)
+}
+
+/// Convert a floating-point value into a half float, returning its bits. This works the same as the least significant
+/// half of packHalf2x16 in GLSL.
+pub fn pack_half(value: f32) -> u32 {
+    f16::from_f32(value).to_bits() as u32
+}
+
+/// Convert the least significant 16 bits of `packed` from a half float into a floating-point value. This works the
+/// same as the least significant half of unpackHalf2x16 in GLSL.
+fn unpack_half(packed: u32) -> f32 {
+    f16::from_bits(packed as u16).to_f32()
+}
+
+/// Pack each column of `matrix` into a u32 as two half floats.
+fn pack_uv_matrix(matrix: &Mat2) -> [u32; 2] {
+    [matrix.x_axis, matrix.y_axis].map(|column| pack2x16(pack_half(column.x), pack_half(column.y)))
+}
+
 /// Pack the least significant 16 bits from two u32 into a single u32.
 pub fn pack2x16(lsb: u32, msb: u32) -> u32 {
     (msb << 16) | (lsb & 0xFFFF)
@@ -209,4 +675,147 @@ mod tests {
         assert_eq!(pack_unorm4x8(&[0.0, 0.0, 1.0, 0.0]), 0x00FF0000);
         assert_eq!(pack_unorm4x8(&[0.0, 0.0, 0.0, 1.0]), 0xFF000000);
     }
+
+    #[test]
+    fn unpack_unorm4x8_test() {
+        let values = [1.0, 0.0, 0.2, 0.6];
+        assert_eq!(unpack_unorm4x8(pack_unorm4x8(&values)), values);
+    }
+
+    #[test]
+    fn setters_test() {
+        let mut material = Material::gltf_default();
+        material.set_base_color_factor([1.0, 0.2, 0.0, 0.6]);
+        material.set_metallic_roughness_factor(0.0, 0.4);
+        material.set_emissive_factor([0.0, 1.0, 0.0]);
+        material.set_emissive_strength(4.0);
+        assert_eq!(material.base_color_factor(), [1.0, 0.2, 0.0, 0.6]);
+        assert_eq!(material.metallic_factor(), 0.0);
+        assert_eq!(material.roughness_factor(), 0.4);
+        assert_eq!(material.emissive_factor(), [0.0, 1.0, 0.0]);
+        assert_eq!(material.emissive_strength(), 4.0);
+        assert!(!material.is_alpha_masked());
+
+        // Setting the cutoff makes the material alpha masked, without touching its other properties
+        material.set_alpha_cutoff(0.6);
+        assert!(material.is_alpha_masked());
+        assert_eq!(material.alpha_cutoff(), 0.6);
+        assert_eq!(material.roughness_factor(), 0.4);
+        assert_eq!(material.base_color_texture_id(), 0);
+    }
+
+    #[test]
+    fn uv_transform_test() {
+        let mut material = Material::gltf_default();
+        assert_eq!(material.uv_transform(), Affine2::IDENTITY);
+
+        // The matrix is stored as half floats, so it's only approximately the same. The offset is exact.
+        let uv_transform = Affine2::from_scale_angle_translation(
+            Vec2::new(2.0, 0.5),
+            std::f32::consts::FRAC_PI_2,
+            Vec2::new(0.123456, 0.75),
+        );
+        material.set_uv_transform(&uv_transform);
+        assert!(material
+            .uv_transform()
+            .matrix2
+            .abs_diff_eq(uv_transform.matrix2, 0.001));
+        assert_eq!(
+            material.uv_transform().translation,
+            uv_transform.translation
+        );
+        assert_eq!(material.roughness_factor(), 1.0);
+    }
+
+    #[test]
+    fn is_transparent_test() {
+        assert!(!Material::gltf_default().is_transparent());
+
+        let material = Material {
+            packed_flags_and_base_texture_id: pack2x16(MaterialFlags::ALPHA_BLEND.bits, 7),
+            ..Default::default()
+        };
+        assert!(material.is_transparent());
+        assert_eq!(material.flags(), MaterialFlags::ALPHA_BLEND);
+    }
+
+    #[test]
+    fn uses_texture_test() {
+        let material = Material::unlit_texture(7);
+        assert!(material.uses_texture(7));
+        assert!(!material.uses_texture(8));
+
+        // Texture IDs are ignored unless their flag is set
+        assert!(!Material::gltf_default().uses_texture(NO_TEXTURE & 0xFFFF));
+    }
+
+    #[test]
+    fn is_double_sided_test() {
+        assert!(!Material::gltf_default().is_double_sided());
+
+        let material = Material {
+            packed_flags_and_base_texture_id: pack2x16(
+                (MaterialFlags::DOUBLE_SIDED | MaterialFlags::ALPHA_BLEND).bits,
+                7,
+            ),
+            ..Default::default()
+        };
+        assert!(material.is_double_sided());
+        assert!(material.is_transparent());
+    }
+
+    #[test]
+    fn is_unlit_test() {
+        assert!(!Material::gltf_default().is_unlit());
+        assert!(Material::unlit_white().is_unlit());
+    }
+
+    #[test]
+    fn texture_ids_test() {
+        let material = Material {
+            packed_flags_and_base_texture_id: pack2x16(
+                MaterialFlags::HAS_BASE_COLOR_TEXTURE.bits,
+                3,
+            ),
+            packed_metallic_roughness_and_normal_texture_ids: pack2x16(7, 12),
+            packed_emission_texture_id_and_strength: pack2x16(5, pack_half(1.0)),
+            ..Default::default()
+        };
+        assert_eq!(material.base_color_texture_id(), 3);
+        assert_eq!(material.metallic_roughness_texture_id(), 7);
+        assert_eq!(material.normal_texture_id(), 12);
+        assert_eq!(material.emission_texture_id(), 5);
+    }
+
+    #[test]
+    fn emissive_strength_test() {
+        assert_eq!(Material::gltf_default().emissive_strength(), 1.0);
+
+        let material = Material {
+            packed_emission_texture_id_and_strength: pack2x16(5, pack_half(12.5)),
+            ..Default::default()
+        };
+        assert_eq!(material.emission_texture_id(), 5);
+        assert_eq!(material.emissive_strength(), 12.5);
+    }
+
+    #[test]
+    fn transmission_test() {
+        let material = Material::gltf_default();
+        assert_eq!(material.transmission_factor(), 0.0);
+        assert_eq!(material.thickness_factor(), 0.0);
+        assert_eq!(material.attenuation_distance(), f32::INFINITY);
+
+        let material = Material {
+            packed_transmission_factor_and_attenuation_color: pack_unorm4x8(&[0.5, 0.5, 0.5, 1.0]),
+            packed_thickness_factor_and_attenuation_distance: pack2x16(
+                pack_half(0.25),
+                pack_half(2.0),
+            ),
+            ..Default::default()
+        };
+        assert_eq!(material.transmission_factor(), 1.0);
+        assert_eq!(material.thickness_factor(), 0.25);
+        assert_eq!(material.attenuation_distance(), 2.0);
+    }
 }
@@ -10,6 +10,9 @@ pub mod memory;
 /// The virtual camera
 pub mod camera;
 
+/// Axis aligned bounding boxes
+pub mod aabb;
+
 /// A wrapper around the frame-dependent resources
 pub mod frame;
 
@@ -40,7 +43,57 @@ pub mod resources;
 /// Data to instruct the renderer how a primitive should look
 pub mod material;
 
+/// Glow around emissive materials
+pub mod bloom;
+/// Remapping the colors of the scene with a lookup table
+pub mod color_grading;
+/// sRGB and linear color handling
+pub mod color_space;
+/// User supplied compute shaders
+pub mod compute_pass;
+/// Pipelines for entities drawn with their own shaders
+pub mod custom_material;
+/// Immediate mode debug lines
+pub mod debug_draw;
+/// Materials projected onto opaque surfaces
+pub mod decal;
+/// Depth submitted to the compositor for reprojection
+pub mod depth_layer;
+/// Distance and height fog
+pub mod fog;
 /// Lights and related functionality
 pub mod light;
+/// Assigning lights to the clusters of the view they touch
+pub mod light_clustering;
 /// Wrapper around geometry data.
 pub mod mesh_data;
+/// Motion vectors submitted to the compositor for SpaceWarp
+pub mod motion_vectors;
+/// Hierarchical-Z occlusion culling
+pub mod occlusion_culling;
+/// Outlines drawn around highlighted meshes
+pub mod outline;
+/// Debug views such as wireframe and overdraw
+pub mod render_mode;
+/// Offscreen images the world can be rendered into
+pub mod render_target;
+/// Texture filtering and wrapping
+pub mod sampler;
+/// Shadow mapping
+pub mod shadow;
+/// Skinning meshes once per frame, in a compute pre-pass
+pub mod skinning;
+/// Backgrounds drawn behind all geometry
+pub mod skybox;
+/// Background uploads of texture data
+pub mod staging;
+/// Merging static meshes to reduce draw calls
+pub mod static_batching;
+/// Signed distance field text
+pub mod text;
+/// Transcoding of Basis Universal textures
+pub mod transcode;
+/// Recording of rendered frames
+pub mod video_recorder;
+/// Darkening the edges of the view during locomotion
+pub mod vignette;
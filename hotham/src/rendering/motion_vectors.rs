@@ -0,0 +1,380 @@
+use std::{collections::HashMap, slice::from_ref as slice_from_ref};
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use glam::{Affine3A, Mat4, Vec3};
+use hecs::Entity;
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::{render_context::create_shader, xr_context::MOTION_VECTOR_FORMAT, VulkanContext},
+    rendering::{descriptors::Descriptors, texture::DEFAULT_COMPONENT_MAPPING, vertex::Vertex},
+    DEPTH_FORMAT, VIEW_COUNT, VIEW_MASK,
+};
+
+static MOTION_VECTORS_VERT: &[u32] =
+    include_glsl!("src/shaders/motion_vectors.vert", target: vulkan1_1);
+static MOTION_VECTORS_FRAG: &[u32] =
+    include_glsl!("src/shaders/motion_vectors.frag", target: vulkan1_1);
+
+/// Renders the motion of everything in the scene since the previous frame, along with its depth, into the
+/// SpaceWarp swapchains. See [`SpaceWarp`](crate::contexts::xr_context::SpaceWarp).
+///
+/// Both the current and previous positions of each vertex are projected with the current view, so motion vectors
+/// only contain the movement of objects relative to the stage. Skinned and morphed meshes use their current pose
+/// for both positions, so only the movement of the mesh as a whole is captured. Entities with a
+/// [`CustomMaterial`](crate::components::CustomMaterial) aren't drawn.
+pub struct MotionVectors {
+    /// Render pass that writes motion vectors and depth to the SpaceWarp swapchains
+    pub render_pass: vk::RenderPass,
+    /// One framebuffer per swapchain image
+    pub framebuffers: Vec<vk::Framebuffer>,
+    /// Layout of `pipeline`, with the previous `gos_from_local` of each instance as a push constant
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Writes the motion of each pixel in normalized device coordinates
+    pub pipeline: vk::Pipeline,
+    /// The resolution of the SpaceWarp swapchains
+    pub render_area: vk::Rect2D,
+    /// The current stage in the space of the stage last frame, submitted as `app_space_delta_pose`
+    pub stage_delta: Affine3A,
+    previous_global_from_stage: Option<Affine3A>,
+    previous_global_from_local: HashMap<Entity, Affine3A>,
+    global_from_local: HashMap<Entity, Affine3A>,
+}
+
+impl MotionVectors {
+    /// Create the motion vector pass, rendering into `motion_vector_images` and `depth_images`.
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        descriptors: &Descriptors,
+        motion_vector_images: &[vk::Image],
+        depth_images: &[vk::Image],
+        resolution: vk::Extent2D,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let render_area = vk::Rect2D {
+            extent: resolution,
+            ..Default::default()
+        };
+
+        let render_pass = create_render_pass(device)?;
+        let framebuffers = motion_vector_images
+            .iter()
+            .zip(depth_images)
+            .map(|(motion_vector_image, depth_image)| {
+                let attachments = [
+                    vulkan_context.create_image_view(
+                        motion_vector_image,
+                        MOTION_VECTOR_FORMAT,
+                        vk::ImageViewType::TYPE_2D_ARRAY,
+                        VIEW_COUNT,
+                        1,
+                        DEFAULT_COMPONENT_MAPPING,
+                    )?,
+                    vulkan_context.create_image_view(
+                        depth_image,
+                        DEPTH_FORMAT,
+                        vk::ImageViewType::TYPE_2D_ARRAY,
+                        VIEW_COUNT,
+                        1,
+                        DEFAULT_COMPONENT_MAPPING,
+                    )?,
+                ];
+                let create_info = vk::FramebufferCreateInfo::builder()
+                    .render_pass(render_pass)
+                    .attachments(&attachments)
+                    .width(resolution.width)
+                    .height(resolution.height)
+                    .layers(1); // NOTE: multiview takes care of layers.
+                unsafe { device.create_framebuffer(&create_info, None) }.map_err(Into::into)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let push_constant_range = vk::PushConstantRange::builder()
+            .offset(0)
+            .size(std::mem::size_of::<Mat4>() as _)
+            .stage_flags(vk::ShaderStageFlags::VERTEX);
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptors.graphics_layout))
+                    .push_constant_ranges(slice_from_ref(&push_constant_range)),
+                None,
+            )
+        }?;
+        let pipeline = create_pipeline(vulkan_context, pipeline_layout, render_pass, &render_area)?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::RENDER_PASS,
+            render_pass.as_raw(),
+            "Motion Vectors Render Pass",
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::PIPELINE,
+            pipeline.as_raw(),
+            "Motion Vectors Pipeline",
+        )?;
+
+        Ok(Self {
+            render_pass,
+            framebuffers,
+            pipeline_layout,
+            pipeline,
+            render_area,
+            stage_delta: Affine3A::IDENTITY,
+            previous_global_from_stage: None,
+            previous_global_from_local: HashMap::new(),
+            global_from_local: HashMap::new(),
+        })
+    }
+
+    /// Forget the transforms from two frames ago and work out how far the stage has moved since the last frame.
+    /// Must be called once per frame, before `previous_global_from_local`.
+    pub(crate) fn begin_frame(&mut self, global_from_stage: &Affine3A) {
+        std::mem::swap(
+            &mut self.previous_global_from_local,
+            &mut self.global_from_local,
+        );
+        self.global_from_local.clear();
+
+        self.stage_delta = self
+            .previous_global_from_stage
+            .map(|previous_global_from_stage| {
+                previous_global_from_stage.inverse() * *global_from_stage
+            })
+            .unwrap_or(Affine3A::IDENTITY);
+        self.previous_global_from_stage = Some(*global_from_stage);
+    }
+
+    /// Record where `entity` is this frame, and return where it was last frame. Entities that weren't drawn last
+    /// frame haven't moved.
+    pub(crate) fn previous_global_from_local(
+        &mut self,
+        entity: Entity,
+        global_from_local: Affine3A,
+    ) -> Affine3A {
+        self.global_from_local.insert(entity, global_from_local);
+        self.previous_global_from_local
+            .get(&entity)
+            .copied()
+            .unwrap_or(global_from_local)
+    }
+
+    /// Begin the motion vector render pass, rendering into the swapchain images at `swapchain_image_index`.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state and not inside another render pass.
+    pub(crate) unsafe fn begin_render_pass(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        swapchain_image_index: usize,
+    ) {
+        // No motion and infinitely far away, remembering that we're using inverse Z.
+        let clear_values = [
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 0.0],
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 0.0,
+                    stencil: 0,
+                },
+            },
+        ];
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffers[swapchain_image_index])
+            .render_area(self.render_area)
+            .clear_values(&clear_values);
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_begin_info,
+            vk::SubpassContents::INLINE,
+        );
+    }
+}
+
+fn create_render_pass(device: &ash::Device) -> Result<vk::RenderPass> {
+    // The compositor reads the swapchain images in these layouts once they've been released.
+    let attachments = [
+        vk::AttachmentDescription::builder()
+            .format(MOTION_VECTOR_FORMAT)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .build(),
+        vk::AttachmentDescription::builder()
+            .format(DEPTH_FORMAT)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .build(),
+    ];
+
+    let color_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+    let depth_reference = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(slice_from_ref(&color_reference))
+        .depth_stencil_attachment(&depth_reference);
+
+    let dependency = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        )
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+        )
+        .dst_access_mask(
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+        );
+
+    let view_masks = [VIEW_MASK];
+    let mut multiview = vk::RenderPassMultiviewCreateInfo::builder()
+        .view_masks(&view_masks)
+        .correlation_masks(&view_masks);
+
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(&attachments)
+        .subpasses(slice_from_ref(&subpass))
+        .dependencies(slice_from_ref(&dependency))
+        .push_next(&mut multiview);
+
+    unsafe { device.create_render_pass(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_pass: vk::RenderPass,
+    render_area: &vk::Rect2D,
+) -> Result<vk::Pipeline> {
+    let device = &vulkan_context.device;
+    let (vertex_module, vertex_stage) = create_shader(
+        MOTION_VECTORS_VERT,
+        vk::ShaderStageFlags::VERTEX,
+        vulkan_context,
+    )?;
+    let (fragment_module, fragment_stage) = create_shader(
+        MOTION_VECTORS_FRAG,
+        vk::ShaderStageFlags::FRAGMENT,
+        vulkan_context,
+    )?;
+    let stages = [vertex_stage, fragment_stage];
+
+    // Vertex input state - identical to the PBR pipeline so that we can share buffers.
+    let vertex_binding_descriptions = [
+        vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(std::mem::size_of::<Vec3>() as _)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build(),
+        vk::VertexInputBindingDescription::builder()
+            .binding(1)
+            .stride(std::mem::size_of::<Vertex>() as _)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build(),
+    ];
+    let vertex_attribute_descriptions = Vertex::attribute_descriptions();
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_attribute_descriptions(&vertex_attribute_descriptions)
+        .vertex_binding_descriptions(&vertex_binding_descriptions);
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+    let viewport = vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: render_area.extent.width as _,
+        height: render_area.extent.height as _,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    };
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(slice_from_ref(&viewport))
+        .scissors(slice_from_ref(render_area));
+
+    // Double sided materials are drawn with the same pipeline, so nothing is culled.
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .polygon_mode(vk::PolygonMode::FILL)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .line_width(1.0);
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+    // Inverse Z, like the PBR pipeline.
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::GREATER)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(
+            vk::ColorComponentFlags::R
+                | vk::ColorComponentFlags::G
+                | vk::ColorComponentFlags::B
+                | vk::ColorComponentFlags::A,
+        )
+        .blend_enable(false);
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .attachments(slice_from_ref(&color_blend_attachment));
+
+    let create_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(&stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    let pipelines = unsafe {
+        device.create_graphics_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    unsafe {
+        device.destroy_shader_module(vertex_module, None);
+        device.destroy_shader_module(fragment_module, None);
+    }
+
+    Ok(pipelines[0])
+}
@@ -0,0 +1,624 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use glam::{Mat4, Vec2};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::{render_context::create_shader, VulkanContext},
+    rendering::{buffer::Buffer, image::Image},
+    VIEW_COUNT,
+};
+
+static DEPTH_COMPUTE: &[u32] = include_glsl!("src/shaders/occlusion_depth.comp", target: vulkan1_1);
+static DEPTH_MS_COMPUTE: &[u32] =
+    include_glsl!("src/shaders/occlusion_depth_ms.comp", target: vulkan1_1);
+static DOWNSAMPLE_COMPUTE: &[u32] =
+    include_glsl!("src/shaders/occlusion_downsample.comp", target: vulkan1_1);
+static CULLING_COMPUTE: &[u32] =
+    include_glsl!("src/shaders/occlusion_culling.comp", target: vulkan1_1);
+
+/// Format of the depth pyramid.
+pub const DEPTH_PYRAMID_FORMAT: vk::Format = vk::Format::R32_SFLOAT;
+
+/// Must match `local_size_x` and `local_size_y` in occlusion_depth.glsl and occlusion_downsample.comp
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Must match `local_size_x` in occlusion_culling.comp
+const CULLING_WORKGROUP_SIZE: usize = 1024;
+
+/// Must match `OcclusionData` in occlusion_culling.comp
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct OcclusionParams {
+    view_projection: [Mat4; 2],
+    pyramid_size: Vec2,
+    mip_count: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct PyramidDispatch {
+    pipeline: vk::Pipeline,
+    descriptor_set: vk::DescriptorSet,
+    extent: vk::Extent2D,
+}
+
+/// All the GPU resources required for hierarchical-Z occlusion culling.
+///
+/// At the end of each frame the depth buffer is reduced into a pyramid, where each texel holds the farthest
+/// depth of the texels it covers. When culling the next frame, any primitive that survived frustum culling is
+/// projected with the previous frame's cameras and compared against the pyramid. Primitives that are hidden
+/// behind it in both eyes are marked as invisible, so they are never drawn.
+///
+/// As the pyramid is a frame old, objects that are uncovered very quickly may pop in a frame late.
+pub struct OcclusionCulling {
+    /// The depth pyramid, with one mip level per step of the reduction
+    pub pyramid: Image,
+    /// One view per mip level of `pyramid`
+    pub mip_views: Vec<vk::ImageView>,
+    /// Nearest sampler used to read from the pyramid
+    pub sampler: vk::Sampler,
+    /// Layout used by each step of the reduction
+    pub pyramid_descriptor_set_layout: vk::DescriptorSetLayout,
+    /// Layout used by the culling pass, bound after the regular culling descriptor set
+    pub culling_descriptor_set_layout: vk::DescriptorSetLayout,
+    /// Pool the occlusion culling descriptor sets are allocated from
+    pub descriptor_pool: vk::DescriptorPool,
+    /// Pipeline layout shared by every step of the reduction
+    pub pyramid_pipeline_layout: vk::PipelineLayout,
+    /// Reduces the depth buffer into the first level of the pyramid
+    pub depth_pipeline: vk::Pipeline,
+    /// Reduces one level of the pyramid into the next
+    pub downsample_pipeline: vk::Pipeline,
+    /// Pipeline layout for the culling pass
+    pub culling_pipeline_layout: vk::PipelineLayout,
+    /// Tests the primitives that survived frustum culling against the pyramid
+    pub culling_pipeline: vk::Pipeline,
+    /// Descriptor set used by the culling pass
+    pub culling_descriptor_set: vk::DescriptorSet,
+    /// The cameras the pyramid was built with
+    params_buffer: Buffer<OcclusionParams>,
+    /// The depth buffer the pyramid is built from
+    depth_image: vk::Image,
+    dispatches: Vec<PyramidDispatch>,
+    /// The view projection of each camera when the pyramid was last built. `None` until the first frame has been
+    /// rendered, as there's nothing to cull against.
+    view_projection: Option<[Mat4; 2]>,
+}
+
+impl OcclusionCulling {
+    /// Create the occlusion culling resources for the depth buffer of the PBR render pass.
+    /// `depth_image` must have been created with `SAMPLED` usage and stored by the render pass.
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        compute_layout: vk::DescriptorSetLayout,
+        depth_image: &Image,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let extent = vk::Extent2D {
+            width: (depth_image.extent.width / 2).max(1),
+            height: (depth_image.extent.height / 2).max(1),
+        };
+        let mip_count = u32::BITS - extent.width.max(extent.height).leading_zeros();
+
+        let pyramid = vulkan_context.create_image(
+            DEPTH_PYRAMID_FORMAT,
+            &extent,
+            vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::STORAGE,
+            VIEW_COUNT,
+            mip_count,
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::IMAGE,
+            pyramid.handle.as_raw(),
+            "Depth Pyramid",
+        )?;
+
+        let mip_views = (0..mip_count)
+            .map(|mip| create_mip_view(device, &pyramid, mip))
+            .collect::<Result<Vec<_>>>()?;
+
+        let sampler = create_pyramid_sampler(vulkan_context, mip_count)?;
+        let (pyramid_descriptor_set_layout, culling_descriptor_set_layout) =
+            create_descriptor_set_layouts(device)?;
+
+        // One set per level of the pyramid, plus one for the culling pass.
+        let pool_sizes = [
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+                descriptor_count: mip_count + 1,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_IMAGE,
+                descriptor_count: mip_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+            },
+        ];
+        let descriptor_pool = unsafe {
+            device.create_descriptor_pool(
+                &vk::DescriptorPoolCreateInfo::builder()
+                    .pool_sizes(&pool_sizes)
+                    .max_sets(mip_count + 1),
+                None,
+            )
+        }?;
+
+        let mut set_layouts = vec![pyramid_descriptor_set_layout; mip_count as usize];
+        set_layouts.push(culling_descriptor_set_layout);
+        let descriptor_sets = unsafe {
+            device.allocate_descriptor_sets(
+                &vk::DescriptorSetAllocateInfo::builder()
+                    .descriptor_pool(descriptor_pool)
+                    .set_layouts(&set_layouts),
+            )
+        }?;
+
+        let pyramid_pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&pyramid_descriptor_set_layout)),
+                None,
+            )
+        }?;
+        let depth_shader = if msaa_samples == vk::SampleCountFlags::TYPE_1 {
+            DEPTH_COMPUTE
+        } else {
+            DEPTH_MS_COMPUTE
+        };
+        let depth_pipeline =
+            create_compute_pipeline(vulkan_context, pyramid_pipeline_layout, depth_shader)?;
+        let downsample_pipeline =
+            create_compute_pipeline(vulkan_context, pyramid_pipeline_layout, DOWNSAMPLE_COMPUTE)?;
+
+        let culling_set_layouts = [compute_layout, culling_descriptor_set_layout];
+        let culling_pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder().set_layouts(&culling_set_layouts),
+                None,
+            )
+        }?;
+        let culling_pipeline =
+            create_compute_pipeline(vulkan_context, culling_pipeline_layout, CULLING_COMPUTE)?;
+
+        // Build the reduction: the depth buffer into the first level, then each level into the next.
+        let mut dispatches = Vec::with_capacity(mip_count as usize);
+        for mip in 0..mip_count {
+            let descriptor_set = descriptor_sets[mip as usize];
+            let (pipeline, source, source_layout) = if mip == 0 {
+                (
+                    depth_pipeline,
+                    depth_image.view,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                )
+            } else {
+                (
+                    downsample_pipeline,
+                    mip_views[mip as usize - 1],
+                    vk::ImageLayout::GENERAL,
+                )
+            };
+            unsafe {
+                write_pyramid_descriptors(
+                    device,
+                    descriptor_set,
+                    sampler,
+                    source,
+                    source_layout,
+                    mip_views[mip as usize],
+                );
+            }
+            dispatches.push(PyramidDispatch {
+                pipeline,
+                descriptor_set,
+                extent: vk::Extent2D {
+                    width: (extent.width >> mip).max(1),
+                    height: (extent.height >> mip).max(1),
+                },
+            });
+        }
+
+        let params_buffer =
+            unsafe { Buffer::new(vulkan_context, vk::BufferUsageFlags::UNIFORM_BUFFER, 1) };
+        let culling_descriptor_set = *descriptor_sets.last().unwrap();
+        unsafe {
+            let pyramid_info = vk::DescriptorImageInfo {
+                sampler,
+                image_view: pyramid.view,
+                image_layout: vk::ImageLayout::GENERAL,
+            };
+            device.update_descriptor_sets(
+                slice_from_ref(
+                    &vk::WriteDescriptorSet::builder()
+                        .dst_set(culling_descriptor_set)
+                        .dst_binding(0)
+                        .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+                        .image_info(slice_from_ref(&pyramid_info)),
+                ),
+                &[],
+            );
+            params_buffer.update_descriptor_set(device, culling_descriptor_set, 1);
+        }
+
+        // The pyramid is read and written in the GENERAL layout, so transition every mip level into it once.
+        let command_buffer = vulkan_context.begin_single_time_commands();
+        unsafe {
+            let barrier = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::UNDEFINED)
+                .new_layout(vk::ImageLayout::GENERAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(pyramid.handle)
+                .subresource_range(color_range(0, mip_count))
+                .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&barrier),
+            );
+        }
+        vulkan_context.end_single_time_commands(command_buffer);
+
+        let debug_names = [
+            (depth_pipeline, "Depth Pyramid Pipeline"),
+            (downsample_pipeline, "Depth Pyramid Downsample Pipeline"),
+            (culling_pipeline, "Occlusion Culling Pipeline"),
+        ];
+        for (pipeline, name) in debug_names {
+            vulkan_context.set_debug_name(vk::ObjectType::PIPELINE, pipeline.as_raw(), name)?;
+        }
+
+        Ok(Self {
+            pyramid,
+            mip_views,
+            sampler,
+            pyramid_descriptor_set_layout,
+            culling_descriptor_set_layout,
+            descriptor_pool,
+            pyramid_pipeline_layout,
+            depth_pipeline,
+            downsample_pipeline,
+            culling_pipeline_layout,
+            culling_pipeline,
+            culling_descriptor_set,
+            params_buffer,
+            depth_image: depth_image.handle,
+            dispatches,
+            view_projection: None,
+        })
+    }
+
+    /// Mark any primitive in `primitive_cull_data_buffer` that is hidden behind the depth pyramid as invisible.
+    /// Does nothing until the pyramid has been built at least once.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state, after the frustum culling dispatch.
+    pub(crate) unsafe fn cull(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        compute_descriptor_set: vk::DescriptorSet,
+        draw_calls: usize,
+    ) {
+        let view_projection = match self.view_projection {
+            Some(view_projection) => view_projection,
+            None => return,
+        };
+
+        self.params_buffer.overwrite(&[OcclusionParams {
+            view_projection,
+            pyramid_size: Vec2::new(
+                self.pyramid.extent.width as _,
+                self.pyramid.extent.height as _,
+            ),
+            mip_count: self.mip_views.len() as _,
+        }]);
+
+        // Wait for frustum culling to finish writing its results.
+        let barrier = vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            slice_from_ref(&barrier),
+            &[],
+            &[],
+        );
+
+        let descriptor_sets = [compute_descriptor_set, self.culling_descriptor_set];
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::COMPUTE,
+            self.culling_pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::COMPUTE,
+            self.culling_pipeline_layout,
+            0,
+            &descriptor_sets,
+            &[],
+        );
+        device.cmd_dispatch(
+            command_buffer,
+            ((draw_calls / CULLING_WORKGROUP_SIZE) + 1) as u32,
+            1,
+            1,
+        );
+    }
+
+    /// Reduce the depth buffer of the frame that was just rendered into the depth pyramid, ready to cull the next
+    /// frame against. `view_projection` must be the cameras the frame was rendered with.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state, after the PBR render pass has ended.
+    pub(crate) unsafe fn build_pyramid(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        view_projection: [Mat4; 2],
+    ) {
+        // Make the depth buffer readable. The render pass clears it, so there's no need to transition it back.
+        let depth_barrier = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(self.depth_image)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: VIEW_COUNT,
+            })
+            .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ);
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            slice_from_ref(&depth_barrier),
+        );
+
+        for (mip, dispatch) in self.dispatches.iter().enumerate() {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                dispatch.pipeline,
+            );
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                self.pyramid_pipeline_layout,
+                0,
+                slice_from_ref(&dispatch.descriptor_set),
+                &[],
+            );
+            device.cmd_dispatch(
+                command_buffer,
+                (dispatch.extent.width + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                (dispatch.extent.height + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE,
+                VIEW_COUNT,
+            );
+
+            // Each level reads the result of the previous one, and the culling pass of the next frame reads them all.
+            let barrier = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::GENERAL)
+                .new_layout(vk::ImageLayout::GENERAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(self.pyramid.handle)
+                .subresource_range(color_range(mip as u32, 1))
+                .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+                .dst_access_mask(vk::AccessFlags::SHADER_READ);
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&barrier),
+            );
+        }
+
+        // Don't let the next frame write to the depth buffer until we're done reading from it.
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COMPUTE_SHADER,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &[],
+        );
+
+        self.view_projection = Some(view_projection);
+    }
+}
+
+fn color_range(base_mip_level: u32, level_count: u32) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: vk::ImageAspectFlags::COLOR,
+        base_mip_level,
+        level_count,
+        base_array_layer: 0,
+        layer_count: VIEW_COUNT,
+    }
+}
+
+fn create_mip_view(device: &ash::Device, image: &Image, mip: u32) -> Result<vk::ImageView> {
+    let create_info = vk::ImageViewCreateInfo::builder()
+        .view_type(vk::ImageViewType::TYPE_2D_ARRAY)
+        .format(image.format)
+        .subresource_range(color_range(mip, 1))
+        .image(image.handle);
+
+    unsafe { device.create_image_view(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_pyramid_sampler(vulkan_context: &VulkanContext, mip_count: u32) -> Result<vk::Sampler> {
+    let create_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::NEAREST)
+        .min_filter(vk::Filter::NEAREST)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_EDGE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+        .min_lod(0.0)
+        .max_lod(mip_count as _);
+
+    unsafe { vulkan_context.device.create_sampler(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_descriptor_set_layouts(
+    device: &ash::Device,
+) -> Result<(vk::DescriptorSetLayout, vk::DescriptorSetLayout)> {
+    let pyramid_bindings = [
+        // Source
+        vk::DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        },
+        // Destination
+        vk::DescriptorSetLayoutBinding {
+            binding: 1,
+            descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        },
+    ];
+
+    let culling_bindings = [
+        // Depth pyramid
+        vk::DescriptorSetLayoutBinding {
+            binding: 0,
+            descriptor_type: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        },
+        // Occlusion params
+        vk::DescriptorSetLayoutBinding {
+            binding: 1,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            ..Default::default()
+        },
+    ];
+
+    unsafe {
+        let pyramid_layout = device.create_descriptor_set_layout(
+            &vk::DescriptorSetLayoutCreateInfo::builder().bindings(&pyramid_bindings),
+            None,
+        )?;
+        let culling_layout = device.create_descriptor_set_layout(
+            &vk::DescriptorSetLayoutCreateInfo::builder().bindings(&culling_bindings),
+            None,
+        )?;
+        Ok((pyramid_layout, culling_layout))
+    }
+}
+
+unsafe fn write_pyramid_descriptors(
+    device: &ash::Device,
+    descriptor_set: vk::DescriptorSet,
+    sampler: vk::Sampler,
+    source: vk::ImageView,
+    source_layout: vk::ImageLayout,
+    destination: vk::ImageView,
+) {
+    let source_info = vk::DescriptorImageInfo {
+        sampler,
+        image_view: source,
+        image_layout: source_layout,
+    };
+    let destination_info = vk::DescriptorImageInfo {
+        sampler: vk::Sampler::null(),
+        image_view: destination,
+        image_layout: vk::ImageLayout::GENERAL,
+    };
+
+    let writes = [
+        vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(0)
+            .descriptor_type(vk::DescriptorType::COMBINED_IMAGE_SAMPLER)
+            .image_info(slice_from_ref(&source_info))
+            .build(),
+        vk::WriteDescriptorSet::builder()
+            .dst_set(descriptor_set)
+            .dst_binding(1)
+            .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+            .image_info(slice_from_ref(&destination_info))
+            .build(),
+    ];
+
+    device.update_descriptor_sets(&writes, &[]);
+}
+
+fn create_compute_pipeline(
+    vulkan_context: &VulkanContext,
+    layout: vk::PipelineLayout,
+    code: &[u32],
+) -> Result<vk::Pipeline> {
+    let device = &vulkan_context.device;
+    let (compute_shader, compute_stage) =
+        create_shader(code, vk::ShaderStageFlags::COMPUTE, vulkan_context)?;
+
+    let create_info = vk::ComputePipelineCreateInfo::builder()
+        .stage(compute_stage)
+        .layout(layout);
+
+    let pipelines = unsafe {
+        device.create_compute_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    unsafe {
+        device.destroy_shader_module(compute_shader, None);
+    }
+
+    Ok(pipelines[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_occlusion_params_size() {
+        // Must match the std140 layout of `OcclusionData` in occlusion_culling.comp
+        assert_eq!(std::mem::size_of::<OcclusionParams>(), 144);
+    }
+}
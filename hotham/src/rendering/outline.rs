@@ -0,0 +1,392 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk;
+use glam::{Affine3A, Vec3, Vec4};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    components::Highlighted,
+    contexts::{
+        render_context::{create_push_constant, create_shader},
+        VulkanContext,
+    },
+    rendering::{
+        buffer::Buffer,
+        descriptors::Descriptors,
+        frame::push_draw_data,
+        primitive::Primitive,
+        resources::{DrawData, Resources},
+        vertex::Vertex,
+    },
+};
+
+static OUTLINE_VERT: &[u32] = include_glsl!("src/shaders/outline.vert", target: vulkan1_1);
+static OUTLINE_FRAG: &[u32] = include_glsl!("src/shaders/outline.frag", target: vulkan1_1);
+
+/// The stencil value written wherever a highlighted mesh covers the screen
+const OUTLINE_STENCIL_REFERENCE: u32 = 1;
+
+/// Push constants shared by the vertex and fragment shaders of both outline pipelines
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct OutlinePushConstants {
+    pub color: Vec4,
+    pub thickness: f32,
+}
+
+impl From<&Highlighted> for OutlinePushConstants {
+    fn from(highlighted: &Highlighted) -> Self {
+        Self {
+            color: highlighted.color,
+            thickness: highlighted.thickness.max(0.),
+        }
+    }
+}
+
+/// A primitive of a [`Highlighted`] mesh to be outlined this frame.
+pub(crate) struct OutlineDraw {
+    pub primitive: Primitive,
+    pub gos_from_local: Affine3A,
+    pub skin_id: u32,
+    pub morph_weights_id: u32,
+    pub push_constants: OutlinePushConstants,
+}
+
+/// The pipelines used to draw outlines around [`Highlighted`] meshes.
+///
+/// Outlines are drawn in two steps, once everything else has been drawn. First the silhouette of every highlighted
+/// mesh is marked in the stencil buffer, ignoring depth. Then each mesh is drawn again, pushed out along its
+/// normals, everywhere except the marked pixels. What's left is a band around the silhouette, which is depth
+/// tested like any other geometry.
+pub struct OutlinePipeline {
+    /// The PBR descriptor set plus [`Highlighted`]'s color and thickness as a push constant
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Writes the silhouette into the stencil buffer, without touching color or depth
+    pub stencil_pipeline: vk::Pipeline,
+    /// Draws the extruded mesh wherever the stencil buffer isn't marked
+    pub outline_pipeline: vk::Pipeline,
+    /// Every primitive queued this frame
+    draws: Vec<OutlineDraw>,
+}
+
+impl OutlinePipeline {
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        descriptors: &Descriptors,
+        render_pass: vk::RenderPass,
+        render_area: &vk::Rect2D,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let push_constant_range = vk::PushConstantRange::builder()
+            .offset(0)
+            .size(std::mem::size_of::<OutlinePushConstants>() as _)
+            .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptors.graphics_layout))
+                    .push_constant_ranges(slice_from_ref(&push_constant_range)),
+                None,
+            )
+        }?;
+
+        let (vertex_shader, vertex_stage) =
+            create_shader(OUTLINE_VERT, vk::ShaderStageFlags::VERTEX, vulkan_context)?;
+        let (fragment_shader, fragment_stage) =
+            create_shader(OUTLINE_FRAG, vk::ShaderStageFlags::FRAGMENT, vulkan_context)?;
+
+        // Marks the silhouette, even where it's hidden, so that the outline never covers the mesh itself.
+        let mark_stencil = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::REPLACE,
+            depth_fail_op: vk::StencilOp::REPLACE,
+            compare_op: vk::CompareOp::ALWAYS,
+            compare_mask: 0xff,
+            write_mask: 0xff,
+            reference: OUTLINE_STENCIL_REFERENCE,
+        };
+        let stencil_pipeline = create_outline_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            render_pass,
+            render_area,
+            msaa_samples,
+            slice_from_ref(&vertex_stage),
+            false,
+            mark_stencil,
+        )?;
+
+        // Only draws outside the marked silhouettes.
+        let test_stencil = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::NOT_EQUAL,
+            compare_mask: 0xff,
+            write_mask: 0,
+            reference: OUTLINE_STENCIL_REFERENCE,
+        };
+        let outline_pipeline = create_outline_pipeline(
+            vulkan_context,
+            pipeline_layout,
+            render_pass,
+            render_area,
+            msaa_samples,
+            &[vertex_stage, fragment_stage],
+            true,
+            test_stencil,
+        )?;
+
+        unsafe {
+            device.destroy_shader_module(vertex_shader, None);
+            device.destroy_shader_module(fragment_shader, None);
+        }
+
+        Ok(Self {
+            pipeline_layout,
+            stencil_pipeline,
+            outline_pipeline,
+            draws: Vec::new(),
+        })
+    }
+
+    /// Queue a primitive of a highlighted mesh to be outlined this frame.
+    pub(crate) fn queue(&mut self, draw: OutlineDraw) {
+        self.draws.push(draw);
+    }
+
+    /// Remove all the outlines queued this frame
+    pub(crate) fn clear(&mut self) {
+        self.draws.clear();
+    }
+
+    /// Draw all the outlines queued this frame.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be inside the PBR render pass.
+    pub(crate) unsafe fn draw(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        descriptor_set: vk::DescriptorSet,
+        resources: &Resources,
+        draw_data_buffer: &mut Buffer<DrawData>,
+    ) {
+        if self.draws.is_empty() {
+            return;
+        }
+
+        let first_instance = draw_data_buffer.len() as u32;
+        let draw_count = self
+            .draws
+            .iter()
+            .take_while(|draw| {
+                push_draw_data(
+                    draw_data_buffer,
+                    &DrawData::new(
+                        &draw.gos_from_local,
+                        draw.skin_id,
+                        draw.morph_weights_id,
+                        &draw.primitive,
+                    ),
+                )
+                .is_some()
+            })
+            .count();
+
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            slice_from_ref(&descriptor_set),
+            &[],
+        );
+        // Text and debug lines bind their own vertex buffers, so the mesh buffers need to be bound again.
+        device.cmd_bind_index_buffer(
+            command_buffer,
+            resources.index_buffer.buffer,
+            0,
+            vk::IndexType::UINT32,
+        );
+        device.cmd_bind_vertex_buffers(
+            command_buffer,
+            0,
+            &[
+                resources.position_buffer.buffer,
+                resources.vertex_buffer.buffer,
+            ],
+            &[0, 0],
+        );
+
+        // Every silhouette has to be marked before any outline is drawn, or outlines would cover overlapping meshes.
+        for (pipeline, extrude) in [
+            (self.stencil_pipeline, false),
+            (self.outline_pipeline, true),
+        ] {
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+            for (draw, instance) in self.draws[..draw_count].iter().zip(first_instance..) {
+                let push_constants = if extrude {
+                    draw.push_constants
+                } else {
+                    OutlinePushConstants {
+                        thickness: 0.,
+                        ..draw.push_constants
+                    }
+                };
+                device.cmd_push_constants(
+                    command_buffer,
+                    self.pipeline_layout,
+                    vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                    0,
+                    create_push_constant(&push_constants),
+                );
+                device.cmd_draw_indexed(
+                    command_buffer,
+                    draw.primitive.indices_count,
+                    1,
+                    draw.primitive.index_buffer_offset,
+                    draw.primitive.vertex_buffer_offset as _,
+                    instance,
+                );
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn create_outline_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_pass: vk::RenderPass,
+    render_area: &vk::Rect2D,
+    msaa_samples: vk::SampleCountFlags,
+    stages: &[vk::PipelineShaderStageCreateInfo],
+    write_color: bool,
+    stencil: vk::StencilOpState,
+) -> Result<vk::Pipeline> {
+    // Vertex input state - identical to the PBR pipeline so that we can share buffers.
+    let position_binding_description = vk::VertexInputBindingDescription::builder()
+        .binding(0)
+        .stride(std::mem::size_of::<Vec3>() as _)
+        .input_rate(vk::VertexInputRate::VERTEX)
+        .build();
+    let vertex_binding_description = vk::VertexInputBindingDescription::builder()
+        .binding(1)
+        .stride(std::mem::size_of::<Vertex>() as _)
+        .input_rate(vk::VertexInputRate::VERTEX)
+        .build();
+    let vertex_binding_descriptions = [position_binding_description, vertex_binding_description];
+    let vertex_attribute_descriptions = Vertex::attribute_descriptions();
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_attribute_descriptions(&vertex_attribute_descriptions)
+        .vertex_binding_descriptions(&vertex_binding_descriptions);
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+    let viewport = vk::Viewport {
+        x: 0.0,
+        y: 0.0,
+        width: render_area.extent.width as _,
+        height: render_area.extent.height as _,
+        min_depth: 0.0,
+        max_depth: 1.0,
+    };
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewports(slice_from_ref(&viewport))
+        .scissors(slice_from_ref(render_area));
+
+    // Both sides are drawn, so that the outline still works for double sided materials and open meshes.
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .polygon_mode(vk::PolygonMode::FILL)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .line_width(1.0);
+
+    let multisample_state =
+        vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(msaa_samples);
+
+    // Outlines are hidden by anything in front of them, but never write depth themselves.
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(write_color)
+        .depth_write_enable(false)
+        .depth_compare_op(vk::CompareOp::GREATER_OR_EQUAL)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(true)
+        .front(stencil)
+        .back(stencil);
+
+    let color_write_mask = if write_color {
+        vk::ColorComponentFlags::R
+            | vk::ColorComponentFlags::G
+            | vk::ColorComponentFlags::B
+            | vk::ColorComponentFlags::A
+    } else {
+        vk::ColorComponentFlags::empty()
+    };
+    let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(color_write_mask)
+        .blend_enable(true)
+        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .alpha_blend_op(vk::BlendOp::ADD)
+        .build();
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .attachments(slice_from_ref(&color_blend_attachment));
+
+    let create_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    let pipelines = unsafe {
+        vulkan_context.device.create_graphics_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    Ok(pipelines[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_outline_push_constants() {
+        let push_constants = OutlinePushConstants::from(&Highlighted {
+            color: Vec4::ONE,
+            thickness: -1.0,
+        });
+
+        // The outline can't be pushed into the mesh
+        assert_eq!(push_constants.thickness, 0.0);
+        assert_eq!(push_constants.color, Vec4::ONE);
+
+        // The color is read at offset 0 and the thickness at offset 16 by the shaders
+        assert_eq!(memoffset::offset_of!(OutlinePushConstants, color), 0);
+        assert_eq!(memoffset::offset_of!(OutlinePushConstants, thickness), 16);
+    }
+}
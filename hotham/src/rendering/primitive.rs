@@ -1,7 +1,11 @@
 use crate::{
     asset_importer::ImportContext,
     contexts::render_context,
-    rendering::{material::NO_MATERIAL, vertex::Vertex},
+    rendering::{
+        material::NO_MATERIAL,
+        resources::{MorphTargetDelta, MAX_MORPH_TARGETS},
+        vertex::Vertex,
+    },
 };
 use glam::{Affine3A, Vec3, Vec4};
 use itertools::izip;
@@ -17,10 +21,16 @@ pub struct Primitive {
     pub vertex_buffer_offset: u32,
     /// Number of vertices
     pub indices_count: u32,
+    /// Number of vertices in the vertex buffer, starting at `vertex_buffer_offset`
+    pub vertex_count: u32,
     /// Material used
     pub material_id: u32,
     /// Bounding sphere - used for culling
     pub bounding_sphere: Vec4,
+    /// Offset into the morph targets buffer
+    pub morph_target_offset: u32,
+    /// Number of morph targets, zero if the primitive has none
+    pub morph_target_count: u32,
 }
 
 impl Primitive {
@@ -37,7 +47,10 @@ impl Primitive {
             material_id,
             index_buffer_offset: render_context.resources.index_buffer.len() as _,
             vertex_buffer_offset: render_context.resources.vertex_buffer.len() as _,
+            vertex_count: vertices.len() as _,
             bounding_sphere: calculate_bounding_sphere(positions),
+            morph_target_offset: 0,
+            morph_target_count: 0,
         };
 
         unsafe {
@@ -49,6 +62,58 @@ impl Primitive {
         primitive
     }
 
+    /// Upload morph targets (blend shapes) for this primitive. Each target holds one position and one normal delta
+    /// per vertex; missing normal deltas are treated as zero. At most [`MAX_MORPH_TARGETS`] targets are used.
+    ///
+    /// The bounding sphere is grown to cover every target at full weight, so culling remains correct.
+    pub fn set_morph_targets(
+        &mut self,
+        position_deltas: &[Vec<Vec3>],
+        normal_deltas: &[Vec<Vec3>],
+        render_context: &mut RenderContext,
+    ) {
+        if position_deltas.len() > MAX_MORPH_TARGETS {
+            println!(
+                "[HOTHAM_PRIMITIVE] WARNING: Primitive has {} morph targets, only the first {MAX_MORPH_TARGETS} will be used",
+                position_deltas.len()
+            );
+        }
+
+        let targets = &position_deltas[..position_deltas.len().min(MAX_MORPH_TARGETS)];
+        let target_count = targets.len();
+        let vertex_count = targets.first().map(|t| t.len()).unwrap_or_default();
+
+        // The deltas of each vertex are stored next to each other, so the vertex shader reads them in one go.
+        let mut deltas = Vec::with_capacity(vertex_count * target_count);
+        let mut max_displacement = 0.0_f32;
+        for vertex in 0..vertex_count {
+            let mut displacement = 0.0;
+            for (target, positions) in targets.iter().enumerate() {
+                let position = positions.get(vertex).copied().unwrap_or_default();
+                let normal = normal_deltas
+                    .get(target)
+                    .and_then(|n| n.get(vertex))
+                    .copied()
+                    .unwrap_or_default();
+                displacement += position.length();
+                deltas.push(MorphTargetDelta {
+                    position: position.extend(0.),
+                    normal: normal.extend(0.),
+                });
+            }
+            max_displacement = max_displacement.max(displacement);
+        }
+
+        let morph_targets_buffer = &mut render_context.resources.morph_targets_buffer;
+        self.morph_target_offset = morph_targets_buffer.len() as _;
+        self.morph_target_count = target_count as _;
+        self.bounding_sphere.w += max_displacement;
+
+        unsafe {
+            morph_targets_buffer.append(&deltas);
+        }
+    }
+
     pub(crate) fn load(
         primitive_data: gltf::Primitive,
         import_context: &mut ImportContext,
@@ -57,6 +122,7 @@ impl Primitive {
         let mut indices = Vec::new();
         let mut positions = Vec::new();
         let mut tex_coords = Vec::new();
+        let mut tex_coords_1 = Vec::new();
         let mut normals = Vec::new();
         let mut joint_indices = Vec::new();
         let mut joint_weights = Vec::new();
@@ -90,8 +156,14 @@ impl Primitive {
         }
 
         if let Some(iter) = reader.read_tex_coords(0) {
+            // If the base color texture was packed into an atlas, point at where it ended up.
+            let atlas_region = import_context
+                .texture_atlas
+                .region_for_material(&primitive_data.material())
+                .copied();
             for v in iter.into_f32() {
-                tex_coords.push([v[0], v[1]].into());
+                let uv = [v[0], v[1]].into();
+                tex_coords.push(atlas_region.map_or(uv, |region| region.remap(uv)));
             }
         } else {
             for _ in 0..positions.len() {
@@ -99,6 +171,16 @@ impl Primitive {
             }
         }
 
+        if let Some(iter) = reader.read_tex_coords(1) {
+            for v in iter.into_f32() {
+                tex_coords_1.push([v[0], v[1]].into());
+            }
+        } else {
+            for _ in 0..positions.len() {
+                tex_coords_1.push([0., 0.].into());
+            }
+        }
+
         if let Some(iter) = reader.read_joints(0) {
             for t in iter.into_u16() {
                 joint_indices.push([t[0] as u8, t[1] as u8, t[2] as u8, t[3] as u8]);
@@ -119,9 +201,15 @@ impl Primitive {
             }
         }
 
-        let vertices: Vec<Vertex> = izip!(normals, tex_coords, joint_indices, joint_weights)
-            .map(Vertex::from_zip)
-            .collect();
+        let vertices: Vec<Vertex> = izip!(
+            normals,
+            tex_coords,
+            tex_coords_1,
+            joint_indices,
+            joint_weights
+        )
+        .map(Vertex::from_zip)
+        .collect();
 
         // All the materials in this glTF file will be imported into the material buffer, so all we need
         // to do is grab the index of this material and add it to the running offset. If we don't do this,
@@ -132,13 +220,38 @@ impl Primitive {
             NO_MATERIAL as u32
         };
 
-        Primitive::new(
+        let mut primitive = Primitive::new(
             &positions,
             &vertices,
             &indices,
             material_id,
             import_context.render_context,
-        )
+        );
+
+        // Morph targets
+        let mut position_deltas = Vec::new();
+        let mut normal_deltas = Vec::new();
+        for (target_positions, target_normals, _) in reader.read_morph_targets() {
+            position_deltas.push(
+                target_positions
+                    .map(|iter| iter.map(Vec3::from).collect())
+                    .unwrap_or_else(|| vec![Vec3::ZERO; positions.len()]),
+            );
+            normal_deltas.push(
+                target_normals
+                    .map(|iter| iter.map(Vec3::from).collect())
+                    .unwrap_or_else(|| vec![Vec3::ZERO; positions.len()]),
+            );
+        }
+        if !position_deltas.is_empty() {
+            primitive.set_morph_targets(
+                &position_deltas,
+                &normal_deltas,
+                import_context.render_context,
+            );
+        }
+
+        primitive
     }
 
     /// Get a bounding sphere for the primitive, applying a transform
@@ -0,0 +1,74 @@
+/// How the world is drawn. Anything other than [`RenderMode::Lit`] is meant for diagnosing problems with geometry
+/// or performance in-headset, and can be changed at any time with
+/// [`RenderContext::render_mode`](crate::contexts::RenderContext::render_mode).
+///
+/// Render modes only apply to the main view. Render targets are always lit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Materials are shaded as usual
+    #[default]
+    Lit,
+    /// Every material is shaded as if it were unlit, showing only its base color
+    Unlit,
+    /// Only the edges of each triangle are drawn, shaded as usual. Falls back to [`RenderMode::Lit`] if the device
+    /// doesn't support `fillModeNonSolid`.
+    Wireframe,
+    /// The normal of each fragment, in globally oriented stage space, mapped from -1..1 to 0..1
+    Normals,
+    /// Every fragment adds a little heat to its pixel, without depth testing, so the brightest pixels are the ones
+    /// that were shaded the most times. Back faces are still culled, and the skybox isn't drawn.
+    Overdraw,
+}
+
+impl RenderMode {
+    /// Every render mode, in the order [`RenderMode::next`] cycles through them
+    pub const ALL: [RenderMode; 5] = [
+        RenderMode::Lit,
+        RenderMode::Unlit,
+        RenderMode::Wireframe,
+        RenderMode::Normals,
+        RenderMode::Overdraw,
+    ];
+
+    /// The render mode after this one, wrapping around to [`RenderMode::Lit`]
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|m| *m == self).unwrap();
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+
+    /// Passed to the fragment shader in `SceneData.params.w`. Must match the `RENDER_MODE_*` defines in pbr.frag
+    pub(crate) fn shader_index(self) -> f32 {
+        match self {
+            RenderMode::Lit | RenderMode::Wireframe => 0.,
+            RenderMode::Unlit => 1.,
+            RenderMode::Normals => 2.,
+            RenderMode::Overdraw => 3.,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_next() {
+        let mut render_mode = RenderMode::default();
+        assert_eq!(render_mode, RenderMode::Lit);
+        for expected in RenderMode::ALL.iter().skip(1) {
+            render_mode = render_mode.next();
+            assert_eq!(render_mode, *expected);
+        }
+        assert_eq!(render_mode.next(), RenderMode::Lit);
+    }
+
+    #[test]
+    pub fn test_shader_index() {
+        // Wireframes are shaded as usual, only the pipeline changes.
+        assert_eq!(RenderMode::Lit.shader_index(), 0.);
+        assert_eq!(RenderMode::Wireframe.shader_index(), 0.);
+        assert_eq!(RenderMode::Unlit.shader_index(), 1.);
+        assert_eq!(RenderMode::Normals.shader_index(), 2.);
+        assert_eq!(RenderMode::Overdraw.shader_index(), 3.);
+    }
+}
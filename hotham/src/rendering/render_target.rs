@@ -0,0 +1,653 @@
+use std::{convert::TryInto, slice::from_ref as slice_from_ref};
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use glam::{Affine3A, Mat4, Vec4};
+
+use crate::{
+    contexts::{
+        render_context::{
+            create_pipeline, create_pipeline_set, create_transparent_pipeline, destroy_pipelines,
+            Shaders, PIPELINE_DEPTH,
+        },
+        RenderContext, VulkanContext,
+    },
+    id_arena::Id,
+    rendering::{
+        buffer::Buffer,
+        camera::{extract_planes_from_frustum, Frustum},
+        descriptors::{
+            Descriptors, DRAW_DATA_BINDING, GRAPHICS_IMAGE_DESCRIPTOR_COUNT,
+            GRAPHICS_STORAGE_BUFFER_COUNT, SCENE_DATA_BINDING,
+        },
+        frame::DRAW_DATA_BUFFER_SIZE,
+        image::Image,
+        resources::{DrawData, Resources},
+        scene_data::SceneData,
+        skybox::SkyboxPipeline,
+        texture::{Texture, TextureUsage},
+    },
+    COLOR_FORMAT, DEPTH_FORMAT,
+};
+
+static RENDER_TARGET_CLEAR_VALUES: [vk::ClearValue; 2] = [
+    vk::ClearValue {
+        color: vk::ClearColorValue {
+            float32: [0.0, 0.0, 0.0, 1.0],
+        },
+    },
+    vk::ClearValue {
+        depth_stencil: vk::ClearDepthStencilValue {
+            depth: 0.0,
+            stencil: 0,
+        },
+    },
+];
+
+/// An offscreen image the world can be rendered into, instead of the OpenXR swapchain.
+///
+/// Created with [`RenderContext::create_render_target`], and rendered into with [`RenderContext::schedule_render`]
+/// or by a [`Camera`](crate::components::Camera).
+///
+/// The image is part of the shared texture array, so once it's been rendered it can be sampled by any
+/// [`Material`](crate::rendering::material::Material) through `texture.index`. It's rendered from a single,
+/// monoscopic view without MSAA, and holds linear colors: tonemapping and color grading are applied when the
+/// texture is drawn in the world, not when it's rendered.
+///
+/// Only meshes drawn by the PBR pipelines and the skybox are rendered. Custom materials, text, debug lines,
+/// outlines and bloom are left out, as are meshes whose material samples the render target itself.
+pub struct RenderTarget {
+    /// The color image the world is rendered into
+    pub texture: Texture,
+    /// Depth buffer used while rendering
+    pub depth_image: Image,
+    /// Render pass that leaves the color image ready to be sampled
+    pub render_pass: vk::RenderPass,
+    /// Framebuffer wrapping the color image and `depth_image`
+    pub framebuffer: vk::Framebuffer,
+    /// The size of the color image
+    pub render_area: vk::Rect2D,
+    /// Used for opaque materials, created for this render target's render pass and size
+    pub pipeline: vk::Pipeline,
+    /// Used for opaque, double sided materials
+    pub double_sided_pipeline: vk::Pipeline,
+    /// Used for transparent materials
+    pub transparent_pipeline: vk::Pipeline,
+    /// Used for transparent, double sided materials
+    pub double_sided_transparent_pipeline: vk::Pipeline,
+    /// Draws the skybox behind the world
+    pub skybox_pipeline: SkyboxPipeline,
+    /// Pool the descriptor sets are allocated from
+    pub descriptor_pool: vk::DescriptorPool,
+    /// One set per frame. A copy of the frame's shared descriptor set, but with its own scene data and draw data.
+    pub descriptor_sets: [vk::DescriptorSet; PIPELINE_DEPTH],
+    /// The scene data seen from this render target's view, one buffer per frame
+    scene_data_buffers: [Buffer<SceneData>; PIPELINE_DEPTH],
+    /// The draw data for the instances seen from this render target's view, one buffer per frame. Cleared by
+    /// `update`.
+    pub(crate) draw_data_buffers: [Buffer<DrawData>; PIPELINE_DEPTH],
+}
+
+impl RenderTarget {
+    /// Create a render target of `resolution` pixels.
+    pub(crate) fn new(
+        name: &str,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        resolution: vk::Extent2D,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let render_area = vk::Rect2D {
+            extent: resolution,
+            ..Default::default()
+        };
+
+        let image = vulkan_context.create_image(
+            COLOR_FORMAT,
+            &resolution,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+            1,
+            1,
+        )?;
+        let index = render_context.create_texture_image(
+            name,
+            vulkan_context,
+            &[],
+            1,
+            1,
+            vec![0],
+            &image,
+        )?;
+        let texture = Texture {
+            image,
+            index,
+            texture_usage: TextureUsage::Other,
+            sampler_desc: Default::default(),
+        };
+
+        let depth_image = vulkan_context.create_image(
+            DEPTH_FORMAT,
+            &resolution,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            1,
+            1,
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::IMAGE,
+            depth_image.handle.as_raw(),
+            &format!("{name} Depth"),
+        )?;
+
+        let render_pass = create_render_target_render_pass(vulkan_context)?;
+        let attachments = [texture.image.view, depth_image.view];
+        let framebuffer = unsafe {
+            device.create_framebuffer(
+                &vk::FramebufferCreateInfo::builder()
+                    .render_pass(render_pass)
+                    .attachments(&attachments)
+                    .width(resolution.width)
+                    .height(resolution.height)
+                    .layers(1),
+                None,
+            )
+        }?;
+
+        let [pipeline, double_sided_pipeline, transparent_pipeline, double_sided_transparent_pipeline] =
+            create_pbr_pipelines(
+                vulkan_context,
+                render_context.pipeline_layout,
+                &render_area,
+                render_pass,
+                &render_context.shaders,
+            )?;
+        let skybox_pipeline = SkyboxPipeline::new(
+            vulkan_context,
+            &render_context.descriptors,
+            render_pass,
+            &render_area,
+            vk::SampleCountFlags::TYPE_1,
+        )?;
+
+        let (descriptor_pool, descriptor_sets) =
+            allocate_descriptor_sets(device, &render_context.descriptors)?;
+        let scene_data_buffers = descriptor_sets.map(|descriptor_set| unsafe {
+            let mut buffer = Buffer::new(vulkan_context, vk::BufferUsageFlags::UNIFORM_BUFFER, 1);
+            buffer.push(&Default::default());
+            buffer.update_descriptor_set(device, descriptor_set, SCENE_DATA_BINDING);
+            buffer
+        });
+        let draw_data_buffers = descriptor_sets.map(|descriptor_set| unsafe {
+            let buffer = Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                DRAW_DATA_BUFFER_SIZE,
+            );
+            buffer.update_descriptor_set(device, descriptor_set, DRAW_DATA_BINDING);
+            buffer
+        });
+
+        // Other render targets may sample this one before it's first rendered, so it needs to start out readable.
+        let command_buffer = vulkan_context.begin_single_time_commands();
+        unsafe {
+            let barrier = vk::ImageMemoryBarrier::builder()
+                .old_layout(vk::ImageLayout::UNDEFINED)
+                .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .image(texture.image.handle)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::COLOR,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: 1,
+                })
+                .dst_access_mask(vk::AccessFlags::SHADER_READ);
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::FRAGMENT_SHADER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&barrier),
+            );
+        }
+        vulkan_context.end_single_time_commands(command_buffer);
+
+        let debug_names = [
+            (
+                vk::ObjectType::RENDER_PASS,
+                render_pass.as_raw(),
+                "Render Pass",
+            ),
+            (vk::ObjectType::PIPELINE, pipeline.as_raw(), "Pipeline"),
+            (
+                vk::ObjectType::PIPELINE,
+                double_sided_pipeline.as_raw(),
+                "Double Sided Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                transparent_pipeline.as_raw(),
+                "Transparent Pipeline",
+            ),
+            (
+                vk::ObjectType::PIPELINE,
+                double_sided_transparent_pipeline.as_raw(),
+                "Double Sided Transparent Pipeline",
+            ),
+        ];
+        for (object_type, handle, object_name) in debug_names {
+            vulkan_context.set_debug_name(object_type, handle, &format!("{name} {object_name}"))?;
+        }
+
+        Ok(Self {
+            texture,
+            depth_image,
+            render_pass,
+            framebuffer,
+            render_area,
+            pipeline,
+            double_sided_pipeline,
+            transparent_pipeline,
+            double_sided_transparent_pipeline,
+            skybox_pipeline,
+            descriptor_pool,
+            descriptor_sets,
+            scene_data_buffers,
+            draw_data_buffers,
+        })
+    }
+
+    /// The width of the render target divided by its height
+    pub fn aspect_ratio(&self) -> f32 {
+        let extent = self.render_area.extent;
+        extent.width as f32 / extent.height.max(1) as f32
+    }
+
+    /// Create the PBR pipelines this render target draws with from `shaders`, eg. after they've been hot reloaded.
+    /// Swap them in with [`RenderTarget::replace_pipelines`].
+    pub(crate) fn create_pipelines(
+        &self,
+        vulkan_context: &VulkanContext,
+        pipeline_layout: vk::PipelineLayout,
+        shaders: &Shaders,
+    ) -> Result<[vk::Pipeline; 4]> {
+        create_pbr_pipelines(
+            vulkan_context,
+            pipeline_layout,
+            &self.render_area,
+            self.render_pass,
+            shaders,
+        )
+    }
+
+    /// Draw with `pipelines`, created by [`RenderTarget::create_pipelines`], and destroy the ones they replace.
+    ///
+    /// # Safety
+    ///
+    /// The GPU must have finished with every command that uses the old pipelines.
+    pub(crate) unsafe fn replace_pipelines(
+        &mut self,
+        device: &ash::Device,
+        pipelines: [vk::Pipeline; 4],
+    ) {
+        let replaced = [
+            self.pipeline,
+            self.double_sided_pipeline,
+            self.transparent_pipeline,
+            self.double_sided_transparent_pipeline,
+        ];
+        [
+            self.pipeline,
+            self.double_sided_pipeline,
+            self.transparent_pipeline,
+            self.double_sided_transparent_pipeline,
+        ] = pipelines;
+        destroy_pipelines(device, &replaced);
+    }
+
+    /// Bring this frame's descriptor set up to date with the shared one, write the scene data seen from `view` and
+    /// clear this frame's draw data.
+    ///
+    /// # Safety
+    ///
+    /// The GPU must have finished with the commands previously recorded for `frame_index`.
+    pub(crate) unsafe fn update(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        descriptors: &Descriptors,
+        resources: &Resources,
+        frame_index: usize,
+        scene_data: &SceneData,
+    ) {
+        descriptors.copy_graphics_set(
+            vulkan_context,
+            frame_index,
+            self.descriptor_sets[frame_index],
+            resources.texture_count(),
+            resources.cube_texture_count(),
+        );
+        self.scene_data_buffers[frame_index].overwrite(slice_from_ref(scene_data));
+        self.draw_data_buffers[frame_index].clear();
+    }
+
+    /// Begin the render pass that renders into this render target, and bind its descriptor set.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state and not inside another render pass.
+    pub(crate) unsafe fn begin_render_pass(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline_layout: vk::PipelineLayout,
+        frame_index: usize,
+    ) {
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffer)
+            .render_area(self.render_area)
+            .clear_values(&RENDER_TARGET_CLEAR_VALUES);
+
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_begin_info,
+            vk::SubpassContents::INLINE,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline_layout,
+            0,
+            slice_from_ref(&self.descriptor_sets[frame_index]),
+            &[],
+        );
+    }
+}
+
+/// A view the world is rendered from into a [`RenderTarget`], see [`RenderContext::schedule_render`].
+#[derive(Debug, Clone, Copy)]
+pub struct RenderTargetView {
+    /// The render target to render into
+    pub render_target: Id<RenderTarget>,
+    /// Transforms points in the view's space into global space. The view looks down its negative Z axis, with Y
+    /// up.
+    pub global_from_view: Affine3A,
+    /// The vertical field of view, in radians
+    pub fov_y: f32,
+    /// Distance to the near plane, in meters
+    pub near: f32,
+    /// Objects further away than this, in meters, aren't drawn
+    pub far: f32,
+}
+
+impl RenderTargetView {
+    /// The view-projection matrix for a render target with the given aspect ratio, from globally oriented stage
+    /// space. Like the main cameras, the projection uses inverse Z and has no far plane, so that the skybox can be
+    /// drawn at infinity.
+    pub fn view_projection(&self, gos_from_global: &Affine3A, aspect_ratio: f32) -> Mat4 {
+        let half_height = (self.fov_y * 0.5).tan();
+        let half_width = half_height * aspect_ratio;
+        let frustum = Frustum {
+            left: -half_width.atan(),
+            right: half_width.atan(),
+            up: half_height.atan(),
+            down: -half_height.atan(),
+        };
+        let gos_from_view = *gos_from_global * self.global_from_view;
+        frustum.projection(self.near) * Mat4::from(gos_from_view.inverse())
+    }
+
+    /// The view's position in globally oriented stage space, in homogeneous coordinates
+    pub fn position_in_gos(&self, gos_from_global: &Affine3A) -> Vec4 {
+        gos_from_global
+            .transform_point3(self.global_from_view.translation.into())
+            .extend(1.)
+    }
+
+    /// Is a bounding sphere in globally oriented stage space inside the view's frustum, and closer than `far`?
+    pub(crate) fn is_visible(
+        &self,
+        gos_from_global: &Affine3A,
+        clip_planes: &Mat4,
+        bounding_sphere: Vec4,
+    ) -> bool {
+        let center = bounding_sphere.truncate();
+        let radius = bounding_sphere.w;
+        let forward = -gos_from_global
+            .transform_vector3(self.global_from_view.z_axis.into())
+            .normalize();
+        let distance = (center - self.position_in_gos(gos_from_global).truncate()).dot(forward);
+
+        distance - radius <= self.far
+            && (*clip_planes * center.extend(1.))
+                .cmpgt(Vec4::splat(-radius))
+                .all()
+    }
+
+    /// The clip planes used by [`Self::is_visible`]
+    pub(crate) fn clip_planes(&self, gos_from_global: &Affine3A, aspect_ratio: f32) -> Mat4 {
+        extract_planes_from_frustum(&self.view_projection(gos_from_global, aspect_ratio))
+    }
+}
+
+/// Create the pipeline, double sided, transparent and double sided transparent PBR pipelines for a render target. They
+/// bake in the size of the viewport, so each render target needs its own.
+fn create_pbr_pipelines(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_area: &vk::Rect2D,
+    render_pass: vk::RenderPass,
+    shaders: &Shaders,
+) -> Result<[vk::Pipeline; 4]> {
+    let samples = vk::SampleCountFlags::TYPE_1;
+    create_pipeline_set(
+        vulkan_context,
+        [
+            &|| {
+                create_pipeline(
+                    vulkan_context,
+                    pipeline_layout,
+                    render_area,
+                    render_pass,
+                    shaders,
+                    samples,
+                    vk::CullModeFlags::BACK,
+                )
+            },
+            &|| {
+                create_pipeline(
+                    vulkan_context,
+                    pipeline_layout,
+                    render_area,
+                    render_pass,
+                    shaders,
+                    samples,
+                    vk::CullModeFlags::NONE,
+                )
+            },
+            &|| {
+                create_transparent_pipeline(
+                    vulkan_context,
+                    pipeline_layout,
+                    render_area,
+                    render_pass,
+                    shaders,
+                    samples,
+                    vk::CullModeFlags::BACK,
+                )
+            },
+            &|| {
+                create_transparent_pipeline(
+                    vulkan_context,
+                    pipeline_layout,
+                    render_area,
+                    render_pass,
+                    shaders,
+                    samples,
+                    vk::CullModeFlags::NONE,
+                )
+            },
+        ],
+    )
+}
+
+fn create_render_target_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass> {
+    let color_attachment = vk::AttachmentDescription::builder()
+        .format(COLOR_FORMAT)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .build();
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(DEPTH_FORMAT)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+        .build();
+    let attachments = [color_attachment, depth_attachment];
+
+    let color_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        .build();
+    let depth_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(slice_from_ref(&color_attachment_reference))
+        .depth_stencil_attachment(&depth_attachment_reference);
+
+    // Make sure the previous frame has finished reading the image before we write to it, and that our writes are
+    // finished before any fragment shader reads from it.
+    let dependencies = [
+        vk::SubpassDependency::builder()
+            .src_subpass(vk::SUBPASS_EXTERNAL)
+            .dst_subpass(0)
+            .src_stage_mask(
+                vk::PipelineStageFlags::FRAGMENT_SHADER
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            )
+            .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_stage_mask(
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                    | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            )
+            .dst_access_mask(
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            )
+            .build(),
+        vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+            .build(),
+    ];
+
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(&attachments)
+        .subpasses(slice_from_ref(&subpass))
+        .dependencies(&dependencies);
+
+    unsafe { vulkan_context.device.create_render_pass(&create_info, None) }.map_err(Into::into)
+}
+
+/// Allocate one graphics descriptor set per frame from a pool of their own, as each one holds a full copy of the
+/// texture array.
+fn allocate_descriptor_sets(
+    device: &ash::Device,
+    descriptors: &Descriptors,
+) -> Result<(vk::DescriptorPool, [vk::DescriptorSet; PIPELINE_DEPTH])> {
+    let set_count = PIPELINE_DEPTH as u32;
+    let pool_sizes = [
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_BUFFER,
+            descriptor_count: GRAPHICS_STORAGE_BUFFER_COUNT * set_count,
+        },
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::UNIFORM_BUFFER,
+            descriptor_count: set_count,
+        },
+        vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            descriptor_count: GRAPHICS_IMAGE_DESCRIPTOR_COUNT * set_count,
+        },
+    ];
+
+    let descriptor_pool = unsafe {
+        device.create_descriptor_pool(
+            &vk::DescriptorPoolCreateInfo::builder()
+                .pool_sizes(&pool_sizes)
+                .max_sets(set_count),
+            None,
+        )
+    }?;
+    let set_layouts = [descriptors.graphics_layout; PIPELINE_DEPTH];
+    let descriptor_sets = unsafe {
+        device.allocate_descriptor_sets(
+            &vk::DescriptorSetAllocateInfo::builder()
+                .descriptor_pool(descriptor_pool)
+                .set_layouts(&set_layouts),
+        )
+    }?;
+
+    Ok((
+        descriptor_pool,
+        descriptor_sets.as_slice().try_into().unwrap(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::id_arena::Arena;
+
+    #[test]
+    pub fn test_is_visible() {
+        // Looking down the negative Z axis from two metres up
+        let view = RenderTargetView {
+            render_target: Arena::<RenderTarget>::new().next_id(),
+            global_from_view: Affine3A::from_translation([0., 2., 0.].into()),
+            fov_y: 90_f32.to_radians(),
+            near: 0.05,
+            far: 10.,
+        };
+        let gos_from_global = Affine3A::IDENTITY;
+        let clip_planes = view.clip_planes(&gos_from_global, 1.);
+        let is_visible =
+            |sphere: [f32; 4]| view.is_visible(&gos_from_global, &clip_planes, sphere.into());
+
+        assert!(is_visible([0., 2., -5., 0.5]));
+
+        // Behind the view, outside the frustum and too far away
+        assert!(!is_visible([0., 2., 5., 0.5]));
+        assert!(!is_visible([10., 2., -5., 0.5]));
+        assert!(!is_visible([0., 2., -20., 0.5]));
+
+        // Spheres that reach into the frustum are visible
+        assert!(is_visible([6., 2., -5., 1.5]));
+        assert!(is_visible([0., 2., -11., 1.5]));
+
+        // Moving the stage moves the view along with everything else
+        let gos_from_global = Affine3A::from_translation([0., -2., 0.].into());
+        let clip_planes = view.clip_planes(&gos_from_global, 1.);
+        assert!(view.is_visible(&gos_from_global, &clip_planes, [0., 0., -5., 0.5].into()));
+        assert!(!view.is_visible(&gos_from_global, &clip_planes, [0., 2., -5., 0.5].into()));
+    }
+}
@@ -1,35 +1,45 @@
-use ash::vk;
-use glam::{Mat4, Vec3, Vec4};
+use std::collections::HashMap;
+
+use ash::vk::{self, Handle};
+use glam::{Affine3A, Mat4, Vec3, Vec4};
 use id_arena::Arena;
 use vulkan_context::VulkanContext;
 
-use crate::contexts::vulkan_context;
+use crate::contexts::{render_context::PIPELINE_DEPTH, vulkan_context};
 
 use super::{
     buffer::Buffer,
-    descriptors::{Descriptors, SKINS_BINDING},
+    descriptors::{Descriptors, MORPH_TARGETS_BINDING, SKINS_BINDING},
     image::Image,
     material::Material,
     memory::allocate_memory,
     mesh_data::MeshData,
+    primitive::Primitive,
+    sampler::SamplerDesc,
+    skinning::SKINNED_VERTEX_BUFFER_SIZE,
     texture::{parse_ktx2, DEFAULT_COMPONENT_MAPPING},
     vertex::Vertex,
 };
 
-static VERTEX_BUFFER_SIZE: usize = 2_000_000; // TODO
+/// The number of vertices that can be loaded. Skinned vertices are written to the buffers after these.
+pub(crate) const VERTEX_BUFFER_SIZE: usize = 2_000_000; // TODO
 static SKINS_BUFFER_SIZE: usize = 4; // TODO
+static MORPH_TARGETS_BUFFER_SIZE: usize = 500_000;
 
 pub(crate) const MAX_JOINTS: usize = 64;
 
+/// The maximum number of morph targets a primitive can have. Must match `MAX_MORPH_TARGETS` in morph_targets.glsl
+pub const MAX_MORPH_TARGETS: usize = 8;
+
 /// f16vec3
 pub type F16VEC3 = [u16; 3];
 
 /// A container that holds all of the resources required to draw a frame.
 pub struct Resources {
-    /// Position only data
+    /// Position only data. Followed by the skinned positions of each frame in flight, see [`super::skinning`].
     pub position_buffer: Buffer<Vec3>,
 
-    /// All the vertices that will be drawn this frame.
+    /// All the vertices that will be drawn this frame. Followed by the skinned vertices of each frame in flight.
     pub vertex_buffer: Buffer<Vertex>,
 
     /// All the indices that will be drawn this frame.
@@ -46,12 +56,18 @@ pub struct Resources {
     /// Buffer for skins
     pub skins_buffer: Buffer<[Mat4; 64]>,
 
+    /// Position and normal deltas for every primitive with morph targets
+    pub morph_targets_buffer: Buffer<MorphTargetDelta>,
+
     /// Shared sampler in repeat mode, takes care of most things
     pub texture_sampler: vk::Sampler,
 
     /// Shared sampler
     pub cube_sampler: vk::Sampler,
 
+    /// Samplers created for textures with their own [`SamplerDesc`]
+    pub samplers: HashMap<SamplerDesc, vk::Sampler>,
+
     /// Staging buffer for GPU data transfer
     pub staging_buffer: StagingBuffer,
 
@@ -63,15 +79,17 @@ pub struct Resources {
 impl Resources {
     /// Create all the buffers required and update the relevant descriptor sets.
     pub(crate) unsafe fn new(vulkan_context: &VulkanContext, descriptors: &Descriptors) -> Self {
+        // The skinning pre-pass writes to the end of the vertex buffers, so leave room for it.
+        let vertex_buffer_size = VERTEX_BUFFER_SIZE + PIPELINE_DEPTH * SKINNED_VERTEX_BUFFER_SIZE;
         let position_buffer = Buffer::new(
             vulkan_context,
-            vk::BufferUsageFlags::VERTEX_BUFFER,
-            VERTEX_BUFFER_SIZE,
+            vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER,
+            vertex_buffer_size,
         );
         let vertex_buffer = Buffer::new(
             vulkan_context,
-            vk::BufferUsageFlags::VERTEX_BUFFER,
-            VERTEX_BUFFER_SIZE,
+            vk::BufferUsageFlags::VERTEX_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER,
+            vertex_buffer_size,
         );
 
         let index_buffer = Buffer::new(
@@ -93,8 +111,19 @@ impl Resources {
             SKINS_BUFFER_SIZE,
         );
 
+        let morph_targets_buffer = Buffer::new(
+            vulkan_context,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            MORPH_TARGETS_BUFFER_SIZE,
+        );
+
         for set in descriptors.sets {
             skins_buffer.update_descriptor_set(&vulkan_context.device, set, SKINS_BINDING);
+            morph_targets_buffer.update_descriptor_set(
+                &vulkan_context.device,
+                set,
+                MORPH_TARGETS_BINDING,
+            );
         }
 
         let texture_sampler = vulkan_context
@@ -109,21 +138,59 @@ impl Resources {
 
         let staging_buffer = StagingBuffer::new(vulkan_context);
 
+        let debug_names = [
+            (position_buffer.buffer, "Positions"),
+            (vertex_buffer.buffer, "Vertices"),
+            (index_buffer.buffer, "Indices"),
+            (materials_buffer.buffer, "Materials"),
+            (skins_buffer.buffer, "Skins"),
+            (morph_targets_buffer.buffer, "Morph Targets"),
+        ];
+        for (buffer, name) in debug_names {
+            vulkan_context
+                .set_debug_name(vk::ObjectType::BUFFER, buffer.as_raw(), name)
+                .unwrap();
+        }
+
         Self {
             position_buffer,
             vertex_buffer,
             index_buffer,
             materials_buffer,
             skins_buffer,
+            morph_targets_buffer,
             mesh_data: Default::default(),
             texture_count: 1, // IMPORTANT! Because we stashed the BRDF Lut texture in here, make sure we increment the count accordingly
             cube_texture_count: 2, // IMPORTANT! We stashed the IBL textures in here, so increment the count
             texture_sampler,
             cube_sampler,
+            samplers: HashMap::from([(SamplerDesc::default(), texture_sampler)]),
             staging_buffer,
         }
     }
 
+    /// Get the sampler matching `sampler_desc`, creating it if this is the first time it's been used.
+    pub fn get_sampler(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        sampler_desc: &SamplerDesc,
+    ) -> vk::Sampler {
+        *self.samplers.entry(*sampler_desc).or_insert_with(|| {
+            let create_info = sampler_desc.create_info(vulkan_context.max_sampler_anisotropy());
+            unsafe { vulkan_context.device.create_sampler(&create_info, None) }.unwrap()
+        })
+    }
+
+    /// The number of textures that have been written to the texture array
+    pub(crate) fn texture_count(&self) -> u32 {
+        self.texture_count
+    }
+
+    /// The number of cube textures that have been written to the cube texture array
+    pub(crate) fn cube_texture_count(&self) -> u32 {
+        self.cube_texture_count
+    }
+
     pub(crate) unsafe fn write_texture_to_array(
         &mut self,
         vulkan_context: &VulkanContext,
@@ -249,6 +316,44 @@ pub struct DrawData {
     pub local_from_gos: Mat4,
     /// An optional skin to use.
     pub skin_id: u32,
+    /// An optional set of morph target weights to use, indexing into the frame's morph weights buffer.
+    pub morph_weights_id: u32,
+    /// Offset of the primitive's deltas in the morph targets buffer
+    pub morph_target_offset: u32,
+    /// The number of morph targets the primitive has
+    pub morph_target_count: u32,
+    /// Offset of the primitive's first vertex in the vertex buffer, used to find the deltas of each vertex
+    pub vertex_offset: u32,
+}
+
+impl DrawData {
+    /// Create the draw data for an instance of `primitive`
+    pub fn new(
+        gos_from_local: &Affine3A,
+        skin_id: u32,
+        morph_weights_id: u32,
+        primitive: &Primitive,
+    ) -> Self {
+        Self {
+            gos_from_local: (*gos_from_local).into(),
+            local_from_gos: gos_from_local.inverse().into(),
+            skin_id,
+            morph_weights_id,
+            morph_target_offset: primitive.morph_target_offset,
+            morph_target_count: primitive.morph_target_count,
+            vertex_offset: primitive.vertex_buffer_offset,
+        }
+    }
+}
+
+/// The difference between a vertex in its base pose and in a single morph target.
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct MorphTargetDelta {
+    /// Position delta, in local space. `w` is unused.
+    pub position: Vec4,
+    /// Normal delta, in local space. `w` is unused.
+    pub normal: Vec4,
 }
 
 /// Information for the culling shader on how to cull this primitive.
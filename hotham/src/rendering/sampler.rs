@@ -0,0 +1,133 @@
+use ash::vk;
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+/// Describes how a texture is filtered and wrapped when it's sampled.
+///
+/// Samplers are created on demand and shared between every texture with the same description, see
+/// [`super::resources::Resources::get_sampler`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SamplerDesc {
+    /// Filter used when the texture is magnified
+    pub mag_filter: vk::Filter,
+    /// Filter used when the texture is minified
+    pub min_filter: vk::Filter,
+    /// How mip levels are blended together
+    pub mipmap_mode: vk::SamplerMipmapMode,
+    /// Wrap mode for the U (S) texture coordinate
+    pub address_mode_u: vk::SamplerAddressMode,
+    /// Wrap mode for the V (T) texture coordinate
+    pub address_mode_v: vk::SamplerAddressMode,
+    /// The maximum level of anisotropic filtering, eg. `4` or `16`. `1` disables anisotropic filtering.
+    /// Clamped to the maximum supported by the device.
+    pub max_anisotropy: u32,
+}
+
+impl Default for SamplerDesc {
+    fn default() -> Self {
+        Self {
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            mipmap_mode: vk::SamplerMipmapMode::LINEAR,
+            address_mode_u: vk::SamplerAddressMode::REPEAT,
+            address_mode_v: vk::SamplerAddressMode::REPEAT,
+            max_anisotropy: 1,
+        }
+    }
+}
+
+impl SamplerDesc {
+    /// Create a description from a glTF sampler. Anything the sampler doesn't specify uses the default.
+    pub fn from_gltf(sampler: &gltf::texture::Sampler) -> Self {
+        let default = Self::default();
+        let mag_filter = match sampler.mag_filter() {
+            Some(MagFilter::Nearest) => vk::Filter::NEAREST,
+            Some(MagFilter::Linear) => vk::Filter::LINEAR,
+            None => default.mag_filter,
+        };
+        let (min_filter, mipmap_mode) = match sampler.min_filter() {
+            Some(MinFilter::Nearest) | Some(MinFilter::NearestMipmapNearest) => {
+                (vk::Filter::NEAREST, vk::SamplerMipmapMode::NEAREST)
+            }
+            Some(MinFilter::Linear) | Some(MinFilter::LinearMipmapNearest) => {
+                (vk::Filter::LINEAR, vk::SamplerMipmapMode::NEAREST)
+            }
+            Some(MinFilter::NearestMipmapLinear) => {
+                (vk::Filter::NEAREST, vk::SamplerMipmapMode::LINEAR)
+            }
+            Some(MinFilter::LinearMipmapLinear) => {
+                (vk::Filter::LINEAR, vk::SamplerMipmapMode::LINEAR)
+            }
+            None => (default.min_filter, default.mipmap_mode),
+        };
+
+        Self {
+            mag_filter,
+            min_filter,
+            mipmap_mode,
+            address_mode_u: address_mode_from_gltf(sampler.wrap_s()),
+            address_mode_v: address_mode_from_gltf(sampler.wrap_t()),
+            ..default
+        }
+    }
+
+    /// Set the level of anisotropic filtering
+    pub fn with_max_anisotropy(mut self, max_anisotropy: u32) -> Self {
+        self.max_anisotropy = max_anisotropy;
+        self
+    }
+
+    /// Get the create info for this sampler. `device_max_anisotropy` is zero if the device doesn't support
+    /// anisotropic filtering.
+    pub(crate) fn create_info(&self, device_max_anisotropy: f32) -> vk::SamplerCreateInfo {
+        let max_anisotropy = (self.max_anisotropy as f32).min(device_max_anisotropy);
+        vk::SamplerCreateInfo::builder()
+            .mag_filter(self.mag_filter)
+            .min_filter(self.min_filter)
+            .mipmap_mode(self.mipmap_mode)
+            .address_mode_u(self.address_mode_u)
+            .address_mode_v(self.address_mode_v)
+            .address_mode_w(self.address_mode_v)
+            .anisotropy_enable(max_anisotropy > 1.0)
+            .max_anisotropy(max_anisotropy.max(1.0))
+            .border_color(vk::BorderColor::FLOAT_TRANSPARENT_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vk::CompareOp::NEVER)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(vk::LOD_CLAMP_NONE)
+            .build()
+    }
+}
+
+fn address_mode_from_gltf(wrapping_mode: WrappingMode) -> vk::SamplerAddressMode {
+    match wrapping_mode {
+        WrappingMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+        WrappingMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+        WrappingMode::Repeat => vk::SamplerAddressMode::REPEAT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_sampler_create_info() {
+        let desc = SamplerDesc::default().with_max_anisotropy(16);
+
+        // Clamped to the device limit
+        let create_info = desc.create_info(8.0);
+        assert_eq!(create_info.anisotropy_enable, vk::TRUE);
+        assert_eq!(create_info.max_anisotropy, 8.0);
+
+        // Disabled if the device doesn't support it
+        let create_info = desc.create_info(0.0);
+        assert_eq!(create_info.anisotropy_enable, vk::FALSE);
+        assert_eq!(create_info.max_anisotropy, 1.0);
+
+        let create_info = SamplerDesc::default().create_info(16.0);
+        assert_eq!(create_info.anisotropy_enable, vk::FALSE);
+        assert_eq!(create_info.address_mode_u, vk::SamplerAddressMode::REPEAT);
+    }
+}
@@ -1,7 +1,10 @@
 use glam::{Mat4, Vec4};
 use serde::{Deserialize, Serialize};
 
-use super::light::{Light, MAX_LIGHTS};
+use super::{
+    light::{Light, MAX_LIGHTS},
+    shadow::MAX_LOCAL_SHADOW_LAYERS,
+};
 
 /// The amount of Image Based Lighting (IBL) to show in the scene
 pub const DEFAULT_IBL_INTENSITY: f32 = 1.0;
@@ -14,10 +17,35 @@ pub struct SceneData {
     pub view_projection: [Mat4; 2],
     /// Position of the cameras (one per eye)
     pub camera_position: [Vec4; 2],
-    /// Scene Parameters - x = IBL intensity, y = unused, z = debug render inputs, w = debug render algorithm
+    /// Scene Parameters - x = IBL intensity, y = one when rendering into a render target, which skips tonemapping,
+    /// z = debug render inputs, w = render mode, see [`RenderMode`](super::render_mode::RenderMode)
     pub params: Vec4,
-    /// Dynamic punctual lights
+    /// Directional lights, lights without a range and lights that cast shadows. Every fragment is lit by all of
+    /// them, other lights are assigned to clusters.
     pub lights: [Light; MAX_LIGHTS],
+    /// Transforms points in globally oriented stage space into the shadow map's clip space
+    pub shadow_from_gos: Mat4,
+    /// Shadow parameters - x = shadows enabled, y = shadow strength, zw = unused
+    pub shadow_params: Vec4,
+    /// Transforms points in globally oriented stage space into the clip space of each point/spot light shadow map
+    pub local_shadow_from_gos: [Mat4; MAX_LOCAL_SHADOW_LAYERS],
+    /// Decal parameters - x = number of decals, yzw = unused
+    pub decal_params: Vec4,
+    /// Color grading parameters - x = LUT texture index, y = LUT size (zero when disabled), z = strength, w = unused
+    pub color_grading_params: Vec4,
+    /// Fog parameters - xyz = fog color, w = distance fog density (zero when disabled)
+    pub fog_params: Vec4,
+    /// Height fog parameters - x = density (zero when disabled), y = falloff, z = base height, w = unused
+    pub height_fog_params: Vec4,
+    /// Transforms points in globally oriented stage space into the space of the frustum divided into light clusters
+    pub cluster_from_gos: Mat4,
+    /// Tangents of the light cluster frustum's left, right, down and up angles
+    pub cluster_tangents: Vec4,
+    /// Light cluster parameters - x = one over the depth of the first slice's far edge, y = slices per unit of log
+    /// depth, z = number of clustered lights, w = unused
+    pub cluster_params: Vec4,
+    /// Vignette parameters - x = strength (zero when disabled), y = inner radius, z = outer radius, w = unused
+    pub vignette_params: Vec4,
 }
 
 impl Default for SceneData {
@@ -27,6 +55,17 @@ impl Default for SceneData {
             camera_position: [Vec4::ZERO, Vec4::ZERO],
             params: [DEFAULT_IBL_INTENSITY, 0., 0., 0.].into(),
             lights: [Light::none(), Light::none(), Light::none(), Light::none()],
+            shadow_from_gos: Mat4::IDENTITY,
+            shadow_params: Vec4::ZERO,
+            local_shadow_from_gos: [Mat4::IDENTITY; MAX_LOCAL_SHADOW_LAYERS],
+            decal_params: Vec4::ZERO,
+            color_grading_params: Vec4::ZERO,
+            fog_params: Vec4::ZERO,
+            height_fog_params: Vec4::ZERO,
+            cluster_from_gos: Mat4::IDENTITY,
+            cluster_tangents: Vec4::ZERO,
+            cluster_params: Vec4::ZERO,
+            vignette_params: Vec4::ZERO,
         }
     }
 }
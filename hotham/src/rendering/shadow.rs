@@ -0,0 +1,649 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk::{self, Handle};
+use glam::{Mat4, Vec3, Vec4};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::{render_context::create_shader, VulkanContext},
+    rendering::{
+        descriptors::Descriptors,
+        image::Image,
+        light::{Light, LIGHT_TYPE_POINT, LIGHT_TYPE_SPOT, NO_SHADOW},
+        vertex::Vertex,
+    },
+    DEPTH_FORMAT,
+};
+
+static SHADOW_VERT: &[u32] = include_glsl!("src/shaders/shadow.vert", target: vulkan1_1);
+
+/// The resolution of the (square) shadow map
+pub const SHADOW_MAP_RESOLUTION: u32 = 2048;
+
+/// The resolution of each point or spot light shadow map
+pub const LOCAL_SHADOW_MAP_RESOLUTION: u32 = 512;
+
+/// The number of shadow maps available to point and spot lights. A spotlight uses one, a point light uses six.
+pub const MAX_LOCAL_SHADOW_LAYERS: usize = 12;
+
+/// Near plane used when rendering point and spot light shadows, in meters
+const LOCAL_SHADOW_NEAR: f32 = 0.05;
+
+/// Far plane used for lights that have an infinite range, in meters
+const LOCAL_SHADOW_DEFAULT_RANGE: f32 = 20.0;
+
+/// The shadow map is cleared to the far plane; we use regular (not inverse) Z here.
+static SHADOW_CLEAR_VALUE: vk::ClearValue = vk::ClearValue {
+    depth_stencil: vk::ClearDepthStencilValue {
+        depth: 1.0,
+        stencil: 0,
+    },
+};
+
+/// Settings for the shadows cast by the primary directional light in the scene.
+/// Point and spot lights opt in to shadows individually with [`CastsShadows`](crate::components::CastsShadows).
+///
+/// A single (non-cascaded) orthographic shadow map is rendered each frame, centred on the
+/// player's head. Only directional lights receive shadows from it, so `direction` should
+/// match the direction of the directional light in your scene.
+#[derive(Debug, Clone)]
+pub struct ShadowCaster {
+    /// Whether or not shadows are rendered at all
+    pub enabled: bool,
+    /// The direction the light is travelling in, in globally oriented stage space
+    pub direction: Vec3,
+    /// Half the width of the area around the player that receives shadows, in meters
+    pub half_extent: f32,
+    /// How far (in meters) from the player objects can be and still cast shadows
+    pub depth_range: f32,
+    /// How dark shadowed areas are. 0 = no shadow, 1 = fully shadowed
+    pub strength: f32,
+}
+
+impl Default for ShadowCaster {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            direction: Vec3::new(-0.3, -1.0, -0.3).normalize(),
+            half_extent: 10.0,
+            depth_range: 50.0,
+            strength: 1.0,
+        }
+    }
+}
+
+impl ShadowCaster {
+    /// Create a shadow caster for a directional light travelling in `direction`
+    pub fn new(direction: Vec3) -> Self {
+        Self {
+            enabled: true,
+            direction: direction.normalize(),
+            ..Default::default()
+        }
+    }
+
+    /// Compute the matrix that transforms points in globally oriented stage space into the
+    /// shadow map's clip space, for a shadow map centred on `center`.
+    pub fn shadow_from_gos(&self, center: Vec3) -> Mat4 {
+        let direction = self.direction.normalize();
+
+        // Pick an up vector that is guaranteed not to be parallel to the light direction
+        let up = if direction.y.abs() > 0.99 {
+            Vec3::Z
+        } else {
+            Vec3::Y
+        };
+
+        let eye = center - direction * self.depth_range * 0.5;
+        let view = Mat4::look_at_rh(eye, center, up);
+        let projection = Mat4::orthographic_rh(
+            -self.half_extent,
+            self.half_extent,
+            -self.half_extent,
+            self.half_extent,
+            0.0,
+            self.depth_range,
+        );
+
+        projection * view
+    }
+
+    /// Parameters passed to the shaders in `SceneData`.
+    /// x = enabled, y = strength, zw = unused
+    pub(crate) fn params(&self) -> Vec4 {
+        let enabled = if self.enabled { 1. } else { 0. };
+        [enabled, self.strength, 0., 0.].into()
+    }
+}
+
+/// Compute the matrices used to render and sample the shadow maps of a point or spot light, in globally
+/// oriented stage space. Spotlights use a single perspective shadow map facing down the cone, point lights use
+/// six shadow maps, one for each axis, in the order +X, -X, +Y, -Y, +Z, -Z.
+///
+/// Returns an empty list for lights that can't have local shadows (eg. directional lights).
+pub fn local_shadow_matrices(light: &Light) -> Vec<Mat4> {
+    let far = if light.falloff > 0. {
+        1. / light.falloff.sqrt()
+    } else {
+        LOCAL_SHADOW_DEFAULT_RANGE
+    };
+    let position = light.position;
+
+    match light.light_type {
+        LIGHT_TYPE_SPOT => {
+            // Recover the outer cone angle from the pre-computed spotlight values.
+            let cos_outer = (-light.light_angle_offset / light.light_angle_scale).clamp(0., 1.);
+            let fov = (2. * cos_outer.acos()).clamp(0.01, std::f32::consts::PI - 0.01);
+            let direction = light.direction.normalize();
+            let up = if direction.y.abs() > 0.99 {
+                Vec3::Z
+            } else {
+                Vec3::Y
+            };
+
+            vec![
+                Mat4::perspective_rh(fov, 1., LOCAL_SHADOW_NEAR, far)
+                    * Mat4::look_at_rh(position, position + direction, up),
+            ]
+        }
+        LIGHT_TYPE_POINT => {
+            let projection =
+                Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1., LOCAL_SHADOW_NEAR, far);
+            [
+                (Vec3::X, Vec3::Y),
+                (Vec3::NEG_X, Vec3::Y),
+                (Vec3::Y, Vec3::Z),
+                (Vec3::NEG_Y, Vec3::Z),
+                (Vec3::Z, Vec3::Y),
+                (Vec3::NEG_Z, Vec3::Y),
+            ]
+            .iter()
+            .map(|(forward, up)| projection * Mat4::look_at_rh(position, position + *forward, *up))
+            .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Hand out local shadow map layers to each light whose entry in `casts_shadows` is set, writing their matrices
+/// into `shadow_from_gos`. Lights that don't fit into the remaining layers are rendered without shadows.
+///
+/// Returns the number of layers in use.
+pub(crate) fn assign_local_shadow_maps(
+    lights: &mut [Light],
+    casts_shadows: &[bool],
+    shadow_from_gos: &mut [Mat4; MAX_LOCAL_SHADOW_LAYERS],
+) -> usize {
+    let mut layers_in_use = 0;
+
+    for (light, casts_shadows) in lights.iter_mut().zip(casts_shadows) {
+        light.shadow_index = NO_SHADOW;
+        if !casts_shadows {
+            continue;
+        }
+
+        let matrices = local_shadow_matrices(light);
+        if matrices.is_empty() || layers_in_use + matrices.len() > MAX_LOCAL_SHADOW_LAYERS {
+            continue;
+        }
+
+        light.shadow_index = layers_in_use as _;
+        for matrix in matrices {
+            shadow_from_gos[layers_in_use] = matrix;
+            layers_in_use += 1;
+        }
+    }
+
+    layers_in_use
+}
+
+/// All the GPU resources required to render the shadow maps.
+pub struct ShadowMap {
+    /// The depth image written by the directional shadow pass and sampled by the PBR shader
+    pub image: Image,
+    /// An array of depth images used for point and spot light shadows
+    pub local_image: Image,
+    /// A comparison sampler used to read the shadow maps
+    pub sampler: vk::Sampler,
+    /// Depth only render pass
+    pub render_pass: vk::RenderPass,
+    /// Framebuffer wrapping `image`
+    pub framebuffer: vk::Framebuffer,
+    /// One framebuffer per layer of `local_image`
+    pub local_framebuffers: Vec<vk::Framebuffer>,
+    /// Depth only pipeline layout. The shadow matrix is passed as a push constant.
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Depth only pipeline
+    pub pipeline: vk::Pipeline,
+    /// The number of layers of `local_image` that will be rendered this frame
+    pub local_layers_in_use: usize,
+}
+
+impl ShadowMap {
+    pub(crate) fn new(vulkan_context: &VulkanContext, descriptors: &Descriptors) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let extent = vk::Extent2D {
+            width: SHADOW_MAP_RESOLUTION,
+            height: SHADOW_MAP_RESOLUTION,
+        };
+
+        let image = vulkan_context.create_image(
+            DEPTH_FORMAT,
+            &extent,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+            1,
+            1,
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::IMAGE,
+            image.handle.as_raw(),
+            "Shadow Map",
+        )?;
+
+        let local_extent = vk::Extent2D {
+            width: LOCAL_SHADOW_MAP_RESOLUTION,
+            height: LOCAL_SHADOW_MAP_RESOLUTION,
+        };
+        let local_image = vulkan_context.create_image(
+            DEPTH_FORMAT,
+            &local_extent,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+            MAX_LOCAL_SHADOW_LAYERS as _,
+            1,
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::IMAGE,
+            local_image.handle.as_raw(),
+            "Local Shadow Maps",
+        )?;
+
+        let sampler = create_shadow_sampler(vulkan_context)?;
+        let render_pass = create_shadow_render_pass(vulkan_context)?;
+
+        let framebuffer = create_framebuffer(device, render_pass, image.view, extent)?;
+        let local_framebuffers = (0..MAX_LOCAL_SHADOW_LAYERS as u32)
+            .map(|layer| {
+                let view = create_layer_view(device, &local_image, layer)?;
+                create_framebuffer(device, render_pass, view, local_extent)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let pipeline_layout = create_shadow_pipeline_layout(vulkan_context, descriptors)?;
+        let pipeline = create_shadow_pipeline(vulkan_context, pipeline_layout, render_pass)?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::RENDER_PASS,
+            render_pass.as_raw(),
+            "Shadow Render Pass",
+        )?;
+        vulkan_context.set_debug_name(
+            vk::ObjectType::PIPELINE,
+            pipeline.as_raw(),
+            "Shadow Pipeline",
+        )?;
+
+        let shadow_map = Self {
+            image,
+            local_image,
+            sampler,
+            render_pass,
+            framebuffer,
+            local_framebuffers,
+            pipeline_layout,
+            pipeline,
+            local_layers_in_use: 0,
+        };
+
+        // Clear the shadow maps once so that they are in the correct layout even if shadows are never rendered.
+        let command_buffer = vulkan_context.begin_single_time_commands();
+        unsafe {
+            shadow_map.begin_render_pass(device, command_buffer, shadow_map.framebuffer, extent);
+            device.cmd_end_render_pass(command_buffer);
+            for framebuffer in &shadow_map.local_framebuffers {
+                shadow_map.begin_render_pass(device, command_buffer, *framebuffer, local_extent);
+                device.cmd_end_render_pass(command_buffer);
+            }
+        }
+        vulkan_context.end_single_time_commands(command_buffer);
+
+        unsafe {
+            descriptors.write_shadow_map_descriptors(
+                vulkan_context,
+                shadow_map.image.view,
+                shadow_map.local_image.view,
+                shadow_map.sampler,
+            );
+        }
+
+        Ok(shadow_map)
+    }
+
+    /// Begin a shadow render pass into `framebuffer`, clearing it and setting the viewport.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state and not inside another render pass.
+    pub(crate) unsafe fn begin_render_pass(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        framebuffer: vk::Framebuffer,
+        extent: vk::Extent2D,
+    ) {
+        let render_area = vk::Rect2D {
+            extent,
+            ..Default::default()
+        };
+        let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(framebuffer)
+            .render_area(render_area)
+            .clear_values(slice_from_ref(&SHADOW_CLEAR_VALUE));
+
+        device.cmd_begin_render_pass(
+            command_buffer,
+            &render_pass_begin_info,
+            vk::SubpassContents::INLINE,
+        );
+
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: extent.width as _,
+            height: extent.height as _,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        device.cmd_set_viewport(command_buffer, 0, slice_from_ref(&viewport));
+        device.cmd_set_scissor(command_buffer, 0, slice_from_ref(&render_area));
+    }
+}
+
+fn create_framebuffer(
+    device: &ash::Device,
+    render_pass: vk::RenderPass,
+    view: vk::ImageView,
+    extent: vk::Extent2D,
+) -> Result<vk::Framebuffer> {
+    unsafe {
+        device.create_framebuffer(
+            &vk::FramebufferCreateInfo::builder()
+                .render_pass(render_pass)
+                .attachments(slice_from_ref(&view))
+                .width(extent.width)
+                .height(extent.height)
+                .layers(1),
+            None,
+        )
+    }
+    .map_err(Into::into)
+}
+
+fn create_layer_view(device: &ash::Device, image: &Image, layer: u32) -> Result<vk::ImageView> {
+    let create_info = vk::ImageViewCreateInfo::builder()
+        .view_type(vk::ImageViewType::TYPE_2D)
+        .format(image.format)
+        .subresource_range(vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::DEPTH,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: layer,
+            layer_count: 1,
+        })
+        .image(image.handle);
+
+    unsafe { device.create_image_view(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_shadow_pipeline_layout(
+    vulkan_context: &VulkanContext,
+    descriptors: &Descriptors,
+) -> Result<vk::PipelineLayout> {
+    let push_constant_range = vk::PushConstantRange::builder()
+        .offset(0)
+        .size(std::mem::size_of::<Mat4>() as _)
+        .stage_flags(vk::ShaderStageFlags::VERTEX);
+
+    let create_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(slice_from_ref(&descriptors.graphics_layout))
+        .push_constant_ranges(slice_from_ref(&push_constant_range));
+
+    unsafe {
+        vulkan_context
+            .device
+            .create_pipeline_layout(&create_info, None)
+    }
+    .map_err(Into::into)
+}
+
+fn create_shadow_sampler(vulkan_context: &VulkanContext) -> Result<vk::Sampler> {
+    // Anything outside the shadow map is considered to be lit.
+    let create_info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::LINEAR)
+        .min_filter(vk::Filter::LINEAR)
+        .address_mode_u(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .address_mode_v(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .address_mode_w(vk::SamplerAddressMode::CLAMP_TO_BORDER)
+        .border_color(vk::BorderColor::FLOAT_OPAQUE_WHITE)
+        .anisotropy_enable(false)
+        .max_anisotropy(1.0)
+        .unnormalized_coordinates(false)
+        .compare_enable(true)
+        .compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .mipmap_mode(vk::SamplerMipmapMode::NEAREST)
+        .min_lod(0.0)
+        .max_lod(0.0);
+
+    unsafe { vulkan_context.device.create_sampler(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_shadow_render_pass(vulkan_context: &VulkanContext) -> Result<vk::RenderPass> {
+    let depth_attachment = vk::AttachmentDescription::builder()
+        .format(DEPTH_FORMAT)
+        .samples(vk::SampleCountFlags::TYPE_1)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL);
+
+    let depth_attachment_reference = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .depth_stencil_attachment(&depth_attachment_reference);
+
+    // Make sure the previous frame has finished reading the shadow map before we write to it,
+    // and that our writes are finished before the PBR fragment shader reads from it.
+    let dependencies = [
+        vk::SubpassDependency::builder()
+            .src_subpass(vk::SUBPASS_EXTERNAL)
+            .dst_subpass(0)
+            .src_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+            .src_access_mask(vk::AccessFlags::SHADER_READ)
+            .dst_stage_mask(vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+            .dst_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .build(),
+        vk::SubpassDependency::builder()
+            .src_subpass(0)
+            .dst_subpass(vk::SUBPASS_EXTERNAL)
+            .src_stage_mask(vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+            .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags::FRAGMENT_SHADER)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ)
+            .build(),
+    ];
+
+    let create_info = vk::RenderPassCreateInfo::builder()
+        .attachments(slice_from_ref(&depth_attachment))
+        .subpasses(slice_from_ref(&subpass))
+        .dependencies(&dependencies);
+
+    unsafe { vulkan_context.device.create_render_pass(&create_info, None) }.map_err(Into::into)
+}
+
+fn create_shadow_pipeline(
+    vulkan_context: &VulkanContext,
+    pipeline_layout: vk::PipelineLayout,
+    render_pass: vk::RenderPass,
+) -> Result<vk::Pipeline> {
+    // Depth only, so we only need a vertex shader.
+    let (vertex_shader, vertex_stage) =
+        create_shader(SHADOW_VERT, vk::ShaderStageFlags::VERTEX, vulkan_context)?;
+
+    // Vertex input state - identical to the PBR pipeline so that we can share buffers.
+    let position_binding_description = vk::VertexInputBindingDescription::builder()
+        .binding(0)
+        .stride(std::mem::size_of::<Vec3>() as _)
+        .input_rate(vk::VertexInputRate::VERTEX)
+        .build();
+    let vertex_binding_description = vk::VertexInputBindingDescription::builder()
+        .binding(1)
+        .stride(std::mem::size_of::<Vertex>() as _)
+        .input_rate(vk::VertexInputRate::VERTEX)
+        .build();
+    let vertex_binding_descriptions = [position_binding_description, vertex_binding_description];
+    let vertex_attribute_descriptions = Vertex::attribute_descriptions();
+
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_attribute_descriptions(&vertex_attribute_descriptions)
+        .vertex_binding_descriptions(&vertex_binding_descriptions);
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+    // Viewport and scissor are set when the render pass begins, as the shadow maps differ in size.
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1);
+    let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state =
+        vk::PipelineDynamicStateCreateInfo::builder().dynamic_states(&dynamic_states);
+
+    // Slope scaled depth bias to avoid shadow acne.
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .polygon_mode(vk::PolygonMode::FILL)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .rasterizer_discard_enable(false)
+        .depth_clamp_enable(false)
+        .depth_bias_enable(true)
+        .depth_bias_constant_factor(1.25)
+        .depth_bias_clamp(0.0)
+        .depth_bias_slope_factor(1.75)
+        .line_width(1.0);
+
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .rasterization_samples(vk::SampleCountFlags::TYPE_1);
+
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::LESS_OR_EQUAL)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false);
+
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder().logic_op_enable(false);
+
+    let create_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(slice_from_ref(&vertex_stage))
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    let pipelines = unsafe {
+        vulkan_context.device.create_graphics_pipelines(
+            vk::PipelineCache::null(),
+            slice_from_ref(&create_info),
+            None,
+        )
+    }
+    .map_err(|(_, r)| r)?;
+
+    unsafe {
+        vulkan_context
+            .device
+            .destroy_shader_module(vertex_shader, None);
+    }
+
+    Ok(pipelines[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    pub fn test_shadow_from_gos() {
+        let shadow_caster = ShadowCaster::new(Vec3::NEG_Y);
+        let center = Vec3::new(1., 2., 3.);
+        let shadow_from_gos = shadow_caster.shadow_from_gos(center);
+
+        // The center of the shadow map should project to the middle of clip space.
+        let p = shadow_from_gos.project_point3(center);
+        assert_relative_eq!(p.x, 0.);
+        assert_relative_eq!(p.y, 0.);
+        assert_relative_eq!(p.z, 0.5);
+
+        // Points closer to the light should have a smaller depth.
+        let above = shadow_from_gos.project_point3(center + Vec3::Y);
+        assert!(above.z < p.z);
+
+        // Points at the edge of the shadow area should be at the edge of clip space.
+        let edge = shadow_from_gos.project_point3(center + Vec3::X * shadow_caster.half_extent);
+        assert_relative_eq!(edge.x.abs().max(edge.y.abs()), 1., epsilon = 0.0001);
+    }
+
+    #[test]
+    pub fn test_assign_local_shadow_maps() {
+        let point = Light::new_point(Vec3::ZERO, 10., 1., Vec3::ONE);
+        let spot = Light::new_spotlight(
+            Vec3::NEG_Y,
+            10.,
+            1.,
+            Vec3::ONE,
+            Vec3::Y,
+            0.,
+            std::f32::consts::FRAC_PI_4,
+        );
+        let unshadowed = Light::new_point(Vec3::ZERO, 10., 1., Vec3::ONE);
+
+        let mut lights = [point.clone(), unshadowed, spot, point];
+        let casts_shadows = [true, false, true, true];
+        let mut shadow_from_gos = [Mat4::IDENTITY; MAX_LOCAL_SHADOW_LAYERS];
+        let layers_in_use =
+            assign_local_shadow_maps(&mut lights, &casts_shadows, &mut shadow_from_gos);
+
+        // The second point light doesn't fit, so it gets no shadows.
+        assert_eq!(layers_in_use, 7);
+        assert_eq!(lights[0].shadow_index, 0);
+        assert_eq!(lights[1].shadow_index, NO_SHADOW);
+        assert_eq!(lights[2].shadow_index, 6);
+        assert_eq!(lights[3].shadow_index, NO_SHADOW);
+
+        // A point directly below the spotlight should land in the middle of its shadow map.
+        let p = shadow_from_gos[6].project_point3(Vec3::ZERO);
+        assert_relative_eq!(p.x, 0., epsilon = 0.0001);
+        assert_relative_eq!(p.y, 0., epsilon = 0.0001);
+
+        // A point along +X from the point light should land in the middle of the first face.
+        let p = shadow_from_gos[0].project_point3(Vec3::X);
+        assert_relative_eq!(p.x, 0., epsilon = 0.0001);
+        assert_relative_eq!(p.y, 0., epsilon = 0.0001);
+        assert!(p.z > 0. && p.z < 1.);
+    }
+}
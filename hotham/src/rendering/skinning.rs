@@ -0,0 +1,255 @@
+use anyhow::Result;
+use ash::vk;
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    contexts::VulkanContext,
+    rendering::{
+        buffer::Buffer,
+        compute_pass::{ComputeBinding, ComputePass},
+        frame::Frame,
+        primitive::Primitive,
+        resources::{Resources, VERTEX_BUFFER_SIZE},
+    },
+};
+
+static SKINNING_COMP: &[u32] = include_glsl!("src/shaders/skinning.comp", target: vulkan1_1);
+
+/// The number of skinned vertices that can be drawn each frame. Skinned primitives that don't fit are skinned in the
+/// vertex shader instead.
+pub const SKINNED_VERTEX_BUFFER_SIZE: usize = 100_000;
+
+/// The number of vertices skinned by each workgroup. Must match `local_size_x` in skinning.comp
+const SKINNING_WORKGROUP_SIZE: u32 = 64;
+
+/// The number of skinned primitives that can be drawn each frame
+const MAX_SKINNING_JOBS: usize = 1000;
+
+/// A request to skin the vertices of a single primitive. Must match `SkinningJob` in skinning.comp
+#[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SkinningJob {
+    /// Offset of the primitive's first vertex in the vertex buffers
+    pub source_vertex_offset: u32,
+    /// Where the skinned vertices are written to
+    pub target_vertex_offset: u32,
+    /// The number of vertices in the primitive
+    pub vertex_count: u32,
+    /// The skin to apply
+    pub skin_id: u32,
+    /// Morph target weights to apply before skinning, if any
+    pub morph_weights_id: u32,
+    /// Offset of the primitive's deltas in the morph targets buffer
+    pub morph_target_offset: u32,
+    /// The number of morph targets the primitive has
+    pub morph_target_count: u32,
+    _padding: u32,
+}
+
+/// A compute pre-pass that skins every skinned primitive drawn this frame, once.
+///
+/// The skinned vertices are written to a region at the end of the shared vertex buffers that is reserved for each
+/// frame in flight. Each skinned primitive is then drawn as a copy of the original whose `vertex_buffer_offset`
+/// points at its skinned vertices, so every pass that draws it - the PBR pass, shadow maps, motion vectors and render
+/// targets - can treat it as if it had no skin, and none of them have to skin it again.
+pub struct Skinning {
+    /// One pass per frame in flight, each bound to that frame's jobs and morph weights
+    pub passes: Vec<ComputePass>,
+    /// The jobs of each frame in flight
+    pub job_buffers: Vec<Buffer<SkinningJob>>,
+    /// The jobs queued this frame
+    jobs: Vec<SkinningJob>,
+    /// The first vertex of this frame's region of the vertex buffers
+    first_vertex: u32,
+    /// The number of vertices of this frame's region that have been handed out
+    vertices_in_use: u32,
+}
+
+impl Skinning {
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        resources: &Resources,
+        frames: &[Frame],
+    ) -> Result<Self> {
+        let job_buffers = frames
+            .iter()
+            .map(|_| unsafe {
+                Buffer::new(
+                    vulkan_context,
+                    vk::BufferUsageFlags::STORAGE_BUFFER,
+                    MAX_SKINNING_JOBS,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let passes = frames
+            .iter()
+            .zip(&job_buffers)
+            .map(|(frame, job_buffer)| {
+                ComputePass::new(
+                    vulkan_context,
+                    SKINNING_COMP,
+                    vec![
+                        ComputeBinding::StorageBuffer(job_buffer.buffer),
+                        ComputeBinding::StorageBuffer(resources.skins_buffer.buffer),
+                        ComputeBinding::StorageBuffer(resources.position_buffer.buffer),
+                        ComputeBinding::StorageBuffer(resources.vertex_buffer.buffer),
+                        ComputeBinding::StorageBuffer(resources.morph_targets_buffer.buffer),
+                        ComputeBinding::StorageBuffer(frame.morph_weights_buffer.buffer),
+                    ],
+                    0,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            passes,
+            job_buffers,
+            jobs: Vec::new(),
+            first_vertex: VERTEX_BUFFER_SIZE as _,
+            vertices_in_use: 0,
+        })
+    }
+
+    /// Forget the jobs of the last frame, and start handing out vertices from the region of `frame_index`.
+    pub(crate) fn begin_frame(&mut self, frame_index: usize) {
+        self.jobs.clear();
+        self.first_vertex = (VERTEX_BUFFER_SIZE + frame_index * SKINNED_VERTEX_BUFFER_SIZE) as _;
+        self.vertices_in_use = 0;
+    }
+
+    /// Queue `primitive` to be skinned with `skin_id` this frame. Returns the primitive to draw in its place, or
+    /// `None` if there's no room left this frame, in which case it must be skinned in the vertex shader.
+    ///
+    /// The skinned copy shares the index buffer of the original, but each copy has its own vertices, so its
+    /// `vertex_buffer_offset` is unique this frame and can be used as its primitive ID.
+    pub(crate) fn queue(
+        &mut self,
+        primitive: &Primitive,
+        skin_id: u32,
+        morph_weights_id: u32,
+    ) -> Option<Primitive> {
+        let vertex_count = primitive.vertex_count;
+        if vertex_count == 0
+            || self.jobs.len() == MAX_SKINNING_JOBS
+            || (self.vertices_in_use + vertex_count) as usize > SKINNED_VERTEX_BUFFER_SIZE
+        {
+            return None;
+        }
+
+        let target_vertex_offset = self.first_vertex + self.vertices_in_use;
+        self.vertices_in_use += vertex_count;
+        self.jobs.push(SkinningJob {
+            source_vertex_offset: primitive.vertex_buffer_offset,
+            target_vertex_offset,
+            vertex_count,
+            skin_id,
+            morph_weights_id,
+            morph_target_offset: primitive.morph_target_offset,
+            morph_target_count: primitive.morph_target_count,
+            _padding: 0,
+        });
+
+        // Morph targets have already been applied too.
+        Some(Primitive {
+            vertex_buffer_offset: target_vertex_offset,
+            morph_target_count: 0,
+            ..primitive.clone()
+        })
+    }
+
+    /// The jobs queued this frame
+    pub fn jobs(&self) -> &[SkinningJob] {
+        &self.jobs
+    }
+
+    /// Skin every primitive queued this frame. Barriers are inserted so that the skinned vertices can be read by any
+    /// draw recorded after it.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be in the recording state and not inside a render pass.
+    pub(crate) unsafe fn dispatch(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+    ) {
+        if self.jobs.is_empty() {
+            return;
+        }
+
+        self.job_buffers[frame_index].overwrite(&self.jobs);
+        let max_vertex_count = self
+            .jobs
+            .iter()
+            .map(|job| job.vertex_count)
+            .max()
+            .unwrap_or_default();
+        let group_count = [
+            (max_vertex_count + SKINNING_WORKGROUP_SIZE - 1) / SKINNING_WORKGROUP_SIZE,
+            self.jobs.len() as u32,
+            1,
+        ];
+        self.passes[frame_index].record(device, command_buffer, group_count, &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_skinning_job_layout() {
+        // Must match the std430 layout of `SkinningJob` in skinning.comp
+        assert_eq!(std::mem::size_of::<SkinningJob>(), 32);
+    }
+
+    #[test]
+    pub fn test_queue() {
+        let mut skinning = Skinning {
+            passes: Vec::new(),
+            job_buffers: Vec::new(),
+            jobs: Vec::new(),
+            first_vertex: 0,
+            vertices_in_use: 0,
+        };
+        skinning.begin_frame(1);
+        let first_vertex = (VERTEX_BUFFER_SIZE + SKINNED_VERTEX_BUFFER_SIZE) as u32;
+
+        let primitive = Primitive {
+            index_buffer_offset: 30,
+            vertex_buffer_offset: 10,
+            vertex_count: 100,
+            morph_target_count: 2,
+            ..Default::default()
+        };
+
+        // Each copy gets its own vertices, sharing the original's indices.
+        let first = skinning.queue(&primitive, 3, 4).unwrap();
+        let second = skinning.queue(&primitive, 5, 6).unwrap();
+        assert_eq!(first.vertex_buffer_offset, first_vertex);
+        assert_eq!(second.vertex_buffer_offset, first_vertex + 100);
+        assert_eq!(first.index_buffer_offset, 30);
+        assert_eq!(first.morph_target_count, 0);
+        assert_eq!(skinning.jobs().len(), 2);
+        assert_eq!(skinning.jobs()[1].source_vertex_offset, 10);
+        assert_eq!(skinning.jobs()[1].target_vertex_offset, first_vertex + 100);
+        assert_eq!(skinning.jobs()[1].skin_id, 5);
+        assert_eq!(skinning.jobs()[1].morph_target_count, 2);
+
+        // Primitives that don't fit are left to the vertex shader.
+        let huge = Primitive {
+            vertex_count: SKINNED_VERTEX_BUFFER_SIZE as _,
+            ..primitive.clone()
+        };
+        assert!(skinning.queue(&huge, 3, 4).is_none());
+        assert_eq!(skinning.jobs().len(), 2);
+
+        // The next frame starts again, in its own region.
+        skinning.begin_frame(0);
+        assert!(skinning.jobs().is_empty());
+        let first = skinning.queue(&primitive, 3, 4).unwrap();
+        assert_eq!(first.vertex_buffer_offset, VERTEX_BUFFER_SIZE as u32);
+    }
+}
@@ -0,0 +1,235 @@
+use std::slice::from_ref as slice_from_ref;
+
+use anyhow::Result;
+use ash::vk;
+use glam::Vec4;
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    components::Skybox,
+    contexts::{
+        render_context::{create_push_constant, create_shader},
+        VulkanContext,
+    },
+    rendering::{descriptors::Descriptors, texture::NO_TEXTURE},
+};
+
+static SKYBOX_VERT: &[u32] = include_glsl!("src/shaders/skybox.vert", target: vulkan1_1);
+static SKYBOX_FRAG: &[u32] = include_glsl!("src/shaders/skybox.frag", target: vulkan1_1);
+
+/// The representation of a [`Skybox`] passed to the skybox shader as push constants.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct SkyboxParams {
+    zenith: Vec4,
+    horizon: Vec4,
+    ground: Vec4,
+    texture_id: u32,
+    intensity: f32,
+}
+
+impl From<&Skybox> for SkyboxParams {
+    fn from(skybox: &Skybox) -> Self {
+        match *skybox {
+            Skybox::Cubemap {
+                texture_id,
+                intensity,
+            } => SkyboxParams {
+                zenith: Vec4::ZERO,
+                horizon: Vec4::ZERO,
+                ground: Vec4::ZERO,
+                texture_id,
+                intensity,
+            },
+            Skybox::Gradient {
+                zenith,
+                horizon,
+                ground,
+            } => SkyboxParams {
+                zenith: zenith.extend(1.),
+                horizon: horizon.extend(1.),
+                ground: ground.extend(1.),
+                texture_id: NO_TEXTURE,
+                intensity: 1.,
+            },
+        }
+    }
+}
+
+/// The pipeline used to draw a [`Skybox`] behind everything else in the PBR render pass.
+pub struct SkyboxPipeline {
+    /// The scene data descriptor set plus the skybox parameters as push constants
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Draws a single fullscreen triangle on the far plane
+    pub pipeline: vk::Pipeline,
+}
+
+impl SkyboxPipeline {
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        descriptors: &Descriptors,
+        render_pass: vk::RenderPass,
+        render_area: &vk::Rect2D,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let push_constant_range = vk::PushConstantRange::builder()
+            .offset(0)
+            .size(std::mem::size_of::<SkyboxParams>() as _)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptors.graphics_layout))
+                    .push_constant_ranges(slice_from_ref(&push_constant_range)),
+                None,
+            )
+        }?;
+
+        let (vertex_shader, vertex_stage) =
+            create_shader(SKYBOX_VERT, vk::ShaderStageFlags::VERTEX, vulkan_context)?;
+        let (fragment_shader, fragment_stage) =
+            create_shader(SKYBOX_FRAG, vk::ShaderStageFlags::FRAGMENT, vulkan_context)?;
+        let stages = [vertex_stage, fragment_stage];
+
+        // The fullscreen triangle is generated in the vertex shader.
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder();
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: render_area.extent.width as _,
+            height: render_area.extent.height as _,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+            .viewports(slice_from_ref(&viewport))
+            .scissors(slice_from_ref(render_area));
+
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+            .line_width(1.0);
+
+        let multisample_state =
+            vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(msaa_samples);
+
+        // The skybox is drawn after the world, on the far plane, so it only covers pixels nothing else was
+        // drawn to. Remember that we're using inverse Z, so the depth buffer is cleared to 0.
+        let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(true)
+            .depth_write_enable(false)
+            .depth_compare_op(vk::CompareOp::GREATER_OR_EQUAL)
+            .depth_bounds_test_enable(false)
+            .min_depth_bounds(0.0)
+            .max_depth_bounds(1.0)
+            .stencil_test_enable(false);
+
+        let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(
+                vk::ColorComponentFlags::R
+                    | vk::ColorComponentFlags::G
+                    | vk::ColorComponentFlags::B
+                    | vk::ColorComponentFlags::A,
+            )
+            .blend_enable(false)
+            .build();
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op_enable(false)
+            .attachments(slice_from_ref(&color_blend_attachment));
+
+        let create_info = vk::GraphicsPipelineCreateInfo::builder()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .depth_stencil_state(&depth_stencil_state)
+            .color_blend_state(&color_blend_state)
+            .layout(pipeline_layout)
+            .render_pass(render_pass)
+            .subpass(0);
+
+        let pipelines = unsafe {
+            device.create_graphics_pipelines(
+                vk::PipelineCache::null(),
+                slice_from_ref(&create_info),
+                None,
+            )
+        }
+        .map_err(|(_, r)| r)?;
+
+        unsafe {
+            device.destroy_shader_module(vertex_shader, None);
+            device.destroy_shader_module(fragment_shader, None);
+        }
+
+        Ok(Self {
+            pipeline_layout,
+            pipeline: pipelines[0],
+        })
+    }
+
+    /// Draw `skybox` behind everything that has been drawn so far.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be inside the PBR render pass.
+    pub(crate) unsafe fn draw(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        descriptor_set: vk::DescriptorSet,
+        skybox: &Skybox,
+    ) {
+        let params = SkyboxParams::from(skybox);
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            slice_from_ref(&descriptor_set),
+            &[],
+        );
+        device.cmd_push_constants(
+            command_buffer,
+            self.pipeline_layout,
+            vk::ShaderStageFlags::FRAGMENT,
+            0,
+            create_push_constant(&params),
+        );
+        device.cmd_draw(command_buffer, 3, 1, 0, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_skybox_params() {
+        let params = SkyboxParams::from(&Skybox::environment_map());
+        assert_eq!(params.texture_id, 1);
+        assert_eq!(params.intensity, 1.);
+
+        let params = SkyboxParams::from(&Skybox::Gradient {
+            zenith: [0., 0., 1.].into(),
+            horizon: [1., 1., 1.].into(),
+            ground: [0., 1., 0.].into(),
+        });
+        assert_eq!(params.texture_id, NO_TEXTURE);
+        assert_eq!(params.zenith, [0., 0., 1., 1.].into());
+        assert_eq!(params.ground, [0., 1., 0., 1.].into());
+    }
+}
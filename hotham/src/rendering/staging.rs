@@ -0,0 +1,334 @@
+use std::{collections::VecDeque, slice::from_ref as slice_from_ref};
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::contexts::{vulkan_context::get_buffer_image_copy_regions, VulkanContext};
+
+use super::{buffer::Buffer, image::Image};
+
+/// The size of the staging ring. Uploads larger than this fall back to a temporary staging buffer.
+pub const STAGING_RING_SIZE: usize = 64 * 1024 * 1024;
+
+// Buffer offsets used in image copies must be a multiple of the texel block size, which is at most 16 bytes.
+const STAGING_ALIGNMENT: u64 = 16;
+
+/// Streams texture data to the GPU without stalling the CPU.
+///
+/// Data is copied into a persistently mapped ring buffer and the copy into the image is submitted to the transfer
+/// queue, signalling a timeline semaphore when it completes. Nothing waits for the upload until the renderer
+/// submits its next batch of work, which waits on [`StagingRing::submitted_value`]. The CPU only blocks if the
+/// ring is full of uploads that are still in flight.
+pub struct StagingRing {
+    buffer: Buffer<u8>,
+    allocator: RingAllocator,
+    command_pool: vk::CommandPool,
+    /// Signalled by every upload, with a value one higher than the upload before it
+    pub timeline_semaphore: vk::Semaphore,
+    timeline_value: u64,
+    in_flight: VecDeque<InFlightUpload>,
+}
+
+struct InFlightUpload {
+    timeline_value: u64,
+    allocation_size: u64,
+    command_buffer: vk::CommandBuffer,
+}
+
+impl StagingRing {
+    /// Create the staging ring
+    pub fn new(vulkan_context: &VulkanContext) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let buffer = unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::TRANSFER_SRC,
+                STAGING_RING_SIZE,
+            )
+        };
+
+        let command_pool = unsafe {
+            device.create_command_pool(
+                &vk::CommandPoolCreateInfo::builder()
+                    .queue_family_index(vulkan_context.queue_family_index)
+                    .flags(vk::CommandPoolCreateFlags::TRANSIENT),
+                None,
+            )
+        }?;
+
+        let mut timeline_create_info = vk::SemaphoreTypeCreateInfo::builder()
+            .semaphore_type(vk::SemaphoreType::TIMELINE)
+            .initial_value(0);
+        let timeline_semaphore = unsafe {
+            device.create_semaphore(
+                &vk::SemaphoreCreateInfo::builder().push_next(&mut timeline_create_info),
+                None,
+            )
+        }?;
+
+        Ok(Self {
+            buffer,
+            allocator: RingAllocator::new(STAGING_RING_SIZE as _),
+            command_pool,
+            timeline_semaphore,
+            timeline_value: 0,
+            in_flight: VecDeque::new(),
+        })
+    }
+
+    /// The value the timeline semaphore will reach once every upload submitted so far has completed.
+    /// Work that reads uploaded images must wait for this value.
+    pub fn submitted_value(&self) -> u64 {
+        self.timeline_value
+    }
+
+    /// Copy `image_buf` into `image` on the transfer queue, leaving it in `SHADER_READ_ONLY_OPTIMAL`.
+    /// `offsets` contains the size of each mip level, as in [`VulkanContext::upload_image`].
+    pub fn upload_image(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        image_buf: &[u8],
+        mip_count: u32,
+        offsets: Vec<vk::DeviceSize>,
+        image: &Image,
+    ) -> Result<()> {
+        let len = image_buf.len() as u64;
+        if len > self.allocator.size {
+            println!("[HOTHAM_STAGING] Image of {len} bytes is larger than the staging ring, uploading synchronously..");
+            self.wait_idle(vulkan_context)?;
+            vulkan_context.upload_image(image_buf, mip_count, offsets, image);
+            return Ok(());
+        }
+
+        // Wait for the oldest uploads to complete until there's enough space.
+        let (buffer_offset, allocation_size) = loop {
+            self.retire(vulkan_context)?;
+            if let Some(allocation) = self.allocator.allocate(len, STAGING_ALIGNMENT) {
+                break allocation;
+            }
+            let oldest = self.in_flight.front().unwrap().timeline_value;
+            self.wait_for_value(vulkan_context, oldest)?;
+        };
+
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                image_buf.as_ptr(),
+                self.buffer
+                    .memory_address
+                    .as_ptr()
+                    .add(buffer_offset as usize),
+                image_buf.len(),
+            );
+        }
+
+        let device = &vulkan_context.device;
+        let command_buffer = unsafe {
+            device.allocate_command_buffers(
+                &vk::CommandBufferAllocateInfo::builder()
+                    .command_buffer_count(1)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_pool(self.command_pool),
+            )
+        }?[0];
+
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: mip_count,
+            base_array_layer: 0,
+            layer_count: image.layer_count,
+        };
+        let to_transfer_dst = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(subresource_range)
+            .image(image.handle);
+        // The renderer waits on the timeline semaphore before reading the image, so there's nothing to make
+        // the layout transition visible to here.
+        let to_shader_read = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::TRANSFER_DST_OPTIMAL)
+            .new_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(subresource_range)
+            .image(image.handle);
+        let regions =
+            get_buffer_image_copy_regions(image, image.layer_count, &offsets, buffer_offset);
+
+        unsafe {
+            device.begin_command_buffer(
+                command_buffer,
+                &vk::CommandBufferBeginInfo::builder()
+                    .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT),
+            )?;
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&to_transfer_dst),
+            );
+            device.cmd_copy_buffer_to_image(
+                command_buffer,
+                self.buffer.buffer,
+                image.handle,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions,
+            );
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[],
+                slice_from_ref(&to_shader_read),
+            );
+            device.end_command_buffer(command_buffer)?;
+        }
+
+        let signal_value = self.timeline_value + 1;
+        let mut timeline_info = vk::TimelineSemaphoreSubmitInfo::builder()
+            .signal_semaphore_values(slice_from_ref(&signal_value));
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(slice_from_ref(&command_buffer))
+            .signal_semaphores(slice_from_ref(&self.timeline_semaphore))
+            .push_next(&mut timeline_info);
+        unsafe {
+            device.queue_submit(
+                vulkan_context.transfer_queue,
+                slice_from_ref(&submit_info),
+                vk::Fence::null(),
+            )
+        }?;
+
+        self.timeline_value = signal_value;
+        self.in_flight.push_back(InFlightUpload {
+            timeline_value: signal_value,
+            allocation_size,
+            command_buffer,
+        });
+
+        Ok(())
+    }
+
+    /// Release the ring space and command buffers of any uploads that have completed.
+    pub fn retire(&mut self, vulkan_context: &VulkanContext) -> Result<()> {
+        if self.in_flight.is_empty() {
+            return Ok(());
+        }
+
+        let device = &vulkan_context.device;
+        let completed = unsafe { device.get_semaphore_counter_value(self.timeline_semaphore) }?;
+        while let Some(upload) = self.in_flight.front() {
+            if upload.timeline_value > completed {
+                break;
+            }
+            unsafe {
+                device
+                    .free_command_buffers(self.command_pool, slice_from_ref(&upload.command_buffer))
+            };
+            self.allocator.free(upload.allocation_size);
+            self.in_flight.pop_front();
+        }
+
+        Ok(())
+    }
+
+    /// Block until every upload has completed.
+    pub fn wait_idle(&mut self, vulkan_context: &VulkanContext) -> Result<()> {
+        self.wait_for_value(vulkan_context, self.timeline_value)?;
+        self.retire(vulkan_context)
+    }
+
+    fn wait_for_value(&self, vulkan_context: &VulkanContext, value: u64) -> Result<()> {
+        let wait_info = vk::SemaphoreWaitInfo::builder()
+            .semaphores(slice_from_ref(&self.timeline_semaphore))
+            .values(slice_from_ref(&value));
+        unsafe { vulkan_context.device.wait_semaphores(&wait_info, u64::MAX) }?;
+        Ok(())
+    }
+}
+
+/// Hands out space in a ring buffer, where allocations are freed in the order they were made.
+#[derive(Debug, Clone)]
+struct RingAllocator {
+    size: u64,
+    head: u64,
+    used: u64,
+}
+
+impl RingAllocator {
+    fn new(size: u64) -> Self {
+        Self {
+            size,
+            head: 0,
+            used: 0,
+        }
+    }
+
+    /// Allocate `len` bytes aligned to `alignment`. Returns the offset of the allocation and the number of bytes
+    /// that must be passed to [`RingAllocator::free`] to release it, including any padding. Returns `None` if
+    /// there isn't enough free space.
+    fn allocate(&mut self, len: u64, alignment: u64) -> Option<(u64, u64)> {
+        let aligned_head = (self.head + alignment - 1) & !(alignment - 1);
+
+        // Allocations never straddle the end of the ring. Skip to the start instead.
+        let offset = if aligned_head + len > self.size {
+            0
+        } else {
+            aligned_head
+        };
+        let padding = if offset == 0 && self.head != 0 {
+            self.size - self.head
+        } else {
+            offset - self.head
+        };
+
+        let allocation_size = padding + len;
+        if self.used + allocation_size > self.size {
+            return None;
+        }
+
+        self.head = (offset + len) % self.size;
+        self.used += allocation_size;
+        Some((offset, allocation_size))
+    }
+
+    /// Release the oldest allocation
+    fn free(&mut self, allocation_size: u64) {
+        self.used -= allocation_size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_ring_allocator() {
+        let mut allocator = RingAllocator::new(64);
+
+        assert_eq!(allocator.allocate(20, 16), Some((0, 20)));
+        // Padded up to the alignment
+        assert_eq!(allocator.allocate(20, 16), Some((32, 32)));
+
+        // Doesn't fit before the end, and the start of the ring is still in use
+        assert_eq!(allocator.allocate(16, 16), None);
+
+        // Once the first allocation is freed we wrap around to the start
+        allocator.free(20);
+        assert_eq!(allocator.allocate(16, 16), Some((0, 28)));
+        assert_eq!(allocator.used, 60);
+
+        allocator.free(32);
+        allocator.free(28);
+        assert_eq!(allocator.used, 0);
+    }
+}
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+
+use glam::{Affine3A, Vec3};
+use hecs::{Entity, World};
+
+use crate::{
+    components::{
+        CustomMaterial, GlobalTransform, Highlighted, LocalTransform, Lod, Mesh, MorphWeights,
+        Parent, Skin, Static, Visible,
+    },
+    contexts::RenderContext,
+    id_arena::Id,
+    rendering::{mesh_data::MeshData, primitive::Primitive, vertex::Vertex},
+    systems::update_global_transform::update_global_transform_system_inner,
+};
+
+/// Merge the meshes of [`Static`] entities that share a material, so they're drawn with a single draw call per
+/// material instead of one per primitive. Returns the number of batches that were created.
+///
+/// Call this once the scene has been loaded and the static entities have been placed. Every batch is baked into
+/// global space and added to a new entity, and the merged primitives are removed from the [`Mesh`] of the
+/// entities they came from. Those entities keep all their other components, so colliders and the like still
+/// work.
+///
+/// Meshes that are hidden, skinned, morphed, highlighted, have a custom material or levels of detail aren't
+/// batched, and neither are transparent primitives, as they need to be sorted. A batch is culled as a whole, so
+/// avoid marking meshes that are spread far apart with the same material as static.
+///
+/// Batched entities can't be hidden afterwards: the batch entity is always [`Visible`], so setting `Visible(false)`
+/// on an entity whose primitives were merged only hides the ones that weren't. Hide it before batching to keep it
+/// out, or leave anything that needs to be shown and hidden unmarked.
+pub fn batch_static_meshes(world: &mut World, render_context: &mut RenderContext) -> usize {
+    // Make sure the meshes are baked where they're actually placed.
+    update_global_transform_system_inner(world);
+
+    let candidates = world
+        .query::<(&Mesh, &GlobalTransform, &Visible)>()
+        .without::<&Skin>()
+        .without::<&MorphWeights>()
+        .without::<&CustomMaterial>()
+        .without::<&Highlighted>()
+        .without::<&Lod>()
+        .iter()
+        .filter(|(entity, (_, _, visible))| visible.0 && is_static(world, *entity))
+        .map(|(entity, (mesh, global_transform, _))| (entity, mesh.handle, global_transform.0))
+        .collect::<Vec<(Entity, Id<MeshData>, Affine3A)>>();
+
+    // Group every primitive that can be batched by its material.
+    let materials = unsafe { render_context.resources.materials_buffer.as_slice() };
+    let mut groups: BTreeMap<u32, Vec<(usize, usize)>> = BTreeMap::new();
+    for (candidate, (_, handle, _)) in candidates.iter().enumerate() {
+        let mesh_data = render_context.resources.mesh_data.get(*handle).unwrap();
+        for (index, primitive) in mesh_data.primitives.iter().enumerate() {
+            let is_transparent = materials
+                .get(primitive.material_id as usize)
+                .map_or(false, |material| material.is_transparent());
+            if primitive.morph_target_count == 0 && !is_transparent {
+                groups
+                    .entry(primitive.material_id)
+                    .or_default()
+                    .push((candidate, index));
+            }
+        }
+    }
+
+    // There's nothing to gain from batching a primitive on its own.
+    groups.retain(|_, parts| parts.len() > 1);
+    if groups.is_empty() {
+        return 0;
+    }
+
+    let mut merged = vec![Vec::new(); candidates.len()];
+    let mut batches = Vec::with_capacity(groups.len());
+    for (material_id, parts) in groups {
+        let mut geometry = BatchGeometry::default();
+        for (candidate, index) in parts {
+            let (_, handle, global_from_local) = &candidates[candidate];
+            let primitive = &render_context
+                .resources
+                .mesh_data
+                .get(*handle)
+                .unwrap()
+                .primitives[index];
+            let (positions, vertices, indices) = read_primitive(render_context, primitive);
+            geometry.append(&positions, &vertices, &indices, global_from_local);
+            merged[candidate].push(index);
+        }
+
+        batches.push(Primitive::new(
+            &geometry.positions,
+            &geometry.vertices,
+            &geometry.indices,
+            material_id,
+            render_context,
+        ));
+    }
+
+    // Remove the merged primitives from the meshes they came from.
+    let merged_count = merged.iter().filter(|merged| !merged.is_empty()).count();
+    for ((entity, handle, _), merged) in candidates.iter().zip(merged) {
+        if merged.is_empty() {
+            continue;
+        }
+
+        let remaining = render_context
+            .resources
+            .mesh_data
+            .get(*handle)
+            .unwrap()
+            .primitives
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !merged.contains(index))
+            .map(|(_, primitive)| primitive.clone())
+            .collect::<Vec<_>>();
+
+        if remaining.is_empty() {
+            world.remove_one::<Mesh>(*entity).unwrap();
+        } else {
+            let mesh = Mesh::new(MeshData::new(remaining), render_context);
+            world.insert_one(*entity, mesh).unwrap();
+        }
+    }
+
+    let batch_count = batches.len();
+    println!("[HOTHAM_RENDERING] Merged {merged_count} static meshes into {batch_count} batches");
+    world.spawn((
+        Mesh::new(MeshData::new(batches), render_context),
+        LocalTransform::default(),
+        GlobalTransform::default(),
+        Visible(true),
+        Static,
+    ));
+
+    batch_count
+}
+
+/// Is `entity`, or any of its ancestors, marked as [`Static`]?
+fn is_static(world: &World, entity: Entity) -> bool {
+    let mut entity = entity;
+    loop {
+        if world.get::<&Static>(entity).is_ok() {
+            return true;
+        }
+        match world.get::<&Parent>(entity) {
+            Ok(parent) => entity = parent.0,
+            Err(_) => return false,
+        }
+    }
+}
+
+/// Copy the geometry of `primitive` back out of the GPU buffers.
+fn read_primitive(
+    render_context: &RenderContext,
+    primitive: &Primitive,
+) -> (Vec<Vec3>, Vec<Vertex>, Vec<u32>) {
+    let resources = &render_context.resources;
+    let first_index = primitive.index_buffer_offset as usize;
+    let indices = unsafe {
+        &resources.index_buffer.as_slice()
+            [first_index..first_index + primitive.indices_count as usize]
+    };
+
+    // Indices are relative to the primitive's first vertex, which is all we need to find its vertices.
+    let first_vertex = primitive.vertex_buffer_offset as usize;
+    let vertex_count = indices.iter().max().map_or(0, |index| *index as usize + 1);
+    let vertices = first_vertex..first_vertex + vertex_count;
+
+    unsafe {
+        (
+            resources.position_buffer.as_slice()[vertices.clone()].to_vec(),
+            resources.vertex_buffer.as_slice()[vertices].to_vec(),
+            indices.to_vec(),
+        )
+    }
+}
+
+/// The geometry of a batch, in global space.
+#[derive(Debug, Default)]
+struct BatchGeometry {
+    positions: Vec<Vec3>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl BatchGeometry {
+    /// Append the geometry of a primitive, moving it from local into global space.
+    fn append(
+        &mut self,
+        positions: &[Vec3],
+        vertices: &[Vertex],
+        indices: &[u32],
+        global_from_local: &Affine3A,
+    ) {
+        let first_vertex = self.positions.len() as u32;
+        let normal_matrix = global_from_local.matrix3.inverse().transpose();
+
+        self.positions.extend(
+            positions
+                .iter()
+                .map(|position| global_from_local.transform_point3(*position)),
+        );
+        self.vertices.extend(vertices.iter().map(|vertex| Vertex {
+            normal: (normal_matrix * vertex.normal).normalize_or_zero(),
+            ..*vertex
+        }));
+
+        // Mirrored transforms turn triangles inside out, so flip their winding back.
+        let is_mirrored = global_from_local.matrix3.determinant() < 0.;
+        for triangle in indices.chunks_exact(3) {
+            let triangle = if is_mirrored {
+                [triangle[0], triangle[2], triangle[1]]
+            } else {
+                [triangle[0], triangle[1], triangle[2]]
+            };
+            self.indices
+                .extend(triangle.iter().map(|index| index + first_vertex));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    pub fn test_batch_geometry() {
+        let positions = [Vec3::ZERO, Vec3::X, Vec3::Y];
+        let vertices = [Vertex {
+            normal: Vec3::Z,
+            ..Default::default()
+        }; 3];
+        let indices = [0, 1, 2];
+
+        let mut geometry = BatchGeometry::default();
+        geometry.append(
+            &positions,
+            &vertices,
+            &indices,
+            &Affine3A::from_translation([0., 2., 0.].into()),
+        );
+
+        // The second triangle is mirrored along X, so its winding is flipped
+        geometry.append(
+            &positions,
+            &vertices,
+            &indices,
+            &Affine3A::from_scale([-2., 1., 1.].into()),
+        );
+
+        assert_eq!(geometry.indices, [0, 1, 2, 3, 5, 4]);
+        assert_eq!(geometry.positions[1], [1., 2., 0.].into());
+        assert_eq!(geometry.positions[4], [-2., 0., 0.].into());
+        assert_relative_eq!(geometry.vertices[4].normal, Vec3::Z);
+    }
+
+    #[test]
+    pub fn test_is_static() {
+        let mut world = World::new();
+        let root = world.spawn((Static,));
+        let child = world.spawn((Parent(root),));
+        let grandchild = world.spawn((Parent(child),));
+        let other = world.spawn(());
+
+        assert!(is_static(&world, root));
+        assert!(is_static(&world, grandchild));
+        assert!(!is_static(&world, other));
+    }
+}
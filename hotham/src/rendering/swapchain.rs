@@ -3,7 +3,7 @@ use ash::vk::{self, Handle};
 use openxr::{Swapchain as SwapchainHandle, Vulkan};
 use vulkan_context::VulkanContext;
 
-use crate::{contexts::vulkan_context, COLOR_FORMAT, DEPTH_FORMAT};
+use crate::{contexts::vulkan_context, DEPTH_STENCIL_FORMAT, VIEW_COUNT};
 
 use super::texture::DEFAULT_COMPONENT_MAPPING;
 
@@ -13,39 +13,64 @@ pub struct SwapchainInfo {
     pub resolution: vk::Extent2D,
     /// The images held in the swapchain
     pub images: Vec<vk::Image>,
-    /// Images used for fixed foveated rendering
+    /// The images held in the depth swapchain. Empty unless depth is submitted to the compositor.
+    pub depth_images: Vec<vk::Image>,
+    /// Images used for fixed foveated rendering. Empty unless the runtime provides a fragment density map.
     #[cfg(target_os = "android")]
     pub ffr_images: Vec<FFRImage>,
 }
 
 impl SwapchainInfo {
+    /// Get the swapchain's images, and its fragment density maps if it was created with them
     #[cfg(target_os = "android")]
     pub(crate) fn from_openxr_swapchain(
         handle: &SwapchainHandle<Vulkan>,
         resolution: vk::Extent2D,
+        fragment_density_map: bool,
     ) -> Result<Self> {
-        #[cfg(target_os = "android")]
-        {
-            let (images, ffr_images) = get_swapchain_images_with_ffr(handle);
-            Ok(Self {
-                resolution,
-                images,
-                ffr_images,
-            })
-        }
+        let (images, ffr_images) = if fragment_density_map {
+            get_swapchain_images_with_ffr(handle)
+        } else {
+            let images = handle
+                .enumerate_images()?
+                .into_iter()
+                .map(vk::Image::from_raw)
+                .collect();
+            (images, Vec::new())
+        };
+        Ok(Self {
+            resolution,
+            images,
+            depth_images: Vec::new(),
+            ffr_images,
+        })
     }
 
+    /// Get the swapchain's images. Fragment density maps are only supported on Android.
     #[cfg(not(target_os = "android"))]
     pub(crate) fn from_openxr_swapchain(
         handle: &SwapchainHandle<Vulkan>,
         resolution: vk::Extent2D,
+        _fragment_density_map: bool,
     ) -> Result<Self> {
         let images = handle
             .enumerate_images()?
             .into_iter()
             .map(vk::Image::from_raw)
             .collect::<Vec<_>>();
-        Ok(Self { resolution, images })
+        Ok(Self {
+            resolution,
+            images,
+            depth_images: Vec::new(),
+        })
+    }
+
+    /// Should the render pass use a fragment density map?
+    pub(crate) fn has_fragment_density_map(&self) -> bool {
+        #[cfg(target_os = "android")]
+        return !self.ffr_images.is_empty();
+        #[cfg(not(target_os = "android"))]
+        return false;
     }
 }
 
@@ -133,14 +158,24 @@ pub struct Swapchain {
     pub render_area: vk::Rect2D,
     /// The framebuffers of the swapchain, one per swapchain image.
     pub framebuffers: Vec<vk::Framebuffer>,
+    /// The color images of the swapchain, owned by OpenXR.
+    pub images: Vec<vk::Image>,
+    /// The depth buffer, shared between frames.
+    pub depth_image: super::image::Image,
+    /// A view of only the depth aspect of the depth buffer, for sampling it in shaders. Null unless the depth
+    /// buffer was created with `sampled_depth`.
+    pub depth_sample_view: vk::ImageView,
 }
 
 impl Swapchain {
-    /// Create a swapchain
+    /// Create a swapchain. If `sampled_depth` is set the depth buffer can be read from after the render pass.
     pub fn new(
         swapchain_info: &SwapchainInfo,
         vulkan_context: &VulkanContext,
         render_pass: vk::RenderPass,
+        color_format: vk::Format,
+        msaa_samples: vk::SampleCountFlags,
+        sampled_depth: bool,
     ) -> Self {
         let render_area = vk::Rect2D {
             extent: swapchain_info.resolution,
@@ -148,40 +183,79 @@ impl Swapchain {
         };
 
         // Depth image, shared between frames
+        let depth_usage = if sampled_depth {
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED
+        } else {
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+        };
         let depth_image = vulkan_context
-            .create_image(
-                DEPTH_FORMAT,
+            .create_multisampled_image(
+                DEPTH_STENCIL_FORMAT,
                 &swapchain_info.resolution,
-                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
-                    | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+                depth_usage,
                 2,
-                1,
+                msaa_samples,
             )
             .unwrap();
 
-        // Color image, used for MSAA.
-        let color_image = vulkan_context
-            .create_image(
-                COLOR_FORMAT,
-                &swapchain_info.resolution,
-                vk::ImageUsageFlags::TRANSIENT_ATTACHMENT | vk::ImageUsageFlags::COLOR_ATTACHMENT,
-                2,
-                1,
+        // Shaders can only sample one aspect of a depth/stencil image at a time.
+        let depth_sample_view = if sampled_depth {
+            let create_info = vk::ImageViewCreateInfo::builder()
+                .view_type(vk::ImageViewType::TYPE_2D_ARRAY)
+                .format(DEPTH_STENCIL_FORMAT)
+                .subresource_range(vk::ImageSubresourceRange {
+                    aspect_mask: vk::ImageAspectFlags::DEPTH,
+                    base_mip_level: 0,
+                    level_count: 1,
+                    base_array_layer: 0,
+                    layer_count: VIEW_COUNT,
+                })
+                .image(depth_image.handle);
+            unsafe {
+                vulkan_context
+                    .device
+                    .create_image_view(&create_info, None)
+                    .unwrap()
+            }
+        } else {
+            vk::ImageView::null()
+        };
+
+        // Color image, used for MSAA. If MSAA is disabled we render directly into the swapchain images.
+        let color_image = if msaa_samples != vk::SampleCountFlags::TYPE_1 {
+            Some(
+                vulkan_context
+                    .create_multisampled_image(
+                        color_format,
+                        &swapchain_info.resolution,
+                        vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+                            | vk::ImageUsageFlags::COLOR_ATTACHMENT,
+                        2,
+                        msaa_samples,
+                    )
+                    .unwrap(),
             )
-            .unwrap();
+        } else {
+            None
+        };
 
         // Framebuffers, used for rendering the final image to the swapchain.
         let framebuffers = create_framebuffers(
             swapchain_info,
             vulkan_context,
+            color_format,
             color_image,
-            depth_image,
+            &depth_image,
             render_pass,
         );
 
         Self {
             render_area,
             framebuffers,
+            images: swapchain_info.images.clone(),
+            depth_image,
+            depth_sample_view,
         }
     }
 }
@@ -190,20 +264,23 @@ impl Swapchain {
 fn create_framebuffers(
     swapchain_info: &SwapchainInfo,
     vulkan_context: &VulkanContext,
-    color_image: super::image::Image,
-    depth_image: super::image::Image,
+    color_format: vk::Format,
+    color_image: Option<super::image::Image>,
+    depth_image: &super::image::Image,
     render_pass: vk::RenderPass,
 ) -> Vec<vk::Framebuffer> {
-    let ffr_image_view = vulkan_context
-        .create_image_view(
-            &swapchain_info.ffr_images[0].image,
-            vk::Format::R8G8_UNORM,
-            vk::ImageViewType::TYPE_2D_ARRAY,
-            2,
-            1,
-            DEFAULT_COMPONENT_MAPPING,
-        )
-        .unwrap();
+    let ffr_image_view = swapchain_info.ffr_images.first().map(|ffr_image| {
+        vulkan_context
+            .create_image_view(
+                &ffr_image.image,
+                vk::Format::R8G8_UNORM,
+                vk::ImageViewType::TYPE_2D_ARRAY,
+                2,
+                1,
+                DEFAULT_COMPONENT_MAPPING,
+            )
+            .unwrap()
+    });
     let framebuffers = swapchain_info
         .images
         .iter()
@@ -211,7 +288,7 @@ fn create_framebuffers(
             vulkan_context
                 .create_image_view(
                     swapchain_image,
-                    COLOR_FORMAT,
+                    color_format,
                     vk::ImageViewType::TYPE_2D_ARRAY,
                     2,
                     1,
@@ -220,16 +297,19 @@ fn create_framebuffers(
                 .unwrap()
         })
         .map(|swapchain_image_view| {
-            let msaa_attachments = [
-                color_image.view,
-                depth_image.view,
-                ffr_image_view,
-                swapchain_image_view,
-            ];
+            // See `create_render_pass` for the attachment layout.
+            let mut attachments = match &color_image {
+                Some(color_image) => vec![color_image.view, depth_image.view],
+                None => vec![swapchain_image_view, depth_image.view],
+            };
+            attachments.extend(ffr_image_view);
+            if color_image.is_some() {
+                attachments.push(swapchain_image_view);
+            }
 
             let frame_buffer_create_info = vk::FramebufferCreateInfo::builder()
                 .render_pass(render_pass)
-                .attachments(&msaa_attachments)
+                .attachments(&attachments)
                 .width(swapchain_info.resolution.width)
                 .height(swapchain_info.resolution.height)
                 .layers(1); // NOTE: multiview takes care of layers.
@@ -249,8 +329,9 @@ fn create_framebuffers(
 fn create_framebuffers(
     swapchain_info: &SwapchainInfo,
     vulkan_context: &VulkanContext,
-    color_image: super::image::Image,
-    depth_image: super::image::Image,
+    color_format: vk::Format,
+    color_image: Option<super::image::Image>,
+    depth_image: &super::image::Image,
     render_pass: vk::RenderPass,
 ) -> Vec<vk::Framebuffer> {
     let framebuffers = swapchain_info
@@ -259,7 +340,7 @@ fn create_framebuffers(
         .flat_map(|i| {
             vulkan_context.create_image_view(
                 i,
-                COLOR_FORMAT,
+                color_format,
                 vk::ImageViewType::TYPE_2D_ARRAY,
                 2,
                 1,
@@ -267,11 +348,15 @@ fn create_framebuffers(
             )
         })
         .map(|swapchain_image_view| {
-            let msaa_attachments = [color_image.view, depth_image.view, swapchain_image_view];
+            // See `create_render_pass` for the attachment layout.
+            let attachments = match &color_image {
+                Some(color_image) => vec![color_image.view, depth_image.view, swapchain_image_view],
+                None => vec![swapchain_image_view, depth_image.view],
+            };
 
             let frame_buffer_create_info = vk::FramebufferCreateInfo::builder()
                 .render_pass(render_pass)
-                .attachments(&msaa_attachments)
+                .attachments(&attachments)
                 .width(swapchain_info.resolution.width)
                 .height(swapchain_info.resolution.height)
                 .layers(1); // NOTE: multiview takes care of layers.
@@ -0,0 +1,635 @@
+use std::{collections::HashMap, slice::from_ref as slice_from_ref, sync::Arc};
+
+use anyhow::{anyhow, Result};
+use ash::vk;
+use glam::{Affine3A, Vec2, Vec3, Vec4};
+use vk_shader_macros::include_glsl;
+
+use crate::{
+    components::{text::TextAlignment, Text},
+    contexts::{
+        render_context::{create_push_constant, create_shader, PIPELINE_DEPTH},
+        RenderContext, VulkanContext,
+    },
+    rendering::{
+        buffer::Buffer,
+        descriptors::Descriptors,
+        texture::{Texture, TextureUsage},
+    },
+};
+
+static TEXT_VERT: &[u32] = include_glsl!("src/shaders/text.vert", target: vulkan1_1);
+static TEXT_FRAG: &[u32] = include_glsl!("src/shaders/text.frag", target: vulkan1_1);
+
+/// The maximum number of glyphs that can be drawn each frame.
+pub const MAX_GLYPHS: usize = 10_000;
+
+/// The size, in pixels, that glyphs are rasterized at before their distance fields are generated.
+const GLYPH_SIZE_PX: f32 = 32.;
+
+/// How far, in pixels, the distance field extends from the edge of each glyph.
+const SDF_SPREAD_PX: usize = 4;
+
+/// The width of the font atlas. Its height is as large as it needs to be.
+const ATLAS_WIDTH: usize = 512;
+
+/// A single corner of a glyph quad, in globally oriented stage space.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextVertex {
+    /// Position in gos space
+    pub position: Vec3,
+    /// Coordinates in the font atlas
+    pub uv: Vec2,
+    /// Color, packed as RGBA8
+    pub color: [u8; 4],
+}
+
+/// Where a glyph is in the font atlas and how it is placed relative to the pen. Measured in ems.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Glyph {
+    /// Top left of the glyph in the atlas
+    pub uv_min: Vec2,
+    /// Bottom right of the glyph in the atlas
+    pub uv_max: Vec2,
+    /// Offset from the pen to the bottom left of the glyph quad
+    pub offset: Vec2,
+    /// Size of the glyph quad
+    pub size: Vec2,
+    /// How far the pen moves after this glyph
+    pub advance: f32,
+}
+
+/// A font whose glyphs are stored as a signed distance field, so they stay sharp at any size.
+///
+/// Fonts are cheap to clone and can be shared between any number of [`Text`] components.
+///
+/// Basic usage:
+/// ```ignore
+/// let font = SdfFont::from_ttf(vulkan_context, render_context, include_bytes!("font.ttf"), SdfFont::ASCII)?;
+/// world.spawn((Text::new("Score: 0", font), LocalTransform::default(), GlobalTransform::default(), Visible(true)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SdfFont {
+    /// Index of the atlas in the texture array
+    pub texture_id: u32,
+    /// The distance between two lines, in ems
+    pub line_height: f32,
+    glyphs: Arc<HashMap<char, Glyph>>,
+}
+
+impl SdfFont {
+    /// The printable ASCII characters
+    pub const ASCII: &'static str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+    /// Create a font from a TrueType or OpenType font file, generating distance fields for each of `characters`.
+    pub fn from_ttf(
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        font_data: &[u8],
+        characters: &str,
+    ) -> Result<Self> {
+        println!("[HOTHAM_TEXT] Generating font atlas..");
+        let font = fontdue::Font::from_bytes(font_data, Default::default())
+            .map_err(|e| anyhow!("Unable to load font: {e}"))?;
+        let line_height = font
+            .horizontal_line_metrics(GLYPH_SIZE_PX)
+            .map(|m| m.new_line_size / GLYPH_SIZE_PX)
+            .unwrap_or(1.2);
+
+        let mut atlas = AtlasBuilder::default();
+        let mut glyphs = HashMap::new();
+        for character in characters.chars() {
+            let (metrics, coverage) = font.rasterize(character, GLYPH_SIZE_PX);
+            let sdf = generate_sdf(&coverage, metrics.width, metrics.height);
+            let (width, height) = (
+                metrics.width + SDF_SPREAD_PX * 2,
+                metrics.height + SDF_SPREAD_PX * 2,
+            );
+            let (x, y) = atlas.insert(&sdf, width, height);
+
+            let spread = SDF_SPREAD_PX as f32;
+            glyphs.insert(
+                character,
+                Glyph {
+                    uv_min: Vec2::new(x as f32, y as f32),
+                    uv_max: Vec2::new((x + width) as f32, (y + height) as f32),
+                    offset: Vec2::new(metrics.xmin as f32 - spread, metrics.ymin as f32 - spread)
+                        / GLYPH_SIZE_PX,
+                    size: Vec2::new(width as f32, height as f32) / GLYPH_SIZE_PX,
+                    advance: metrics.advance_width / GLYPH_SIZE_PX,
+                },
+            );
+        }
+
+        let (pixels, extent) = atlas.finish();
+        let atlas_size = Vec2::new(extent.width as f32, extent.height as f32);
+        for glyph in glyphs.values_mut() {
+            glyph.uv_min /= atlas_size;
+            glyph.uv_max /= atlas_size;
+        }
+
+        let texture = Texture::new(
+            "Font atlas",
+            vulkan_context,
+            render_context,
+            &pixels,
+            &extent,
+            1,
+            1,
+            vk::Format::R8_UNORM,
+            TextureUsage::Other,
+        );
+        println!("[HOTHAM_TEXT] ..done! Font atlas is {extent:?}");
+
+        Ok(Self {
+            texture_id: texture.index,
+            line_height,
+            glyphs: Arc::new(glyphs),
+        })
+    }
+
+    /// Create a font from glyphs that have already been placed in an atlas.
+    pub fn from_glyphs(texture_id: u32, line_height: f32, glyphs: HashMap<char, Glyph>) -> Self {
+        Self {
+            texture_id,
+            line_height,
+            glyphs: Arc::new(glyphs),
+        }
+    }
+
+    /// Get the glyph for `character`, if the font has one
+    pub fn glyph(&self, character: char) -> Option<&Glyph> {
+        self.glyphs.get(&character)
+    }
+
+    /// The width of a single line of text, in ems
+    pub fn line_width(&self, line: &str) -> f32 {
+        line.chars()
+            .filter_map(|c| self.glyph(c))
+            .map(|g| g.advance)
+            .sum()
+    }
+}
+
+/// Packs glyphs into rows, from the top of the atlas down.
+#[derive(Default)]
+struct AtlasBuilder {
+    pixels: Vec<u8>,
+    cursor_x: usize,
+    cursor_y: usize,
+    row_height: usize,
+}
+
+impl AtlasBuilder {
+    fn insert(&mut self, glyph: &[u8], width: usize, height: usize) -> (usize, usize) {
+        if self.cursor_x + width > ATLAS_WIDTH {
+            self.cursor_x = 0;
+            self.cursor_y += self.row_height;
+            self.row_height = 0;
+        }
+
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        self.pixels
+            .resize(self.pixels.len().max((y + height) * ATLAS_WIDTH), 0);
+        for row in 0..height {
+            let start = (y + row) * ATLAS_WIDTH + x;
+            self.pixels[start..start + width]
+                .copy_from_slice(&glyph[row * width..(row + 1) * width]);
+        }
+
+        self.cursor_x += width;
+        self.row_height = self.row_height.max(height);
+        (x, y)
+    }
+
+    fn finish(mut self) -> (Vec<u8>, vk::Extent2D) {
+        let height = (self.pixels.len() / ATLAS_WIDTH).max(1).next_power_of_two();
+        self.pixels.resize(height * ATLAS_WIDTH, 0);
+        (
+            self.pixels,
+            vk::Extent2D {
+                width: ATLAS_WIDTH as _,
+                height: height as _,
+            },
+        )
+    }
+}
+
+/// Turn the coverage of a glyph into a signed distance field, padded by [`SDF_SPREAD_PX`] on each side.
+/// Edges are at 0.5, with larger values inside the glyph.
+fn generate_sdf(coverage: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let spread = SDF_SPREAD_PX as i32;
+    let (padded_width, padded_height) = (width as i32 + spread * 2, height as i32 + spread * 2);
+    let inside = |x: i32, y: i32| {
+        let (x, y) = (x - spread, y - spread);
+        x >= 0
+            && y >= 0
+            && x < width as i32
+            && y < height as i32
+            && coverage[y as usize * width + x as usize] >= 128
+    };
+
+    let mut sdf = Vec::with_capacity((padded_width * padded_height) as usize);
+    for y in 0..padded_height {
+        for x in 0..padded_width {
+            let is_inside = inside(x, y);
+
+            // Find the nearest pixel on the other side of the edge.
+            let mut nearest = spread as f32;
+            for dy in -spread..=spread {
+                for dx in -spread..=spread {
+                    if inside(x + dx, y + dy) != is_inside {
+                        nearest = nearest.min(((dx * dx + dy * dy) as f32).sqrt());
+                    }
+                }
+            }
+
+            let distance = if is_inside { nearest } else { -nearest };
+            let value = 0.5 + distance / (spread as f32 * 2.);
+            sdf.push((value.clamp(0., 1.) * 255.).round() as u8);
+        }
+    }
+
+    sdf
+}
+
+/// A run of glyphs that share a font.
+#[derive(Debug, Clone, Copy)]
+struct TextBatch {
+    texture_id: u32,
+    first_vertex: u32,
+    vertex_count: u32,
+}
+
+/// Lay out `text` on the local XY plane, with the entity's origin on the baseline of the first line.
+/// Each glyph becomes two triangles.
+pub(crate) fn layout_text(text: &Text, gos_from_local: &Affine3A, vertices: &mut Vec<TextVertex>) {
+    let font = &text.font;
+    let color = pack_color(text.color);
+    let mut pen_y = 0.;
+
+    for line in text.text.lines() {
+        let mut pen_x = match text.alignment {
+            TextAlignment::Left => 0.,
+            TextAlignment::Center => -font.line_width(line) * 0.5,
+            TextAlignment::Right => -font.line_width(line),
+        };
+
+        for character in line.chars() {
+            let glyph = match font.glyph(character) {
+                Some(glyph) => glyph,
+                None => continue,
+            };
+
+            let min = Vec2::new(pen_x, pen_y) + glyph.offset;
+            let max = min + glyph.size;
+            let corner = |x: f32, y: f32, u: f32, v: f32| TextVertex {
+                position: gos_from_local.transform_point3(Vec3::new(x, y, 0.) * text.size),
+                uv: Vec2::new(u, v),
+                color,
+            };
+            let (uv_min, uv_max) = (glyph.uv_min, glyph.uv_max);
+
+            // The atlas is stored top down, so the top of the quad samples uv_min.y
+            let bottom_left = corner(min.x, min.y, uv_min.x, uv_max.y);
+            let bottom_right = corner(max.x, min.y, uv_max.x, uv_max.y);
+            let top_right = corner(max.x, max.y, uv_max.x, uv_min.y);
+            let top_left = corner(min.x, max.y, uv_min.x, uv_min.y);
+            vertices.extend_from_slice(&[
+                bottom_left,
+                bottom_right,
+                top_right,
+                bottom_left,
+                top_right,
+                top_left,
+            ]);
+
+            pen_x += glyph.advance;
+        }
+
+        pen_y -= font.line_height;
+    }
+}
+
+fn pack_color(color: Vec4) -> [u8; 4] {
+    let c = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.).round();
+    [c.x as u8, c.y as u8, c.z as u8, c.w as u8]
+}
+
+/// The pipeline used to draw [`Text`] components.
+pub struct TextPipeline {
+    /// The scene data descriptor set plus the font atlas ID as a push constant
+    pub pipeline_layout: vk::PipelineLayout,
+    /// Draws alpha blended glyphs, tested against but not written to the depth buffer
+    pub pipeline: vk::Pipeline,
+    /// One vertex buffer per frame
+    vertex_buffers: [Buffer<TextVertex>; PIPELINE_DEPTH],
+    /// Every glyph queued this frame
+    vertices: Vec<TextVertex>,
+    batches: Vec<TextBatch>,
+}
+
+impl TextPipeline {
+    pub(crate) fn new(
+        vulkan_context: &VulkanContext,
+        descriptors: &Descriptors,
+        render_pass: vk::RenderPass,
+        render_area: &vk::Rect2D,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> Result<Self> {
+        let device = &vulkan_context.device;
+        let push_constant_range = vk::PushConstantRange::builder()
+            .offset(0)
+            .size(std::mem::size_of::<u32>() as _)
+            .stage_flags(vk::ShaderStageFlags::FRAGMENT);
+
+        let pipeline_layout = unsafe {
+            device.create_pipeline_layout(
+                &vk::PipelineLayoutCreateInfo::builder()
+                    .set_layouts(slice_from_ref(&descriptors.graphics_layout))
+                    .push_constant_ranges(slice_from_ref(&push_constant_range)),
+                None,
+            )
+        }?;
+
+        let (vertex_shader, vertex_stage) =
+            create_shader(TEXT_VERT, vk::ShaderStageFlags::VERTEX, vulkan_context)?;
+        let (fragment_shader, fragment_stage) =
+            create_shader(TEXT_FRAG, vk::ShaderStageFlags::FRAGMENT, vulkan_context)?;
+        let stages = [vertex_stage, fragment_stage];
+
+        let vertex_binding = vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(std::mem::size_of::<TextVertex>() as _)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build();
+        let vertex_attributes = [
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(0)
+                .format(vk::Format::R32G32B32_SFLOAT)
+                .offset(memoffset::offset_of!(TextVertex, position) as _)
+                .build(),
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(1)
+                .format(vk::Format::R32G32_SFLOAT)
+                .offset(memoffset::offset_of!(TextVertex, uv) as _)
+                .build(),
+            vk::VertexInputAttributeDescription::builder()
+                .binding(0)
+                .location(2)
+                .format(vk::Format::R8G8B8A8_UNORM)
+                .offset(memoffset::offset_of!(TextVertex, color) as _)
+                .build(),
+        ];
+        let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+            .vertex_binding_descriptions(slice_from_ref(&vertex_binding))
+            .vertex_attribute_descriptions(&vertex_attributes);
+        let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+            .topology(vk::PrimitiveTopology::TRIANGLE_LIST);
+
+        let viewport = vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: render_area.extent.width as _,
+            height: render_area.extent.height as _,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        };
+        let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+            .viewports(slice_from_ref(&viewport))
+            .scissors(slice_from_ref(render_area));
+
+        // Text can be read from behind, albeit backwards.
+        let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+            .polygon_mode(vk::PolygonMode::FILL)
+            .cull_mode(vk::CullModeFlags::NONE)
+            .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+            .line_width(1.0);
+
+        let multisample_state =
+            vk::PipelineMultisampleStateCreateInfo::builder().rasterization_samples(msaa_samples);
+
+        // Remember that we're using inverse Z.
+        let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(true)
+            .depth_write_enable(false)
+            .depth_compare_op(vk::CompareOp::GREATER_OR_EQUAL)
+            .depth_bounds_test_enable(false)
+            .min_depth_bounds(0.0)
+            .max_depth_bounds(1.0)
+            .stencil_test_enable(false);
+
+        let color_blend_attachment = vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(
+                vk::ColorComponentFlags::R
+                    | vk::ColorComponentFlags::G
+                    | vk::ColorComponentFlags::B
+                    | vk::ColorComponentFlags::A,
+            )
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ONE)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .build();
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op_enable(false)
+            .attachments(slice_from_ref(&color_blend_attachment));
+
+        let create_info = vk::GraphicsPipelineCreateInfo::builder()
+            .stages(&stages)
+            .vertex_input_state(&vertex_input_state)
+            .input_assembly_state(&input_assembly_state)
+            .viewport_state(&viewport_state)
+            .rasterization_state(&rasterization_state)
+            .multisample_state(&multisample_state)
+            .depth_stencil_state(&depth_stencil_state)
+            .color_blend_state(&color_blend_state)
+            .layout(pipeline_layout)
+            .render_pass(render_pass)
+            .subpass(0);
+
+        let pipelines = unsafe {
+            device.create_graphics_pipelines(
+                vk::PipelineCache::null(),
+                slice_from_ref(&create_info),
+                None,
+            )
+        }
+        .map_err(|(_, r)| r)?;
+
+        unsafe {
+            device.destroy_shader_module(vertex_shader, None);
+            device.destroy_shader_module(fragment_shader, None);
+        }
+
+        let vertex_buffers = [(); PIPELINE_DEPTH].map(|_| unsafe {
+            Buffer::new(
+                vulkan_context,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+                MAX_GLYPHS * 6,
+            )
+        });
+
+        Ok(Self {
+            pipeline_layout,
+            pipeline: pipelines[0],
+            vertex_buffers,
+            vertices: Vec::new(),
+            batches: Vec::new(),
+        })
+    }
+
+    /// Lay out `text` and queue it to be drawn this frame.
+    pub(crate) fn queue(&mut self, text: &Text, gos_from_local: &Affine3A) {
+        let first_vertex = self.vertices.len() as u32;
+        layout_text(text, gos_from_local, &mut self.vertices);
+        let vertex_count = self.vertices.len() as u32 - first_vertex;
+        if vertex_count == 0 {
+            return;
+        }
+
+        // Consecutive text with the same font can be drawn together.
+        match self.batches.last_mut() {
+            Some(batch) if batch.texture_id == text.font.texture_id => {
+                batch.vertex_count += vertex_count
+            }
+            _ => self.batches.push(TextBatch {
+                texture_id: text.font.texture_id,
+                first_vertex,
+                vertex_count,
+            }),
+        }
+    }
+
+    /// Remove all the text queued this frame
+    pub(crate) fn clear(&mut self) {
+        self.vertices.clear();
+        self.batches.clear();
+    }
+
+    /// Draw all the text queued this frame.
+    ///
+    /// # Safety
+    ///
+    /// `command_buffer` must be inside the PBR render pass.
+    pub(crate) unsafe fn draw(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        frame_index: usize,
+        descriptor_set: vk::DescriptorSet,
+    ) {
+        if self.vertices.is_empty() {
+            return;
+        }
+
+        if self.vertices.len() > MAX_GLYPHS * 6 {
+            println!("[HOTHAM_TEXT] WARNING: More than {MAX_GLYPHS} glyphs were drawn this frame, some will be missing!");
+        }
+
+        let vertex_count = self.vertices.len().min(MAX_GLYPHS * 6);
+        let vertex_buffer = &mut self.vertex_buffers[frame_index];
+        vertex_buffer.overwrite(&self.vertices[..vertex_count]);
+
+        device.cmd_bind_pipeline(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline,
+        );
+        device.cmd_bind_descriptor_sets(
+            command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            self.pipeline_layout,
+            0,
+            slice_from_ref(&descriptor_set),
+            &[],
+        );
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[vertex_buffer.buffer], &[0]);
+
+        for batch in &self.batches {
+            let first_vertex = batch.first_vertex.min(vertex_count as u32);
+            let vertex_count = batch.vertex_count.min(vertex_count as u32 - first_vertex);
+            if vertex_count == 0 {
+                break;
+            }
+
+            device.cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                create_push_constant(&batch.texture_id),
+            );
+            device.cmd_draw(command_buffer, vertex_count, 1, first_vertex, 0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_font() -> SdfFont {
+        let glyph = Glyph {
+            uv_min: Vec2::ZERO,
+            uv_max: Vec2::ONE,
+            offset: Vec2::ZERO,
+            size: Vec2::ONE,
+            advance: 1.,
+        };
+        SdfFont::from_glyphs(0, 2., [('a', glyph), ('b', glyph)].into_iter().collect())
+    }
+
+    #[test]
+    pub fn test_layout_text() {
+        let mut text = Text::new("ab\nb", test_font());
+        text.size = 1.;
+        let mut vertices = Vec::new();
+
+        layout_text(&text, &Affine3A::IDENTITY, &mut vertices);
+        assert_eq!(vertices.len(), 3 * 6);
+
+        // The second glyph starts where the first one ends
+        assert_eq!(vertices[6].position, Vec3::new(1., 0., 0.));
+        // The second line is below the first
+        assert_eq!(vertices[12].position, Vec3::new(0., -2., 0.));
+        // The top of the quad samples the top of the atlas
+        assert_eq!(vertices[2].uv, Vec2::new(1., 0.));
+
+        // Centered text is scaled and transformed
+        text.alignment = TextAlignment::Center;
+        text.size = 0.5;
+        vertices.clear();
+        layout_text(&text, &Affine3A::from_translation(Vec3::Z), &mut vertices);
+        assert_eq!(vertices[0].position, Vec3::new(-0.5, 0., 1.));
+        assert_eq!(vertices[12].position, Vec3::new(-0.25, -1., 1.));
+
+        // Unknown characters are skipped
+        text.text = "a?".to_string();
+        vertices.clear();
+        layout_text(&text, &Affine3A::IDENTITY, &mut vertices);
+        assert_eq!(vertices.len(), 6);
+    }
+
+    #[test]
+    pub fn test_generate_sdf() {
+        // A single filled pixel
+        let sdf = generate_sdf(&[255], 1, 1);
+        let size = 1 + SDF_SPREAD_PX * 2;
+        assert_eq!(sdf.len(), size * size);
+
+        let centre = sdf[SDF_SPREAD_PX * size + SDF_SPREAD_PX];
+        let neighbour = sdf[SDF_SPREAD_PX * size + SDF_SPREAD_PX + 1];
+        let corner = sdf[0];
+        assert!(centre > 128);
+        assert!(neighbour < 128);
+        assert!(corner < neighbour);
+        assert_eq!(corner, 0);
+    }
+}
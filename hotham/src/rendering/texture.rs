@@ -3,11 +3,16 @@ use std::io::{Cursor, Read};
 use crate::{
     asset_importer::ImportContext,
     contexts::{RenderContext, VulkanContext},
-    rendering::image::Image,
+    rendering::{
+        color_space::texture_format_for_usage,
+        image::Image,
+        sampler::SamplerDesc,
+        transcode::{compress_rgba8, transcode_uastc_level, TranscodeTarget},
+    },
     COLOR_FORMAT,
 };
 use ash::vk;
-use image::io::Reader as ImageReader;
+use image::{io::Reader as ImageReader, RgbaImage};
 
 #[derive(Debug, Clone)]
 /// A texture that can be accessed in a fragment shader on the GPU
@@ -18,6 +23,8 @@ pub struct Texture {
     pub index: u32,
     /// How the texture will be used
     pub texture_usage: TextureUsage,
+    /// How the texture is filtered and wrapped. Change it with [`Texture::set_sampler`].
+    pub sampler_desc: SamplerDesc,
 }
 
 /// Describes how this texture will be used by the fragment shader.
@@ -32,6 +39,8 @@ pub enum TextureUsage {
     Emission,
     /// The occlusion (red) roughness (green) and metalness (blue) of the material
     MetallicRoughnessOcclusion,
+    /// The transmission (red) and/or thickness (green) of the material
+    Transmission,
     /// Indicates this texture is used for Image Based Lighting (IBL)
     IBL,
     /// A non PBR texture
@@ -84,6 +93,7 @@ impl Texture {
             image,
             index,
             texture_usage,
+            sampler_desc: Default::default(),
         }
     }
 
@@ -93,9 +103,24 @@ impl Texture {
         texture_usage: TextureUsage,
         import_context: &mut ImportContext,
     ) -> u32 {
+        // Textures packed into the atlas have already been uploaded as part of it.
+        if let Some(region) = import_context.texture_atlas.regions.get(&texture.index()) {
+            return region.texture_id;
+        }
+
         let texture_name = &format!("Texture {}", texture.name().unwrap_or(""));
 
-        let texture = match texture.source().source() {
+        // Textures using KHR_texture_basisu point at a KTX2 image in the extension, with an optional fallback in
+        // `source` for viewers that don't support it.
+        let basisu_source = texture
+            .extension_value("KHR_texture_basisu")
+            .and_then(|extension| extension.get("source"))
+            .and_then(|source| source.as_u64())
+            .and_then(|index| import_context.document.images().nth(index as _));
+        let image = basisu_source.unwrap_or_else(|| texture.source());
+        let sampler_desc = SamplerDesc::from_gltf(&texture.sampler());
+
+        let mut texture = match image.source() {
             // HACK
             // This is a *hack*. Storing ktx2 images in the source field without the KHR_texture_basisu extension
             // is *not allowed*. But, such is life.
@@ -128,9 +153,44 @@ impl Texture {
             ),
         };
 
+        if sampler_desc != texture.sampler_desc {
+            texture.set_sampler(
+                import_context.vulkan_context,
+                import_context.render_context,
+                sampler_desc,
+            );
+        }
+
         texture.index
     }
 
+    /// Change how the texture is filtered and wrapped. Only supported for 2D textures: cube maps always use the
+    /// shared cube sampler.
+    pub fn set_sampler(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        sampler_desc: SamplerDesc,
+    ) {
+        if self.image.view_type != vk::ImageViewType::TYPE_2D {
+            println!("[HOTHAM_TEXTURE] - Samplers can only be changed on 2D textures, ignoring..");
+            return;
+        }
+
+        let sampler = render_context
+            .resources
+            .get_sampler(vulkan_context, &sampler_desc);
+        unsafe {
+            render_context.descriptors.write_texture_descriptor(
+                vulkan_context,
+                self.image.view,
+                sampler,
+                self.index,
+            );
+        }
+        self.sampler_desc = sampler_desc;
+    }
+
     /// Create an empty texture. Useful for obtaining a texture you want to write to later on.
     pub fn empty(
         vulkan_context: &VulkanContext,
@@ -154,6 +214,7 @@ impl Texture {
             image,
             index,
             texture_usage: TextureUsage::Other,
+            sampler_desc: Default::default(),
         }
     }
 
@@ -171,12 +232,31 @@ impl Texture {
         texture_usage: TextureUsage,
     ) -> Self {
         println!("[HOTHAM_TEXTURE] Parsing KTX2 file {name}");
-        let ktx2_image = parse_ktx2(ktx2_data);
+        let ktx2_image =
+            parse_ktx2_with_target(ktx2_data, TranscodeTarget::for_device(vulkan_context));
         println!(
             "[HOTHAM_TEXTURE] KTX2 data: mip levels {}, array_layers: {}, faces: {}",
             ktx2_image.mip_levels, ktx2_image.array_layers, ktx2_image.faces
         );
 
+        Texture::from_ktx2_image(
+            name,
+            vulkan_context,
+            render_context,
+            ktx2_image,
+            texture_usage,
+        )
+    }
+
+    /// Create a texture from image data that has already been parsed or compressed.
+    pub fn from_ktx2_image(
+        name: &str,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        mut ktx2_image: KTX2Image,
+        texture_usage: TextureUsage,
+    ) -> Self {
+        ktx2_image.format = texture_format_for_usage(ktx2_image.format, &texture_usage);
         let component_mapping = get_component_mapping(&ktx2_image.format, &texture_usage);
 
         // Right. Now we've got to do the array/mip count dance.
@@ -207,6 +287,7 @@ impl Texture {
             image,
             index,
             texture_usage,
+            sampler_desc: Default::default(),
         }
     }
 
@@ -214,6 +295,9 @@ impl Texture {
     ///
     /// This is slow because we have to extract the image on the CPU before we can upload it to the GPU: hardly ideal. It is necessary
     /// when testing in the simulator because compressing images into desktop friendly formats like BCn would be overkill for testing.
+    ///
+    /// On Android the image is then compressed into ASTC, which is slower still but keeps uncompressed textures from blowing the
+    /// memory budget.
     pub fn from_uncompressed(
         name: &str,
         mime_type: &str,
@@ -225,24 +309,51 @@ impl Texture {
         #[cfg(target_os = "android")]
         println!("[HOTHAM_TEXTURE] - @@ WARNING: Non-optimal image format detected. For best performance, compress your images into ktx2 using Squisher: https://github.com/leetvr/squisher. @@");
 
-        println!("[HOTHAM_TEXTURE] - Decompressing image. This may take some time..");
-        let decompressed_format = get_format_from_mime_type(mime_type);
-        let asset = Cursor::new(data);
-        let mut image = ImageReader::new(asset);
-        image.set_format(decompressed_format);
-        let image = image.decode().expect("Unable to decompress image!");
-        let image = image.to_rgba8();
+        let image = decode_rgba8(mime_type, data);
+        Texture::from_rgba8(name, vulkan_context, render_context, image, texture_usage)
+    }
+
+    /// Create a texture from an image that has already been decompressed, eg. a
+    /// [texture atlas](crate::asset_importer::ImportSettings::texture_atlas). On Android the image is compressed into
+    /// ASTC first.
+    pub fn from_rgba8(
+        name: &str,
+        vulkan_context: &VulkanContext,
+        render_context: &mut RenderContext,
+        image: RgbaImage,
+        texture_usage: TextureUsage,
+    ) -> Self {
         let extent = vk::Extent2D {
             width: image.width(),
             height: image.height(),
         };
 
-        let format = match texture_usage {
-            TextureUsage::BaseColor | TextureUsage::Emission => vk::Format::R8G8B8A8_SRGB,
-            _ => vk::Format::R8G8B8A8_UNORM,
-        };
+        let format = texture_format_for_usage(vk::Format::R8G8B8A8_UNORM, &texture_usage);
 
-        println!("[HOTHAM_TEXTURE] ..done!");
+        if cfg!(target_os = "android") {
+            println!("[HOTHAM_TEXTURE] - Compressing image into ASTC..");
+            let mut image = image;
+            if let TextureUsage::Normal = texture_usage {
+                // Compressed normal maps aren't swizzled when sampled, so move X and Y into the channels the shader reads.
+                swizzle_normals(&mut image);
+            }
+            let compressed = compress_rgba8(
+                &image,
+                extent.width,
+                extent.height,
+                format == vk::Format::R8G8B8A8_SRGB,
+                TranscodeTarget::Astc4x4,
+            )
+            .expect("Unable to compress image!");
+            println!("[HOTHAM_TEXTURE] ..done!");
+            return Texture::from_ktx2_image(
+                name,
+                vulkan_context,
+                render_context,
+                compressed,
+                texture_usage,
+            );
+        }
 
         Texture::new(
             name,
@@ -277,8 +388,15 @@ pub struct KTX2Image {
     pub faces: u32,
 }
 
-/// Parse some ktx2 data
+/// Parse some ktx2 data. Basis Universal textures are transcoded into the default [`TranscodeTarget`] for this platform.
 pub fn parse_ktx2(ktx2_data: &[u8]) -> KTX2Image {
+    parse_ktx2_with_target(ktx2_data, TranscodeTarget::default())
+}
+
+/// Parse some ktx2 data, transcoding Basis Universal textures into `transcode_target`.
+///
+/// Only UASTC encoded Basis Universal textures are supported. ETC1S (BasisLZ) textures should be re-encoded as UASTC.
+pub fn parse_ktx2_with_target(ktx2_data: &[u8], transcode_target: TranscodeTarget) -> KTX2Image {
     let ktx2_reader = ktx2::Reader::new(ktx2_data).unwrap();
     let header = ktx2_reader.header();
     let extent = vk::Extent2D {
@@ -288,33 +406,66 @@ pub fn parse_ktx2(ktx2_data: &[u8]) -> KTX2Image {
     let mut image_buf = Vec::new();
     let mut offsets = Vec::new();
 
+    // Basis Universal textures have no Vulkan format; the data format descriptor tells us how they were encoded.
+    let basic_dfd = ktx2_reader
+        .data_format_descriptors()
+        .find(|dfd| dfd.header == ktx2::DataFormatDescriptorHeader::BASIC)
+        .and_then(|dfd| ktx2::BasicDataFormatDescriptor::parse(dfd.data).ok());
+    let is_uastc = header.format.is_none()
+        && basic_dfd.as_ref().and_then(|dfd| dfd.color_model) == Some(ktx2::ColorModel::UASTC);
+    let is_srgb = basic_dfd.as_ref().and_then(|dfd| dfd.transfer_function)
+        == Some(ktx2::TransferFunction::SRGB);
+
     println!(
         "[HOTHAM_TEXTURE] Importing KTX2 texture in {:?} format with {} levels.",
-        header.format,
+        if is_uastc { "UASTC" } else { "native" },
         ktx2_reader.header().level_count,
     );
-    for mipmap_level in ktx2_reader.levels() {
-        let len = match header.supercompression_scheme {
+    let slice_count = header.face_count * header.layer_count.max(1);
+    for (level, mipmap_level) in ktx2_reader.levels().enumerate() {
+        let level_data = match header.supercompression_scheme {
             // Lifted from Bevy, with Love:
             // https://github.com/bevyengine/bevy/blob/05e5008624b35f51cd6418acc745236be2cddd28/crates/bevy_render/src/texture/ktx2.rs#L62
             Some(ktx2::SupercompressionScheme::Zstandard) => {
                 let mut cursor = std::io::Cursor::new(mipmap_level.data);
                 let mut decoder = ruzstd::StreamingDecoder::new(&mut cursor).unwrap();
-                decoder.read_to_end(&mut image_buf).unwrap()
-            }
-            None => {
-                image_buf.extend(mipmap_level.data);
-                mipmap_level.data.len()
+                let mut level_data = Vec::new();
+                decoder.read_to_end(&mut level_data).unwrap();
+                level_data
             }
+            None => mipmap_level.data.to_vec(),
+            Some(ktx2::SupercompressionScheme::BasisLZ) => panic!(
+                "Unable to parse KTX2 file, ETC1S textures are not supported. Please encode your textures as UASTC."
+            ),
             s => panic!("Unable to parse KTX2 file, unsupported supercompression scheme: {s:?}",),
         };
 
-        let offset_increment = len as u32 / header.face_count;
+        let level_data = if is_uastc {
+            transcode_uastc_level(
+                &level_data,
+                (extent.width >> level).max(1),
+                (extent.height >> level).max(1),
+                slice_count,
+                transcode_target,
+            )
+            .unwrap()
+        } else {
+            level_data
+        };
+
+        let offset_increment = level_data.len() as u32 / header.face_count;
         offsets.push(offset_increment as _);
+        image_buf.extend(level_data);
     }
 
+    let format = if is_uastc {
+        transcode_target.vk_format(is_srgb)
+    } else {
+        get_format_from_ktx2(header.format)
+    };
+
     KTX2Image {
-        format: get_format_from_ktx2(header.format),
+        format,
         extent,
         image_buf,
         offsets,
@@ -324,6 +475,14 @@ pub fn parse_ktx2(ktx2_data: &[u8]) -> KTX2Image {
     }
 }
 
+/// Store the X and Y of an RGBA8 normal map in green and alpha, matching the layout of compressed normal maps.
+fn swizzle_normals(rgba: &mut [u8]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        let (x, y) = (pixel[0], pixel[1]);
+        pixel.copy_from_slice(&[0, x, 0, y]);
+    }
+}
+
 fn get_component_mapping(
     format: &vk::Format,
     texture_usage: &TextureUsage,
@@ -349,6 +508,18 @@ pub const DEFAULT_COMPONENT_MAPPING: vk::ComponentMapping = vk::ComponentMapping
     a: vk::ComponentSwizzle::IDENTITY,
 };
 
+/// Decompress a JPG or PNG image into RGBA. This is slow, see [`Texture::from_uncompressed`].
+pub(crate) fn decode_rgba8(mime_type: &str, data: &[u8]) -> RgbaImage {
+    println!("[HOTHAM_TEXTURE] - Decompressing image. This may take some time..");
+    let decompressed_format = get_format_from_mime_type(mime_type);
+    let asset = Cursor::new(data);
+    let mut image = ImageReader::new(asset);
+    image.set_format(decompressed_format);
+    let image = image.decode().expect("Unable to decompress image!");
+    println!("[HOTHAM_TEXTURE] ..done!");
+    image.to_rgba8()
+}
+
 fn get_format_from_mime_type(mime_type: &str) -> image::ImageFormat {
     match mime_type {
         "image/png" => image::ImageFormat::Png,
@@ -362,3 +533,15 @@ pub(crate) fn get_format_from_ktx2(format: Option<ktx2::Format>) -> vk::Format {
     let raw = format.expect("No format specified").0;
     vk::Format::from_raw(raw.get() as _)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_swizzle_normals() {
+        let mut rgba = [10, 20, 30, 40, 50, 60, 70, 80];
+        swizzle_normals(&mut rgba);
+        assert_eq!(rgba, [0, 10, 0, 20, 0, 50, 0, 60]);
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Result};
+use ash::vk;
+use basis_universal::{
+    BasisTextureFormat, ColorSpace, Compressor, CompressorParams, DecodeFlags,
+    LowLevelUastcTranscoder, SliceParametersUastc, TranscodeParameters, Transcoder,
+    TranscoderBlockFormat, TranscoderTextureFormat, UASTC_QUALITY_DEFAULT,
+};
+
+use crate::contexts::VulkanContext;
+
+use super::texture::KTX2Image;
+
+// Every format we transcode to uses 4x4 blocks of 16 bytes.
+const BLOCK_SIZE: u32 = 4;
+const BYTES_PER_BLOCK: u32 = 16;
+
+/// The GPU compressed format Basis Universal textures are transcoded into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscodeTarget {
+    /// ASTC 4x4, supported by mobile GPUs like the Quest's
+    Astc4x4,
+    /// BC7, supported by desktop GPUs
+    Bc7,
+}
+
+impl Default for TranscodeTarget {
+    fn default() -> Self {
+        if cfg!(target_os = "android") {
+            TranscodeTarget::Astc4x4
+        } else {
+            TranscodeTarget::Bc7
+        }
+    }
+}
+
+impl TranscodeTarget {
+    /// Pick a target the device can sample from, preferring ASTC.
+    pub fn for_device(vulkan_context: &VulkanContext) -> Self {
+        let features = unsafe {
+            vulkan_context
+                .instance
+                .get_physical_device_features(vulkan_context.physical_device)
+        };
+
+        if features.texture_compression_astc_ldr == vk::TRUE {
+            TranscodeTarget::Astc4x4
+        } else if features.texture_compression_bc == vk::TRUE {
+            TranscodeTarget::Bc7
+        } else {
+            Self::default()
+        }
+    }
+
+    /// The Vulkan format of the transcoded data
+    pub fn vk_format(&self, srgb: bool) -> vk::Format {
+        match (self, srgb) {
+            (TranscodeTarget::Astc4x4, true) => vk::Format::ASTC_4X4_SRGB_BLOCK,
+            (TranscodeTarget::Astc4x4, false) => vk::Format::ASTC_4X4_UNORM_BLOCK,
+            (TranscodeTarget::Bc7, true) => vk::Format::BC7_SRGB_BLOCK,
+            (TranscodeTarget::Bc7, false) => vk::Format::BC7_UNORM_BLOCK,
+        }
+    }
+
+    fn block_format(&self) -> TranscoderBlockFormat {
+        match self {
+            TranscodeTarget::Astc4x4 => TranscoderBlockFormat::ASTC_4x4,
+            TranscodeTarget::Bc7 => TranscoderBlockFormat::BC7,
+        }
+    }
+
+    fn texture_format(&self) -> TranscoderTextureFormat {
+        match self {
+            TranscodeTarget::Astc4x4 => TranscoderTextureFormat::ASTC_4x4_RGBA,
+            TranscodeTarget::Bc7 => TranscoderTextureFormat::BC7_RGBA,
+        }
+    }
+}
+
+/// Compress an RGBA8 image into `target`, generating a full mip chain.
+///
+/// The image is encoded as UASTC and then transcoded, which is much slower than loading pre-compressed textures
+/// but uses a quarter of the GPU memory of the uncompressed image.
+pub(crate) fn compress_rgba8(
+    rgba: &[u8],
+    width: u32,
+    height: u32,
+    srgb: bool,
+    target: TranscodeTarget,
+) -> Result<KTX2Image> {
+    let mut params = CompressorParams::new();
+    params.set_basis_format(BasisTextureFormat::UASTC4x4);
+    params.set_uastc_quality_level(UASTC_QUALITY_DEFAULT);
+    params.set_generate_mipmaps(true);
+    params.set_color_space(if srgb {
+        ColorSpace::Srgb
+    } else {
+        ColorSpace::Linear
+    });
+    params.source_image_mut(0).init(rgba, width, height, 4);
+
+    let thread_count = std::thread::available_parallelism()
+        .map(|n| n.get() as u32)
+        .unwrap_or(1);
+    let mut compressor = Compressor::new(thread_count);
+    unsafe {
+        if !compressor.init(&params) {
+            return Err(anyhow!("Unable to initialise the texture compressor"));
+        }
+        compressor
+            .process()
+            .map_err(|e| anyhow!("Unable to compress texture: {e:?}"))?;
+    }
+    let basis_file = compressor.basis_file();
+
+    let mut transcoder = Transcoder::new();
+    let mip_levels = transcoder.image_level_count(basis_file, 0);
+    transcoder
+        .prepare_transcoding(basis_file)
+        .map_err(|_| anyhow!("Unable to prepare texture for transcoding"))?;
+
+    let mut image_buf = Vec::new();
+    let mut offsets = Vec::new();
+    for level_index in 0..mip_levels {
+        let level_data = transcoder
+            .transcode_image_level(
+                basis_file,
+                target.texture_format(),
+                TranscodeParameters {
+                    image_index: 0,
+                    level_index,
+                    ..Default::default()
+                },
+            )
+            .map_err(|e| anyhow!("Unable to transcode texture: {e:?}"))?;
+        offsets.push(level_data.len() as _);
+        image_buf.extend(level_data);
+    }
+    transcoder.end_transcoding();
+
+    Ok(KTX2Image {
+        format: target.vk_format(srgb),
+        extent: vk::Extent2D { width, height },
+        image_buf,
+        offsets,
+        mip_levels,
+        array_layers: 1,
+        faces: 1,
+    })
+}
+
+/// Transcode one mip level of a UASTC texture, containing `slice_count` faces or layers stored one after another.
+pub(crate) fn transcode_uastc_level(
+    level_data: &[u8],
+    width: u32,
+    height: u32,
+    slice_count: u32,
+    target: TranscodeTarget,
+) -> Result<Vec<u8>> {
+    let (blocks_x, blocks_y) = block_count(width, height);
+    let slice_len = (blocks_x * blocks_y * BYTES_PER_BLOCK) as usize;
+    if level_data.len() < slice_len * slice_count as usize {
+        return Err(anyhow!(
+            "UASTC level has {} bytes, expected {}",
+            level_data.len(),
+            slice_len * slice_count as usize
+        ));
+    }
+
+    let transcoder = LowLevelUastcTranscoder::new();
+    let mut transcoded = Vec::with_capacity(slice_len * slice_count as usize);
+    for slice in level_data.chunks_exact(slice_len).take(slice_count as _) {
+        let slice = transcoder
+            .transcode_slice(
+                slice,
+                SliceParametersUastc {
+                    num_blocks_x: blocks_x,
+                    num_blocks_y: blocks_y,
+                    has_alpha: true,
+                    original_width: width,
+                    original_height: height,
+                },
+                DecodeFlags::HIGH_QUALITY,
+                target.block_format(),
+            )
+            .map_err(|e| anyhow!("Unable to transcode UASTC texture: {e:?}"))?;
+        transcoded.extend(slice);
+    }
+
+    Ok(transcoded)
+}
+
+/// The number of 4x4 blocks needed to cover an image
+fn block_count(width: u32, height: u32) -> (u32, u32) {
+    (
+        ((width + BLOCK_SIZE - 1) / BLOCK_SIZE).max(1),
+        ((height + BLOCK_SIZE - 1) / BLOCK_SIZE).max(1),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_block_count() {
+        assert_eq!(block_count(1024, 512), (256, 128));
+        assert_eq!(block_count(5, 3), (2, 1));
+        assert_eq!(block_count(1, 1), (1, 1));
+    }
+
+    #[test]
+    pub fn test_vk_format() {
+        assert_eq!(
+            TranscodeTarget::Astc4x4.vk_format(true),
+            vk::Format::ASTC_4X4_SRGB_BLOCK
+        );
+        assert_eq!(
+            TranscodeTarget::Bc7.vk_format(false),
+            vk::Format::BC7_UNORM_BLOCK
+        );
+    }
+
+    #[test]
+    pub fn test_transcode_rejects_short_levels() {
+        // A 16x16 level needs 16 blocks
+        let level_data = vec![0; 15 * BYTES_PER_BLOCK as usize];
+        assert!(transcode_uastc_level(&level_data, 16, 16, 1, TranscodeTarget::Bc7).is_err());
+    }
+}
@@ -13,6 +13,8 @@ pub struct Vertex {
     pub normal: Vec3,
     /// First set of texture coordinates
     pub texture_coords: Vec2,
+    /// Second set of texture coordinates, used by textures with a `texCoord` of 1
+    pub texture_coords_1: Vec2,
     /// Joint indices (for skinning), one byte per index.
     pub joint_indices: u32,
     /// Joint weights (for skinning), one byte per weight.
@@ -21,11 +23,18 @@ pub struct Vertex {
 
 impl Vertex {
     /// Create a new vertex
-    pub fn new(normal: Vec3, texture_coords: Vec2, joint_indices: u32, joint_weights: u32) -> Self {
+    pub fn new(
+        normal: Vec3,
+        texture_coords: Vec2,
+        texture_coords_1: Vec2,
+        joint_indices: u32,
+        joint_weights: u32,
+    ) -> Self {
         Self {
             // position,
             normal,
             texture_coords,
+            texture_coords_1,
             joint_indices,
             joint_weights,
         }
@@ -34,23 +43,24 @@ impl Vertex {
     /// Create a new vertex from a zip - useful when importing from glTF
     // Clippy warning suppressed for adjudication separately
     #[cfg_attr(feature = "cargo-clippy", allow(clippy::type_complexity))]
-    pub fn from_zip(t: (Vec3, Vec2, [u8; 4], Vec4)) -> Self {
+    pub fn from_zip(t: (Vec3, Vec2, Vec2, [u8; 4], Vec4)) -> Self {
         // Normalize weights to 0 <= w <= 255 while avoiding division with zero.
-        let max_weight = t.3.max_element().max(f32::EPSILON);
+        let max_weight = t.4.max_element().max(f32::EPSILON);
         let weight_normalization = 255.0 / max_weight;
         Vertex::new(
             t.0,
             t.1,
+            t.2,
             // Pack indices into one u32 with one byte per index.
-            (t.2[0] as u32)
-                + (t.2[1] as u32) * 256
-                + (t.2[2] as u32) * 256 * 256
-                + (t.2[3] as u32) * 256 * 256 * 256,
+            (t.3[0] as u32)
+                + (t.3[1] as u32) * 256
+                + (t.3[2] as u32) * 256 * 256
+                + (t.3[3] as u32) * 256 * 256 * 256,
             // Pack weights into one u32 with one byte per weight.
-            ((t.3[0] * weight_normalization).round() as u32)
-                + ((t.3[1] * weight_normalization).round() as u32) * 256
-                + ((t.3[2] * weight_normalization).round() as u32) * 256 * 256
-                + ((t.3[3] * weight_normalization).round() as u32) * 256 * 256 * 256,
+            ((t.4[0] * weight_normalization).round() as u32)
+                + ((t.4[1] * weight_normalization).round() as u32) * 256
+                + ((t.4[2] * weight_normalization).round() as u32) * 256 * 256
+                + ((t.4[3] * weight_normalization).round() as u32) * 256 * 256 * 256,
         )
     }
 }
@@ -93,12 +103,20 @@ impl Vertex {
             .offset(memoffset::offset_of!(Vertex, joint_weights) as _)
             .build();
 
+        let texture_coords_1 = vk::VertexInputAttributeDescription::builder()
+            .binding(1)
+            .location(5)
+            .format(vk::Format::R32G32_SFLOAT)
+            .offset(memoffset::offset_of!(Vertex, texture_coords_1) as _)
+            .build();
+
         vec![
             position,
             normal,
             texture_coords,
             joint_indices,
             joint_weights,
+            texture_coords_1,
         ]
     }
 }
@@ -0,0 +1,583 @@
+use std::{
+    path::PathBuf,
+    slice::from_ref as slice_from_ref,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use ash::vk;
+
+use crate::contexts::{render_context::PIPELINE_DEPTH, VulkanContext};
+
+use super::buffer::Buffer;
+
+#[cfg(target_os = "android")]
+pub use android::MediaCodecEncoder;
+
+/// Receives the frames captured by a [`VideoRecorder`].
+pub trait FrameEncoder {
+    /// Encode a frame. `rgba` holds the left eye's image, tightly packed with four bytes per pixel, in the
+    /// swapchain's color space. `timestamp` is the time since the recording started.
+    fn encode_frame(
+        &mut self,
+        rgba: &[u8],
+        extent: vk::Extent2D,
+        timestamp: Duration,
+    ) -> Result<()>;
+
+    /// Flush any buffered frames and close the output. Called once, when the recording stops.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Records the frames rendered into the swapchain.
+///
+/// Once a frame has been rendered, the left eye is copied into a host visible buffer, one for each frame in
+/// flight. The buffer is handed to the [`FrameEncoder`] the next time its frame begins, after the renderer has
+/// already waited for the GPU to finish with it, so recording never stalls the GPU. Encoding does happen on the
+/// render thread though, so slow encoders will lower the frame rate.
+pub struct VideoRecorder {
+    buffers: Vec<Buffer<u8>>,
+    /// When the frame waiting in each buffer was captured, if there is one
+    pending: [Option<Duration>; PIPELINE_DEPTH],
+    extent: vk::Extent2D,
+    started_at: Instant,
+    stopping: bool,
+    encoder: Box<dyn FrameEncoder>,
+}
+
+impl VideoRecorder {
+    /// Create a recorder for a swapchain of size `extent`
+    pub fn new(
+        vulkan_context: &VulkanContext,
+        extent: vk::Extent2D,
+        encoder: Box<dyn FrameEncoder>,
+    ) -> Self {
+        let buffers = (0..PIPELINE_DEPTH)
+            .map(|_| unsafe {
+                Buffer::new(
+                    vulkan_context,
+                    vk::BufferUsageFlags::TRANSFER_DST,
+                    (extent.width * extent.height * 4) as _,
+                )
+            })
+            .collect();
+
+        Self {
+            buffers,
+            pending: Default::default(),
+            extent,
+            started_at: Instant::now(),
+            stopping: false,
+            encoder,
+        }
+    }
+
+    /// Stop copying frames. The recording is finished once the frames in flight have been encoded.
+    pub(crate) fn stop(&mut self) {
+        self.stopping = true;
+    }
+
+    /// Whether the recording has been stopped, see [`VideoRecorder::stop`]
+    pub fn is_stopping(&self) -> bool {
+        self.stopping
+    }
+
+    /// Record a copy of the first layer of `image`, which must be in `COLOR_ATTACHMENT_OPTIMAL`, into the buffer
+    /// for `frame_index`. The image is left in `COLOR_ATTACHMENT_OPTIMAL`.
+    pub(crate) unsafe fn record_copy(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        frame_index: usize,
+    ) {
+        if self.stopping {
+            return;
+        }
+
+        let buffer = &self.buffers[frame_index];
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+        let to_transfer_src = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .dst_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(subresource_range)
+            .image(image);
+        let to_color_attachment = vk::ImageMemoryBarrier::builder()
+            .old_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+            .new_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .src_access_mask(vk::AccessFlags::TRANSFER_READ)
+            .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .subresource_range(subresource_range)
+            .image(image);
+        let to_host_read = vk::BufferMemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(buffer.buffer)
+            .offset(0)
+            .size(vk::WHOLE_SIZE);
+        let region = vk::BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_length: 0,
+            buffer_image_height: 0,
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level: 0,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_offset: vk::Offset3D::default(),
+            image_extent: vk::Extent3D {
+                width: self.extent.width,
+                height: self.extent.height,
+                depth: 1,
+            },
+        };
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            slice_from_ref(&to_transfer_src),
+        );
+        device.cmd_copy_image_to_buffer(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            buffer.buffer,
+            slice_from_ref(&region),
+        );
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            slice_from_ref(&to_color_attachment),
+        );
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::HOST,
+            vk::DependencyFlags::empty(),
+            &[],
+            slice_from_ref(&to_host_read),
+            &[],
+        );
+
+        self.pending[frame_index] = Some(self.started_at.elapsed());
+    }
+
+    /// Encode the frame copied for `frame_index`, if there is one. The GPU must have finished with the frame.
+    pub(crate) fn encode_completed(
+        &mut self,
+        vulkan_context: &VulkanContext,
+        frame_index: usize,
+    ) -> Result<()> {
+        let timestamp = match self.pending[frame_index].take() {
+            Some(timestamp) => timestamp,
+            None => return Ok(()),
+        };
+        let buffer = &self.buffers[frame_index];
+
+        // The buffer isn't necessarily host coherent.
+        let range = vk::MappedMemoryRange::builder()
+            .memory(buffer.device_memory)
+            .offset(0)
+            .size(vk::WHOLE_SIZE);
+        unsafe {
+            vulkan_context
+                .device
+                .invalidate_mapped_memory_ranges(slice_from_ref(&range))?;
+        }
+
+        let rgba =
+            unsafe { std::slice::from_raw_parts(buffer.memory_address.as_ptr(), buffer.max_len) };
+        self.encoder.encode_frame(rgba, self.extent, timestamp)
+    }
+
+    /// Encode any frames still waiting in the buffers, finish the encoder and destroy the buffers.
+    /// The GPU must have finished every frame.
+    pub(crate) fn finish(mut self, vulkan_context: &VulkanContext) -> Result<()> {
+        // Encode the remaining frames in the order they were captured.
+        let mut frame_indices: Vec<usize> = (0..PIPELINE_DEPTH)
+            .filter(|i| self.pending[*i].is_some())
+            .collect();
+        frame_indices.sort_by_key(|i| self.pending[*i]);
+        let result = frame_indices
+            .into_iter()
+            .try_for_each(|i| self.encode_completed(vulkan_context, i))
+            .and_then(|_| self.encoder.finish());
+
+        for buffer in &mut self.buffers {
+            unsafe { buffer.destroy(&vulkan_context.device) };
+        }
+
+        result
+    }
+}
+
+/// Writes each frame to a numbered PNG file in a directory.
+///
+/// Useful on desktop, where the frames can be turned into a video afterwards, eg.
+/// `ffmpeg -framerate 90 -i frame_%05d.png recording.mp4`. Writing PNGs is slow, so expect the frame rate to drop.
+pub struct PngSequenceEncoder {
+    directory: PathBuf,
+    frame_count: u32,
+}
+
+impl PngSequenceEncoder {
+    /// Write frames into `directory`, creating it if it doesn't exist.
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self {
+            directory,
+            frame_count: 0,
+        })
+    }
+}
+
+impl FrameEncoder for PngSequenceEncoder {
+    fn encode_frame(
+        &mut self,
+        rgba: &[u8],
+        extent: vk::Extent2D,
+        _timestamp: Duration,
+    ) -> Result<()> {
+        let path = self
+            .directory
+            .join(format!("frame_{:05}.png", self.frame_count));
+        image::save_buffer(
+            path,
+            rgba,
+            extent.width,
+            extent.height,
+            image::ColorType::Rgba8,
+        )?;
+        self.frame_count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        println!(
+            "[HOTHAM_VIDEO] Wrote {} frames to {:?}",
+            self.frame_count, self.directory
+        );
+        Ok(())
+    }
+}
+
+/// Convert an RGBA image to NV12: a full resolution plane of luma followed by a half resolution plane of
+/// interleaved chroma, using the BT.601 limited range coefficients that hardware encoders expect.
+/// `width` and `height` must be even.
+pub fn rgba_to_nv12(rgba: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let mut nv12 = vec![0; width * height * 3 / 2];
+    let (luma, chroma) = nv12.split_at_mut(width * height);
+
+    let pixel = |x: usize, y: usize| {
+        let i = (y * width + x) * 4;
+        (rgba[i] as i32, rgba[i + 1] as i32, rgba[i + 2] as i32)
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            let (r, g, b) = pixel(x, y);
+            luma[y * width + x] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+        }
+    }
+
+    // Each chroma sample covers a 2x2 block of pixels.
+    for y in 0..height / 2 {
+        for x in 0..width / 2 {
+            let (mut r, mut g, mut b) = (0, 0, 0);
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let p = pixel(x * 2 + dx, y * 2 + dy);
+                r += p.0;
+                g += p.1;
+                b += p.2;
+            }
+            let (r, g, b) = (r / 4, g / 4, b / 4);
+            let i = (y * width / 2 + x) * 2;
+            chroma[i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+            chroma[i + 1] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+        }
+    }
+
+    nv12
+}
+
+#[cfg(target_os = "android")]
+mod android {
+    use std::{fs::File, os::unix::io::AsRawFd, path::Path, ptr, time::Duration};
+
+    use anyhow::{anyhow, Result};
+    use ash::vk;
+    use ndk_sys as ffi;
+
+    use super::{rgba_to_nv12, FrameEncoder};
+
+    const MIME_TYPE: &[u8] = b"video/avc\0";
+    // MediaCodecInfo.CodecCapabilities.COLOR_FormatYUV420SemiPlanar, ie. NV12
+    const COLOR_FORMAT_YUV420_SEMI_PLANAR: i32 = 21;
+    const CONFIGURE_FLAG_ENCODE: u32 = 1;
+    const BUFFER_FLAG_CODEC_CONFIG: u32 = 2;
+    const BUFFER_FLAG_END_OF_STREAM: u32 = 4;
+    const INFO_TRY_AGAIN_LATER: isize = -1;
+    const INFO_OUTPUT_FORMAT_CHANGED: isize = -2;
+    const OUTPUT_FORMAT_MPEG_4: u32 = 0;
+    const TIMEOUT_US: i64 = 10_000;
+
+    /// Encodes frames into an H.264 MP4 file, using the hardware encoder through Android's MediaCodec.
+    ///
+    /// The file must be somewhere the app can write to, such as
+    /// `/sdcard/Android/data/<package name>/files/recording.mp4`.
+    pub struct MediaCodecEncoder {
+        codec: *mut ffi::AMediaCodec,
+        muxer: *mut ffi::AMediaMuxer,
+        /// Only known once the encoder has produced its output format
+        track_index: Option<usize>,
+        finished: bool,
+        // The muxer writes through this file's descriptor, so it must outlive the muxer.
+        _file: File,
+    }
+
+    impl MediaCodecEncoder {
+        /// Create an encoder that writes to `path`. `extent` must match the swapchain, `frame_rate` is the display's
+        /// refresh rate and `bit_rate` is in bits per second, eg. `20_000_000`.
+        pub fn new(
+            path: impl AsRef<Path>,
+            extent: vk::Extent2D,
+            frame_rate: i32,
+            bit_rate: i32,
+        ) -> Result<Self> {
+            let file = File::create(path)?;
+            unsafe {
+                let format = ffi::AMediaFormat_new();
+                ffi::AMediaFormat_setString(format, c_str(b"mime\0"), c_str(MIME_TYPE));
+                ffi::AMediaFormat_setInt32(format, c_str(b"width\0"), extent.width as _);
+                ffi::AMediaFormat_setInt32(format, c_str(b"height\0"), extent.height as _);
+                ffi::AMediaFormat_setInt32(
+                    format,
+                    c_str(b"color-format\0"),
+                    COLOR_FORMAT_YUV420_SEMI_PLANAR,
+                );
+                ffi::AMediaFormat_setInt32(format, c_str(b"bitrate\0"), bit_rate);
+                ffi::AMediaFormat_setInt32(format, c_str(b"frame-rate\0"), frame_rate);
+                ffi::AMediaFormat_setInt32(format, c_str(b"i-frame-interval\0"), 1);
+
+                let codec = ffi::AMediaCodec_createEncoderByType(c_str(MIME_TYPE));
+                if codec.is_null() {
+                    ffi::AMediaFormat_delete(format);
+                    return Err(anyhow!("Unable to create an H.264 encoder"));
+                }
+                let status = ffi::AMediaCodec_configure(
+                    codec,
+                    format,
+                    ptr::null_mut(),
+                    ptr::null_mut(),
+                    CONFIGURE_FLAG_ENCODE,
+                );
+                ffi::AMediaFormat_delete(format);
+                check(status, "configure the encoder")
+                    .and_then(|_| check(ffi::AMediaCodec_start(codec), "start the encoder"))
+                    .map_err(|e| {
+                        ffi::AMediaCodec_delete(codec);
+                        e
+                    })?;
+
+                let muxer = ffi::AMediaMuxer_new(file.as_raw_fd(), OUTPUT_FORMAT_MPEG_4 as _);
+                if muxer.is_null() {
+                    ffi::AMediaCodec_stop(codec);
+                    ffi::AMediaCodec_delete(codec);
+                    return Err(anyhow!("Unable to create an MP4 muxer"));
+                }
+
+                Ok(Self {
+                    codec,
+                    muxer,
+                    track_index: None,
+                    finished: false,
+                    _file: file,
+                })
+            }
+        }
+
+        /// Write everything the encoder has produced to the muxer. If `end_of_stream` is set, blocks until the
+        /// encoder signals the end of the stream.
+        unsafe fn drain(&mut self, end_of_stream: bool) -> Result<()> {
+            loop {
+                let mut info: ffi::AMediaCodecBufferInfo = std::mem::zeroed();
+                let timeout = if end_of_stream { TIMEOUT_US } else { 0 };
+                let index = ffi::AMediaCodec_dequeueOutputBuffer(self.codec, &mut info, timeout);
+
+                if index == INFO_OUTPUT_FORMAT_CHANGED {
+                    let format = ffi::AMediaCodec_getOutputFormat(self.codec);
+                    let track_index = ffi::AMediaMuxer_addTrack(self.muxer, format);
+                    ffi::AMediaFormat_delete(format);
+                    if track_index < 0 {
+                        return Err(anyhow!("Unable to add the video track to the muxer"));
+                    }
+                    check(ffi::AMediaMuxer_start(self.muxer), "start the muxer")?;
+                    self.track_index = Some(track_index as _);
+                    continue;
+                }
+                if index == INFO_TRY_AGAIN_LATER && end_of_stream {
+                    continue;
+                }
+                if index < 0 {
+                    return Ok(());
+                }
+
+                let flags = info.flags as u32;
+                if flags & BUFFER_FLAG_CODEC_CONFIG == 0 && info.size > 0 {
+                    if let Some(track_index) = self.track_index {
+                        let mut size = 0;
+                        let data =
+                            ffi::AMediaCodec_getOutputBuffer(self.codec, index as _, &mut size);
+                        ffi::AMediaMuxer_writeSampleData(self.muxer, track_index as _, data, &info);
+                    }
+                }
+                ffi::AMediaCodec_releaseOutputBuffer(self.codec, index as _, false);
+
+                if flags & BUFFER_FLAG_END_OF_STREAM != 0 {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    impl FrameEncoder for MediaCodecEncoder {
+        fn encode_frame(
+            &mut self,
+            rgba: &[u8],
+            extent: vk::Extent2D,
+            timestamp: Duration,
+        ) -> Result<()> {
+            let nv12 = rgba_to_nv12(rgba, extent.width, extent.height);
+            unsafe {
+                let index = ffi::AMediaCodec_dequeueInputBuffer(self.codec, TIMEOUT_US);
+                if index < 0 {
+                    println!("[HOTHAM_VIDEO] Encoder is busy, dropping frame..");
+                } else {
+                    let mut capacity = 0;
+                    let input =
+                        ffi::AMediaCodec_getInputBuffer(self.codec, index as _, &mut capacity);
+                    let len = nv12.len().min(capacity as _);
+                    ptr::copy_nonoverlapping(nv12.as_ptr(), input, len);
+                    check(
+                        ffi::AMediaCodec_queueInputBuffer(
+                            self.codec,
+                            index as _,
+                            0,
+                            len as _,
+                            timestamp.as_micros() as _,
+                            0,
+                        ),
+                        "queue a frame",
+                    )?;
+                }
+                self.drain(false)
+            }
+        }
+
+        fn finish(&mut self) -> Result<()> {
+            if self.finished {
+                return Ok(());
+            }
+            self.finished = true;
+
+            unsafe {
+                let index = ffi::AMediaCodec_dequeueInputBuffer(self.codec, -1);
+                if index < 0 {
+                    return Err(anyhow!("Unable to signal the end of the stream"));
+                }
+                check(
+                    ffi::AMediaCodec_queueInputBuffer(
+                        self.codec,
+                        index as _,
+                        0,
+                        0,
+                        0,
+                        BUFFER_FLAG_END_OF_STREAM,
+                    ),
+                    "signal the end of the stream",
+                )?;
+                self.drain(true)?;
+                if self.track_index.is_some() {
+                    check(ffi::AMediaMuxer_stop(self.muxer), "stop the muxer")?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Drop for MediaCodecEncoder {
+        fn drop(&mut self) {
+            if let Err(e) = self.finish() {
+                println!("[HOTHAM_VIDEO] ERROR: Unable to finish recording: {e:?}");
+            }
+            unsafe {
+                ffi::AMediaMuxer_delete(self.muxer);
+                ffi::AMediaCodec_stop(self.codec);
+                ffi::AMediaCodec_delete(self.codec);
+            }
+        }
+    }
+
+    fn c_str(bytes: &[u8]) -> *const std::os::raw::c_char {
+        bytes.as_ptr() as _
+    }
+
+    fn check(status: ffi::media_status_t, action: &str) -> Result<()> {
+        if status as i32 == 0 {
+            Ok(())
+        } else {
+            Err(anyhow!("Unable to {action}: {}", status as i32))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_rgba_to_nv12() {
+        // Top half white, bottom half red
+        let mut rgba = Vec::new();
+        for _ in 0..8 {
+            rgba.extend([255, 255, 255, 255]);
+        }
+        for _ in 0..8 {
+            rgba.extend([255, 0, 0, 255]);
+        }
+
+        let nv12 = rgba_to_nv12(&rgba, 4, 4);
+        assert_eq!(nv12.len(), 16 + 8);
+
+        let (luma, chroma) = nv12.split_at(16);
+        assert_eq!(luma[..8], [235; 8]);
+        assert_eq!(luma[8..], [82; 8]);
+        assert_eq!(chroma[..4], [128, 128, 128, 128]);
+        assert_eq!(chroma[4..], [90, 240, 90, 240]);
+    }
+}
@@ -0,0 +1,60 @@
+use glam::Vec4;
+
+/// A comfort vignette: the edges of the player's view fading to black, which reduces motion sickness during
+/// artificial locomotion by hiding the movement in their peripheral vision.
+///
+/// The vignette is applied in the PBR and skybox fragment shaders, after tonemapping. It isn't applied to render
+/// targets. `strength` is usually driven by [`crate::systems::locomotion_system`], but can be set at any time and
+/// takes effect from the next frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Vignette {
+    /// How dark the edges of the view are, from 0 (no vignette) to 1 (black)
+    pub strength: f32,
+    /// Where the vignette starts to darken, as a distance from the centre of each eye's view, where 1 is its edge
+    pub inner_radius: f32,
+    /// Where the vignette reaches its full `strength`, in the same units as `inner_radius`
+    pub outer_radius: f32,
+}
+
+impl Default for Vignette {
+    fn default() -> Self {
+        Self {
+            strength: 0.,
+            inner_radius: 0.4,
+            outer_radius: 0.9,
+        }
+    }
+}
+
+impl Vignette {
+    /// Parameters passed to the shaders in `SceneData`.
+    /// Returns (strength, inner radius, outer radius, unused)
+    pub(crate) fn params(&self) -> Vec4 {
+        let strength = self.strength.clamp(0., 1.);
+        if strength == 0. {
+            return Vec4::ZERO;
+        }
+
+        let inner_radius = self.inner_radius.max(0.);
+        let outer_radius = self.outer_radius.max(inner_radius + 0.001);
+        [strength, inner_radius, outer_radius, 0.].into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_vignette_params() {
+        // No strength sends nothing at all
+        assert_eq!(Vignette::default().params(), Vec4::ZERO);
+
+        let vignette = Vignette {
+            strength: 2.,
+            inner_radius: 0.5,
+            outer_radius: 0.25,
+        };
+        assert_eq!(vignette.params(), [1., 0.5, 0.501, 0.].into());
+    }
+}
@@ -0,0 +1,31 @@
+// Renders the emissive parts of the scene into the bloom image. Everything else is rendered black
+// so that objects in front of emissive surfaces block their glow.
+#version 460
+
+#extension GL_GOOGLE_include_directive : require
+#extension GL_EXT_shader_explicit_arithmetic_types_int16 : require
+#extension GL_EXT_shader_explicit_arithmetic_types_float16 : require
+#extension GL_EXT_shader_16bit_storage : require
+
+#include "common.glsl"
+#include "lights.glsl"
+#include "brdf.glsl"
+#include "pbr.glsl"
+
+// Inputs
+layout (location = 0) in vec3 inGosPos;
+layout (location = 1) in vec2 inUV;
+layout (location = 2) in vec3 inNormal;
+layout (location = 3) in vec2 inUV1;
+
+// Outputs
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    // Unpack the material parameters
+    unpackMaterial();
+    uv = inUV;
+    uv1 = inUV1;
+
+    outColor = vec4(vec3(getEmission()), 1.0);
+}
@@ -0,0 +1,20 @@
+#version 460
+
+#extension GL_EXT_multiview : enable
+
+layout (set = 0, binding = 0) uniform sampler2DArray bloomImage;
+
+layout (push_constant) uniform constants {
+    float intensity;
+} params;
+
+// Inputs
+layout (location = 0) in vec2 inUV;
+
+// Outputs
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    // Blended additively on top of the tonemapped scene.
+    outColor = vec4(texture(bloomImage, vec3(inUV, float(gl_ViewIndex))).rgb * params.intensity, 0.0);
+}
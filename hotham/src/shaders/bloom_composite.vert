@@ -0,0 +1,10 @@
+#version 460
+
+// Outputs
+layout (location = 0) out vec2 outUV;
+
+void main() {
+    // A single triangle that covers the whole viewport.
+    outUV = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    gl_Position = vec4(outUV * 2.0 - 1.0, 0.0, 1.0);
+}
@@ -28,6 +28,8 @@ struct Light {
 
     float lightAngleOffset;
     uint type;
+
+    uint shadowIndex;
 };
 
 const uint LightType_Directional = 0;
@@ -39,4 +41,15 @@ layout (set = 0, binding = 2) readonly uniform SceneData {
     vec4 cameraPosition[2];
     vec4 params;
     Light lights[4];
+    mat4 shadowFromGos;
+    vec4 shadowParams;
+    mat4 localShadowFromGos[12];
+    vec4 decalParams;
+    vec4 colorGradingParams;
+    vec4 fogParams;
+    vec4 heightFogParams;
+    mat4 clusterFromGos;
+    vec4 clusterTangents;
+    vec4 clusterParams;
+    vec4 vignetteParams;
 } sceneData;
@@ -0,0 +1,23 @@
+#version 460
+#extension GL_EXT_multiview : enable
+
+#include "common.glsl"
+
+layout (location = 0) in vec3 inPos;
+layout (location = 1) in vec4 inColor;
+
+layout (location = 0) out vec4 outColor;
+
+// Debug lines are added in global space
+layout (push_constant) uniform constants {
+    mat4 gosFromGlobal;
+} debug;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    outColor = inColor;
+    gl_Position = sceneData.viewProjection[gl_ViewIndex] * debug.gosFromGlobal * vec4(inPos, 1.0);
+}
@@ -0,0 +1,42 @@
+// Decals, projected onto opaque surfaces. Must be included after pbr.glsl.
+
+struct Decal {
+    mat4 decalFromGos;
+    uint flagsAndBaseTextureID;
+    uint packedBaseColor;
+};
+
+layout (std430, set = 0, binding = 9) readonly buffer DecalsBuffer {
+    Decal decals[];
+} decalsBuffer;
+
+// Blend every decal whose box contains `gosPos` into the base color. The decal's texture covers the XY plane of its
+// box, and fades out towards the ends of the box along Z.
+f16vec3 applyDecals(f16vec3 baseColor, vec3 gosPos) {
+    uint decalCount = uint(sceneData.decalParams.x);
+
+    // Derivatives must be taken outside the loop, as not every fragment is inside the same decals.
+    vec3 dPdx = dFdx(gosPos);
+    vec3 dPdy = dFdy(gosPos);
+
+    for (uint i = 0; i < decalCount; i++) {
+        Decal decal = decalsBuffer.decals[i];
+        vec3 p = (decal.decalFromGos * vec4(gosPos, 1.0)).xyz;
+        if (any(greaterThan(abs(p), vec3(0.5)))) {
+            continue;
+        }
+
+        f16vec4 color = f16vec4(unpackUnorm4x8(decal.packedBaseColor));
+        if ((decal.flagsAndBaseTextureID & MATERIAL_FLAG_HAS_BASE_COLOR_TEXTURE) != 0) {
+            vec2 decalUV = vec2(p.x + 0.5, 0.5 - p.y);
+            vec2 dUVdx = (decal.decalFromGos * vec4(dPdx, 0.0)).xy * vec2(1.0, -1.0);
+            vec2 dUVdy = (decal.decalFromGos * vec4(dPdy, 0.0)).xy * vec2(1.0, -1.0);
+            color *= f16vec4(textureGrad(textures[decal.flagsAndBaseTextureID >> 16], decalUV, dUVdx, dUVdy));
+        }
+
+        float16_t fade = saturate(F16((0.5 - abs(p.z)) * 4.0));
+        baseColor = mix(baseColor, color.rgb, color.a * fade);
+    }
+
+    return baseColor;
+}
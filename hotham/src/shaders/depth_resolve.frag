@@ -0,0 +1,3 @@
+#version 460
+
+#include "depth_resolve.glsl"
@@ -0,0 +1,27 @@
+#extension GL_EXT_multiview : enable
+
+// Copies the depth buffer of the PBR render pass into the depth swapchain that is submitted to the compositor.
+// When multisampled, the nearest sample is kept. Remember that we're using inverse Z, so that's the largest one.
+
+#ifdef MULTISAMPLED
+layout (set = 0, binding = 0) uniform sampler2DMSArray source;
+#else
+layout (set = 0, binding = 0) uniform sampler2DArray source;
+#endif
+
+layout (location = 0) in vec2 inUV;
+
+void main() {
+    ivec3 coord = ivec3(gl_FragCoord.xy, gl_ViewIndex);
+
+#ifdef MULTISAMPLED
+    float depth = 0.0;
+    for (int i = 0; i < textureSamples(source); i++) {
+        depth = max(depth, texelFetch(source, coord, i).r);
+    }
+#else
+    float depth = texelFetch(source, coord, 0).r;
+#endif
+
+    gl_FragDepth = depth;
+}
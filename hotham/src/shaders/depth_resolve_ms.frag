@@ -0,0 +1,4 @@
+#version 460
+
+#define MULTISAMPLED
+#include "depth_resolve.glsl"
@@ -0,0 +1,30 @@
+// Distance and height fog. Must be included after common.glsl.
+
+// Blend the lit color of the fragment at `gosPos` towards the fog color, by how much fog lies between it and the
+// camera.
+f16vec3 applyFog(f16vec3 color, vec3 gosPos) {
+    float density = sceneData.fogParams.w;
+    float heightDensity = sceneData.heightFogParams.x;
+    if (density == 0.0 && heightDensity == 0.0) {
+        return color;
+    }
+
+    vec3 cameraPos = sceneData.cameraPosition[gl_ViewIndex].xyz;
+    vec3 ray = gosPos - cameraPos;
+    float rayLength = length(ray);
+
+    // Distance fog has the same density everywhere.
+    float opticalDepth = density * rayLength;
+
+    // Height fog falls off exponentially above its base height, so integrate its density along the ray.
+    if (heightDensity > 0.0) {
+        float falloff = max(sceneData.heightFogParams.y, 0.0001);
+        float baseHeight = sceneData.heightFogParams.z;
+        float rise = falloff * ray.y;
+        float integral = abs(rise) > 0.0001 ? (1.0 - exp(-rise)) / rise : 1.0;
+        opticalDepth += heightDensity * exp(-falloff * (cameraPos.y - baseHeight)) * integral * rayLength;
+    }
+
+    float fogAmount = 1.0 - exp(-opticalDepth);
+    return V16(mix(vec3(color), sceneData.fogParams.rgb, fogAmount));
+}
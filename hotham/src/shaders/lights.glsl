@@ -35,3 +35,46 @@ float16_t getLightAttenuation(Light light, vec3 pointToLight, vec3 l) {
 
     return rangeAttenuation * spotAttenuation;
 }
+
+// Point and spot lights that only light the clusters they touch. Each cluster has a range of light indices, which
+// point into the clustered lights.
+#define CLUSTER_COUNT_X 16
+#define CLUSTER_COUNT_Y 8
+#define CLUSTER_COUNT_Z 24
+
+struct LightCluster {
+    uint offset;
+    uint count;
+};
+
+layout (std430, set = 0, binding = 10) readonly buffer ClusteredLightsBuffer {
+    Light lights[];
+} clusteredLightsBuffer;
+
+layout (std430, set = 0, binding = 11) readonly buffer LightClustersBuffer {
+    LightCluster clusters[];
+} lightClustersBuffer;
+
+layout (std430, set = 0, binding = 12) readonly buffer LightIndicesBuffer {
+    uint indices[];
+} lightIndicesBuffer;
+
+// Find the cluster containing `gosPos`, or NOT_PRESENT if it's outside the clustered frustum, eg. when rendering
+// into a render target. Must match `light_clustering.rs`.
+uint getClusterIndex(vec3 gosPos) {
+    vec3 clusterPos = (sceneData.clusterFromGos * vec4(gosPos, 1.0)).xyz;
+    float depth = -clusterPos.z;
+    if (depth <= 0.0) {
+        return NOT_PRESENT;
+    }
+
+    vec4 tangents = sceneData.clusterTangents;
+    vec2 tile = (clusterPos.xy / depth - tangents.xz) / (tangents.yw - tangents.xz);
+    if (any(lessThan(tile, vec2(0.0))) || any(greaterThanEqual(tile, vec2(1.0)))) {
+        return NOT_PRESENT;
+    }
+
+    float slice = clamp(ceil(log(depth * sceneData.clusterParams.x) * sceneData.clusterParams.y), 0.0, float(CLUSTER_COUNT_Z - 1));
+    uvec2 xy = uvec2(tile * vec2(CLUSTER_COUNT_X, CLUSTER_COUNT_Y));
+    return (uint(slice) * CLUSTER_COUNT_Y + xy.y) * CLUSTER_COUNT_X + xy.x;
+}
@@ -0,0 +1,22 @@
+// Material, pushed for each draw. Must match `Material` in rendering/material.rs
+layout( push_constant ) uniform constants
+{
+    uint flagsAndBaseTextureID;
+    uint packedBaseColor;
+    uint packedMetallicRoughnessFactor;
+    uint packedEmissiveFactor;
+    uint packedMetallicRoughnessAndNormalTextureIDs;
+    uint packedEmissionTextureIDAndStrength;
+    uint packedTransmissionFactorAndAttenuationColor;
+    uint packedThicknessFactorAndAttenuationDistance;
+    uint packedTransmissionAndThicknessTextureIDs;
+    uint texCoordSets;
+    uvec2 packedUvMatrix;
+    vec2 uvOffset;
+} material;
+
+// Apply the material's UV transform to a set of texture coordinates.
+vec2 transformUV(vec2 uv) {
+    mat2 uvMatrix = mat2(unpackHalf2x16(material.packedUvMatrix.x), unpackHalf2x16(material.packedUvMatrix.y));
+    return uvMatrix * uv + material.uvOffset;
+}
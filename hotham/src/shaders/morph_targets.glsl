@@ -0,0 +1,40 @@
+// Morph targets (blend shapes). Must match `MorphTargetDelta` and `MAX_MORPH_TARGETS` in rendering/resources.rs
+#define MAX_MORPH_TARGETS 8
+
+struct MorphTargetDelta {
+    vec4 position;
+    vec4 normal;
+};
+
+// Shaders outside the graphics descriptor set can move the buffers by defining these first.
+#ifndef MORPH_TARGETS_BINDING
+#define MORPH_TARGETS_BINDING 7
+#endif
+#ifndef MORPH_WEIGHTS_BINDING
+#define MORPH_WEIGHTS_BINDING 8
+#endif
+
+// The deltas of each vertex are stored together, one per target.
+layout (std430, set = 0, binding = MORPH_TARGETS_BINDING) readonly buffer MorphTargetsBuffer {
+    MorphTargetDelta deltas[];
+} morphTargetsBuffer;
+
+layout (std430, set = 0, binding = MORPH_WEIGHTS_BINDING) readonly buffer MorphWeightsBuffer {
+    float weights[][MAX_MORPH_TARGETS];
+} morphWeightsBuffer;
+
+// Add the weighted deltas of every morph target to this vertex's position and normal.
+// `vertexIndex` is the index of the vertex within its primitive.
+void applyMorphTargets(uint weightsID, uint targetOffset, uint targetCount, uint vertexIndex, inout vec3 position, inout vec3 normal) {
+    if (weightsID == NOT_PRESENT) {
+        return;
+    }
+
+    uint firstDelta = targetOffset + vertexIndex * targetCount;
+    for (uint t = 0; t < min(targetCount, MAX_MORPH_TARGETS); t++) {
+        float weight = morphWeightsBuffer.weights[weightsID][t];
+        MorphTargetDelta delta = morphTargetsBuffer.deltas[firstDelta + t];
+        position += weight * delta.position.xyz;
+        normal += weight * delta.normal.xyz;
+    }
+}
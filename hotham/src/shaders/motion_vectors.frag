@@ -0,0 +1,12 @@
+#version 460
+
+layout (location = 0) in vec4 inClipPos;
+layout (location = 1) in vec4 inPreviousClipPos;
+
+layout (location = 0) out vec4 outMotionVector;
+
+void main() {
+    // XR_FB_space_warp expects motion in normalized device coordinates, from last frame to this one.
+    vec3 motion = inClipPos.xyz / inClipPos.w - inPreviousClipPos.xyz / inPreviousClipPos.w;
+    outMotionVector = vec4(motion, 0.0);
+}
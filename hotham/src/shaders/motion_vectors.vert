@@ -0,0 +1,73 @@
+// Renders the motion of each vertex since the previous frame, for Application SpaceWarp.
+#version 460
+
+#include "common.glsl"
+
+layout (location = 0) in vec3 inPos;
+layout (location = 3) in uint inJoint;
+layout (location = 4) in uint inWeight;
+
+layout (location = 0) out vec4 outClipPos;
+layout (location = 1) out vec4 outPreviousClipPos;
+
+struct DrawData {
+    mat4 gosFromLocal;
+    mat4 localFromGos;
+    uint skinID;
+    uint morphWeightsID;
+    uint morphTargetOffset;
+    uint morphTargetCount;
+    uint vertexOffset;
+};
+
+layout (set = 0, binding = 0) readonly buffer DrawDataBuffer {
+    DrawData data[5000];
+} drawDataBuffer;
+
+layout (std430, set = 0, binding = 1) readonly buffer SkinsBuffer {
+    mat4 jointMatrices[100][64];
+} skinsBuffer;
+
+#include "morph_targets.glsl"
+
+// Where the instance being drawn was last frame
+layout (push_constant) uniform constants {
+    mat4 previousGosFromLocal;
+} motion;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    uint skinID = drawDataBuffer.data[gl_InstanceIndex].skinID;
+    mat4 gosFromLocal = drawDataBuffer.data[gl_InstanceIndex].gosFromLocal;
+
+    // Normals aren't needed for motion, but the helper applies both.
+    vec3 pos = inPos;
+    vec3 normal = vec3(0.0);
+    applyMorphTargets(
+        drawDataBuffer.data[gl_InstanceIndex].morphWeightsID,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetOffset,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetCount,
+        uint(gl_VertexIndex) - drawDataBuffer.data[gl_InstanceIndex].vertexOffset,
+        pos,
+        normal);
+
+    // The previous pose of the skin isn't kept, so the current one is used for both positions.
+    vec4 localPos = vec4(pos, 1.0);
+    if (skinID != NOT_PRESENT) {
+        mat4 skinMatrix =
+            ((inWeight) & 255)       * skinsBuffer.jointMatrices[skinID][(inJoint) & 255] +
+            ((inWeight >> 8) & 255)  * skinsBuffer.jointMatrices[skinID][(inJoint >> 8) & 255] +
+            ((inWeight >> 16) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 16) & 255] +
+            ((inWeight >> 24) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 24) & 255];
+        localPos = skinMatrix * localPos;
+    }
+
+    // Both positions are projected with the current view, as the compositor accounts for head movement itself.
+    mat4 viewProjection = sceneData.viewProjection[gl_ViewIndex];
+    outClipPos = viewProjection * gosFromLocal * localPos;
+    outPreviousClipPos = viewProjection * motion.previousGosFromLocal * localPos;
+    gl_Position = outClipPos;
+}
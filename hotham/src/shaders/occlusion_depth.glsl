@@ -0,0 +1,37 @@
+// Builds the first level of the depth pyramid from the depth buffer of the previous frame.
+// Each texel of the pyramid holds the farthest depth of the 2x2 block of depth texels it covers.
+// Remember that we're using inverse Z, so the farthest depth is the smallest one.
+
+layout (local_size_x = 8, local_size_y = 8) in;
+
+#ifdef MULTISAMPLED
+layout (set = 0, binding = 0) uniform sampler2DMSArray source;
+#else
+layout (set = 0, binding = 0) uniform sampler2DArray source;
+#endif
+layout (set = 0, binding = 1, r32f) uniform writeonly image2DArray destination;
+
+void main() {
+    ivec3 coord = ivec3(gl_GlobalInvocationID);
+    ivec2 destinationSize = imageSize(destination).xy;
+    if (any(greaterThanEqual(coord.xy, destinationSize))) { return; }
+
+#ifdef MULTISAMPLED
+    ivec2 sourceMax = textureSize(source).xy - 1;
+#else
+    ivec2 sourceMax = textureSize(source, 0).xy - 1;
+#endif
+    ivec2 base = coord.xy * 2;
+
+    float depth = 1.0;
+    for (int y = 0; y < 2; y++) {
+        for (int x = 0; x < 2; x++) {
+            ivec2 sourceCoord = min(base + ivec2(x, y), sourceMax);
+            // When multisampled, the last argument is the sample rather than the mip level.
+            // Only the first sample is used.
+            depth = min(depth, texelFetch(source, ivec3(sourceCoord, coord.z), 0).r);
+        }
+    }
+
+    imageStore(destination, coord, vec4(depth));
+}
@@ -0,0 +1,13 @@
+// Fills in the outline around highlighted meshes with a flat color.
+#version 460
+
+layout (push_constant) uniform constants {
+    vec4 color;
+    float thickness;
+} outline;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    outColor = outline.color;
+}
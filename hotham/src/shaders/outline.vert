@@ -0,0 +1,75 @@
+// Draws a mesh pushed out along its normals, for the outline around highlighted meshes.
+#version 460
+#extension GL_EXT_multiview : enable
+
+#include "common.glsl"
+
+layout (location = 0) in vec3 inPos;
+layout (location = 1) in vec3 inNormal;
+layout (location = 3) in uint inJoint;
+layout (location = 4) in uint inWeight;
+
+struct DrawData {
+    mat4 gosFromLocal;
+    mat4 localFromGos;
+    uint skinID;
+    uint morphWeightsID;
+    uint morphTargetOffset;
+    uint morphTargetCount;
+    uint vertexOffset;
+};
+
+layout (set = 0, binding = 0) readonly buffer DrawDataBuffer {
+    DrawData data[5000];
+} drawDataBuffer;
+
+layout (std430, set = 0, binding = 1) readonly buffer SkinsBuffer {
+    mat4 jointMatrices[100][64];
+} skinsBuffer;
+
+#include "morph_targets.glsl"
+
+layout (push_constant) uniform constants {
+    vec4 color;
+    float thickness;
+} outline;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    uint skinID = drawDataBuffer.data[gl_InstanceIndex].skinID;
+    mat4 gosFromLocal = drawDataBuffer.data[gl_InstanceIndex].gosFromLocal;
+    mat4 localFromGos = drawDataBuffer.data[gl_InstanceIndex].localFromGos;
+
+    vec3 pos = inPos;
+    vec3 normal = inNormal;
+    applyMorphTargets(
+        drawDataBuffer.data[gl_InstanceIndex].morphWeightsID,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetOffset,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetCount,
+        uint(gl_VertexIndex) - drawDataBuffer.data[gl_InstanceIndex].vertexOffset,
+        pos,
+        normal);
+
+    vec4 gosPos;
+    vec3 gosNormal;
+    if (skinID == NOT_PRESENT) {
+        gosPos = gosFromLocal * vec4(pos, 1.0);
+        gosNormal = normalize(normal * mat3(localFromGos));
+    } else {
+        mat4 skinMatrix =
+            ((inWeight) & 255)       * skinsBuffer.jointMatrices[skinID][(inJoint) & 255] +
+            ((inWeight >> 8) & 255)  * skinsBuffer.jointMatrices[skinID][(inJoint >> 8) & 255] +
+            ((inWeight >> 16) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 16) & 255] +
+            ((inWeight >> 24) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 24) & 255];
+
+        gosPos = gosFromLocal * skinMatrix * vec4(pos, 1.0);
+        gosNormal = normalize(mat3(skinMatrix) * normal * mat3(localFromGos));
+    }
+
+    // Extruding in gos space keeps the outline the same thickness no matter how the mesh is scaled.
+    gosPos.xyz += gosNormal * outline.thickness;
+    gl_Position = sceneData.viewProjection[gl_ViewIndex] * gosPos;
+}
@@ -11,26 +11,45 @@
 #include "lights.glsl"
 #include "brdf.glsl"
 #include "pbr.glsl"
+#include "decals.glsl"
+#include "fog.glsl"
+#include "vignette.glsl"
 
 // Inputs
 layout (location = 0) in vec3 inGosPos;
 layout (location = 1) in vec2 inUV;
 layout (location = 2) in vec3 inNormal;
+layout (location = 3) in vec2 inUV1;
 
 // Outputs
 layout (location = 0) out vec4 outColor;
 
+// Set by the pipelines for alpha masked materials when MSAA is enabled.
+layout (constant_id = 0) const bool ALPHA_TO_COVERAGE = false;
+
+// Render modes that change how fragments are shaded. Must match `RenderMode::shader_index`
+#define RENDER_MODE_UNLIT 1
+#define RENDER_MODE_NORMALS 2
+#define RENDER_MODE_OVERDRAW 3
+
+// The heat each fragment adds in overdraw mode. Pixels go from red to yellow to white the more they're shaded.
+#define OVERDRAW_HEAT vec3(0.1, 0.04, 0.015)
+
 // Get normal, tangent and bitangent vectors.
 vec3 getNormal() {
     vec3 N = normalize(inNormal);
 
+    // Back faces are only drawn for double sided materials, and are lit as if they were facing the other way.
+    float facing = gl_FrontFacing ? 1.0 : -1.0;
+
     // If we don't have a normal texture, then just use the vertex normal
     if ((materialFlags & MATERIAL_FLAG_HAS_NORMAL_TEXTURE) == 0) {
-        return N;
+        return N * facing;
     }
 
+    vec2 normalUV = getUV(TEX_COORD_SET_NORMAL);
     f16vec3 textureNormal;
-    textureNormal.xy = f16vec2(texture(textures[baseTextureID + 2], inUV).ga) * F16(2) - F16(1);
+    textureNormal.xy = f16vec2(texture(textures[normalTextureID], normalUV).ga) * F16(2) - F16(1);
     textureNormal.z = sqrt(F16(1) - dot(textureNormal.xy, textureNormal.xy));
 
     // We compute the tangents on the fly because it is faster, presumably because it saves bandwidth.
@@ -40,48 +59,89 @@ vec3 getNormal() {
     // globally oriented stage space instead of view space and we rely on the UV map not being too distorted.
     vec3 dGosPosDx = dFdx(inGosPos);
     vec3 dGosPosDy = dFdy(inGosPos);
-    vec2 dUvDx = dFdx(inUV);
-    vec2 dUvDy = dFdy(inUV);
+    vec2 dUvDx = dFdx(normalUV);
+    vec2 dUvDy = dFdy(normalUV);
 
     vec3 T = normalize(dGosPosDx * dUvDy.t - dGosPosDy * dUvDx.t);
     vec3 B = normalize(cross(N, T));
     mat3 TBN = mat3(T, B, N);
 
-    return normalize(TBN * textureNormal);
+    return normalize(TBN * textureNormal) * facing;
 }
 
 void main() {
     // Unpack the material parameters
-    materialFlags = material.flagsAndBaseTextureID & 0xFFFF;
-    baseTextureID = material.flagsAndBaseTextureID >> 16;
+    unpackMaterial();
+    uv = inUV;
+    uv1 = inUV1;
 
-    // Determine the base color
+    // Determine the base color and opacity
     f16vec3 baseColor;
+    float16_t alpha;
 
+    // The base color factor is multiplied with the texture, so it can be changed at runtime to tint the material.
+    f16vec4 baseColorFactor = f16vec4(unpackUnorm4x8(material.packedBaseColor));
     if ((materialFlags & MATERIAL_FLAG_HAS_BASE_COLOR_TEXTURE) != 0) {
-        // This is *technically* against the spec, since material base color is meant to be treated as a "factor",
-        // but as of writing no texture authoring tool actually changes these values, so we can skip unnecessary
-        // arithmetic.
-        baseColor = V16(texture(textures[baseTextureID], inUV));
+        f16vec4 baseColorTexture = f16vec4(texture(textures[baseTextureID], getUV(TEX_COORD_SET_BASE_COLOR)));
+        baseColor = baseColorTexture.rgb * baseColorFactor.rgb;
+        alpha = baseColorTexture.a * baseColorFactor.a;
     } else {
-        // If no base color texture is present, check to see if the material had the base color factors set. This
-        // is usually only for very simple materials or prototyping.`
-        baseColor = V16(unpackUnorm4x8(material.packedBaseColor));
+        baseColor = baseColorFactor.rgb;
+        alpha = baseColorFactor.a;
+    }
+
+    // Alpha masked materials are either fully opaque or not drawn at all. The cutoff is stored next to the metallic
+    // and roughness factors. With alpha-to-coverage, alpha is instead sharpened into a ramp about a pixel wide around
+    // the cutoff, which the hardware turns into the fraction of samples covered, so the edge is anti-aliased.
+    bool alphaMasked = (materialFlags & MATERIAL_FLAG_ALPHA_MASK) != 0;
+    if (alphaMasked) {
+        float alphaCutoff = unpackUnorm4x8(material.packedMetallicRoughnessFactor).z;
+        if (ALPHA_TO_COVERAGE) {
+            float coverage = (float(alpha) - alphaCutoff) / max(fwidth(float(alpha)), 0.0001) + 0.5;
+            alpha = F16(clamp(coverage, 0.0, 1.0));
+        } else if (alpha < F16(alphaCutoff)) {
+            discard;
+        }
+    }
+
+    // Overdraw counts every fragment that survives the alpha test, so there's no need to shade it.
+    uint renderMode = uint(sceneData.params.w);
+    if (renderMode == RENDER_MODE_OVERDRAW) {
+        outColor = vec4(OVERDRAW_HEAT, 1.0);
+        return;
+    }
+
+    // Unlit materials skip lighting entirely, so there's no need to calculate the normal either.
+    // Opaque materials always write an alpha of one, so passthrough only shows where nothing was drawn. The exception
+    // is alpha-to-coverage, which needs the masked alpha and forces the written alpha to one itself where supported.
+    // Decals are blended into their base color before they're lit, much like a deferred decal pass writing into a G-buffer.
+    if ((materialFlags & MATERIAL_FLAG_ALPHA_BLEND) == 0) {
+        alpha = (ALPHA_TO_COVERAGE && alphaMasked) ? alpha : F16(1);
+        baseColor = applyDecals(baseColor, inGosPos);
+    }
+
+    if ((materialFlags & PBR_WORKFLOW_UNLIT) != 0 || renderMode == RENDER_MODE_UNLIT) {
+        outColor.rgb = applyVignette(tonemap(applyFog(baseColor, inGosPos)), vec4(inGosPos, 1.0));
+        outColor.a = alpha;
+        return;
     }
 
     // Set globals that are read inside functions for lighting etc.
     pos = inGosPos;
     v = normalize(sceneData.cameraPosition[gl_ViewIndex].xyz - inGosPos);
     n = getNormal();
-    uv = inUV;
 
-    // Choose the correct workflow for this material
-    if ((materialFlags & PBR_WORKFLOW_UNLIT) == 0) {
-        outColor.rgb = tonemap(getPBRMetallicRoughnessColor(baseColor));
-    } else {
-        outColor.rgb = tonemap(baseColor);
+    if (renderMode == RENDER_MODE_NORMALS) {
+        outColor = vec4(n * 0.5 + 0.5, 1.0);
+        return;
     }
 
+    outColor.rgb = applyVignette(tonemap(applyFog(getPBRMetallicRoughnessColor(baseColor), inGosPos)), vec4(inGosPos, 1.0));
+
+    // Transparent materials are drawn with blending enabled. The alpha channel is also used to blend the frame over
+    // passthrough.
+    outColor.a = alpha;
+
     // Debugging
     // Shader inputs debug visualization
     if (sceneData.params.z > 0.0) {
@@ -97,19 +157,19 @@ void main() {
                 break;
             // Occlusion
             case 3:
-                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_AO_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[baseTextureID + 1], inUV).rrr;
+                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_AO_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[metallicRoughnessTextureID], inUV).rrr;
                 break;
             // Emission
             case 4:
-                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_EMISSION_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[baseTextureID + 3], inUV).rgb;
+                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_EMISSION_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[emissionTextureID], inUV).rgb;
                 break;
             // Roughness
             case 5:
-                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_METALLIC_ROUGHNESS_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[baseTextureID + 1], inUV).ggg;
+                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_METALLIC_ROUGHNESS_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[metallicRoughnessTextureID], inUV).ggg;
                 break;
             // Metallic
             case 6:
-                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_METALLIC_ROUGHNESS_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[baseTextureID + 1], inUV).bbb;
+                outColor.rgb = ((materialFlags & MATERIAL_FLAG_HAS_METALLIC_ROUGHNESS_TEXTURE) != 0) ? ERROR_MAGENTA.rgb : texture(textures[metallicRoughnessTextureID], inUV).bbb;
                 break;
         }
         outColor = outColor;
@@ -12,31 +12,129 @@
 #define MATERIAL_FLAG_HAS_AO_TEXTURE 8
 #define MATERIAL_FLAG_HAS_EMISSION_TEXTURE 16
 #define PBR_WORKFLOW_UNLIT 32
+#define MATERIAL_FLAG_ALPHA_BLEND 64
+#define MATERIAL_FLAG_HAS_TRANSMISSION 128
+#define MATERIAL_FLAG_HAS_TRANSMISSION_TEXTURE 256
+#define MATERIAL_FLAG_HAS_THICKNESS_TEXTURE 512
+#define MATERIAL_FLAG_DOUBLE_SIDED 1024
+#define MATERIAL_FLAG_ALPHA_MASK 2048
+
+#define TEX_COORD_SET_BASE_COLOR 1
+#define TEX_COORD_SET_METALLIC_ROUGHNESS 2
+#define TEX_COORD_SET_NORMAL 4
+#define TEX_COORD_SET_EMISSION 8
+#define TEX_COORD_SET_TRANSMISSION 16
+#define TEX_COORD_SET_THICKNESS 32
 
 // The default index of refraction of 1.5 yields a dielectric normal incidence reflectance (eg. f0) of 0.04
 #define DEFAULT_F0 V16(0.04)
+#define DEFAULT_IOR 1.5
 
 // Textures
 layout (set = 0, binding = 3) uniform sampler2D textures[10000];
 layout (set = 0, binding = 4) uniform samplerCube cubeTextures[100];
+layout (set = 0, binding = 5) uniform sampler2DShadow shadowMap;
+layout (set = 0, binding = 6) uniform sampler2DArrayShadow localShadowMaps;
 
-// Material
-layout( push_constant ) uniform constants
-{
-    uint flagsAndBaseTextureID;
-    uint packedBaseColor;
-    uint packedMetallicRoughnessFactor;
-} material;
+#include "material.glsl"
 
 // Store the unpacked material in globals to avoid copying when calling functions.
 uint materialFlags;
 uint baseTextureID;
+uint metallicRoughnessTextureID;
+uint normalTextureID;
+uint emissionTextureID;
+uint transmissionTextureID;
+uint thicknessTextureID;
+
+// Unpack the material's flags and texture IDs into the globals above.
+void unpackMaterial() {
+    materialFlags = material.flagsAndBaseTextureID & 0xFFFF;
+    baseTextureID = material.flagsAndBaseTextureID >> 16;
+    metallicRoughnessTextureID = material.packedMetallicRoughnessAndNormalTextureIDs & 0xFFFF;
+    normalTextureID = material.packedMetallicRoughnessAndNormalTextureIDs >> 16;
+    emissionTextureID = material.packedEmissionTextureIDAndStrength & 0xFFFF;
+    transmissionTextureID = material.packedTransmissionAndThicknessTextureIDs & 0xFFFF;
+    thicknessTextureID = material.packedTransmissionAndThicknessTextureIDs >> 16;
+}
 
 // Common variables used throughout lighting equations
 vec3 pos;   // pos
 vec3 n;     // normal
 vec3 v;     // view vector
 vec2 uv;    // inUV
+vec2 uv1;   // inUV1
+float16_t shadow; // how much light from the primary directional light reaches this fragment
+
+// Get the texture coordinates a texture should be sampled with, as per the texCoord property of its textureInfo.
+vec2 getUV(uint texCoordSet) {
+    return (material.texCoordSets & texCoordSet) != 0 ? uv1 : uv;
+}
+
+// Sample the shadow map for the current fragment. Returns 1 if the fragment is fully lit.
+float16_t getShadow() {
+    if (sceneData.shadowParams.x == 0.) {
+        return F16(1);
+    }
+
+    vec4 shadowPos = sceneData.shadowFromGos * vec4(pos, 1.0);
+    vec3 shadowCoords = shadowPos.xyz / shadowPos.w;
+
+    // Anything beyond the far plane of the shadow map is considered to be lit.
+    if (shadowCoords.z > 1.0) {
+        return F16(1);
+    }
+
+    // The comparison sampler gives us hardware 2x2 PCF for free.
+    float lit = texture(shadowMap, vec3(shadowCoords.xy * 0.5 + 0.5, shadowCoords.z));
+    return F16(mix(1.0, lit, sceneData.shadowParams.y));
+}
+
+// Get the light emitted by the current fragment, as per the glTF spec: the emissive factor multiplied by the emissive texture
+// and the emissive strength from KHR_materials_emissive_strength.
+f16vec3 getEmission() {
+    float16_t emissiveStrength = F16(unpackHalf2x16(material.packedEmissionTextureIDAndStrength).y);
+    f16vec3 emission = V16(unpackUnorm4x8(material.packedEmissiveFactor).rgb) * emissiveStrength;
+    if ((materialFlags & MATERIAL_FLAG_HAS_EMISSION_TEXTURE) != 0) {
+        emission *= V16(texture(textures[emissionTextureID], getUV(TEX_COORD_SET_EMISSION)).rgb);
+    }
+    return emission;
+}
+
+// Get the fraction of light transmitted through the surface of the current fragment, as per KHR_materials_transmission.
+float16_t getTransmission() {
+    float16_t transmission = F16(unpackUnorm4x8(material.packedTransmissionFactorAndAttenuationColor).a);
+    if ((materialFlags & MATERIAL_FLAG_HAS_TRANSMISSION_TEXTURE) != 0) {
+        transmission *= F16(texture(textures[transmissionTextureID], getUV(TEX_COORD_SET_TRANSMISSION)).r);
+    }
+    return transmission;
+}
+
+// Get the light transmitted through the current fragment, as per KHR_materials_transmission and KHR_materials_volume.
+// Rather than sampling the scene behind the surface, which would need a copy of the opaque pass, the refracted ray is
+// looked up in the environment map: glass shows the environment through it, but not other objects.
+f16vec3 getTransmittedLight(f16vec3 transmissionColor, f16vec3 f0, float16_t perceptualRoughness, float16_t NdotV) {
+    vec2 thicknessAndAttenuationDistance = unpackHalf2x16(material.packedThicknessFactorAndAttenuationDistance);
+    float thickness = thicknessAndAttenuationDistance.x;
+    if ((materialFlags & MATERIAL_FLAG_HAS_THICKNESS_TEXTURE) != 0) {
+        thickness *= texture(textures[thicknessTextureID], getUV(TEX_COORD_SET_THICKNESS)).g;
+    }
+
+    // Thin walled materials don't bend light, volumes refract it as it enters.
+    vec3 direction = thickness > 0. ? refract(-v, n, 1.0 / DEFAULT_IOR) : -v;
+    float16_t lod = perceptualRoughness * DEFAULT_CUBE_MIPMAP_LEVELS - F16(1);
+    f16vec3 light = V16(textureLod(cubeTextures[ENVIRONMENT_MAP_TEXTURE_ID], direction, lod)) * F16(sceneData.params.x);
+
+    // Light is absorbed as it passes through the volume, as per the Beer-Lambert law.
+    f16vec3 attenuation = V16(1);
+    if (thickness > 0.) {
+        vec3 attenuationColor = unpackUnorm4x8(material.packedTransmissionFactorAndAttenuationColor).rgb;
+        attenuation = V16(pow(attenuationColor, vec3(thickness / thicknessAndAttenuationDistance.y)));
+    }
+
+    // Light reflected by the surface isn't transmitted.
+    return (V16(1) - F_Schlick(f0, NdotV)) * transmissionColor * attenuation * light;
+}
 
 // Calculation of the lighting contribution from an optional Image Based Light source.
 f16vec3 getIBLContribution(f16vec3 F0, float16_t perceptualRoughness, f16vec3 diffuseColor, f16vec3 reflection, float16_t NdotV) {
@@ -62,6 +160,33 @@ f16vec3 getIBLContribution(f16vec3 F0, float16_t perceptualRoughness, f16vec3 di
     return diffuse + specular;
 }
 
+// Sample the shadow map of a point or spot light. Returns 1 if the fragment is fully lit.
+float16_t getLocalShadow(Light light, vec3 pointToLight) {
+    uint layer = light.shadowIndex;
+
+    // Point lights have one shadow map per axis: +X, -X, +Y, -Y, +Z, -Z
+    if (light.type == LightType_Point) {
+        vec3 lightToPoint = -pointToLight;
+        vec3 a = abs(lightToPoint);
+        if (a.x >= a.y && a.x >= a.z) {
+            layer += lightToPoint.x < 0. ? 1u : 0u;
+        } else if (a.y >= a.z) {
+            layer += lightToPoint.y < 0. ? 3u : 2u;
+        } else {
+            layer += lightToPoint.z < 0. ? 5u : 4u;
+        }
+    }
+
+    vec4 shadowPos = sceneData.localShadowFromGos[layer] * vec4(pos, 1.0);
+    vec3 shadowCoords = shadowPos.xyz / shadowPos.w;
+
+    if (shadowCoords.z > 1.0) {
+        return F16(1);
+    }
+
+    return F16(texture(localShadowMaps, vec4(shadowCoords.xy * 0.5 + 0.5, float(layer), shadowCoords.z)));
+}
+
 f16vec3 getLightContribution(f16vec3 f0, float16_t alphaRoughness, f16vec3 diffuseColor, float16_t NdotV, Light light, float16_t ao) {
     // Get a vector between this point and the light.
     vec3 pointToLight;
@@ -88,6 +213,14 @@ f16vec3 getLightContribution(f16vec3 f0, float16_t alphaRoughness, f16vec3 diffu
 
         // Finally, combine the diffuse and specular contributions
         color = (diffuseContrib + specContrib) * (F16(light.intensity) * attenuation * NdotL * ao);
+
+        // Directional lights are shadowed using the shadow map rendered from the ShadowCaster,
+        // point and spot lights have their own shadow maps.
+        if (light.type == LightType_Directional) {
+            color *= shadow;
+        } else if (light.shadowIndex != NOT_PRESENT) {
+            color *= getLocalShadow(light, pointToLight);
+        }
     }
 
     return color;
@@ -97,7 +230,7 @@ f16vec3 getPBRMetallicRoughnessColor(f16vec3 baseColor) {
     f16vec3 amrSample;
 
     if ((materialFlags & MATERIAL_FLAG_HAS_METALLIC_ROUGHNESS_TEXTURE) != 0) {
-        amrSample = V16(texture(textures[baseTextureID + 1], uv).rgb);
+        amrSample = V16(texture(textures[metallicRoughnessTextureID], getUV(TEX_COORD_SET_METALLIC_ROUGHNESS)).rgb);
     } else {
         // If we don't have a metallic roughness texture, unpack the factors from the material.
         // Note the awkward swizzle: the variable name is "metallicRoughness", indicating that the
@@ -119,6 +252,14 @@ f16vec3 getPBRMetallicRoughnessColor(f16vec3 baseColor) {
     // Get the diffuse color
     f16vec3 diffuseColor = baseColor * (F16(1.0) - metalness);
 
+    // Light transmitted through the surface isn't diffusely reflected.
+    float16_t transmission = F16(0);
+    if ((materialFlags & MATERIAL_FLAG_HAS_TRANSMISSION) != 0) {
+        transmission = getTransmission();
+    }
+    f16vec3 transmissionColor = diffuseColor;
+    diffuseColor *= F16(1) - transmission;
+
     // Roughness is authored as perceptual roughness; as is convention,
     // convert to material roughness by squaring the perceptual roughness
     float16_t alphaRoughness = perceptualRoughness * perceptualRoughness;
@@ -126,6 +267,12 @@ f16vec3 getPBRMetallicRoughnessColor(f16vec3 baseColor) {
     // Get NdotV and reflection
     float16_t NdotV = saturate(F16(abs(dot(n, v))));
 
+    // Transmitted light comes from the environment map, so it's scaled by the IBL parameter.
+    f16vec3 color = V16(0.0);
+    if (transmission > F16(0) && sceneData.params.x > 0.) {
+        color = getTransmittedLight(transmissionColor, f0, perceptualRoughness, NdotV) * transmission;
+    }
+
     // Ambient Occlusion is stored in the 'r' channel as per the glTF spec
     float16_t ao;
     if ((materialFlags & MATERIAL_FLAG_HAS_AO_TEXTURE) != 0) {
@@ -135,15 +282,13 @@ f16vec3 getPBRMetallicRoughnessColor(f16vec3 baseColor) {
     }
 
     // Calculate lighting contribution from image based lighting source (IBL), scaled by a scene data parameter.
-    f16vec3 color;
     if (sceneData.params.x > 0.) {
         f16vec3 reflection = normalize(reflect(V16(-v), V16(n)));
-        color = getIBLContribution(f0, perceptualRoughness, diffuseColor, reflection, NdotV) * ao * F16(sceneData.params.x);
-    } else {
-        // If there is no IBL, set color to 0 to handle the edge-case of having no lights at all.
-        color = V16(0.0);
+        color += getIBLContribution(f0, perceptualRoughness, diffuseColor, reflection, NdotV) * ao * F16(sceneData.params.x);
     }
 
+    shadow = getShadow();
+
     // Walk through each light and add its color contribution.
     // Qualcomm's documentation suggests that loops are undesirable, so we do branches instead.
     // Since these values are uniform, they shouldn't have too high of a penalty.
@@ -160,25 +305,28 @@ f16vec3 getPBRMetallicRoughnessColor(f16vec3 baseColor) {
         color += getLightContribution(f0, alphaRoughness, diffuseColor, NdotV, sceneData.lights[3], ao);
     }
 
-    // Add emission, if present
-    if ((materialFlags & MATERIAL_FLAG_HAS_EMISSION_TEXTURE) > 0) {
-        color += V16(texture(textures[baseTextureID + 3], uv)).rgb;
+    // Clustered lights are only considered in the clusters they touch. Fragments outside the clusters fall back to
+    // considering every one of them.
+    uint clusteredLightCount = uint(sceneData.clusterParams.z);
+    if (clusteredLightCount > 0) {
+        uint cluster = getClusterIndex(pos);
+        if (cluster == NOT_PRESENT) {
+            for (uint i = 0; i < clusteredLightCount; i++) {
+                color += getLightContribution(f0, alphaRoughness, diffuseColor, NdotV, clusteredLightsBuffer.lights[i], ao);
+            }
+        } else {
+            LightCluster lightCluster = lightClustersBuffer.clusters[cluster];
+            for (uint i = 0; i < lightCluster.count; i++) {
+                uint lightIndex = lightIndicesBuffer.indices[lightCluster.offset + i];
+                color += getLightContribution(f0, alphaRoughness, diffuseColor, NdotV, clusteredLightsBuffer.lights[lightIndex], ao);
+            }
+        }
     }
 
-    return color;
-}
+    // Add emission, if present
+    color += getEmission();
 
-// Fast approximation of ACES tonemap
-// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
-f16vec3 toneMapACES_Narkowicz(const f16vec3 color) {
-    const float16_t A = F16(2.51);
-    const float16_t B = F16(0.03);
-    const float16_t C = F16(2.43);
-    const float16_t D = F16(0.59);
-    const float16_t E = F16(0.14);
-    return clamp((color * (A * color + B)) / (color * (C * color + D) + E), F16(0), F16(1));
+    return color;
 }
 
-f16vec3 tonemap(const f16vec3 color) {
-    return toneMapACES_Narkowicz(color);
-}
+#include "tonemap.glsl"
@@ -10,15 +10,21 @@ layout (location = 1) in vec3 inNormal;
 layout (location = 2) in vec2 inUV;
 layout (location = 3) in uint inJoint;
 layout (location = 4) in uint inWeight;
+layout (location = 5) in vec2 inUV1;
 
 layout (location = 0) out vec4 outGosPos;
 layout (location = 1) out vec2 outUV;
 layout (location = 2) out vec3 outNormal;
+layout (location = 3) out vec2 outUV1;
 
 struct DrawData {
     mat4 gosFromLocal;
     mat4 localFromGos;
     uint skinID;
+    uint morphWeightsID;
+    uint morphTargetOffset;
+    uint morphTargetCount;
+    uint vertexOffset;
 };
 
 layout (set = 0, binding = 0) readonly buffer DrawDataBuffer {
@@ -29,6 +35,9 @@ layout (std430, set = 0, binding = 1) readonly buffer SkinsBuffer {
     mat4 jointMatrices[100][64];
 } skinsBuffer;
 
+#include "morph_targets.glsl"
+#include "material.glsl"
+
 out gl_PerVertex {
     vec4 gl_Position;
 };
@@ -38,10 +47,21 @@ void main() {
     mat4 gosFromLocal = drawDataBuffer.data[gl_InstanceIndex].gosFromLocal;
     mat4 localFromGos = drawDataBuffer.data[gl_InstanceIndex].localFromGos;
 
+    // Morph targets are applied in the mesh's bind pose, before skinning.
+    vec3 pos = inPos;
+    vec3 normal = inNormal;
+    applyMorphTargets(
+        drawDataBuffer.data[gl_InstanceIndex].morphWeightsID,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetOffset,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetCount,
+        uint(gl_VertexIndex) - drawDataBuffer.data[gl_InstanceIndex].vertexOffset,
+        pos,
+        normal);
+
     if (skinID == NOT_PRESENT) {
         // Mesh has no skin
-        outGosPos = gosFromLocal * vec4(inPos, 1.0);
-        outNormal = normalize(inNormal * mat3(localFromGos));
+        outGosPos = gosFromLocal * vec4(pos, 1.0);
+        outNormal = normalize(normal * mat3(localFromGos));
     } else {
         // Mesh is skinned
         // Shift and mask to unpack the individual indices and weights.
@@ -52,10 +72,11 @@ void main() {
             ((inWeight >> 16) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 16) & 255] +
             ((inWeight >> 24) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 24) & 255];
 
-        outGosPos = gosFromLocal * skinMatrix * vec4(inPos, 1.0);
-        outNormal = normalize(mat3(skinMatrix) * inNormal * mat3(localFromGos));
+        outGosPos = gosFromLocal * skinMatrix * vec4(pos, 1.0);
+        outNormal = normalize(mat3(skinMatrix) * normal * mat3(localFromGos));
     }
 
-    outUV = inUV;
+    outUV = transformUV(inUV);
+    outUV1 = transformUV(inUV1);
     gl_Position = sceneData.viewProjection[gl_ViewIndex] * outGosPos;
 }
@@ -0,0 +1,68 @@
+// Depth only shader used to render the shadow map.
+#version 460
+
+#include "common.glsl"
+
+layout (location = 0) in vec3 inPos;
+layout (location = 3) in uint inJoint;
+layout (location = 4) in uint inWeight;
+
+struct DrawData {
+    mat4 gosFromLocal;
+    mat4 localFromGos;
+    uint skinID;
+    uint morphWeightsID;
+    uint morphTargetOffset;
+    uint morphTargetCount;
+    uint vertexOffset;
+};
+
+layout (set = 0, binding = 0) readonly buffer DrawDataBuffer {
+    DrawData data[5000];
+} drawDataBuffer;
+
+layout (std430, set = 0, binding = 1) readonly buffer SkinsBuffer {
+    mat4 jointMatrices[100][64];
+} skinsBuffer;
+
+#include "morph_targets.glsl"
+
+// The matrix for the shadow map currently being rendered
+layout (push_constant) uniform constants {
+    mat4 shadowFromGos;
+} shadow;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    uint skinID = drawDataBuffer.data[gl_InstanceIndex].skinID;
+    mat4 gosFromLocal = drawDataBuffer.data[gl_InstanceIndex].gosFromLocal;
+
+    // Normals aren't needed for depth, but the helper applies both.
+    vec3 pos = inPos;
+    vec3 normal = vec3(0.0);
+    applyMorphTargets(
+        drawDataBuffer.data[gl_InstanceIndex].morphWeightsID,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetOffset,
+        drawDataBuffer.data[gl_InstanceIndex].morphTargetCount,
+        uint(gl_VertexIndex) - drawDataBuffer.data[gl_InstanceIndex].vertexOffset,
+        pos,
+        normal);
+
+    vec4 gosPos;
+    if (skinID == NOT_PRESENT) {
+        gosPos = gosFromLocal * vec4(pos, 1.0);
+    } else {
+        mat4 skinMatrix =
+            ((inWeight) & 255)       * skinsBuffer.jointMatrices[skinID][(inJoint) & 255] +
+            ((inWeight >> 8) & 255)  * skinsBuffer.jointMatrices[skinID][(inJoint >> 8) & 255] +
+            ((inWeight >> 16) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 16) & 255] +
+            ((inWeight >> 24) & 255) * skinsBuffer.jointMatrices[skinID][(inJoint >> 24) & 255];
+
+        gosPos = gosFromLocal * skinMatrix * vec4(pos, 1.0);
+    }
+
+    gl_Position = shadow.shadowFromGos * gosPos;
+}
@@ -0,0 +1,41 @@
+#version 460
+
+#extension GL_GOOGLE_include_directive : require
+#extension GL_EXT_shader_explicit_arithmetic_types_float16 : require
+
+#include "common.glsl"
+
+layout (set = 0, binding = 3) uniform sampler2D textures[10000];
+layout (set = 0, binding = 4) uniform samplerCube cubeTextures[100];
+
+#include "tonemap.glsl"
+#include "vignette.glsl"
+
+layout (push_constant) uniform constants {
+    vec4 zenith;
+    vec4 horizon;
+    vec4 ground;
+    uint textureID;
+    float intensity;
+} skybox;
+
+// Inputs
+layout (location = 0) in vec3 inDirection;
+
+// Outputs
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    vec3 direction = normalize(inDirection);
+    vec3 color;
+
+    if (skybox.textureID != NOT_PRESENT) {
+        color = textureLod(cubeTextures[skybox.textureID], direction, 0.0).rgb;
+    } else if (direction.y > 0.0) {
+        color = mix(skybox.horizon.rgb, skybox.zenith.rgb, sqrt(direction.y));
+    } else {
+        color = mix(skybox.horizon.rgb, skybox.ground.rgb, sqrt(-direction.y));
+    }
+
+    outColor = vec4(applyVignette(tonemap(f16vec3(color * skybox.intensity)), vec4(direction, 0.0)), 1.0);
+}
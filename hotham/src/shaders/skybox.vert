@@ -0,0 +1,21 @@
+#version 460
+#extension GL_EXT_multiview : enable
+
+#include "common.glsl"
+
+// Outputs
+layout (location = 0) out vec3 outDirection;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+void main() {
+    // A single triangle that covers the whole viewport, on the far plane. With an infinite, inverse Z
+    // projection the far plane is at z = 0, so unprojecting gives us a direction rather than a point.
+    vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    vec4 clipPos = vec4(uv * 2.0 - 1.0, 0.0, 1.0);
+
+    outDirection = (inverse(sceneData.viewProjection[gl_ViewIndex]) * clipPos).xyz;
+    gl_Position = clipPos;
+}
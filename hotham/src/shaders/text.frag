@@ -0,0 +1,24 @@
+#version 460
+
+layout (set = 0, binding = 3) uniform sampler2D textures[10000];
+
+layout (push_constant) uniform constants {
+    uint textureID;
+} text;
+
+layout (location = 0) in vec2 inUV;
+layout (location = 1) in vec4 inColor;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+    // The edge of each glyph is at 0.5. Smooth it over a single pixel, whatever the size of the text.
+    float distance = texture(textures[text.textureID], inUV).r;
+    float width = fwidth(distance) * 0.5;
+    float alpha = smoothstep(0.5 - width, 0.5 + width, distance);
+    if (alpha <= 0.0) {
+        discard;
+    }
+
+    outColor = vec4(inColor.rgb, inColor.a * alpha);
+}
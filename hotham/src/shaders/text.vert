@@ -0,0 +1,22 @@
+#version 460
+#extension GL_EXT_multiview : enable
+
+#include "common.glsl"
+
+layout (location = 0) in vec3 inPos;
+layout (location = 1) in vec2 inUV;
+layout (location = 2) in vec4 inColor;
+
+layout (location = 0) out vec2 outUV;
+layout (location = 1) out vec4 outColor;
+
+out gl_PerVertex {
+    vec4 gl_Position;
+};
+
+// Glyphs are laid out in gos space on the CPU
+void main() {
+    outUV = inUV;
+    outColor = inColor;
+    gl_Position = sceneData.viewProjection[gl_ViewIndex] * vec4(inPos, 1.0);
+}
@@ -0,0 +1,56 @@
+// Fast approximation of ACES tonemap
+// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+f16vec3 toneMapACES_Narkowicz(const f16vec3 color) {
+    const float16_t A = F16(2.51);
+    const float16_t B = F16(0.03);
+    const float16_t C = F16(2.43);
+    const float16_t D = F16(0.59);
+    const float16_t E = F16(0.14);
+    return clamp((color * (A * color + B)) / (color * (C * color + D) + E), F16(0), F16(1));
+}
+
+vec3 linearToSrgb(const vec3 color) {
+    bvec3 cutoff = lessThan(color, vec3(0.0031308));
+    vec3 lower = color * vec3(12.92);
+    vec3 higher = vec3(1.055) * pow(color, vec3(1.0 / 2.4)) - vec3(0.055);
+    return mix(higher, lower, cutoff);
+}
+
+vec3 srgbToLinear(const vec3 color) {
+    bvec3 cutoff = lessThan(color, vec3(0.04045));
+    vec3 lower = color / vec3(12.92);
+    vec3 higher = pow((color + vec3(0.055)) / vec3(1.055), vec3(2.4));
+    return mix(higher, lower, cutoff);
+}
+
+// Look the tonemapped color up in the color grading LUT, if there is one. The LUT is a strip of `size` slices laid
+// out left to right, one per blue value, with red increasing to the right and green increasing downwards within
+// each slice. It maps sRGB encoded colors, so the color is encoded before the lookup and decoded afterwards.
+// Requires the textures array to have been declared.
+f16vec3 colorGrade(const f16vec3 color) {
+    float size = sceneData.colorGradingParams.y;
+    if (size == 0.0) {
+        return color;
+    }
+
+    uint lutID = uint(sceneData.colorGradingParams.x);
+    vec3 cell = clamp(linearToSrgb(vec3(color)), 0.0, 1.0) * (size - 1.0);
+
+    // Blend between the two slices either side of the blue value.
+    float slice = min(floor(cell.b), size - 2.0);
+    float t = cell.b - slice;
+    vec2 uv = vec2((slice + (cell.r + 0.5) / size) / size, (cell.g + 0.5) / size);
+    vec3 lower = textureLod(textures[lutID], uv, 0.0).rgb;
+    vec3 upper = textureLod(textures[lutID], uv + vec2(1.0 / size, 0.0), 0.0).rgb;
+    vec3 graded = srgbToLinear(mix(lower, upper, t));
+
+    return V16(mix(vec3(color), graded, sceneData.colorGradingParams.z));
+}
+
+f16vec3 tonemap(const f16vec3 color) {
+    // Render targets are tonemapped when they're drawn as part of the scene, not when they're rendered into.
+    if (sceneData.params.y != 0.0) {
+        return color;
+    }
+    return colorGrade(toneMapACES_Narkowicz(color));
+}
@@ -0,0 +1,16 @@
+// Comfort vignette. Must be included after common.glsl.
+
+// Darken the tonemapped color of the fragment at `gosPos` by how far it is from the centre of the view. `gosPos` can
+// also be a direction, with a w of zero, for fragments that are infinitely far away like the skybox.
+f16vec3 applyVignette(f16vec3 color, vec4 gosPos) {
+    float strength = sceneData.vignetteParams.x;
+    // Render targets skip tonemapping, and the vignette with it.
+    if (strength == 0.0 || sceneData.params.y == 1.0) {
+        return color;
+    }
+
+    vec4 clipPos = sceneData.viewProjection[gl_ViewIndex] * gosPos;
+    float radius = length(clipPos.xy / max(abs(clipPos.w), 0.0001));
+    float amount = smoothstep(sceneData.vignetteParams.y, sceneData.vignetteParams.z, radius) * strength;
+    return V16(vec3(color) * (1.0 - amount));
+}
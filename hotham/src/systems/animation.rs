@@ -1,5 +1,5 @@
 use crate::{
-    components::{animation_controller::AnimationController, LocalTransform},
+    components::{animation_controller::AnimationController, HandAnimation, LocalTransform},
     Engine,
 };
 
@@ -10,12 +10,22 @@ pub fn animation_system(engine: &mut Engine) {
 }
 
 fn animation_system_inner(world: &mut hecs::World) {
-    for (_, controller) in world.query::<&AnimationController>().iter() {
+    for (_, (controller, hand_animation)) in world
+        .query::<(&AnimationController, Option<&HandAnimation>)>()
+        .iter()
+    {
         let blend_from = controller.blend_from;
         let blend_to = controller.blend_to;
-        let blend_amount = controller.blend_amount;
 
-        for target in &controller.targets {
+        // Hands blend each finger by its own amount.
+        let blend_amounts = match hand_animation {
+            Some(hand_animation) => hand_animation
+                .target_blend_amounts(controller.blend_amount)
+                .collect(),
+            None => vec![controller.blend_amount; controller.targets.len()],
+        };
+
+        for (target, blend_amount) in controller.targets.iter().zip(blend_amounts) {
             let mut local_transform = world.get::<&mut LocalTransform>(target.target).unwrap();
             local_transform.translation =
                 target.translations[blend_from].lerp(target.translations[blend_to], blend_amount);
@@ -111,7 +111,7 @@ mod tests {
 
     use crate::{
         contexts::{audio_context::MusicTrack, PhysicsContext, XrContext},
-        HothamError, VIEW_TYPE,
+        HothamError,
     };
 
     use super::*;
@@ -201,7 +201,7 @@ mod tests {
         let (view_state_flags, views) = xr_context
             .session
             .locate_views(
-                VIEW_TYPE,
+                xr_context.view_configuration_type(),
                 xr_context.frame_state.predicted_display_time,
                 &xr_context.stage_space,
             )
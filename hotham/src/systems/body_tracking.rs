@@ -0,0 +1,84 @@
+use crate::{
+    components::{stage, BodyJoints},
+    contexts::InputContext,
+    Engine,
+};
+use hecs::World;
+
+/// Body tracking system
+/// Moves the joints of each `BodyJoints` component to where the player's body is, if it's being tracked.
+pub fn body_tracking_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    let input_context = &engine.input_context;
+    body_tracking_system_inner(world, input_context);
+}
+
+pub fn body_tracking_system_inner(world: &mut World, input_context: &InputContext) {
+    let global_from_stage = stage::get_global_from_stage(world);
+    let stage_from_body_joints = input_context.body.stage_from_body_joints();
+
+    for (_, body_joints) in world.query_mut::<&mut BodyJoints>() {
+        // If the body isn't tracked, leave it where it was last seen.
+        body_joints.is_tracked = stage_from_body_joints.is_some();
+        let stage_from_body_joints = match stage_from_body_joints {
+            Some(stage_from_body_joints) => stage_from_body_joints,
+            None => continue,
+        };
+
+        body_joints.confidence = stage_from_body_joints.confidence;
+        for (joint, stage_from_joint) in body_joints
+            .joints
+            .iter_mut()
+            .zip(&stage_from_body_joints.joints)
+        {
+            if let Some(stage_from_joint) = stage_from_joint {
+                *joint = global_from_stage * *stage_from_joint;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{body_joints::BODY_JOINT_COUNT, BodyJoint, GlobalTransform, Stage};
+    use approx::assert_relative_eq;
+    use glam::{Affine3A, Vec3};
+
+    #[test]
+    pub fn test_body_tracking_system() {
+        let mut world = World::new();
+        let input_context = InputContext::testing();
+
+        // The stage has been moved, eg. by teleporting.
+        let global_from_stage = Affine3A::from_translation([1., 0., 2.].into());
+        world.spawn((Stage, GlobalTransform(global_from_stage)));
+        let last_seen = Affine3A::from_translation(Vec3::NEG_Y);
+        let body = world.spawn((BodyJoints {
+            joints: [last_seen; BODY_JOINT_COUNT],
+            ..Default::default()
+        },));
+
+        body_tracking_system_inner(&mut world, &input_context);
+
+        let body_joints = world.get::<&BodyJoints>(body).unwrap();
+        assert!(body_joints.is_tracked);
+        assert_relative_eq!(body_joints.confidence, 1.);
+        assert_relative_eq!(
+            body_joints.joint(BodyJoint::Head).translation,
+            [1., 1.4, 2.].into()
+        );
+        assert_relative_eq!(
+            body_joints.joint(BodyJoint::Hips).translation,
+            [1., 0., 2.].into()
+        );
+
+        // The hands couldn't be estimated, so they stay where they were.
+        assert_relative_eq!(
+            body_joints
+                .left_hand_joint(crate::xr::HandJoint::PALM)
+                .translation,
+            last_seen.translation
+        );
+    }
+}
@@ -0,0 +1,153 @@
+use glam::{Affine3A, Vec3};
+use rapier3d::prelude::{Collider, RigidBodyType};
+
+use crate::{
+    contexts::PhysicsContext,
+    rendering::{aabb::Aabb, debug_draw::DebugDraw},
+    util::{decompose_isometry, glam_vec_from_na},
+    Engine,
+};
+
+/// Colliders that aren't attached to a rigid body, ie. those moved by the game.
+const COLLIDER_ONLY_COLOR: Vec3 = Vec3::new(1.0, 0.9, 0.2);
+const DYNAMIC_COLOR: Vec3 = Vec3::new(0.2, 1.0, 0.2);
+const SLEEPING_COLOR: Vec3 = Vec3::new(0.1, 0.4, 0.1);
+const KINEMATIC_COLOR: Vec3 = Vec3::new(0.3, 0.5, 1.0);
+const FIXED_COLOR: Vec3 = Vec3::new(0.6, 0.6, 0.6);
+const SENSOR_COLOR: Vec3 = Vec3::new(1.0, 0.3, 1.0);
+
+/// Collider debug system
+/// Draws the shape of every collider known to the [`PhysicsContext`] as a wireframe, using the
+/// [`DebugDraw`] in the render context. Colliders are colored by the type of their rigid body:
+///
+/// - dynamic bodies are green, or dark green while asleep
+/// - kinematic bodies are blue
+/// - fixed bodies are grey
+/// - colliders without a rigid body are yellow, and sensors are pink
+///
+/// Nothing is drawn unless `debug_render_colliders` is set on the [`PhysicsContext`]. Run this system after
+/// [`crate::systems::physics_system`] and before [`crate::systems::rendering_system`].
+pub fn collider_debug_system(engine: &mut Engine) {
+    let physics_context = &engine.physics_context;
+    let debug_draw = &mut engine.render_context.debug_draw;
+    collider_debug_system_inner(physics_context, debug_draw);
+}
+
+pub(crate) fn collider_debug_system_inner(
+    physics_context: &PhysicsContext,
+    debug_draw: &mut DebugDraw,
+) {
+    if !physics_context.debug_render_colliders {
+        return;
+    }
+
+    for (_, collider) in physics_context.colliders.iter() {
+        let color = collider_color(physics_context, collider);
+        draw_collider(collider, color, debug_draw);
+    }
+}
+
+fn collider_color(physics_context: &PhysicsContext, collider: &Collider) -> Vec3 {
+    if collider.is_sensor() {
+        return SENSOR_COLOR;
+    }
+
+    match collider
+        .parent()
+        .and_then(|handle| physics_context.rigid_bodies.get(handle))
+    {
+        None => COLLIDER_ONLY_COLOR,
+        Some(rigid_body) => match rigid_body.body_type() {
+            RigidBodyType::Dynamic if rigid_body.is_sleeping() => SLEEPING_COLOR,
+            RigidBodyType::Dynamic => DYNAMIC_COLOR,
+            RigidBodyType::KinematicPositionBased | RigidBodyType::KinematicVelocityBased => {
+                KINEMATIC_COLOR
+            }
+            RigidBodyType::Fixed => FIXED_COLOR,
+        },
+    }
+}
+
+fn draw_collider(collider: &Collider, color: Vec3, debug_draw: &mut DebugDraw) {
+    let (rotation, translation) = decompose_isometry(collider.position());
+    let transform = Affine3A::from_rotation_translation(rotation, translation);
+    let shape = collider.shape();
+
+    if let Some(ball) = shape.as_ball() {
+        debug_draw.sphere(translation, ball.radius, color);
+    } else if let Some(cuboid) = shape.as_cuboid() {
+        debug_draw.cuboid(&transform, glam_vec_from_na(&cuboid.half_extents), color);
+    } else if let Some(capsule) = shape.as_capsule() {
+        let a = transform.transform_point3(glam_vec_from_na(&capsule.segment.a.coords));
+        let b = transform.transform_point3(glam_vec_from_na(&capsule.segment.b.coords));
+        debug_draw.sphere(a, capsule.radius, color);
+        debug_draw.sphere(b, capsule.radius, color);
+        for offset in [Vec3::X, Vec3::NEG_X, Vec3::Z, Vec3::NEG_Z] {
+            let offset = transform.transform_vector3(offset * capsule.radius);
+            debug_draw.line(a + offset, b + offset, color);
+        }
+    } else if let Some(trimesh) = shape.as_trimesh() {
+        let vertices = trimesh
+            .vertices()
+            .iter()
+            .map(|v| transform.transform_point3(glam_vec_from_na(&v.coords)))
+            .collect::<Vec<_>>();
+        for [a, b, c] in trimesh.indices() {
+            let (a, b, c) = (
+                vertices[*a as usize],
+                vertices[*b as usize],
+                vertices[*c as usize],
+            );
+            debug_draw.line(a, b, color);
+            debug_draw.line(b, c, color);
+            debug_draw.line(c, a, color);
+        }
+    } else {
+        // Anything else is approximated by its bounding box.
+        let aabb = collider.compute_aabb();
+        debug_draw.aabb(
+            &Aabb::new(
+                glam_vec_from_na(&aabb.mins.coords),
+                glam_vec_from_na(&aabb.maxs.coords),
+            ),
+            color,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rapier3d::prelude::{ColliderBuilder, RigidBodyBuilder};
+
+    #[test]
+    pub fn test_collider_debug_system() {
+        let mut physics_context = PhysicsContext::default();
+        let mut debug_draw = DebugDraw::default();
+
+        let rigid_body = physics_context
+            .rigid_bodies
+            .insert(RigidBodyBuilder::dynamic().build());
+        let collider = ColliderBuilder::cuboid(0.5, 0.5, 0.5).build();
+        physics_context.colliders.insert_with_parent(
+            collider,
+            rigid_body,
+            &mut physics_context.rigid_bodies,
+        );
+        physics_context
+            .colliders
+            .insert(ColliderBuilder::ball(1.0).build());
+
+        // Nothing is drawn until it's turned on
+        collider_debug_system_inner(&physics_context, &mut debug_draw);
+        assert_eq!(debug_draw.line_count(), 0);
+
+        physics_context.debug_render_colliders = true;
+        collider_debug_system_inner(&physics_context, &mut debug_draw);
+
+        let mut expected = DebugDraw::default();
+        expected.cuboid(&Affine3A::IDENTITY, Vec3::splat(0.5), DYNAMIC_COLOR);
+        expected.sphere(Vec3::ZERO, 1.0, COLLIDER_ONLY_COLOR);
+        assert_eq!(debug_draw.line_count(), expected.line_count());
+    }
+}
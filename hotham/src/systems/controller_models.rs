@@ -0,0 +1,224 @@
+use hecs::{Entity, World};
+
+use crate::{
+    asset_importer::{add_model_to_world, load_models_from_glb},
+    components::{
+        hand::Handedness, parent::is_descendant_of, stage, ControllerModel, GlobalTransform,
+        LocalTransform, Mesh, RenderModel, TrackedDevice, Visible,
+    },
+    contexts::{InputContext, InputMode},
+    Engine,
+};
+
+/// Controller models system
+/// Loads the runtime's model of the controller for each `ControllerModel`, and moves it to the controller's grip.
+/// Also loads the runtime's model for each `RenderModel`, and hides it while it isn't `visible` or its device isn't
+/// tracked.
+pub fn controller_models_system(engine: &mut Engine) {
+    load_controller_models(engine);
+    load_render_models(engine);
+    controller_models_system_inner(&mut engine.world, &engine.input_context);
+}
+
+fn load_controller_models(engine: &mut Engine) {
+    let unloaded = engine
+        .world
+        .query::<&ControllerModel>()
+        .iter()
+        .filter(|(_, controller_model)| !controller_model.is_loaded)
+        .map(|(entity, controller_model)| (entity, controller_model.handedness))
+        .collect::<Vec<_>>();
+
+    for (entity, handedness) in unloaded {
+        let glb = engine.xr_context.load_controller_model(handedness);
+        let description = format!("{handedness:?} controller model");
+        let mesh_entities = match add_runtime_model(engine, entity, glb, &description) {
+            Some(mesh_entities) => mesh_entities,
+            // The controller may not have connected yet, so try again next frame.
+            None => continue,
+        };
+
+        let mut controller_model = engine.world.get::<&mut ControllerModel>(entity).unwrap();
+        controller_model.is_loaded = true;
+        controller_model.mesh_entities = mesh_entities;
+    }
+}
+
+fn load_render_models(engine: &mut Engine) {
+    let unloaded = engine
+        .world
+        .query::<&RenderModel>()
+        .iter()
+        .filter(|(_, render_model)| !render_model.is_loaded)
+        .map(|(entity, render_model)| (entity, render_model.path.clone()))
+        .collect::<Vec<_>>();
+
+    for (entity, path) in unloaded {
+        let glb = engine.xr_context.load_render_model(&path);
+        let mesh_entities = match add_runtime_model(engine, entity, glb, &path) {
+            Some(mesh_entities) => mesh_entities,
+            // The device may not have connected yet, so try again next frame.
+            None => continue,
+        };
+
+        let mut render_model = engine.world.get::<&mut RenderModel>(entity).unwrap();
+        render_model.is_loaded = true;
+        render_model.mesh_entities = mesh_entities;
+    }
+}
+
+/// Import a model loaded from the runtime as children of `entity`, returning the entities with meshes. Returns
+/// `None` if the runtime doesn't have the model yet, and no entities if it couldn't be loaded.
+fn add_runtime_model(
+    engine: &mut Engine,
+    entity: Entity,
+    glb: anyhow::Result<Option<Vec<u8>>>,
+    description: &str,
+) -> Option<Vec<Entity>> {
+    let glb = match glb {
+        Ok(glb) => glb?,
+        Err(e) => {
+            println!("[HOTHAM_CONTROLLER_MODELS] Unable to load the {description}: {e:?}");
+            return Some(Vec::new());
+        }
+    };
+
+    match load_models_from_glb(&[&glb], &engine.vulkan_context, &mut engine.render_context) {
+        Ok(models) => {
+            for name in models.keys() {
+                add_model_to_world(name, &models, &mut engine.world, Some(entity));
+            }
+            println!("[HOTHAM_CONTROLLER_MODELS] Loaded the {description}");
+            Some(find_mesh_entities(&engine.world, entity))
+        }
+        Err(e) => {
+            println!("[HOTHAM_CONTROLLER_MODELS] Unable to import the {description}: {e:?}");
+            Some(Vec::new())
+        }
+    }
+}
+
+fn find_mesh_entities(world: &World, controller_entity: Entity) -> Vec<Entity> {
+    world
+        .query::<&Mesh>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .filter(|entity| is_descendant_of(world, *entity, controller_entity))
+        .collect()
+}
+
+pub fn controller_models_system_inner(world: &mut World, input_context: &InputContext) {
+    let global_from_stage = stage::get_global_from_stage(world);
+
+    for (_, (controller_model, local_transform, global_transform)) in world
+        .query::<(&ControllerModel, &mut LocalTransform, &mut GlobalTransform)>()
+        .iter()
+    {
+        let (stage_from_grip, input_mode) = match controller_model.handedness {
+            Handedness::Left => (
+                input_context.left.stage_from_grip(),
+                input_context.left.input_mode(),
+            ),
+            Handedness::Right => (
+                input_context.right.stage_from_grip(),
+                input_context.right.input_mode(),
+            ),
+        };
+
+        let global_from_grip = global_from_stage * stage_from_grip;
+        local_transform.update_from_affine(&global_from_grip);
+        global_transform.0 = global_from_grip;
+
+        // Hide the controller while the player is using their hand instead.
+        for mesh_entity in &controller_model.mesh_entities {
+            if let Ok(mut visible) = world.get::<&mut Visible>(*mesh_entity) {
+                visible.0 = input_mode == InputMode::Controller;
+            }
+        }
+    }
+
+    for (_, (render_model, tracked_device)) in world
+        .query::<(&RenderModel, Option<&TrackedDevice>)>()
+        .iter()
+    {
+        let is_visible = render_model.visible
+            && tracked_device.map_or(true, |tracked_device| tracked_device.is_tracked);
+        for mesh_entity in &render_model.mesh_entities {
+            if let Ok(mut visible) = world.get::<&mut Visible>(*mesh_entity) {
+                visible.0 = is_visible;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contexts::TrackerRole;
+    use approx::assert_relative_eq;
+
+    #[test]
+    pub fn test_controller_models_system() {
+        let mut world = World::new();
+        let input_context = InputContext::testing();
+
+        let mesh_entity = world.spawn((Visible(true),));
+        let left = world.spawn((
+            ControllerModel {
+                is_loaded: true,
+                mesh_entities: vec![mesh_entity],
+                ..ControllerModel::left()
+            },
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+        let right = world.spawn((
+            ControllerModel::right(),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        controller_models_system_inner(&mut world, &input_context);
+
+        // Each model follows its controller.
+        let local_transform = world.get::<&LocalTransform>(left).unwrap();
+        assert_relative_eq!(local_transform.translation, [-0.2, 1.4, -0.5].into());
+        let global_transform = world.get::<&GlobalTransform>(right).unwrap();
+        assert_relative_eq!(global_transform.0.translation, [0.2, 1.4, -0.5].into());
+
+        // The player is using their left hand, so its controller is hidden.
+        assert!(!world.get::<&Visible>(mesh_entity).unwrap().0);
+    }
+
+    #[test]
+    pub fn test_render_models_visibility() {
+        let mut world = World::new();
+        let input_context = InputContext::testing();
+
+        let mesh_entity = world.spawn((Visible(true),));
+        let keyboard = world.spawn((
+            RenderModel {
+                is_loaded: true,
+                mesh_entities: vec![mesh_entity],
+                ..RenderModel::new(RenderModel::KEYBOARD_LOCAL)
+            },
+            TrackedDevice::new(TrackerRole::Keyboard),
+        ));
+
+        // The keyboard hasn't been tracked yet, so it's hidden.
+        controller_models_system_inner(&mut world, &input_context);
+        assert!(!world.get::<&Visible>(mesh_entity).unwrap().0);
+
+        world
+            .get::<&mut TrackedDevice>(keyboard)
+            .unwrap()
+            .is_tracked = true;
+        controller_models_system_inner(&mut world, &input_context);
+        assert!(world.get::<&Visible>(mesh_entity).unwrap().0);
+
+        // The application no longer needs the keyboard, eg. because no text field is focused.
+        world.get::<&mut RenderModel>(keyboard).unwrap().visible = false;
+        controller_models_system_inner(&mut world, &input_context);
+        assert!(!world.get::<&Visible>(mesh_entity).unwrap().0);
+    }
+}
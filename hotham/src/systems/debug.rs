@@ -1,22 +1,25 @@
-use crate::Engine;
+use crate::{rendering::render_mode::RenderMode, Engine};
 
-/// A simple system used to assist with debugging the fragment shader.
+/// A simple system used to assist with debugging the fragment shader. X cycles through the shader inputs, Y cycles
+/// through the [`RenderMode`]s, and A and B change the IBL intensity.
 pub fn debug_system(engine: &mut Engine) {
     let input_context = &mut engine.input_context;
     let render_context = &mut engine.render_context;
 
     if input_context.left.x_button_just_pressed() {
+        render_context.render_mode = RenderMode::Lit;
         let params = &mut render_context.scene_data.params;
-        params.w = 0.;
         params.z = (params.z + 1.) % 7.;
         println!("[HOTHAM_DEBUG] params.z is now {}", params.z);
     }
 
     if input_context.left.y_button_just_pressed() {
-        let params = &mut render_context.scene_data.params;
-        params.z = 0.;
-        params.w = (params.w + 1.) % 6.;
-        println!("[HOTHAM_DEBUG] params.w is now {}", params.w);
+        render_context.scene_data.params.z = 0.;
+        render_context.render_mode = render_context.render_mode.next();
+        println!(
+            "[HOTHAM_DEBUG] render_mode is now {:?}",
+            render_context.render_mode
+        );
     }
 
     if input_context.right.b_button_just_pressed() {
@@ -0,0 +1,67 @@
+use hecs::World;
+
+use crate::{
+    components::{
+        hand::{HandPreference, Handedness},
+        Haptic, Locomotion, Pointer, Teleporter,
+    },
+    Engine,
+};
+
+/// Dominant hand system
+/// Moves each [`Pointer`], [`Teleporter`], [`Haptic`] and [`Locomotion`] with a [`HandPreference`] to the hand it
+/// prefers, given [`Engine::dominant_hand`]. Run it before the systems that use them, so changing the dominant hand
+/// takes effect the same frame.
+pub fn dominant_hand_system(engine: &mut Engine) {
+    dominant_hand_system_inner(&mut engine.world, engine.dominant_hand);
+}
+
+fn dominant_hand_system_inner(world: &mut World, dominant_hand: Handedness) {
+    for (_, (preference, pointer)) in world.query_mut::<(&HandPreference, &mut Pointer)>() {
+        pointer.handedness = preference.handedness(dominant_hand);
+    }
+    for (_, (preference, teleporter)) in world.query_mut::<(&HandPreference, &mut Teleporter)>() {
+        teleporter.handedness = preference.handedness(dominant_hand);
+    }
+    for (_, (preference, haptic)) in world.query_mut::<(&HandPreference, &mut Haptic)>() {
+        haptic.handedness = preference.handedness(dominant_hand);
+    }
+    for (_, (preference, locomotion)) in world.query_mut::<(&HandPreference, &mut Locomotion)>() {
+        locomotion.turn_handedness = preference.handedness(dominant_hand);
+        locomotion.move_handedness = locomotion.turn_handedness.other();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_dominant_hand_system() {
+        let mut world = World::new();
+        let pointer = world.spawn((HandPreference::Dominant, Pointer::new(Handedness::Right)));
+        let teleporter = world.spawn((HandPreference::OffHand, Teleporter::new(Handedness::Left)));
+        let locomotion = world.spawn((HandPreference::Dominant, Locomotion::default()));
+        let fixed_pointer = world.spawn((Pointer::new(Handedness::Right),));
+
+        // A left handed player
+        dominant_hand_system_inner(&mut world, Handedness::Left);
+        assert_eq!(
+            world.get::<&Pointer>(pointer).unwrap().handedness,
+            Handedness::Left
+        );
+        assert_eq!(
+            world.get::<&Teleporter>(teleporter).unwrap().handedness,
+            Handedness::Right
+        );
+        let locomotion = world.get::<&Locomotion>(locomotion).unwrap();
+        assert_eq!(locomotion.turn_handedness, Handedness::Left);
+        assert_eq!(locomotion.move_handedness, Handedness::Right);
+
+        // Pointers without a preference stay where they are.
+        assert_eq!(
+            world.get::<&Pointer>(fixed_pointer).unwrap().handedness,
+            Handedness::Right
+        );
+    }
+}
@@ -16,6 +16,7 @@ pub fn draw_gui_system(engine: &mut Engine) {
     let render_context = &mut engine.render_context;
     let gui_context = &mut engine.gui_context;
     let haptic_context = &mut engine.haptic_context;
+    let dominant_hand = engine.dominant_hand;
 
     draw_gui_system_inner(
         world,
@@ -23,6 +24,7 @@ pub fn draw_gui_system(engine: &mut Engine) {
         render_context,
         gui_context,
         haptic_context,
+        dominant_hand,
     );
 }
 
@@ -32,6 +34,7 @@ fn draw_gui_system_inner(
     render_context: &mut RenderContext,
     gui_context: &mut GuiContext,
     haptic_context: &mut HapticContext,
+    dominant_hand: Handedness,
 ) {
     let mut new_hover = false;
 
@@ -56,8 +59,7 @@ fn draw_gui_system_inner(
 
     // Did we hover over a button in this frame? If so request haptic feedback.
     if new_hover {
-        // TODO - We should really have two pointer hands..
-        haptic_context.request_haptic_feedback(GUI_HAPTIC_AMPLITUDE, Handedness::Right);
+        haptic_context.request_haptic_feedback(GUI_HAPTIC_AMPLITUDE, dominant_hand);
     }
 }
 
@@ -187,6 +189,7 @@ mod tests {
             render_context,
             gui_context,
             haptic_context,
+            Handedness::Right,
         );
 
         let views = get_views();
@@ -282,11 +285,13 @@ mod tests {
 
         let swapchain = SwapchainInfo {
             images: vec![image.handle],
+            depth_images: Vec::new(),
             resolution,
         };
 
         let mut render_context =
-            RenderContext::new_from_swapchain_info(&vulkan_context, &swapchain).unwrap();
+            RenderContext::new_from_swapchain_info(&vulkan_context, &swapchain, Default::default())
+                .unwrap();
         let gui_context = GuiContext::new(&vulkan_context);
 
         let gltf_data: Vec<&[u8]> = vec![include_bytes!(
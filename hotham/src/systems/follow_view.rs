@@ -0,0 +1,117 @@
+use glam::Affine3A;
+use hecs::World;
+
+use crate::{
+    components::{stage, FollowView, GlobalTransform, LocalTransform},
+    util::lerp_slerp,
+    Engine,
+};
+
+/// Follow view system
+/// Moves each entity with a `FollowView` component to where it should be relative to the player's view.
+///
+/// Run this system after any systems that move the stage, such as [`crate::systems::locomotion_system`], and before
+/// [`crate::systems::update_global_transform_system`] so anything parented to the entity moves with it.
+pub fn follow_view_system(engine: &mut Engine) {
+    let delta_time = engine.xr_context.predicted_display_period().as_secs_f32();
+    follow_view_system_inner(
+        &mut engine.world,
+        engine.input_context.hmd.hmd_in_stage(),
+        delta_time,
+    );
+}
+
+pub(crate) fn follow_view_system_inner(world: &mut World, hmd_in_stage: Affine3A, delta_time: f32) {
+    let global_from_view = stage::get_global_from_stage(world) * hmd_in_stage;
+
+    for (_, (follow_view, local_transform, global_transform)) in
+        world.query_mut::<(&mut FollowView, &mut LocalTransform, &mut GlobalTransform)>()
+    {
+        let target = follow_view.target(&global_from_view);
+        let global_from_local = if follow_view.has_followed && follow_view.lag > 0. {
+            // Close most of the distance to the target every `lag` seconds, however long the frame was.
+            let s = 1. - (-delta_time / follow_view.lag).exp();
+            lerp_slerp(&global_transform.0, &target, s)
+        } else {
+            target
+        };
+        follow_view.has_followed = true;
+
+        local_transform.update_from_affine(&global_from_local);
+        global_transform.0 = global_from_local;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Stage;
+    use approx::assert_relative_eq;
+    use glam::{Quat, Vec3};
+
+    #[test]
+    pub fn test_follow_view_system() {
+        let mut world = World::new();
+        world.spawn((
+            Stage {},
+            LocalTransform::default(),
+            GlobalTransform(Affine3A::from_translation(Vec3::X)),
+        ));
+        let view_from_local = Affine3A::from_translation([0., 0., -1.].into());
+        let hud = world.spawn((
+            FollowView::new(view_from_local).with_lag(0.5),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+        let level_hud = world.spawn((
+            FollowView::new(view_from_local).level(),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        // The player is looking down at the floor.
+        let hmd_in_stage =
+            Affine3A::from_rotation_translation(Quat::from_rotation_x(-0.5), [0., 1.5, 0.].into());
+        follow_view_system_inner(&mut world, hmd_in_stage, 0.1);
+
+        // The first time, the HUD is placed in front of the player's eyes straight away.
+        let expected = Affine3A::from_translation(Vec3::X) * hmd_in_stage * view_from_local;
+        let hud_transform = world.get::<&GlobalTransform>(hud).unwrap().0;
+        assert_relative_eq!(hud_transform.translation, expected.translation);
+        assert_relative_eq!(
+            world.get::<&LocalTransform>(hud).unwrap().translation,
+            expected.translation.into()
+        );
+
+        // The level HUD stays upright, at the height of the player's eyes.
+        let level_transform = world.get::<&GlobalTransform>(level_hud).unwrap().0;
+        assert_relative_eq!(level_transform.translation, [1., 1.5, -1.].into());
+        assert_relative_eq!(
+            level_transform.transform_vector3(Vec3::Y),
+            Vec3::Y,
+            epsilon = 1e-6
+        );
+
+        // Once the player looks up, the lagging HUD catches up over several frames.
+        follow_view_system_inner(
+            &mut world,
+            Affine3A::from_translation([0., 1.5, 0.].into()),
+            0.1,
+        );
+        let hud_transform = world.get::<&GlobalTransform>(hud).unwrap().0;
+        assert!(hud_transform.translation.y < 1.5);
+        assert!(hud_transform.translation.y > expected.translation.y);
+        for _ in 0..100 {
+            follow_view_system_inner(
+                &mut world,
+                Affine3A::from_translation([0., 1.5, 0.].into()),
+                0.1,
+            );
+        }
+        assert_relative_eq!(
+            world.get::<&GlobalTransform>(hud).unwrap().0.translation,
+            [1., 1.5, -1.].into(),
+            epsilon = 1e-4
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use crate::{
+    components::{stage, FrustumCulled, GlobalTransform, Mesh, Visible},
+    contexts::RenderContext,
+    rendering::{aabb::Aabb, camera::extract_planes_from_frustum, scene_data::SceneData},
+    Engine,
+};
+use glam::Affine3A;
+use hecs::World;
+
+/// How far the view may turn between this system running and the frame being rendered, in radians.
+/// Bounding boxes are grown by this much so that objects at the edge of the view don't pop in late.
+const CULLING_MARGIN: f32 = 10. * std::f32::consts::PI / 180.;
+
+/// Frustum culling system
+/// Walks through each [`Visible`] entity with a [`Mesh`], computes its bounding box in world space and marks
+/// it with [`FrustumCulled`] if it can't be seen by either eye. The renderer skips culled entities entirely,
+/// which saves work on the CPU *and* the GPU for large scenes.
+///
+/// The views for the frame about to be rendered aren't known until the rendering system runs, so the views of
+/// the previous frame are used, with a margin to account for head movement. Run this system after
+/// [`crate::systems::update_global_transform_system`] and before [`crate::systems::rendering_system`].
+pub fn frustum_culling_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    let render_context = &engine.render_context;
+    frustum_culling_system_inner(world, render_context);
+}
+
+pub(crate) fn frustum_culling_system_inner(world: &mut World, render_context: &RenderContext) {
+    let meshes = &render_context.resources.mesh_data;
+    let scene_data = &render_context.scene_data;
+
+    // The cameras live in globally oriented stage space.
+    let global_from_stage = stage::get_global_from_stage(world);
+    let gos_from_global =
+        Affine3A::from_translation(global_from_stage.translation.into()).inverse();
+
+    let mut newly_culled = Vec::new();
+    let mut newly_visible = Vec::new();
+
+    for (entity, (mesh, global_transform, culled, visible)) in world
+        .query::<(&Mesh, &GlobalTransform, Option<&FrustumCulled>, &Visible)>()
+        .iter()
+    {
+        // Hidden entities aren't drawn, so there's no point culling them.
+        if !visible.0 {
+            continue;
+        }
+
+        // Build a box around the mesh in its local space from the bounding spheres of its primitives.
+        let local_aabb = meshes.get(mesh.handle).and_then(|mesh_data| {
+            mesh_data
+                .primitives
+                .iter()
+                .map(|p| Aabb::from_sphere(p.bounding_sphere))
+                .reduce(|a, b| a.union(&b))
+        });
+        let local_aabb = match local_aabb {
+            Some(local_aabb) => local_aabb,
+            None => continue,
+        };
+
+        let gos_from_local = gos_from_global * global_transform.0;
+        let is_visible = is_aabb_visible(&local_aabb.transformed(&gos_from_local), scene_data);
+
+        match (is_visible, culled.is_some()) {
+            (false, false) => newly_culled.push(entity),
+            (true, true) => newly_visible.push(entity),
+            _ => {}
+        }
+    }
+
+    // Only touch the world when something has changed, to avoid moving entities between archetypes every frame.
+    for entity in newly_culled {
+        world.insert_one(entity, FrustumCulled {}).unwrap();
+    }
+
+    for entity in newly_visible {
+        world.remove_one::<FrustumCulled>(entity).unwrap();
+    }
+}
+
+/// Returns true if the box (in globally oriented stage space) can be seen by either eye.
+fn is_aabb_visible(aabb: &Aabb, scene_data: &SceneData) -> bool {
+    scene_data
+        .view_projection
+        .iter()
+        .zip(scene_data.camera_position.iter())
+        .any(|(view_projection, camera_position)| {
+            let distance = (aabb.center() - camera_position.truncate()).length();
+            let margin = distance * CULLING_MARGIN.sin();
+            !aabb
+                .expanded(margin)
+                .is_outside_planes(&extract_planes_from_frustum(view_projection))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Mat4, Vec3};
+
+    #[test]
+    pub fn test_is_aabb_visible() {
+        let mut scene_data = SceneData::default();
+
+        // Both eyes looking down -Z
+        let view_projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1., 0.05, 100.);
+        scene_data.view_projection = [view_projection, view_projection];
+
+        let unit = Aabb::new(Vec3::splat(-0.5), Vec3::splat(0.5));
+        let in_front = unit.transformed(&Affine3A::from_translation(Vec3::NEG_Z * 5.));
+        let behind = unit.transformed(&Affine3A::from_translation(Vec3::Z * 5.));
+
+        // Just outside the view, but within the margin.
+        let just_outside = unit.transformed(&Affine3A::from_translation(Vec3::new(6., 0., -5.)));
+
+        assert!(is_aabb_visible(&in_front, &scene_data));
+        assert!(!is_aabb_visible(&behind, &scene_data));
+        assert!(is_aabb_visible(&just_outside, &scene_data));
+    }
+
+    #[test]
+    #[cfg(target_os = "windows")]
+    pub fn test_frustum_culling_skips_hidden_entities() {
+        use crate::asset_importer::load_models_from_glb;
+
+        let (mut render_context, vulkan_context) = RenderContext::testing();
+        let gltf_data: Vec<&[u8]> = vec![include_bytes!("../../../test_assets/damaged_helmet.glb")];
+        let mut models =
+            load_models_from_glb(&gltf_data, &vulkan_context, &mut render_context).unwrap();
+        let (_, mut model) = models.drain().next().unwrap();
+        let mesh = model
+            .query_mut::<&Mesh>()
+            .into_iter()
+            .next()
+            .unwrap()
+            .1
+            .clone();
+
+        // Both eyes looking down -Z
+        let view_projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1., 0.05, 100.);
+        render_context.scene_data.view_projection = [view_projection, view_projection];
+
+        // Two entities behind the camera, one of them hidden.
+        let behind = GlobalTransform(Affine3A::from_translation(Vec3::Z * 5.));
+        let mut world = World::new();
+        let shown = world.spawn((mesh.clone(), behind, Visible(true)));
+        let hidden = world.spawn((mesh, behind, Visible(false)));
+
+        frustum_culling_system_inner(&mut world, &render_context);
+
+        // Only the visible entity is culled, the hidden one is left alone.
+        assert!(world.get::<&FrustumCulled>(shown).is_ok());
+        assert!(world.get::<&FrustumCulled>(hidden).is_err());
+    }
+}
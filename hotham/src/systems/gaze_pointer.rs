@@ -0,0 +1,118 @@
+use glam::Vec3;
+use hecs::World;
+use rapier3d::prelude::{Group, InteractionGroups, QueryFilter, Ray};
+
+use crate::{
+    components::{stage, GazePointer},
+    contexts::{InputContext, PhysicsContext},
+    util::{glam_vec_from_na, na_vector_from_glam},
+    Engine,
+};
+
+/// Gaze pointer system
+/// Finds the collider the player is looking at for each `GazePointer`
+pub fn gaze_pointer_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    let input_context = &engine.input_context;
+    let physics_context = &engine.physics_context;
+    gaze_pointer_system_inner(world, input_context, physics_context);
+}
+
+pub fn gaze_pointer_system_inner(
+    world: &mut World,
+    input_context: &InputContext,
+    physics_context: &PhysicsContext,
+) {
+    let global_from_stage = stage::get_global_from_stage(world);
+    let stage_from_gaze = input_context.eye_gaze.stage_from_gaze();
+
+    for (_, gaze_pointer) in world.query::<&mut GazePointer>().iter() {
+        // If the eyes aren't tracked, keep looking at whatever was focused last.
+        gaze_pointer.is_tracked = stage_from_gaze.is_some();
+        let stage_from_gaze = match stage_from_gaze {
+            Some(stage_from_gaze) => stage_from_gaze,
+            None => continue,
+        };
+        gaze_pointer.global_from_gaze = global_from_stage * stage_from_gaze;
+
+        let ray_origin: Vec3 = gaze_pointer.global_from_gaze.translation.into();
+        let ray = Ray::new(
+            na_vector_from_glam(ray_origin).into(),
+            na_vector_from_glam(gaze_pointer.direction()),
+        );
+        let hit = physics_context.query_pipeline.cast_ray(
+            &physics_context.rigid_bodies,
+            &physics_context.colliders,
+            &ray,
+            gaze_pointer.max_distance,
+            true,
+            QueryFilter::new().groups(InteractionGroups::new(
+                Group::all(),
+                gaze_pointer.collision_filter,
+            )),
+        );
+
+        gaze_pointer.focused_entity = None;
+        gaze_pointer.focus_point = None;
+        if let Some((handle, toi)) = hit {
+            let collider = physics_context.colliders.get(handle).unwrap();
+            gaze_pointer.focused_entity =
+                Some(unsafe { world.find_entity_from_id(collider.user_data as _) });
+            gaze_pointer.focus_point = Some(glam_vec_from_na(&ray.point_at(toi).coords));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{Collider, GlobalTransform, LocalTransform},
+        contexts::physics_context::HAND_COLLISION_GROUP,
+        systems::physics::physics_system_inner,
+    };
+    use approx::assert_relative_eq;
+    use rapier3d::prelude::SharedShape;
+
+    #[test]
+    pub fn test_gaze_pointer_system() {
+        let mut world = World::new();
+        let mut physics_context = PhysicsContext::default();
+        let input_context = InputContext::testing();
+
+        // The player is looking straight at a box two meters away, through a hand.
+        let local_transform = LocalTransform {
+            translation: [0., 1.4, -2.].into(),
+            ..Default::default()
+        };
+        let target = world.spawn((
+            Collider::new(SharedShape::cuboid(0.5, 0.5, 0.5)),
+            local_transform,
+            GlobalTransform::from(local_transform),
+        ));
+        let local_transform = LocalTransform {
+            translation: [0., 1.4, -0.5].into(),
+            ..Default::default()
+        };
+        world.spawn((
+            Collider {
+                shape: SharedShape::ball(0.05),
+                sensor: true,
+                collision_groups: HAND_COLLISION_GROUP,
+                ..Default::default()
+            },
+            local_transform,
+            GlobalTransform::from(local_transform),
+        ));
+        let gaze_pointer = world.spawn((GazePointer::default(),));
+
+        physics_system_inner(&mut physics_context, &mut world);
+        gaze_pointer_system_inner(&mut world, &input_context, &physics_context);
+
+        let gaze_pointer = world.get::<&GazePointer>(gaze_pointer).unwrap();
+        assert!(gaze_pointer.is_tracked);
+        assert_relative_eq!(gaze_pointer.direction(), Vec3::NEG_Z);
+        assert_eq!(gaze_pointer.focused_entity, Some(target));
+        assert_relative_eq!(gaze_pointer.focus_point.unwrap(), Vec3::new(0., 1.4, -1.5));
+    }
+}
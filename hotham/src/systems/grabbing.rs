@@ -3,23 +3,37 @@ use hecs::World;
 
 use crate::{
     components::{
-        hand::GrabbedEntity, physics::BodyType, Collider, Grabbable, Grabbed, Hand, LocalTransform,
-        Parent, Released, RigidBody,
+        grabbable::GrabMode, hand::GrabbedEntity, physics::BodyType, Collider, Grabbable, Grabbed,
+        Hand, JustGrabbed, LocalTransform, Parent, Released, RigidBody,
     },
     Engine,
 };
 
 /// Grabbing system
 /// Used to allow a player to grab objects. Used in conjunction with `hands_system`
+///
+/// Grabbed entities are given a [`Grabbed`] component while they're held, and [`JustGrabbed`] and [`Released`]
+/// components on the frames they're picked up and let go. Released rigid bodies are thrown with the velocity of the
+/// hand, so run this system before `physics_system`.
 pub fn grabbing_system(engine: &mut Engine) {
     let world = &mut engine.world;
     grabbing_system_inner(world);
 }
 
 fn grabbing_system_inner(world: &mut World) {
-    // First, clean up any `Released` marker traits from the previous frame. This is important as otherwise
-    // any entity that was ever grabbed will still have a `Released` component
+    // First, clean up any `JustGrabbed` and `Released` marker traits from the previous frame. This is important as
+    // otherwise any entity that was ever grabbed will still have a `Released` component
     {
+        let entities_with_just_grabbed = world
+            .query::<()>()
+            .with::<&JustGrabbed>()
+            .into_iter()
+            .map(|(entity, _)| entity)
+            .collect::<Vec<_>>();
+        for entity in entities_with_just_grabbed {
+            world.remove_one::<JustGrabbed>(entity).unwrap();
+        }
+
         let entities_with_released = world
             .query::<()>()
             .with::<&Released>()
@@ -32,7 +46,10 @@ fn grabbing_system_inner(world: &mut World) {
 
     let mut command_buffer = hecs::CommandBuffer::new();
 
-    for (_, (hand, collider, local_transform)) in world
+    // `Grabbed` isn't inserted until the end of the frame, so keep track of what's been grabbed since then too.
+    let mut grabbed_this_frame = Vec::new();
+
+    for (hand_entity, (hand, collider, local_transform)) in world
         .query::<(&mut Hand, &Collider, &LocalTransform)>()
         .iter()
     {
@@ -50,7 +67,10 @@ fn grabbing_system_inner(world: &mut World) {
             let mut closest_length_squared = f32::INFINITY;
             let mut closest_grippable = None;
             for collided_entity in collider.collisions_this_frame.iter() {
-                if world.get::<&Grabbable>(*collided_entity).is_ok() {
+                // Entities held by the other hand can't be grabbed.
+                let is_held = world.entity(*collided_entity).unwrap().has::<Grabbed>()
+                    || grabbed_this_frame.contains(collided_entity);
+                if world.get::<&Grabbable>(*collided_entity).is_ok() && !is_held {
                     let global_from_local = world
                         .get::<&LocalTransform>(*collided_entity)
                         .unwrap()
@@ -72,7 +92,21 @@ fn grabbing_system_inner(world: &mut World) {
                 }
 
                 // Add a "Grabbed" marker trait for other systems to read
-                command_buffer.insert_one(*entity, Grabbed);
+                command_buffer.insert(*entity, (Grabbed, JustGrabbed { hand: hand_entity }));
+                grabbed_this_frame.push(*entity);
+
+                // Kinematic grabs are moved by the hand, so they mustn't be moved by the physics simulation as well.
+                let mode = world.get::<&Grabbable>(*entity).unwrap().mode;
+                let previous_body_type = match world.get::<&mut RigidBody>(*entity) {
+                    Ok(mut rigid_body) => {
+                        let previous_body_type = rigid_body.body_type;
+                        if mode == GrabMode::Kinematic {
+                            rigid_body.body_type = BodyType::KinematicPositionBased;
+                        }
+                        Some(previous_body_type)
+                    }
+                    Err(_) => None,
+                };
 
                 // Store a reference to the grabbed entity
                 let global_from_local = world.get::<&LocalTransform>(*entity).unwrap().to_affine();
@@ -80,21 +114,39 @@ fn grabbing_system_inner(world: &mut World) {
                 let grabbed_entity = GrabbedEntity {
                     entity: *entity,
                     grip_from_local,
+                    previous_body_type,
                 };
                 hand.grabbed_entity.replace(grabbed_entity);
             }
         } else {
             // If we are not gripping, but we have a grabbed entity, release it
             if let Some(grabbed_entity) = hand.grabbed_entity.take() {
-                // If what we're grabbing has a rigid-body, set it back to dynamic.
-                // TODO: This is a bug. We could have grabbed a rigid-body that was originally kinematic!
+                // If what we're grabbing has a rigid-body, set it back to what it was before it was grabbed.
                 if let Ok(mut rigid_body) = world.get::<&mut RigidBody>(grabbed_entity.entity) {
-                    rigid_body.body_type = BodyType::Dynamic;
+                    if let Some(previous_body_type) = grabbed_entity.previous_body_type {
+                        rigid_body.body_type = previous_body_type;
+                    }
+
+                    // Throw it with the velocity of the hand, including the hand's spin.
+                    if rigid_body.body_type == BodyType::Dynamic {
+                        let throw_velocity_scale = world
+                            .get::<&Grabbable>(grabbed_entity.entity)
+                            .map(|grabbable| grabbable.throw_velocity_scale)
+                            .unwrap_or(1.);
+                        let hand_to_entity = world
+                            .get::<&LocalTransform>(grabbed_entity.entity)
+                            .map(|t| t.translation - local_transform.translation)
+                            .unwrap_or(Vec3::ZERO);
+                        rigid_body.linear_velocity = (hand.linear_velocity
+                            + hand.angular_velocity.cross(hand_to_entity))
+                            * throw_velocity_scale;
+                        rigid_body.angular_velocity = hand.angular_velocity * throw_velocity_scale;
+                    }
                 }
 
                 // Add a marker trait for other systems to know that this item has at some point been grabbed
                 command_buffer.remove_one::<Grabbed>(grabbed_entity.entity);
-                command_buffer.insert_one(grabbed_entity.entity, Released);
+                command_buffer.insert_one(grabbed_entity.entity, Released { hand: hand_entity });
             }
         }
     }
@@ -119,8 +171,11 @@ mod tests {
                 name: "Test entity".to_string(),
                 node_id: 0,
             },
-            Grabbable {},
-            LocalTransform::default(),
+            Grabbable::default(),
+            LocalTransform {
+                translation: [0., 0., -1.].into(),
+                ..Default::default()
+            },
         ));
         world
             .insert(grabbed_entity, (grabbed_collider, grabbed_rigid_body))
@@ -132,6 +187,8 @@ mod tests {
             grip_value: 1.0,
             grip_button_just_pressed: true,
             grabbed_entity: None,
+            linear_velocity: Vec3::ZERO,
+            angular_velocity: Vec3::ZERO,
         };
 
         // Collider
@@ -149,14 +206,38 @@ mod tests {
 
         let mut hand = world.get::<&mut Hand>(hand_entity).unwrap();
         assert_eq!(hand.grabbed_entity.as_ref().unwrap().entity, grabbed_entity);
+        assert_eq!(
+            world.get::<&JustGrabbed>(grabbed_entity).unwrap().hand,
+            hand_entity
+        );
+        assert_eq!(
+            world.get::<&RigidBody>(grabbed_entity).unwrap().body_type,
+            BodyType::KinematicPositionBased
+        );
+
+        // Let go while swinging the hand forward and spinning it
         hand.grip_value = 0.0;
         hand.grip_button_just_pressed = false;
+        hand.linear_velocity = [0., 0., -2.].into();
+        hand.angular_velocity = [0., 1., 0.].into();
         drop(hand);
 
         tick(&mut world);
 
         let mut hand = world.get::<&mut Hand>(hand_entity).unwrap();
         assert!(hand.grabbed_entity.is_none());
+        assert!(world.get::<&JustGrabbed>(grabbed_entity).is_err());
+        assert!(world.get::<&Grabbed>(grabbed_entity).is_err());
+        assert_eq!(
+            world.get::<&Released>(grabbed_entity).unwrap().hand,
+            hand_entity
+        );
+        {
+            let rigid_body = world.get::<&RigidBody>(grabbed_entity).unwrap();
+            assert_eq!(rigid_body.body_type, BodyType::Dynamic);
+            assert_eq!(rigid_body.linear_velocity, Vec3::new(-1., 0., -2.));
+            assert_eq!(rigid_body.angular_velocity, Vec3::Y);
+        }
 
         // Make sure hand can't grip colliders *without* a Grabbable component
         hand.grip_value = 1.0;
@@ -170,6 +251,71 @@ mod tests {
         assert!(hand.grabbed_entity.is_none());
     }
 
+    #[test]
+    fn test_grabbing_with_two_hands() {
+        let mut world = World::default();
+        let grabbed_entity = world.spawn((
+            Grabbable::default(),
+            LocalTransform::default(),
+            RigidBody::default(),
+        ));
+
+        let spawn_hand = |world: &mut World, handedness| {
+            world.spawn((
+                Hand {
+                    handedness,
+                    grip_value: 1.0,
+                    grip_button_just_pressed: true,
+                    grabbed_entity: None,
+                    linear_velocity: Vec3::ZERO,
+                    angular_velocity: Vec3::ZERO,
+                },
+                Collider {
+                    collisions_this_frame: [grabbed_entity].into(),
+                    ..Default::default()
+                },
+                LocalTransform::default(),
+            ))
+        };
+
+        // Both hands try to grab the entity on the same frame, but only one of them can.
+        let left_hand = spawn_hand(&mut world, Handedness::Left);
+        let right_hand = spawn_hand(&mut world, Handedness::Right);
+        tick(&mut world);
+        let holding_hands = [left_hand, right_hand]
+            .into_iter()
+            .filter(|h| world.get::<&Hand>(*h).unwrap().grabbed_entity.is_some())
+            .collect::<Vec<_>>();
+        assert_eq!(holding_hands.len(), 1);
+        let holding_hand = holding_hands[0];
+        let other_hand = if holding_hand == left_hand {
+            right_hand
+        } else {
+            left_hand
+        };
+
+        // The other hand grips again on a later frame, and still can't take it.
+        world
+            .get::<&mut Hand>(other_hand)
+            .unwrap()
+            .grip_button_just_pressed = true;
+        tick(&mut world);
+        assert!(world
+            .get::<&Hand>(other_hand)
+            .unwrap()
+            .grabbed_entity
+            .is_none());
+
+        // Letting go restores the body type it had before it was grabbed.
+        world.get::<&mut Hand>(holding_hand).unwrap().grip_value = 0.0;
+        tick(&mut world);
+        assert!(world.get::<&Grabbed>(grabbed_entity).is_err());
+        assert_eq!(
+            world.get::<&RigidBody>(grabbed_entity).unwrap().body_type,
+            BodyType::Dynamic
+        );
+    }
+
     fn tick(world: &mut World) {
         grabbing_system_inner(world);
     }
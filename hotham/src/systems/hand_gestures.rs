@@ -0,0 +1,233 @@
+use glam::Vec3;
+use hecs::World;
+
+use crate::{
+    components::{
+        hand_gestures::{Gesture, GestureEvent},
+        HandGestures, HandJoints,
+    },
+    Engine,
+};
+
+/// Fingers curled more than this count as curled..
+const CURLED: f32 = 0.5;
+/// ..and fingers curled less than this count as extended
+const EXTENDED: f32 = 0.25;
+/// How close to straight up the thumb has to point for a thumbs up, as the cosine of the angle from up
+const THUMBS_UP_MIN_COS: f32 = 0.7;
+
+/// Hand gestures system
+/// Recognises the gestures made by each tracked hand with a `HandGestures` component, from its `HandJoints`.
+///
+/// Run this system after [`crate::systems::hand_tracking_system`].
+pub fn hand_gestures_system(engine: &mut Engine) {
+    hand_gestures_system_inner(&mut engine.world);
+}
+
+pub(crate) fn hand_gestures_system_inner(world: &mut World) {
+    for (_, (hand_joints, hand_gestures)) in world.query_mut::<(&HandJoints, &mut HandGestures)>() {
+        let previous = std::mem::take(&mut hand_gestures.active);
+        hand_gestures.events.clear();
+
+        // Gestures can't be seen if the hand isn't tracked, so they all end.
+        if hand_joints.is_tracked {
+            hand_gestures.finger_curls = hand_joints.finger_curls();
+            hand_gestures.active = recognise(hand_joints, hand_gestures, &previous);
+        }
+
+        for gesture in &previous {
+            if !hand_gestures.active.contains(gesture) {
+                hand_gestures.events.push(GestureEvent::Ended(*gesture));
+            }
+        }
+        for gesture in &hand_gestures.active {
+            if !previous.contains(gesture) {
+                hand_gestures.events.push(GestureEvent::Started(*gesture));
+            }
+        }
+    }
+}
+
+fn recognise(
+    hand_joints: &HandJoints,
+    hand_gestures: &HandGestures,
+    previous: &[Gesture],
+) -> Vec<Gesture> {
+    let mut active = Vec::new();
+    let [thumb, index, middle, ring, little] = hand_gestures.finger_curls;
+    let others_curled = middle > CURLED && ring > CURLED && little > CURLED;
+
+    let pinch_threshold = if previous.contains(&Gesture::Pinch) {
+        hand_gestures.pinch_end_distance
+    } else {
+        hand_gestures.pinch_start_distance
+    };
+    if hand_joints.pinch_distance() < pinch_threshold {
+        active.push(Gesture::Pinch);
+    }
+
+    let thumbs_up = others_curled
+        && index > CURLED
+        && thumb < EXTENDED
+        && hand_joints.thumb_direction().dot(Vec3::Y) > THUMBS_UP_MIN_COS;
+    if thumbs_up {
+        active.push(Gesture::ThumbsUp);
+    } else if others_curled && index > CURLED {
+        active.push(Gesture::Grab);
+    }
+
+    if others_curled && index < EXTENDED {
+        active.push(Gesture::Point);
+    }
+
+    for (i, pose) in hand_gestures.custom_poses.iter().enumerate() {
+        let matches = pose
+            .finger_curls
+            .iter()
+            .zip(&hand_gestures.finger_curls)
+            .all(|(target, curl)| (target - curl).abs() <= pose.tolerance);
+        if matches {
+            active.push(Gesture::Custom(i));
+        }
+    }
+
+    active
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::{hand_gestures::GesturePose, hand_joints::HandJointLocation},
+        xr,
+    };
+    use glam::Affine3A;
+
+    #[test]
+    pub fn test_hand_gestures_system() {
+        let mut world = World::new();
+        let hand = world.spawn((HandJoints::right(), HandGestures::default()));
+        world
+            .get::<&mut HandGestures>(hand)
+            .unwrap()
+            .custom_poses
+            .push(GesturePose {
+                name: "Rock on".to_string(),
+                finger_curls: [0.8, 0., 0.8, 0.8, 0.],
+                tolerance: 0.2,
+            });
+
+        let pose = |world: &mut World, curled: [bool; 5], thumb_up: bool| {
+            *world.get::<&mut HandJoints>(hand).unwrap() = posed_hand(curled, thumb_up);
+            hand_gestures_system_inner(world);
+            world.get::<&HandGestures>(hand).unwrap().clone()
+        };
+
+        // A fist is a grab, and letting it go ends it.
+        let gestures = pose(&mut world, [true; 5], false);
+        assert_eq!(gestures.active, vec![Gesture::Grab]);
+        assert!(gestures.just_started(Gesture::Grab));
+        let gestures = pose(&mut world, [true; 5], false);
+        assert!(gestures.is_active(Gesture::Grab));
+        assert!(gestures.events.is_empty());
+        let gestures = pose(&mut world, [false; 5], false);
+        assert!(gestures.active.is_empty());
+        assert_eq!(gestures.events, vec![GestureEvent::Ended(Gesture::Grab)]);
+
+        // Pointing and thumbs up
+        let gestures = pose(&mut world, [true, false, true, true, true], false);
+        assert_eq!(gestures.active, vec![Gesture::Point]);
+        let gestures = pose(&mut world, [false, true, true, true, true], true);
+        assert_eq!(gestures.active, vec![Gesture::ThumbsUp]);
+
+        // A custom pose
+        let gestures = pose(&mut world, [true, false, true, true, false], false);
+        assert_eq!(gestures.active, vec![Gesture::Custom(0)]);
+
+        // Touching the tips of the thumb and index finger together is a pinch.
+        let mut hand_joints = posed_hand([false; 5], false);
+        hand_joints.joints[xr::HandJoint::THUMB_TIP.into_raw() as usize] =
+            *hand_joints.joint(xr::HandJoint::INDEX_TIP);
+        *world.get::<&mut HandJoints>(hand).unwrap() = hand_joints.clone();
+        hand_gestures_system_inner(&mut world);
+        assert!(world
+            .get::<&HandGestures>(hand)
+            .unwrap()
+            .just_started(Gesture::Pinch));
+
+        // Losing track of the hand ends every gesture.
+        hand_joints.is_tracked = false;
+        *world.get::<&mut HandJoints>(hand).unwrap() = hand_joints;
+        hand_gestures_system_inner(&mut world);
+        let gestures = world.get::<&HandGestures>(hand).unwrap();
+        assert!(gestures.active.is_empty());
+        assert!(gestures.just_ended(Gesture::Pinch));
+    }
+
+    /// A hand pointing down -Z, with each finger either straight or curled, and the thumb optionally pointing up
+    fn posed_hand(curled: [bool; 5], thumb_up: bool) -> HandJoints {
+        let mut hand_joints = HandJoints::right();
+        hand_joints.is_tracked = true;
+        let mut set = |joint: xr::HandJoint, position: Vec3| {
+            hand_joints.joints[joint.into_raw() as usize] = HandJointLocation {
+                transform: Affine3A::from_translation(position),
+                radius: 0.,
+            };
+        };
+
+        let fingers = [
+            [
+                xr::HandJoint::THUMB_METACARPAL,
+                xr::HandJoint::THUMB_PROXIMAL,
+                xr::HandJoint::THUMB_DISTAL,
+                xr::HandJoint::THUMB_TIP,
+            ],
+            [
+                xr::HandJoint::INDEX_METACARPAL,
+                xr::HandJoint::INDEX_PROXIMAL,
+                xr::HandJoint::INDEX_DISTAL,
+                xr::HandJoint::INDEX_TIP,
+            ],
+            [
+                xr::HandJoint::MIDDLE_METACARPAL,
+                xr::HandJoint::MIDDLE_PROXIMAL,
+                xr::HandJoint::MIDDLE_DISTAL,
+                xr::HandJoint::MIDDLE_TIP,
+            ],
+            [
+                xr::HandJoint::RING_METACARPAL,
+                xr::HandJoint::RING_PROXIMAL,
+                xr::HandJoint::RING_DISTAL,
+                xr::HandJoint::RING_TIP,
+            ],
+            [
+                xr::HandJoint::LITTLE_METACARPAL,
+                xr::HandJoint::LITTLE_PROXIMAL,
+                xr::HandJoint::LITTLE_DISTAL,
+                xr::HandJoint::LITTLE_TIP,
+            ],
+        ];
+        for (i, [metacarpal, proximal, distal, tip]) in fingers.into_iter().enumerate() {
+            let x = i as f32 * 0.02;
+            if i == 0 && thumb_up {
+                set(metacarpal, Vec3::new(x, 0., 0.));
+                set(proximal, Vec3::new(x, 0.03, 0.));
+                set(distal, Vec3::new(x, 0.06, 0.));
+                set(tip, Vec3::new(x, 0.08, 0.));
+                continue;
+            }
+
+            set(metacarpal, Vec3::new(x, 0., 0.));
+            set(proximal, Vec3::new(x, 0., -0.05));
+            if curled[i] {
+                set(distal, Vec3::new(x, -0.03, -0.07));
+                set(tip, Vec3::new(x, -0.02, -0.05));
+            } else {
+                set(distal, Vec3::new(x, 0., -0.09));
+                set(tip, Vec3::new(x, 0., -0.11));
+            }
+        }
+
+        hand_joints
+    }
+}
@@ -0,0 +1,215 @@
+use crate::{
+    components::{hand::Handedness, stage, GlobalTransform, HandJoints, HandSkeleton, Skin},
+    contexts::InputContext,
+    xr, Engine,
+};
+use hecs::World;
+
+/// Hand tracking system
+/// Moves the joints of each `HandJoints` component to where the player's hand is, if it's being tracked, and poses
+/// the hand models of any with a `HandSkeleton` to match.
+///
+/// This overrides the global transforms of the models' joints, so it must run after
+/// `update_global_transform_system` and before `skinning_system`.
+pub fn hand_tracking_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    let input_context = &engine.input_context;
+    hand_tracking_system_inner(world, input_context);
+}
+
+pub fn hand_tracking_system_inner(world: &mut World, input_context: &InputContext) {
+    let global_from_stage = stage::get_global_from_stage(world);
+
+    for (_, hand_joints) in world.query_mut::<&mut HandJoints>() {
+        let stage_from_hand_joints = match hand_joints.handedness {
+            Handedness::Left => input_context.left.stage_from_hand_joints(),
+            Handedness::Right => input_context.right.stage_from_hand_joints(),
+        };
+
+        // If the hand isn't tracked, leave it where it was last seen.
+        hand_joints.is_tracked = stage_from_hand_joints.is_some();
+        if let Some(stage_from_hand_joints) = stage_from_hand_joints {
+            for (joint, stage_from_joint) in
+                hand_joints.joints.iter_mut().zip(stage_from_hand_joints)
+            {
+                joint.transform = global_from_stage * stage_from_joint.transform;
+                joint.radius = stage_from_joint.radius;
+            }
+        }
+    }
+
+    for (_, (hand_joints, hand_skeleton)) in world.query::<(&HandJoints, &HandSkeleton)>().iter() {
+        if hand_joints.is_tracked {
+            pose_hand_skeleton(world, hand_joints, hand_skeleton);
+        }
+    }
+}
+
+fn pose_hand_skeleton(world: &World, hand_joints: &HandJoints, hand_skeleton: &HandSkeleton) {
+    let skin = world.get::<&Skin>(hand_skeleton.skin_entity).unwrap();
+    let global_from_skin = world
+        .get::<&GlobalTransform>(hand_skeleton.skin_entity)
+        .unwrap()
+        .0;
+    let (skin_scale, _, _) = global_from_skin.to_scale_rotation_translation();
+    let global_from_bones = hand_skeleton
+        .global_from_bones(hand_joints, skin_scale)
+        .collect::<Vec<_>>();
+
+    // Move the whole model so that its wrist lines up with the tracked wrist first. Joints that aren't tracked, like
+    // the base of the thumb, keep their animated pose, but stay attached to the rest of the hand.
+    let wrist = xr::HandJoint::WRIST.into_raw() as usize;
+    let tracked_wrist = hand_skeleton
+        .bones
+        .iter()
+        .zip(&global_from_bones)
+        .find(|(bone, _)| bone.hand_joint == wrist)
+        .map(|(_, global_from_bone)| *global_from_bone);
+    if let Some((wrist_entity, global_from_wrist)) = tracked_wrist {
+        let global_from_animated_wrist = world.get::<&GlobalTransform>(wrist_entity).unwrap().0;
+        let tracked_from_animated = global_from_wrist * global_from_animated_wrist.inverse();
+        for entity in skin.joints.iter().chain([&hand_skeleton.skin_entity]) {
+            let mut global_transform = world.get::<&mut GlobalTransform>(*entity).unwrap();
+            global_transform.0 = tracked_from_animated * global_transform.0;
+        }
+    }
+
+    for (entity, global_from_bone) in global_from_bones {
+        world.get::<&mut GlobalTransform>(entity).unwrap().0 = global_from_bone;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{Info, LocalTransform, Parent, Stage};
+    use approx::assert_relative_eq;
+    use glam::{Affine3A, Vec3};
+
+    #[test]
+    pub fn test_hand_tracking_system() {
+        let mut world = World::new();
+        let input_context = InputContext::testing();
+        let global_from_stage = Affine3A::from_translation(Vec3::X);
+        world.spawn((
+            Stage {},
+            LocalTransform::default(),
+            GlobalTransform(global_from_stage),
+        ));
+        let left = world.spawn((HandJoints::left(),));
+        let right = world.spawn((HandJoints::right(),));
+
+        hand_tracking_system_inner(&mut world, &input_context);
+
+        // The left hand is tracked, and moved into global space.
+        let hand_joints = world.get::<&HandJoints>(left).unwrap();
+        assert!(hand_joints.is_tracked);
+        let expected = global_from_stage * input_context.left.stage_from_grip();
+        assert_relative_eq!(
+            hand_joints.joints[0].transform.translation,
+            expected.translation
+        );
+        assert_relative_eq!(hand_joints.joints[0].radius, 0.01);
+
+        // The right hand isn't tracked, so it stays where it was.
+        let hand_joints = world.get::<&HandJoints>(right).unwrap();
+        assert!(!hand_joints.is_tracked);
+        assert_eq!(hand_joints.joints[0].transform, Affine3A::IDENTITY);
+    }
+
+    #[test]
+    pub fn test_pose_hand_skeleton() {
+        let mut world = World::new();
+        let hand = world.spawn((LocalTransform::default(), GlobalTransform::default()));
+
+        // A flat left hand, palm down, pointing down -Z, in centimeters.
+        let bind_joints = [
+            ("hands:b_l_wrist", Some(xr::HandJoint::WRIST), Vec3::ZERO),
+            ("hands:b_l_thumb0", None, Vec3::new(2., 0., -2.)),
+            (
+                "hands:b_l_index1",
+                Some(xr::HandJoint::INDEX_PROXIMAL),
+                Vec3::new(2., 0., -10.),
+            ),
+            (
+                "hands:b_l_index2",
+                Some(xr::HandJoint::INDEX_INTERMEDIATE),
+                Vec3::new(2., 0., -15.),
+            ),
+            (
+                "hands:b_l_middle1",
+                Some(xr::HandJoint::MIDDLE_PROXIMAL),
+                Vec3::new(0., 0., -10.),
+            ),
+            (
+                "hands:b_l_middle2",
+                Some(xr::HandJoint::MIDDLE_INTERMEDIATE),
+                Vec3::new(0., 0., -15.),
+            ),
+            (
+                "hands:b_l_pinky1",
+                Some(xr::HandJoint::LITTLE_PROXIMAL),
+                Vec3::new(-4., 0., -10.),
+            ),
+        ];
+        let global_from_skin = Affine3A::from_scale(Vec3::splat(0.01));
+        let joints = bind_joints
+            .iter()
+            .map(|(name, _, position)| {
+                world.spawn((
+                    Info {
+                        name: name.to_string(),
+                        node_id: 0,
+                    },
+                    GlobalTransform(global_from_skin * Affine3A::from_translation(*position)),
+                ))
+            })
+            .collect::<Vec<_>>();
+        let inverse_bind_matrices = bind_joints
+            .iter()
+            .map(|(_, _, position)| Affine3A::from_translation(-*position))
+            .collect();
+        world.spawn((
+            Skin {
+                joints: joints.clone(),
+                inverse_bind_matrices,
+                id: 0,
+            },
+            Parent(hand),
+            GlobalTransform(global_from_skin),
+        ));
+
+        let hand_skeleton = HandSkeleton::from_hand_model(&world, hand, Handedness::Left).unwrap();
+        // The base of the thumb isn't tracked, and the little finger has nowhere to point.
+        assert_eq!(hand_skeleton.bones.len(), 5);
+
+        // Track the hand a meter to the right, turned to point down -X, in the same pose as the model.
+        let global_from_tracked = Affine3A::from_rotation_translation(
+            glam::Quat::from_rotation_y(90_f32.to_radians()),
+            Vec3::X,
+        );
+        let mut hand_joints = HandJoints::left();
+        hand_joints.is_tracked = true;
+        for (_, joint, position) in &bind_joints {
+            if let Some(joint) = joint {
+                hand_joints.joints[joint.into_raw() as usize].transform =
+                    global_from_tracked * Affine3A::from_translation(*position * 0.01);
+            }
+        }
+
+        pose_hand_skeleton(&world, &hand_joints, &hand_skeleton);
+
+        // Every joint follows the hand, keeping the scale of the skin, including the ones that aren't tracked.
+        for (joint, (_, _, position)) in joints.iter().zip(bind_joints) {
+            let global_transform = world.get::<&GlobalTransform>(*joint).unwrap();
+            let expected =
+                global_from_tracked * global_from_skin * Affine3A::from_translation(position);
+            assert_relative_eq!(
+                global_transform.0.translation,
+                expected.translation,
+                epsilon = 1e-5
+            );
+            assert_relative_eq!(global_transform.0.matrix3, expected.matrix3, epsilon = 1e-5);
+        }
+    }
+}
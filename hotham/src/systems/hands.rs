@@ -2,48 +2,112 @@ use crate::{
     asset_importer::add_model_to_world,
     components::{
         global_transform::GlobalTransform,
+        grabbable::GrabMode,
         hand::{GrabbedEntity, Handedness},
         local_transform::LocalTransform,
-        stage, AnimationController, Collider, Grabbed, Hand,
+        stage, AnimationController, Collider, Grabbable, Grabbed, Hand, HandAnimation, HandJoints,
+        HandSkeleton, RigidBody,
     },
-    contexts::{physics_context::HAND_COLLISION_GROUP, InputContext},
-    Engine,
+    contexts::{
+        physics_context::{DELTA_TIME, HAND_COLLISION_GROUP},
+        EngineAction, InputBindings, InputContext, InputMode,
+    },
+    xr, Engine,
 };
+use glam::Vec3;
 use hecs::World;
 use rapier3d::prelude::{ActiveCollisionTypes, Group, SharedShape};
 
 /// Hands system
-/// Used to allow users to interact with objects using their controllers as representations of their hands
+/// Used to allow users to interact with objects using their controllers as representations of their hands. Hands
+/// grab with the input [`EngineAction::Grab`] is bound to in [`Engine::input_bindings`], the grip by default.
 pub fn hands_system(engine: &mut Engine) {
     let world = &mut engine.world;
     let input_context = &mut engine.input_context;
-    hands_system_inner(world, input_context);
+    let input_bindings = &engine.input_bindings;
+    hands_system_inner(world, input_context, input_bindings);
 }
 
-pub fn hands_system_inner(world: &mut World, input_context: &InputContext) {
+pub fn hands_system_inner(
+    world: &mut World,
+    input_context: &InputContext,
+    input_bindings: &InputBindings,
+) {
     // Get the position
     let global_from_stage = stage::get_global_from_stage(world);
 
-    for (_, (hand, animation_controller, local_transform, global_transform)) in world
-        .query::<(
-            &mut Hand,
-            &mut AnimationController,
-            &mut LocalTransform,
-            &mut GlobalTransform,
-        )>()
-        .iter()
+    for (_, (hand, animation_controller, local_transform, global_transform, hand_animation)) in
+        world
+            .query::<(
+                &mut Hand,
+                &mut AnimationController,
+                &mut LocalTransform,
+                &mut GlobalTransform,
+                Option<&mut HandAnimation>,
+            )>()
+            .iter()
     {
         // Get the position of the hand in stage space.
-        let (stage_from_grip, grip_value, grip_button_just_pressed) = match hand.handedness {
+        let (
+            stage_from_grip,
+            input_mode,
+            stage_from_hand_joints,
+            linear_velocity,
+            angular_velocity,
+            controller_state,
+        ) = match hand.handedness {
             Handedness::Left => (
                 input_context.left.stage_from_grip(),
-                input_context.left.grip_analog(),
-                input_context.left.grip_button_just_pressed(),
+                input_context.left.input_mode(),
+                input_context.left.stage_from_hand_joints(),
+                input_context.left.linear_velocity(),
+                input_context.left.angular_velocity(),
+                input_context.left.controller_state(),
             ),
             Handedness::Right => (
                 input_context.right.stage_from_grip(),
-                input_context.right.grip_analog(),
-                input_context.right.grip_button_just_pressed(),
+                input_context.right.input_mode(),
+                input_context.right.stage_from_hand_joints(),
+                input_context.right.linear_velocity(),
+                input_context.right.angular_velocity(),
+                input_context.right.controller_state(),
+            ),
+        };
+        let grab = input_bindings.engine_binding(EngineAction::Grab, hand.handedness);
+        let grip_value = grab.float(input_context);
+        let grip_button_just_pressed = grab.button(input_context).just_pressed;
+
+        // If the player put the controller down and is using their hand instead, follow the palm. The hand model is
+        // posed by `hand_tracking_system`, so there's nothing to grip. If the hand isn't tracked for a moment, eg.
+        // because it's out of view, leave it where it was last seen rather than jumping to the controller.
+        // The controller's velocities don't apply to the hand, and the palm's aren't tracked, so it stands still.
+        let (
+            stage_from_grip,
+            grip_value,
+            grip_button_just_pressed,
+            linear_velocity,
+            angular_velocity,
+        ) = match (input_mode, stage_from_hand_joints) {
+            (InputMode::Hand, Some(stage_from_hand_joints)) => (
+                stage_from_hand_joints[xr::HandJoint::PALM.into_raw() as usize].transform,
+                0.,
+                false,
+                Vec3::ZERO,
+                Vec3::ZERO,
+            ),
+            (InputMode::Hand, None) => (
+                global_from_stage.inverse() * global_transform.0,
+                0.,
+                false,
+                Vec3::ZERO,
+                Vec3::ZERO,
+            ),
+            (InputMode::Controller, _) => (
+                stage_from_grip,
+                grip_value,
+                grip_button_just_pressed,
+                linear_velocity,
+                angular_velocity,
             ),
         };
 
@@ -53,11 +117,14 @@ pub fn hands_system_inner(world: &mut World, input_context: &InputContext) {
         // Apply transform
         local_transform.update_from_affine(&global_from_grip);
         global_transform.0 = global_from_grip;
+        hand.linear_velocity = global_from_stage.transform_vector3(linear_velocity);
+        hand.angular_velocity = global_from_stage.transform_vector3(angular_velocity);
 
         // If we've grabbed something, update its transform, being careful to preserve its scale.
         if let Some(GrabbedEntity {
             entity,
             grip_from_local,
+            ..
         }) = hand.grabbed_entity
         {
             // We first need to check if some other system has decided that this item should no longer be grabbed.
@@ -66,11 +133,39 @@ pub fn hands_system_inner(world: &mut World, input_context: &InputContext) {
             } else {
                 // OK. We are sure that this entity exists, and is being grabbed.
                 let global_from_local = global_from_grip * grip_from_local;
-                let mut local_transform = world.get::<&mut LocalTransform>(entity).unwrap();
-                local_transform.update_rotation_translation_from_affine(&global_from_local);
-
-                let mut global_transform = world.get::<&mut GlobalTransform>(entity).unwrap();
-                *global_transform = (*local_transform).into();
+                let mode = world
+                    .get::<&Grabbable>(entity)
+                    .map(|grabbable| grabbable.mode)
+                    .unwrap_or(GrabMode::Kinematic);
+                let rigid_body = world.get::<&mut RigidBody>(entity).ok();
+
+                match (mode, rigid_body) {
+                    (GrabMode::Physics, Some(mut rigid_body)) => {
+                        // Set the velocities that will carry the entity to the hand by the next physics step.
+                        let global_transform = world.get::<&GlobalTransform>(entity).unwrap();
+                        let (_, rotation, translation) =
+                            global_transform.to_scale_rotation_translation();
+                        let (_, target_rotation, target_translation) =
+                            global_from_local.to_scale_rotation_translation();
+                        rigid_body.linear_velocity =
+                            (target_translation - translation) / DELTA_TIME;
+                        let (axis, angle) = (target_rotation * rotation.inverse()).to_axis_angle();
+                        let angle = if angle > std::f32::consts::PI {
+                            angle - std::f32::consts::TAU
+                        } else {
+                            angle
+                        };
+                        rigid_body.angular_velocity = axis * angle / DELTA_TIME;
+                    }
+                    _ => {
+                        let mut local_transform = world.get::<&mut LocalTransform>(entity).unwrap();
+                        local_transform.update_rotation_translation_from_affine(&global_from_local);
+
+                        let mut global_transform =
+                            world.get::<&mut GlobalTransform>(entity).unwrap();
+                        *global_transform = (*local_transform).into();
+                    }
+                }
             }
         }
 
@@ -80,10 +175,20 @@ pub fn hands_system_inner(world: &mut World, input_context: &InputContext) {
 
         // Apply to AnimationController
         animation_controller.blend_amount = grip_value;
+
+        // Curl each finger with how the controller is being held. A tracked hand poses its own fingers.
+        if let Some(hand_animation) = hand_animation {
+            if input_mode == InputMode::Controller {
+                hand_animation.update(&controller_state, DELTA_TIME);
+            }
+        }
     }
 }
 
-/// Convenience function to add a Hand, Collider and corresponding Mesh to the world
+/// Convenience function to add a Hand, Collider and corresponding Mesh to the world. If the model's animations move
+/// its fingers, it's also given a `HandAnimation`, so each finger follows the controller's trigger, grip and touch
+/// sensors. If the model uses the Oculus skeleton, it's also given `HandJoints` and a `HandSkeleton`, so it follows
+/// the player's hand when hand tracking is enabled and they aren't holding a controller.
 pub fn add_hand(
     models: &std::collections::HashMap<String, World>,
     handedness: Handedness,
@@ -116,16 +221,29 @@ pub fn add_hand(
     world
         .insert(hand_entity, (collider, hand_component))
         .unwrap();
+
+    if let Some(hand_animation) = HandAnimation::from_hand_model(world, hand_entity) {
+        world.insert_one(hand_entity, hand_animation).unwrap();
+    }
+
+    if let Some(hand_skeleton) = HandSkeleton::from_hand_model(world, hand_entity, handedness) {
+        let hand_joints = match handedness {
+            Handedness::Left => HandJoints::left(),
+            Handedness::Right => HandJoints::right(),
+        };
+        world
+            .insert(hand_entity, (hand_joints, hand_skeleton))
+            .unwrap();
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use approx::assert_relative_eq;
-    use glam::Vec3;
     use hecs::Entity;
 
-    use crate::components::{LocalTransform, RigidBody};
+    use crate::components::{physics::BodyType, LocalTransform, RigidBody};
 
     #[test]
     pub fn test_hands_system() {
@@ -143,6 +261,70 @@ mod tests {
         assert_relative_eq!(animation_controller.blend_amount, 0.0);
     }
 
+    #[test]
+    pub fn test_hands_system_hand_tracking() {
+        // Only the left hand is tracked, so the right hand still follows its controller.
+        let (mut world, input_context) = setup();
+        let left_hand = add_hand_to_world(&mut world, None);
+        let right_hand = world.spawn((
+            AnimationController::default(),
+            Hand::right(),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        tick(&mut world, &input_context);
+
+        let stage_from_palm = input_context.left.stage_from_hand_joints().unwrap()
+            [xr::HandJoint::PALM.into_raw() as usize]
+            .transform;
+        let (local_transform, hand) = world
+            .query_one_mut::<(&LocalTransform, &Hand)>(left_hand)
+            .unwrap();
+        assert_relative_eq!(
+            local_transform.translation,
+            stage_from_palm.translation.into()
+        );
+        assert_relative_eq!(hand.grip_value, 0.0);
+        assert!(!hand.grip_button_just_pressed);
+        assert_eq!(hand.linear_velocity, Vec3::ZERO);
+        assert_eq!(hand.angular_velocity, Vec3::ZERO);
+
+        let local_transform = world.get::<&LocalTransform>(right_hand).unwrap();
+        assert_relative_eq!(local_transform.translation, [0.2, 1.4, -0.5].into());
+    }
+
+    #[test]
+    pub fn test_hands_system_hand_animation() {
+        // The right hand's controller is at rest, so its fingers uncurl. The left hand is tracked, so its fingers are
+        // left alone.
+        let (mut world, input_context) = setup();
+        let hand_animation = HandAnimation {
+            finger_curls: [1.; 5],
+            target_fingers: vec![Some(0)],
+            speed: 10.,
+        };
+        let left_hand = add_hand_to_world(&mut world, None);
+        world.insert_one(left_hand, hand_animation.clone()).unwrap();
+        let right_hand = world.spawn((
+            AnimationController::default(),
+            Hand::right(),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+            hand_animation,
+        ));
+
+        tick(&mut world, &input_context);
+
+        let left_hand_animation = world.get::<&HandAnimation>(left_hand).unwrap();
+        assert_relative_eq!(&left_hand_animation.finger_curls[..], &[1.; 5][..]);
+        let right_hand_animation = world.get::<&HandAnimation>(right_hand).unwrap();
+        assert_relative_eq!(
+            &right_hand_animation.finger_curls[..],
+            &[1. - 10. * DELTA_TIME; 5][..]
+        );
+    }
+
     #[test]
     pub fn test_move_grabbed_objects() {
         let (mut world, input_context) = setup();
@@ -160,6 +342,7 @@ mod tests {
         let grabbed_entity = GrabbedEntity {
             entity,
             grip_from_local: Default::default(),
+            previous_body_type: None,
         };
         add_hand_to_world(&mut world, Some(grabbed_entity));
 
@@ -172,6 +355,45 @@ mod tests {
         assert_relative_eq!(local_transform.scale, expected_scale);
     }
 
+    #[test]
+    pub fn test_physics_grabbed_objects_are_pulled_by_velocity() {
+        let (mut world, input_context) = setup();
+
+        let entity = world.spawn((
+            Grabbed,
+            Grabbable {
+                mode: GrabMode::Physics,
+                ..Default::default()
+            },
+            RigidBody::default(),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+        let grabbed_entity = GrabbedEntity {
+            entity,
+            grip_from_local: Default::default(),
+            previous_body_type: Some(BodyType::Dynamic),
+        };
+        add_hand_to_world(&mut world, Some(grabbed_entity));
+
+        tick(&mut world, &input_context);
+
+        // The entity is left to the physics simulation, with the velocity that carries it to the hand in one step.
+        let local_transform = world.get::<&LocalTransform>(entity).unwrap();
+        assert_relative_eq!(local_transform.translation, Vec3::ZERO);
+        let rigid_body = world.get::<&RigidBody>(entity).unwrap();
+        assert_relative_eq!(
+            rigid_body.linear_velocity * DELTA_TIME,
+            [-0.2, 1.4, -0.5].into(),
+            epsilon = 0.0001
+        );
+        assert_relative_eq!(
+            rigid_body.angular_velocity * DELTA_TIME,
+            Vec3::X * std::f32::consts::FRAC_PI_2,
+            epsilon = 0.001
+        );
+    }
+
     #[test]
     pub fn test_ungrabbed_object_do_not_move() {
         let (mut world, input_context) = setup();
@@ -184,6 +406,7 @@ mod tests {
         let grabbed_entity = GrabbedEntity {
             entity,
             grip_from_local: Default::default(),
+            previous_body_type: None,
         };
         add_hand_to_world(&mut world, Some(grabbed_entity));
 
@@ -203,7 +426,7 @@ mod tests {
     }
 
     fn tick(world: &mut World, input_context: &InputContext) {
-        hands_system_inner(world, input_context);
+        hands_system_inner(world, input_context, &InputBindings::default());
     }
 
     fn add_hand_to_world(world: &mut World, grabbed_entity: Option<GrabbedEntity>) -> Entity {
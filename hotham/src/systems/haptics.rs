@@ -1,58 +1,346 @@
-use openxr::{Duration, HapticVibration};
+use hecs::{Entity, World};
+use openxr::{Duration, HapticVibration, Path};
 
 use crate::{
-    contexts::{HapticContext, XrContext},
+    components::{hand::Handedness, haptic::HapticEffect, Haptic, HapticClip, HapticPulse},
+    contexts::{
+        xr_context::{apply_haptic_amplitude_envelope, apply_haptic_pcm, MAX_HAPTIC_SAMPLES},
+        HapticContext, XrContext,
+    },
     Engine,
 };
 static HAPTIC_FREQUENCY: f32 = 400.;
-static HAPTIC_DURATION: i64 = 1e+8 as _; // 100ms
+static HAPTIC_DURATION: u64 = 1e+8 as _; // 100ms
+
+/// What should be sent to a controller this frame
+#[derive(Debug, Clone, PartialEq)]
+enum HapticOutput {
+    Pulse(HapticPulse),
+    /// The samples of a clip from `start`, played after the ones already sent if `append` is set. The runtime only
+    /// takes as many samples as it has room for, so the rest are sent again next frame.
+    Clip {
+        entity: Entity,
+        clip: HapticClip,
+        start: usize,
+        append: bool,
+    },
+}
 
 /// Triggers the application of vibrations to the appropriate user input device at prescribed amplitude, frequency, and duration given a Hotham::resources::XrContent and Hotham::resources::HapticContext.
 ///
-/// During each tick of the Hotham engine, haptic feedback is applied to generate a HapticVibration
-/// event which propagates to the appropriate user input device.
+/// During each tick of the Hotham engine, the next pulse of each [`Haptic`] component whose previous pulse has
+/// finished is applied, along with any feedback requested through the [`HapticContext`] this frame. Clips are
+/// streamed to the runtime with `XR_FB_haptic_pcm` where it's supported.
 ///
 /// Basic usage:
 /// ```ignore
 /// fn tick (...) {
-///    apply_haptic_feedback(xr_context, haptic_context)
+///    haptics_system(engine)
 /// }
 /// ```
 pub fn haptics_system(engine: &mut Engine) {
-    haptics_system_inner(&mut engine.xr_context, &mut engine.haptic_context)
+    let now = engine
+        .xr_context
+        .frame_state
+        .predicted_display_time
+        .as_nanos();
+    let runtime_capabilities = &engine.xr_context.runtime_capabilities;
+    let clips_supported = runtime_capabilities.has_haptic_pcm()
+        || runtime_capabilities.has_haptic_amplitude_envelope();
+    let outputs = haptics_system_inner(
+        &mut engine.world,
+        &mut engine.haptic_context,
+        now,
+        clips_supported,
+    );
+    apply_haptic_outputs(&mut engine.world, &engine.xr_context, &outputs);
 }
 
-fn haptics_system_inner(xr_context: &mut XrContext, haptic_context: &mut HapticContext) {
+/// Work out which pulses and clips should be sent this frame, given the current XR time in nanoseconds and whether
+/// the runtime can play clips
+fn haptics_system_inner(
+    world: &mut World,
+    haptic_context: &mut HapticContext,
+    now: i64,
+    clips_supported: bool,
+) -> Vec<(Handedness, HapticOutput)> {
+    let mut outputs = Vec::new();
+
+    for (entity, haptic) in world.query_mut::<&mut Haptic>() {
+        if matches!(haptic.next_pulse_time, Some(next_pulse_time) if now < next_pulse_time) {
+            // Keep sending the rest of the clip that's playing.
+            if let Some((clip, start)) = &haptic.pcm_clip {
+                outputs.push((
+                    haptic.handedness,
+                    HapticOutput::Clip {
+                        entity,
+                        clip: clip.clone(),
+                        start: *start,
+                        append: true,
+                    },
+                ));
+            }
+            continue;
+        }
+        haptic.pcm_clip = None;
+
+        // The runtime can only play constant vibrations, so play the clip as pulses instead.
+        if !clips_supported {
+            if let Some(HapticEffect::Clip(clip)) = haptic.effects.front() {
+                let pulses = clip.to_pulses();
+                haptic.effects.pop_front();
+                for pulse in pulses.into_iter().rev() {
+                    haptic.effects.push_front(HapticEffect::Pulse(pulse));
+                }
+            }
+        }
+
+        haptic.next_pulse_time = match haptic.effects.pop_front() {
+            Some(HapticEffect::Pulse(pulse)) => {
+                outputs.push((haptic.handedness, HapticOutput::Pulse(pulse)));
+                Some(now + (pulse.duration + pulse.gap).as_nanos() as i64)
+            }
+            Some(HapticEffect::Clip(clip)) => {
+                outputs.push((
+                    haptic.handedness,
+                    HapticOutput::Clip {
+                        entity,
+                        clip: clip.clone(),
+                        start: 0,
+                        append: false,
+                    },
+                ));
+                let end = now + clip.duration().as_nanos() as i64;
+                haptic.pcm_clip = Some((clip, 0));
+                Some(end)
+            }
+            None => None,
+        };
+    }
+
+    let haptic_duration = std::time::Duration::from_nanos(HAPTIC_DURATION);
+    for (handedness, amplitude) in [
+        (
+            Handedness::Left,
+            &mut haptic_context.left_hand_amplitude_this_frame,
+        ),
+        (
+            Handedness::Right,
+            &mut haptic_context.right_hand_amplitude_this_frame,
+        ),
+    ] {
+        if *amplitude != 0. {
+            outputs.push((
+                handedness,
+                HapticOutput::Pulse(
+                    HapticPulse::new(*amplitude, haptic_duration).with_frequency(HAPTIC_FREQUENCY),
+                ),
+            ));
+
+            // Reset the value
+            *amplitude = 0.;
+        }
+    }
+
+    outputs
+}
+
+fn apply_haptic_outputs(
+    world: &mut World,
+    xr_context: &XrContext,
+    outputs: &[(Handedness, HapticOutput)],
+) {
     let input = &xr_context.input;
 
-    let haptic_duration = Duration::from_nanos(HAPTIC_DURATION);
-    if haptic_context.left_hand_amplitude_this_frame != 0. {
-        let event = HapticVibration::new()
-            .amplitude(haptic_context.left_hand_amplitude_this_frame)
-            .frequency(HAPTIC_FREQUENCY)
-            .duration(haptic_duration);
+    for (handedness, output) in outputs {
+        let subaction_path = match handedness {
+            Handedness::Left => input.left_hand_subaction_path,
+            Handedness::Right => input.right_hand_subaction_path,
+        };
+        match output {
+            HapticOutput::Pulse(pulse) => {
+                let event = HapticVibration::new()
+                    .amplitude(pulse.amplitude)
+                    .frequency(pulse.frequency)
+                    .duration(Duration::from_nanos(pulse.duration.as_nanos() as _));
+
+                input
+                    .haptic_feedback_action
+                    .apply_feedback(&xr_context.session, subaction_path, &event)
+                    .expect("Unable to apply haptic feedback!");
+            }
+            HapticOutput::Clip {
+                entity,
+                clip,
+                start,
+                append,
+            } => {
+                let consumed = apply_haptic_clip(xr_context, subaction_path, clip, *start, *append);
+                if let Ok(mut haptic) = world.get::<&mut Haptic>(*entity) {
+                    let position = start + consumed;
+                    haptic.pcm_clip =
+                        (position < clip.samples().len()).then(|| (clip.clone(), position));
+                }
+            }
+        }
+    }
+}
+
+/// Send the samples of a clip from `start` to the runtime, returning how many it took
+fn apply_haptic_clip(
+    xr_context: &XrContext,
+    subaction_path: Path,
+    clip: &HapticClip,
+    start: usize,
+    append: bool,
+) -> usize {
+    let action = &xr_context.input.haptic_feedback_action;
+    if xr_context.runtime_capabilities.has_haptic_pcm() {
+        return apply_haptic_pcm(
+            &xr_context.session,
+            action,
+            subaction_path,
+            &clip.samples()[start..],
+            clip.sample_rate(),
+            append,
+        )
+        .expect("Unable to apply haptic feedback!");
+    }
+
+    // Without PCM, the whole clip is played at once as its amplitude envelope.
+    apply_haptic_amplitude_envelope(
+        &xr_context.session,
+        action,
+        subaction_path,
+        &clip.amplitude_envelope(MAX_HAPTIC_SAMPLES),
+        clip.duration(),
+    )
+    .expect("Unable to apply haptic feedback!");
+    clip.samples().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    pub fn test_haptics_system() {
+        let mut world = World::new();
+        let mut haptic_context = HapticContext::default();
+
+        let mut haptic = Haptic::right();
+        let first =
+            HapticPulse::new(0.5, Duration::from_millis(10)).with_gap(Duration::from_millis(5));
+        let second = HapticPulse::thunk();
+        haptic.play([first, second]);
+        let entity = world.spawn((haptic,));
+
+        // The first pulse starts straight away.
+        let pulses = haptics_system_inner(&mut world, &mut haptic_context, 0, true);
+        assert_eq!(
+            pulses,
+            vec![(Handedness::Right, HapticOutput::Pulse(first))]
+        );
+
+        // Nothing else starts until it and its gap have finished.
+        let pulses = haptics_system_inner(&mut world, &mut haptic_context, 14_000_000, true);
+        assert!(pulses.is_empty());
+
+        let pulses = haptics_system_inner(&mut world, &mut haptic_context, 15_000_000, true);
+        assert_eq!(
+            pulses,
+            vec![(Handedness::Right, HapticOutput::Pulse(second))]
+        );
+        assert!(world.get::<&Haptic>(entity).unwrap().is_playing());
+
+        // Once the last pulse has finished, the component stops playing.
+        let pulses = haptics_system_inner(&mut world, &mut haptic_context, 1_000_000_000, true);
+        assert!(pulses.is_empty());
+        assert!(!world.get::<&Haptic>(entity).unwrap().is_playing());
+    }
+
+    #[test]
+    pub fn test_haptic_context_requests() {
+        let mut world = World::new();
+        let mut haptic_context = HapticContext::default();
+        haptic_context.request_haptic_feedback(0.5, Handedness::Left);
+
+        let pulses = haptics_system_inner(&mut world, &mut haptic_context, 0, true);
+        assert_eq!(pulses.len(), 1);
+        assert_eq!(pulses[0].0, Handedness::Left);
+        match &pulses[0].1 {
+            HapticOutput::Pulse(pulse) => assert_eq!(pulse.amplitude, 0.5),
+            output => panic!("Expected a pulse, got {output:?}"),
+        }
+        assert_eq!(haptic_context.left_hand_amplitude_this_frame, 0.);
+    }
+
+    #[test]
+    pub fn test_haptic_clips() {
+        let mut world = World::new();
+        let mut haptic_context = HapticContext::default();
+
+        // A 20ms clip
+        let clip = HapticClip::new(vec![0.5; 20], 1000.);
+        let mut haptic = Haptic::left();
+        haptic.play_clip(&clip);
+        haptic.play([HapticPulse::tick()]);
+        let entity = world.spawn((haptic,));
+
+        // The clip starts straight away.
+        let outputs = haptics_system_inner(&mut world, &mut haptic_context, 0, true);
+        let expected = HapticOutput::Clip {
+            entity,
+            clip: clip.clone(),
+            start: 0,
+            append: false,
+        };
+        assert_eq!(outputs, vec![(Handedness::Left, expected)]);
 
-        input
-            .haptic_feedback_action
-            .apply_feedback(&xr_context.session, input.left_hand_subaction_path, &event)
-            .expect("Unable to apply haptic feedback!");
+        // The runtime only took some of the samples, so the rest are sent next frame.
+        world.get::<&mut Haptic>(entity).unwrap().pcm_clip = Some((clip.clone(), 12));
+        let outputs = haptics_system_inner(&mut world, &mut haptic_context, 10_000_000, true);
+        let expected = HapticOutput::Clip {
+            entity,
+            clip: clip.clone(),
+            start: 12,
+            append: true,
+        };
+        assert_eq!(outputs, vec![(Handedness::Left, expected)]);
 
-        // Reset the value
-        haptic_context.left_hand_amplitude_this_frame = 0.;
+        // Once the clip has finished, the next pulse starts.
+        let outputs = haptics_system_inner(&mut world, &mut haptic_context, 20_000_000, true);
+        assert_eq!(
+            outputs,
+            vec![(Handedness::Left, HapticOutput::Pulse(HapticPulse::tick()))]
+        );
+        assert!(world.get::<&Haptic>(entity).unwrap().pcm_clip.is_none());
     }
 
-    if haptic_context.right_hand_amplitude_this_frame != 0. {
-        let event = HapticVibration::new()
-            .amplitude(haptic_context.right_hand_amplitude_this_frame)
-            .frequency(HAPTIC_FREQUENCY)
-            .duration(haptic_duration);
+    #[test]
+    pub fn test_haptic_clips_as_pulses() {
+        let mut world = World::new();
+        let mut haptic_context = HapticContext::default();
 
-        input
-            .haptic_feedback_action
-            .apply_feedback(&xr_context.session, input.right_hand_subaction_path, &event)
-            .expect("Unable to apply haptic feedback!");
+        let clip = HapticClip::new(vec![0.5; 20], 1000.);
+        let mut haptic = Haptic::right();
+        haptic.play_clip(&clip);
+        world.spawn((haptic,));
 
-        // Reset the value
-        haptic_context.right_hand_amplitude_this_frame = 0.;
+        // The runtime can't play clips, so each 10ms of the clip is played as a pulse.
+        let pulse = HapticPulse::new(0.5, HapticClip::PULSE_DURATION);
+        let outputs = haptics_system_inner(&mut world, &mut haptic_context, 0, false);
+        assert_eq!(
+            outputs,
+            vec![(Handedness::Right, HapticOutput::Pulse(pulse))]
+        );
+        let outputs = haptics_system_inner(&mut world, &mut haptic_context, 10_000_000, false);
+        assert_eq!(
+            outputs,
+            vec![(Handedness::Right, HapticOutput::Pulse(pulse))]
+        );
+        let outputs = haptics_system_inner(&mut world, &mut haptic_context, 20_000_000, false);
+        assert!(outputs.is_empty());
     }
 }
@@ -0,0 +1,169 @@
+use crate::{
+    components::{CastsShadows, GlobalTransform},
+    rendering::{
+        light::{Light, LIGHT_TYPE_DIRECTIONAL, MAX_LIGHTS},
+        scene_data::SceneData,
+    },
+    Engine,
+};
+use hecs::World;
+
+/// Lights system
+/// Walks through each entity with a [`Light`] and a [`GlobalTransform`] and uploads them to the shaders.
+///
+/// Directional lights, lights without a range and lights that cast shadows light every fragment, so they go in
+/// [`SceneData`], where only the first [`MAX_LIGHTS`] of them are used. Every other point and spot light is
+/// assigned to the clusters of the view it touches, which lets scenes have dozens of small lights.
+///
+/// The lights in [`SceneData`] and `render_context.clustered_lights` are replaced every time this system runs, so
+/// if you'd rather set them yourself, don't run it.
+pub fn lights_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    let render_context = &mut engine.render_context;
+    lights_system_inner(
+        world,
+        &mut render_context.scene_data,
+        &mut render_context.local_shadow_casters,
+        &mut render_context.clustered_lights,
+    );
+}
+
+pub(crate) fn lights_system_inner(
+    world: &mut World,
+    scene_data: &mut SceneData,
+    local_shadow_casters: &mut [bool; MAX_LIGHTS],
+    clustered_lights: &mut Vec<Light>,
+) {
+    clustered_lights.clear();
+    let mut lights = Vec::new();
+    for (_, (light, global_transform, casts_shadows)) in
+        world.query_mut::<(&Light, &GlobalTransform, Option<&CastsShadows>)>()
+    {
+        let light = light.transformed(&global_transform.0);
+        let casts_shadows = casts_shadows.is_some() && light.light_type != LIGHT_TYPE_DIRECTIONAL;
+        if light.light_type == LIGHT_TYPE_DIRECTIONAL || light.falloff <= 0. || casts_shadows {
+            lights.push((light, casts_shadows));
+        } else {
+            clustered_lights.push(light);
+        }
+    }
+
+    if lights.len() > MAX_LIGHTS {
+        println!("[HOTHAM_LIGHTS] WARNING: There are more than {MAX_LIGHTS} directional, unbounded or shadow casting lights in the world, some will be ignored!");
+    }
+    let mut lights = lights.into_iter();
+    for (slot, casts_shadows) in scene_data.lights.iter_mut().zip(local_shadow_casters) {
+        (*slot, *casts_shadows) = lights.next().unwrap_or_else(|| (Light::none(), false));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::light::{LIGHT_TYPE_NONE, LIGHT_TYPE_POINT, LIGHT_TYPE_SPOT};
+    use approx::assert_relative_eq;
+    use glam::{Affine3A, Quat, Vec3};
+
+    #[test]
+    pub fn test_lights_system() {
+        let mut world = World::new();
+        let mut scene_data = SceneData::default();
+
+        // A point light, moved by its transform
+        let point = Light::new_point(Vec3::ZERO, 10., 1., Vec3::ONE);
+        world.spawn((
+            point,
+            GlobalTransform(Affine3A::from_translation([1., 2., 3.].into())),
+        ));
+
+        // A spotlight, rotated to face down
+        let spot = Light::new_spotlight(
+            Vec3::NEG_Z,
+            10.,
+            1.,
+            Vec3::ONE,
+            Vec3::ZERO,
+            0.,
+            std::f32::consts::FRAC_PI_4,
+        );
+        world.spawn((
+            spot,
+            GlobalTransform(Affine3A::from_quat(Quat::from_rotation_x(
+                -std::f32::consts::FRAC_PI_2,
+            ))),
+        ));
+
+        // A directional light, which lights every fragment
+        world.spawn((
+            Light::new_directional(Vec3::NEG_Y, 1., Vec3::ONE),
+            GlobalTransform::default(),
+        ));
+
+        // Lights without a transform are ignored.
+        world.spawn((Light::new_point(Vec3::ZERO, 10., 1., Vec3::ONE),));
+
+        let mut local_shadow_casters = [true; MAX_LIGHTS];
+        let mut clustered_lights = Vec::new();
+        lights_system_inner(
+            &mut world,
+            &mut scene_data,
+            &mut local_shadow_casters,
+            &mut clustered_lights,
+        );
+
+        // The point and spot lights have a range and don't cast shadows, so they're clustered.
+        assert_eq!(clustered_lights.len(), 2);
+        let lights = &clustered_lights;
+        let point = lights
+            .iter()
+            .find(|l| l.light_type == LIGHT_TYPE_POINT)
+            .unwrap();
+        assert_relative_eq!(point.position, Vec3::new(1., 2., 3.));
+
+        let spot = lights
+            .iter()
+            .find(|l| l.light_type == LIGHT_TYPE_SPOT)
+            .unwrap();
+        assert_relative_eq!(spot.direction, Vec3::NEG_Y, epsilon = 0.0001);
+
+        let lights = &scene_data.lights;
+        assert_eq!(lights[0].light_type, LIGHT_TYPE_DIRECTIONAL);
+        assert_eq!(
+            lights
+                .iter()
+                .filter(|l| l.light_type == LIGHT_TYPE_NONE)
+                .count(),
+            MAX_LIGHTS - 1
+        );
+        assert_eq!(local_shadow_casters, [false; MAX_LIGHTS]);
+
+        // Lights that cast shadows need a shadow map, so they light every fragment.
+        let shadow_casting_point = Light::new_point(Vec3::ZERO, 10., 1., Vec3::ONE);
+        world.spawn((
+            shadow_casting_point,
+            GlobalTransform::default(),
+            CastsShadows,
+        ));
+        lights_system_inner(
+            &mut world,
+            &mut scene_data,
+            &mut local_shadow_casters,
+            &mut clustered_lights,
+        );
+        assert_eq!(clustered_lights.len(), 2);
+        let shadow_casting_index = scene_data
+            .lights
+            .iter()
+            .position(|l| l.light_type == LIGHT_TYPE_POINT)
+            .unwrap();
+        assert_eq!(
+            local_shadow_casters
+                .iter()
+                .enumerate()
+                .filter(|(_, casts_shadows)| **casts_shadows)
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>(),
+            vec![shadow_casting_index]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use glam::{Affine3A, Quat, Vec2, Vec3};
+use hecs::World;
+
+use crate::{
+    components::{
+        hand::Handedness, locomotion::TurnMode, GlobalTransform, LocalTransform, Locomotion,
+    },
+    contexts::EngineAction,
+    rendering::vignette::Vignette,
+    Engine,
+};
+
+/// How far to the side the turn thumbstick has to be pushed to snap turn
+const SNAP_THRESHOLD: f32 = 0.7;
+
+/// Locomotion system
+/// Moves and turns the rig of each entity with a `Locomotion` component with the controllers' thumbsticks, and
+/// fades the comfort vignette in while it's moving. The thumbsticks are the ones [`EngineAction::Move`] and
+/// [`EngineAction::Turn`] are bound to in [`Engine::input_bindings`].
+///
+/// Run this system before any systems that read the global transforms of entities parented to the stage, such as
+/// [`crate::systems::update_global_transform_system`].
+pub fn locomotion_system(engine: &mut Engine) {
+    let input_context = &engine.input_context;
+    let input_bindings = &engine.input_bindings;
+    let delta_time = engine.xr_context.predicted_display_period().as_secs_f32();
+    locomotion_system_inner(
+        &mut engine.world,
+        |action, handedness| {
+            input_bindings
+                .engine_binding(action, handedness)
+                .vector2(input_context)
+        },
+        input_context.hmd.hmd_in_stage(),
+        delta_time,
+        &mut engine.render_context.vignette,
+    );
+}
+
+pub(crate) fn locomotion_system_inner(
+    world: &mut World,
+    thumbstick: impl Fn(EngineAction, Handedness) -> Vec2,
+    hmd_in_stage: Affine3A,
+    delta_time: f32,
+    vignette: &mut Vignette,
+) {
+    for (_, (locomotion, local_transform, global_transform)) in
+        world.query_mut::<(&mut Locomotion, &mut LocalTransform, &mut GlobalTransform)>()
+    {
+        let move_input = apply_dead_zone(
+            thumbstick(EngineAction::Move, locomotion.move_handedness),
+            locomotion.dead_zone,
+        );
+        let turn_input = apply_dead_zone(
+            thumbstick(EngineAction::Turn, locomotion.turn_handedness),
+            locomotion.dead_zone,
+        )
+        .x;
+        let mut global_from_rig = global_transform.0;
+        let global_from_hmd = global_from_rig * hmd_in_stage;
+
+        // Move along the ground in the direction the player is looking.
+        let forward = global_from_hmd.transform_vector3(Vec3::NEG_Z) * Vec3::new(1., 0., 1.);
+        let forward = forward.normalize_or_zero();
+        let right = Vec3::new(-forward.z, 0., forward.x);
+        let velocity = (forward * move_input.y + right * move_input.x) * locomotion.speed;
+        global_from_rig.translation += (velocity * delta_time).into();
+
+        // Turn around the player's head, so it stays where it is.
+        let mut turning = 0.;
+        let turn_angle = match locomotion.turn_mode {
+            TurnMode::Snap { angle } => {
+                if turn_input.abs() < SNAP_THRESHOLD {
+                    locomotion.has_snapped = false;
+                    0.
+                } else if locomotion.has_snapped {
+                    0.
+                } else {
+                    locomotion.has_snapped = true;
+                    angle * turn_input.signum()
+                }
+            }
+            TurnMode::Smooth { speed } => {
+                turning = turn_input.abs();
+                speed * turn_input * delta_time
+            }
+        };
+        if turn_angle != 0. {
+            let pivot: Vec3 = (global_from_rig * hmd_in_stage).translation.into();
+            global_from_rig = Affine3A::from_translation(pivot)
+                * Affine3A::from_quat(Quat::from_rotation_y(-turn_angle))
+                * Affine3A::from_translation(-pivot)
+                * global_from_rig;
+        }
+
+        local_transform.update_from_affine(&global_from_rig);
+        global_transform.0 = global_from_rig;
+
+        if let Some(max_strength) = locomotion.vignette {
+            vignette.strength = max_strength * move_input.length().max(turning).min(1.);
+        }
+    }
+}
+
+/// Ignore thumbstick movement inside the dead zone, and rescale the rest so movement still starts from zero
+fn apply_dead_zone(thumbstick: Vec2, dead_zone: f32) -> Vec2 {
+    let length = thumbstick.length().min(1.);
+    if length <= dead_zone {
+        return Vec2::ZERO;
+    }
+
+    thumbstick.normalize() * (length - dead_zone) / (1. - dead_zone).max(0.001)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    pub fn test_locomotion_system() {
+        let mut world = World::new();
+        let mut vignette = Vignette::default();
+        let rig = world.spawn((
+            Locomotion::default(),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        // The player is standing half a meter to the right of the centre of the stage, looking down -Z.
+        let hmd_in_stage = Affine3A::from_translation([0.5, 1.6, 0.].into());
+        let run = |world: &mut World, vignette: &mut Vignette, left: Vec2, right: Vec2| {
+            locomotion_system_inner(
+                world,
+                |_, handedness| match handedness {
+                    Handedness::Left => left,
+                    Handedness::Right => right,
+                },
+                hmd_in_stage,
+                0.5,
+                vignette,
+            )
+        };
+
+        // Pushing the left thumbstick forward moves them forward, and fades in the vignette.
+        run(&mut world, &mut vignette, Vec2::Y, Vec2::ZERO);
+        assert_relative_eq!(
+            world.get::<&LocalTransform>(rig).unwrap().translation,
+            Vec3::new(0., 0., -1.)
+        );
+        assert_relative_eq!(vignette.strength, 0.8);
+
+        // Pushing the right thumbstick turns them once around their head, until it's let go.
+        run(&mut world, &mut vignette, Vec2::ZERO, Vec2::X);
+        run(&mut world, &mut vignette, Vec2::ZERO, Vec2::X);
+        assert_eq!(vignette.strength, 0.);
+        let global_from_rig = world.get::<&GlobalTransform>(rig).unwrap().0;
+        let global_from_hmd = global_from_rig * hmd_in_stage;
+        assert_relative_eq!(
+            Vec3::from(global_from_hmd.translation),
+            Vec3::new(0.5, 1.6, -1.),
+            epsilon = 0.0001
+        );
+        assert_relative_eq!(
+            global_from_hmd.transform_vector3(Vec3::NEG_Z),
+            Vec3::new(30_f32.to_radians().sin(), 0., -30_f32.to_radians().cos()),
+            epsilon = 0.0001
+        );
+        run(&mut world, &mut vignette, Vec2::ZERO, Vec2::ZERO);
+        run(&mut world, &mut vignette, Vec2::ZERO, Vec2::X);
+        let global_from_rig = world.get::<&GlobalTransform>(rig).unwrap().0;
+        assert_relative_eq!(
+            global_from_rig.transform_vector3(Vec3::NEG_Z),
+            Vec3::new(60_f32.to_radians().sin(), 0., -60_f32.to_radians().cos()),
+            epsilon = 0.0001
+        );
+
+        // Smooth turning turns a little each frame.
+        world.get::<&mut Locomotion>(rig).unwrap().turn_mode = TurnMode::Smooth {
+            speed: 90_f32.to_radians(),
+        };
+        run(&mut world, &mut vignette, Vec2::ZERO, -Vec2::X);
+        let global_from_rig = world.get::<&GlobalTransform>(rig).unwrap().0;
+        assert_relative_eq!(
+            global_from_rig.transform_vector3(Vec3::NEG_Z),
+            Vec3::new(15_f32.to_radians().sin(), 0., -15_f32.to_radians().cos()),
+            epsilon = 0.0001
+        );
+        assert_relative_eq!(vignette.strength, 0.8);
+    }
+}
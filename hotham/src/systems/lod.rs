@@ -0,0 +1,84 @@
+use crate::{
+    components::{GlobalTransform, Lod, Mesh, HMD},
+    Engine,
+};
+use hecs::{With, World};
+
+/// Level of detail system
+/// Walks through each entity with a [`Lod`] and replaces its [`Mesh`] with the level of detail matching its
+/// distance from the player's head.
+///
+/// Run this system after [`crate::systems::update_global_transform_system`] and before
+/// [`crate::systems::rendering_system`].
+pub fn lod_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    lod_system_inner(world);
+}
+
+pub(crate) fn lod_system_inner(world: &mut World) {
+    let hmd_position = match world
+        .query_mut::<With<&GlobalTransform, &HMD>>()
+        .into_iter()
+        .next()
+    {
+        Some((_, global_transform)) => global_transform.0.translation,
+        None => return,
+    };
+
+    for (_, (lod, mesh, global_transform)) in
+        world.query_mut::<(&Lod, &mut Mesh, &GlobalTransform)>()
+    {
+        let distance = global_transform.0.translation.distance(hmd_position);
+        if let Some(selected) = lod.select(distance) {
+            if selected.handle != mesh.handle {
+                *mesh = selected.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{id_arena::Arena, rendering::mesh_data::MeshData};
+    use glam::Affine3A;
+
+    #[test]
+    pub fn test_lod_system() {
+        let mut world = World::new();
+        let mut arena = Arena::new();
+        let meshes = (0..3)
+            .map(|_| Mesh {
+                handle: arena.alloc(MeshData::new(Vec::new())),
+            })
+            .collect::<Vec<_>>();
+        let lod = Lod::from_meshes(meshes.clone(), 5.);
+
+        world.spawn((
+            HMD {},
+            GlobalTransform(Affine3A::from_translation([0., 1.5, 0.].into())),
+        ));
+        let near = world.spawn((
+            lod.clone(),
+            meshes[2].clone(),
+            GlobalTransform(Affine3A::from_translation([0., 1.5, -1.].into())),
+        ));
+        let middle = world.spawn((
+            lod.clone(),
+            meshes[0].clone(),
+            GlobalTransform(Affine3A::from_translation([0., 1.5, -7.].into())),
+        ));
+        let far = world.spawn((
+            lod,
+            meshes[0].clone(),
+            GlobalTransform(Affine3A::from_translation([0., 1.5, -100.].into())),
+        ));
+
+        lod_system_inner(&mut world);
+
+        let handle = |entity| world.get::<&Mesh>(entity).unwrap().handle;
+        assert_eq!(handle(near), meshes[0].handle);
+        assert_eq!(handle(middle), meshes[1].handle);
+        assert_eq!(handle(far), meshes[2].handle);
+    }
+}
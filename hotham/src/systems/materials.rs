@@ -0,0 +1,77 @@
+use crate::{components::DynamicMaterial, rendering::material::Material, Engine};
+use hecs::World;
+
+/// Materials system
+/// Walks through each entity with a [`DynamicMaterial`] and writes any material that has changed since the last
+/// frame into the materials buffer.
+///
+/// Materials are read from the buffer when draw commands are recorded, so run this system before
+/// [`crate::systems::rendering_system`] for changes to show up in the same frame.
+pub fn materials_system(engine: &mut Engine) {
+    let world = &mut engine.world;
+    let materials = unsafe {
+        engine
+            .render_context
+            .resources
+            .materials_buffer
+            .as_slice_mut()
+    };
+    materials_system_inner(world, materials);
+}
+
+pub(crate) fn materials_system_inner(world: &mut World, materials: &mut [Material]) {
+    for (_, dynamic_material) in world.query_mut::<&DynamicMaterial>() {
+        let slot = match materials.get_mut(dynamic_material.material_id as usize) {
+            Some(slot) => slot,
+            None => {
+                println!(
+                    "[HOTHAM_MATERIALS] WARNING: Material {} does not exist, ignoring..",
+                    dynamic_material.material_id
+                );
+                continue;
+            }
+        };
+
+        // Only materials that have actually changed are written.
+        if *slot != dynamic_material.material {
+            *slot = dynamic_material.material.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_materials_system() {
+        let mut world = World::new();
+        let mut materials = vec![Material::gltf_default(), Material::gltf_default()];
+
+        let mut material = Material::gltf_default();
+        material.set_base_color_factor([1.0, 0.0, 0.0, 1.0]);
+        let entity = world.spawn((DynamicMaterial {
+            material_id: 1,
+            material: material.clone(),
+        },));
+
+        // A material that doesn't exist is ignored
+        world.spawn((DynamicMaterial {
+            material_id: 7,
+            material: Material::unlit_white(),
+        },));
+
+        materials_system_inner(&mut world, &mut materials);
+        assert_eq!(materials[0], Material::gltf_default());
+        assert_eq!(materials[1], material);
+
+        // Changes are picked up on the next run
+        world
+            .get::<&mut DynamicMaterial>(entity)
+            .unwrap()
+            .material
+            .set_emissive_strength(2.0);
+        materials_system_inner(&mut world, &mut materials);
+        assert_eq!(materials[1].emissive_strength(), 2.0);
+    }
+}
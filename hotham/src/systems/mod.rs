@@ -1,25 +1,61 @@
 #![allow(missing_docs)]
 pub mod animation;
 pub mod audio;
+pub mod body_tracking;
+pub mod collider_debug;
+pub mod controller_models;
 pub mod debug;
+pub mod dominant_hand;
 pub mod draw_gui;
+pub mod follow_view;
+pub mod frustum_culling;
+pub mod gaze_pointer;
 pub mod grabbing;
+pub mod hand_gestures;
+pub mod hand_tracking;
 pub mod hands;
 pub mod haptics;
+pub mod lights;
+pub mod locomotion;
+pub mod lod;
+pub mod materials;
 pub mod physics;
 pub mod pointers;
 pub mod rendering;
+pub mod scene;
 pub mod skinning;
+pub mod spatial_anchors;
+pub mod teleport;
+pub mod tracked_devices;
 pub mod update_global_transform;
+pub mod uv_transform;
 
 pub use animation::animation_system;
 pub use audio::audio_system;
+pub use body_tracking::body_tracking_system;
+pub use collider_debug::collider_debug_system;
+pub use controller_models::controller_models_system;
+pub use dominant_hand::dominant_hand_system;
 pub use draw_gui::draw_gui_system;
+pub use follow_view::follow_view_system;
+pub use frustum_culling::frustum_culling_system;
+pub use gaze_pointer::gaze_pointer_system;
 pub use grabbing::grabbing_system;
+pub use hand_gestures::hand_gestures_system;
+pub use hand_tracking::hand_tracking_system;
 pub use hands::hands_system;
 pub use haptics::haptics_system;
+pub use lights::lights_system;
+pub use locomotion::locomotion_system;
+pub use lod::lod_system;
+pub use materials::materials_system;
 pub use physics::physics_system;
 pub use pointers::pointers_system;
-pub use rendering::rendering_system;
+pub use rendering::{rendering_system, simulated_rendering_system};
+pub use scene::scene_system;
 pub use skinning::skinning_system;
+pub use spatial_anchors::spatial_anchors_system;
+pub use teleport::teleport_system;
+pub use tracked_devices::tracked_devices_system;
 pub use update_global_transform::update_global_transform_system;
+pub use uv_transform::uv_transform_system;
@@ -178,6 +178,7 @@ fn update_rigid_bodies_from_world(physics_context: &mut PhysicsContext, world: &
         }
 
         let component_linear_velocity = na_vector_from_glam(rigid_body_component.linear_velocity);
+        let component_angular_velocity = na_vector_from_glam(rigid_body_component.angular_velocity);
 
         match body_type {
             BodyType::KinematicPositionBased => {
@@ -197,10 +198,13 @@ fn update_rigid_bodies_from_world(physics_context: &mut PhysicsContext, world: &
                 }
             }
             BodyType::Dynamic => {
-                // Update the linear velocity if it's been updated
+                // Update the linear and angular velocities if they've been updated
                 if rigid_body.linvel() != &component_linear_velocity {
                     rigid_body.set_linvel(component_linear_velocity, true);
                 }
+                if rigid_body.angvel() != &component_angular_velocity {
+                    rigid_body.set_angvel(component_angular_velocity, true);
+                }
 
                 // Teleport the entity
                 if world.get::<&Teleport>(entity).is_ok() {
@@ -288,8 +292,9 @@ fn update_world_from_physics(physics_context: &PhysicsContext, world: &mut hecs:
             local_transform.update_from_isometry(rigid_body.position());
         }
 
-        // Update the component's linear velocity.
+        // Update the component's linear and angular velocities.
         rigid_body_component.linear_velocity = glam_vec_from_na(rigid_body.linvel());
+        rigid_body_component.angular_velocity = glam_vec_from_na(rigid_body.angvel());
 
         // Update the component's mass
         rigid_body_component.mass = rigid_body.mass();
@@ -1,35 +1,44 @@
 use ash::vk;
 use egui::Pos2;
 use glam::{Affine3A, Quat, Vec2, Vec3};
-use hecs::{With, World};
+use hecs::{Entity, World};
 use rapier3d::na::{Isometry3, Orthographic3, Point3};
-use rapier3d::prelude::{InteractionGroups, QueryFilter, Ray};
+use rapier3d::prelude::{Group, InteractionGroups, QueryFilter, Ray};
 
 pub const POSITION_OFFSET: Vec3 = Vec3::new(4.656613e-10, 0.029968515, 0.0741747);
 pub const ROTATION_OFFSET: Quat = Quat::from_xyzw(0.8274912, 0.03413791, -0.050611533, -0.5581499);
 
-use crate::util::na_vector_from_glam;
+use crate::util::{glam_vec_from_na, na_vector_from_glam};
 use crate::{
     components::{
-        hand::Handedness, panel::PanelInput, stage, Info, LocalTransform, Panel, Pointer, Visible,
+        hand::Handedness, panel::PanelInput, pointer::PointerEvent, stage, LocalTransform, Panel,
+        Pointer, Visible,
     },
-    contexts::{physics_context::PANEL_COLLISION_GROUP, InputContext, PhysicsContext},
+    contexts::{EngineAction, InputBindings, InputContext, PhysicsContext},
     Engine,
 };
 
+/// How far the trigger has to be pulled to press what the pointer is pointing at
+const PRESS_THRESHOLD: f32 = 0.7;
+/// How far the trigger has to be let go to release it again
+const RELEASE_THRESHOLD: f32 = 0.3;
+
 /// Pointers system
-/// Allows users to interact with `Panel`s using their controllers
+/// Allows users to interact with `Panel`s and other colliders using their controllers, see [`Pointer`]. Pointers
+/// press with the input [`EngineAction::Point`] is bound to in [`Engine::input_bindings`], the trigger by default.
 pub fn pointers_system(engine: &mut Engine) {
     let world = &mut engine.world;
     let input_context = &mut engine.input_context;
+    let input_bindings = &engine.input_bindings;
     let physics_context = &mut engine.physics_context;
 
-    pointers_system_inner(world, input_context, physics_context);
+    pointers_system_inner(world, input_context, input_bindings, physics_context);
 }
 
 pub fn pointers_system_inner(
     world: &mut World,
     input_context: &InputContext,
+    input_bindings: &InputBindings,
     physics_context: &mut PhysicsContext,
 ) {
     // Get the isometry of the stage
@@ -41,21 +50,24 @@ pub fn pointers_system_inner(
 
     let grip_from_local = Affine3A::from_rotation_translation(ROTATION_OFFSET, POSITION_OFFSET);
 
-    for (_, (pointer, local_transform)) in world
-        .query::<With<(&mut Pointer, &mut LocalTransform), &Visible>>()
+    for (_, (pointer, local_transform, visible)) in world
+        .query::<(&mut Pointer, &mut LocalTransform, &Visible)>()
         .iter()
     {
+        pointer.events.clear();
+        if !visible.0 {
+            update_pointer(pointer, None, false);
+            continue;
+        }
+
         // Get the position of the pointer in stage space.
-        let (stage_from_grip, trigger_value) = match pointer.handedness {
-            Handedness::Left => (
-                input_context.left.stage_from_grip(),
-                input_context.left.trigger_analog(),
-            ),
-            Handedness::Right => (
-                input_context.right.stage_from_grip(),
-                input_context.right.trigger_analog(),
-            ),
+        let stage_from_grip = match pointer.handedness {
+            Handedness::Left => input_context.left.stage_from_grip(),
+            Handedness::Right => input_context.right.stage_from_grip(),
         };
+        let trigger_value = input_bindings
+            .engine_binding(EngineAction::Point, pointer.handedness)
+            .float(input_context);
 
         // Compose transform
         let global_from_local = global_from_stage * stage_from_grip * grip_from_local;
@@ -68,13 +80,21 @@ pub fn pointers_system_inner(
         let ray_direction = na_vector_from_glam(local_transform.rotation * Vec3::Y);
         let ray_origin = na_vector_from_glam(local_transform.translation);
 
+        // Hold the press until the trigger is mostly let go, so a trigger resting near the threshold doesn't flicker.
+        let trigger_pressed = if pointer.pressed_entity.is_some() {
+            trigger_value > RELEASE_THRESHOLD
+        } else {
+            trigger_value > PRESS_THRESHOLD
+        };
+
         // Sweet baby ray
         let ray = Ray::new(ray_origin.into(), ray_direction);
-        let max_toi = 40.0;
+        let max_toi = pointer.max_distance;
         let solid = true;
-        let groups = InteractionGroups::new(PANEL_COLLISION_GROUP, PANEL_COLLISION_GROUP);
+        let groups = InteractionGroups::new(Group::all(), pointer.collision_filter);
         let filter = QueryFilter::new().groups(groups);
 
+        let mut hit = None;
         if let Some((handle, toi)) = physics_context.query_pipeline.cast_ray(
             &physics_context.rigid_bodies,
             &physics_context.colliders,
@@ -88,27 +108,61 @@ pub fn pointers_system_inner(
             let hit_point = ray.point_at(toi); // Same as: `ray.origin + ray.dir * toi`
             let hit_collider = physics_context.colliders.get(handle).unwrap();
             let entity = unsafe { world.find_entity_from_id(hit_collider.user_data as _) };
-            match world.get::<&mut Panel>(entity) {
-                Ok(mut panel) => {
-                    let panel_transform = hit_collider.position();
-                    let cursor_location = get_cursor_location_for_panel(
-                        &hit_point,
-                        panel_transform,
-                        &panel.resolution,
-                        &panel.world_size,
-                    );
-                    panel.input = Some(PanelInput {
-                        cursor_location,
-                        trigger_value,
-                    });
-                }
-                Err(_) => {
-                    let info = world.get::<&Info>(entity).map(|i| format!("{:?}", *i));
-                    println!("[HOTHAM_POINTERS] Ray collided with object that does not have a panel: {entity:?} - {info:?}");
-                }
+            if let Ok(mut panel) = world.get::<&mut Panel>(entity) {
+                let panel_transform = hit_collider.position();
+                let cursor_location = get_cursor_location_for_panel(
+                    &hit_point,
+                    panel_transform,
+                    &panel.resolution,
+                    &panel.world_size,
+                );
+                panel.input = Some(PanelInput {
+                    cursor_location,
+                    trigger_value,
+                });
+            }
+            hit = Some((entity, glam_vec_from_na(&hit_point.coords)));
+        }
+
+        update_pointer(pointer, hit, trigger_pressed);
+    }
+}
+
+/// Update what the pointer is hovering over and pressing, given what it hit this frame and whether the trigger is
+/// pressed, and record what changed in its `events`
+fn update_pointer(pointer: &mut Pointer, hit: Option<(Entity, Vec3)>, trigger_pressed: bool) {
+    let hovered_entity = hit.map(|(entity, _)| entity);
+    let hit_point = hit.map(|(_, point)| point);
+
+    if pointer.hovered_entity != hovered_entity {
+        if let Some(entity) = pointer.hovered_entity {
+            pointer.events.push(PointerEvent::HoverEnd { entity });
+        }
+        if let Some(entity) = hovered_entity {
+            pointer.events.push(PointerEvent::HoverStart { entity });
+        }
+    }
+
+    match (pointer.pressed_entity, trigger_pressed, hit) {
+        (None, true, Some((entity, point))) => {
+            pointer.pressed_entity = Some(entity);
+            pointer.events.push(PointerEvent::Pressed { entity, point });
+        }
+        (Some(entity), false, _) => {
+            pointer.pressed_entity = None;
+            let point = hit_point.filter(|_| hovered_entity == Some(entity));
+            pointer
+                .events
+                .push(PointerEvent::Released { entity, point });
+            if let Some(point) = point {
+                pointer.events.push(PointerEvent::Clicked { entity, point });
             }
         }
+        _ => {}
     }
+
+    pointer.hovered_entity = hovered_entity;
+    pointer.hit_point = hit_point;
 }
 
 fn get_cursor_location_for_panel(
@@ -225,11 +279,8 @@ mod tests {
         ));
 
         let pointer_entity = world.spawn((
-            Visible {},
-            Pointer {
-                handedness: Handedness::Left,
-                trigger_value: 0.0,
-            },
+            Visible(true),
+            Pointer::new(Handedness::Left),
             LocalTransform::default(),
         ));
 
@@ -248,6 +299,134 @@ mod tests {
         assert_relative_eq!(input.cursor_location.x, 150.00153);
         assert_relative_eq!(input.cursor_location.y, 77.21234);
         assert_eq!(input.trigger_value, 0.);
+
+        let pointer = world.get::<&Pointer>(pointer_entity).unwrap();
+        assert_eq!(pointer.hovered_entity, Some(panel_entity));
+        assert_eq!(
+            pointer.events,
+            vec![PointerEvent::HoverStart {
+                entity: panel_entity
+            }]
+        );
+    }
+
+    #[test]
+    pub fn test_pointer_events() {
+        let mut world = World::new();
+        let a = world.spawn(());
+        let b = world.spawn(());
+        let mut pointer = Pointer::new(Handedness::Right);
+        let tick = |pointer: &mut Pointer, hit: Option<(Entity, Vec3)>, pressed: bool| {
+            pointer.events.clear();
+            update_pointer(pointer, hit, pressed);
+            pointer.events.clone()
+        };
+
+        // Hover over a, then click it
+        assert_eq!(
+            tick(&mut pointer, Some((a, Vec3::X)), false),
+            vec![PointerEvent::HoverStart { entity: a }]
+        );
+        assert_eq!(
+            tick(&mut pointer, Some((a, Vec3::X)), true),
+            vec![PointerEvent::Pressed {
+                entity: a,
+                point: Vec3::X
+            }]
+        );
+        assert_eq!(tick(&mut pointer, Some((a, Vec3::Y)), true), vec![]);
+        assert_eq!(pointer.pressed_entity, Some(a));
+        assert_eq!(
+            tick(&mut pointer, Some((a, Vec3::Y)), false),
+            vec![
+                PointerEvent::Released {
+                    entity: a,
+                    point: Some(Vec3::Y)
+                },
+                PointerEvent::Clicked {
+                    entity: a,
+                    point: Vec3::Y
+                }
+            ]
+        );
+
+        // Press a, then drag off onto b before letting go, which isn't a click
+        tick(&mut pointer, Some((a, Vec3::X)), true);
+        assert_eq!(
+            tick(&mut pointer, Some((b, Vec3::Z)), true),
+            vec![
+                PointerEvent::HoverEnd { entity: a },
+                PointerEvent::HoverStart { entity: b }
+            ]
+        );
+        assert_eq!(
+            tick(&mut pointer, Some((b, Vec3::Z)), false),
+            vec![PointerEvent::Released {
+                entity: a,
+                point: None
+            }]
+        );
+
+        // Point at nothing
+        assert_eq!(
+            tick(&mut pointer, None, false),
+            vec![PointerEvent::HoverEnd { entity: b }]
+        );
+        assert_eq!(pointer.hit_point, None);
+    }
+
+    #[test]
+    pub fn test_hidden_pointer_is_skipped() {
+        let mut physics_context = PhysicsContext::default();
+        let input_context = InputContext::testing();
+        let mut world = World::new();
+        let panel = world.spawn(());
+
+        // A pointer that was pressing a panel when it was hidden
+        let mut pointer = Pointer::new(Handedness::Right);
+        pointer.hovered_entity = Some(panel);
+        pointer.hit_point = Some(Vec3::X);
+        pointer.pressed_entity = Some(panel);
+        let pointer_entity = world.spawn((Visible(false), pointer, LocalTransform::default()));
+
+        let run = |world: &mut World, physics_context: &mut PhysicsContext| {
+            pointers_system_inner(
+                world,
+                &input_context,
+                &InputBindings::default(),
+                physics_context,
+            );
+        };
+
+        // The hover and press are released, and the pointer isn't moved.
+        run(&mut world, &mut physics_context);
+        {
+            let pointer = world.get::<&Pointer>(pointer_entity).unwrap();
+            assert_eq!(
+                pointer.events,
+                vec![
+                    PointerEvent::HoverEnd { entity: panel },
+                    PointerEvent::Released {
+                        entity: panel,
+                        point: None
+                    }
+                ]
+            );
+            assert_eq!(pointer.hovered_entity, None);
+            assert_eq!(pointer.pressed_entity, None);
+            assert_eq!(pointer.hit_point, None);
+            assert_eq!(
+                *world.get::<&LocalTransform>(pointer_entity).unwrap(),
+                LocalTransform::default()
+            );
+        }
+
+        // After that, a hidden pointer does nothing at all.
+        run(&mut world, &mut physics_context);
+        assert_eq!(
+            world.get::<&Pointer>(pointer_entity).unwrap().events,
+            vec![]
+        );
     }
 
     #[cfg(windows)]
@@ -259,7 +438,12 @@ mod tests {
         use crate::systems::physics::physics_system_inner;
 
         physics_system_inner(physics_context, world);
-        pointers_system_inner(world, input_context, physics_context);
+        pointers_system_inner(
+            world,
+            input_context,
+            &InputBindings::default(),
+            physics_context,
+        );
     }
 
     #[test]
@@ -1,20 +1,29 @@
 use crate::{
-    components::{skin::NO_SKIN, stage, GlobalTransform, Mesh, Skin, Visible},
-    contexts::{render_context::create_push_constant, VulkanContext},
+    components::{
+        morph_weights::NO_MORPH_WEIGHTS, skin::NO_SKIN, stage, Camera, CustomMaterial, Decal,
+        FrustumCulled, GlobalTransform, Highlighted, Mesh, MorphWeights, Skin, Skybox, Text,
+        Visible,
+    },
+    contexts::{render_context::create_push_constant, SimulatedXrContext, VulkanContext},
     contexts::{
         render_context::{Instance, InstancedPrimitive},
         RenderContext,
     },
     rendering::{
         buffer::Buffer,
+        custom_material::CustomDraw,
+        decal::{DecalData, MAX_DECALS},
+        frame::push_draw_data,
         material::Material,
+        outline::OutlineDraw,
         primitive::Primitive,
+        render_mode::RenderMode,
         resources::{DrawData, PrimitiveCullData},
     },
     Engine,
 };
 use glam::Affine3A;
-use hecs::{With, World};
+use hecs::World;
 use openxr as xr;
 
 /// Rendering system
@@ -38,6 +47,25 @@ pub fn rendering_system(engine: &mut Engine, swapchain_image_index: usize) {
     );
 }
 
+/// Simulated rendering system
+/// Renders the world from the eyes of a [`SimulatedXrContext`], for running without a headset. Call it between
+/// [`SimulatedXrContext::begin_frame`] and [`SimulatedXrContext::end_frame`].
+pub fn simulated_rendering_system(
+    world: &mut World,
+    vulkan_context: &VulkanContext,
+    render_context: &mut RenderContext,
+    xr_context: &SimulatedXrContext,
+    swapchain_image_index: usize,
+) {
+    rendering_system_inner(
+        world,
+        vulkan_context,
+        render_context,
+        xr_context.views(),
+        swapchain_image_index,
+    );
+}
+
 pub(crate) fn rendering_system_inner(
     world: &mut World,
     vulkan_context: &VulkanContext,
@@ -60,7 +88,8 @@ pub(crate) fn rendering_system_inner(
 
 /// Prepare to draw the world
 ///
-/// Begins the render pass used to draw the world, but records no drawing commands.
+/// Renders each [`Camera`] and scheduled render into its render target, then begins the render pass used to draw
+/// the world, but records no drawing commands for it.
 ///
 /// # Safety
 ///
@@ -77,7 +106,8 @@ pub unsafe fn begin(
     // and create a list of instances, indexed by primitive ID.
     //
     // We use primitive.index_buffer_offset as our primitive ID as it is guaranteed to be unique between
-    // primitives.
+    // primitives. Copies made by the skinning pre-pass use their vertex buffer offset instead, which is always
+    // past the end of the index buffer.
     let meshes = &render_context.resources.mesh_data;
 
     // Create transformations to globally oriented stage space
@@ -89,17 +119,76 @@ pub unsafe fn begin(
 
     let gos_from_stage: Affine3A = gos_from_global * global_from_stage;
 
-    for (_, (mesh, global_transform, skin)) in
-        world.query_mut::<With<(&Mesh, &GlobalTransform, Option<&Skin>), &Visible>>()
+    // Morph target weights are written fresh each frame, one set per entity that has them.
+    let morph_weights_buffer =
+        &mut render_context.frames[render_context.frame_index].morph_weights_buffer;
+    morph_weights_buffer.clear();
+
+    // Skinned primitives are skinned once, up front, by the skinning pre-pass.
+    let skinning = &mut render_context.skinning;
+    skinning.begin_frame(render_context.frame_index);
+
+    // SpaceWarp needs to know where everything was last frame.
+    let mut motion_vectors = render_context.motion_vectors.as_mut();
+    if let Some(motion_vectors) = &mut motion_vectors {
+        motion_vectors.begin_frame(&global_from_stage);
+    }
+
+    // Entities marked by the frustum culling system are skipped entirely.
+    for (entity, (mesh, global_transform, skin, morph_weights, visible)) in world
+        .query_mut::<(
+            &Mesh,
+            &GlobalTransform,
+            Option<&Skin>,
+            Option<&MorphWeights>,
+            &Visible,
+        )>()
+        .without::<&FrustumCulled>()
+        .without::<&CustomMaterial>()
     {
+        if !visible.0 {
+            continue;
+        }
         let mesh = meshes.get(mesh.handle).unwrap();
         let skin_id = skin.map(|s| s.id).unwrap_or(NO_SKIN);
+        let morph_weights_id = morph_weights
+            .map(|w| morph_weights_buffer.push(&w.to_gpu()))
+            .unwrap_or(NO_MORPH_WEIGHTS);
+        let previous_gos_from_local = match &mut motion_vectors {
+            Some(motion_vectors) => {
+                gos_from_global
+                    * motion_vectors.previous_global_from_local(entity, global_transform.0)
+            }
+            None => gos_from_global * global_transform.0,
+        };
         for primitive in &mesh.primitives {
-            let key = primitive.index_buffer_offset;
-
             // Create a transform from this primitive's local space into gos space.
             let gos_from_local = gos_from_global * global_transform.0;
 
+            // A skinned primitive is replaced by a copy of its skinned vertices, which is drawn without a skin.
+            // Each copy is its own primitive, using its vertex buffer offset as its ID. If the pre-pass is full, the
+            // vertex shader skins it instead.
+            let skinned_primitive = if skin_id == NO_SKIN {
+                None
+            } else {
+                skinning.queue(primitive, skin_id, morph_weights_id)
+            };
+            let (key, primitive, instance_skin_id, instance_morph_weights_id) =
+                match &skinned_primitive {
+                    Some(skinned_primitive) => (
+                        skinned_primitive.vertex_buffer_offset,
+                        skinned_primitive,
+                        NO_SKIN,
+                        NO_MORPH_WEIGHTS,
+                    ),
+                    None => (
+                        primitive.index_buffer_offset,
+                        primitive,
+                        skin_id,
+                        morph_weights_id,
+                    ),
+                };
+
             render_context
                 .primitive_map
                 .entry(key)
@@ -110,12 +199,77 @@ pub unsafe fn begin(
                 .instances
                 .push(Instance {
                     gos_from_local,
+                    previous_gos_from_local,
                     bounding_sphere: primitive.get_bounding_sphere_in_gos(&gos_from_local),
-                    skin_id,
+                    skin_id: instance_skin_id,
+                    morph_weights_id: instance_morph_weights_id,
                 });
         }
     }
 
+    // Entities with a custom material are drawn with their own pipelines, outside of the culling shader.
+    for (_, (mesh, global_transform, skin, morph_weights, material, visible)) in world
+        .query_mut::<(
+            &Mesh,
+            &GlobalTransform,
+            Option<&Skin>,
+            Option<&MorphWeights>,
+            &CustomMaterial,
+            &Visible,
+        )>()
+        .without::<&FrustumCulled>()
+    {
+        if !visible.0 {
+            continue;
+        }
+        let mesh = meshes.get(mesh.handle).unwrap();
+        let skin_id = skin.map(|s| s.id).unwrap_or(NO_SKIN);
+        let morph_weights_id = morph_weights
+            .map(|w| morph_weights_buffer.push(&w.to_gpu()))
+            .unwrap_or(NO_MORPH_WEIGHTS);
+        let gos_from_local = gos_from_global * global_transform.0;
+        for primitive in &mesh.primitives {
+            render_context.custom_draws.push(CustomDraw {
+                primitive: primitive.clone(),
+                gos_from_local,
+                skin_id,
+                morph_weights_id,
+                material: material.clone(),
+            });
+        }
+    }
+
+    // Highlighted meshes are outlined even if they've been frustum culled, as the outline may still be in view.
+    for (_, (mesh, global_transform, skin, morph_weights, highlighted, visible)) in world
+        .query_mut::<(
+            &Mesh,
+            &GlobalTransform,
+            Option<&Skin>,
+            Option<&MorphWeights>,
+            &Highlighted,
+            &Visible,
+        )>()
+    {
+        if !visible.0 {
+            continue;
+        }
+        let mesh = meshes.get(mesh.handle).unwrap();
+        let skin_id = skin.map(|s| s.id).unwrap_or(NO_SKIN);
+        let morph_weights_id = morph_weights
+            .map(|w| morph_weights_buffer.push(&w.to_gpu()))
+            .unwrap_or(NO_MORPH_WEIGHTS);
+        let gos_from_local = gos_from_global * global_transform.0;
+        for primitive in &mesh.primitives {
+            render_context.outline_pipeline.queue(OutlineDraw {
+                primitive: primitive.clone(),
+                gos_from_local,
+                skin_id,
+                morph_weights_id,
+                push_constants: highlighted.into(),
+            });
+        }
+    }
+
     // Next organize this data into a layout that's easily consumed by the compute shader.
     // ORDER IS IMPORTANT HERE! The final buffer should look something like:
     //
@@ -132,31 +286,101 @@ pub unsafe fn begin(
     let cull_data = &mut frame.primitive_cull_data_buffer;
     cull_data.clear();
 
-    for instanced_primitive in render_context.primitive_map.values() {
-        let primitive = &instanced_primitive.primitive;
+    for (primitive_id, instanced_primitive) in &render_context.primitive_map {
         for (instance, i) in instanced_primitive.instances.iter().zip(0u32..) {
             cull_data.push(&PrimitiveCullData {
                 bounding_sphere: instance.bounding_sphere,
                 index_instance: i,
-                primitive_id: primitive.index_buffer_offset,
+                primitive_id: *primitive_id,
                 visible: false,
             });
         }
     }
 
+    // Text is laid out on the CPU, straight into gos space.
+    for (_, (text, global_transform, visible)) in world
+        .query_mut::<(&Text, &GlobalTransform, &Visible)>()
+        .without::<&FrustumCulled>()
+    {
+        if !visible.0 {
+            continue;
+        }
+        render_context
+            .text_pipeline
+            .queue(text, &(gos_from_global * global_transform.0));
+    }
+
+    // Find the skybox to draw behind the world, if there is one.
+    render_context.skybox = world
+        .query_mut::<&Skybox>()
+        .into_iter()
+        .next()
+        .map(|(_, skybox)| *skybox);
+
+    // Decals are projected onto opaque surfaces in the fragment shader.
+    let materials = render_context.resources.materials_buffer.as_slice();
+    let decals_buffer = &mut render_context.frames[render_context.frame_index].decals_buffer;
+    decals_buffer.clear();
+    for (_, (decal, global_transform, visible)) in
+        world.query_mut::<(&Decal, &GlobalTransform, &Visible)>()
+    {
+        if !visible.0 {
+            continue;
+        }
+        if decals_buffer.len() == MAX_DECALS {
+            println!("[HOTHAM_RENDERING] WARNING: There are more than {MAX_DECALS} decals in the world, some will be ignored!");
+            break;
+        }
+        if let Some(material) = materials.get(decal.material_id as usize) {
+            decals_buffer.push(&DecalData::new(
+                &(gos_from_global * global_transform.0),
+                material,
+            ));
+        }
+    }
+    render_context.scene_data.decal_params.x = decals_buffer.len() as f32;
+
+    // Each camera renders the world into its own texture before the world itself is drawn, along with any renders
+    // scheduled through the render context.
+    let mut render_target_views = std::mem::take(&mut render_context.scheduled_renders);
+    render_target_views.extend(
+        world
+            .query_mut::<(&Camera, &GlobalTransform)>()
+            .into_iter()
+            .map(|(_, (camera, global_transform))| camera.view(global_transform.0)),
+    );
+
     // This is the VERY LATEST we can possibly update our views, as the compute shader will need them.
     render_context.update_scene_data(views, &gos_from_global, &gos_from_stage);
 
     // Execute the culling shader on the GPU.
     render_context.cull_objects(vulkan_context);
 
+    // Skin, then render the shadow maps, motion vectors, bloom and cameras. This must happen outside of the PBR render pass.
+    let frame = &mut render_context.frames[render_context.frame_index];
+    frame.draw_data_buffer.clear();
+    render_context.skin_primitives(vulkan_context);
+    render_context.render_shadow_maps(vulkan_context);
+    render_context.render_motion_vectors(vulkan_context, swapchain_image_index);
+    render_context.render_bloom(vulkan_context);
+    render_context.render_to_targets(vulkan_context, &render_target_views);
+
     // Begin the render pass, bind descriptor sets.
     render_context.begin_pbr_render_pass(vulkan_context, swapchain_image_index);
 }
 
+/// A visible instance of a primitive with a transparent material, waiting to be sorted.
+struct TransparentInstance {
+    distance_squared: f32,
+    primitive_id: u32,
+    index_instance: usize,
+}
+
 /// Draw the world
 ///
-/// Records commands to draw all visible meshes
+/// Records commands to draw all visible opaque meshes, followed by the [`Skybox`], if there is one, any
+/// debug lines and any [`Text`]. Meshes with transparent materials are drawn next, sorted from back to front,
+/// and finally the outlines around [`Highlighted`] meshes.
 ///
 /// # Safety
 ///
@@ -168,9 +392,34 @@ pub unsafe fn draw_world(vulkan_context: &VulkanContext, render_context: &mut Re
     let command_buffer = frame.command_buffer;
     let draw_data_buffer = &mut frame.draw_data_buffer;
     let material_buffer = &mut render_context.resources.materials_buffer;
-    draw_data_buffer.clear();
 
-    let mut instance_offset = 0;
+    // Transparent instances are drawn separately, once everything behind them has been drawn.
+    let mut transparent_instances = Vec::new();
+    let scene_data = &render_context.scene_data;
+    let eye_position =
+        (scene_data.camera_position[0].truncate() + scene_data.camera_position[1].truncate()) * 0.5;
+
+    // `begin_pbr_render_pass` binds the single sided pipeline. Double sided materials switch to their own, unless
+    // the render mode draws every material with the same pipeline.
+    let opaque_pipelines = match render_context.render_mode_pipeline() {
+        Some(pipeline) => OpaquePipelines {
+            single_sided: pipeline,
+            double_sided: pipeline,
+            alpha_to_coverage: pipeline,
+            double_sided_alpha_to_coverage: pipeline,
+        },
+        None => OpaquePipelines {
+            single_sided: render_context.pipeline,
+            double_sided: render_context.double_sided_pipeline,
+            alpha_to_coverage: render_context.alpha_to_coverage_pipeline,
+            double_sided_alpha_to_coverage: render_context.double_sided_alpha_to_coverage_pipeline,
+        },
+    };
+    let mut bound_pipeline = render_context.pipeline;
+    vulkan_context.begin_debug_label(command_buffer, "Opaque");
+
+    // The shadow and bloom passes may have already written draw data, so start after it.
+    let mut instance_offset = draw_data_buffer.len() as u32;
     let mut current_primitive_id = u32::MAX;
     let mut instance_count = 0;
     let cull_data = frame.primitive_cull_data_buffer.as_slice();
@@ -190,6 +439,13 @@ pub unsafe fn draw_world(vulkan_context: &VulkanContext, render_context: &mut Re
                     .get(&current_primitive_id)
                     .unwrap()
                     .primitive;
+                opaque_pipelines.bind(
+                    device,
+                    command_buffer,
+                    material_buffer,
+                    primitive,
+                    &mut bound_pipeline,
+                );
                 draw_primitive(
                     material_buffer,
                     render_context.pipeline_layout,
@@ -213,13 +469,30 @@ pub unsafe fn draw_world(vulkan_context: &VulkanContext, render_context: &mut Re
                 .get(&cull_result.primitive_id)
                 .unwrap();
             let instance = &instanced_primitive.instances[cull_result.index_instance as usize];
-            let draw_data = DrawData {
-                gos_from_local: instance.gos_from_local.into(),
-                local_from_gos: instance.gos_from_local.inverse().into(),
-                skin_id: instance.skin_id,
-            };
-            draw_data_buffer.push(&draw_data);
-            instance_count += 1;
+
+            let material =
+                &material_buffer.as_slice()[instanced_primitive.primitive.material_id as usize];
+            if material.is_transparent() {
+                transparent_instances.push(TransparentInstance {
+                    distance_squared: instance
+                        .bounding_sphere
+                        .truncate()
+                        .distance_squared(eye_position),
+                    primitive_id: cull_result.primitive_id,
+                    index_instance: cull_result.index_instance as usize,
+                });
+                continue;
+            }
+
+            let draw_data = DrawData::new(
+                &instance.gos_from_local,
+                instance.skin_id,
+                instance.morph_weights_id,
+                &instanced_primitive.primitive,
+            );
+            if push_draw_data(draw_data_buffer, &draw_data).is_some() {
+                instance_count += 1;
+            }
         }
     }
 
@@ -233,6 +506,13 @@ pub unsafe fn draw_world(vulkan_context: &VulkanContext, render_context: &mut Re
             .unwrap()
             .primitive;
 
+        opaque_pipelines.bind(
+            device,
+            command_buffer,
+            material_buffer,
+            primitive,
+            &mut bound_pipeline,
+        );
         draw_primitive(
             material_buffer,
             render_context.pipeline_layout,
@@ -243,6 +523,143 @@ pub unsafe fn draw_world(vulkan_context: &VulkanContext, render_context: &mut Re
             instance_offset,
         );
     }
+    vulkan_context.end_debug_label(command_buffer);
+
+    render_context.draw_custom_materials(vulkan_context);
+
+    // Drawing the skybox after opaque meshes means it only shades pixels that weren't covered by the world.
+    // Nothing writes depth in overdraw mode, so the skybox would cover everything.
+    if render_context.render_mode != RenderMode::Overdraw {
+        render_context.draw_skybox(vulkan_context);
+    }
+
+    // Debug lines are drawn over the opaque world, but are hidden by anything in front of them.
+    render_context.draw_debug_lines(vulkan_context);
+
+    // Text is blended over the opaque world, but doesn't hide anything behind it.
+    render_context.draw_text(vulkan_context);
+
+    draw_transparent_instances(vulkan_context, render_context, transparent_instances);
+
+    // Outlines go on top of everything, including transparent meshes, as they're drawn to be noticed.
+    render_context.draw_outlines(vulkan_context);
+}
+
+/// The pipelines used for opaque materials, selected by whether the material is double sided and alpha masked.
+struct OpaquePipelines {
+    single_sided: ash::vk::Pipeline,
+    double_sided: ash::vk::Pipeline,
+    alpha_to_coverage: ash::vk::Pipeline,
+    double_sided_alpha_to_coverage: ash::vk::Pipeline,
+}
+
+impl OpaquePipelines {
+    /// Bind the pipeline for `primitive`'s material, if it isn't already bound.
+    unsafe fn bind(
+        &self,
+        device: &ash::Device,
+        command_buffer: ash::vk::CommandBuffer,
+        materials_buffer: &Buffer<Material>,
+        primitive: &Primitive,
+        bound_pipeline: &mut ash::vk::Pipeline,
+    ) {
+        let material = &materials_buffer.as_slice()[primitive.material_id as usize];
+        let pipeline = match (material.is_double_sided(), material.is_alpha_masked()) {
+            (false, false) => self.single_sided,
+            (true, false) => self.double_sided,
+            (false, true) => self.alpha_to_coverage,
+            (true, true) => self.double_sided_alpha_to_coverage,
+        };
+
+        if pipeline != *bound_pipeline {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                ash::vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+            *bound_pipeline = pipeline;
+        }
+    }
+}
+
+/// Draw transparent instances from back to front, so that each one is blended with everything behind it.
+/// Sorting breaks up instancing, so each instance gets its own draw call.
+unsafe fn draw_transparent_instances(
+    vulkan_context: &VulkanContext,
+    render_context: &mut RenderContext,
+    mut transparent_instances: Vec<TransparentInstance>,
+) {
+    if transparent_instances.is_empty() {
+        return;
+    }
+
+    transparent_instances.sort_by(|a, b| b.distance_squared.total_cmp(&a.distance_squared));
+
+    let device = &vulkan_context.device;
+    let frame_index = render_context.frame_index;
+    let frame = &mut render_context.frames[frame_index];
+    let command_buffer = frame.command_buffer;
+    let draw_data_buffer = &mut frame.draw_data_buffer;
+
+    vulkan_context.begin_debug_label(command_buffer, "Transparent");
+
+    // The skybox pipeline has a different layout, so the descriptor sets need to be bound again.
+    let mut bound_pipeline = ash::vk::Pipeline::null();
+    device.cmd_bind_descriptor_sets(
+        command_buffer,
+        ash::vk::PipelineBindPoint::GRAPHICS,
+        render_context.pipeline_layout,
+        0,
+        std::slice::from_ref(&render_context.descriptors.sets[frame_index]),
+        &[],
+    );
+
+    for transparent_instance in &transparent_instances {
+        let instanced_primitive = &render_context.primitive_map[&transparent_instance.primitive_id];
+        let instance = &instanced_primitive.instances[transparent_instance.index_instance];
+
+        // Double sided materials have their own pipeline. Switching doesn't change the order instances are drawn in.
+        let material = &render_context.resources.materials_buffer.as_slice()
+            [instanced_primitive.primitive.material_id as usize];
+        let pipeline = if let Some(pipeline) = render_context.render_mode_pipeline() {
+            pipeline
+        } else if material.is_double_sided() {
+            render_context.double_sided_transparent_pipeline
+        } else {
+            render_context.transparent_pipeline
+        };
+        if pipeline != bound_pipeline {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                ash::vk::PipelineBindPoint::GRAPHICS,
+                pipeline,
+            );
+            bound_pipeline = pipeline;
+        }
+
+        let instance_offset = match push_draw_data(
+            draw_data_buffer,
+            &DrawData::new(
+                &instance.gos_from_local,
+                instance.skin_id,
+                instance.morph_weights_id,
+                &instanced_primitive.primitive,
+            ),
+        ) {
+            Some(instance_offset) => instance_offset,
+            None => break,
+        };
+        draw_primitive(
+            &render_context.resources.materials_buffer,
+            render_context.pipeline_layout,
+            &instanced_primitive.primitive,
+            device,
+            command_buffer,
+            1,
+            instance_offset,
+        );
+    }
+    vulkan_context.end_debug_label(command_buffer);
 }
 
 // TODO: Just push this into `RenderContext`
@@ -261,7 +678,7 @@ pub unsafe fn draw_primitive(
     device.cmd_push_constants(
         command_buffer,
         pipeline_layout,
-        ash::vk::ShaderStageFlags::FRAGMENT,
+        ash::vk::ShaderStageFlags::VERTEX | ash::vk::ShaderStageFlags::FRAGMENT,
         0,
         constants,
     );
@@ -281,8 +698,17 @@ pub unsafe fn draw_primitive(
 ///
 /// Must be called after `begin`
 pub fn end(vulkan_context: &VulkanContext, render_context: &mut RenderContext) {
+    // Bloom is added on top of everything that was drawn.
+    render_context.composite_bloom(vulkan_context);
+
     // OK. We're all done!
     render_context.primitive_map.clear();
+    render_context.unculled_draws.clear();
+    render_context.skybox = None;
+    render_context.custom_draws.clear();
+    render_context.debug_draw.clear();
+    render_context.text_pipeline.clear();
+    render_context.outline_pipeline.clear();
     render_context.end_pbr_render_pass(vulkan_context);
 }
 
@@ -0,0 +1,184 @@
+use glam::Affine3A;
+use hecs::{Entity, World};
+
+use crate::{
+    components::{stage, Collider, GlobalTransform, LocalTransform, ScenePlane, SceneVolume},
+    contexts::xr_context::{AnchorUuid, SceneElement},
+    Engine,
+};
+
+/// Scene system
+/// Spawns an entity for each part of the player's room once the runtime has loaded it, and keeps each of them where
+/// the runtime says that part of the room is.
+pub fn scene_system(engine: &mut Engine) {
+    let xr_context = &mut engine.xr_context;
+    let scene = match &mut xr_context.scene {
+        Some(scene) => scene,
+        None => return,
+    };
+    let stage_space = &xr_context.stage_space;
+    let time = xr_context.frame_state.predicted_display_time;
+
+    let elements = scene.drain_loaded();
+    let colliders = scene.colliders;
+    scene_system_inner(&mut engine.world, elements, colliders, |uuid| {
+        scene.locate(uuid, stage_space, time).unwrap_or_else(|e| {
+            println!("[HOTHAM_SCENE] Unable to locate part of the room: {e:?}");
+            None
+        })
+    });
+}
+
+pub(crate) fn scene_system_inner(
+    world: &mut World,
+    elements: Vec<SceneElement>,
+    colliders: bool,
+    stage_from_element: impl Fn(&AnchorUuid) -> Option<Affine3A>,
+) {
+    for element in elements {
+        spawn_element(world, element, colliders);
+    }
+
+    let global_from_stage = stage::get_global_from_stage(world);
+    for (_, (plane, volume, local_transform, global_transform)) in world.query_mut::<(
+        Option<&ScenePlane>,
+        Option<&SceneVolume>,
+        &mut LocalTransform,
+        &mut GlobalTransform,
+    )>() {
+        let uuid = match (plane, volume) {
+            (Some(plane), _) => &plane.uuid,
+            (None, Some(volume)) => &volume.uuid,
+            (None, None) => continue,
+        };
+
+        // If the part of the room can't be located, leave it where it was last seen.
+        if let Some(stage_from_element) = stage_from_element(uuid) {
+            let global_from_element = global_from_stage * stage_from_element;
+            local_transform.update_from_affine(&global_from_element);
+            global_transform.0 = global_from_element;
+        }
+    }
+}
+
+fn spawn_element(world: &mut World, element: SceneElement, colliders: bool) {
+    let plane = element.plane.map(|(offset, size)| ScenePlane {
+        uuid: element.uuid,
+        label: element.label.clone(),
+        offset,
+        size,
+    });
+    let volume = element.volume.map(|(offset, size)| SceneVolume {
+        uuid: element.uuid,
+        label: element.label.clone(),
+        offset,
+        size,
+    });
+    // Content should rest on furniture, rather than on its top, so prefer its volume.
+    let collider = match (&plane, &volume) {
+        (_, Some(volume)) => Some(Collider::new(volume.collider_shape())),
+        (Some(plane), None) => Some(Collider::new(plane.collider_shape())),
+        (None, None) => None,
+    }
+    .filter(|_| colliders);
+
+    // If the room has been loaded again, update the entity that's already there.
+    let entity = find_element(world, &element.uuid)
+        .unwrap_or_else(|| world.spawn((LocalTransform::default(), GlobalTransform::default())));
+    let _ = world.remove_one::<ScenePlane>(entity);
+    let _ = world.remove_one::<SceneVolume>(entity);
+    let _ = world.remove_one::<Collider>(entity);
+    if let Some(plane) = plane {
+        world.insert_one(entity, plane).unwrap();
+    }
+    if let Some(volume) = volume {
+        world.insert_one(entity, volume).unwrap();
+    }
+    if let Some(collider) = collider {
+        world.insert_one(entity, collider).unwrap();
+    }
+}
+
+fn find_element(world: &mut World, uuid: &AnchorUuid) -> Option<Entity> {
+    world
+        .query_mut::<(Option<&ScenePlane>, Option<&SceneVolume>)>()
+        .into_iter()
+        .find(|(_, (plane, volume))| {
+            plane.map(|plane| &plane.uuid) == Some(uuid)
+                || volume.map(|volume| &volume.uuid) == Some(uuid)
+        })
+        .map(|(entity, _)| entity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Stage, contexts::xr_context::SceneLabel};
+    use approx::assert_relative_eq;
+    use glam::{Vec2, Vec3};
+
+    #[test]
+    pub fn test_scene_system() {
+        let mut world = World::new();
+        world.spawn((
+            Stage {},
+            LocalTransform::default(),
+            GlobalTransform(Affine3A::from_translation(Vec3::X)),
+        ));
+
+        let wall = SceneElement {
+            uuid: [1; 16],
+            label: SceneLabel::Wall,
+            plane: Some((Vec2::new(-1., -1.), Vec2::new(2., 2.))),
+            volume: None,
+        };
+        let table = SceneElement {
+            uuid: [2; 16],
+            label: SceneLabel::Table,
+            plane: Some((Vec2::new(-0.5, -0.5), Vec2::new(1., 1.))),
+            volume: Some((Vec3::new(-0.5, -0.5, -0.8), Vec3::new(1., 1., 0.8))),
+        };
+        let stage_from_element = |uuid: &AnchorUuid| {
+            (*uuid == [1; 16]).then(|| Affine3A::from_translation(Vec3::new(0., 1., -2.)))
+        };
+        scene_system_inner(
+            &mut world,
+            vec![wall, table.clone()],
+            true,
+            stage_from_element,
+        );
+
+        // The wall is spawned where the runtime says it is, with a collider.
+        let wall = find_element(&mut world, &[1; 16]).unwrap();
+        assert_eq!(
+            world.get::<&ScenePlane>(wall).unwrap().label,
+            SceneLabel::Wall
+        );
+        assert!(world.get::<&SceneVolume>(wall).is_err());
+        assert!(world.get::<&Collider>(wall).is_ok());
+        let global_transform = world.get::<&GlobalTransform>(wall).unwrap();
+        assert_relative_eq!(global_transform.0.translation, [1., 1., -2.].into());
+
+        // The table has both a top and a volume, and can't be located yet.
+        let table_entity = find_element(&mut world, &[2; 16]).unwrap();
+        assert!(world.get::<&ScenePlane>(table_entity).is_ok());
+        assert_relative_eq!(
+            world.get::<&SceneVolume>(table_entity).unwrap().center(),
+            Vec3::new(0., 0., -0.4)
+        );
+        assert_eq!(
+            world.get::<&GlobalTransform>(table_entity).unwrap().0,
+            Affine3A::IDENTITY
+        );
+
+        // Loading the room again updates the entities that are already there.
+        let table = SceneElement {
+            volume: None,
+            ..table
+        };
+        scene_system_inner(&mut world, vec![table], false, stage_from_element);
+        assert_eq!(find_element(&mut world, &[2; 16]), Some(table_entity));
+        assert!(world.get::<&SceneVolume>(table_entity).is_err());
+        assert!(world.get::<&Collider>(table_entity).is_err());
+    }
+}
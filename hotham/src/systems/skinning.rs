@@ -8,7 +8,8 @@ use crate::{
 };
 
 /// Skinning system
-/// Walks through each joint in the system and builds up the `joint_matrices` that will be sent to the vertex shader
+/// Walks through each joint in the system and builds up the `joint_matrices` that will be used by the skinning
+/// pre-pass, see [`crate::rendering::skinning`]
 pub fn skinning_system(engine: &mut Engine) {
     let world = &mut engine.world;
     let render_context = &mut engine.render_context;
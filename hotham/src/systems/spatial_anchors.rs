@@ -0,0 +1,209 @@
+use glam::Affine3A;
+use hecs::World;
+
+use crate::{
+    components::{
+        spatial_anchor::SpatialAnchorState, stage, GlobalTransform, LocalTransform, SpatialAnchor,
+    },
+    contexts::xr_context::{AnchorUuid, SpatialAnchorEvent},
+    Engine,
+};
+
+/// Spatial anchors system
+/// Asks the runtime to create an anchor for each new `SpatialAnchor`, spawns entities for anchors persisted by
+/// earlier runs, and moves each anchored entity to where the runtime says its anchor is.
+pub fn spatial_anchors_system(engine: &mut Engine) {
+    let xr_context = &mut engine.xr_context;
+    let spatial_anchors = match &mut xr_context.spatial_anchors {
+        Some(spatial_anchors) => spatial_anchors,
+        None => return,
+    };
+    let world = &mut engine.world;
+    let session = &xr_context.session;
+    let stage_space = &xr_context.stage_space;
+    let time = xr_context.frame_state.predicted_display_time;
+
+    let stage_from_global = stage::get_global_from_stage(world).inverse();
+    for (_, (spatial_anchor, global_transform)) in
+        world.query_mut::<(&mut SpatialAnchor, &GlobalTransform)>()
+    {
+        if spatial_anchor.state != SpatialAnchorState::New {
+            continue;
+        }
+        spatial_anchor.state = match spatial_anchors.create_anchor(
+            session,
+            stage_space,
+            stage_from_global * global_transform.0,
+            time,
+            spatial_anchor.persist,
+        ) {
+            Ok(request_id) => SpatialAnchorState::Creating(request_id),
+            Err(e) => {
+                println!("[HOTHAM_SPATIAL_ANCHORS] Unable to create anchor: {e:?}");
+                SpatialAnchorState::Failed
+            }
+        };
+    }
+
+    let events = spatial_anchors.drain_events();
+    spatial_anchors_system_inner(world, &events, |uuid| {
+        spatial_anchors
+            .locate(uuid, stage_space, time)
+            .unwrap_or_else(|e| {
+                println!("[HOTHAM_SPATIAL_ANCHORS] Unable to locate anchor: {e:?}");
+                None
+            })
+    });
+}
+
+pub(crate) fn spatial_anchors_system_inner(
+    world: &mut World,
+    events: &[SpatialAnchorEvent],
+    stage_from_anchor: impl Fn(&AnchorUuid) -> Option<Affine3A>,
+) {
+    for event in events {
+        match *event {
+            SpatialAnchorEvent::Created { request_id, uuid } => {
+                for (_, spatial_anchor) in world.query_mut::<&mut SpatialAnchor>() {
+                    if spatial_anchor.state == SpatialAnchorState::Creating(request_id) {
+                        spatial_anchor.uuid = uuid;
+                        spatial_anchor.state = if uuid.is_some() {
+                            SpatialAnchorState::Created
+                        } else {
+                            SpatialAnchorState::Failed
+                        };
+                    }
+                }
+            }
+            SpatialAnchorEvent::Persisted { uuid, success } => {
+                for (_, spatial_anchor) in world.query_mut::<&mut SpatialAnchor>() {
+                    if spatial_anchor.uuid == Some(uuid) {
+                        spatial_anchor.is_persisted = success;
+                    }
+                }
+            }
+            SpatialAnchorEvent::Loaded { uuid } => {
+                let already_spawned = world
+                    .query_mut::<&SpatialAnchor>()
+                    .into_iter()
+                    .any(|(_, spatial_anchor)| spatial_anchor.uuid == Some(uuid));
+                if !already_spawned {
+                    world.spawn((
+                        SpatialAnchor::loaded(uuid),
+                        LocalTransform::default(),
+                        GlobalTransform::default(),
+                    ));
+                }
+            }
+        }
+    }
+
+    let global_from_stage = stage::get_global_from_stage(world);
+    for (_, (spatial_anchor, local_transform, global_transform)) in world.query_mut::<(
+        &mut SpatialAnchor,
+        &mut LocalTransform,
+        &mut GlobalTransform,
+    )>() {
+        if spatial_anchor.state != SpatialAnchorState::Created {
+            continue;
+        }
+
+        // If the anchor can't be located, leave the entity where it was last seen.
+        let stage_from_anchor = spatial_anchor.uuid.as_ref().and_then(&stage_from_anchor);
+        spatial_anchor.is_tracked = stage_from_anchor.is_some();
+        if let Some(stage_from_anchor) = stage_from_anchor {
+            let global_from_anchor = global_from_stage * stage_from_anchor;
+            local_transform.update_from_affine(&global_from_anchor);
+            global_transform.0 = global_from_anchor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::Stage;
+    use approx::assert_relative_eq;
+    use glam::Vec3;
+
+    #[test]
+    pub fn test_spatial_anchors_system() {
+        let mut world = World::new();
+        world.spawn((
+            Stage {},
+            LocalTransform::default(),
+            GlobalTransform(Affine3A::from_translation(Vec3::X)),
+        ));
+        let created = world.spawn((
+            SpatialAnchor {
+                state: SpatialAnchorState::Creating(7),
+                ..SpatialAnchor::new(true)
+            },
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+        let failed = world.spawn((
+            SpatialAnchor {
+                state: SpatialAnchorState::Creating(8),
+                ..SpatialAnchor::new(false)
+            },
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        let events = [
+            SpatialAnchorEvent::Created {
+                request_id: 7,
+                uuid: Some([1; 16]),
+            },
+            SpatialAnchorEvent::Created {
+                request_id: 8,
+                uuid: None,
+            },
+            SpatialAnchorEvent::Persisted {
+                uuid: [1; 16],
+                success: true,
+            },
+            SpatialAnchorEvent::Loaded { uuid: [2; 16] },
+        ];
+        let stage_from_anchor =
+            |uuid: &AnchorUuid| (*uuid == [1; 16]).then(|| Affine3A::from_translation(Vec3::Y));
+        spatial_anchors_system_inner(&mut world, &events, stage_from_anchor);
+
+        // The created anchor is persisted, and the entity moved to it.
+        {
+            let spatial_anchor = world.get::<&SpatialAnchor>(created).unwrap();
+            assert_eq!(spatial_anchor.uuid, Some([1; 16]));
+            assert!(spatial_anchor.is_persisted);
+            assert!(spatial_anchor.is_tracked);
+            let global_transform = world.get::<&GlobalTransform>(created).unwrap();
+            assert_relative_eq!(global_transform.0.translation, [1., 1., 0.].into());
+            let local_transform = world.get::<&LocalTransform>(created).unwrap();
+            assert_relative_eq!(local_transform.translation, [1., 1., 0.].into());
+        }
+
+        assert!(world.get::<&SpatialAnchor>(failed).unwrap().has_failed());
+
+        // The loaded anchor is spawned, but can't be located yet.
+        let loaded = |world: &mut World| {
+            world
+                .query_mut::<&SpatialAnchor>()
+                .into_iter()
+                .filter(|(_, spatial_anchor)| spatial_anchor.uuid == Some([2; 16]))
+                .map(|(_, spatial_anchor)| spatial_anchor.clone())
+                .collect::<Vec<_>>()
+        };
+        let spatial_anchors = loaded(&mut world);
+        assert_eq!(spatial_anchors.len(), 1);
+        assert!(spatial_anchors[0].is_persisted);
+        assert!(!spatial_anchors[0].is_tracked);
+
+        // Loading the same anchor again doesn't spawn another entity.
+        spatial_anchors_system_inner(
+            &mut world,
+            &[SpatialAnchorEvent::Loaded { uuid: [2; 16] }],
+            stage_from_anchor,
+        );
+        assert_eq!(loaded(&mut world).len(), 1);
+    }
+}
@@ -0,0 +1,293 @@
+use glam::{Affine3A, Vec2, Vec3};
+use hecs::World;
+use rapier3d::prelude::{Group, InteractionGroups, QueryFilter, Ray};
+
+use crate::{
+    components::{
+        hand::Handedness, stage, GlobalTransform, LocalTransform, Stage, Teleporter, Visible,
+    },
+    contexts::{EngineAction, PhysicsContext},
+    rendering::debug_draw::DebugDraw,
+    util::{glam_vec_from_na, na_vector_from_glam},
+    Engine,
+};
+
+/// How far forward the thumbstick has to be pushed to start aiming
+const AIM_THRESHOLD: f32 = 0.7;
+/// How close to the centre the thumbstick has to be let go to teleport
+const RELEASE_THRESHOLD: f32 = 0.3;
+/// The arc is traced in steps of this many seconds of flight..
+const ARC_TIME_STEP: f32 = 1. / 30.;
+/// ..for at most this many steps
+const MAX_ARC_STEPS: usize = 90;
+const GRAVITY: Vec3 = Vec3::new(0., -9.81, 0.);
+const VALID_COLOR: Vec3 = Vec3::new(0.2, 1.0, 0.4);
+const INVALID_COLOR: Vec3 = Vec3::new(1.0, 0.2, 0.2);
+
+/// Teleport system
+/// Traces an arc from the controller of each `Teleporter` while its thumbstick is pushed forward, highlighting
+/// whether it lands somewhere the player can stand, and moves the stage there once the thumbstick is let go. The
+/// thumbstick is the one [`EngineAction::Teleport`] is bound to in [`Engine::input_bindings`], the teleporter's own
+/// hand's by default.
+///
+/// The arc is drawn with the [`DebugDraw`] in the render context, so run this system after
+/// [`crate::systems::physics_system`] and before [`crate::systems::rendering_system`].
+pub fn teleport_system(engine: &mut Engine) {
+    let input_context = &engine.input_context;
+    let input_bindings = &engine.input_bindings;
+    let hmd_in_stage = input_context.hmd.hmd_in_stage();
+    teleport_system_inner(
+        &mut engine.world,
+        &engine.physics_context,
+        &mut engine.render_context.debug_draw,
+        |handedness| {
+            let stage_from_aim = match handedness {
+                Handedness::Left => input_context.left.stage_from_aim(),
+                Handedness::Right => input_context.right.stage_from_aim(),
+            };
+            let thumbstick = input_bindings
+                .engine_binding(EngineAction::Teleport, handedness)
+                .vector2(input_context);
+            (stage_from_aim, thumbstick)
+        },
+        hmd_in_stage,
+    );
+}
+
+pub(crate) fn teleport_system_inner(
+    world: &mut World,
+    physics_context: &PhysicsContext,
+    debug_draw: &mut DebugDraw,
+    controller: impl Fn(Handedness) -> (Affine3A, Vec2),
+    hmd_in_stage: Affine3A,
+) {
+    let global_from_stage = stage::get_global_from_stage(world);
+    let mut destination = None;
+
+    for (_, teleporter) in world.query_mut::<&mut Teleporter>() {
+        let (stage_from_aim, thumbstick) = controller(teleporter.handedness);
+
+        if !teleporter.is_aiming {
+            teleporter.is_aiming = thumbstick.y > AIM_THRESHOLD;
+        } else if thumbstick.length() < RELEASE_THRESHOLD {
+            teleporter.is_aiming = false;
+            destination = destination.or(teleporter.target);
+        }
+
+        teleporter.arc.clear();
+        teleporter.target = None;
+        if !teleporter.is_aiming {
+            continue;
+        }
+
+        let global_from_aim = global_from_stage * stage_from_aim;
+        trace_arc(physics_context, teleporter, &global_from_aim);
+
+        let color = if teleporter.target.is_some() {
+            VALID_COLOR
+        } else {
+            INVALID_COLOR
+        };
+        for segment in teleporter.arc.windows(2) {
+            debug_draw.line(segment[0], segment[1], color);
+        }
+    }
+
+    // Show each teleporter's marker at its target, if it has one.
+    let markers = world
+        .query_mut::<&Teleporter>()
+        .into_iter()
+        .filter_map(|(_, teleporter)| Some((teleporter.target_marker?, teleporter.target)))
+        .collect::<Vec<_>>();
+    for (marker, target) in markers {
+        if let Ok(mut visible) = world.get::<&mut Visible>(marker) {
+            visible.0 = target.is_some();
+        }
+        if let (Some(target), Ok(mut local_transform)) =
+            (target, world.get::<&mut LocalTransform>(marker))
+        {
+            local_transform.translation = target;
+        }
+    }
+
+    if let Some(destination) = destination {
+        teleport(world, global_from_stage, hmd_in_stage, destination);
+    }
+}
+
+/// Follow the arc from the controller until it hits something, and work out whether the player can stand there
+fn trace_arc(
+    physics_context: &PhysicsContext,
+    teleporter: &mut Teleporter,
+    global_from_aim: &Affine3A,
+) {
+    let filter = QueryFilter::new().groups(InteractionGroups::new(
+        Group::all(),
+        teleporter.collision_filter,
+    ));
+    let min_normal_y = teleporter.max_slope.cos();
+
+    let mut position: Vec3 = global_from_aim.translation.into();
+    let mut velocity = global_from_aim
+        .transform_vector3(Vec3::NEG_Z)
+        .normalize_or_zero()
+        * teleporter.speed;
+    teleporter.arc.push(position);
+
+    for _ in 0..MAX_ARC_STEPS {
+        let next_position =
+            position + velocity * ARC_TIME_STEP + 0.5 * GRAVITY * ARC_TIME_STEP * ARC_TIME_STEP;
+        velocity += GRAVITY * ARC_TIME_STEP;
+
+        // Cast along the segment; with an unnormalized direction, a time of impact of 1 is its end.
+        let ray = Ray::new(
+            na_vector_from_glam(position).into(),
+            na_vector_from_glam(next_position - position),
+        );
+        if let Some((_, intersection)) = physics_context.query_pipeline.cast_ray_and_get_normal(
+            &physics_context.rigid_bodies,
+            &physics_context.colliders,
+            &ray,
+            1.0,
+            true,
+            filter,
+        ) {
+            let hit_point = glam_vec_from_na(&ray.point_at(intersection.toi).coords);
+            teleporter.arc.push(hit_point);
+            if intersection.normal.y >= min_normal_y {
+                teleporter.target = Some(hit_point);
+            }
+            return;
+        }
+
+        teleporter.arc.push(next_position);
+        position = next_position;
+    }
+}
+
+/// Move the stage so that the player is standing at `destination`, without changing which way they're facing
+fn teleport(
+    world: &mut World,
+    global_from_stage: Affine3A,
+    hmd_in_stage: Affine3A,
+    destination: Vec3,
+) {
+    let hmd_in_global: Vec3 = (global_from_stage * hmd_in_stage).translation.into();
+    let floor_in_global: Vec3 = global_from_stage.translation.into();
+    let offset = Vec3::new(
+        destination.x - hmd_in_global.x,
+        destination.y - floor_in_global.y,
+        destination.z - hmd_in_global.z,
+    );
+
+    for (_, (local_transform, global_transform)) in world
+        .query_mut::<(&mut LocalTransform, &mut GlobalTransform)>()
+        .with::<&Stage>()
+    {
+        local_transform.translation += offset;
+        global_transform.0.translation += offset.into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Collider, systems::physics::physics_system_inner};
+    use approx::assert_relative_eq;
+    use rapier3d::prelude::SharedShape;
+
+    #[test]
+    pub fn test_teleport_system() {
+        let mut world = World::new();
+        let mut physics_context = PhysicsContext::default();
+        let mut debug_draw = DebugDraw::default();
+        let stage = world.spawn((
+            Stage {},
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        // A floor to land on, and a steep wall that can't be stood on.
+        let floor = LocalTransform {
+            translation: [0., -0.5, 0.].into(),
+            ..Default::default()
+        };
+        world.spawn((
+            Collider::new(SharedShape::cuboid(20., 0.5, 20.)),
+            floor,
+            GlobalTransform::from(floor),
+        ));
+        let wall = LocalTransform {
+            translation: [-2.5, 0., 0.].into(),
+            ..Default::default()
+        };
+        world.spawn((
+            Collider::new(SharedShape::cuboid(0.5, 10., 10.)),
+            wall,
+            GlobalTransform::from(wall),
+        ));
+        let marker = world.spawn((Visible(false), LocalTransform::default()));
+        let teleporter = world.spawn((Teleporter {
+            target_marker: Some(marker),
+            ..Teleporter::new(Handedness::Right)
+        },));
+        physics_system_inner(&mut physics_context, &mut world);
+
+        // The player stands half a meter from the centre of the stage, aiming forward and a little up.
+        let hmd_in_stage = Affine3A::from_translation([0.5, 1.6, 0.].into());
+        let stage_from_aim = Affine3A::from_rotation_translation(
+            glam::Quat::from_rotation_x(20_f32.to_radians()),
+            [0.7, 1.2, -0.2].into(),
+        );
+        let mut run = |world: &mut World, stage_from_aim: Affine3A, thumbstick: Vec2| {
+            teleport_system_inner(
+                world,
+                &physics_context,
+                &mut debug_draw,
+                |_| (stage_from_aim, thumbstick),
+                hmd_in_stage,
+            )
+        };
+
+        run(&mut world, stage_from_aim, Vec2::Y);
+        let target = {
+            let teleporter = world.get::<&Teleporter>(teleporter).unwrap();
+            assert!(teleporter.is_aiming);
+            assert!(teleporter.arc.len() > 2);
+            teleporter.target.unwrap()
+        };
+        assert_relative_eq!(target.y, 0., epsilon = 0.001);
+        assert!(target.z < -2.);
+        assert!(world.get::<&Visible>(marker).unwrap().0);
+        assert_eq!(
+            world.get::<&LocalTransform>(marker).unwrap().translation,
+            target
+        );
+
+        // Letting go moves the player's head over the target, with their feet on the floor.
+        run(&mut world, stage_from_aim, Vec2::ZERO);
+        assert!(!world.get::<&Teleporter>(teleporter).unwrap().is_aiming);
+        let stage_translation = world.get::<&LocalTransform>(stage).unwrap().translation;
+        assert_relative_eq!(stage_translation.x, target.x - 0.5, epsilon = 0.001);
+        assert_relative_eq!(stage_translation.z, target.z, epsilon = 0.001);
+        assert_relative_eq!(stage_translation.y, 0., epsilon = 0.001);
+
+        // Aiming at the wall isn't a valid target, so nothing happens.
+        let stage_from_aim = Affine3A::from_rotation_translation(
+            glam::Quat::from_rotation_y(90_f32.to_radians()),
+            [0.7, 1.2, -0.2].into(),
+        );
+        run(&mut world, stage_from_aim, Vec2::Y);
+        assert!(world
+            .get::<&Teleporter>(teleporter)
+            .unwrap()
+            .target
+            .is_none());
+        assert!(!world.get::<&Visible>(marker).unwrap().0);
+        run(&mut world, stage_from_aim, Vec2::ZERO);
+        assert_eq!(
+            world.get::<&LocalTransform>(stage).unwrap().translation,
+            stage_translation
+        );
+    }
+}
@@ -0,0 +1,115 @@
+use hecs::World;
+
+use crate::{
+    components::{stage, GlobalTransform, LocalTransform, TrackedDevice},
+    contexts::InputContext,
+    Engine,
+};
+
+/// Tracked devices system
+/// Moves each entity with a `TrackedDevice` to where its tracker is, and spawns one for each tracker that's tracked
+/// but not followed by an entity yet.
+pub fn tracked_devices_system(engine: &mut Engine) {
+    tracked_devices_system_inner(&mut engine.world, &engine.input_context);
+}
+
+pub(crate) fn tracked_devices_system_inner(world: &mut World, input_context: &InputContext) {
+    let trackers = &input_context.trackers;
+    for (role, _) in trackers.tracked() {
+        let has_entity = world
+            .query_mut::<&TrackedDevice>()
+            .into_iter()
+            .any(|(_, tracked_device)| tracked_device.role == *role);
+        if !has_entity {
+            world.spawn((
+                TrackedDevice::new(*role),
+                LocalTransform::default(),
+                GlobalTransform::default(),
+            ));
+        }
+    }
+
+    let global_from_stage = stage::get_global_from_stage(world);
+    for (_, (tracked_device, local_transform, global_transform)) in world.query_mut::<(
+        &mut TrackedDevice,
+        &mut LocalTransform,
+        &mut GlobalTransform,
+    )>() {
+        // If the tracker isn't tracked, leave it where it was last seen.
+        let stage_from_tracker = trackers.stage_from_tracker(tracked_device.role);
+        tracked_device.is_tracked = stage_from_tracker.is_some();
+        if let Some(stage_from_tracker) = stage_from_tracker {
+            let global_from_tracker = global_from_stage * stage_from_tracker;
+            local_transform.update_from_affine(&global_from_tracker);
+            global_transform.0 = global_from_tracker;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::Stage, contexts::xr_context::TrackerRole};
+    use approx::assert_relative_eq;
+    use glam::{Affine3A, Vec3};
+
+    #[test]
+    pub fn test_tracked_devices_system() {
+        let mut world = World::new();
+        world.spawn((
+            Stage {},
+            LocalTransform::default(),
+            GlobalTransform(Affine3A::from_translation(Vec3::X)),
+        ));
+        let waist = world.spawn((
+            TrackedDevice::new(TrackerRole::Waist),
+            LocalTransform::default(),
+            GlobalTransform::default(),
+        ));
+
+        let mut input_context = InputContext::default();
+        input_context.trackers.stage_from_trackers = vec![
+            (
+                TrackerRole::Waist,
+                Affine3A::from_translation([0., 1., 0.].into()),
+            ),
+            (
+                TrackerRole::LeftFoot,
+                Affine3A::from_translation([-0.1, 0., 0.].into()),
+            ),
+        ];
+        tracked_devices_system_inner(&mut world, &input_context);
+
+        // The waist tracker moves the entity that was already following it.
+        let tracked_device = *world.get::<&TrackedDevice>(waist).unwrap();
+        assert!(tracked_device.is_tracked);
+        assert_relative_eq!(
+            world.get::<&GlobalTransform>(waist).unwrap().0.translation,
+            [1., 1., 0.].into()
+        );
+
+        // An entity is spawned for the left foot.
+        let (left_foot, _) = world
+            .query_mut::<&TrackedDevice>()
+            .into_iter()
+            .find(|(_, tracked_device)| tracked_device.role == TrackerRole::LeftFoot)
+            .unwrap();
+        assert_relative_eq!(
+            world
+                .get::<&GlobalTransform>(left_foot)
+                .unwrap()
+                .0
+                .translation,
+            [0.9, 0., 0.].into()
+        );
+
+        // Once the waist tracker is lost, it stays where it was last seen.
+        input_context.trackers.stage_from_trackers.clear();
+        tracked_devices_system_inner(&mut world, &input_context);
+        assert!(!world.get::<&TrackedDevice>(waist).unwrap().is_tracked);
+        assert_relative_eq!(
+            world.get::<&GlobalTransform>(waist).unwrap().0.translation,
+            [1., 1., 0.].into()
+        );
+    }
+}
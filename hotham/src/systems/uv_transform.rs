@@ -0,0 +1,63 @@
+use crate::{components::UvTransform, rendering::material::Material, Engine};
+use hecs::World;
+
+/// UV transform system
+/// Walks through each entity with a [`UvTransform`] and writes its transform at the predicted display time into
+/// its material.
+///
+/// Run this system after [`crate::systems::materials_system`], which writes whole materials, and before
+/// [`crate::systems::rendering_system`].
+pub fn uv_transform_system(engine: &mut Engine) {
+    let time = engine
+        .xr_context
+        .frame_state
+        .predicted_display_time
+        .as_nanos() as f64
+        * 1e-9;
+    let world = &mut engine.world;
+    let materials = unsafe {
+        engine
+            .render_context
+            .resources
+            .materials_buffer
+            .as_slice_mut()
+    };
+    uv_transform_system_inner(world, materials, time);
+}
+
+pub(crate) fn uv_transform_system_inner(world: &mut World, materials: &mut [Material], time: f64) {
+    for (_, uv_transform) in world.query_mut::<&UvTransform>() {
+        match materials.get_mut(uv_transform.material_id as usize) {
+            Some(material) => material.set_uv_transform(&uv_transform.affine_at(time)),
+            None => println!(
+                "[HOTHAM_MATERIALS] WARNING: Material {} does not exist, ignoring..",
+                uv_transform.material_id
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Affine2, Vec2};
+
+    use super::*;
+
+    #[test]
+    pub fn test_uv_transform_system() {
+        let mut world = World::new();
+        let mut materials = vec![Material::gltf_default(), Material::gltf_default()];
+        world.spawn((UvTransform::scrolling(1, Vec2::new(0.5, 0.)),));
+
+        // A material that doesn't exist is ignored
+        world.spawn((UvTransform::new(7),));
+
+        uv_transform_system_inner(&mut world, &mut materials, 0.5);
+        assert_eq!(materials[0].uv_transform(), Affine2::IDENTITY);
+        assert_eq!(materials[1].uv_transform().translation, Vec2::new(0.25, 0.));
+
+        // The transform is animated every run
+        uv_transform_system_inner(&mut world, &mut materials, 1.0);
+        assert_eq!(materials[1].uv_transform().translation, Vec2::new(0.5, 0.));
+    }
+}
@@ -211,6 +211,40 @@ pub(crate) fn get_asset_from_path(path: &str) -> Result<Vec<u8>> {
     Ok(asset.get_buffer()?.to_vec())
 }
 
+/// Where the application can keep files between runs: its internal data directory on Android, or a directory named
+/// after the application in the platform's per-user data directory elsewhere, eg. `~/.local/share/<application_name>`
+/// on Linux. The directory isn't created until something is saved to it.
+pub fn app_storage_dir(application_name: &str) -> std::path::PathBuf {
+    #[cfg(target_os = "android")]
+    {
+        let _ = application_name;
+        ndk_glue::native_activity()
+            .internal_data_path()
+            .to_path_buf()
+    }
+
+    #[cfg(not(target_os = "android"))]
+    user_data_dir().join(application_name)
+}
+
+#[cfg(not(target_os = "android"))]
+fn user_data_dir() -> std::path::PathBuf {
+    use std::{env::var_os, path::PathBuf};
+
+    let home = || var_os("HOME").map(PathBuf::from);
+    let dir = if cfg!(target_os = "windows") {
+        var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home().map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| home().map(|home| home.join(".local").join("share")))
+    };
+    dir.unwrap_or_else(|| PathBuf::from("."))
+}
+
 #[cfg(test)]
 pub(crate) unsafe fn save_image_to_disk(
     vulkan_context: &VulkanContext,
@@ -1,3 +1,7 @@
+#[cfg(not(target_os = "android"))]
+mod shader_watcher;
+
+use ash::vk;
 use hotham_asset_client::{watch, AssetUpdatedMessage};
 
 use std::sync::mpsc;
@@ -5,14 +9,25 @@ use std::sync::mpsc;
 #[derive(Debug, Clone)]
 pub(crate) enum WorkerMessage {
     AssetUpdated(AssetUpdatedMessage),
+    ShaderUpdated(ShaderUpdatedMessage),
     Error(WorkerError),
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct ShaderUpdatedMessage {
+    pub shader_name: String,
+    pub stage: vk::ShaderStageFlags,
+    pub spirv: Vec<u32>,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) enum WorkerError {
     TaskFailed(String),
 }
 
+#[cfg(not(target_os = "android"))]
+pub(crate) use shader_watcher::ShaderWatcher;
+
 pub(crate) struct Workers {
     pub(crate) receiver: mpsc::Receiver<WorkerMessage>,
 }
@@ -0,0 +1,133 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use ash::vk;
+use notify_debouncer_mini::{
+    new_debouncer,
+    notify::{RecommendedWatcher, RecursiveMode},
+    DebounceEventResult, Debouncer,
+};
+
+use super::{ShaderUpdatedMessage, WorkerError, WorkerMessage};
+
+/// The shaders that are kept around by the renderer and can therefore be swapped at runtime.
+const HOT_RELOADABLE_SHADERS: [(&str, vk::ShaderStageFlags); 2] = [
+    ("pbr.vert", vk::ShaderStageFlags::VERTEX),
+    ("pbr.frag", vk::ShaderStageFlags::FRAGMENT),
+];
+
+/// Watches a directory of GLSL source files and recompiles the PBR shaders whenever they, or any
+/// of the files they include, change on disk. Only available on desktop builds.
+pub(crate) struct ShaderWatcher {
+    pub(crate) receiver: mpsc::Receiver<WorkerMessage>,
+    _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl ShaderWatcher {
+    pub fn new(shader_dir: &Path) -> Result<Self, WorkerError> {
+        let (to_engine, from_watcher) = mpsc::channel();
+        let shader_dir = shader_dir.to_path_buf();
+        let watched_dir = shader_dir.clone();
+
+        let mut debouncer = new_debouncer(
+            Duration::from_millis(100),
+            None,
+            move |result: DebounceEventResult| match result {
+                Ok(events) => {
+                    let changed: Vec<PathBuf> = events.into_iter().map(|e| e.path).collect();
+                    for message in recompile_changed_shaders(&watched_dir, &changed) {
+                        let _ = to_engine.send(message);
+                    }
+                }
+                Err(errors) => {
+                    for e in errors {
+                        println!("[HOTHAM_SHADER_WATCHER] Watch error: {e:?}");
+                    }
+                }
+            },
+        )
+        .map_err(|e| WorkerError::TaskFailed(format!("{e:?}")))?;
+
+        debouncer
+            .watcher()
+            .watch(&shader_dir, RecursiveMode::NonRecursive)
+            .map_err(|e| WorkerError::TaskFailed(format!("{e:?}")))?;
+
+        println!("[HOTHAM_SHADER_WATCHER] Watching {shader_dir:?} for changes..");
+
+        Ok(Self {
+            receiver: from_watcher,
+            _debouncer: debouncer,
+        })
+    }
+}
+
+/// Work out which of the hot reloadable shaders are affected by the changed files and recompile them.
+/// A change to an included file (eg. `pbr.glsl`) affects every shader.
+fn recompile_changed_shaders(shader_dir: &Path, changed: &[PathBuf]) -> Vec<WorkerMessage> {
+    let changed_names: Vec<&str> = changed
+        .iter()
+        .filter_map(|p| p.file_name().and_then(|n| n.to_str()))
+        .collect();
+    let include_changed = changed_names.iter().any(|n| n.ends_with(".glsl"));
+
+    HOT_RELOADABLE_SHADERS
+        .iter()
+        .filter(|(name, _)| include_changed || changed_names.contains(name))
+        .filter_map(|(name, stage)| {
+            let path = shader_dir.join(name);
+            match compile_shader(shader_dir, &path, *stage) {
+                Ok(spirv) => Some(WorkerMessage::ShaderUpdated(ShaderUpdatedMessage {
+                    shader_name: name.to_string(),
+                    stage: *stage,
+                    spirv,
+                })),
+                Err(e) => {
+                    // A typo in a shader shouldn't take the whole session down; keep the old pipeline.
+                    println!("[HOTHAM_SHADER_WATCHER] Failed to compile {name}:\n{e}");
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn compile_shader(
+    shader_dir: &Path,
+    path: &Path,
+    stage: vk::ShaderStageFlags,
+) -> Result<Vec<u32>, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let kind = match stage {
+        vk::ShaderStageFlags::VERTEX => shaderc::ShaderKind::Vertex,
+        vk::ShaderStageFlags::FRAGMENT => shaderc::ShaderKind::Fragment,
+        _ => shaderc::ShaderKind::Compute,
+    };
+
+    let compiler = shaderc::Compiler::new().ok_or("Unable to create shader compiler")?;
+    let mut options =
+        shaderc::CompileOptions::new().ok_or("Unable to create shader compile options")?;
+    options.set_target_env(
+        shaderc::TargetEnv::Vulkan,
+        shaderc::EnvVersion::Vulkan1_1 as u32,
+    );
+    let include_dir = shader_dir.to_path_buf();
+    options.set_include_callback(move |name, _, _, _| {
+        let resolved = include_dir.join(name);
+        std::fs::read_to_string(&resolved)
+            .map(|content| shaderc::ResolvedInclude {
+                resolved_name: resolved.to_string_lossy().into_owned(),
+                content,
+            })
+            .map_err(|e| format!("Unable to include {resolved:?}: {e}"))
+    });
+
+    let file_name = path.to_string_lossy();
+    compiler
+        .compile_into_spirv(&source, kind, &file_name, "main", Some(&options))
+        .map(|artifact| artifact.as_binary().to_vec())
+        .map_err(|e| e.to_string())
+}